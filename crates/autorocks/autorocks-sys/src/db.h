@@ -285,6 +285,15 @@ struct TransactionDBWrapper
         return db->GetIntProperty(cf, property, value);
     }
 
+    // begin and end can be null, which means the start/end of the column family.
+    Status compact_range(ColumnFamilyHandle *cf, const Slice *begin, const Slice *end) const
+    {
+        CompactRangeOptions options;
+        // Also rewrite the bottommost level so that deleted keys are dropped.
+        options.bottommost_level_compaction = BottommostLevelCompaction::kForceOptimized;
+        return db->CompactRange(options, cf, begin, end);
+    }
+
     unique_ptr<Iterator> iter(const ReadOptions &options, ColumnFamilyHandle *cf) const
     {
         return unique_ptr<Iterator>(db->NewIterator(options, cf));
@@ -219,6 +219,25 @@ impl TransactionDb {
         got.then_some(val)
     }
 
+    /// Manually compact keys in `[begin, end]` of a column family. `None` means
+    /// the start/end of the column family.
+    pub fn compact_range(
+        &self,
+        col: usize,
+        begin: Option<&[u8]>,
+        end: Option<&[u8]>,
+    ) -> Result<()> {
+        let cf = self.inner.get_cf(col);
+        assert!(!cf.is_null());
+        let begin: Option<Slice> = begin.map(Into::into);
+        let end: Option<Slice> = end.map(Into::into);
+        let as_ptr = |s: &Option<Slice>| s.as_ref().map_or(std::ptr::null(), |s| s as *const _);
+        moveit! {
+            let status = unsafe { self.inner.compact_range(cf, as_ptr(&begin), as_ptr(&end)) };
+        }
+        into_result(&status)
+    }
+
     pub fn snapshot(&self) -> Snapshot {
         Snapshot {
             inner: self.inner.get_snapshot(),
@@ -193,3 +193,22 @@ fn test_clear_cf() {
     db.put(0, b"key", b"value").unwrap();
     assert_eq!(db.iter(0, Direction::Forward).count(), 1);
 }
+
+#[test]
+fn test_compact_range() {
+    let (db, _dir) = open_temp(1);
+    for i in 0u32..100 {
+        db.put(0, &i.to_be_bytes(), b"value").unwrap();
+    }
+    for i in 0u32..50 {
+        db.delete(0, &i.to_be_bytes()).unwrap();
+    }
+    db.compact_range(0, Some(&0u32.to_be_bytes()), Some(&49u32.to_be_bytes()))
+        .unwrap();
+    db.compact_range(0, None, None).unwrap();
+    assert_eq!(db.iter(0, Direction::Forward).count(), 50);
+    let sst_size = db
+        .get_int_property(0, "rocksdb.total-sst-files-size")
+        .unwrap();
+    assert!(sst_size > 0);
+}
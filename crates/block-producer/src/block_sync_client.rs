@@ -7,9 +7,17 @@ use bytes::Bytes;
 use futures::TryStreamExt;
 use gw_chain::chain::Chain;
 use gw_generator::generator::CyclesPool;
-use gw_mem_pool::pool::MemPool;
-use gw_p2p_network::{FnSpawn, P2P_SYNC_PROTOCOL, P2P_SYNC_PROTOCOL_NAME};
+use gw_mem_pool::{
+    block_sync_server::{block_range_server_spawn, block_sync_server_spawn, BlockSyncServerState},
+    pool::MemPool,
+};
+use gw_p2p_network::{
+    peers::Peers, DirectionalSpawn, FnSpawn, P2P_BLOCK_RANGE_PROTOCOL,
+    P2P_BLOCK_RANGE_PROTOCOL_NAME, P2P_SYNC_PROTOCOL, P2P_SYNC_PROTOCOL_NAME,
+    P2P_SYNC_PROTOCOL_VERSION,
+};
 use gw_rpc_client::rpc_client::RPCClient;
+use gw_rpc_server::registry::BackendForkScheduler;
 use gw_store::{autorocks::RocksDBStatusError, traits::chain_store::ChainStore, Store};
 use gw_telemetry::{
     trace::{SpanContext, SpanId, TraceFlags, TraceId, TraceState},
@@ -17,17 +25,21 @@ use gw_telemetry::{
 };
 use gw_types::{
     packed::{
-        BlockSync, BlockSyncReader, BlockSyncUnion, NumberHash, P2PSyncRequest,
-        P2PSyncResponseReader, P2PSyncResponseUnionReader, Script,
+        BlockRange, BlockRangeReader, BlockRangeRequest, BlockSync, BlockSyncReader,
+        BlockSyncUnion, FinalizedBlock, NumberHash, P2PSyncRequest, P2PSyncResponseReader,
+        P2PSyncResponseUnionReader, Script,
     },
     prelude::*,
 };
-use gw_utils::{compression::StreamDecoder, liveness::Liveness};
+use gw_utils::{
+    compression::StreamDecoder, export_block::insert_bad_block_hashes, liveness::Liveness,
+};
 
 use tentacle::{
     builder::MetaBuilder,
     service::{ProtocolMeta, ServiceAsyncControl},
-    SessionId, SubstreamReadPart,
+    traits::ProtocolSpawn,
+    ProtocolId, SessionId, SubstreamReadPart,
 };
 use tokio::{sync::Mutex, task::block_in_place};
 use tracing::{info_span, Instrument};
@@ -37,6 +49,9 @@ use crate::{
     sync_l1::{revert, sync_l1, SyncL1Context},
 };
 
+/// Number of finalized blocks requested at a time when catching up.
+const BLOCK_RANGE_COUNT: u32 = 64;
+
 pub struct BlockSyncClient {
     pub store: Store,
     pub rpc_client: RPCClient,
@@ -45,8 +60,15 @@ pub struct BlockSyncClient {
     pub chain_updater: ChainUpdater,
     pub rollup_type_script: Script,
     pub p2p_stream_inbox: Arc<std::sync::Mutex<Option<P2PStream>>>,
+    /// Stream of the block range protocol, for catching up finalized blocks.
+    pub block_range_stream_inbox: Arc<std::sync::Mutex<Option<P2PStream>>>,
     pub completed_initial_syncing: bool,
     pub liveness: Arc<Liveness>,
+    /// Re-publish applied messages to downstream peers in relay mode.
+    pub relay: Option<Arc<std::sync::Mutex<BlockSyncServerState>>>,
+    pub max_hops: u32,
+    /// Apply backend forks scheduled on the full node.
+    pub backend_fork_scheduler: Arc<BackendForkScheduler>,
 }
 
 impl SyncL1Context for BlockSyncClient {
@@ -126,6 +148,9 @@ async fn run_once_without_p2p_stream(client: &mut BlockSyncClient) -> Result<()>
 
 async fn run_with_p2p_stream(client: &mut BlockSyncClient, stream: &mut P2PStream) -> Result<()> {
     loop {
+        // Catch up finalized blocks from the peer, it's much faster than
+        // syncing them from L1.
+        sync_block_range(client).await?;
         sync_l1(client).await.context(RecoverableCtx)?;
         notify_new_tip(client, false)
             .await
@@ -210,6 +235,122 @@ async fn run_with_p2p_stream(client: &mut BlockSyncClient, stream: &mut P2PStrea
     Ok(())
 }
 
+/// Catch up finalized blocks over the block range protocol. Peers without
+/// the protocol are synced from L1 only.
+async fn sync_block_range(client: &mut BlockSyncClient) -> Result<()> {
+    let stream = client.block_range_stream_inbox.lock().unwrap().take();
+    let mut stream = match stream {
+        Some(stream) => stream,
+        None => return Ok(()),
+    };
+    match sync_block_range_with_stream(client, &mut stream).await {
+        Ok(()) => {
+            let mut inbox = client.block_range_stream_inbox.lock().unwrap();
+            // Keep the stream unless the session is replaced.
+            if inbox.is_none() {
+                *inbox = Some(stream);
+            }
+            Ok(())
+        }
+        Err(err) if err.is::<RocksDBStatusError>() => Err(err),
+        Err(err) => {
+            // Stop catching up from this peer, the remaining blocks are synced
+            // from L1.
+            log::warn!("catch up finalized blocks from peer: {:#}", err);
+            Ok(())
+        }
+    }
+}
+
+async fn sync_block_range_with_stream(
+    client: &mut BlockSyncClient,
+    stream: &mut P2PStream,
+) -> Result<()> {
+    loop {
+        let last_confirmed = client
+            .store
+            .get_last_confirmed_block_number_hash()
+            .context("last confirmed")?
+            .number()
+            .unpack();
+        let request = BlockRangeRequest::new_builder()
+            .start_block_number((last_confirmed + 1).pack())
+            .count(BLOCK_RANGE_COUNT.pack())
+            .build();
+        stream.send(request.as_bytes()).await?;
+        let response = stream.recv().await?.context("unexpected end of stream")?;
+        BlockRangeReader::from_slice(&response)?;
+        let range = BlockRange::new_unchecked(response);
+        if range.blocks().is_empty() {
+            return Ok(());
+        }
+        log::info!(
+            "received finalized blocks {}..={}",
+            last_confirmed + 1,
+            last_confirmed + range.blocks().len() as u64
+        );
+        for block in range.blocks() {
+            apply_finalized_block(client, block).await?;
+        }
+        notify_new_tip(client, false).await?;
+        client.liveness.tick();
+    }
+}
+
+/// Apply a finalized block, and mark it as confirmed.
+///
+/// The block must be submitted by its submit tx, which must be committed on
+/// L1, so a peer can't make us confirm a block that isn't on L1.
+async fn apply_finalized_block(client: &mut BlockSyncClient, f: FinalizedBlock) -> Result<()> {
+    let l = f.local_block();
+    let block_hash = l.block().hash();
+    let block_number = l.block().raw().number().unpack();
+    client
+        .chain_updater
+        .verify_submitted_block(&f.submit_tx_hash().unpack(), &block_hash)
+        .await
+        .with_context(|| format!("verify finalized block {}", block_number))?;
+    let mut store_tx = client.store.begin_transaction();
+    let known = match store_tx.get_block_hash_by_number(block_number)? {
+        Some(store_block_hash) if store_block_hash == block_hash => true,
+        Some(_) => {
+            log::info!("revert to {}", block_number - 1);
+            revert(client, &mut store_tx, block_number - 1).await?;
+            store_tx.commit()?;
+            false
+        }
+        None => false,
+    };
+    let mut chain = client.chain.lock().await;
+    block_in_place(|| {
+        let mut store_tx = client.store.begin_transaction();
+        if !known {
+            // Reverted block root of the post global state must match.
+            if let Some(bad_block_hashes) = l.bad_block_hashes().unpack() {
+                insert_bad_block_hashes(&mut store_tx, bad_block_hashes)?;
+            }
+            chain.update_local(
+                &mut store_tx,
+                l.block(),
+                l.deposit_info_vec(),
+                l.deposit_asset_scripts().into_iter().collect(),
+                l.withdrawals().into_iter().collect(),
+                l.post_global_state(),
+            )?;
+            chain.calculate_and_store_finalized_custodians(&mut store_tx, block_number)?;
+        }
+        store_tx.set_block_submit_tx_hash(block_number, &f.submit_tx_hash().unpack())?;
+        let nh = NumberHash::new_builder()
+            .number(block_number.pack())
+            .block_hash(block_hash.pack())
+            .build();
+        store_tx.set_last_confirmed_block_number_hash(&nh.as_reader())?;
+        store_tx.set_last_submitted_block_number_hash(&nh.as_reader())?;
+        store_tx.commit()?;
+        anyhow::Ok(())
+    })
+}
+
 async fn apply_msg(client: &mut BlockSyncClient, msg: BlockSync) -> Result<()> {
     match msg.to_enum() {
         BlockSyncUnion::Revert(r) => {
@@ -226,8 +367,12 @@ async fn apply_msg(client: &mut BlockSyncClient, msg: BlockSync) -> Result<()> {
             store_tx.set_last_confirmed_block_number_hash(nh)?;
             store_tx.set_last_submitted_block_number_hash(nh)?;
             store_tx.commit()?;
+            if let Some(ref relay) = client.relay {
+                relay.lock().unwrap().publish_revert(r);
+            }
         }
         BlockSyncUnion::LocalBlock(l) => {
+            check_hops(client, l.hops().unpack())?;
             // Use remote span context as parent.
             let trace_id: [u8; 16] = l.trace_id().as_slice().try_into().unwrap();
             let span_id: [u8; 8] = l.span_id().as_slice().try_into().unwrap();
@@ -241,7 +386,12 @@ async fn apply_msg(client: &mut BlockSyncClient, msg: BlockSync) -> Result<()> {
             let span = gw_telemetry::current_context()
                 .with_remote_span_context(span_cx)
                 .new_span(info_span!("handle_local_block"));
-            handle_local_block(client, l).instrument(span).await?;
+            let applied = handle_local_block(client, l.clone())
+                .instrument(span)
+                .await?;
+            if let (true, Some(relay)) = (applied, &client.relay) {
+                relay.lock().unwrap().publish_local_block(l);
+            }
             client.liveness.tick();
         }
         BlockSyncUnion::Submitted(s) => {
@@ -259,6 +409,9 @@ async fn apply_msg(client: &mut BlockSyncClient, msg: BlockSync) -> Result<()> {
             )?;
             store_tx.set_last_submitted_block_number_hash(&s.number_hash().as_reader())?;
             store_tx.commit()?;
+            if let Some(ref relay) = client.relay {
+                relay.lock().unwrap().publish_submitted(s);
+            }
             client.liveness.tick();
         }
         BlockSyncUnion::Confirmed(c) => {
@@ -272,6 +425,9 @@ async fn apply_msg(client: &mut BlockSyncClient, msg: BlockSync) -> Result<()> {
             let mut store_tx = client.store.begin_transaction();
             store_tx.set_last_confirmed_block_number_hash(&c.number_hash().as_reader())?;
             store_tx.commit()?;
+            if let Some(ref relay) = client.relay {
+                relay.lock().unwrap().publish_confirmed(c);
+            }
             client.liveness.tick();
         }
         BlockSyncUnion::NextMemBlock(m) => {
@@ -287,9 +443,13 @@ async fn apply_msg(client: &mut BlockSyncClient, msg: BlockSync) -> Result<()> {
                     log::warn!("{:#}", err);
                 }
             }
+            if let Some(ref relay) = client.relay {
+                relay.lock().unwrap().publish_next_mem_block(m);
+            }
             client.liveness.tick();
         }
         BlockSyncUnion::PushTransaction(push_tx) => {
+            check_hops(client, push_tx.hops().unpack())?;
             // Use remote span context as parent.
             let trace_id: [u8; 16] = push_tx.trace_id().as_slice().try_into().unwrap();
             let span_id: [u8; 8] = push_tx.span_id().as_slice().try_into().unwrap();
@@ -321,14 +481,24 @@ async fn apply_msg(client: &mut BlockSyncClient, msg: BlockSync) -> Result<()> {
                 }
             }
         }
+        BlockSyncUnion::ScheduledBackendFork(fork) => {
+            log::info!("received scheduled backend fork");
+            // Keep syncing blocks below the fork height even if the fork is
+            // rejected, e.g. the generator binaries are not deployed yet.
+            let scheduler = client.backend_fork_scheduler.clone();
+            if let Err(err) = block_in_place(|| scheduler.schedule(fork)) {
+                log::error!("[backend fork] failed to apply: {:#}", err);
+            }
+        }
     }
     Ok(())
 }
 
+/// Returns false if the block is already known.
 async fn handle_local_block(
     client: &mut BlockSyncClient,
     l: gw_types::packed::LocalBlock,
-) -> Result<(), anyhow::Error> {
+) -> Result<bool, anyhow::Error> {
     let block_hash = l.block().hash();
     let block_number = l.block().raw().number().unpack();
     log::info!(
@@ -344,7 +514,7 @@ async fn handle_local_block(
             store_tx.commit()?;
         } else {
             log::info!("block already known");
-            return Ok(());
+            return Ok(false);
         }
     }
     {
@@ -366,12 +536,28 @@ async fn handle_local_block(
         })?;
     }
     notify_new_tip(client, false).await?;
+    Ok(true)
+}
+
+/// Reject messages relayed too many times, and update hops of the relay.
+fn check_hops(client: &BlockSyncClient, hops: u32) -> Result<()> {
+    ensure!(
+        hops < client.max_hops,
+        "message relayed {} times, max hops {}, maybe there is a relay loop",
+        hops,
+        client.max_hops
+    );
+    if let Some(ref relay) = client.relay {
+        relay.lock().unwrap().set_hops(hops + 1);
+    }
     Ok(())
 }
 
 pub struct P2PStream {
     id: SessionId,
+    protocol: ProtocolId,
     control: ServiceAsyncControl,
+    peers: Arc<Peers>,
     read_part: Option<SubstreamReadPart>,
     decoder: StreamDecoder,
 }
@@ -383,7 +569,9 @@ impl P2PStream {
     fn take_receiver(&mut self) -> Self {
         Self {
             id: self.id,
+            protocol: self.protocol,
             control: self.control.clone(),
+            peers: self.peers.clone(),
             read_part: self.read_part.take(),
             decoder: core::mem::take(&mut self.decoder),
         }
@@ -392,6 +580,8 @@ impl P2PStream {
     async fn recv(&mut self) -> Result<Option<Bytes>> {
         let receiver = self.read_part.as_mut().context("stream is taken")?;
         Ok(if let Some(msg) = receiver.try_next().await? {
+            self.peers
+                .record_received(self.id, self.protocol, msg.len());
             // Decompress message.
             Some(self.decoder.decode(&msg)?.into())
         } else {
@@ -400,8 +590,9 @@ impl P2PStream {
     }
 
     async fn send(&mut self, msg: Bytes) -> Result<()> {
+        self.peers.record_sent(self.id, self.protocol, msg.len());
         self.control
-            .send_message_to(self.id, P2P_SYNC_PROTOCOL, msg)
+            .send_message_to(self.id, self.protocol, msg)
             .await?;
         Ok(())
     }
@@ -415,23 +606,86 @@ impl P2PStream {
 /// The p2p protocol just sends the p2p stream to the client.
 pub fn block_sync_client_protocol(
     stream_inbox: Arc<std::sync::Mutex<Option<P2PStream>>>,
+    peers: Arc<Peers>,
+) -> ProtocolMeta {
+    MetaBuilder::new()
+        .name(|_| P2P_SYNC_PROTOCOL_NAME.into())
+        .id(P2P_SYNC_PROTOCOL)
+        .support_versions(vec![P2P_SYNC_PROTOCOL_VERSION.into()])
+        .protocol_spawn(p2p_stream_spawn(P2P_SYNC_PROTOCOL, stream_inbox, peers))
+        .build()
+}
+
+/// The block range protocol just sends the p2p stream to the client.
+pub fn block_range_client_protocol(
+    stream_inbox: Arc<std::sync::Mutex<Option<P2PStream>>>,
+    peers: Arc<Peers>,
+) -> ProtocolMeta {
+    MetaBuilder::new()
+        .name(|_| P2P_BLOCK_RANGE_PROTOCOL_NAME.into())
+        .id(P2P_BLOCK_RANGE_PROTOCOL)
+        .protocol_spawn(p2p_stream_spawn(
+            P2P_BLOCK_RANGE_PROTOCOL,
+            stream_inbox,
+            peers,
+        ))
+        .build()
+}
+
+/// Sync from the upstream peer we dial, and serve downstream peers connected
+/// to us.
+pub fn block_sync_relay_protocol(
+    stream_inbox: Arc<std::sync::Mutex<Option<P2PStream>>>,
+    relay: Arc<std::sync::Mutex<BlockSyncServerState>>,
+    peers: Arc<Peers>,
+) -> ProtocolMeta {
+    MetaBuilder::new()
+        .name(|_| P2P_SYNC_PROTOCOL_NAME.into())
+        .id(P2P_SYNC_PROTOCOL)
+        .support_versions(vec![P2P_SYNC_PROTOCOL_VERSION.into()])
+        .protocol_spawn(DirectionalSpawn {
+            outbound: p2p_stream_spawn(P2P_SYNC_PROTOCOL, stream_inbox, peers.clone()),
+            inbound: block_sync_server_spawn(relay, peers),
+        })
+        .build()
+}
+
+/// Catch up from the upstream peer we dial, and serve downstream peers
+/// connected to us.
+pub fn block_range_relay_protocol(
+    stream_inbox: Arc<std::sync::Mutex<Option<P2PStream>>>,
+    relay: Arc<std::sync::Mutex<BlockSyncServerState>>,
+    peers: Arc<Peers>,
 ) -> ProtocolMeta {
-    let spawn = FnSpawn(move |context, control, read_part| {
+    MetaBuilder::new()
+        .name(|_| P2P_BLOCK_RANGE_PROTOCOL_NAME.into())
+        .id(P2P_BLOCK_RANGE_PROTOCOL)
+        .protocol_spawn(DirectionalSpawn {
+            outbound: p2p_stream_spawn(P2P_BLOCK_RANGE_PROTOCOL, stream_inbox, peers.clone()),
+            inbound: block_range_server_spawn(relay, peers),
+        })
+        .build()
+}
+
+fn p2p_stream_spawn(
+    protocol: ProtocolId,
+    stream_inbox: Arc<std::sync::Mutex<Option<P2PStream>>>,
+    peers: Arc<Peers>,
+) -> impl ProtocolSpawn + Send + Sync + 'static {
+    FnSpawn(move |context, control, read_part| {
         let control = control.clone();
         let id = context.id;
+        peers.open_protocol(id, protocol);
         let stream = P2PStream {
             id,
+            protocol,
             control,
+            peers: peers.clone(),
             read_part: Some(read_part),
             decoder: StreamDecoder::new(),
         };
         *stream_inbox.lock().unwrap() = Some(stream);
-    });
-    MetaBuilder::new()
-        .name(|_| P2P_SYNC_PROTOCOL_NAME.into())
-        .id(P2P_SYNC_PROTOCOL)
-        .protocol_spawn(spawn)
-        .build()
+    })
 }
 
 async fn notify_new_tip(client: &mut BlockSyncClient, update_state: bool) -> Result<()> {
@@ -119,6 +119,36 @@ impl ChainUpdater {
         Ok(())
     }
 
+    /// Verify that the tx is committed on L1 and submits the block.
+    #[instrument(skip_all)]
+    pub async fn verify_submitted_block(
+        &self,
+        tx_hash: &[u8; 32],
+        block_hash: &[u8; 32],
+    ) -> Result<()> {
+        let status = self.rpc_client.ckb.get_transaction_status(*tx_hash).await?;
+        if !matches!(
+            status,
+            Some(gw_jsonrpc_types::ckb_jsonrpc_types::Status::Committed)
+        ) {
+            return Err(anyhow!("submit tx is not committed: {:?}", status));
+        }
+        let tx = self
+            .rpc_client
+            .ckb
+            .get_packed_transaction(*tx_hash)
+            .await?
+            .context("get transaction")?;
+        match self.extract_rollup_action(&tx)?.to_enum() {
+            RollupActionUnion::RollupSubmitBlock(submitted)
+                if &submitted.block().hash() == block_hash =>
+            {
+                Ok(())
+            }
+            _ => Err(anyhow!("tx doesn't submit the block")),
+        }
+    }
+
     #[instrument(skip_all)]
     fn extract_rollup_action(&self, tx: &Transaction) -> Result<RollupAction> {
         let rollup_type_hash: [u8; 32] = self.rollup_type_script.hash();
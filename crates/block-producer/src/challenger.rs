@@ -18,15 +18,20 @@ use gw_challenge::{
     offchain::{
         mock_cancel_challenge_tx,
         verify_tx::{verify_tx, TxWithContext},
-        OffChainMockContext,
+        OffChainCancelChallengeValidator, OffChainMockContext, OffChainValidatorContext,
     },
     revert::Revert,
     types::{RevertContext, VerifyContext},
 };
+use gw_common::registry_address::RegistryAddress;
 use gw_config::{BlockProducerConfig, DebugConfig};
 use gw_generator::types::vm::ChallengeContext;
 use gw_jsonrpc_types::{test_mode::TestModePayload, JsonCalcHash};
+use gw_metrics::challenger::ChallengeAction;
 use gw_rpc_client::{contract::ContractsCellDepManager, rpc_client::RPCClient};
+use gw_store::{
+    state::MemStateDB, traits::chain_store::ChainStore, transaction::StoreTransaction, Store,
+};
 use gw_types::{
     bytes::Bytes,
     core::{ChallengeTargetType, Status},
@@ -34,7 +39,7 @@ use gw_types::{
     offchain::{global_state_from_slice, CellInfo, InputCellInfo},
     packed::{
         CellDep, CellOutput, ChallengeLockArgs, ChallengeLockArgsReader, ChallengeTarget,
-        GlobalState, OutPoint, Script, Transaction, WitnessArgs,
+        GlobalState, OutPoint, Script, Transaction, WithdrawalKey, WitnessArgs,
     },
     prelude::*,
 };
@@ -52,8 +57,10 @@ use crate::{
     utils,
 };
 
-const MAX_CANCEL_CYCLES: u64 = 7000_0000;
-const MAX_CANCEL_TX_BYTES: u64 = ckb_chain_spec::consensus::MAX_BLOCK_BYTES;
+/// Cycle budget of cancel challenge transactions.
+pub const MAX_CANCEL_CYCLES: u64 = 7000_0000;
+/// Size budget of cancel challenge transactions.
+pub const MAX_CANCEL_TX_BYTES: u64 = ckb_chain_spec::consensus::MAX_BLOCK_BYTES;
 const TRANSACTION_FAILED_TO_RESOLVE_ERROR: &str = "TransactionFailedToResolve";
 
 pub struct Challenger {
@@ -68,8 +75,11 @@ pub struct Challenger {
     cleaner: Arc<Cleaner>,
     debug_config: DebugConfig,
     offchain_mock_context: OffChainMockContext,
+    offchain_validator_context: Option<OffChainValidatorContext>,
     contracts_dep_manager: ContractsCellDepManager,
     last_submit_tx: Option<H256>,
+    // Bad block target which the offchain validator can cancel.
+    cancelable_target: Option<ChallengeTarget>,
 }
 
 pub struct ChallengerNewArgs {
@@ -84,6 +94,9 @@ pub struct ChallengerNewArgs {
     pub tests_control: Option<TestModeControl>,
     pub cleaner: Arc<Cleaner>,
     pub offchain_mock_context: OffChainMockContext,
+    /// Verify bad blocks with the offchain validator before challenging
+    /// them, enabled in watchtower mode.
+    pub offchain_validator_context: Option<OffChainValidatorContext>,
     pub contracts_dep_manager: ContractsCellDepManager,
 }
 
@@ -101,6 +114,7 @@ impl Challenger {
             tests_control,
             cleaner,
             offchain_mock_context,
+            offchain_validator_context,
             contracts_dep_manager,
         } = args;
 
@@ -116,8 +130,10 @@ impl Challenger {
             tests_control,
             cleaner,
             offchain_mock_context,
+            offchain_validator_context,
             contracts_dep_manager,
             last_submit_tx: None,
+            cancelable_target: None,
         }
     }
 
@@ -179,7 +195,11 @@ impl Challenger {
                         .map_err(|_| anyhow!("invalid challenge type"))?;
                     log::info!("challenge block 0x{} target {} type {:?}", hash, idx, type_);
                 }
-                self.challenge_block(rollup, context).await
+                if self.is_cancelable_bad_block(&context.target).await {
+                    return Ok(());
+                }
+                let result = self.challenge_block(rollup, context).await;
+                record_failure(ChallengeAction::EnterChallenge, result)
             }
             SyncEvent::BadChallenge { cell, context } => {
                 if let Some(ref tests_control) = self.tests_control {
@@ -189,7 +209,8 @@ impl Challenger {
                         _ => unreachable!(),
                     }
                 }
-                self.cancel_challenge(rollup, cell, *context).await
+                let result = self.cancel_challenge(rollup, cell, *context).await;
+                record_failure(ChallengeAction::CancelChallenge, result)
             }
             SyncEvent::WaitChallenge { cell, context } => {
                 if let Some(ref tests_control) = self.tests_control {
@@ -204,7 +225,40 @@ impl Challenger {
                     }
                 }
                 let tip_number = to_tip_number(&event);
-                self.revert(rollup, cell, context, tip_number).await
+                let result = self.revert(rollup, cell, context, tip_number).await;
+                record_failure(ChallengeAction::Revert, result)
+            }
+        }
+    }
+
+    async fn is_cancelable_bad_block(&mut self, target: &ChallengeTarget) -> bool {
+        let validator_context = match self.offchain_validator_context {
+            Some(ref ctx) => ctx.clone(),
+            None => return false,
+        };
+        if self.cancelable_target.as_ref().map(|t| t.as_slice()) == Some(target.as_slice()) {
+            return true;
+        }
+
+        let result = {
+            let chain = self.chain.lock().await;
+            verify_bad_withdrawal(validator_context, chain.store(), target)
+        };
+        match result {
+            Ok(true) => {
+                let hash = hex::encode::<[u8; 32]>(target.block_hash().unpack());
+                log::error!(
+                    "offchain validator can cancel the challenge of bad block 0x{}, skip challenge",
+                    hash
+                );
+                gw_metrics::challenger().cancelable_bad_blocks.inc();
+                self.cancelable_target = Some(target.to_owned());
+                true
+            }
+            Ok(false) => false,
+            Err(err) => {
+                log::warn!("offchain validator verify bad block: {:#}", err);
+                false
             }
         }
     }
@@ -284,6 +338,7 @@ impl Challenger {
 
         let tx_hash = self.rpc_client.send_transaction(&tx).await?;
         log::info!("Challenge block {} in tx {}", block_numer, to_hex(&tx_hash));
+        gw_metrics::challenger().sent(ChallengeAction::EnterChallenge);
         self.last_submit_tx = Some(tx_hash);
 
         Ok(())
@@ -401,11 +456,13 @@ impl Challenger {
             Ok(tx_hash) => {
                 self.cleaner.watch_verifier(verifier, Some(tx_hash)).await;
                 log::info!("Cancel challenge in tx {}", to_hex(&tx_hash));
+                gw_metrics::challenger().sent(ChallengeAction::CancelChallenge);
                 self.last_submit_tx = Some(tx_hash);
             }
             Err(err) => {
                 self.cleaner.watch_verifier(verifier, None).await;
                 log::warn!("Cancel challenge failed {}", err);
+                gw_metrics::challenger().failed(ChallengeAction::CancelChallenge);
             }
         }
 
@@ -561,6 +618,7 @@ impl Challenger {
 
         let tx_hash = self.rpc_client.send_transaction(&tx).await?;
         log::info!("Revert block in tx {}", to_hex(&tx_hash));
+        gw_metrics::challenger().sent(ChallengeAction::Revert);
         self.last_submit_tx = Some(tx_hash);
 
         Ok(())
@@ -765,6 +823,71 @@ impl Challenger {
     }
 }
 
+/// Returns true if the cancel challenge of the bad withdrawal target passes
+/// the offchain validator, i.e. the block producer can cancel the challenge.
+///
+/// Other targets aren't verified, the offchain validator only verifies
+/// transactions with the `gw_challenge` cfg.
+fn verify_bad_withdrawal(
+    ctx: OffChainValidatorContext,
+    store: &Store,
+    target: &ChallengeTarget,
+) -> Result<bool> {
+    let target_type = ChallengeTargetType::try_from(target.target_type())
+        .map_err(|_| anyhow!("invalid challenge type"))?;
+    if target_type != ChallengeTargetType::Withdrawal {
+        return Ok(false);
+    }
+
+    let block_hash: H256 = target.block_hash().unpack();
+    let bad_block = store
+        .get_bad_block(&block_hash)
+        .ok_or_else(|| anyhow!("bad block not found"))?;
+    let block_producer = {
+        let address: Bytes = bad_block.raw().block_producer().unpack();
+        RegistryAddress::from_slice(&address).ok_or_else(|| anyhow!("invalid block producer"))?
+    };
+    // The first bad block is the child of the last valid tip.
+    let parent_block = store.get_last_valid_tip_block()?;
+    let mut db = store.begin_transaction();
+    let reverted_block_root = db.get_reverted_block_smt_root()?;
+    let mut mem_tree = MemStateDB::from_store(store.get_snapshot())?;
+    let mut validator = OffChainCancelChallengeValidator::new(
+        ctx,
+        block_producer,
+        &parent_block,
+        bad_block.raw().timestamp().unpack(),
+        reverted_block_root,
+    );
+
+    let get_withdrawal = |db: &StoreTransaction, index: u32| -> Result<_> {
+        let key = WithdrawalKey::new_builder()
+            .block_hash(block_hash.pack())
+            .index(index.pack())
+            .build();
+        let withdrawal = db.get_withdrawal_by_key(&key)?;
+        withdrawal.ok_or_else(|| anyhow!("bad block withdrawal {} not found", index))
+    };
+    // Withdrawals before the target are packaged in the mock block too.
+    let target_index: u32 = target.target_index().unpack();
+    for index in 0..target_index {
+        let withdrawal = get_withdrawal(&db, index)?;
+        validator
+            .verify_withdrawal_request(&mut db, &mut mem_tree, withdrawal)
+            .with_context(|| format!("verify withdrawal {}", index))?;
+    }
+    let withdrawal = get_withdrawal(&db, target_index)?;
+    let result = validator.verify_withdrawal_request(&mut db, &mut mem_tree, withdrawal);
+    Ok(matches!(result, Ok(Some(_))))
+}
+
+fn record_failure(action: ChallengeAction, result: Result<()>) -> Result<()> {
+    if result.is_err() {
+        gw_metrics::challenger().failed(action);
+    }
+    result
+}
+
 struct RollupState {
     rollup_cell: CellInfo,
     inner: GlobalState,
@@ -0,0 +1,274 @@
+//! Custodian solvency audit.
+//!
+//! Compare the assets held by custodian cells on L1 with what L2 owes for
+//! them, for CKB and every sUDT.
+//!
+//! The custodian cells are queried at the rollup cell of L2 block N, but the
+//! L2 supply is read at the last valid tip. Blocks after N are not submitted yet,
+//! so their deposits are still deposit cells on L1 and their withdrawals are
+//! still held by custodians. They are counted as pending deposits and pending
+//! withdrawals:
+//!
+//! holdings = custodians + pending deposits
+//! obligations = L2 supply + pending withdrawals
+
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::{bail, Context, Result};
+use gw_common::{
+    builtins::CKB_SUDT_ACCOUNT_ID, ckb_decimal::CKBCapacity, state::State, CKB_SUDT_SCRIPT_ARGS,
+};
+use gw_generator::sudt::build_l2_sudt_script;
+use gw_jsonrpc_types::blockchain::{Uint128, Uint32, Uint64};
+use gw_rpc_client::rpc_client::RPCClient;
+use gw_store::{
+    state::{history::history_state::RWConfig, BlockStateDB},
+    traits::chain_store::ChainStore,
+    Store,
+};
+use gw_types::{
+    core::ScriptHashType,
+    h256::*,
+    offchain::{global_state_from_slice, CellInfo, CompatibleFinalizedTimepoint, CustodianStat},
+    packed::{GlobalState, Script},
+    prelude::*,
+};
+use gw_utils::RollupContext;
+use serde::Serialize;
+
+/// Custodian cells are consumed and created by every submitted block, query
+/// them again if the rollup cell changes during the query.
+const MAX_QUERY_ATTEMPTS: usize = 3;
+
+pub struct CustodianAuditor {
+    rpc_client: RPCClient,
+    store: Store,
+    rollup_context: RollupContext,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CustodianAuditReport {
+    /// L2 block number of the rollup cell that custodians are queried at.
+    pub submitted_block_number: Uint64,
+    /// L2 block number that L2 supply is read at.
+    pub last_valid_tip_block_number: Uint64,
+    pub assets: Vec<AssetAuditReport>,
+}
+
+/// Amounts are in the smallest unit of the asset on L1, e.g. shannons for CKB.
+#[derive(Debug, Clone, Serialize)]
+pub struct AssetAuditReport {
+    /// Script hash of the L1 sUDT, zero for CKB.
+    pub sudt_script_hash: ckb_fixed_hash::H256,
+    /// Account id of the L2 sUDT, none if nothing is deposited yet.
+    pub l2_sudt_id: Option<Uint32>,
+    pub finalized_custodian: Uint128,
+    pub total_custodian: Uint128,
+    pub pending_deposits: Uint128,
+    pub l2_supply: Uint128,
+    pub pending_withdrawals: Uint128,
+    pub deficit: Uint128,
+}
+
+impl CustodianAuditReport {
+    pub fn deficit_assets(&self) -> impl Iterator<Item = &AssetAuditReport> {
+        self.assets.iter().filter(|a| a.deficit.value() > 0)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_vec_pretty(self)?;
+        std::fs::write(path, content)
+            .with_context(|| format!("write custodian audit report to {}", path.display()))
+    }
+}
+
+#[derive(Default)]
+struct AssetBalance {
+    l2_sudt_id: Option<u32>,
+    finalized_custodian: u128,
+    total_custodian: u128,
+    pending_deposits: u128,
+    l2_supply: u128,
+    pending_withdrawals: u128,
+}
+
+impl AssetBalance {
+    fn holdings(&self) -> u128 {
+        self.total_custodian.saturating_add(self.pending_deposits)
+    }
+
+    fn obligations(&self) -> u128 {
+        self.l2_supply.saturating_add(self.pending_withdrawals)
+    }
+}
+
+impl CustodianAuditor {
+    pub fn new(rpc_client: RPCClient, store: Store, rollup_context: RollupContext) -> Self {
+        CustodianAuditor {
+            rpc_client,
+            store,
+            rollup_context,
+        }
+    }
+
+    pub async fn audit(&self) -> Result<CustodianAuditReport> {
+        let (global_state, custodian_stat) = self.query_custodians().await?;
+        let submitted_block_number = global_state.block().count().unpack().saturating_sub(1);
+        let submitted_block_hash: H256 = global_state.tip_block_hash().unpack();
+
+        let mut db = self.store.begin_transaction();
+        if db.get_block_hash_by_number(submitted_block_number)? != Some(submitted_block_hash) {
+            bail!(
+                "local chain is not synced to submitted block #{}",
+                submitted_block_number
+            );
+        }
+        // The state is read at the last valid tip, bad blocks are not counted.
+        let last_valid_tip_block_number: u64 =
+            db.get_last_valid_tip_block()?.raw().number().unpack();
+        if submitted_block_number > last_valid_tip_block_number {
+            bail!(
+                "submitted block #{} is after the last valid tip #{}",
+                submitted_block_number,
+                last_valid_tip_block_number
+            );
+        }
+
+        let mut assets: BTreeMap<H256, AssetBalance> = BTreeMap::new();
+        let ckb = assets.entry(CKB_SUDT_SCRIPT_ARGS).or_default();
+        ckb.finalized_custodian = custodian_stat.finalized_capacity;
+        ckb.total_custodian = custodian_stat.total_capacity;
+        for (sudt_script, stat) in custodian_stat.sudt_stat {
+            let sudt_script_hash = Script::new_unchecked(sudt_script.as_bytes()).hash();
+            let asset = assets.entry(sudt_script_hash).or_default();
+            asset.finalized_custodian = stat.finalized_amount;
+            asset.total_custodian = stat.total_amount;
+        }
+        for sudt_script_hash in db.get_asset_script_hash_iter() {
+            assets.entry(sudt_script_hash).or_default();
+        }
+
+        for block_number in submitted_block_number + 1..=last_valid_tip_block_number {
+            let block_hash = db
+                .get_block_hash_by_number(block_number)?
+                .with_context(|| format!("block #{} not found", block_number))?;
+            let block = db
+                .get_block(&block_hash)?
+                .with_context(|| format!("block #{} not found", block_number))?;
+            for withdrawal in block.withdrawals() {
+                let raw = withdrawal.raw();
+                let capacity: u64 = raw.capacity().unpack();
+                let ckb = assets.entry(CKB_SUDT_SCRIPT_ARGS).or_default();
+                ckb.pending_withdrawals += capacity as u128;
+                let sudt_script_hash: H256 = raw.sudt_script_hash().unpack();
+                if sudt_script_hash != CKB_SUDT_SCRIPT_ARGS {
+                    let amount: u128 = raw.amount().unpack();
+                    let asset = assets.entry(sudt_script_hash).or_default();
+                    asset.pending_withdrawals += amount;
+                }
+            }
+            let deposit_info_vec = db.get_block_deposit_info_vec(block_number);
+            for info in deposit_info_vec.into_iter().flatten() {
+                let request = info.request();
+                let capacity: u64 = request.capacity().unpack();
+                let ckb = assets.entry(CKB_SUDT_SCRIPT_ARGS).or_default();
+                ckb.pending_deposits += capacity as u128;
+                let sudt_script_hash: H256 = request.sudt_script_hash().unpack();
+                if sudt_script_hash != CKB_SUDT_SCRIPT_ARGS {
+                    let amount: u128 = request.amount().unpack();
+                    let asset = assets.entry(sudt_script_hash).or_default();
+                    asset.pending_deposits += amount;
+                }
+            }
+        }
+
+        let tree = BlockStateDB::from_store(&mut db, RWConfig::readonly())?;
+        for (sudt_script_hash, asset) in assets.iter_mut() {
+            if *sudt_script_hash == CKB_SUDT_SCRIPT_ARGS {
+                let supply = tree.get_sudt_total_supply(CKB_SUDT_ACCOUNT_ID)?;
+                let supply = CKBCapacity::from_layer2(supply)
+                    .to_layer1()
+                    .context("CKB total supply overflow")?;
+                asset.l2_sudt_id = Some(CKB_SUDT_ACCOUNT_ID);
+                asset.l2_supply = supply as u128;
+                continue;
+            }
+
+            let l2_sudt_script_hash =
+                build_l2_sudt_script(&self.rollup_context, sudt_script_hash).hash();
+            if let Some(id) = tree.get_account_id_by_script_hash(&l2_sudt_script_hash)? {
+                let supply = tree.get_sudt_total_supply(id)?;
+                asset.l2_sudt_id = Some(id);
+                asset.l2_supply = u128::try_from(supply).unwrap_or(u128::MAX);
+            }
+        }
+
+        let assets = assets
+            .into_iter()
+            .map(|(sudt_script_hash, asset)| {
+                let holdings = asset.holdings();
+                let obligations = asset.obligations();
+                gw_metrics::custodian().set_audit(&sudt_script_hash, holdings, obligations);
+
+                AssetAuditReport {
+                    sudt_script_hash: ckb_fixed_hash::H256(sudt_script_hash),
+                    l2_sudt_id: asset.l2_sudt_id.map(Into::into),
+                    finalized_custodian: asset.finalized_custodian.into(),
+                    total_custodian: asset.total_custodian.into(),
+                    pending_deposits: asset.pending_deposits.into(),
+                    l2_supply: asset.l2_supply.into(),
+                    pending_withdrawals: asset.pending_withdrawals.into(),
+                    deficit: obligations.saturating_sub(holdings).into(),
+                }
+            })
+            .collect();
+
+        Ok(CustodianAuditReport {
+            submitted_block_number: submitted_block_number.into(),
+            last_valid_tip_block_number: last_valid_tip_block_number.into(),
+            assets,
+        })
+    }
+
+    async fn query_custodians(&self) -> Result<(GlobalState, CustodianStat)> {
+        let rollup_config = &self.rollup_context.rollup_config;
+        let rollup_script_hash = self.rollup_context.rollup_script_hash;
+        let finality_blocks = rollup_config.finality_blocks().unpack();
+        let custodian_lock = Script::new_builder()
+            .code_hash(rollup_config.custodian_script_type_hash())
+            .hash_type(ScriptHashType::Type.into())
+            .args(rollup_script_hash.as_slice().to_vec().pack())
+            .build();
+
+        for _ in 0..MAX_QUERY_ATTEMPTS {
+            let rollup_cell = self.query_rollup_cell().await?;
+            let global_state = global_state_from_slice(&rollup_cell.data)?;
+            let compatible_finalized_timepoint =
+                CompatibleFinalizedTimepoint::from_global_state(&global_state, finality_blocks);
+
+            let stat = self
+                .rpc_client
+                .indexer
+                .stat_custodian_cells(
+                    custodian_lock.clone(),
+                    None,
+                    &compatible_finalized_timepoint,
+                )
+                .await?;
+            if self.query_rollup_cell().await?.out_point == rollup_cell.out_point {
+                return Ok((global_state, stat));
+            }
+            log::debug!("[custodian audit] rollup cell changed, query custodians again");
+        }
+
+        bail!(
+            "rollup cell changed in all {} custodian queries",
+            MAX_QUERY_ATTEMPTS
+        )
+    }
+
+    async fn query_rollup_cell(&self) -> Result<CellInfo> {
+        let rollup_cell = self.rpc_client.query_rollup_cell().await?;
+        rollup_cell.context("rollup cell not found")
+    }
+}
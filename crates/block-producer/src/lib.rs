@@ -4,6 +4,7 @@ pub mod chain_updater;
 pub mod challenger;
 pub mod cleaner;
 pub mod custodian;
+pub mod custodian_audit;
 pub mod debugger;
 pub mod deposit;
 pub mod produce_block;
@@ -5,7 +5,10 @@ use std::{collections::HashSet, fmt::Display, sync::Arc, time::Duration};
 use anyhow::{bail, ensure, Context, Result};
 use gw_chain::chain::Chain;
 use gw_config::PscConfig;
-use gw_mem_pool::{block_sync_server::BlockSyncServerState, pool::MemPool};
+use gw_mem_pool::{
+    block_sync_server::{local_block_from_store, BlockSyncServerState},
+    pool::MemPool,
+};
 use gw_rpc_client::{
     error::{get_jsonrpc_error_code, CkbRpcError},
     rpc_client::RPCClient,
@@ -961,53 +964,7 @@ fn publish_local_block(
     snap: &StoreSnapshot,
     b: u64,
 ) -> Result<()> {
-    let block_hash = snap
-        .get_block_hash_by_number(b)?
-        .context("get block hash")?;
-    let block = snap.get_block(&block_hash)?.context("get block")?;
-    let global_state = snap
-        .get_block_post_global_state(&block_hash)?
-        .context("get block post global state")?;
-    let deposit_info_vec = snap
-        .get_block_deposit_info_vec(b)
-        .context("get block deposit info vec")?;
-    let deposit_asset_scripts = {
-        let reader = deposit_info_vec.as_reader();
-        let asset_hashes: HashSet<H256> = reader
-            .iter()
-            .filter_map(|r| {
-                let h: H256 = r.request().sudt_script_hash().unpack();
-                if h.is_zero() {
-                    None
-                } else {
-                    Some(h)
-                }
-            })
-            .collect();
-        let asset_scripts = asset_hashes.into_iter().map(|h| {
-            snap.get_asset_script(&h)?
-                .with_context(|| format!("block {} asset script {} not found", b, h.pack()))
-        });
-        asset_scripts.collect::<Result<Vec<_>>>()?
-    };
-    let withdrawals = {
-        let reqs = block.as_reader().withdrawals();
-        let extra_reqs = reqs.iter().map(|w| {
-            let h = w.hash();
-            snap.get_withdrawal(&h)?
-                .with_context(|| format!("block {} withdrawal {} not found", b, h.pack()))
-        });
-        extra_reqs.collect::<Result<Vec<_>>>()?
-    };
-    sync_server.publish_local_block(
-        LocalBlock::new_builder()
-            .block(block)
-            .post_global_state(global_state)
-            .deposit_info_vec(deposit_info_vec)
-            .deposit_asset_scripts(ScriptVec::new_builder().set(deposit_asset_scripts).build())
-            .withdrawals(withdrawals.pack())
-            .build(),
-    );
+    sync_server.publish_local_block(local_block_from_store(snap, b)?);
     Ok(())
 }
 
@@ -98,7 +98,8 @@ impl ReplayBlock {
         let db = &store.begin_transaction();
         let chain_view = ChainView::new(&db, parent_block_hash);
         for (tx_index, tx) in block.transactions().into_iter().enumerate() {
-            generator.check_transaction_signature(&state, &tx)?;
+            let session_key_spend =
+                generator.check_transaction_signature(&state, &tx, block_number)?;
 
             // check nonce
             let raw_tx = tx.raw();
@@ -123,6 +124,9 @@ impl ReplayBlock {
                 None,
                 None,
             )?;
+            if let Some(spend) = session_key_spend {
+                spend.record(&mut state)?;
+            }
 
             state.finalise()?;
 
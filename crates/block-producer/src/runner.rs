@@ -8,35 +8,49 @@ use std::{
 use anyhow::{anyhow, bail, Context, Result};
 use futures::future::OptionFuture;
 use gw_chain::chain::Chain;
-use gw_challenge::offchain::{OffChainMockContext, OffChainMockContextBuildArgs};
+use gw_challenge::offchain::{
+    OffChainMockContext, OffChainMockContextBuildArgs, OffChainValidatorContext,
+};
 use gw_common::{
     blake2b::new_blake2b, builtins::ETH_REGISTRY_ACCOUNT_ID, registry_address::RegistryAddress,
 };
-use gw_config::{BlockProducerConfig, Config, ForkConfig, NodeMode, RegistryType};
+use gw_config::{
+    BlockProducerConfig, Config, CustodianAuditConfig, ForkConfig, NodeMode, RegistryType,
+    StoreBackend,
+};
 use gw_generator::{
-    account_lock_manage::{secp256k1::Secp256k1Eth, AccountLockManage},
+    account_lock_manage::{
+        multisig::Secp256k1EthMultisig, secp256k1::Secp256k1Eth, webauthn::Secp256r1WebAuthn,
+        AccountLockManage,
+    },
     backend_manage::BackendManage,
     genesis::init_genesis,
     Generator,
 };
 use gw_mem_pool::{
     account_creator::AccountCreator,
-    block_sync_server::{block_sync_server_protocol, BlockSyncServerState},
+    block_sync_server::{
+        block_range_server_protocol, block_sync_server_protocol, BlockSyncServerState,
+    },
     default_provider::DefaultMemPoolProvider,
     pool::{MemPool, MemPoolCreateArgs},
 };
-use gw_p2p_network::P2PNetwork;
+use gw_p2p_network::{peers::Peers, P2PNetwork};
 use gw_polyjuice_sender_recover::recover::PolyjuiceSenderRecover;
 use gw_rpc_client::{
     ckb_client::CkbClient, contract::ContractsCellDepManager, error::get_jsonrpc_error_code,
     indexer_client::CkbIndexerClient, rpc_client::RPCClient,
 };
 use gw_rpc_server::{
+    forward::{
+        forward_client_protocol, forward_relay_protocol, forward_server_protocol, ForwardClient,
+    },
     registry::{BoxedTestModeRpc, Registry, RegistryArgs},
     server::start_jsonrpc_server,
 };
 use gw_store::{
     migrate::{init_migration_factory, open_or_create_db},
+    schema::COLUMNS,
     Store,
 };
 use gw_types::{
@@ -60,10 +74,14 @@ use tracing::{info_span, instrument};
 
 use crate::{
     block_producer::{BlockProducer, BlockProducerCreateArgs},
-    block_sync_client::{block_sync_client_protocol, BlockSyncClient, P2PStream},
+    block_sync_client::{
+        block_range_client_protocol, block_range_relay_protocol, block_sync_client_protocol,
+        block_sync_relay_protocol, BlockSyncClient, P2PStream,
+    },
     chain_updater::ChainUpdater,
     challenger::{Challenger, ChallengerNewArgs},
     cleaner::Cleaner,
+    custodian_audit::CustodianAuditor,
     psc::{PSCContext, ProduceSubmitConfirm},
     test_mode_control::TestModeControl,
     types::ChainEvent,
@@ -310,6 +328,7 @@ impl BaseInitComponents {
             .clone();
 
         check_script(&script_config, &rollup_config, &rollup_type_script)?;
+        check_fee_sponsorship(consensus)?;
         let contracts_dep_manager = ContractsCellDepManager::build(
             rpc_client.clone(),
             script_config,
@@ -330,9 +349,17 @@ impl BaseInitComponents {
 
         // Open store
         let timer = Instant::now();
-        let store = Store::new(open_or_create_db(&config.store, init_migration_factory())?);
+        let store = match config.store.backend {
+            StoreBackend::RocksDb => {
+                Store::new(open_or_create_db(&config.store, init_migration_factory())?)
+            }
+            StoreBackend::Memory => {
+                log::warn!("Using in-memory store, chain data will be lost on exit");
+                Store::open_in_memory(COLUMNS)
+            }
+        };
         let elapsed_ms = timer.elapsed().as_millis();
-        log::debug!("Open rocksdb costs: {}ms.", elapsed_ms);
+        log::debug!("Open store costs: {}ms.", elapsed_ms);
 
         let secp_data: Bytes = {
             let out_point = consensus.genesis.secp_data_dep.out_point.clone();
@@ -364,8 +391,11 @@ impl BaseInitComponents {
 
         let rollup_config_hash: H256 = rollup_config.hash();
         let generator = {
-            let backend_manage = BackendManage::from_config(consensus.backend_forks.clone())
-                .with_context(|| "config backends")?;
+            let backend_manage = BackendManage::from_config_with_warm_up(
+                consensus.backend_forks.clone(),
+                config.aot_cache_dir.clone(),
+            )
+            .with_context(|| "config backends")?;
             let mut account_lock_manage = AccountLockManage::default();
             let allowed_eoa_type_hashes = rollup_config.as_reader().allowed_eoa_type_hashes();
             let eth_lock_script_type_hash = allowed_eoa_type_hashes
@@ -376,6 +406,25 @@ impl BaseInitComponents {
                 eth_lock_script_type_hash.hash().unpack(),
                 Arc::new(Secp256k1Eth::default()),
             );
+            // Passkey and multisig EOAs are optional
+            if let Some(webauthn_lock_script_type_hash) = allowed_eoa_type_hashes
+                .iter()
+                .find(|th| th.type_().to_entity() == AllowedEoaType::WebAuthn.into())
+            {
+                account_lock_manage.register_lock_algorithm(
+                    webauthn_lock_script_type_hash.hash().unpack(),
+                    Arc::new(Secp256r1WebAuthn::default()),
+                );
+            }
+            if let Some(multisig_lock_script_type_hash) = allowed_eoa_type_hashes
+                .iter()
+                .find(|th| th.type_().to_entity() == AllowedEoaType::Multisig.into())
+            {
+                account_lock_manage.register_lock_algorithm(
+                    multisig_lock_script_type_hash.hash().unpack(),
+                    Arc::new(Secp256k1EthMultisig::default()),
+                );
+            }
             Arc::new(Generator::new(
                 backend_manage,
                 account_lock_manage,
@@ -471,15 +520,35 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
 
     let has_block_producer_and_p2p =
         config.block_producer.is_some() && config.p2p_network_config.is_some();
-    let block_sync_server_state = if has_block_producer_and_p2p {
+    // Read-only nodes serve block sync to downstream peers in relay mode.
+    let is_relay = config.node_mode == NodeMode::ReadOnly
+        && config.sync_server.relay
+        && matches!(config.p2p_network_config, Some(ref c) if c.listen.is_some());
+    let block_sync_server_state = if has_block_producer_and_p2p
+        && (matches!(config.node_mode, NodeMode::FullNode | NodeMode::Test) || is_relay)
+    {
         Some(Arc::new(std::sync::Mutex::new(BlockSyncServerState::new(
             &config.sync_server,
+            base.store.clone(),
+            base.rollup_context.clone(),
         ))))
     } else {
         None
     };
 
     let (mem_pool, wallet, offchain_mock_context) = match config.block_producer.as_ref() {
+        // Watchtowers don't produce blocks, they only need the wallet to challenge.
+        Some(block_producer_config) if config.node_mode == NodeMode::Watchtower => {
+            let opt_wallet = block_producer_config
+                .wallet_config
+                .as_ref()
+                .map(|c| Wallet::from_config(c).with_context(|| "init watchtower wallet"))
+                .transpose()?;
+            let opt_offchain_mock_context = base
+                .init_offchain_mock_context(block_producer_config)
+                .await?;
+            (None, opt_wallet, opt_offchain_mock_context)
+        }
         Some(block_producer_config) => {
             let opt_wallet = block_producer_config
                 .wallet_config
@@ -497,6 +566,10 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
             let mem_pool = {
                 let registry_id = match block_producer_config.block_producer.address_type {
                     RegistryType::Eth => ETH_REGISTRY_ACCOUNT_ID,
+                    // The builtin contracts can't pay fees to a script hash address yet.
+                    RegistryType::ScriptHash => {
+                        bail!("block producer script hash address isn't supported yet")
+                    }
                 };
                 let block_producer = RegistryAddress::new(
                     registry_id,
@@ -559,6 +632,13 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
         config.liveness_duration_secs.unwrap_or(60),
     )));
 
+    spawn_store_metrics_updater(store.clone());
+    if let Some(ref audit_config) = config.custodian_audit {
+        let auditor =
+            CustodianAuditor::new(rpc_client.clone(), store.clone(), rollup_context.clone());
+        spawn_custodian_auditor(auditor, audit_config.clone());
+    }
+
     // check state db
     {
         let t = Instant::now();
@@ -591,6 +671,52 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
         .node_mode
     {
         NodeMode::ReadOnly => (None, None, None, None, None),
+        NodeMode::Watchtower => {
+            let block_producer_config = config
+                .block_producer
+                .clone()
+                .ok_or_else(|| anyhow!("must provide block producer config in watchtower mode"))?;
+            let wallet =
+                wallet.ok_or_else(|| anyhow!("wallet must be enabled in watchtower mode"))?;
+            let offchain_mock_context = offchain_mock_context
+                .ok_or_else(|| anyhow!("offchain mock require wallet in watchtower mode"))?;
+            let offchain_validator_context = OffChainValidatorContext::build(
+                &offchain_mock_context,
+                config.debug.clone(),
+                config.offchain_validator.clone().unwrap_or_default(),
+            )?;
+
+            let cleaner = Arc::new(Cleaner::new(
+                rpc_client.clone(),
+                ckb_genesis_info.clone(),
+                wallet,
+                block_producer_config.fee_rate,
+            ));
+
+            let challenger_wallet = match block_producer_config.wallet_config {
+                Some(ref c) => Wallet::from_config(c).with_context(|| "challenger wallet")?,
+                None => bail!("no wallet config for watchtower"),
+            };
+
+            let args = ChallengerNewArgs {
+                rollup_context,
+                rpc_client: rpc_client.clone(),
+                wallet: challenger_wallet,
+                config: block_producer_config,
+                debug_config: config.debug.clone(),
+                builtin_load_data,
+                ckb_genesis_info: ckb_genesis_info.clone(),
+                chain: Arc::clone(&chain),
+                tests_control: None,
+                cleaner: Arc::clone(&cleaner),
+                offchain_mock_context,
+                offchain_validator_context: Some(offchain_validator_context),
+                contracts_dep_manager: contracts_dep_manager.clone(),
+            };
+            let challenger = Challenger::new(args);
+
+            (None, Some(challenger), None, None, Some(cleaner))
+        }
         mode => {
             let block_producer_config = config
                 .block_producer
@@ -658,6 +784,7 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
                 tests_control: tests_control.clone(),
                 cleaner: Arc::clone(&cleaner),
                 offchain_mock_context,
+                offchain_validator_context: None,
                 contracts_dep_manager: contracts_dep_manager.clone(),
             };
             let challenger = Challenger::new(args);
@@ -672,7 +799,7 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
                 ckb_genesis_info,
                 config: block_producer_config,
                 tests_control: tests_control.clone(),
-                contracts_dep_manager,
+                contracts_dep_manager: contracts_dep_manager.clone(),
             };
             let block_producer =
                 BlockProducer::create(create_args).with_context(|| "init block producer")?;
@@ -694,33 +821,24 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
 
     let block_sync_client_p2p_stream_inbox: Arc<std::sync::Mutex<Option<P2PStream>>> =
         Arc::new(std::sync::Mutex::new(None));
-
-    // P2P network.
-    let p2p_control_and_handle = if let Some(ref p2p_network_config) = config.p2p_network_config {
-        let mut protocols: Vec<ProtocolMeta> = Vec::new();
-        match config.node_mode {
-            NodeMode::ReadOnly => {
-                log::info!("will enable p2p block sync client");
-                protocols.push(block_sync_client_protocol(
-                    block_sync_client_p2p_stream_inbox.clone(),
-                ));
-            }
-            NodeMode::FullNode | NodeMode::Test => {
-                if let Some(ref state) = block_sync_server_state {
-                    log::info!("will enable p2p block sync server");
-                    protocols.push(block_sync_server_protocol(state.clone()));
-                }
+    let block_range_client_p2p_stream_inbox: Arc<std::sync::Mutex<Option<P2PStream>>> =
+        Arc::new(std::sync::Mutex::new(None));
+    // Connected p2p peers, shared by the p2p protocols and the RPC server.
+    let peers = Arc::new(Peers::default());
+
+    // Read-only nodes forward submissions to the full node over p2p.
+    let forward_client = match config.p2p_network_config {
+        Some(ref p2p_network_config) if config.node_mode == NodeMode::ReadOnly => {
+            let client =
+                ForwardClient::from_dial_addresses(&p2p_network_config.dial, peers.clone());
+            if client.is_none() {
+                log::warn!(
+                    "won't forward submissions, no dial address with the full node's peer id"
+                );
             }
+            client.map(Arc::new)
         }
-        let mut network = P2PNetwork::init(p2p_network_config, protocols).await?;
-        let control = network.control().clone();
-        let handle = tokio::spawn(async move {
-            log::info!("running the p2p network");
-            network.run().await;
-        });
-        Some((control, handle))
-    } else {
-        None
+        _ => None,
     };
 
     // RPC registry
@@ -757,9 +875,75 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
         polyjuice_sender_recover,
         debug_backend_forks: config.debug_backend_forks.clone(),
         gasless_tx_support_config: config.gasless_tx_support.clone(),
+        forward_client: forward_client.clone(),
+        contracts_dep_manager: Some(contracts_dep_manager),
+        block_sync_server_state: block_sync_server_state.clone(),
+        peers: peers.clone(),
     };
 
     let rpc_registry = Registry::create(args).await?;
+
+    // P2P network.
+    let p2p_control_and_handle = if let Some(ref p2p_network_config) = config.p2p_network_config {
+        let mut protocols: Vec<ProtocolMeta> = Vec::new();
+        match config.node_mode {
+            NodeMode::ReadOnly => {
+                if let Some(ref state) = block_sync_server_state {
+                    log::info!("will enable p2p block sync relay");
+                    protocols.push(block_sync_relay_protocol(
+                        block_sync_client_p2p_stream_inbox.clone(),
+                        state.clone(),
+                        peers.clone(),
+                    ));
+                    protocols.push(block_range_relay_protocol(
+                        block_range_client_p2p_stream_inbox.clone(),
+                        state.clone(),
+                        peers.clone(),
+                    ));
+                } else {
+                    log::info!("will enable p2p block sync client");
+                    protocols.push(block_sync_client_protocol(
+                        block_sync_client_p2p_stream_inbox.clone(),
+                        peers.clone(),
+                    ));
+                    protocols.push(block_range_client_protocol(
+                        block_range_client_p2p_stream_inbox.clone(),
+                        peers.clone(),
+                    ));
+                }
+                if let Some(ref client) = forward_client {
+                    log::info!("will forward submissions to the full node");
+                    if block_sync_server_state.is_some() {
+                        protocols
+                            .push(forward_relay_protocol(client.clone(), rpc_registry.clone()));
+                    } else {
+                        protocols.push(forward_client_protocol(client.clone()));
+                    }
+                }
+            }
+            // Watchtowers follow the rollup from L1 only.
+            NodeMode::Watchtower => {}
+            NodeMode::FullNode | NodeMode::Test => {
+                if let Some(ref state) = block_sync_server_state {
+                    log::info!("will enable p2p block sync server");
+                    protocols.push(block_sync_server_protocol(state.clone(), peers.clone()));
+                    protocols.push(block_range_server_protocol(state.clone(), peers.clone()));
+                    log::info!("will accept forwarded submissions");
+                    protocols.push(forward_server_protocol(rpc_registry.clone()));
+                }
+            }
+        }
+        let mut network = P2PNetwork::init(p2p_network_config, protocols, peers).await?;
+        let control = network.control().clone();
+        let handle = tokio::spawn(async move {
+            log::info!("running the p2p network");
+            network.run().await;
+        });
+        Some((control, handle))
+    } else {
+        None
+    };
+    let backend_fork_scheduler = rpc_registry.backend_fork_scheduler();
     let rpc_handler = Arc::new(rpc_registry.to_handler());
 
     let rpc_address: SocketAddr = {
@@ -822,7 +1006,7 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
     let has_psc_task = psc_task.is_some();
     let psc_task = OptionFuture::from(psc_task);
 
-    let block_sync_task = if config.node_mode == NodeMode::ReadOnly {
+    let block_sync_task = if matches!(config.node_mode, NodeMode::ReadOnly | NodeMode::Watchtower) {
         let client = BlockSyncClient {
             store: store.clone(),
             rpc_client: rpc_client.clone(),
@@ -831,8 +1015,12 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
             chain_updater,
             rollup_type_script: rollup_type_script.clone(),
             p2p_stream_inbox: block_sync_client_p2p_stream_inbox,
+            block_range_stream_inbox: block_range_client_p2p_stream_inbox,
             completed_initial_syncing: false,
             liveness: liveness.clone(),
+            relay: block_sync_server_state.clone(),
+            max_hops: config.sync_server.max_hops,
+            backend_fork_scheduler,
         };
         let shutdown_completed_send = shutdown_completed_send.clone();
         let mut shutdown_event_recv = shutdown_event.subscribe();
@@ -985,6 +1173,20 @@ async fn check_ckb_version(rpc_client: &RPCClient) -> Result<()> {
     Ok(())
 }
 
+/// Sponsors are charged by the backends, the mem-pool must not prioritize
+/// sponsored transactions before the backends are switched.
+fn check_fee_sponsorship(fork_config: &ForkConfig) -> Result<()> {
+    if let Some(fork_number) = fork_config.enable_fee_sponsorship {
+        if fork_config.fee_sponsorship_backend_fork().is_none() {
+            return Err(anyhow!(
+                "enable_fee_sponsorship ({}) requires a backend fork at or before it which switches the Meta and Polyjuice backends, see docs/life_of_a_polyjuice_transaction.md",
+                fork_number
+            ));
+        }
+    }
+    Ok(())
+}
+
 async fn check_rollup_config_cell(
     fork_config: &ForkConfig,
     rollup_config: &RollupConfig,
@@ -1096,6 +1298,40 @@ async fn sigint_or_sigterm() {
     log::info!("received sigint or sigterm, shutting down");
 }
 
+fn spawn_store_metrics_updater(store: Store) {
+    tokio::spawn(async move {
+        loop {
+            gw_metrics::store().update(&store);
+            tokio::time::sleep(Duration::from_secs(15)).await;
+        }
+    });
+}
+
+fn spawn_custodian_auditor(auditor: CustodianAuditor, config: CustodianAuditConfig) {
+    tokio::spawn(async move {
+        loop {
+            match auditor.audit().await {
+                Ok(report) => {
+                    for asset in report.deficit_assets() {
+                        log::warn!(
+                            "[custodian audit] asset {:#x} deficit {}",
+                            asset.sudt_script_hash,
+                            asset.deficit.value()
+                        );
+                    }
+                    if let Some(ref path) = config.report_path {
+                        if let Err(err) = report.write(path) {
+                            log::warn!("[custodian audit] {:#}", err);
+                        }
+                    }
+                }
+                Err(err) => log::warn!("[custodian audit] audit failed: {:#}", err),
+            }
+            tokio::time::sleep(Duration::from_secs(config.interval_secs)).await;
+        }
+    });
+}
+
 fn spawn_starvation_detector() {
     tokio::spawn(async move {
         let mut instant = Instant::now();
@@ -317,7 +317,13 @@ impl Chain {
                     }
 
                     if let Some(ref target) = self.challenge_target {
-                        db.insert_bad_block(&l2block, &global_state)?;
+                        db.insert_bad_block(
+                            &l2block,
+                            &global_state,
+                            &deposit_info_vec,
+                            deposit_asset_scripts,
+                            &withdrawals,
+                        )?;
                         log::info!("insert bad block 0x{}", hex::encode(l2block.hash()));
 
                         let global_block_root: H256 = global_state.block().merkle_root().unpack();
@@ -335,9 +341,9 @@ impl Chain {
                         db,
                         l2block.clone(),
                         global_state.clone(),
-                        deposit_info_vec,
-                        deposit_asset_scripts,
-                        withdrawals,
+                        deposit_info_vec.clone(),
+                        deposit_asset_scripts.clone(),
+                        withdrawals.clone(),
                     )? {
                         db.rollback()?;
 
@@ -347,8 +353,15 @@ impl Chain {
                             you can rewind bad blocks with the rewind-to-last-valid-block subcommand",
                             block_number
                         );
-
-                        db.insert_bad_block(&l2block, &global_state)?;
+                        gw_metrics::challenger().bad_blocks.inc();
+
+                        db.insert_bad_block(
+                            &l2block,
+                            &global_state,
+                            &deposit_info_vec,
+                            deposit_asset_scripts,
+                            &withdrawals,
+                        )?;
                         log::info!("insert bad block 0x{}", hex::encode(l2block.hash()));
 
                         let global_block_root: H256 = global_state.block().merkle_root().unpack();
@@ -411,7 +424,11 @@ impl Chain {
                         && local_tip_block_number >= challenge_block_number)
                         || local_bad_block_number > Some(challenge_block_number)
                     {
-                        log::info!("challenge cancelable, build verify context");
+                        log::warn!(
+                            "challenge on block #{} is cancelable, build verify context",
+                            challenge_block_number
+                        );
+                        gw_metrics::challenger().bad_challenges.inc();
 
                         let generator = Arc::clone(&self.generator);
                         let context = Box::new(gw_challenge::context::build_verify_context(
@@ -2,6 +2,7 @@ use std::collections::{HashMap, HashSet};
 
 use anyhow::{anyhow, Result};
 use gw_config::ContractsCellDep;
+use gw_generator::session_key::session_key_lock;
 use gw_types::{
     bytes::Bytes,
     core::{DepType, SigningType, Status},
@@ -147,7 +148,12 @@ pub fn build_output(
             Ok(cancel.build_output(data, Some(verifier_witness), None, None))
         }
         VerifyWitness::TxSignature(witness) => {
-            let verifier_lock = context.sender_script;
+            let sender_script_hash = context.sender_script.hash();
+            // Txs signed by a session key are verified by the session key's lock
+            let verifier_lock = match context.session_key {
+                Some(ref session_key) => session_key_lock(&context.sender_script, session_key),
+                None => context.sender_script,
+            };
             let receiver_script = context
                 .receiver_script
                 .ok_or_else(|| anyhow!("receiver script not found"))?;
@@ -169,8 +175,16 @@ pub fn build_output(
                 witness,
             );
 
-            let data = cancel.build_verifier_data(receiver_script.hash());
-            Ok(cancel.build_output(data, Some(verifier_witness), None, None))
+            let data = cancel.build_verifier_data(sender_script_hash, receiver_script.hash());
+            let mut output = cancel.build_output(data, Some(verifier_witness), None, None);
+            if let Some(session_key) = context.session_key {
+                output.challenge_witness = output
+                    .challenge_witness
+                    .as_builder()
+                    .input_type(Some(session_key.as_bytes()).pack())
+                    .build();
+            }
+            Ok(output)
         }
         VerifyWitness::TxExecution {
             witness,
@@ -393,9 +407,13 @@ impl<'a> CancelChallenge<'a, CCTransactionWitness> {
 
 impl<'a> CancelChallenge<'a, CCTransactionSignatureWitness> {
     // owner_lock_hash(32 bytes) | message(32 bytes)
-    pub fn build_verifier_data(&self, receiver_script_hash: H256) -> Bytes {
+    pub fn build_verifier_data(
+        &self,
+        sender_script_hash: H256,
+        receiver_script_hash: H256,
+    ) -> Bytes {
         let owner_lock_hash = self.owner_lock.hash();
-        let message = self.calc_tx_message(&receiver_script_hash);
+        let message = self.calc_tx_message(&sender_script_hash, &receiver_script_hash);
 
         let mut data = [0u8; 64];
         data[0..32].copy_from_slice(&owner_lock_hash);
@@ -404,11 +422,11 @@ impl<'a> CancelChallenge<'a, CCTransactionSignatureWitness> {
         data.to_vec().into()
     }
 
-    fn calc_tx_message(&self, receiver_script_hash: &H256) -> [u8; 32] {
+    fn calc_tx_message(&self, sender_script_hash: &H256, receiver_script_hash: &H256) -> [u8; 32] {
         let raw_tx = self.verify_witness.l2tx().raw();
         raw_tx.calc_message(
             &self.rollup_type_hash,
-            &self.verifier_lock.hash(),
+            sender_script_hash,
             receiver_script_hash,
         )
     }
@@ -186,6 +186,7 @@ fn build_verify_withdrawal_witness(
     Ok(VerifyContext {
         sender_script,
         receiver_script: None,
+        session_key: None,
         verify_witness: VerifyWitness::Withdrawal(verify_witness),
     })
 }
@@ -230,6 +231,7 @@ fn build_verify_transaction_signature_witness(
     Ok(VerifyContext {
         sender_script: kv_witness.sender_script,
         receiver_script: Some(kv_witness.receiver_script),
+        session_key: None,
         verify_witness: VerifyWitness::TxSignature(verify_witness),
     })
 }
@@ -271,6 +273,7 @@ fn build_verify_transaction_witness(
     Ok(VerifyContext {
         sender_script: kv_witness.sender_script,
         receiver_script: Some(kv_witness.receiver_script),
+        session_key: None,
         verify_witness: VerifyWitness::TxExecution {
             load_data: kv_witness.load_data.unwrap_or_else(HashMap::default),
             recover_accounts: kv_witness.recover_accounts.unwrap_or_else(Vec::default),
@@ -1,12 +1,19 @@
 use crate::types::{VerifyContext, VerifyWitness};
 
 use anyhow::{anyhow, bail, Context, Result};
+use gw_common::builtins::{ETH_REGISTRY_ACCOUNT_ID, RESERVED_ACCOUNT_ID};
 use gw_common::merkle_utils::{
     calculate_ckb_merkle_root, calculate_state_checkpoint, ckb_merkle_leaf_hash, CBMT,
 };
 use gw_common::registry_address::RegistryAddress;
 use gw_common::state::{
-    build_account_field_key, State, GW_ACCOUNT_NONCE_TYPE, GW_ACCOUNT_SCRIPT_HASH_TYPE,
+    build_account_field_key, build_account_key, State, GW_ACCOUNT_NONCE_TYPE,
+    GW_ACCOUNT_SCRIPT_HASH_TYPE,
+};
+use gw_generator::account_lock_manage::secp256k1::Secp256k1Eth;
+use gw_generator::session_key::{
+    get_session_keys, session_key_slot_key, session_key_spent_key, SessionKeyVerifier,
+    MAX_SESSION_KEYS,
 };
 use gw_generator::traits::StateExt;
 use gw_smt::smt::{Blake2bHasher, SMT, SMTH256};
@@ -22,7 +29,8 @@ use gw_types::offchain::RunResult;
 use gw_types::packed::{
     AccountMerkleState, BlockMerkleState, Byte32, CCTransactionSignatureWitness,
     CCWithdrawalWitness, CKBMerkleProof, ChallengeTarget, GlobalState, L2Block, L2Transaction,
-    RawL2Block, Script, SubmitTransactions, SubmitWithdrawals, Uint64, WithdrawalRequestExtra,
+    RawL2Block, Script, SessionKey, SubmitTransactions, SubmitWithdrawals, Uint64,
+    WithdrawalRequestExtra,
 };
 use gw_types::prelude::*;
 use gw_utils::{global_state_finalized_timepoint, RollupContext};
@@ -369,6 +377,7 @@ impl MockBlockParam {
         Ok(VerifyContext {
             sender_script,
             receiver_script: None,
+            session_key: None,
             verify_witness: VerifyWitness::Withdrawal(verify_witness),
         })
     }
@@ -385,7 +394,7 @@ impl MockBlockParam {
         let sender_script = get_script(mem_tree, sender_id)?;
         let receiver_script = get_script(mem_tree, receiver_id)?;
 
-        let kv_state: Vec<(H256, H256)> = vec![
+        let mut kv_state: Vec<(H256, H256)> = vec![
             (
                 build_account_field_key(sender_id, GW_ACCOUNT_SCRIPT_HASH_TYPE),
                 sender_script.hash(),
@@ -399,6 +408,19 @@ impl MockBlockParam {
                 H256::from_u32(tx.raw().nonce().unpack()),
             ),
         ];
+        // The challenge lock checks session keys against the slots of the sender,
+        // and the spending cap against the spent of the session key
+        let session_key = self.find_session_key(mem_tree, &sender_script, &receiver_script, &tx)?;
+        if let Some(ref session_key) = session_key {
+            for index in 0..MAX_SESSION_KEYS {
+                let slot_key = session_key_slot_key(sender_id, index);
+                let value = mem_tree.get_value(RESERVED_ACCOUNT_ID, &slot_key)?;
+                kv_state.push((build_account_key(RESERVED_ACCOUNT_ID, &slot_key), value));
+            }
+            let spent_key = session_key_spent_key(sender_id, session_key.pubkey_hash().as_slice());
+            let value = mem_tree.get_value(RESERVED_ACCOUNT_ID, &spent_key)?;
+            kv_state.push((build_account_key(RESERVED_ACCOUNT_ID, &spent_key), value));
+        }
         assert_eq!(
             mem_tree.get_nonce(sender_id)?,
             Unpack::<u32>::unpack(&tx.raw().nonce())
@@ -432,10 +454,43 @@ impl MockBlockParam {
         Ok(VerifyContext {
             sender_script,
             receiver_script: Some(receiver_script),
+            session_key,
             verify_witness: VerifyWitness::TxSignature(verify_witness),
         })
     }
 
+    /// The session key which signed the tx, `None` if it's signed by the
+    /// sender itself.
+    fn find_session_key(
+        &self,
+        mem_tree: &MemTree,
+        sender_script: &Script,
+        receiver_script: &Script,
+        tx: &L2Transaction,
+    ) -> Result<Option<SessionKey>> {
+        let sender_id = tx.raw().from_id().unpack();
+        let session_keys = get_session_keys(mem_tree, sender_id)?;
+        if session_keys.is_empty() {
+            return Ok(None);
+        }
+        let sender_address = match mem_tree
+            .get_registry_address_by_script_hash(ETH_REGISTRY_ACCOUNT_ID, &sender_script.hash())?
+        {
+            Some(address) => address,
+            None => return Ok(None),
+        };
+        let verifier = SessionKeyVerifier {
+            rollup_context: &self.rollup_context,
+            lock_algo: &Secp256k1Eth::default(),
+            block_number: self.number,
+        };
+        let session_key = verifier
+            .find_signer(&session_keys, sender_address, receiver_script, tx)
+            .ok()
+            .cloned();
+        Ok(session_key)
+    }
+
     #[cfg(gw_challenge)]
     fn build_transaction_execution_verify_context(
         &self,
@@ -554,6 +609,7 @@ impl MockBlockParam {
         Ok(VerifyContext {
             sender_script,
             receiver_script: Some(receiver_script),
+            session_key: None,
             verify_witness: VerifyWitness::TxExecution {
                 load_data,
                 recover_accounts,
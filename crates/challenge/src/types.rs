@@ -3,7 +3,7 @@ use gw_types::h256::*;
 use gw_types::offchain::RecoverAccount;
 use gw_types::packed::{
     Bytes, CCTransactionSignatureWitness, CCTransactionWitness, CCWithdrawalWitness, RawL2Block,
-    RawL2BlockVec, Script,
+    RawL2BlockVec, Script, SessionKey,
 };
 
 use std::collections::HashMap;
@@ -23,6 +23,8 @@ pub enum VerifyWitness {
 pub struct VerifyContext {
     pub sender_script: Script,
     pub receiver_script: Option<Script>,
+    /// The session key which signed the tx, for tx signature challenges.
+    pub session_key: Option<SessionKey>,
     pub verify_witness: VerifyWitness,
 }
 
@@ -42,6 +42,10 @@ pub struct Config {
     #[serde(default)]
     pub db_block_validator: Option<DBBlockValidatorConfig>,
     pub store: StoreConfig,
+    /// Directory to cache AOT code of backend generators, AOT code is
+    /// compiled on every start if it's not set.
+    #[serde(default)]
+    pub aot_cache_dir: Option<PathBuf>,
     #[serde(default)]
     pub trace: Option<Trace>,
     #[serde(default)]
@@ -51,6 +55,9 @@ pub struct Config {
     /// Gasless tx support is enabled when this config presents.
     #[serde(default)]
     pub gasless_tx_support: Option<GaslessTxSupportConfig>,
+    /// Periodically audit custodian solvency when this config presents.
+    #[serde(default)]
+    pub custodian_audit: Option<CustodianAuditConfig>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Hash)]
@@ -59,6 +66,10 @@ pub enum RPCMethods {
     PProf,
     Test,
     Debug,
+    /// Database maintenance, e.g. manual compaction.
+    Admin,
+    /// Quote and match liquidity provider offers for fast withdrawal.
+    FastWithdrawal,
 }
 
 #[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -68,6 +79,11 @@ pub struct RPCServerConfig {
     #[serde(default)]
     pub enable_methods: HashSet<RPCMethods>,
     pub send_tx_rate_limit: Option<RPCRateLimit>,
+    /// ETH addresses allowed to sign backend forks scheduled by the `Admin`
+    /// RPC `gw_schedule_backend_fork`. Read-only nodes verify forks received
+    /// from the full node with their own list.
+    #[serde(default)]
+    pub backend_fork_admins: Vec<H160>,
 }
 
 #[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -129,6 +145,8 @@ pub struct ContractsCellDep {
 pub enum RegistryType {
     #[default]
     Eth,
+    /// The address is the 32 bytes account script hash, not supported yet
+    ScriptHash,
 }
 
 #[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -275,6 +293,17 @@ pub struct P2PNetworkConfig {
 pub struct SyncServerConfig {
     pub buffer_capacity: u64,
     pub broadcast_channel_capacity: usize,
+    /// Max number of finalized blocks served for one block range request.
+    #[serde(default = "default_max_block_range")]
+    pub max_block_range: u32,
+    /// Read-only nodes with a p2p listen address re-serve the blocks and mem
+    /// blocks they applied to downstream read-only nodes.
+    #[serde(default)]
+    pub relay: bool,
+    /// Block sync messages relayed more than `max_hops` times are rejected,
+    /// which breaks relay loops.
+    #[serde(default = "default_max_hops")]
+    pub max_hops: u32,
 }
 
 impl Default for SyncServerConfig {
@@ -282,10 +311,44 @@ impl Default for SyncServerConfig {
         Self {
             buffer_capacity: 16,
             broadcast_channel_capacity: 1024,
+            max_block_range: default_max_block_range(),
+            relay: false,
+            max_hops: default_max_hops(),
+        }
+    }
+}
+
+fn default_max_block_range() -> u32 {
+    64
+}
+
+fn default_max_hops() -> u32 {
+    8
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CustodianAuditConfig {
+    #[serde(default = "default_custodian_audit_interval_secs")]
+    pub interval_secs: u64,
+    /// Write the report of the last audit to this file as json.
+    #[serde(default)]
+    pub report_path: Option<PathBuf>,
+}
+
+impl Default for CustodianAuditConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: default_custodian_audit_interval_secs(),
+            report_path: None,
         }
     }
 }
 
+fn default_custodian_audit_interval_secs() -> u64 {
+    600
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct MemPoolConfig {
@@ -296,6 +359,34 @@ pub struct MemPoolConfig {
     pub mem_block: MemBlockConfig,
     pub fee: FeeConfig,
     pub extra: MemPoolExtraConfig,
+    #[serde(default)]
+    pub signature_verifier: SignatureVerifierConfig,
+}
+
+/// Signatures of submitted transactions are verified in batches by a worker
+/// pool before the transactions enter the mem pool queue.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SignatureVerifierConfig {
+    /// Number of verification workers, defaults to the number of CPUs.
+    pub workers: usize,
+    /// Max number of transactions verified in one batch.
+    pub max_batch_size: usize,
+    /// Max number of transactions waiting for verification. Submissions are
+    /// rejected as busy once the queue is full.
+    pub queue_size: usize,
+}
+
+impl Default for SignatureVerifierConfig {
+    fn default() -> Self {
+        Self {
+            workers: std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
+            max_batch_size: 256,
+            queue_size: 10000,
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -384,6 +475,7 @@ impl Default for MemPoolConfig {
             mem_block: MemBlockConfig::default(),
             fee: Default::default(),
             extra: Default::default(),
+            signature_verifier: Default::default(),
         }
     }
 }
@@ -407,6 +499,10 @@ pub enum NodeMode {
     FullNode,
     Test,
     ReadOnly,
+    /// Follows the rollup from L1 and challenges bad blocks, without
+    /// producing blocks. Uses the wallet and challenger config of
+    /// `block_producer`.
+    Watchtower,
 }
 
 impl Default for NodeMode {
@@ -415,6 +511,17 @@ impl Default for NodeMode {
     }
 }
 
+#[test]
+fn test_watchtower_node_mode() {
+    #[derive(Deserialize)]
+    struct ModeConfig {
+        node_mode: NodeMode,
+    }
+
+    let config: ModeConfig = toml::from_str(r#"node_mode = "watchtower""#).unwrap();
+    assert_eq!(config.node_mode, NodeMode::Watchtower);
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct DBBlockValidatorConfig {
@@ -444,12 +551,29 @@ pub struct StoreConfig {
     pub cache_size: Option<usize>,
     #[serde(default)]
     pub options_file: Option<PathBuf>,
+    #[serde(default)]
+    pub backend: StoreBackend,
 }
 
 fn default_store_path() -> PathBuf {
     "./gw-db".into()
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StoreBackend {
+    RocksDb,
+    /// Keep everything in memory, `path`, `cache_size` and `options_file` are
+    /// ignored. Data is lost on exit.
+    Memory,
+}
+
+impl Default for StoreBackend {
+    fn default() -> Self {
+        StoreBackend::RocksDb
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct FeeConfig {
     // fee_rate: fee / cycles limit
@@ -523,6 +647,22 @@ pub struct SyscallCyclesConfig {
     pub sys_bn_mul_cycles: u64,
     pub sys_bn_fixed_pairing_cycles: u64,
     pub sys_bn_per_pairing_cycles: u64,
+    #[serde(default = "default_sys_bls12_381_g1_add_cycles")]
+    pub sys_bls12_381_g1_add_cycles: u64,
+    #[serde(default = "default_sys_bls12_381_g1_msm_per_pair_cycles")]
+    pub sys_bls12_381_g1_msm_per_pair_cycles: u64,
+    #[serde(default = "default_sys_bls12_381_g2_add_cycles")]
+    pub sys_bls12_381_g2_add_cycles: u64,
+    #[serde(default = "default_sys_bls12_381_g2_msm_per_pair_cycles")]
+    pub sys_bls12_381_g2_msm_per_pair_cycles: u64,
+    #[serde(default = "default_sys_bls12_381_fixed_pairing_cycles")]
+    pub sys_bls12_381_fixed_pairing_cycles: u64,
+    #[serde(default = "default_sys_bls12_381_per_pairing_cycles")]
+    pub sys_bls12_381_per_pairing_cycles: u64,
+    #[serde(default = "default_sys_bls12_381_map_fp_to_g1_cycles")]
+    pub sys_bls12_381_map_fp_to_g1_cycles: u64,
+    #[serde(default = "default_sys_bls12_381_map_fp2_to_g2_cycles")]
+    pub sys_bls12_381_map_fp2_to_g2_cycles: u64,
     pub sys_snapshot_cycles: u64,
     pub sys_revert_cycles: u64,
 }
@@ -547,8 +687,53 @@ impl SyscallCyclesConfig {
             sys_bn_mul_cycles: 18_000,
             sys_bn_fixed_pairing_cycles: 135_000,
             sys_bn_per_pairing_cycles: 102_000,
+
+            // default cycles of BLS12-381 operations
+            // estimated_cycles = 3 * (Gas Cost of EIP-2537), MSM is charged
+            // per pair without the discount
+            // see: https://eips.ethereum.org/EIPS/eip-2537
+            sys_bls12_381_g1_add_cycles: default_sys_bls12_381_g1_add_cycles(),
+            sys_bls12_381_g1_msm_per_pair_cycles: default_sys_bls12_381_g1_msm_per_pair_cycles(),
+            sys_bls12_381_g2_add_cycles: default_sys_bls12_381_g2_add_cycles(),
+            sys_bls12_381_g2_msm_per_pair_cycles: default_sys_bls12_381_g2_msm_per_pair_cycles(),
+            sys_bls12_381_fixed_pairing_cycles: default_sys_bls12_381_fixed_pairing_cycles(),
+            sys_bls12_381_per_pairing_cycles: default_sys_bls12_381_per_pairing_cycles(),
+            sys_bls12_381_map_fp_to_g1_cycles: default_sys_bls12_381_map_fp_to_g1_cycles(),
+            sys_bls12_381_map_fp2_to_g2_cycles: default_sys_bls12_381_map_fp2_to_g2_cycles(),
             sys_snapshot_cycles: 2000,
             sys_revert_cycles: 2000,
         }
     }
 }
+
+const fn default_sys_bls12_381_g1_add_cycles() -> u64 {
+    1_125
+}
+
+const fn default_sys_bls12_381_g1_msm_per_pair_cycles() -> u64 {
+    36_000
+}
+
+const fn default_sys_bls12_381_g2_add_cycles() -> u64 {
+    1_800
+}
+
+const fn default_sys_bls12_381_g2_msm_per_pair_cycles() -> u64 {
+    67_500
+}
+
+const fn default_sys_bls12_381_fixed_pairing_cycles() -> u64 {
+    113_100
+}
+
+const fn default_sys_bls12_381_per_pairing_cycles() -> u64 {
+    97_800
+}
+
+const fn default_sys_bls12_381_map_fp_to_g1_cycles() -> u64 {
+    16_500
+}
+
+const fn default_sys_bls12_381_map_fp2_to_g2_cycles() -> u64 {
+    71_400
+}
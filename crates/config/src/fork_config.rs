@@ -116,6 +116,29 @@ pub struct ForkConfig {
     ///   - Remove `state_checkpoints` from RawL2Block
     pub upgrade_global_state_version_to_v2: Option<u64>,
 
+    /// Enable BLS12-381 syscalls (EIP-2537 precompiles) from this block.
+    pub enable_bls12_381_syscalls: Option<u64>,
+
+    /// Accept transactions signed by session keys from this block.
+    /// NOTE: must be at or after the backend fork which switches to a Meta
+    /// contract supporting session keys, and the challenge lock must be
+    /// upgraded first. See docs/session_keys.md.
+    pub enable_session_keys: Option<u64>,
+
+    /// Prioritize sponsored transactions in the mem-pool from this block.
+    /// NOTE: must be at or after the backend fork which switches to a Meta
+    /// contract and a Polyjuice backend built with `gwos/c/sponsor_utils.h`,
+    /// the sponsor is charged by the backends. The node refuses to start
+    /// without such a fork in `backend_forks`. See
+    /// docs/life_of_a_polyjuice_transaction.md.
+    pub enable_fee_sponsorship: Option<u64>,
+
+    /// Serve fast withdrawal RPCs from this block.
+    /// NOTE: the withdrawal lock must be upgraded on L1 to a version
+    /// supporting `UnlockWithdrawalViaTransfer` first. See
+    /// docs/fast_withdrawal.md.
+    pub enable_fast_withdrawal: Option<u64>,
+
     /// Backend fork configs
     pub backend_forks: Vec<BackendForkConfig>,
 
@@ -153,6 +176,55 @@ impl ForkConfig {
         self.global_state_version(block_number) <= 1
     }
 
+    /// Returns if BLS12-381 syscalls are enabled for `block_number`.
+    pub fn bls12_381_syscalls_enabled(&self, block_number: u64) -> bool {
+        match self.enable_bls12_381_syscalls {
+            None => false,
+            Some(fork_number) => block_number >= fork_number,
+        }
+    }
+
+    /// Returns if transactions signed by session keys are accepted at `block_number`.
+    pub fn session_keys_enabled(&self, block_number: u64) -> bool {
+        match self.enable_session_keys {
+            None => false,
+            Some(fork_number) => block_number >= fork_number,
+        }
+    }
+
+    /// Returns if sponsors pay fees of sponsored transactions at `block_number`.
+    pub fn fee_sponsorship_enabled(&self, block_number: u64) -> bool {
+        match self.enable_fee_sponsorship {
+            None => false,
+            Some(fork_number) => block_number >= fork_number,
+        }
+    }
+
+    /// Returns the backend fork which switches the Meta and Polyjuice backends
+    /// before `enable_fee_sponsorship`, the sponsor is charged by these backends.
+    pub fn fee_sponsorship_backend_fork(&self) -> Option<&BackendForkConfig> {
+        let fork_number = self.enable_fee_sponsorship?;
+        self.backend_forks.iter().find(|fork| {
+            let has_backend = |backend_type: BackendType| {
+                fork.backends
+                    .iter()
+                    .any(|backend| backend.backend_type == backend_type)
+            };
+            fork.fork_height > 0
+                && fork.fork_height <= fork_number
+                && has_backend(BackendType::Meta)
+                && has_backend(BackendType::Polyjuice)
+        })
+    }
+
+    /// Returns if withdrawal cells can be transferred to liquidity providers at `block_number`.
+    pub fn fast_withdrawal_enabled(&self, block_number: u64) -> bool {
+        match self.enable_fast_withdrawal {
+            None => false,
+            Some(fork_number) => block_number >= fork_number,
+        }
+    }
+
     /// Return l2 tx cycles limit by block height
     pub fn max_l2_tx_cycles(&self, block_number: u64) -> u64 {
         match self.increase_max_l2_tx_cycles_to_500m {
@@ -183,7 +255,7 @@ impl ForkConfig {
 mod tests {
     use crate::{
         constants::{L2TX_MAX_CYCLES_150M, L2TX_MAX_CYCLES_500M},
-        ForkConfig,
+        BackendConfig, BackendForkConfig, BackendType, ForkConfig, Resource,
     };
 
     #[test]
@@ -206,4 +278,95 @@ mod tests {
         assert_eq!(fork.max_l2_tx_cycles(100), L2TX_MAX_CYCLES_500M);
         assert_eq!(fork.max_l2_tx_cycles(u64::MAX), L2TX_MAX_CYCLES_500M);
     }
+
+    #[test]
+    fn test_bls12_381_syscalls_fork() {
+        assert!(!ForkConfig::default().bls12_381_syscalls_enabled(u64::MAX));
+
+        let fork = ForkConfig {
+            enable_bls12_381_syscalls: Some(42),
+            ..Default::default()
+        };
+        assert!(!fork.bls12_381_syscalls_enabled(41));
+        assert!(fork.bls12_381_syscalls_enabled(42));
+        assert!(fork.bls12_381_syscalls_enabled(u64::MAX));
+    }
+
+    #[test]
+    fn test_session_keys_fork() {
+        assert!(!ForkConfig::default().session_keys_enabled(u64::MAX));
+
+        let fork = ForkConfig {
+            enable_session_keys: Some(42),
+            ..Default::default()
+        };
+        assert!(!fork.session_keys_enabled(41));
+        assert!(fork.session_keys_enabled(42));
+    }
+
+    #[test]
+    fn test_fee_sponsorship_fork() {
+        assert!(!ForkConfig::default().fee_sponsorship_enabled(u64::MAX));
+
+        let fork = ForkConfig {
+            enable_fee_sponsorship: Some(42),
+            ..Default::default()
+        };
+        assert!(!fork.fee_sponsorship_enabled(41));
+        assert!(fork.fee_sponsorship_enabled(42));
+    }
+
+    #[test]
+    fn test_fee_sponsorship_backend_fork() {
+        let backend = |backend_type| BackendConfig {
+            generator_checksum: Default::default(),
+            validator_script_type_hash: Default::default(),
+            backend_type,
+            generator: Resource::file_system("generator".into()),
+        };
+        let backend_fork = |fork_height, backends| BackendForkConfig {
+            fork_height,
+            backends,
+            sudt_proxy: None,
+        };
+        let mut fork = ForkConfig {
+            enable_fee_sponsorship: Some(42),
+            backend_forks: vec![backend_fork(
+                0,
+                vec![backend(BackendType::Meta), backend(BackendType::Polyjuice)],
+            )],
+            ..Default::default()
+        };
+        // Genesis backends don't support sponsorship
+        assert!(fork.fee_sponsorship_backend_fork().is_none());
+
+        fork.backend_forks
+            .push(backend_fork(10, vec![backend(BackendType::Meta)]));
+        assert!(fork.fee_sponsorship_backend_fork().is_none());
+
+        fork.backend_forks.push(backend_fork(
+            50,
+            vec![backend(BackendType::Meta), backend(BackendType::Polyjuice)],
+        ));
+        // After the sponsorship fork
+        assert!(fork.fee_sponsorship_backend_fork().is_none());
+
+        fork.enable_fee_sponsorship = Some(50);
+        assert_eq!(fork.fee_sponsorship_backend_fork().unwrap().fork_height, 50);
+
+        fork.enable_fee_sponsorship = None;
+        assert!(fork.fee_sponsorship_backend_fork().is_none());
+    }
+
+    #[test]
+    fn test_fast_withdrawal_fork() {
+        assert!(!ForkConfig::default().fast_withdrawal_enabled(u64::MAX));
+
+        let fork = ForkConfig {
+            enable_fast_withdrawal: Some(42),
+            ..Default::default()
+        };
+        assert!(!fork.fast_withdrawal_enabled(41));
+        assert!(fork.fast_withdrawal_enabled(42));
+    }
 }
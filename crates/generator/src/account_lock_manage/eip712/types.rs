@@ -1,5 +1,5 @@
 use anyhow::{anyhow, bail, Result};
-use gw_common::builtins::ETH_REGISTRY_ACCOUNT_ID;
+use gw_common::builtins::{ETH_REGISTRY_ACCOUNT_ID, SCRIPT_HASH_REGISTRY_ACCOUNT_ID};
 use gw_types::{
     core::ScriptHashType,
     h256::*,
@@ -73,16 +73,28 @@ impl EIP712Encode for WithdrawalAsset {
 #[derive(Debug)]
 pub enum AddressRegistry {
     ETH,
+    ScriptHash,
 }
 
 impl AddressRegistry {
     fn to_string(&self) -> &str {
-        "ETH"
+        match self {
+            Self::ETH => "ETH",
+            Self::ScriptHash => "SCRIPT_HASH",
+        }
+    }
+
+    fn address_len(&self) -> usize {
+        match self {
+            Self::ETH => 20,
+            Self::ScriptHash => 32,
+        }
     }
 
     pub fn from_registry_id(registry_id: u32) -> Result<Self> {
         match registry_id {
             ETH_REGISTRY_ACCOUNT_ID => Ok(Self::ETH),
+            SCRIPT_HASH_REGISTRY_ACCOUNT_ID => Ok(Self::ScriptHash),
             _ => {
                 bail!("Unsupported registry id : {}", registry_id)
             }
@@ -93,21 +105,23 @@ impl AddressRegistry {
 #[derive(Debug)]
 pub struct RegistryAddress {
     registry: AddressRegistry,
-    address: [u8; 20],
+    address: Vec<u8>,
 }
 
 impl RegistryAddress {
     fn from_address(address: gw_common::registry_address::RegistryAddress) -> Result<Self> {
         let registry = AddressRegistry::from_registry_id(address.registry_id)?;
-        if address.address.len() != 20 {
+        if address.address.len() != registry.address_len() {
             bail!(
-                "Invalid ETH address len, expected 20, got {}",
+                "Invalid {} address len, expected {}, got {}",
+                registry.to_string(),
+                registry.address_len(),
                 address.address.len()
             );
         }
         Ok(RegistryAddress {
             registry,
-            address: address.address.try_into().expect("eth address"),
+            address: address.address,
         })
     }
 }
@@ -118,7 +132,12 @@ impl EIP712Encode for RegistryAddress {
     }
 
     fn encode_type(&self, buf: &mut Vec<u8>) {
-        buf.extend(b"RegistryAddress(string registry,address address)");
+        match self.registry {
+            AddressRegistry::ETH => buf.extend(b"RegistryAddress(string registry,address address)"),
+            AddressRegistry::ScriptHash => {
+                buf.extend(b"RegistryAddress(string registry,bytes32 address)")
+            }
+        }
     }
 
     fn encode_data(&self, buf: &mut Vec<u8>) {
@@ -129,7 +148,16 @@ impl EIP712Encode for RegistryAddress {
             hasher.finalize().into()
         };
         buf.extend(ethabi::encode(&[Token::Uint(registry.into())]));
-        buf.extend(ethabi::encode(&[Token::Address(self.address.into())]));
+        match self.registry {
+            AddressRegistry::ETH => {
+                let address = ethabi::Address::from_slice(&self.address);
+                buf.extend(ethabi::encode(&[Token::Address(address)]));
+            }
+            AddressRegistry::ScriptHash => {
+                let address: [u8; 32] = self.address.as_slice().try_into().expect("script hash");
+                buf.extend(ethabi::encode(&[Token::Uint(address.into())]));
+            }
+        }
     }
 }
 
@@ -476,10 +504,7 @@ mod tests {
         let withdrawal = Withdrawal {
             address: RegistryAddress {
                 registry: AddressRegistry::ETH,
-                address: hex::decode("dddddddddddddddddddddddddddddddddddddddd")
-                    .unwrap()
-                    .try_into()
-                    .expect("address"),
+                address: hex::decode("dddddddddddddddddddddddddddddddddddddddd").unwrap(),
             },
             nonce: 1,
             chain_id: 1,
@@ -531,10 +556,7 @@ mod tests {
             chain_id: 1,
             from: RegistryAddress {
                 registry: AddressRegistry::ETH,
-                address: hex::decode("e8ae579256c3b84efb76bbb69cb6bcbef1375f00")
-                    .unwrap()
-                    .try_into()
-                    .expect("address"),
+                address: hex::decode("e8ae579256c3b84efb76bbb69cb6bcbef1375f00").unwrap(),
             },
             to: hex::decode("ae39eea37dfa6b41004c50efddeb6747f72bb25ea174b2a68bd4eafc641e7c3e")
                 .unwrap()
@@ -11,7 +11,9 @@ use gw_utils::RollupContext;
 #[cfg(any(debug_assertions, feature = "enable-always-success-lock"))]
 pub mod always_success;
 pub mod eip712;
+pub mod multisig;
 pub mod secp256k1;
+pub mod webauthn;
 
 use crate::error::LockAlgorithmError;
 
@@ -34,6 +36,19 @@ pub trait LockAlgorithm {
         withdrawal: &WithdrawalRequestExtra,
         withdrawal_address: RegistryAddress,
     ) -> Result<(), LockAlgorithmError>;
+
+    /// The message signed by the transaction signature, used to verify
+    /// transactions signed by session keys. Returns `None` if the lock doesn't
+    /// support session keys.
+    fn tx_signing_message(
+        &self,
+        _ctx: &RollupContext,
+        _sender_address: RegistryAddress,
+        _receiver_script: &Script,
+        _tx: &L2Transaction,
+    ) -> Result<Option<H256>, LockAlgorithmError> {
+        Ok(None)
+    }
 }
 
 #[derive(Default, Clone)]
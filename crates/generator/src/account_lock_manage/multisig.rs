@@ -0,0 +1,240 @@
+use super::secp256k1::Secp256k1Eth;
+use super::LockAlgorithm;
+use crate::account_lock_manage::eip712::traits::EIP712Encode;
+use crate::account_lock_manage::eip712::types::Withdrawal;
+use crate::error::LockAlgorithmError;
+use gw_common::registry::eth_registry::{
+    calc_multisig_address, extract_eth_address_from_multisig_eoa, MULTISIG_MAX_PUBKEY_HASHES,
+};
+use gw_common::registry_address::RegistryAddress;
+use gw_types::packed::WithdrawalRequestExtra;
+use gw_types::prelude::*;
+use gw_types::{
+    bytes::Bytes,
+    h256::*,
+    packed::{L2Transaction, Script},
+};
+use gw_utils::RollupContext;
+
+pub const SIGNATURE_SIZE: usize = 65;
+
+/// M-of-N config of a multisig EOA.
+///
+/// Lock args: rollup_script_hash | eth_address | threshold | pubkey_hashes_count | pubkey_hashes,
+/// the eth_address is `calc_multisig_address(threshold | pubkey_hashes_count | pubkey_hashes)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultisigConfig {
+    pub threshold: u8,
+    pub pubkey_hashes: Vec<[u8; 20]>,
+}
+
+impl MultisigConfig {
+    pub fn new(threshold: u8, pubkey_hashes: Vec<[u8; 20]>) -> Result<Self, LockAlgorithmError> {
+        if threshold == 0
+            || threshold as usize > pubkey_hashes.len()
+            || pubkey_hashes.len() > MULTISIG_MAX_PUBKEY_HASHES
+        {
+            return Err(LockAlgorithmError::InvalidLockArgs);
+        }
+        Ok(MultisigConfig {
+            threshold,
+            pubkey_hashes,
+        })
+    }
+
+    pub fn from_lock_args(lock_args: &[u8]) -> Result<Self, LockAlgorithmError> {
+        extract_eth_address_from_multisig_eoa(lock_args)
+            .map_err(|_| LockAlgorithmError::InvalidLockArgs)?;
+        let pubkey_hashes = lock_args[54..]
+            .chunks_exact(20)
+            .map(|h| h.try_into().expect("pubkey hash"))
+            .collect();
+        Ok(MultisigConfig {
+            threshold: lock_args[52],
+            pubkey_hashes,
+        })
+    }
+
+    /// threshold | pubkey_hashes_count | pubkey_hashes
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut buf = vec![self.threshold, self.pubkey_hashes.len() as u8];
+        for pubkey_hash in &self.pubkey_hashes {
+            buf.extend_from_slice(pubkey_hash);
+        }
+        buf
+    }
+
+    pub fn address(&self) -> [u8; 20] {
+        calc_multisig_address(&self.serialize())
+    }
+
+    pub fn to_lock_args(&self, rollup_script_hash: &H256) -> Bytes {
+        let mut args = rollup_script_hash.as_slice().to_vec();
+        args.extend_from_slice(&self.address());
+        args.extend(self.serialize());
+        args.into()
+    }
+
+    /// Pick `threshold` signatures of `message` from distinct members and
+    /// concatenate them. Signatures from non members or duplicated signers
+    /// are ignored.
+    pub fn combine_signatures(
+        &self,
+        message: H256,
+        signatures: &[[u8; SIGNATURE_SIZE]],
+    ) -> Result<Bytes, LockAlgorithmError> {
+        let mut signed = vec![false; self.pubkey_hashes.len()];
+        let mut combined = Vec::with_capacity(self.threshold as usize * SIGNATURE_SIZE);
+        for signature in signatures {
+            let pubkey_hash = match Secp256k1Eth::default().recover(message, signature) {
+                Ok(pubkey_hash) => pubkey_hash,
+                Err(_) => continue,
+            };
+            if let Some(index) = self.position(&pubkey_hash) {
+                if !signed[index] {
+                    signed[index] = true;
+                    combined.extend_from_slice(signature);
+                }
+            }
+            if combined.len() == self.threshold as usize * SIGNATURE_SIZE {
+                return Ok(combined.into());
+            }
+        }
+        Err(LockAlgorithmError::InvalidSignature(format!(
+            "Secp256k1EthMultisig: {} of {} signatures required, got {}",
+            self.threshold,
+            self.pubkey_hashes.len(),
+            combined.len() / SIGNATURE_SIZE
+        )))
+    }
+
+    fn position(&self, pubkey_hash: &[u8]) -> Option<usize> {
+        self.pubkey_hashes
+            .iter()
+            .position(|h| h.as_slice() == pubkey_hash)
+    }
+}
+
+/// M-of-N secp256k1 multisig lock for EOAs, e.g. treasury and operator accounts.
+///
+/// The signature is `threshold` recoverable signatures of the EIP-712 message,
+/// each from a different member.
+#[derive(Debug, Default)]
+pub struct Secp256k1EthMultisig;
+
+impl Secp256k1EthMultisig {
+    fn verify_alone(
+        &self,
+        lock_args: Bytes,
+        signature: Bytes,
+        message: H256,
+    ) -> Result<(), LockAlgorithmError> {
+        let config = MultisigConfig::from_lock_args(&lock_args)?;
+        if signature.len() != config.threshold as usize * SIGNATURE_SIZE {
+            return Err(LockAlgorithmError::InvalidSignature(format!(
+                "Secp256k1EthMultisig: signature length is {}, expect {}",
+                signature.len(),
+                config.threshold as usize * SIGNATURE_SIZE
+            )));
+        }
+
+        let pubkey_hashes = self.recover(message, &signature)?;
+        let mut signed = vec![false; config.pubkey_hashes.len()];
+        for pubkey_hash in pubkey_hashes.chunks_exact(20) {
+            let index = config.position(pubkey_hash).ok_or_else(|| {
+                LockAlgorithmError::InvalidSignature(
+                    "Secp256k1EthMultisig: signer is not a member".to_string(),
+                )
+            })?;
+            if signed[index] {
+                return Err(LockAlgorithmError::InvalidSignature(
+                    "Secp256k1EthMultisig: duplicated signer".to_string(),
+                ));
+            }
+            signed[index] = true;
+        }
+        Ok(())
+    }
+}
+
+impl LockAlgorithm for Secp256k1EthMultisig {
+    /// Return the concatenated pubkey hashes of all signers.
+    fn recover(&self, message: H256, signature: &[u8]) -> Result<Bytes, LockAlgorithmError> {
+        if signature.is_empty() || signature.len() % SIGNATURE_SIZE != 0 {
+            return Err(LockAlgorithmError::InvalidSignature(format!(
+                "Secp256k1EthMultisig: invalid signature length {}",
+                signature.len()
+            )));
+        }
+        let eth = Secp256k1Eth::default();
+        let mut pubkey_hashes = Vec::with_capacity(signature.len() / SIGNATURE_SIZE * 20);
+        for signature in signature.chunks_exact(SIGNATURE_SIZE) {
+            pubkey_hashes.extend_from_slice(&eth.recover(message, signature)?);
+        }
+        Ok(pubkey_hashes.into())
+    }
+
+    fn verify_tx(
+        &self,
+        ctx: &RollupContext,
+        sender_address: RegistryAddress,
+        sender_script: Script,
+        receiver_script: Script,
+        tx: L2Transaction,
+    ) -> Result<(), LockAlgorithmError> {
+        // Members always sign the EIP-712 message, so chain id protection is required.
+        let expected_chain_id = ctx.rollup_config.chain_id().unpack();
+        let raw_tx = tx.raw();
+        let chain_id = raw_tx.chain_id().unpack();
+        if !raw_tx.is_chain_id_protected() || expected_chain_id != chain_id {
+            return Err(LockAlgorithmError::InvalidTransactionArgs);
+        }
+
+        let message = Secp256k1Eth::eip712_signing_message(
+            chain_id,
+            &raw_tx,
+            sender_address,
+            receiver_script.hash(),
+        )
+        .map_err(|err| {
+            LockAlgorithmError::InvalidSignature(format!("Invalid l2 transaction format {}", err))
+        })?;
+        self.verify_alone(
+            sender_script.args().unpack(),
+            tx.signature().unpack(),
+            message,
+        )
+    }
+
+    fn verify_withdrawal(
+        &self,
+        ctx: &RollupContext,
+        sender_script: Script,
+        withdrawal: &WithdrawalRequestExtra,
+        address: RegistryAddress,
+    ) -> Result<(), LockAlgorithmError> {
+        let expected_chain_id = ctx.rollup_config.chain_id().unpack();
+        let chain_id = withdrawal.raw().chain_id().unpack();
+        if expected_chain_id != chain_id {
+            return Err(LockAlgorithmError::InvalidSignature(format!(
+                "Invalid chain id {} expected {}",
+                chain_id, expected_chain_id
+            )));
+        }
+        let typed_message = Withdrawal::from_raw(
+            withdrawal.raw(),
+            withdrawal.owner_lock(),
+            address,
+        )
+        .map_err(|err| {
+            LockAlgorithmError::InvalidSignature(format!("Invalid withdrawal format {}", err))
+        })?;
+        let message = typed_message
+            .eip712_message(Secp256k1Eth::domain_with_chain_id(chain_id).hash_struct());
+        self.verify_alone(
+            sender_script.args().unpack(),
+            withdrawal.request().signature().unpack(),
+            message,
+        )
+    }
+}
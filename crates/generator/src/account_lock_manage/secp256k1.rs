@@ -84,6 +84,55 @@ impl Secp256k1Eth {
         }
     }
 
+    /// Signing message of the transaction, either a polyjuice transaction
+    /// message or an EIP-712 message.
+    fn signing_message(
+        &self,
+        ctx: &RollupContext,
+        sender_address: RegistryAddress,
+        receiver_script: &Script,
+        tx: &L2Transaction,
+    ) -> Result<H256, LockAlgorithmError> {
+        let expected_chain_id = ctx.rollup_config.chain_id().unpack();
+        let chain_id = tx.raw().chain_id().unpack();
+        // Non EIP-155 transaction's chain_id is zero.
+        // We support non EIP-155 for the compatibility.
+        // Related issue: https://github.com/nervosnetwork/godwoken/issues/775
+        let is_protected = tx.raw().is_chain_id_protected();
+        // check protected chain id
+        if is_protected && expected_chain_id != chain_id {
+            return Err(LockAlgorithmError::InvalidTransactionArgs);
+        }
+        if let Some(rlp_data) = try_assemble_polyjuice_args(&tx.raw(), receiver_script) {
+            let mut hasher = Keccak256::new();
+            hasher.update(&rlp_data);
+            let signing_message: [u8; 32] = hasher.finalize().into();
+            return Ok(signing_message);
+        }
+
+        // Try verify transaction with EIP-712 message
+        // Reject transaction without chain_id protection
+        if !is_protected {
+            return Err(LockAlgorithmError::InvalidTransactionArgs);
+        }
+
+        let raw_tx = tx.raw();
+        let chain_id = raw_tx.chain_id().unpack();
+
+        let to_script_hash = receiver_script.hash();
+
+        let typed_tx = crate::account_lock_manage::eip712::types::L2Transaction::from_raw(
+            &raw_tx,
+            sender_address,
+            to_script_hash,
+        )
+        .map_err(|err| {
+            LockAlgorithmError::InvalidSignature(format!("Invalid l2 transaction format {}", err))
+        })?;
+        let message = typed_tx.eip712_message(Self::domain_with_chain_id(chain_id).hash_struct());
+        Ok(message)
+    }
+
     fn verify_alone(
         &self,
         lock_args: Bytes,
@@ -150,48 +199,7 @@ impl LockAlgorithm for Secp256k1Eth {
         receiver_script: Script,
         tx: L2Transaction,
     ) -> Result<(), LockAlgorithmError> {
-        let expected_chain_id = ctx.rollup_config.chain_id().unpack();
-        let chain_id = tx.raw().chain_id().unpack();
-        // Non EIP-155 transaction's chain_id is zero.
-        // We support non EIP-155 for the compatibility.
-        // Related issue: https://github.com/nervosnetwork/godwoken/issues/775
-        let is_protected = tx.raw().is_chain_id_protected();
-        // check protected chain id
-        if is_protected && expected_chain_id != chain_id {
-            return Err(LockAlgorithmError::InvalidTransactionArgs);
-        }
-        if let Some(rlp_data) = try_assemble_polyjuice_args(&tx.raw(), &receiver_script) {
-            let mut hasher = Keccak256::new();
-            hasher.update(&rlp_data);
-            let signing_message: [u8; 32] = hasher.finalize().into();
-            self.verify_alone(
-                sender_script.args().unpack(),
-                tx.signature().unpack(),
-                signing_message,
-            )?;
-            return Ok(());
-        }
-
-        // Try verify transaction with EIP-712 message
-        // Reject transaction without chain_id protection
-        if !is_protected {
-            return Err(LockAlgorithmError::InvalidTransactionArgs);
-        }
-
-        let raw_tx = tx.raw();
-        let chain_id = raw_tx.chain_id().unpack();
-
-        let to_script_hash = receiver_script.hash();
-
-        let typed_tx = crate::account_lock_manage::eip712::types::L2Transaction::from_raw(
-            &raw_tx,
-            sender_address,
-            to_script_hash,
-        )
-        .map_err(|err| {
-            LockAlgorithmError::InvalidSignature(format!("Invalid l2 transaction format {}", err))
-        })?;
-        let message = typed_tx.eip712_message(Self::domain_with_chain_id(chain_id).hash_struct());
+        let message = self.signing_message(ctx, sender_address, &receiver_script, &tx)?;
         self.verify_alone(
             sender_script.args().unpack(),
             tx.signature().unpack(),
@@ -232,6 +240,17 @@ impl LockAlgorithm for Secp256k1Eth {
         )?;
         Ok(())
     }
+
+    fn tx_signing_message(
+        &self,
+        ctx: &RollupContext,
+        sender_address: RegistryAddress,
+        receiver_script: &Script,
+        tx: &L2Transaction,
+    ) -> Result<Option<H256>, LockAlgorithmError> {
+        self.signing_message(ctx, sender_address, receiver_script, tx)
+            .map(Some)
+    }
 }
 
 fn try_assemble_polyjuice_args(
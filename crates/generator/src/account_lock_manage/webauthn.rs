@@ -0,0 +1,212 @@
+use super::secp256k1::Secp256k1Eth;
+use super::LockAlgorithm;
+use crate::account_lock_manage::eip712::traits::EIP712Encode;
+use crate::account_lock_manage::eip712::types::Withdrawal;
+use crate::error::LockAlgorithmError;
+use gw_common::{registry_address::RegistryAddress, webauthn};
+use gw_types::packed::{WebAuthnSignature, WithdrawalRequestExtra};
+use gw_types::prelude::*;
+use gw_types::{
+    bytes::Bytes,
+    h256::*,
+    packed::{L2Transaction, Script},
+};
+use gw_utils::RollupContext;
+use p256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+use p256::EncodedPoint;
+use sha2::Sha256;
+use sha3::{Digest, Keccak256};
+
+const PUBKEY_SIZE: usize = 64;
+const SIGNATURE_SIZE: usize = 64;
+// rp id hash (32 bytes) | flags (1 byte) | sign count (4 bytes)
+const MIN_AUTHENTICATOR_DATA_SIZE: usize = 37;
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+// Must be the same as the webauthn-account-lock contract.
+const MAX_AUTHENTICATOR_DATA_SIZE: usize = 1024 - 32;
+
+fn invalid_signature(msg: &str) -> LockAlgorithmError {
+    LockAlgorithmError::InvalidSignature(format!("Secp256r1WebAuthn: {}", msg))
+}
+
+/// Passkey (secp256r1 WebAuthn assertion) lock for EOAs.
+///
+/// The lock args share the layout of ETH EOAs: rollup_script_hash | pubkey_hash,
+/// where pubkey_hash is keccak256(rp_id_hash | x | y)[12..], so an account only
+/// accepts assertions of its own relying party. The signature is a serialized
+/// `WebAuthnSignature` whose client data challenge is the base64url encoded
+/// EIP-712 signing message, and whose origin belongs to the relying party.
+#[derive(Debug, Default)]
+pub struct Secp256r1WebAuthn;
+
+impl Secp256r1WebAuthn {
+    fn verify_alone(
+        &self,
+        lock_args: Bytes,
+        signature: Bytes,
+        message: H256,
+    ) -> Result<(), LockAlgorithmError> {
+        if lock_args.len() != 52 {
+            return Err(LockAlgorithmError::InvalidLockArgs);
+        }
+
+        let pubkey_hash = self.recover(message, signature.as_ref())?;
+        if pubkey_hash.as_ref() != &lock_args[32..52] {
+            return Err(invalid_signature("Mismatch pubkey hash"));
+        }
+        Ok(())
+    }
+}
+
+impl LockAlgorithm for Secp256r1WebAuthn {
+    /// Verify the assertion and return the pubkey hash.
+    fn recover(&self, message: H256, signature: &[u8]) -> Result<Bytes, LockAlgorithmError> {
+        let assertion = WebAuthnSignature::from_slice(signature)
+            .map_err(|err| invalid_signature(&format!("Invalid WebAuthnSignature {}", err)))?;
+        let pubkey = assertion.pubkey().raw_data();
+        let signature = assertion.signature().raw_data();
+        let authenticator_data = assertion.authenticator_data().raw_data();
+        let client_data_json = assertion.client_data_json().raw_data();
+
+        if pubkey.len() != PUBKEY_SIZE {
+            return Err(invalid_signature(&format!(
+                "Pubkey length is {}, expect {}",
+                pubkey.len(),
+                PUBKEY_SIZE
+            )));
+        }
+        if signature.len() != SIGNATURE_SIZE {
+            return Err(invalid_signature(&format!(
+                "Signature length is {}, expect {}",
+                signature.len(),
+                SIGNATURE_SIZE
+            )));
+        }
+
+        // check authenticator flags
+        if authenticator_data.len() < MIN_AUTHENTICATOR_DATA_SIZE
+            || authenticator_data.len() > MAX_AUTHENTICATOR_DATA_SIZE
+        {
+            return Err(invalid_signature(&format!(
+                "Invalid authenticator data length {}",
+                authenticator_data.len()
+            )));
+        }
+        let flags = authenticator_data[32];
+        if flags & FLAG_USER_PRESENT == 0 || flags & FLAG_USER_VERIFIED == 0 {
+            return Err(invalid_signature(&format!(
+                "User not present or not verified, flags {:#x}",
+                flags
+            )));
+        }
+
+        // check client data
+        let client_data = webauthn::parse_client_data(&client_data_json)
+            .ok_or_else(|| invalid_signature("Invalid client data json"))?;
+        if client_data.type_ != webauthn::CLIENT_DATA_TYPE_GET {
+            return Err(invalid_signature("Invalid client data type"));
+        }
+        if client_data.challenge
+            != base64::encode_config(message.as_slice(), base64::URL_SAFE_NO_PAD)
+        {
+            return Err(invalid_signature("Mismatch challenge"));
+        }
+        let rp_id_hash = &authenticator_data[..32];
+        if !webauthn::verify_origin(client_data.origin, rp_id_hash, |data| {
+            Sha256::digest(data).into()
+        }) {
+            return Err(invalid_signature(&format!(
+                "Origin {} mismatches rp id hash",
+                client_data.origin
+            )));
+        }
+
+        // verify signature of authenticator_data | sha256(client_data_json)
+        if !webauthn::is_low_s(&signature) {
+            return Err(invalid_signature("High s signature"));
+        }
+        let verifying_key = VerifyingKey::from_encoded_point(&EncodedPoint::from_untagged_bytes(
+            pubkey.as_ref().into(),
+        ))
+        .map_err(|err| invalid_signature(&format!("Invalid pubkey {}", err)))?;
+        let signature = Signature::try_from(signature.as_ref())
+            .map_err(|err| invalid_signature(&format!("Invalid signature {}", err)))?;
+        let mut signed_data = authenticator_data.to_vec();
+        signed_data.extend_from_slice(&Sha256::digest(&client_data_json));
+        verifying_key
+            .verify(&signed_data, &signature)
+            .map_err(|err| invalid_signature(&err.to_string()))?;
+
+        // The account is bound to the relying party.
+        let mut hasher = Keccak256::new();
+        hasher.update(rp_id_hash);
+        hasher.update(&pubkey);
+        let buf = hasher.finalize();
+        Ok(Bytes::copy_from_slice(&buf[12..]))
+    }
+
+    fn verify_tx(
+        &self,
+        ctx: &RollupContext,
+        sender_address: RegistryAddress,
+        sender_script: Script,
+        receiver_script: Script,
+        tx: L2Transaction,
+    ) -> Result<(), LockAlgorithmError> {
+        // Passkeys always sign the EIP-712 message, so chain id protection is required.
+        let expected_chain_id = ctx.rollup_config.chain_id().unpack();
+        let raw_tx = tx.raw();
+        let chain_id = raw_tx.chain_id().unpack();
+        if !raw_tx.is_chain_id_protected() || expected_chain_id != chain_id {
+            return Err(LockAlgorithmError::InvalidTransactionArgs);
+        }
+
+        let message = Secp256k1Eth::eip712_signing_message(
+            chain_id,
+            &raw_tx,
+            sender_address,
+            receiver_script.hash(),
+        )
+        .map_err(|err| {
+            LockAlgorithmError::InvalidSignature(format!("Invalid l2 transaction format {}", err))
+        })?;
+        self.verify_alone(
+            sender_script.args().unpack(),
+            tx.signature().unpack(),
+            message,
+        )
+    }
+
+    fn verify_withdrawal(
+        &self,
+        ctx: &RollupContext,
+        sender_script: Script,
+        withdrawal: &WithdrawalRequestExtra,
+        address: RegistryAddress,
+    ) -> Result<(), LockAlgorithmError> {
+        let expected_chain_id = ctx.rollup_config.chain_id().unpack();
+        let chain_id = withdrawal.raw().chain_id().unpack();
+        if expected_chain_id != chain_id {
+            return Err(LockAlgorithmError::InvalidSignature(format!(
+                "Invalid chain id {} expected {}",
+                chain_id, expected_chain_id
+            )));
+        }
+        let typed_message = Withdrawal::from_raw(
+            withdrawal.raw(),
+            withdrawal.owner_lock(),
+            address,
+        )
+        .map_err(|err| {
+            LockAlgorithmError::InvalidSignature(format!("Invalid withdrawal format {}", err))
+        })?;
+        let message = typed_message
+            .eip712_message(Secp256k1Eth::domain_with_chain_id(chain_id).hash_struct());
+        self.verify_alone(
+            sender_script.args().unpack(),
+            withdrawal.request().signature().unpack(),
+            message,
+        )
+    }
+}
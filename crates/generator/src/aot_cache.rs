@@ -0,0 +1,202 @@
+//! Persistent cache of AOT code, so restarting a node doesn't compile every
+//! backend generator again.
+//!
+//! A cache file is keyed by the generator checksum and `CACHE_KEY_VERSION`.
+//! The file ends with a blake2b hash of its content, and is discarded on any
+//! mismatch.
+
+use std::{
+    collections::HashMap,
+    convert::TryInto,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, ensure, Context, Result};
+use gw_common::blake2b::new_blake2b;
+use gw_types::h256::*;
+use memmap2::MmapMut;
+
+use crate::types::vm::AotCode;
+
+/// Both the ckb-vm version and the cycles cost model are compiled into AOT
+/// code, bump this version when any of them changes.
+const CACHE_KEY_VERSION: &str = "ckb-vm-0.22.0-v1";
+const MAGIC: &[u8; 8] = b"GWAOT\0\0\x01";
+const HASH_SIZE: usize = 32;
+
+pub struct AotCache {
+    dir: PathBuf,
+}
+
+impl AotCache {
+    pub fn open(dir: PathBuf) -> Result<Self> {
+        fs::create_dir_all(&dir).with_context(|| format!("create {}", dir.display()))?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, generator_checksum: &H256) -> PathBuf {
+        self.dir.join(format!(
+            "{}-{}.aot",
+            hex::encode(generator_checksum),
+            CACHE_KEY_VERSION
+        ))
+    }
+
+    /// Load AOT code of the generator, returns `None` if it isn't cached or
+    /// the cache file is invalid.
+    pub fn load(&self, generator_checksum: &H256) -> Option<AotCode> {
+        let path = self.path(generator_checksum);
+        let content = match fs::read(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(err) => {
+                log::warn!("[aot cache] read {}: {}", path.display(), err);
+                return None;
+            }
+        };
+        match decode(&content, generator_checksum) {
+            Ok(aot_code) => Some(aot_code),
+            Err(err) => {
+                log::warn!("[aot cache] discard {}: {}", path.display(), err);
+                let _ = fs::remove_file(&path);
+                None
+            }
+        }
+    }
+
+    pub fn store(&self, generator_checksum: &H256, aot_code: &AotCode) -> Result<()> {
+        let path = self.path(generator_checksum);
+        let content = encode(aot_code, generator_checksum);
+        // Write to a temporary file first, so a crash never leaves a partial cache file
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, content).with_context(|| format!("write {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, &path).with_context(|| format!("rename to {}", path.display()))?;
+        Ok(())
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+fn content_hash(content: &[u8]) -> [u8; HASH_SIZE] {
+    let mut hasher = new_blake2b();
+    hasher.update(content);
+    let mut hash = [0u8; HASH_SIZE];
+    hasher.finalize(&mut hash);
+    hash
+}
+
+fn encode(aot_code: &AotCode, generator_checksum: &H256) -> Vec<u8> {
+    let mut labels: Vec<_> = aot_code.labels.iter().collect();
+    labels.sort_unstable();
+
+    let mut buf = Vec::with_capacity(aot_code.code.len() + labels.len() * 12 + 128);
+    buf.extend_from_slice(MAGIC);
+    buf.extend_from_slice(generator_checksum);
+    buf.extend_from_slice(&(labels.len() as u32).to_le_bytes());
+    for (addr, offset) in labels {
+        buf.extend_from_slice(&addr.to_le_bytes());
+        buf.extend_from_slice(&offset.to_le_bytes());
+    }
+    buf.extend_from_slice(&(aot_code.code.len() as u64).to_le_bytes());
+    buf.extend_from_slice(&aot_code.code);
+    let hash = content_hash(&buf);
+    buf.extend_from_slice(&hash);
+    buf
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        ensure!(self.0.len() >= n, "unexpected end of file");
+        let (data, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(data)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into()?))
+    }
+}
+
+fn decode(content: &[u8], generator_checksum: &H256) -> Result<AotCode> {
+    ensure!(content.len() >= HASH_SIZE, "file too short");
+    let (content, hash) = content.split_at(content.len() - HASH_SIZE);
+    if content_hash(content) != hash {
+        bail!("content hash mismatch");
+    }
+
+    let mut reader = Reader(content);
+    if reader.take(MAGIC.len())? != MAGIC {
+        bail!("unknown format");
+    }
+    if reader.take(32)? != generator_checksum {
+        bail!("generator checksum mismatch");
+    }
+    let labels_len = reader.u32()? as usize;
+    let mut labels = HashMap::with_capacity(labels_len);
+    for _ in 0..labels_len {
+        let addr = reader.u64()?;
+        let offset = reader.u32()?;
+        labels.insert(addr, offset);
+    }
+    let code_len: usize = reader.u64()?.try_into()?;
+    let code = reader.take(code_len)?;
+    ensure!(reader.0.is_empty(), "trailing data");
+
+    let mut buffer = MmapMut::map_anon(code.len())?;
+    buffer.copy_from_slice(code);
+    let code = buffer.make_exec()?;
+    Ok(AotCode { code, labels })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aot_code() -> AotCode {
+        let mut buffer = MmapMut::map_anon(64).unwrap();
+        buffer.copy_from_slice(&[0x90u8; 64]);
+        AotCode {
+            code: buffer.make_exec().unwrap(),
+            labels: [(0x10000, 0), (0x10004, 16)].into_iter().collect(),
+        }
+    }
+
+    #[test]
+    fn test_store_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = AotCache::open(dir.path().join("aot")).unwrap();
+        let checksum = [1u8; 32];
+        assert!(cache.load(&checksum).is_none());
+
+        cache.store(&checksum, &aot_code()).unwrap();
+        let loaded = cache.load(&checksum).unwrap();
+        assert_eq!(&loaded.code[..], &[0x90u8; 64][..]);
+        assert_eq!(loaded.labels, aot_code().labels);
+        // keyed by checksum
+        assert!(cache.load(&[2u8; 32]).is_none());
+    }
+
+    #[test]
+    fn test_discard_corrupted() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = AotCache::open(dir.path().to_path_buf()).unwrap();
+        let checksum = [1u8; 32];
+        cache.store(&checksum, &aot_code()).unwrap();
+
+        let path = cache.path(&checksum);
+        let mut content = fs::read(&path).unwrap();
+        content[MAGIC.len() + 40] ^= 1;
+        fs::write(&path, content).unwrap();
+        assert!(cache.load(&checksum).is_none());
+        assert!(!path.exists(), "corrupted file is removed");
+    }
+}
@@ -1,11 +1,19 @@
 use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+#[cfg(has_asm)]
+use std::{
+    sync::{Arc, RwLock},
+    time::Instant,
+};
 
 use anyhow::{bail, Context, Result};
-use gw_config::{content_checksum, BackendConfig, BackendForkConfig, BackendType};
-use gw_types::{bytes::Bytes, h256::*};
+use gw_config::{content_checksum, BackendConfig, BackendForkConfig, BackendType, Resource};
+use gw_traits::CodeStore;
+use gw_types::{bytes::Bytes, core::ScriptHashType, h256::*, prelude::*};
+use sha3::{Digest, Keccak256};
 
 #[cfg(has_asm)]
-use crate::types::vm::AotCode;
+use crate::{aot_cache::AotCache, types::vm::AotCode};
 
 #[derive(Clone)]
 pub struct Backend {
@@ -13,6 +21,9 @@ pub struct Backend {
     pub validator_script_type_hash: H256,
     pub backend_type: BackendType,
     pub generator_checksum: H256,
+    /// The `<generator>.debug` companion, which keeps the symbols stripped
+    /// from the generator. Only the profiler loads it.
+    pub symbols: Option<Resource>,
 }
 
 impl Backend {
@@ -38,10 +49,23 @@ impl Backend {
             validator_script_type_hash,
             backend_type,
             generator_checksum,
+            symbols: None,
         })
     }
 }
 
+fn debug_symbols(generator: &Resource) -> Option<Resource> {
+    let symbols = match generator {
+        Resource::Bundled { bundled } => Resource::bundled(format!("{}.debug", bundled)),
+        Resource::FileSystem { file } => {
+            let mut file = file.clone().into_os_string();
+            file.push(".debug");
+            Resource::file_system(file.into())
+        }
+    };
+    symbols.exists().then(|| symbols)
+}
+
 /// SUDT Proxy config
 #[derive(Clone, Default, Debug, PartialEq, Eq)]
 pub struct SUDTProxyConfig {
@@ -57,13 +81,96 @@ pub struct BlockConsensus {
     pub backends: HashMap<H256, Backend>,
 }
 
+/// Message to sign by an upgrade admin to schedule a backend fork on a running node.
+///
+/// Generators are committed by checksums, the location of binaries and the
+/// `sudt_proxy` are not signed, so a runtime fork must not change `sudt_proxy`.
+pub fn backend_fork_signing_message(rollup_script_hash: &H256, config: &BackendForkConfig) -> H256 {
+    let mut hasher = Keccak256::new();
+    hasher.update(b"godwoken backend fork");
+    hasher.update(rollup_script_hash);
+    hasher.update(config.fork_height.to_le_bytes());
+    for backend in &config.backends {
+        let backend_type: u8 = match backend.backend_type {
+            BackendType::Unknown => 0,
+            BackendType::Meta => 1,
+            BackendType::Sudt => 2,
+            BackendType::Polyjuice => 3,
+            BackendType::EthAddrReg => 4,
+        };
+        hasher.update([backend_type]);
+        hasher.update(backend.validator_script_type_hash.as_bytes());
+        hasher.update(backend.generator_checksum.as_bytes());
+    }
+    hasher.finalize().into()
+}
+
+/// AOT codes shared by clones of `BackendManage`, so codes compiled in
+/// background are visible to all of them.
+#[cfg(has_asm)]
 #[derive(Default)]
+struct AotCodes {
+    codes: RwLock<HashMap<H256, Arc<AotCode>>>,
+    cache: Option<AotCache>,
+}
+
+#[cfg(has_asm)]
+impl AotCodes {
+    fn get(&self, generator_checksum: &H256) -> Option<Arc<AotCode>> {
+        self.codes
+            .read()
+            .expect("aot codes")
+            .get(generator_checksum)
+            .cloned()
+    }
+
+    /// Load AOT code from the cache or compile it.
+    fn load_or_compile(&self, backend: &Backend) -> Result<()> {
+        let checksum = backend.generator_checksum;
+        if self.get(&checksum).is_some() {
+            return Ok(());
+        }
+        let cached = self.cache.as_ref().and_then(|cache| cache.load(&checksum));
+        let aot_code = match cached {
+            Some(aot_code) => aot_code,
+            None => {
+                let aot_code = aot_compile(&backend.generator)
+                    .with_context(|| format!("Ahead-of-time compile {}", hex::encode(checksum)))?;
+                if let Some(cache) = self.cache.as_ref() {
+                    if let Err(err) = cache.store(&checksum, &aot_code) {
+                        log::warn!("[aot cache] store {}: {:#}", hex::encode(checksum), err);
+                    }
+                }
+                aot_code
+            }
+        };
+        self.codes
+            .write()
+            .expect("aot codes")
+            .insert(checksum, Arc::new(aot_code));
+        Ok(())
+    }
+}
+
+#[cfg(has_asm)]
+fn aot_compile(code_bytes: &Bytes) -> Result<AotCode, ckb_vm::Error> {
+    let vm_version = crate::types::vm::VMVersion::V1;
+    let mut aot_machine = ckb_vm_aot::AotCompilingMachine::load(
+        code_bytes,
+        Some(Box::new(crate::vm_cost_model::instruction_cycles)),
+        vm_version.vm_isa(),
+        vm_version.vm_version(),
+    )?;
+    aot_machine.compile()
+}
+
+#[derive(Clone, Default)]
 pub struct BackendManage {
     backend_forks: Vec<(u64, BlockConsensus)>,
     /// define here not in backends,
     /// so we don't need to implement the trait `Clone` of AotCode
     #[cfg(has_asm)]
-    aot_codes: HashMap<H256, AotCode>,
+    aot_codes: Arc<AotCodes>,
 }
 
 impl BackendManage {
@@ -76,6 +183,66 @@ impl BackendManage {
         Ok(backend_manage)
     }
 
+    /// Like `from_config`, but AOT codes are loaded from the cache or compiled
+    /// in background, generators run in the interpreter until their codes are
+    /// ready.
+    pub fn from_config_with_warm_up(
+        configs: Vec<BackendForkConfig>,
+        #[allow(unused_variables)] aot_cache_dir: Option<PathBuf>,
+    ) -> Result<Self> {
+        let mut backend_manage: BackendManage = Default::default();
+        #[cfg(has_asm)]
+        if let Some(dir) = aot_cache_dir {
+            backend_manage.aot_codes = Arc::new(AotCodes {
+                cache: Some(AotCache::open(dir)?),
+                ..Default::default()
+            });
+        }
+        for config in configs {
+            backend_manage.register_backend_fork(config, false)?;
+        }
+        #[cfg(has_asm)]
+        backend_manage.warm_up_in_background()?;
+
+        Ok(backend_manage)
+    }
+
+    #[cfg(has_asm)]
+    fn warm_up_in_background(&self) -> Result<()> {
+        // The latest backends are used first
+        let mut backends: Vec<Backend> = Vec::new();
+        for (_height, consensus) in self.backend_forks.iter().rev() {
+            for backend in consensus.backends.values() {
+                if !backends
+                    .iter()
+                    .any(|b| b.generator_checksum == backend.generator_checksum)
+                {
+                    backends.push(backend.clone());
+                }
+            }
+        }
+
+        let aot_codes = Arc::clone(&self.aot_codes);
+        std::thread::Builder::new()
+            .name("aot warm up".to_string())
+            .spawn(move || {
+                let t = Instant::now();
+                for backend in backends {
+                    let checksum = hex::encode(backend.generator_checksum);
+                    match aot_codes.load_or_compile(&backend) {
+                        Ok(()) => {
+                            log::info!("[aot] {:?}({}) is ready", backend.backend_type, checksum)
+                        }
+                        Err(err) => {
+                            log::error!("[aot] {:?}({}): {:#}", backend.backend_type, checksum, err)
+                        }
+                    }
+                }
+                log::info!("[aot] warm up done: {}ms", t.elapsed().as_millis());
+            })?;
+        Ok(())
+    }
+
     pub fn register_backend_fork(
         &mut self,
         config: BackendForkConfig,
@@ -126,20 +293,22 @@ impl BackendManage {
                 validator_script_type_hash,
                 backend_type,
             } = config;
+            let symbols = debug_symbols(&generator);
             let generator = generator
                 .get()
                 .with_context(|| format!("load generator from {}", generator))?
                 .into_owned()
                 .into();
-            let backend = Backend::build(
+            let mut backend = Backend::build(
                 backend_type,
                 validator_script_type_hash.into(),
                 generator,
                 generator_checksum.into(),
             )?;
+            backend.symbols = symbols;
             #[cfg(has_asm)]
             if compile {
-                self.compile_backend(&backend);
+                self.compile_backend(&backend)?;
             }
 
             log::debug!(
@@ -160,12 +329,83 @@ impl BackendManage {
     }
 
     #[cfg(has_asm)]
-    fn compile_backend(&mut self, backend: &Backend) {
-        self.aot_codes.insert(
-            backend.generator_checksum,
-            self.aot_compile(&backend.generator)
-                .expect("Ahead-of-time compile"),
+    fn compile_backend(&mut self, backend: &Backend) -> Result<()> {
+        self.aot_codes.load_or_compile(backend)
+    }
+
+    /// Whether a fork at the same height with the same generators is registered.
+    pub fn contains_backend_fork(&self, config: &BackendForkConfig) -> bool {
+        self.backend_forks
+            .iter()
+            .find(|(height, _consensus)| *height == config.fork_height)
+            .map(|(_height, consensus)| {
+                config.backends.iter().all(|backend| {
+                    consensus
+                        .backends
+                        .get(&backend.validator_script_type_hash.0)
+                        .map(|b| b.generator_checksum == backend.generator_checksum.0)
+                        .unwrap_or(false)
+                })
+            })
+            .unwrap_or(false)
+    }
+
+    /// Backend forks above the height, and the backends changed by each of them.
+    pub fn get_backend_forks_after(&self, block_number: u64) -> Vec<(u64, Vec<&Backend>)> {
+        let mut prev: Option<&BlockConsensus> = None;
+        let mut forks = Vec::new();
+        for (height, consensus) in &self.backend_forks {
+            if *height > block_number {
+                let mut backends: Vec<_> = consensus
+                    .backends
+                    .iter()
+                    .filter(|(code_hash, backend)| {
+                        prev.and_then(|prev| prev.backends.get(*code_hash))
+                            .map(|prev_backend| {
+                                prev_backend.generator_checksum != backend.generator_checksum
+                            })
+                            .unwrap_or(true)
+                    })
+                    .map(|(_code_hash, backend)| backend)
+                    .collect();
+                backends.sort_by_key(|backend| backend.validator_script_type_hash);
+                forks.push((*height, backends));
+            }
+            prev = Some(consensus);
+        }
+        forks
+    }
+
+    pub fn load_backend_and_block_consensus<S: CodeStore>(
+        &self,
+        block_number: u64,
+        state: &S,
+        script_hash: &H256,
+    ) -> Option<(&Backend, &BlockConsensus)> {
+        log::debug!(
+            "load_backend for script_hash: {}",
+            hex::encode(script_hash.as_slice())
         );
+        state.get_script(script_hash).and_then(|script| {
+            // only accept type script hash type for now
+            if script.hash_type() == ScriptHashType::Type.into() {
+                let code_hash: [u8; 32] = script.code_hash().unpack();
+                log::debug!("load_backend by code_hash: {}", hex::encode(code_hash));
+                let block_consensus = self.get_block_consensus_at_height(block_number);
+                block_consensus.and_then(|(_height, consensus)| {
+                    consensus
+                        .backends
+                        .get(&code_hash)
+                        .map(|backend| (backend, consensus))
+                })
+            } else {
+                log::error!(
+                    "Found a invalid account script which hash_type is data: {:?}",
+                    script
+                );
+                None
+            }
+        })
     }
 
     pub fn get_block_consensus_at_height(
@@ -202,21 +442,9 @@ impl BackendManage {
             })
     }
 
-    #[cfg(has_asm)]
-    fn aot_compile(&self, code_bytes: &Bytes) -> Result<AotCode, ckb_vm::Error> {
-        let vm_version = crate::types::vm::VMVersion::V1;
-        let mut aot_machine = ckb_vm_aot::AotCompilingMachine::load(
-            code_bytes,
-            Some(Box::new(crate::vm_cost_model::instruction_cycles)),
-            vm_version.vm_isa(),
-            vm_version.vm_version(),
-        )?;
-        aot_machine.compile()
-    }
-
     /// get aot_code according to special VM version
     #[cfg(has_asm)]
-    pub(crate) fn get_aot_code(&self, code_hash: &H256) -> Option<&AotCode> {
+    pub(crate) fn get_aot_code(&self, code_hash: &H256) -> Option<Arc<AotCode>> {
         log::debug!("get_aot_code hash: {}", hex::encode(code_hash.as_slice()),);
         self.aot_codes.get(code_hash)
     }
@@ -415,4 +643,88 @@ mod tests {
             vec![[42u8; 20]]
         );
     }
+
+    #[test]
+    fn test_get_backend_forks_after() {
+        let mut m = BackendManage::default();
+        let dir = tempfile::tempdir().unwrap().into_path();
+        let backend = |name: &str, code_hash: u8| {
+            let path = dir.join(name);
+            std::fs::write(&path, name).unwrap();
+            BackendConfig {
+                validator_script_type_hash: [code_hash; 32].into(),
+                backend_type: BackendType::Sudt,
+                generator: Resource::file_system(path),
+                generator_checksum: content_checksum(name.as_bytes()).into(),
+            }
+        };
+        let forks = [
+            (0, vec![backend("sudt_v0", 42), backend("meta_v0", 41)]),
+            (10, vec![backend("sudt_v1", 42), backend("meta_v0", 41)]),
+            (20, vec![backend("meta_v1", 41)]),
+        ];
+        for (fork_height, backends) in forks {
+            let config = BackendForkConfig {
+                fork_height,
+                sudt_proxy: None,
+                backends,
+            };
+            m.register_backend_fork(config, false).unwrap();
+        }
+
+        let changed_checksums = |block_number| {
+            m.get_backend_forks_after(block_number)
+                .into_iter()
+                .map(|(height, backends)| {
+                    let checksums: Vec<_> =
+                        backends.into_iter().map(|b| b.generator_checksum).collect();
+                    (height, checksums)
+                })
+                .collect::<Vec<_>>()
+        };
+        // unchanged meta_v0 is not staged at height 10
+        assert_eq!(
+            changed_checksums(5),
+            vec![
+                (10, vec![content_checksum(b"sudt_v1")]),
+                (20, vec![content_checksum(b"meta_v1")]),
+            ]
+        );
+        assert_eq!(
+            changed_checksums(10),
+            vec![(20, vec![content_checksum(b"meta_v1")])]
+        );
+        assert!(changed_checksums(20).is_empty());
+    }
+
+    #[test]
+    fn test_contains_backend_fork() {
+        let mut m = BackendManage::default();
+        let dir = tempfile::tempdir().unwrap().into_path();
+        let backend = |name: &str, code_hash: u8| {
+            let path = dir.join(name);
+            std::fs::write(&path, name).unwrap();
+            BackendConfig {
+                validator_script_type_hash: [code_hash; 32].into(),
+                backend_type: BackendType::Sudt,
+                generator: Resource::file_system(path),
+                generator_checksum: content_checksum(name.as_bytes()).into(),
+            }
+        };
+        let fork = |fork_height, backends| BackendForkConfig {
+            fork_height,
+            sudt_proxy: None,
+            backends,
+        };
+        m.register_backend_fork(fork(0, vec![backend("sudt_v0", 42)]), false)
+            .unwrap();
+        m.register_backend_fork(fork(10, vec![backend("sudt_v1", 42)]), false)
+            .unwrap();
+
+        assert!(m.contains_backend_fork(&fork(10, vec![backend("sudt_v1", 42)])));
+        // different height or generator
+        assert!(!m.contains_backend_fork(&fork(20, vec![backend("sudt_v1", 42)])));
+        assert!(!m.contains_backend_fork(&fork(10, vec![backend("sudt_v2", 42)])));
+        assert!(!m.contains_backend_fork(&fork(10, vec![backend("meta_v1", 41)])));
+    }
 }
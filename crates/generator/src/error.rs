@@ -41,6 +41,8 @@ pub enum LockAlgorithmError {
     UnknownAccountLock,
     #[error("Invalid transaction args")]
     InvalidTransactionArgs,
+    #[error("Session key: {0}")]
+    SessionKey(String),
 }
 
 impl From<LockAlgorithmError> for Error {
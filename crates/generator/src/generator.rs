@@ -1,10 +1,13 @@
-use anyhow::{Context, Result};
-use std::{collections::HashSet, sync::Arc, time::Instant};
+use anyhow::{bail, Context, Result};
+use std::{borrow::Cow, collections::HashSet, sync::Arc, time::Instant};
 
 use crate::{
     account_lock_manage::AccountLockManage,
     backend_manage::{BackendManage, BlockConsensus},
     error::{BlockError, TransactionValidateError, WithdrawalError},
+    profiler::Profile,
+    session_key::{get_session_key_spent, get_session_keys, SessionKeySpend, SessionKeyVerifier},
+    sponsor::{get_sponsorship, Sponsorship},
     syscalls::RunContext,
     typed_transaction::types::TypedRawTransaction,
     types::vm::VMVersion,
@@ -17,7 +20,7 @@ use crate::{
 };
 use crate::{error::AccountError, syscalls::L2Syscalls};
 use crate::{error::LockAlgorithmError, traits::StateExt};
-use arc_swap::ArcSwapOption;
+use arc_swap::{ArcSwap, ArcSwapOption};
 use gw_common::{
     builtins::{CKB_SUDT_ACCOUNT_ID, ETH_REGISTRY_ACCOUNT_ID},
     error::Error as StateError,
@@ -25,7 +28,7 @@ use gw_common::{
     state::{build_account_key, State, SUDT_TOTAL_SUPPLY_KEY},
 };
 
-use gw_config::{ContractLogConfig, ForkConfig, SyscallCyclesConfig};
+use gw_config::{BackendForkConfig, ContractLogConfig, ForkConfig, SyscallCyclesConfig};
 use gw_store::{
     state::{history::history_state::RWConfig, traits::JournalDB, BlockStateDB},
     transaction::StoreTransaction,
@@ -33,7 +36,7 @@ use gw_store::{
 use gw_traits::{ChainView, CodeStore};
 use gw_types::{
     bytes::Bytes,
-    core::{ChallengeTargetType, ScriptHashType},
+    core::{AllowedContractType, ChallengeTargetType},
     h256::H256Ext,
     h256::*,
     offchain::{CycleMeter, RunResult},
@@ -43,8 +46,9 @@ use gw_types::{
         WithdrawalRequestExtra,
     },
     prelude::*,
+    U256,
 };
-use gw_utils::RollupContext;
+use gw_utils::{polyjuice_parser::PolyjuiceParser, RollupContext};
 
 use ckb_vm::{DefaultMachineBuilder, SupportMachine};
 
@@ -143,10 +147,11 @@ pub struct MachineRunArgs<'a, C, S> {
     max_cycles: u64,
     backend: &'a Backend,
     cycles_pool: Option<&'a mut CyclesPool>,
+    profile: Option<&'a mut Profile>,
 }
 
 pub struct Generator {
-    backend_manage: BackendManage,
+    backend_manage: ArcSwap<BackendManage>,
     account_lock_manage: AccountLockManage,
     rollup_context: RollupContext,
     contract_log_config: ContractLogConfig,
@@ -161,7 +166,7 @@ impl Generator {
         contract_log_config: ContractLogConfig,
     ) -> Self {
         Generator {
-            backend_manage,
+            backend_manage: ArcSwap::from_pointee(backend_manage),
             account_lock_manage,
             rollup_context,
             contract_log_config,
@@ -171,7 +176,7 @@ impl Generator {
 
     pub fn clone_with_new_backends(&self, backend_manage: BackendManage) -> Self {
         Self {
-            backend_manage,
+            backend_manage: ArcSwap::from_pointee(backend_manage),
             account_lock_manage: self.account_lock_manage.clone(),
             rollup_context: self.rollup_context.clone(),
             contract_log_config: self.contract_log_config.clone(),
@@ -207,10 +212,14 @@ impl Generator {
             max_cycles,
             backend,
             mut cycles_pool,
+            mut profile,
         } = args;
 
         let mut context = RunContext::default();
         context.debug_log_buf.reserve(1024);
+        if profile.is_some() {
+            context.syscall_profile = Some(Default::default());
+        }
         let used_cycles;
         let exit_code;
         let org_cycles_pool = cycles_pool.as_mut().map(|p| p.clone());
@@ -232,25 +241,53 @@ impl Generator {
                 .instruction_cycle_func(&instruction_cycles);
             let default_machine = machine_builder.build();
 
-            #[cfg(has_asm)]
-            let aot_code_opt = self
-                .backend_manage
-                .get_aot_code(&backend.generator_checksum);
-            #[cfg(has_asm)]
-            if aot_code_opt.is_none() {
-                log::warn!("[machine_run] Not AOT mode!");
-            }
+            let (maybe_ok, execution_cycles) = match profile.as_deref_mut() {
+                // Profiling steps the interpreter, so it never runs in AOT mode
+                Some(profile) => {
+                    // Released generators are stripped, symbolize with the `.debug` companion
+                    let symbols = match backend.symbols {
+                        Some(ref symbols) => symbols.get().map_err(|err| {
+                            ckb_vm::error::Error::Unexpected(format!(
+                                "load symbols from {}: {}",
+                                symbols, err
+                            ))
+                        })?,
+                        None => Cow::Borrowed(backend.generator.as_ref()),
+                    };
+                    profile
+                        .load_symbols(&symbols)
+                        .map_err(|err| ckb_vm::error::Error::Unexpected(err.to_string()))?;
+                    let mut machine = default_machine;
+                    machine.load_program(&backend.generator, &[])?;
+                    let maybe_ok = profile.run(&mut machine);
+                    (maybe_ok, machine.cycles())
+                }
+                None => {
+                    #[cfg(has_asm)]
+                    let aot_code_opt = self
+                        .backend_manage
+                        .load()
+                        .get_aot_code(&backend.generator_checksum);
+                    #[cfg(has_asm)]
+                    if aot_code_opt.is_none() {
+                        log::warn!("[machine_run] Not AOT mode!");
+                    }
 
-            #[cfg(has_asm)]
-            let mut machine = ckb_vm_aot::AotMachine::new(default_machine, aot_code_opt);
+                    #[cfg(has_asm)]
+                    let mut machine =
+                        ckb_vm_aot::AotMachine::new(default_machine, aot_code_opt.as_deref());
 
-            #[cfg(not(has_asm))]
-            let mut machine = TraceMachine::new(default_machine);
+                    #[cfg(not(has_asm))]
+                    let mut machine = TraceMachine::new(default_machine);
 
-            machine.load_program(&backend.generator, &[])?;
-            let maybe_ok = machine.run();
-            let execution_cycles = machine.machine.cycles();
-            drop(machine);
+                    machine.load_program(&backend.generator, &[])?;
+                    let maybe_ok = machine.run();
+                    (maybe_ok, machine.machine.cycles())
+                }
+            };
+            if let Some((profile, syscalls)) = profile.zip(context.syscall_profile.take()) {
+                profile.set_syscalls(syscalls);
+            }
 
             // Subtract tx execution cycles.
             if let Some(cycles_pool) = &mut cycles_pool {
@@ -339,13 +376,16 @@ impl Generator {
         Ok(())
     }
 
-    // Check transaction signature
+    /// Check transaction signature, returns the spend of the session key if
+    /// the transaction is signed by a session key of the sender. The spend
+    /// must be recorded after the transaction is executed.
     #[instrument(skip_all, err(Debug))]
     pub fn check_transaction_signature<S: State + CodeStore>(
         &self,
         state: &S,
         tx: &L2Transaction,
-    ) -> Result<(), TransactionValidateError> {
+        block_number: u64,
+    ) -> Result<Option<SessionKeySpend>, TransactionValidateError> {
         let raw_tx = tx.raw();
         let sender_id: u32 = raw_tx.from_id().unpack();
         let receiver_id: u32 = raw_tx.to_id().unpack();
@@ -381,14 +421,88 @@ impl Generator {
             .get_registry_address_by_script_hash(ETH_REGISTRY_ACCOUNT_ID, &script_hash)?
             .ok_or(AccountError::RegistryAddressNotFound)?;
 
-        lock_algo.verify_tx(
+        let err = match lock_algo.verify_tx(
             &self.rollup_context,
-            sender_address,
+            sender_address.clone(),
             script,
-            receiver_script,
+            receiver_script.clone(),
             tx.to_owned(),
-        )?;
-        Ok(())
+        ) {
+            Ok(()) => return Ok(None),
+            Err(err) => err,
+        };
+        if !self.fork_config().session_keys_enabled(block_number) {
+            return Err(err.into());
+        }
+
+        // Fallback to session keys of the sender
+        let session_keys = get_session_keys(state, sender_id)?;
+        if session_keys.is_empty() {
+            return Err(err.into());
+        }
+        let verifier = SessionKeyVerifier {
+            rollup_context: &self.rollup_context,
+            lock_algo: lock_algo.as_ref(),
+            block_number,
+        };
+        let session_key =
+            verifier.find_signer(&session_keys, sender_address, &receiver_script, tx)?;
+        // Rejected by the verifier if the cost can't be computed
+        let tx_cost = get_tx_type(&self.rollup_context, state, &raw_tx)
+            .ok()
+            .and_then(|tx_type| TypedRawTransaction::from_tx(raw_tx, tx_type))
+            .and_then(|typed_tx| typed_tx.cost());
+        let spent = get_session_key_spent(state, sender_id, session_key)?;
+        verifier.check_limits(session_key, &receiver_script, tx, tx_cost, spent)?;
+        Ok(tx_cost.map(|cost| SessionKeySpend {
+            owner_id: sender_id,
+            pubkey_hash: session_key.pubkey_hash().unpack(),
+            cost,
+        }))
+    }
+
+    /// The sponsorship of the transaction, `None` if it isn't sponsored.
+    pub fn get_tx_sponsorship<S: State + CodeStore>(
+        &self,
+        state: &S,
+        raw_tx: &RawL2Transaction,
+        block_number: u64,
+    ) -> Result<Option<Sponsorship>> {
+        if !self.fork_config().fee_sponsorship_enabled(block_number) {
+            return Ok(None);
+        }
+        let parser = match PolyjuiceParser::from_raw_l2_tx(raw_tx) {
+            Some(parser) => parser,
+            None => return Ok(None),
+        };
+        if get_tx_type(&self.rollup_context, state, raw_tx)? != AllowedContractType::Polyjuice {
+            return Ok(None);
+        }
+        let target = state.get_script_hash(raw_tx.to_id().unpack())?;
+        let sponsorship = get_sponsorship(state, &target)?.filter(|s| s.matches(&parser));
+        Ok(sponsorship)
+    }
+
+    /// The gas price paid by the sponsor of the transaction, `None` if it
+    /// isn't sponsored or the sponsor can't pay its max fee.
+    pub fn get_sponsored_gas_price<S: State + CodeStore>(
+        &self,
+        state: &S,
+        raw_tx: &RawL2Transaction,
+        block_number: u64,
+    ) -> Result<Option<u128>> {
+        let sponsorship = match self.get_tx_sponsorship(state, raw_tx, block_number)? {
+            Some(sponsorship) => sponsorship,
+            None => return Ok(None),
+        };
+        let gas_limit = PolyjuiceParser::from_raw_l2_tx(raw_tx)
+            .map(|p| p.gas())
+            .unwrap_or_default();
+        let max_fee = U256::from(gas_limit).saturating_mul(sponsorship.gas_price().into());
+        if sponsorship.max_fee(state, raw_tx.from_id().unpack())? < max_fee {
+            return Ok(None);
+        }
+        Ok(Some(sponsorship.gas_price()))
     }
 
     /// Apply l2 state transition
@@ -546,18 +660,22 @@ impl Generator {
                 hex::encode(tx.hash())
             );
             let now = Instant::now();
-            if let Err(err) = self.check_transaction_signature(&state, &tx) {
-                let target = build_challenge_target(
-                    block_hash,
-                    ChallengeTargetType::TxSignature,
-                    tx_index as u32,
-                );
+            let session_key_spend =
+                match self.check_transaction_signature(&state, &tx, block_number) {
+                    Ok(spend) => spend,
+                    Err(err) => {
+                        let target = build_challenge_target(
+                            block_hash,
+                            ChallengeTargetType::TxSignature,
+                            tx_index as u32,
+                        );
 
-                return ApplyBlockResult::Challenge {
-                    target,
-                    error: err.into(),
+                        return ApplyBlockResult::Challenge {
+                            target,
+                            error: err.into(),
+                        };
+                    }
                 };
-            }
             check_signature_total_ms += now.elapsed().as_millis();
 
             // check nonce
@@ -621,6 +739,11 @@ impl Generator {
                 }
             };
             execute_tx_total_ms += now.elapsed().as_millis();
+            if let Some(spend) = session_key_spend {
+                if let Err(err) = spend.record(&mut state) {
+                    return ApplyBlockResult::Error(err.into());
+                }
+            }
 
             {
                 let now = Instant::now();
@@ -712,39 +835,33 @@ impl Generator {
         }
     }
 
+    /// Load the backend of the account at the block number
     #[instrument(skip_all, fields(script_hash = %script_hash.pack()))]
-    pub fn load_backend_and_block_consensus<S: State + CodeStore>(
+    pub fn load_backend<S: State + CodeStore>(
         &self,
         block_number: u64,
         state: &S,
         script_hash: &H256,
-    ) -> Option<(&Backend, &BlockConsensus)> {
-        log::debug!(
-            "load_backend for script_hash: {}",
-            hex::encode(script_hash.as_slice())
-        );
-        state.get_script(script_hash).and_then(|script| {
-            // only accept type script hash type for now
-            if script.hash_type() == ScriptHashType::Type.into() {
-                let code_hash: [u8; 32] = script.code_hash().unpack();
-                log::debug!("load_backend by code_hash: {}", hex::encode(code_hash));
-                let block_consensus = self
-                    .backend_manage
-                    .get_block_consensus_at_height(block_number);
-                block_consensus.and_then(|(_height, consensus)| {
-                    consensus
-                        .backends
-                        .get(&code_hash)
-                        .map(|backend| (backend, consensus))
-                })
-            } else {
-                log::error!(
-                    "Found a invalid account script which hash_type is data: {:?}",
-                    script
-                );
-                None
-            }
-        })
+    ) -> Option<Backend> {
+        self.backend_manage
+            .load()
+            .load_backend_and_block_consensus(block_number, state, script_hash)
+            .map(|(backend, _consensus)| backend.clone())
+    }
+
+    /// Schedule a backend fork on the running node, the generator binaries
+    /// are checked against checksums and compiled before taking effect.
+    pub fn register_backend_fork(&self, config: BackendForkConfig) -> Result<()> {
+        let current = self.backend_manage.load_full();
+        let mut backend_manage = BackendManage::clone(&current);
+        backend_manage.register_backend_fork(config, true)?;
+        let prev = self
+            .backend_manage
+            .compare_and_swap(&current, Arc::new(backend_manage));
+        if !Arc::ptr_eq(&*prev, &current) {
+            bail!("backends are changed by another fork registration, please retry");
+        }
+        Ok(())
     }
 
     /// execute a layer2 tx
@@ -772,10 +889,58 @@ impl Generator {
         raw_tx: &RawL2Transaction,
         override_max_cycles: Option<u64>,
         cycles_pool: Option<&mut CyclesPool>,
+    ) -> Result<RunResult> {
+        self.execute_transaction_inner(
+            chain,
+            state,
+            block_info,
+            raw_tx,
+            override_max_cycles,
+            cycles_pool,
+            None,
+        )
+    }
+
+    /// execute a layer2 tx and profile the cycles consumed by the backend,
+    /// the backend always runs in the interpreter, it is much slower than
+    /// `execute_transaction`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn execute_transaction_with_profile<S: State + CodeStore + JournalDB, C: ChainView>(
+        &self,
+        chain: &C,
+        state: &mut S,
+        block_info: &BlockInfo,
+        raw_tx: &RawL2Transaction,
+        override_max_cycles: Option<u64>,
+        cycles_pool: Option<&mut CyclesPool>,
+        profile: &mut Profile,
+    ) -> Result<RunResult> {
+        self.execute_transaction_inner(
+            chain,
+            state,
+            block_info,
+            raw_tx,
+            override_max_cycles,
+            cycles_pool,
+            Some(profile),
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn execute_transaction_inner<S: State + CodeStore + JournalDB, C: ChainView>(
+        &self,
+        chain: &C,
+        state: &mut S,
+        block_info: &BlockInfo,
+        raw_tx: &RawL2Transaction,
+        override_max_cycles: Option<u64>,
+        cycles_pool: Option<&mut CyclesPool>,
+        profile: Option<&mut Profile>,
     ) -> Result<RunResult> {
         let account_id = raw_tx.to_id().unpack();
         let script_hash = state.get_script_hash(account_id)?;
-        let (backend, block_consensus) = self
+        let backend_manage = self.backend_manage.load_full();
+        let (backend, block_consensus) = backend_manage
             .load_backend_and_block_consensus(block_info.number().unpack(), state, &script_hash)
             .ok_or(TransactionError::BackendNotFound { script_hash })?;
         let block_number = block_info.number().unpack();
@@ -812,6 +977,7 @@ impl Generator {
             max_cycles,
             backend,
             cycles_pool,
+            profile,
         };
 
         let run_context = self.machine_run(args).map_err(|err| {
@@ -924,12 +1090,8 @@ impl Generator {
         Ok(r)
     }
 
-    pub fn backend_manage(&self) -> &BackendManage {
-        &self.backend_manage
-    }
-
-    pub fn backend_manage_mut(&mut self) -> &mut BackendManage {
-        &mut self.backend_manage
+    pub fn backend_manage(&self) -> Arc<BackendManage> {
+        self.backend_manage.load_full()
     }
 
     pub fn get_polyjuice_creator_id<S: State + CodeStore>(
@@ -938,7 +1100,7 @@ impl Generator {
     ) -> Result<Option<u32>, TransactionError> {
         if self.polyjuice_creator_id.load_full().is_none() {
             let polyjuice_creator_id =
-                get_polyjuice_creator_id(self.rollup_context(), self.backend_manage(), state)?
+                get_polyjuice_creator_id(self.rollup_context(), &self.backend_manage(), state)?
                     .map(Arc::new);
             self.polyjuice_creator_id.store(polyjuice_creator_id);
         }
@@ -1,5 +1,5 @@
 use crate::traits::StateExt;
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use gw_common::{
     blake2b::new_blake2b,
     builtins::{CKB_SUDT_ACCOUNT_ID, ETH_REGISTRY_ACCOUNT_ID, RESERVED_ACCOUNT_ID},
@@ -22,7 +22,7 @@ use gw_store::{
 use gw_traits::CodeStore;
 use gw_types::{
     bytes::Bytes,
-    core::{ScriptHashType, Status},
+    core::{AllowedContractType, ScriptHashType, Status},
     h256::*,
     packed::{
         AccountMerkleState, BlockMerkleState, DepositInfoVec, FinalizedCustodianCapacity,
@@ -107,6 +107,20 @@ pub fn build_genesis_from_store(
         ETH_REGISTRY_ACCOUNT_ID
     );
 
+    // The builtin contracts only handle registry addresses of up to 20 bytes,
+    // refuse the script hash registry until they support script hash addresses.
+    let script_hash_registry_allowed = rollup_context
+        .rollup_config
+        .allowed_contract_type_hashes()
+        .into_iter()
+        .any(|type_hash| {
+            let type_: u8 = type_hash.type_().into();
+            type_ == AllowedContractType::ScriptHashAddrReg as u8
+        });
+    if script_hash_registry_allowed {
+        bail!("script hash registry isn't supported by the builtin contracts yet");
+    }
+
     // insert secp256k1 data
     let secp_data_hash = {
         let mut hasher = new_blake2b();
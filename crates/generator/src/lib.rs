@@ -2,10 +2,15 @@
 //! and generate new status that can be committed to layer1
 
 pub mod account_lock_manage;
+#[cfg(has_asm)]
+pub mod aot_cache;
 pub mod backend_manage;
 pub mod error;
 pub mod generator;
 pub mod genesis;
+pub mod profiler;
+pub mod session_key;
+pub mod sponsor;
 pub mod sudt;
 pub mod syscalls;
 pub mod traits;
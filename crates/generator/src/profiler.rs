@@ -0,0 +1,261 @@
+//! Cycle profiler for generator execution.
+//!
+//! The profiler drives the interpreter step by step, samples the PC after
+//! each instruction and attributes the consumed cycles to the function
+//! symbol of the backend ELF. Call stacks are reconstructed from the sampled
+//! PCs: entering a function which is already on the stack is treated as a
+//! return to it, otherwise as a call. The result is written in the folded
+//! stack format, which can be rendered with `inferno-flamegraph` or
+//! `flamegraph.pl`.
+//!
+//! Syscalls are reported separately, since their cycles are charged as
+//! virtual cycles and never show up in the sampled PCs.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Write,
+};
+
+use anyhow::{anyhow, Result};
+use ckb_vm::{
+    decoder::build_decoder, CoreMachine, DefaultMachine, Error as VMError, Register, SupportMachine,
+};
+
+use crate::syscalls::syscall_name;
+
+/// Stop tracking deeper frames, e.g. on a deep recursion.
+const MAX_STACK_DEPTH: usize = 256;
+const UNKNOWN_SYMBOL: &str = "??";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SyscallProfile {
+    pub count: u64,
+    pub cycles: u64,
+}
+
+impl SyscallProfile {
+    pub fn record(&mut self, cycles: u64) {
+        self.count += 1;
+        self.cycles = self.cycles.saturating_add(cycles);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Symbol {
+    start: u64,
+    end: u64,
+    name: String,
+}
+
+#[derive(Debug, Default)]
+pub struct Profile {
+    // Sorted by start address
+    symbols: Vec<Symbol>,
+    // Symbol indexes, `None` for PCs without a symbol
+    stack: Vec<Option<usize>>,
+    folded: HashMap<Vec<Option<usize>>, u64>,
+    syscalls: BTreeMap<u64, SyscallProfile>,
+    execution_cycles: u64,
+}
+
+impl Profile {
+    /// Load function symbols from the program or its `.debug` companion, the
+    /// ELF must keep the symbol table.
+    pub fn load_symbols(&mut self, program: &[u8]) -> Result<()> {
+        let elf = goblin::elf::Elf::parse(program).map_err(|err| anyhow!("parse elf: {}", err))?;
+        let mut symbols: Vec<_> = elf
+            .syms
+            .iter()
+            .filter(|sym| sym.is_function() && sym.st_size > 0)
+            .filter_map(|sym| {
+                let name = elf.strtab.get_at(sym.st_name)?;
+                Some(Symbol {
+                    start: sym.st_value,
+                    end: sym.st_value.saturating_add(sym.st_size),
+                    name: name.to_string(),
+                })
+            })
+            .collect();
+        if symbols.is_empty() {
+            log::warn!("[profiler] no function symbols found, is the program stripped without a `.debug` companion?");
+        }
+        symbols.sort_by_key(|sym| sym.start);
+        symbols.dedup_by_key(|sym| sym.start);
+        self.symbols = symbols;
+        Ok(())
+    }
+
+    /// Run the machine until exit, attributing cycles of each step.
+    pub fn run<Inner: SupportMachine>(
+        &mut self,
+        machine: &mut DefaultMachine<'_, Inner>,
+    ) -> Result<i8, VMError> {
+        let mut decoder = build_decoder::<Inner::REG>(machine.isa(), machine.version());
+        machine.set_running(true);
+        while machine.running() {
+            let pc = machine.pc().to_u64();
+            let cycles = machine.cycles();
+            let ret = machine.step(&mut decoder);
+            self.sample(pc, machine.cycles().saturating_sub(cycles));
+            ret?;
+        }
+        Ok(machine.exit_code())
+    }
+
+    pub(crate) fn set_syscalls(&mut self, syscalls: BTreeMap<u64, SyscallProfile>) {
+        self.syscalls = syscalls;
+    }
+
+    pub fn execution_cycles(&self) -> u64 {
+        self.execution_cycles
+    }
+
+    pub fn syscalls(&self) -> &BTreeMap<u64, SyscallProfile> {
+        &self.syscalls
+    }
+
+    fn lookup(&self, pc: u64) -> Option<usize> {
+        let i = self.symbols.partition_point(|sym| sym.start <= pc);
+        let i = i.checked_sub(1)?;
+        (pc < self.symbols[i].end).then(|| i)
+    }
+
+    fn sample(&mut self, pc: u64, cycles: u64) {
+        let frame = self.lookup(pc);
+        match self.stack.iter().rposition(|f| *f == frame) {
+            // Returned to a caller, or still in the same function
+            Some(i) => self.stack.truncate(i + 1),
+            None if self.stack.len() < MAX_STACK_DEPTH => self.stack.push(frame),
+            None => {}
+        }
+        self.execution_cycles = self.execution_cycles.saturating_add(cycles);
+        let total = self.folded.entry(self.stack.clone()).or_default();
+        *total = total.saturating_add(cycles);
+    }
+
+    fn symbol_name(&self, frame: Option<usize>) -> &str {
+        frame.map_or(UNKNOWN_SYMBOL, |i| &self.symbols[i].name)
+    }
+
+    /// Folded stacks, one `caller;callee cycles` per line, sorted.
+    pub fn folded_stacks(&self) -> Vec<String> {
+        let mut stacks: BTreeMap<String, u64> = BTreeMap::new();
+        for (stack, cycles) in &self.folded {
+            if *cycles == 0 {
+                continue;
+            }
+            let names: Vec<_> = stack.iter().map(|f| self.symbol_name(*f)).collect();
+            let total = stacks.entry(names.join(";")).or_default();
+            *total = total.saturating_add(*cycles);
+        }
+        stacks
+            .into_iter()
+            .map(|(stack, cycles)| format!("{} {}", stack, cycles))
+            .collect()
+    }
+
+    pub fn write_folded<W: Write>(&self, mut w: W) -> Result<()> {
+        for line in self.folded_stacks() {
+            writeln!(w, "{}", line)?;
+        }
+        Ok(())
+    }
+
+    /// Per syscall report, sorted by cycles in descending order.
+    pub fn syscall_report(&self) -> Vec<(u64, &'static str, SyscallProfile)> {
+        let mut report: Vec<_> = self
+            .syscalls
+            .iter()
+            .map(|(code, p)| (*code, syscall_name(*code).unwrap_or(UNKNOWN_SYMBOL), *p))
+            .collect();
+        report.sort_by(|a, b| b.2.cycles.cmp(&a.2.cycles).then(a.0.cmp(&b.0)));
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile() -> Profile {
+        let symbols = [
+            ("main", 0x100, 0x200),
+            ("foo", 0x200, 0x280),
+            ("bar", 0x300, 0x380),
+        ]
+        .into_iter()
+        .map(|(name, start, end)| Symbol {
+            start,
+            end,
+            name: name.to_string(),
+        })
+        .collect();
+        Profile {
+            symbols,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_lookup_symbol() {
+        let p = profile();
+        assert_eq!(p.lookup(0x50), None);
+        assert_eq!(p.lookup(0x100), Some(0));
+        assert_eq!(p.lookup(0x1ff), Some(0));
+        assert_eq!(p.lookup(0x200), Some(1));
+        assert_eq!(p.lookup(0x290), None);
+        assert_eq!(p.lookup(0x37c), Some(2));
+        assert_eq!(p.lookup(0x380), None);
+    }
+
+    #[test]
+    fn test_folded_stacks() {
+        let mut p = profile();
+        // main -> foo -> bar -> foo (return) -> main (return) -> ??
+        for (pc, cycles) in [
+            (0x100, 1),
+            (0x104, 1),
+            (0x200, 2),
+            (0x300, 3),
+            (0x304, 3),
+            (0x204, 2),
+            (0x108, 1),
+            (0x50, 5),
+        ] {
+            p.sample(pc, cycles);
+        }
+        assert_eq!(p.execution_cycles(), 18);
+        assert_eq!(
+            p.folded_stacks(),
+            vec![
+                "main 3".to_string(),
+                "main;?? 5".to_string(),
+                "main;foo 4".to_string(),
+                "main;foo;bar 6".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_syscall_report() {
+        let mut p = Profile::default();
+        let mut syscalls = BTreeMap::new();
+        let store: &mut SyscallProfile = syscalls.entry(3101).or_default();
+        store.record(100);
+        store.record(100);
+        syscalls.entry(3603).or_default().record(1000);
+        p.set_syscalls(syscalls);
+
+        let report = p.syscall_report();
+        assert_eq!(report[0].1, "bn_pairing");
+        assert_eq!(report[0].2.cycles, 1000);
+        assert_eq!(report[1].1, "store");
+        assert_eq!(
+            report[1].2,
+            SyscallProfile {
+                count: 2,
+                cycles: 200
+            }
+        );
+    }
+}
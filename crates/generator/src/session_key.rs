@@ -0,0 +1,299 @@
+//! Session keys, temporary keys which sign transactions on behalf of an EOA.
+//!
+//! An EOA registers a session key through the Meta contract with an expiry
+//! block, the contracts it's allowed to call and a spending cap. The Meta
+//! contract keeps up to `MAX_SESSION_KEYS` session keys of an account in
+//! slots of its own storage, a slot holds the data hash of a `SessionKey`.
+//!
+//! The limits are checked by `gw_common::session_key`, which is shared with
+//! the challenge lock, so a challenged transaction signed by a session key
+//! can be proven valid on-chain. Transactions signed by session keys are
+//! accepted after the `enable_session_keys` fork.
+//!
+//! The spending cap limits the total cost of the transactions signed by a
+//! session key. The cost of a transaction is added to the spent of its
+//! session key in the Meta contract storage once it's executed.
+
+use gw_common::{
+    builtins::RESERVED_ACCOUNT_ID,
+    error::Error as StateError,
+    registry_address::RegistryAddress,
+    session_key::{check_session_key_limits, SessionKeyError},
+    state::State,
+};
+use gw_traits::CodeStore;
+use gw_types::{
+    bytes::Bytes,
+    core::ScriptHashType,
+    h256::*,
+    packed::{L2Transaction, Script, SessionKey},
+    prelude::*,
+    U256,
+};
+use gw_utils::RollupContext;
+
+use crate::{account_lock_manage::LockAlgorithm, error::LockAlgorithmError};
+
+pub use gw_common::session_key::{
+    get_session_key_spent, session_key_slot_key, session_key_spent_key, MAX_SESSION_KEYS,
+};
+
+/// The cost of a transaction signed by a session key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionKeySpend {
+    pub owner_id: u32,
+    pub pubkey_hash: [u8; 20],
+    pub cost: U256,
+}
+
+impl SessionKeySpend {
+    /// Add the cost to the spent of the session key, it must be called after
+    /// the transaction is executed.
+    pub fn record<S: State>(&self, state: &mut S) -> Result<(), StateError> {
+        let key = session_key_spent_key(self.owner_id, &self.pubkey_hash);
+        let spent = state.get_value(RESERVED_ACCOUNT_ID, &key)?.to_u256();
+        let value = H256::from_u256(spent.saturating_add(self.cost));
+        state.update_value(RESERVED_ACCOUNT_ID, &key, value)
+    }
+}
+
+/// Session keys registered by the account, including expired ones.
+pub fn get_session_keys<S: State + CodeStore>(
+    state: &S,
+    owner_id: u32,
+) -> Result<Vec<SessionKey>, StateError> {
+    let mut session_keys = Vec::new();
+    for index in 0..MAX_SESSION_KEYS {
+        let slot_key = session_key_slot_key(owner_id, index);
+        let data_hash = state.get_value(RESERVED_ACCOUNT_ID, &slot_key)?;
+        if data_hash.is_zero() {
+            continue;
+        }
+        let data = state.get_data(&data_hash).ok_or(StateError::MissingKey)?;
+        let session_key = SessionKey::from_slice(&data).map_err(|_| StateError::InvalidArgs)?;
+        session_keys.push(session_key);
+    }
+    Ok(session_keys)
+}
+
+/// Lock of the session key in the rollup, it's the sender's ETH account lock
+/// with the pubkey hash of the session key. The challenge lock requires a
+/// signature cell of it to cancel a challenge of a tx signed by the session
+/// key.
+pub fn session_key_lock(sender_script: &Script, session_key: &SessionKey) -> Script {
+    let sender_args: Bytes = sender_script.args().unpack();
+    let mut args = sender_args[..32.min(sender_args.len())].to_vec();
+    args.extend_from_slice(session_key.pubkey_hash().as_slice());
+    Script::new_builder()
+        .code_hash(sender_script.code_hash())
+        .hash_type(ScriptHashType::Type.into())
+        .args(args.pack())
+        .build()
+}
+
+/// Verifies transactions signed by session keys.
+pub struct SessionKeyVerifier<'a> {
+    pub rollup_context: &'a RollupContext,
+    pub lock_algo: &'a dyn LockAlgorithm,
+    pub block_number: u64,
+}
+
+impl<'a> SessionKeyVerifier<'a> {
+    /// Find the session key which signed the transaction.
+    pub fn find_signer<'k>(
+        &self,
+        session_keys: &'k [SessionKey],
+        sender_address: RegistryAddress,
+        receiver_script: &Script,
+        tx: &L2Transaction,
+    ) -> Result<&'k SessionKey, LockAlgorithmError> {
+        let message = self
+            .lock_algo
+            .tx_signing_message(self.rollup_context, sender_address, receiver_script, tx)?
+            .ok_or_else(|| {
+                LockAlgorithmError::SessionKey("the account lock doesn't support it".to_string())
+            })?;
+        let signature: Bytes = tx.signature().unpack();
+        let signer = self.lock_algo.recover(message, &signature)?;
+        session_keys
+            .iter()
+            .find(|k| k.pubkey_hash().as_slice() == signer.as_ref())
+            .ok_or_else(|| {
+                LockAlgorithmError::InvalidSignature(
+                    "no session key matches the signer".to_string(),
+                )
+            })
+    }
+
+    /// Check the transaction is within the limits of the session key which
+    /// signed it. `tx_cost` is `None` if the cost of the transaction can't be
+    /// computed, such transactions are rejected. `spent` is the cost spent by
+    /// the session key before the transaction.
+    pub fn check_limits(
+        &self,
+        session_key: &SessionKey,
+        receiver_script: &Script,
+        tx: &L2Transaction,
+        tx_cost: Option<U256>,
+        spent: U256,
+    ) -> Result<(), LockAlgorithmError> {
+        let to_id: u32 = tx.raw().to_id().unpack();
+        check_session_key_limits(
+            session_key,
+            self.block_number,
+            to_id,
+            &receiver_script.hash(),
+            tx_cost,
+            spent,
+        )
+        .map_err(|err| {
+            let reason = match err {
+                SessionKeyError::Expired { expiry_block } => {
+                    format!("expired at block {}", expiry_block)
+                }
+                SessionKeyError::MetaContractCall => {
+                    "not allowed to call the Meta contract".to_string()
+                }
+                SessionKeyError::TargetNotAllowed => format!(
+                    "not allowed to call 0x{}",
+                    hex::encode(receiver_script.hash())
+                ),
+                SessionKeyError::UnknownCost => "can't compute the cost of the tx".to_string(),
+                SessionKeyError::ExceedsSpendingCap {
+                    spending_cap,
+                    spent,
+                } => format!(
+                    "cost {} exceeds the spending cap {}, spent {}",
+                    tx_cost.unwrap_or_default(),
+                    spending_cap,
+                    spent
+                ),
+            };
+            LockAlgorithmError::SessionKey(reason)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gw_types::{
+        core::ScriptHashType,
+        packed::{Byte20, RawL2Transaction, RollupConfig},
+    };
+    use secp256k1::{PublicKey, SecretKey};
+    use sha3::{Digest, Keccak256};
+
+    use super::*;
+    use crate::account_lock_manage::secp256k1::{Secp256k1Eth, SECP256K1};
+    use gw_common::builtins::ETH_REGISTRY_ACCOUNT_ID;
+
+    const CHAIN_ID: u64 = 42;
+
+    fn rollup_context() -> RollupContext {
+        RollupContext {
+            rollup_config: RollupConfig::new_builder()
+                .chain_id(CHAIN_ID.pack())
+                .build(),
+            ..Default::default()
+        }
+    }
+
+    fn eth_address(privkey: &SecretKey) -> [u8; 20] {
+        let pubkey = PublicKey::from_secret_key(&SECP256K1, privkey);
+        let hash = Keccak256::digest(&pubkey.serialize_uncompressed()[1..]);
+        hash[12..].try_into().unwrap()
+    }
+
+    fn sign(privkey: &SecretKey, message: H256) -> Bytes {
+        let msg = secp256k1::Message::from_slice(&message).unwrap();
+        let (rec_id, data) = SECP256K1
+            .sign_ecdsa_recoverable(&msg, privkey)
+            .serialize_compact();
+        let mut sig = data.to_vec();
+        sig.push(rec_id.to_i32() as u8);
+        sig.into()
+    }
+
+    fn session_key(privkey: &SecretKey, target: H256) -> SessionKey {
+        SessionKey::new_builder()
+            .pubkey_hash(Byte20::from_slice(&eth_address(privkey)).unwrap())
+            .expiry_block(100u64.pack())
+            .allowed_targets(vec![target.pack()].pack())
+            .spending_cap(1000u128.pack())
+            .build()
+    }
+
+    #[test]
+    fn test_verify_tx_with_session_key() {
+        let ctx = rollup_context();
+        let lock_algo = Secp256k1Eth::default();
+        let receiver_script = Script::new_builder()
+            .code_hash([1u8; 32].pack())
+            .hash_type(ScriptHashType::Type.into())
+            .build();
+        let sender_address = RegistryAddress::new(ETH_REGISTRY_ACCOUNT_ID, vec![3u8; 20]);
+        let session_privkey = SecretKey::from_slice(&[7u8; 32]).unwrap();
+        let session_keys = vec![session_key(&session_privkey, receiver_script.hash())];
+
+        let raw_tx = RawL2Transaction::new_builder()
+            .chain_id(CHAIN_ID.pack())
+            .from_id(4u32.pack())
+            .to_id(5u32.pack())
+            .build();
+        let sign_tx = |privkey: &SecretKey, receiver_script: &Script| {
+            let tx = L2Transaction::new_builder().raw(raw_tx.clone()).build();
+            let message = lock_algo
+                .tx_signing_message(&ctx, sender_address.clone(), receiver_script, &tx)
+                .unwrap()
+                .unwrap();
+            tx.as_builder()
+                .signature(sign(privkey, message).pack())
+                .build()
+        };
+        let verify =
+            |block_number, privkey: &SecretKey, receiver_script: &Script, cost: Option<u128>| {
+                let verifier = SessionKeyVerifier {
+                    rollup_context: &ctx,
+                    lock_algo: &lock_algo,
+                    block_number,
+                };
+                let tx = sign_tx(privkey, receiver_script);
+                let session_key = verifier.find_signer(
+                    &session_keys,
+                    sender_address.clone(),
+                    receiver_script,
+                    &tx,
+                )?;
+                verifier.check_limits(
+                    session_key,
+                    receiver_script,
+                    &tx,
+                    cost.map(U256::from),
+                    U256::zero(),
+                )
+            };
+        verify(99, &session_privkey, &receiver_script, Some(1000)).unwrap();
+
+        // expired
+        let err = verify(100, &session_privkey, &receiver_script, Some(1000)).unwrap_err();
+        assert!(matches!(err, LockAlgorithmError::SessionKey(_)));
+        // exceeds the spending cap
+        let err = verify(99, &session_privkey, &receiver_script, Some(1001)).unwrap_err();
+        assert!(matches!(err, LockAlgorithmError::SessionKey(_)));
+        // target isn't allowed
+        let other_script = receiver_script
+            .clone()
+            .as_builder()
+            .args(vec![1u8].pack())
+            .build();
+        let err = verify(99, &session_privkey, &other_script, Some(0)).unwrap_err();
+        assert!(matches!(err, LockAlgorithmError::SessionKey(_)));
+        // unknown cost
+        let err = verify(99, &session_privkey, &receiver_script, None).unwrap_err();
+        assert!(matches!(err, LockAlgorithmError::SessionKey(_)));
+        // unknown signer
+        let other_privkey = SecretKey::from_slice(&[8u8; 32]).unwrap();
+        let err = verify(99, &other_privkey, &receiver_script, Some(0)).unwrap_err();
+        assert!(matches!(err, LockAlgorithmError::InvalidSignature(_)));
+    }
+}
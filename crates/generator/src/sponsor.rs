@@ -0,0 +1,196 @@
+//! Fee sponsorship, a sponsor pays fees of Polyjuice transactions calling
+//! the sponsored contract.
+//!
+//! A sponsor sets a `SponsorPolicy` of a contract through the Meta contract,
+//! the policy limits the sponsored function selectors, the gas price paid by
+//! the sponsor and the max fee paid for each user. A sponsored transaction
+//! sets its gas price to zero, the Polyjuice backend charges the sponsor
+//! `gas_used * policy.gas_price` by `gw_sponsor_pay_fee` of
+//! `gwos/c/sponsor_utils.h`, so it is verified on-chain like any other fee.
+//!
+//! This module reads the sponsorship for the mem-pool only, the generator
+//! never writes it. Transactions are sponsored after the
+//! `enable_fee_sponsorship` fork.
+
+use gw_common::{
+    blake2b::new_blake2b,
+    builtins::{CKB_SUDT_ACCOUNT_ID, ETH_REGISTRY_ACCOUNT_ID, RESERVED_ACCOUNT_ID},
+    error::Error as StateError,
+    registry_address::RegistryAddress,
+    state::State,
+};
+use gw_traits::CodeStore;
+use gw_types::{h256::*, packed::SponsorPolicy, prelude::*, U256};
+use gw_utils::polyjuice_parser::PolyjuiceParser;
+
+/// Keep in sync with the Meta contract.
+const SPONSOR_PREFIX: &[u8] = b"sponsor";
+const SPONSOR_POLICY_PREFIX: &[u8] = b"sponsor_policy";
+const SPONSOR_USED_PREFIX: &[u8] = b"sponsor_used";
+const SELECTOR_SIZE: usize = 4;
+
+fn build_key(prefix: &[u8], target: &H256, user_id: Option<u32>) -> H256 {
+    let mut key = [0u8; 32];
+    let mut hasher = new_blake2b();
+    hasher.update(prefix);
+    hasher.update(target);
+    if let Some(user_id) = user_id {
+        hasher.update(&user_id.to_le_bytes());
+    }
+    hasher.finalize(&mut key);
+    key
+}
+
+/// Key of the sponsor id of a contract in the Meta contract storage.
+pub fn sponsor_key(target: &H256) -> H256 {
+    build_key(SPONSOR_PREFIX, target, None)
+}
+
+/// Key of the policy data hash of a contract in the Meta contract storage.
+pub fn sponsor_policy_key(target: &H256) -> H256 {
+    build_key(SPONSOR_POLICY_PREFIX, target, None)
+}
+
+/// Key of the fee paid by the sponsor for a user in the Meta contract storage.
+pub fn sponsor_used_key(target: &H256, user_id: u32) -> H256 {
+    build_key(SPONSOR_USED_PREFIX, target, Some(user_id))
+}
+
+#[derive(Debug, Clone)]
+pub struct Sponsorship {
+    pub sponsor_id: u32,
+    pub policy: SponsorPolicy,
+}
+
+impl Sponsorship {
+    pub fn target(&self) -> H256 {
+        self.policy.target().unpack()
+    }
+
+    pub fn gas_price(&self) -> u128 {
+        self.policy.gas_price().unpack()
+    }
+
+    /// Returns if the polyjuice transaction matches the policy.
+    pub fn matches(&self, parser: &PolyjuiceParser) -> bool {
+        if parser.gas_price() != 0 || !parser.is_call() || parser.is_native_transfer() {
+            return false;
+        }
+        let selectors = self.policy.selectors();
+        if selectors.is_empty() {
+            return true;
+        }
+        let data = parser.data();
+        data.len() >= SELECTOR_SIZE
+            && selectors
+                .into_iter()
+                .any(|selector| selector.as_slice() == &data[..SELECTOR_SIZE])
+    }
+
+    /// The remaining fee the sponsor pays for the user.
+    pub fn remaining_quota<S: State>(&self, state: &S, user_id: u32) -> Result<U256, StateError> {
+        let used = get_sponsored_fee(state, &self.target(), user_id)?;
+        let quota: u128 = self.policy.per_user_quota().unpack();
+        Ok(U256::from(quota).saturating_sub(used))
+    }
+
+    pub fn sponsor_address<S: State>(
+        &self,
+        state: &S,
+    ) -> Result<Option<RegistryAddress>, StateError> {
+        let script_hash = state.get_script_hash(self.sponsor_id)?;
+        state.get_registry_address_by_script_hash(ETH_REGISTRY_ACCOUNT_ID, &script_hash)
+    }
+
+    /// The max fee the sponsor pays for the user, limited by the remaining
+    /// quota and the balance of the sponsor.
+    pub fn max_fee<S: State>(&self, state: &S, user_id: u32) -> Result<U256, StateError> {
+        let sponsor_address = match self.sponsor_address(state)? {
+            Some(address) => address,
+            None => return Ok(U256::zero()),
+        };
+        let balance = state.get_sudt_balance(CKB_SUDT_ACCOUNT_ID, &sponsor_address)?;
+        let remaining_quota = self.remaining_quota(state, user_id)?;
+        Ok(balance.min(remaining_quota))
+    }
+}
+
+/// The sponsorship of the contract, `None` if it isn't sponsored.
+pub fn get_sponsorship<S: State + CodeStore>(
+    state: &S,
+    target: &H256,
+) -> Result<Option<Sponsorship>, StateError> {
+    let sponsor_id = {
+        let value = state.get_value(RESERVED_ACCOUNT_ID, &sponsor_key(target))?;
+        u32::from_le_bytes(value[..4].try_into().expect("sponsor id"))
+    };
+    if sponsor_id == 0 {
+        return Ok(None);
+    }
+    let data_hash = state.get_value(RESERVED_ACCOUNT_ID, &sponsor_policy_key(target))?;
+    let data = state.get_data(&data_hash).ok_or(StateError::MissingKey)?;
+    let policy = SponsorPolicy::from_slice(&data).map_err(|_| StateError::InvalidArgs)?;
+    Ok(Some(Sponsorship { sponsor_id, policy }))
+}
+
+/// The fee paid by the sponsor of the contract for the user.
+pub fn get_sponsored_fee<S: State>(
+    state: &S,
+    target: &H256,
+    user_id: u32,
+) -> Result<U256, StateError> {
+    let value = state.get_value(RESERVED_ACCOUNT_ID, &sponsor_used_key(target, user_id))?;
+    Ok(value.to_u256())
+}
+
+#[cfg(test)]
+mod tests {
+    use gw_types::{
+        bytes::Bytes,
+        packed::{Byte4, Byte4Vec, RawL2Transaction},
+    };
+
+    use super::*;
+
+    fn polyjuice_args(gas_price: u128, data: &[u8]) -> Bytes {
+        let mut args = b"\xFF\xFF\xFFPOLY\x00".to_vec();
+        args.extend_from_slice(&21000u64.to_le_bytes());
+        args.extend_from_slice(&gas_price.to_le_bytes());
+        args.extend_from_slice(&0u128.to_le_bytes());
+        args.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        args.extend_from_slice(data);
+        args.into()
+    }
+
+    fn parser(gas_price: u128, data: &[u8]) -> PolyjuiceParser {
+        let raw_tx = RawL2Transaction::new_builder()
+            .args(polyjuice_args(gas_price, data).pack())
+            .build();
+        PolyjuiceParser::from_raw_l2_tx(&raw_tx).unwrap()
+    }
+
+    #[test]
+    fn test_sponsorship_matches() {
+        let policy = SponsorPolicy::new_builder()
+            .target([1u8; 32].pack())
+            .gas_price(1000u128.pack())
+            .build();
+        let mut sponsorship = Sponsorship {
+            sponsor_id: 2,
+            policy,
+        };
+        // all calls are sponsored
+        assert!(sponsorship.matches(&parser(0, &[])));
+        assert!(sponsorship.matches(&parser(0, &[9, 9, 9, 9, 1])));
+        // pays by the sender
+        assert!(!sponsorship.matches(&parser(1, &[])));
+
+        let selectors = Byte4Vec::new_builder()
+            .push(Byte4::from_slice(&[1, 2, 3, 4]).unwrap())
+            .build();
+        sponsorship.policy = sponsorship.policy.as_builder().selectors(selectors).build();
+        assert!(sponsorship.matches(&parser(0, &[1, 2, 3, 4, 5])));
+        assert!(!sponsorship.matches(&parser(0, &[9, 9, 9, 9, 5])));
+        assert!(!sponsorship.matches(&parser(0, &[1, 2, 3])));
+    }
+}
@@ -0,0 +1,410 @@
+//! BLS12-381 operations with the EIP-2537 encoding
+//! https://eips.ethereum.org/EIPS/eip-2537
+//!
+//! - A base field element is encoded as 64 bytes, 16 zero bytes followed by
+//!   the 48 bytes big-endian value, which must be less than the modulus.
+//! - A G1 point is encoded as `x | y` (128 bytes), a G2 point is encoded as
+//!   `x.c0 | x.c1 | y.c0 | y.c1` (256 bytes). The point at infinity is
+//!   encoded as zeros.
+//! - A scalar is encoded as 32 bytes big-endian, it is not required to be
+//!   less than the group order.
+
+use blst::{
+    blst_bendian_from_fp, blst_final_exp, blst_fp, blst_fp12, blst_fp12_is_one, blst_fp12_mul,
+    blst_fp2, blst_fp_from_bendian, blst_map_to_g1, blst_map_to_g2, blst_miller_loop, blst_p1,
+    blst_p1_add_or_double, blst_p1_add_or_double_affine, blst_p1_affine, blst_p1_affine_in_g1,
+    blst_p1_affine_is_inf, blst_p1_affine_on_curve, blst_p1_from_affine, blst_p1_mult,
+    blst_p1_to_affine, blst_p2, blst_p2_add_or_double, blst_p2_add_or_double_affine,
+    blst_p2_affine, blst_p2_affine_in_g2, blst_p2_affine_is_inf, blst_p2_affine_on_curve,
+    blst_p2_from_affine, blst_p2_mult, blst_p2_to_affine, blst_scalar, blst_scalar_from_bendian,
+};
+
+use super::error_codes::{
+    GW_ERROR_BLS12_381_INVALID_FIELD_ELEMENT, GW_ERROR_BLS12_381_INVALID_INPUT_LENGTH,
+    GW_ERROR_BLS12_381_POINT_NOT_IN_SUBGROUP, GW_ERROR_BLS12_381_POINT_NOT_ON_CURVE,
+};
+
+const FP_LENGTH: usize = 48;
+const PADDING_LENGTH: usize = 16;
+const PADDED_FP_LENGTH: usize = PADDING_LENGTH + FP_LENGTH;
+const SCALAR_LENGTH: usize = 32;
+const SCALAR_BITS: usize = 256;
+
+pub const G1_LENGTH: usize = 2 * PADDED_FP_LENGTH;
+pub const G2_LENGTH: usize = 4 * PADDED_FP_LENGTH;
+pub const G1_MSM_PAIR_LENGTH: usize = G1_LENGTH + SCALAR_LENGTH;
+pub const G2_MSM_PAIR_LENGTH: usize = G2_LENGTH + SCALAR_LENGTH;
+pub const PAIRING_PAIR_LENGTH: usize = G1_LENGTH + G2_LENGTH;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    InvalidInputLength,
+    InvalidFieldElement,
+    PointNotOnCurve,
+    PointNotInSubgroup,
+}
+
+impl Error {
+    pub fn error_code(&self) -> i8 {
+        match self {
+            Error::InvalidInputLength => GW_ERROR_BLS12_381_INVALID_INPUT_LENGTH,
+            Error::InvalidFieldElement => GW_ERROR_BLS12_381_INVALID_FIELD_ELEMENT,
+            Error::PointNotOnCurve => GW_ERROR_BLS12_381_POINT_NOT_ON_CURVE,
+            Error::PointNotInSubgroup => GW_ERROR_BLS12_381_POINT_NOT_IN_SUBGROUP,
+        }
+    }
+}
+
+fn read_fp(input: &[u8]) -> Result<blst_fp, Error> {
+    let (padding, bytes) = input.split_at(PADDING_LENGTH);
+    if padding.iter().any(|b| *b != 0) {
+        return Err(Error::InvalidFieldElement);
+    }
+    let mut fp = blst_fp::default();
+    let mut canonical = [0u8; FP_LENGTH];
+    unsafe {
+        blst_fp_from_bendian(&mut fp, bytes.as_ptr());
+        blst_bendian_from_fp(canonical.as_mut_ptr(), &fp);
+    }
+    // The value is reduced if it isn't less than the modulus
+    if canonical.as_slice() != bytes {
+        return Err(Error::InvalidFieldElement);
+    }
+    Ok(fp)
+}
+
+fn write_fp(fp: &blst_fp, output: &mut [u8]) {
+    unsafe {
+        blst_bendian_from_fp(output[PADDING_LENGTH..].as_mut_ptr(), fp);
+    }
+}
+
+fn read_fp2(input: &[u8]) -> Result<blst_fp2, Error> {
+    let c0 = read_fp(&input[..PADDED_FP_LENGTH])?;
+    let c1 = read_fp(&input[PADDED_FP_LENGTH..])?;
+    Ok(blst_fp2 { fp: [c0, c1] })
+}
+
+fn write_fp2(fp2: &blst_fp2, output: &mut [u8]) {
+    write_fp(&fp2.fp[0], &mut output[..PADDED_FP_LENGTH]);
+    write_fp(&fp2.fp[1], &mut output[PADDED_FP_LENGTH..]);
+}
+
+fn read_g1(input: &[u8], subgroup_check: bool) -> Result<blst_p1_affine, Error> {
+    let p = blst_p1_affine {
+        x: read_fp(&input[..PADDED_FP_LENGTH])?,
+        y: read_fp(&input[PADDED_FP_LENGTH..G1_LENGTH])?,
+    };
+    // zeros is treated as the point at infinity
+    if unsafe { !blst_p1_affine_on_curve(&p) } {
+        return Err(Error::PointNotOnCurve);
+    }
+    if subgroup_check && unsafe { !blst_p1_affine_in_g1(&p) } {
+        return Err(Error::PointNotInSubgroup);
+    }
+    Ok(p)
+}
+
+fn write_g1(p: &blst_p1) -> [u8; G1_LENGTH] {
+    let mut affine = blst_p1_affine::default();
+    unsafe {
+        blst_p1_to_affine(&mut affine, p);
+    }
+    let mut output = [0u8; G1_LENGTH];
+    write_fp(&affine.x, &mut output[..PADDED_FP_LENGTH]);
+    write_fp(&affine.y, &mut output[PADDED_FP_LENGTH..]);
+    output
+}
+
+fn read_g2(input: &[u8], subgroup_check: bool) -> Result<blst_p2_affine, Error> {
+    let p = blst_p2_affine {
+        x: read_fp2(&input[..2 * PADDED_FP_LENGTH])?,
+        y: read_fp2(&input[2 * PADDED_FP_LENGTH..G2_LENGTH])?,
+    };
+    // zeros is treated as the point at infinity
+    if unsafe { !blst_p2_affine_on_curve(&p) } {
+        return Err(Error::PointNotOnCurve);
+    }
+    if subgroup_check && unsafe { !blst_p2_affine_in_g2(&p) } {
+        return Err(Error::PointNotInSubgroup);
+    }
+    Ok(p)
+}
+
+fn write_g2(p: &blst_p2) -> [u8; G2_LENGTH] {
+    let mut affine = blst_p2_affine::default();
+    unsafe {
+        blst_p2_to_affine(&mut affine, p);
+    }
+    let mut output = [0u8; G2_LENGTH];
+    write_fp2(&affine.x, &mut output[..2 * PADDED_FP_LENGTH]);
+    write_fp2(&affine.y, &mut output[2 * PADDED_FP_LENGTH..]);
+    output
+}
+
+fn read_scalar(input: &[u8]) -> blst_scalar {
+    let mut scalar = blst_scalar::default();
+    unsafe {
+        blst_scalar_from_bendian(&mut scalar, input.as_ptr());
+    }
+    scalar
+}
+
+pub fn g1_add(input: &[u8]) -> Result<[u8; G1_LENGTH], Error> {
+    if input.len() != 2 * G1_LENGTH {
+        return Err(Error::InvalidInputLength);
+    }
+    let a = read_g1(&input[..G1_LENGTH], false)?;
+    let b = read_g1(&input[G1_LENGTH..], false)?;
+
+    let mut a_jacobian = blst_p1::default();
+    let mut sum = blst_p1::default();
+    unsafe {
+        blst_p1_from_affine(&mut a_jacobian, &a);
+        blst_p1_add_or_double_affine(&mut sum, &a_jacobian, &b);
+    }
+    Ok(write_g1(&sum))
+}
+
+pub fn g1_msm(input: &[u8]) -> Result<[u8; G1_LENGTH], Error> {
+    if input.is_empty() || input.len() % G1_MSM_PAIR_LENGTH != 0 {
+        return Err(Error::InvalidInputLength);
+    }
+
+    // the default jacobian point is the point at infinity
+    let mut acc = blst_p1::default();
+    for pair in input.chunks_exact(G1_MSM_PAIR_LENGTH) {
+        let p = read_g1(&pair[..G1_LENGTH], true)?;
+        let scalar = read_scalar(&pair[G1_LENGTH..]);
+
+        let mut p_jacobian = blst_p1::default();
+        let mut product = blst_p1::default();
+        let prev = acc;
+        unsafe {
+            blst_p1_from_affine(&mut p_jacobian, &p);
+            blst_p1_mult(&mut product, &p_jacobian, scalar.b.as_ptr(), SCALAR_BITS);
+            blst_p1_add_or_double(&mut acc, &prev, &product);
+        }
+    }
+    Ok(write_g1(&acc))
+}
+
+pub fn g2_add(input: &[u8]) -> Result<[u8; G2_LENGTH], Error> {
+    if input.len() != 2 * G2_LENGTH {
+        return Err(Error::InvalidInputLength);
+    }
+    let a = read_g2(&input[..G2_LENGTH], false)?;
+    let b = read_g2(&input[G2_LENGTH..], false)?;
+
+    let mut a_jacobian = blst_p2::default();
+    let mut sum = blst_p2::default();
+    unsafe {
+        blst_p2_from_affine(&mut a_jacobian, &a);
+        blst_p2_add_or_double_affine(&mut sum, &a_jacobian, &b);
+    }
+    Ok(write_g2(&sum))
+}
+
+pub fn g2_msm(input: &[u8]) -> Result<[u8; G2_LENGTH], Error> {
+    if input.is_empty() || input.len() % G2_MSM_PAIR_LENGTH != 0 {
+        return Err(Error::InvalidInputLength);
+    }
+
+    // the default jacobian point is the point at infinity
+    let mut acc = blst_p2::default();
+    for pair in input.chunks_exact(G2_MSM_PAIR_LENGTH) {
+        let p = read_g2(&pair[..G2_LENGTH], true)?;
+        let scalar = read_scalar(&pair[G2_LENGTH..]);
+
+        let mut p_jacobian = blst_p2::default();
+        let mut product = blst_p2::default();
+        let prev = acc;
+        unsafe {
+            blst_p2_from_affine(&mut p_jacobian, &p);
+            blst_p2_mult(&mut product, &p_jacobian, scalar.b.as_ptr(), SCALAR_BITS);
+            blst_p2_add_or_double(&mut acc, &prev, &product);
+        }
+    }
+    Ok(write_g2(&acc))
+}
+
+/// Returns 1 in the last byte if the product of the pairings is one,
+/// otherwise returns zeros.
+pub fn pairing(input: &[u8]) -> Result<[u8; 32], Error> {
+    if input.is_empty() || input.len() % PAIRING_PAIR_LENGTH != 0 {
+        return Err(Error::InvalidInputLength);
+    }
+
+    let mut acc: Option<blst_fp12> = None;
+    for pair in input.chunks_exact(PAIRING_PAIR_LENGTH) {
+        let p1 = read_g1(&pair[..G1_LENGTH], true)?;
+        let p2 = read_g2(&pair[G1_LENGTH..], true)?;
+        // e(P, Q) is one if either point is the point at infinity
+        if unsafe { blst_p1_affine_is_inf(&p1) || blst_p2_affine_is_inf(&p2) } {
+            continue;
+        }
+
+        let mut miller_loop = blst_fp12::default();
+        unsafe {
+            blst_miller_loop(&mut miller_loop, &p2, &p1);
+        }
+        acc = Some(match acc {
+            Some(prev) => {
+                let mut product = blst_fp12::default();
+                unsafe {
+                    blst_fp12_mul(&mut product, &prev, &miller_loop);
+                }
+                product
+            }
+            None => miller_loop,
+        });
+    }
+
+    let is_one = match acc {
+        Some(acc) => {
+            let mut result = blst_fp12::default();
+            unsafe {
+                blst_final_exp(&mut result, &acc);
+                blst_fp12_is_one(&result)
+            }
+        }
+        None => true,
+    };
+    let mut output = [0u8; 32];
+    output[31] = is_one.into();
+    Ok(output)
+}
+
+pub fn map_fp_to_g1(input: &[u8]) -> Result<[u8; G1_LENGTH], Error> {
+    if input.len() != PADDED_FP_LENGTH {
+        return Err(Error::InvalidInputLength);
+    }
+    let fp = read_fp(input)?;
+    let mut p = blst_p1::default();
+    unsafe {
+        blst_map_to_g1(&mut p, &fp, std::ptr::null());
+    }
+    Ok(write_g1(&p))
+}
+
+pub fn map_fp2_to_g2(input: &[u8]) -> Result<[u8; G2_LENGTH], Error> {
+    if input.len() != 2 * PADDED_FP_LENGTH {
+        return Err(Error::InvalidInputLength);
+    }
+    let fp2 = read_fp2(input)?;
+    let mut p = blst_p2::default();
+    unsafe {
+        blst_map_to_g2(&mut p, &fp2, std::ptr::null());
+    }
+    Ok(write_g2(&p))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Generators of G1 and G2 in the EIP-2537 encoding
+    const G1_GENERATOR: &str = "0000000000000000000000000000000017f1d3a73197d7942695638c4fa9ac0fc3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb0000000000000000000000000000000008b3f481e3aaa0f1a09e30ed741d8ae4fcf5e095d5d00af600db18cb2c04b3edd03cc744a2888ae40caa232946c5e7e1";
+    const G2_GENERATOR: &str = "00000000000000000000000000000000024aa2b2f08f0a91260805272dc51051c6e47ad4fa403b02b4510b647ae3d1770bac0326a805bbefd48056c8c121bdb80000000000000000000000000000000013e02b6052719f607dacd3a088274f65596bd0d09920b61ab5da61bbdc7f5049334cf11213945d57e5ac7d055d042b7e000000000000000000000000000000000ce5d527727d6e118cc9cdc6da2e351aadfd9baa8cbdd3a76d429a695160d12c923ac9cc3baca289e193548608b82801000000000000000000000000000000000606c4a02ea734cc32acd2b02bc28b99cb3e287e85a763af267492ab572e99ab3f370d275cec1da1aaa9075ff05f79be";
+
+    fn scalar(n: u8) -> [u8; SCALAR_LENGTH] {
+        let mut s = [0u8; SCALAR_LENGTH];
+        s[SCALAR_LENGTH - 1] = n;
+        s
+    }
+
+    #[test]
+    fn test_g1_add_and_msm() {
+        let g1 = hex::decode(G1_GENERATOR).unwrap();
+        let doubled = g1_add(&[g1.as_slice(), g1.as_slice()].concat()).unwrap();
+
+        let msm = g1_msm(&[g1.as_slice(), &scalar(2)].concat()).unwrap();
+        assert_eq!(doubled, msm);
+        // g * 1 + g * 1
+        let msm = g1_msm(&[g1.as_slice(), &scalar(1), g1.as_slice(), &scalar(1)].concat()).unwrap();
+        assert_eq!(doubled, msm);
+
+        // P + infinity
+        let sum = g1_add(&[g1.as_slice(), &[0u8; G1_LENGTH]].concat()).unwrap();
+        assert_eq!(sum.as_slice(), g1.as_slice());
+        // g * 0 is the point at infinity
+        let msm = g1_msm(&[g1.as_slice(), &scalar(0)].concat()).unwrap();
+        assert_eq!(msm, [0u8; G1_LENGTH]);
+    }
+
+    #[test]
+    fn test_g2_add_and_msm() {
+        let g2 = hex::decode(G2_GENERATOR).unwrap();
+        let doubled = g2_add(&[g2.as_slice(), g2.as_slice()].concat()).unwrap();
+        let msm = g2_msm(&[g2.as_slice(), &scalar(2)].concat()).unwrap();
+        assert_eq!(doubled, msm);
+    }
+
+    #[test]
+    fn test_pairing() {
+        let g1 = hex::decode(G1_GENERATOR).unwrap();
+        let g2 = hex::decode(G2_GENERATOR).unwrap();
+        let neg_g1 = {
+            let mut scalar_minus_one = [0u8; SCALAR_LENGTH];
+            // r - 1
+            hex::decode_to_slice(
+                "73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000000",
+                &mut scalar_minus_one,
+            )
+            .unwrap();
+            g1_msm(&[g1.as_slice(), &scalar_minus_one].concat()).unwrap()
+        };
+
+        // e(g1, g2) * e(-g1, g2) == 1
+        let output = pairing(&[g1.as_slice(), &g2, &neg_g1, &g2].concat()).unwrap();
+        assert_eq!(output[31], 1);
+        // e(g1, g2) != 1
+        let output = pairing(&[g1.as_slice(), &g2].concat()).unwrap();
+        assert_eq!(output, [0u8; 32]);
+        // e(infinity, g2) == 1
+        let output = pairing(&[&[0u8; G1_LENGTH], g2.as_slice()].concat()).unwrap();
+        assert_eq!(output[31], 1);
+    }
+
+    #[test]
+    fn test_map_to_curve() {
+        let mut fp = [0u8; PADDED_FP_LENGTH];
+        fp[PADDED_FP_LENGTH - 1] = 42;
+        let p1 = map_fp_to_g1(&fp).unwrap();
+        // the result is in G1
+        g1_msm(&[p1.as_slice(), &scalar(1)].concat()).unwrap();
+
+        let fp2 = [fp, fp].concat();
+        let p2 = map_fp2_to_g2(&fp2).unwrap();
+        g2_msm(&[p2.as_slice(), &scalar(1)].concat()).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let g1 = hex::decode(G1_GENERATOR).unwrap();
+        assert_eq!(g1_add(&g1), Err(Error::InvalidInputLength));
+        assert_eq!(g1_msm(&[]), Err(Error::InvalidInputLength));
+        assert_eq!(pairing(&[]), Err(Error::InvalidInputLength));
+
+        // non-zero padding
+        let mut bad_padding = g1.clone();
+        bad_padding[0] = 1;
+        assert_eq!(
+            g1_add(&[bad_padding.as_slice(), &g1].concat()),
+            Err(Error::InvalidFieldElement)
+        );
+
+        // not less than the modulus
+        let mut fp = [0xffu8; PADDED_FP_LENGTH];
+        fp[..PADDING_LENGTH].fill(0);
+        assert_eq!(map_fp_to_g1(&fp), Err(Error::InvalidFieldElement));
+
+        // not on curve
+        let mut bad_point = g1.clone();
+        bad_point[G1_LENGTH - 1] ^= 1;
+        assert_eq!(
+            g1_add(&[bad_point.as_slice(), &g1].concat()),
+            Err(Error::PointNotOnCurve)
+        );
+    }
+}
@@ -33,3 +33,9 @@ pub const GW_ERROR_ACCOUNT_NOT_FOUND: i8 = 85;
 pub const GW_SUDT_ERROR_INSUFFICIENT_BALANCE: i8 = 92i8;
 pub const GW_SUDT_ERROR_AMOUNT_OVERFLOW: i8 = 93i8;
 pub const GW_SUDT_ERROR_UNPERMITTED_ADDRESS: i8 = 94i8;
+
+/* BLS12-381 */
+pub const GW_ERROR_BLS12_381_INVALID_INPUT_LENGTH: i8 = 110;
+pub const GW_ERROR_BLS12_381_INVALID_FIELD_ELEMENT: i8 = 111;
+pub const GW_ERROR_BLS12_381_POINT_NOT_ON_CURVE: i8 = 112;
+pub const GW_ERROR_BLS12_381_POINT_NOT_IN_SUBGROUP: i8 = 113;
@@ -1,6 +1,6 @@
 use crate::{
     account_lock_manage::AccountLockManage, backend_manage::BlockConsensus, generator::CyclesPool,
-    syscalls::error_codes::GW_FATAL_UNKNOWN_ARGS,
+    profiler::SyscallProfile, syscalls::error_codes::GW_FATAL_UNKNOWN_ARGS,
 };
 use ckb_vm::{
     memory::Memory,
@@ -27,7 +27,7 @@ use gw_types::{
     prelude::*,
 };
 use gw_utils::RollupContext;
-use std::{cmp, convert::TryInto};
+use std::{cmp, collections::BTreeMap, convert::TryInto};
 
 use self::error_codes::{
     GW_ERROR_ACCOUNT_NOT_FOUND, GW_ERROR_DUPLICATED_SCRIPT_HASH, GW_ERROR_INVALID_ACCOUNT_SCRIPT,
@@ -35,6 +35,7 @@ use self::error_codes::{
     GW_SUDT_ERROR_UNPERMITTED_ADDRESS, SUCCESS,
 };
 
+pub mod bls12_381;
 pub mod bn;
 pub mod error_codes;
 
@@ -68,6 +69,14 @@ const SYS_RECOVER_ACCOUNT: u64 = 3503;
 const SYS_BN_ADD: u64 = 3601;
 const SYS_BN_MUL: u64 = 3602;
 const SYS_BN_PAIRING: u64 = 3603;
+/* Syscall for BLS12-381 curve operations (EIP-2537), enabled by fork */
+const SYS_BLS12_381_G1_ADD: u64 = 3611;
+const SYS_BLS12_381_G1_MSM: u64 = 3612;
+const SYS_BLS12_381_G2_ADD: u64 = 3613;
+const SYS_BLS12_381_G2_MSM: u64 = 3614;
+const SYS_BLS12_381_PAIRING: u64 = 3615;
+const SYS_BLS12_381_MAP_FP_TO_G1: u64 = 3616;
+const SYS_BLS12_381_MAP_FP2_TO_G2: u64 = 3617;
 /* Syscall state revert */
 const SYS_SNAPSHOT: u64 = 3701;
 const SYS_REVERT: u64 = 3702;
@@ -76,12 +85,54 @@ const SYS_CHECK_SUDT_ADDRESS: u64 = 3801;
 /* CKB compatible syscalls */
 const DEBUG_PRINT_SYSCALL_NUMBER: u64 = 2177;
 
+fn is_bls12_381_syscall(code: u64) -> bool {
+    (SYS_BLS12_381_G1_ADD..=SYS_BLS12_381_MAP_FP2_TO_G2).contains(&code)
+}
+
+/// Returns the name of a syscall, used in profiling reports.
+pub fn syscall_name(code: u64) -> Option<&'static str> {
+    let name = match code {
+        SYS_CREATE => "create",
+        SYS_STORE => "store",
+        SYS_LOAD => "load",
+        SYS_LOAD_ACCOUNT_SCRIPT => "load_account_script",
+        SYS_SET_RETURN_DATA => "set_return_data",
+        SYS_STORE_DATA => "store_data",
+        SYS_LOAD_DATA => "load_data",
+        SYS_LOAD_ROLLUP_CONFIG => "load_rollup_config",
+        SYS_LOAD_TRANSACTION => "load_transaction",
+        SYS_LOAD_BLOCKINFO => "load_blockinfo",
+        SYS_GET_BLOCK_HASH => "get_block_hash",
+        SYS_PAY_FEE => "pay_fee",
+        SYS_LOG => "log",
+        SYS_RECOVER_ACCOUNT => "recover_account",
+        SYS_BN_ADD => "bn_add",
+        SYS_BN_MUL => "bn_mul",
+        SYS_BN_PAIRING => "bn_pairing",
+        SYS_BLS12_381_G1_ADD => "bls12_381_g1_add",
+        SYS_BLS12_381_G1_MSM => "bls12_381_g1_msm",
+        SYS_BLS12_381_G2_ADD => "bls12_381_g2_add",
+        SYS_BLS12_381_G2_MSM => "bls12_381_g2_msm",
+        SYS_BLS12_381_PAIRING => "bls12_381_pairing",
+        SYS_BLS12_381_MAP_FP_TO_G1 => "bls12_381_map_fp_to_g1",
+        SYS_BLS12_381_MAP_FP2_TO_G2 => "bls12_381_map_fp2_to_g2",
+        SYS_SNAPSHOT => "snapshot",
+        SYS_REVERT => "revert",
+        SYS_CHECK_SUDT_ADDRESS => "check_sudt_address",
+        DEBUG_PRINT_SYSCALL_NUMBER => "debug_print",
+        _ => return None,
+    };
+    Some(name)
+}
+
 #[derive(Debug, Default)]
 pub struct RunContext {
     pub cycle_meter: CycleMeter,
     pub return_data: Bytes,
     pub exit_code: i8,
     pub debug_log_buf: Vec<u8>,
+    /// Count and virtual cycles of each syscall, only collected when profiling
+    pub syscall_profile: Option<BTreeMap<u64, SyscallProfile>>,
 }
 
 impl RunContext {
@@ -170,7 +221,29 @@ impl<'a, 'b, S: State + CodeStore + JournalDB, C: ChainView, Mac: SupportMachine
     }
 
     fn ecall(&mut self, machine: &mut Mac) -> Result<bool, VMError> {
+        if self.context.syscall_profile.is_none() {
+            return self.handle_ecall(machine);
+        }
+
         let code = machine.registers()[A7].to_u64();
+        let virtual_cycles = self.context.cycle_meter.r#virtual;
+        let ret = self.handle_ecall(machine);
+        if let (Ok(true), Some(profile)) = (&ret, self.context.syscall_profile.as_mut()) {
+            let cycles = self.context.cycle_meter.r#virtual - virtual_cycles;
+            profile.entry(code).or_default().record(cycles);
+        }
+        ret
+    }
+}
+
+impl<'a, 'b, S: State + CodeStore + JournalDB, C: ChainView> L2Syscalls<'a, 'b, S, C> {
+    fn handle_ecall<Mac: SupportMachine>(&mut self, machine: &mut Mac) -> Result<bool, VMError> {
+        let code = machine.registers()[A7].to_u64();
+
+        // Unknown syscalls before the fork
+        if is_bls12_381_syscall(code) && !self.bls12_381_syscalls_enabled() {
+            return Ok(false);
+        }
 
         if let Some(cycles_pool) = self.cycles_pool {
             let syscall_cycles = Self::get_syscall_cycles(code, cycles_pool.syscall_config());
@@ -526,8 +599,8 @@ impl<'a, 'b, S: State + CodeStore + JournalDB, C: ChainView, Mac: SupportMachine
                 let payer_addr = {
                     let payer_addr = machine.registers()[A0].to_u64();
                     let payer_addr_len = machine.registers()[A1].to_u64();
-                    // addr len: 4 registry id + 4 addr len + 20 addr
-                    if payer_addr_len != 28u64 {
+                    // addr len: 4 registry id + 4 addr len + 20 eth addr or 32 script hash addr
+                    if payer_addr_len != 28u64 && payer_addr_len != 40u64 {
                         let err_msg =
                             format!("unexpected payer address length: {}", payer_addr_len);
                         log::error!("{}", err_msg);
@@ -595,30 +668,12 @@ impl<'a, 'b, S: State + CodeStore + JournalDB, C: ChainView, Mac: SupportMachine
                 let input_size = machine.registers()[A4].to_u64();
                 let input = load_bytes(machine, input_addr, input_size as usize)?;
 
-                if let Some(cycles_pool) = self.cycles_pool {
-                    // k is the number of pairings being computed
-                    let k: u64 = input_size / 192;
-                    if k > 0 {
-                        // Subtract additional cycles per pairing
-                        let additional_cycles =
-                            k * cycles_pool.syscall_config().sys_bn_per_pairing_cycles;
-                        self.context.cycle_meter.r#virtual = self
-                            .context
-                            .cycle_meter
-                            .r#virtual
-                            .saturating_add(additional_cycles);
-                        let execution_and_virtual = machine
-                            .cycles()
-                            .saturating_add(self.context.cycle_meter.r#virtual);
-                        if cycles_pool.consume_cycles(additional_cycles).is_none()
-                            || execution_and_virtual > cycles_pool.limit()
-                        {
-                            return Err(VMError::Unexpected(
-                                "cycles pool limit reached".to_owned(),
-                            ));
-                        }
-                    }
-                }
+                // k is the number of pairings being computed
+                let k: u64 = input_size / 192;
+                // Subtract additional cycles per pairing
+                self.consume_cycles_per_item(machine, k, |config| {
+                    config.sys_bn_per_pairing_cycles
+                })?;
 
                 let output = bn::pairing(&input).map_err(|err| {
                     let err_msg = format!("syscall SYS_BN_PAIRING error: {:?}", err.0);
@@ -629,6 +684,33 @@ impl<'a, 'b, S: State + CodeStore + JournalDB, C: ChainView, Mac: SupportMachine
                 machine.set_register(A0, Mac::REG::from_u8(SUCCESS));
                 Ok(true)
             }
+            SYS_BLS12_381_G1_ADD => self.bls12_381_syscall(machine, bls12_381::g1_add),
+            SYS_BLS12_381_G1_MSM => {
+                let k = machine.registers()[A4].to_u64() / bls12_381::G1_MSM_PAIR_LENGTH as u64;
+                self.consume_cycles_per_item(machine, k, |config| {
+                    config.sys_bls12_381_g1_msm_per_pair_cycles
+                })?;
+                self.bls12_381_syscall(machine, bls12_381::g1_msm)
+            }
+            SYS_BLS12_381_G2_ADD => self.bls12_381_syscall(machine, bls12_381::g2_add),
+            SYS_BLS12_381_G2_MSM => {
+                let k = machine.registers()[A4].to_u64() / bls12_381::G2_MSM_PAIR_LENGTH as u64;
+                self.consume_cycles_per_item(machine, k, |config| {
+                    config.sys_bls12_381_g2_msm_per_pair_cycles
+                })?;
+                self.bls12_381_syscall(machine, bls12_381::g2_msm)
+            }
+            SYS_BLS12_381_PAIRING => {
+                let k = machine.registers()[A4].to_u64() / bls12_381::PAIRING_PAIR_LENGTH as u64;
+                self.consume_cycles_per_item(machine, k, |config| {
+                    config.sys_bls12_381_per_pairing_cycles
+                })?;
+                self.bls12_381_syscall(machine, bls12_381::pairing)
+            }
+            SYS_BLS12_381_MAP_FP_TO_G1 => self.bls12_381_syscall(machine, bls12_381::map_fp_to_g1),
+            SYS_BLS12_381_MAP_FP2_TO_G2 => {
+                self.bls12_381_syscall(machine, bls12_381::map_fp2_to_g2)
+            }
             SYS_SNAPSHOT => {
                 let snapshot_addr = machine.registers()[A0].clone();
                 // create snapshot
@@ -729,6 +811,69 @@ impl<'a, 'b, S: State, C: ChainView> L2Syscalls<'a, 'b, S, C> {
         Ok(())
     }
 
+    fn bls12_381_syscalls_enabled(&self) -> bool {
+        let block_number: u64 = self.block_info.number().unpack();
+        self.rollup_context
+            .fork_config
+            .bls12_381_syscalls_enabled(block_number)
+    }
+
+    /// Subtract additional cycles for syscalls whose cost depends on the
+    /// number of items, e.g. pairings.
+    fn consume_cycles_per_item<Mac: SupportMachine>(
+        &mut self,
+        machine: &mut Mac,
+        items: u64,
+        per_item_cycles: impl FnOnce(&SyscallCyclesConfig) -> u64,
+    ) -> Result<(), VMError> {
+        if let Some(cycles_pool) = self.cycles_pool {
+            if items > 0 {
+                let additional_cycles =
+                    items.saturating_mul(per_item_cycles(cycles_pool.syscall_config()));
+                self.context.cycle_meter.r#virtual = self
+                    .context
+                    .cycle_meter
+                    .r#virtual
+                    .saturating_add(additional_cycles);
+                let execution_and_virtual = machine
+                    .cycles()
+                    .saturating_add(self.context.cycle_meter.r#virtual);
+                if cycles_pool.consume_cycles(additional_cycles).is_none()
+                    || execution_and_virtual > cycles_pool.limit()
+                {
+                    return Err(VMError::Unexpected("cycles pool limit reached".to_owned()));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Load input from A3 and A4, store output with `store_data`.
+    ///
+    /// Invalid input is returned as an error code so contracts can handle
+    /// it like a failed precompile.
+    fn bls12_381_syscall<Mac: SupportMachine, const N: usize>(
+        &mut self,
+        machine: &mut Mac,
+        f: impl FnOnce(&[u8]) -> Result<[u8; N], bls12_381::Error>,
+    ) -> Result<bool, VMError> {
+        let input_addr = machine.registers()[A3].to_u64();
+        let input_size = machine.registers()[A4].to_u64();
+        let input = load_bytes(machine, input_addr, input_size as usize)?;
+
+        match f(&input) {
+            Ok(output) => {
+                store_data(machine, output.as_slice())?;
+                machine.set_register(A0, Mac::REG::from_u8(SUCCESS));
+            }
+            Err(err) => {
+                log::debug!("syscall BLS12-381 error: {:?}", err);
+                machine.set_register(A0, Mac::REG::from_i8(err.error_code()));
+            }
+        }
+        Ok(true)
+    }
+
     fn get_syscall_cycles(syscall: u64, cycles_config: &SyscallCyclesConfig) -> u64 {
         match syscall {
             SYS_STORE => cycles_config.sys_store_cycles,
@@ -743,6 +888,11 @@ impl<'a, 'b, S: State, C: ChainView> L2Syscalls<'a, 'b, S, C> {
             SYS_BN_ADD => cycles_config.sys_bn_add_cycles,
             SYS_BN_MUL => cycles_config.sys_bn_mul_cycles,
             SYS_BN_PAIRING => cycles_config.sys_bn_fixed_pairing_cycles,
+            SYS_BLS12_381_G1_ADD => cycles_config.sys_bls12_381_g1_add_cycles,
+            SYS_BLS12_381_G2_ADD => cycles_config.sys_bls12_381_g2_add_cycles,
+            SYS_BLS12_381_PAIRING => cycles_config.sys_bls12_381_fixed_pairing_cycles,
+            SYS_BLS12_381_MAP_FP_TO_G1 => cycles_config.sys_bls12_381_map_fp_to_g1_cycles,
+            SYS_BLS12_381_MAP_FP2_TO_G2 => cycles_config.sys_bls12_381_map_fp2_to_g2_cycles,
             SYS_SNAPSHOT => cycles_config.sys_snapshot_cycles,
             SYS_REVERT => cycles_config.sys_revert_cycles,
             _ => 0,
@@ -7,7 +7,13 @@ use gw_store::{
     Store,
 };
 use gw_traits::CodeStore;
-use gw_types::{bytes::Bytes, core::ScriptHashType, h256::*, packed::RollupConfig, prelude::*};
+use gw_types::{
+    bytes::Bytes,
+    core::{AllowedContractType, ScriptHashType},
+    h256::*,
+    packed::{AllowedTypeHash, RollupConfig},
+    prelude::*,
+};
 use std::convert::TryInto;
 
 const GENESIS_BLOCK_HASH: [u8; 32] = [
@@ -58,3 +64,29 @@ fn test_init_genesis() {
     let code_hash: [u8; 32] = script.code_hash().unpack();
     assert_eq!(code_hash, meta_contract_code_hash);
 }
+
+#[test]
+fn test_init_genesis_refuses_script_hash_registry() {
+    let rollup_script_hash: [u8; 32] = [42u8; 32];
+    let rollup_config = RollupConfig::new_builder()
+        .allowed_contract_type_hashes(
+            vec![AllowedTypeHash::new(
+                AllowedContractType::ScriptHashAddrReg,
+                [3u8; 32],
+            )]
+            .pack(),
+        )
+        .build();
+    let config = GenesisConfig {
+        timestamp: 42,
+        meta_contract_validator_type_hash: [1u8; 32].into(),
+        eth_registry_validator_type_hash: [2u8; 32].into(),
+        rollup_config: rollup_config.into(),
+        rollup_type_hash: rollup_script_hash.into(),
+        secp_data_dep: Default::default(),
+    };
+    let store: Store = Store::open_tmp().unwrap();
+    // The builtin contracts can't handle script hash addresses yet.
+    let err = init_genesis(&store, &config, &[0u8; 32], Bytes::default()).unwrap_err();
+    assert!(err.to_string().contains("script hash registry"));
+}
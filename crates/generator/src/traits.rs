@@ -114,16 +114,10 @@ impl<S: State + CodeStore + JournalDB> StateExt for S {
                     hex::encode(account_script_hash.as_slice()),
                     new_id
                 );
-                let registry_ctx = RegistryContext::new(
-                    ctx.rollup_config
-                        .allowed_eoa_type_hashes()
-                        .into_iter()
-                        .collect(),
-                );
+                let registry_ctx = RegistryContext::from_rollup_config(&ctx.rollup_config);
                 let addr = registry_ctx.extract_registry_address_from_deposit(
                     request.registry_id().unpack(),
-                    &request.script().code_hash(),
-                    &request.script().args().raw_data(),
+                    &request.script(),
                 )?;
                 // mapping addr to script hash
                 self.mapping_registry_address_to_script_hash(addr.clone(), account_script_hash)?;
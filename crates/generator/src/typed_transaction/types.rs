@@ -25,7 +25,8 @@ impl TypedRawTransaction {
             AllowedContractType::Meta => Self::Meta(MetaTx(raw_tx)),
             AllowedContractType::Sudt => Self::SimpleUDT(SimpleUDTTx(raw_tx)),
             AllowedContractType::Polyjuice => Self::Polyjuice(PolyjuiceTx(raw_tx)),
-            AllowedContractType::Unknown => return None,
+            // The script hash registry is populated by deposits only
+            AllowedContractType::ScriptHashAddrReg | AllowedContractType::Unknown => return None,
         };
         Some(tx)
     }
@@ -75,6 +76,10 @@ impl MetaTx {
         match args.to_enum() {
             CreateAccount(args) => Some(args.fee().amount().unpack().into()),
             BatchCreateEthAccounts(args) => Some(args.fee().amount().unpack().into()),
+            RegisterSessionKey(args) => Some(args.fee().amount().unpack().into()),
+            RevokeSessionKey(args) => Some(args.fee().amount().unpack().into()),
+            SetSponsorPolicy(args) => Some(args.fee().amount().unpack().into()),
+            RemoveSponsorPolicy(args) => Some(args.fee().amount().unpack().into()),
         }
     }
 
@@ -2,10 +2,16 @@
 #[global_allocator]
 static GLOBAL_ALLOC: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use clap::{Arg, Command, CommandFactory, Parser};
+use godwoken_bin::subcommand::challenge_audit::{ChallengeAuditCommand, COMMAND_CHALLENGE_AUDIT};
+use godwoken_bin::subcommand::custodian_audit::{CustodianAuditCommand, COMMAND_CUSTODIAN_AUDIT};
+use godwoken_bin::subcommand::db::{DbCommand, COMMAND_DB};
 use godwoken_bin::subcommand::db_block_validator;
-use godwoken_bin::subcommand::export_block::{ExportArgs, ExportBlock};
+use godwoken_bin::subcommand::export_block::{ExportArgs, ExportBlock, ExportFormat};
+use godwoken_bin::subcommand::follow_export::{
+    FollowExport, FollowExportArgs, DEFAULT_POLL_INTERVAL, DEFAULT_ROTATE_BLOCKS,
+};
 use godwoken_bin::subcommand::import_block::{ImportArgs, ImportBlock};
 use godwoken_bin::subcommand::migrate::{MigrateCommand, COMMAND_MIGRATE};
 use godwoken_bin::subcommand::peer_id::{PeerIdCommand, COMMAND_PEER_ID};
@@ -16,7 +22,7 @@ use gw_block_producer::runner;
 use gw_config::{BuiltinConsensus, Config, Consensus};
 use gw_telemetry::trace;
 use gw_version::Version;
-use std::{env, fs, path::Path};
+use std::{env, fs, path::Path, time::Duration};
 
 const COMMAND_RUN: &str = "run";
 const COMMAND_EXAMPLE_CONFIG: &str = "generate-example-config";
@@ -29,6 +35,12 @@ const ARG_SKIP_CONFIG_CHECK: &str = "skip-config-check";
 const ARG_FROM_BLOCK: &str = "from-block";
 const ARG_TO_BLOCK: &str = "to-block";
 const ARG_SHOW_PROGRESS: &str = "show-progress";
+const ARG_FORMAT: &str = "format";
+const ARG_INCLUDE_BAD_BLOCKS: &str = "include-bad-blocks";
+const ARG_FOLLOW: &str = "follow";
+const ARG_ROTATE_BLOCKS: &str = "rotate-blocks";
+const ARG_ROTATE_SIZE: &str = "rotate-size";
+const ARG_POLL_INTERVAL: &str = "poll-interval";
 const ARG_SOURCE_PATH: &str = "source-path";
 const ARG_READ_BATCH: &str = "read-batch";
 const ARG_REWIND_TO_LAST_VALID_TIP: &str = "rewind-to-last-valid-tip";
@@ -148,6 +160,62 @@ async fn run_cli() -> Result<()> {
                         .takes_value(true)
                         .help("To block number"),
                 )
+                .arg(
+                    Arg::new(ARG_FORMAT)
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(["v1", "v2"])
+                        .help(
+                            "Export format [default: v1], v2 is compressed and indexed. \
+                            Follow mode always writes v2",
+                        ),
+                )
+                .arg(
+                    Arg::new(ARG_INCLUDE_BAD_BLOCKS)
+                        .long("include-bad-blocks")
+                        .required(false)
+                        .takes_value(false)
+                        .help(
+                            "Also export bad blocks after the last valid block, \
+                            requires --format v2",
+                        ),
+                )
+                .arg(
+                    Arg::new(ARG_FOLLOW)
+                        .long("follow")
+                        .required(false)
+                        .takes_value(false)
+                        .conflicts_with_all(&[ARG_INCLUDE_BAD_BLOCKS, ARG_SHOW_PROGRESS])
+                        .help(
+                            "Keep exporting finalized blocks to rotating v2 segment files, \
+                            each with a manifest",
+                        ),
+                )
+                .arg(
+                    Arg::new(ARG_ROTATE_BLOCKS)
+                        .long("rotate-blocks")
+                        .takes_value(true)
+                        .requires(ARG_FOLLOW)
+                        .help("Follow mode, max blocks per segment file [default: 100000]"),
+                )
+                .arg(
+                    Arg::new(ARG_ROTATE_SIZE)
+                        .long("rotate-size")
+                        .takes_value(true)
+                        .requires(ARG_FOLLOW)
+                        .help(
+                            "Follow mode, rotate segment file once it reaches this size in bytes",
+                        ),
+                )
+                .arg(
+                    Arg::new(ARG_POLL_INTERVAL)
+                        .long("poll-interval")
+                        .takes_value(true)
+                        .requires(ARG_FOLLOW)
+                        .help(
+                            "Follow mode, seconds to wait for new finalized blocks [default: 10]",
+                        ),
+                )
                 .arg(
                     Arg::new(ARG_SHOW_PROGRESS)
                         .short('p')
@@ -175,7 +243,7 @@ async fn run_cli() -> Result<()> {
                         .long("source-path")
                         .takes_value(true)
                         .required(true)
-                        .help("The source file for exported blocks"),
+                        .help("The source file for exported blocks, v1 or v2 format"),
                 )
                 .arg(
                     Arg::new(ARG_READ_BATCH)
@@ -210,7 +278,10 @@ async fn run_cli() -> Result<()> {
         )
         .subcommand(PeerIdCommand::command())
         .subcommand(RewindToLastValidBlockCommand::command())
-        .subcommand(MigrateCommand::command());
+        .subcommand(MigrateCommand::command())
+        .subcommand(ChallengeAuditCommand::command())
+        .subcommand(CustodianAuditCommand::command())
+        .subcommand(DbCommand::command());
 
     // handle subcommands
     let matches = app.clone().get_matches();
@@ -242,16 +313,44 @@ async fn run_cli() -> Result<()> {
             let output = m.value_of(ARG_OUTPUT_PATH).unwrap().into();
             let from_block: Option<u64> = m.value_of(ARG_FROM_BLOCK).map(str::parse).transpose()?;
             let to_block: Option<u64> = m.value_of(ARG_TO_BLOCK).map(str::parse).transpose()?;
+            let format: Option<ExportFormat> =
+                m.value_of(ARG_FORMAT).map(str::parse).transpose()?;
+            let include_bad_blocks = m.is_present(ARG_INCLUDE_BAD_BLOCKS);
             let show_progress = m.is_present(ARG_SHOW_PROGRESS);
 
-            let args = ExportArgs {
-                config,
-                output,
-                from_block,
-                to_block,
-                show_progress,
-            };
-            ExportBlock::create(args)?.execute()?;
+            if m.is_present(ARG_FOLLOW) {
+                if format == Some(ExportFormat::V1) {
+                    bail!("follow mode only supports v2 format");
+                }
+                let rotate_blocks = m.value_of(ARG_ROTATE_BLOCKS).map(str::parse).transpose()?;
+                let rotate_size = m.value_of(ARG_ROTATE_SIZE).map(str::parse).transpose()?;
+                let poll_interval: Option<u64> =
+                    m.value_of(ARG_POLL_INTERVAL).map(str::parse).transpose()?;
+
+                let args = FollowExportArgs {
+                    config,
+                    output,
+                    from_block,
+                    to_block,
+                    rotate_blocks: rotate_blocks.unwrap_or(DEFAULT_ROTATE_BLOCKS),
+                    rotate_size,
+                    poll_interval: poll_interval
+                        .map(Duration::from_secs)
+                        .unwrap_or(DEFAULT_POLL_INTERVAL),
+                };
+                FollowExport::create(args)?.execute()?;
+            } else {
+                let args = ExportArgs {
+                    config,
+                    output,
+                    from_block,
+                    to_block,
+                    format: format.unwrap_or(ExportFormat::V1),
+                    include_bad_blocks,
+                    show_progress,
+                };
+                ExportBlock::create(args)?.execute()?;
+            }
         }
         Some((COMMAND_IMPORT_BLOCK, m)) => {
             let config_path = m.value_of(ARG_CONFIG).unwrap();
@@ -283,6 +382,18 @@ async fn run_cli() -> Result<()> {
         Some((COMMAND_MIGRATE, m)) => {
             MigrateCommand::from_clap(m).run()?;
         }
+        Some((COMMAND_CHALLENGE_AUDIT, m)) => {
+            let _guard = trace::init()?;
+            ChallengeAuditCommand::from_clap(m).run().await?;
+        }
+        Some((COMMAND_CUSTODIAN_AUDIT, m)) => {
+            let _guard = trace::init()?;
+            CustodianAuditCommand::from_clap(m).run().await?;
+        }
+        Some((COMMAND_DB, m)) => {
+            let _guard = trace::init()?;
+            DbCommand::from_clap(m).run()?;
+        }
         _ => {
             // default command: start a Godwoken node
             let config_path = "./config.toml";
@@ -0,0 +1,333 @@
+use std::{path::PathBuf, sync::Arc};
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use gw_block_producer::{
+    challenger::{MAX_CANCEL_CYCLES, MAX_CANCEL_TX_BYTES},
+    runner::BaseInitComponents,
+};
+use gw_challenge::{
+    cancel_challenge::LoadDataStrategy,
+    context::build_verify_context,
+    offchain::{
+        mock_cancel_challenge_tx,
+        verify_tx::{verify_tx, TxWithContext},
+        OffChainMockContext,
+    },
+    types::VerifyContext,
+};
+use gw_config::Config;
+use gw_generator::Generator;
+use gw_jsonrpc_types::godwoken::ChallengeTargetType as JsonChallengeTargetType;
+use gw_store::{traits::chain_store::ChainStore, Store};
+use gw_types::{
+    core::{ChallengeTargetType, Status},
+    h256::*,
+    packed::{ChallengeTarget, GlobalState},
+    prelude::*,
+};
+use rayon::prelude::*;
+use serde_json::json;
+
+use super::db_block_validator::build_challenge_target;
+
+pub const COMMAND_CHALLENGE_AUDIT: &str = "challenge-audit";
+// Number of blocks audited in parallel.
+const AUDIT_BATCH_BLOCKS: u64 = 64;
+
+/// Check that every target of the blocks can be defended by a cancel
+/// challenge transaction within the L1 size and cycle budgets
+#[derive(Parser)]
+#[clap(name = COMMAND_CHALLENGE_AUDIT)]
+pub struct ChallengeAuditCommand {
+    /// The config file path
+    #[clap(short, long, default_value = "./config.toml")]
+    config_path: PathBuf,
+    /// From block number [default: 0]
+    #[clap(short, long)]
+    from_block: Option<u64>,
+    /// To block number [default: last valid tip]
+    #[clap(short, long)]
+    to_block: Option<u64>,
+    /// Cycle budget of cancel challenge transactions
+    #[clap(long, default_value_t = MAX_CANCEL_CYCLES)]
+    max_cycles: u64,
+    /// Size budget of cancel challenge transactions in bytes
+    #[clap(long, default_value_t = MAX_CANCEL_TX_BYTES)]
+    max_tx_size: u64,
+}
+
+impl ChallengeAuditCommand {
+    /// Prints the targets that are not defensible as json lines followed
+    /// by a summary, fails if there are any.
+    pub async fn run(self) -> Result<()> {
+        let content = std::fs::read(&self.config_path).with_context(|| {
+            format!(
+                "read config file from {}",
+                self.config_path.to_string_lossy()
+            )
+        })?;
+        let config: Config = toml::from_slice(&content).context("parse config file")?;
+        if config.store.path.as_os_str().is_empty() {
+            bail!("empty store path, no db block to audit");
+        }
+        let block_producer_config = config
+            .block_producer
+            .as_ref()
+            .context("challenge audit require block producer config")?;
+
+        let base = BaseInitComponents::init(&config, true).await?;
+        let mock_ctx = base
+            .init_offchain_mock_context(block_producer_config)
+            .await?
+            .context("no wallet config for block producer")?;
+        let auditor = ChallengeAuditor::new(
+            base.generator,
+            base.store,
+            mock_ctx,
+            self.max_cycles,
+            self.max_tx_size,
+        );
+
+        let from_block = self.from_block.unwrap_or(0);
+        let to_block = match self.to_block {
+            Some(to) => to,
+            None => {
+                let db = auditor.store.begin_transaction();
+                db.get_last_valid_tip_block()?.raw().number().unpack()
+            }
+        };
+
+        // Print the failed targets as soon as a batch of blocks is audited,
+        // only the counts are kept for the summary.
+        let mut targets = 0;
+        let mut failed = 0;
+        let mut batch_start = from_block;
+        while batch_start <= to_block {
+            let batch_end = to_block.min(batch_start.saturating_add(AUDIT_BATCH_BLOCKS - 1));
+            let reports = (batch_start..=batch_end)
+                .into_par_iter()
+                .map(|block_number| auditor.audit_block(block_number))
+                .collect::<Result<Vec<_>>>()?;
+            for report in reports.into_iter().flatten() {
+                targets += 1;
+                if !auditor.is_defensible(&report) {
+                    failed += 1;
+                    println!("{}", serde_json::to_string(&report.to_json())?);
+                }
+            }
+            if batch_end == u64::MAX {
+                break;
+            }
+            batch_start = batch_end + 1;
+        }
+
+        let summary = json!({
+            "from_block": from_block,
+            "to_block": to_block,
+            "max_cycles": self.max_cycles,
+            "max_tx_size": self.max_tx_size,
+            "targets": targets,
+            "failed": failed,
+        });
+        println!("{}", serde_json::to_string(&summary)?);
+
+        if failed > 0 {
+            bail!("{} of {} targets are not defensible", failed, targets);
+        }
+        Ok(())
+    }
+}
+
+/// Audits the cancel challenge transactions of the targets of db blocks.
+pub struct ChallengeAuditor {
+    generator: Arc<Generator>,
+    store: Store,
+    mock_ctx: OffChainMockContext,
+    max_cycles: u64,
+    max_tx_size: u64,
+}
+
+impl ChallengeAuditor {
+    pub fn new(
+        generator: Arc<Generator>,
+        store: Store,
+        mock_ctx: OffChainMockContext,
+        max_cycles: u64,
+        max_tx_size: u64,
+    ) -> Self {
+        ChallengeAuditor {
+            generator,
+            store,
+            mock_ctx,
+            max_cycles,
+            max_tx_size,
+        }
+    }
+
+    /// Audit the withdrawals and transactions of the block.
+    pub fn audit_block(&self, block_number: u64) -> Result<Vec<TargetReport>> {
+        log::info!("audit block #{}", block_number);
+        let db = self.store.begin_transaction();
+        let block_hash = db
+            .get_block_hash_by_number(block_number)?
+            .with_context(|| format!("block #{} not found", block_number))?;
+        let block = db
+            .get_block(&block_hash)?
+            .with_context(|| format!("block #{} not found", block_number))?;
+        // Challenged at the block, like the rollup is halting right after it.
+        let global_state = db
+            .get_block_post_global_state(&block_hash)?
+            .with_context(|| format!("block #{} global state not found", block_number))?
+            .as_builder()
+            .status((Status::Halting as u8).into())
+            .build();
+
+        let mut targets = Vec::new();
+        for (idx, withdrawal) in block.withdrawals().into_iter().enumerate() {
+            let target_type = ChallengeTargetType::Withdrawal;
+            targets.push((target_type, idx as u32, withdrawal.hash()));
+        }
+        for (idx, tx) in block.transactions().into_iter().enumerate() {
+            let tx_hash = tx.hash();
+            targets.push((ChallengeTargetType::TxSignature, idx as u32, tx_hash));
+            targets.push((ChallengeTargetType::TxExecution, idx as u32, tx_hash));
+        }
+
+        let reports = targets
+            .into_par_iter()
+            .map(|(target_type, target_index, target_hash)| {
+                let target = build_challenge_target(block_hash, target_index, target_type);
+                let mut report = TargetReport {
+                    block_number,
+                    target_type,
+                    target_index,
+                    target_hash,
+                    error: None,
+                    attempts: Vec::new(),
+                };
+                self.audit_target(&global_state, &target, &mut report);
+                report
+            })
+            .collect();
+        Ok(reports)
+    }
+
+    fn audit_target(
+        &self,
+        global_state: &GlobalState,
+        target: &ChallengeTarget,
+        report: &mut TargetReport,
+    ) {
+        let mut db = self.store.begin_transaction();
+        let verify_context = match build_verify_context(self.generator.clone(), &mut db, target) {
+            Ok(ctx) => ctx,
+            Err(err) => {
+                report.error = Some(format!("build verify context: {:#}", err));
+                return;
+            }
+        };
+
+        // Same as the challenger, load data by witness first.
+        for strategy in [LoadDataStrategy::Witness, LoadDataStrategy::CellDep] {
+            let attempt = self.attempt(global_state, target, &verify_context, strategy);
+            let within_budgets = self.is_within_budgets(&attempt);
+            report.attempts.push(attempt);
+            if within_budgets {
+                break;
+            }
+        }
+    }
+
+    fn attempt(
+        &self,
+        global_state: &GlobalState,
+        target: &ChallengeTarget,
+        verify_context: &VerifyContext,
+        strategy: LoadDataStrategy,
+    ) -> Attempt {
+        let mut attempt = Attempt {
+            strategy,
+            tx_size: None,
+            cycles: None,
+            error: None,
+        };
+        let mock_output = match mock_cancel_challenge_tx(
+            &self.mock_ctx.mock_rollup,
+            global_state.clone(),
+            target.clone(),
+            verify_context.clone(),
+            Some(strategy),
+        ) {
+            Ok(output) => output,
+            Err(err) => {
+                attempt.error = Some(format!("build cancel tx: {:#}", err));
+                return attempt;
+            }
+        };
+        attempt.tx_size = Some(mock_output.tx.as_slice().len() as u64);
+
+        // Run without the budget to report the actual cycles.
+        let tx_with_context = TxWithContext::from(mock_output);
+        match verify_tx(&self.mock_ctx.rollup_cell_deps, tx_with_context, u64::MAX) {
+            Ok(cycles) => attempt.cycles = Some(cycles),
+            Err(err) => attempt.error = Some(format!("{:#}", err)),
+        }
+        attempt
+    }
+
+    fn is_within_budgets(&self, attempt: &Attempt) -> bool {
+        matches!(
+            (attempt.tx_size, attempt.cycles),
+            (Some(size), Some(cycles)) if size <= self.max_tx_size && cycles <= self.max_cycles
+        )
+    }
+
+    /// Some attempt is within the size and cycle budgets.
+    pub fn is_defensible(&self, report: &TargetReport) -> bool {
+        report.attempts.iter().any(|a| self.is_within_budgets(a))
+    }
+}
+
+pub struct TargetReport {
+    pub block_number: u64,
+    pub target_type: ChallengeTargetType,
+    pub target_index: u32,
+    pub target_hash: H256,
+    pub error: Option<String>,
+    /// Attempts with the load data strategies, stop at the first one within
+    /// the budgets.
+    pub attempts: Vec<Attempt>,
+}
+
+impl TargetReport {
+    pub fn to_json(&self) -> serde_json::Value {
+        let attempts: Vec<_> = self
+            .attempts
+            .iter()
+            .map(|a| {
+                json!({
+                    "load_data_strategy": format!("{:?}", a.strategy),
+                    "tx_size": a.tx_size,
+                    "cycles": a.cycles,
+                    "error": a.error,
+                })
+            })
+            .collect();
+        json!({
+            "block_number": self.block_number,
+            "target_type": JsonChallengeTargetType::from(self.target_type),
+            "target_index": self.target_index,
+            "target_hash": ckb_types::H256(self.target_hash),
+            "error": self.error,
+            "attempts": attempts,
+        })
+    }
+}
+
+pub struct Attempt {
+    pub strategy: LoadDataStrategy,
+    pub tx_size: Option<u64>,
+    pub cycles: Option<u64>,
+    pub error: Option<String>,
+}
@@ -0,0 +1,55 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use gw_block_producer::{custodian_audit::CustodianAuditor, runner::BaseInitComponents};
+use gw_config::Config;
+
+pub const COMMAND_CUSTODIAN_AUDIT: &str = "custodian-audit";
+
+/// Check that custodians on L1 cover the L2 supply and pending withdrawals
+/// of CKB and every sUDT
+#[derive(Parser)]
+#[clap(name = COMMAND_CUSTODIAN_AUDIT)]
+pub struct CustodianAuditCommand {
+    /// The config file path
+    #[clap(short, long, default_value = "./config.toml")]
+    config_path: PathBuf,
+    /// Also write the report to this file
+    #[clap(short, long)]
+    output: Option<PathBuf>,
+}
+
+impl CustodianAuditCommand {
+    /// Prints the report as json, fails if any asset has a deficit.
+    pub async fn run(self) -> Result<()> {
+        let content = std::fs::read(&self.config_path).with_context(|| {
+            format!(
+                "read config file from {}",
+                self.config_path.to_string_lossy()
+            )
+        })?;
+        let config: Config = toml::from_slice(&content).context("parse config file")?;
+        if config.store.path.as_os_str().is_empty() {
+            bail!("empty store path, no L2 state to audit");
+        }
+
+        let base = BaseInitComponents::init(&config, true).await?;
+        let auditor = CustodianAuditor::new(base.rpc_client, base.store, base.rollup_context);
+        let report = auditor.audit().await?;
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        if let Some(ref output) = self.output {
+            report.write(output)?;
+        }
+
+        let deficits = report.deficit_assets().count();
+        if deficits > 0 {
+            bail!(
+                "{} of {} assets have a deficit",
+                deficits,
+                report.assets.len()
+            );
+        }
+        Ok(())
+    }
+}
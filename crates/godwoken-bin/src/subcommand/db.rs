@@ -0,0 +1,93 @@
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use gw_config::Config;
+use gw_store::schema::COLUMNS;
+use gw_store::Store;
+
+pub const COMMAND_DB: &str = "db";
+
+/// Database statistics and maintenance commands.
+///
+/// These commands open the database exclusively, so the node must be stopped.
+/// Use the `gw_get_rocksdb_cf_stats` and `gw_compact_rocksdb_column` RPCs for a
+/// running node.
+#[derive(Parser)]
+#[clap(name = COMMAND_DB)]
+pub enum DbCommand {
+    /// Print per column family storage statistics as JSON.
+    Stats {
+        /// The config file path
+        #[clap(short, long, default_value = "./config.toml")]
+        config_path: PathBuf,
+    },
+    /// Manually compact a key range of a column family.
+    Compact {
+        /// The config file path
+        #[clap(short, long, default_value = "./config.toml")]
+        config_path: PathBuf,
+        /// Column id, see `gw_store::schema`.
+        #[clap(long)]
+        column: usize,
+        /// First key (hex) of the range, default to the start of the column.
+        #[clap(long)]
+        begin: Option<String>,
+        /// Last key (hex) of the range, default to the end of the column.
+        #[clap(long)]
+        end: Option<String>,
+    },
+}
+
+impl DbCommand {
+    pub fn run(self) -> Result<()> {
+        match self {
+            DbCommand::Stats { config_path } => {
+                let store = open_store(&config_path)?;
+                let stats = store.gather_cf_stats();
+                println!("{}", serde_json::to_string_pretty(&stats)?);
+            }
+            DbCommand::Compact {
+                config_path,
+                column,
+                begin,
+                end,
+            } => {
+                if column >= COLUMNS {
+                    bail!("column {} not found", column);
+                }
+                let begin = begin.as_deref().map(parse_hex).transpose()?;
+                let end = end.as_deref().map(parse_hex).transpose()?;
+
+                let store = open_store(&config_path)?;
+                let t = Instant::now();
+                store.compact_range(column, begin.as_deref(), end.as_deref())?;
+                log::info!(
+                    "compact column {} done: {}ms",
+                    column,
+                    t.elapsed().as_millis()
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+fn open_store(config_path: &Path) -> Result<Store> {
+    let content = std::fs::read(config_path)
+        .with_context(|| format!("read config file from {}", config_path.to_string_lossy()))?;
+    let config: Config = toml::from_slice(&content).context("parse config file")?;
+    Store::open(&config.store, COLUMNS).context("open database")
+}
+
+fn parse_hex(s: &str) -> Result<Vec<u8>> {
+    let s = s.strip_prefix("0x").unwrap_or(s);
+    if !s.is_ascii() || s.len() % 2 != 0 {
+        bail!("invalid hex {}", s);
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).with_context(|| format!("invalid hex {}", s)))
+        .collect()
+}
@@ -363,7 +363,7 @@ impl DumpContext {
     }
 }
 
-fn build_challenge_target(
+pub(crate) fn build_challenge_target(
     block_hash: H256,
     target_index: u32,
     target_type: ChallengeTargetType,
@@ -9,13 +9,37 @@ use gw_store::schema::COLUMNS;
 use gw_store::traits::chain_store::ChainStore;
 use gw_types::packed;
 use gw_types::prelude::{Entity, Unpack};
+use gw_utils::export_block::v2;
 use indicatif::{ProgressBar, ProgressStyle};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Length prefixed molecule `ExportedBlock`s, the default format.
+    V1,
+    /// Compressed segments with block index and checksums, see
+    /// `gw_utils::export_block::v2`.
+    V2,
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "v1" => Ok(ExportFormat::V1),
+            "v2" => Ok(ExportFormat::V2),
+            _ => bail!("unknown export format {}", s),
+        }
+    }
+}
+
 pub struct ExportArgs {
     pub config: Config,
     pub output: PathBuf,
     pub from_block: Option<u64>,
     pub to_block: Option<u64>,
+    pub format: ExportFormat,
+    pub include_bad_blocks: bool,
     pub show_progress: bool,
 }
 
@@ -27,6 +51,7 @@ pub struct ExportBlock {
     output: PathBuf,
     from_block: u64,
     to_block: u64,
+    format: ExportFormat,
     progress_bar: Option<ProgressBar>,
 }
 
@@ -44,35 +69,41 @@ impl ExportBlock {
             output,
             from_block,
             to_block,
+            format: ExportFormat::V1,
             progress_bar: None,
         }
     }
 
+    // Disable warning for bin
+    #[allow(dead_code)]
+    pub fn with_format(mut self, format: ExportFormat) -> Self {
+        self.format = format;
+        self
+    }
+
     pub fn create(args: ExportArgs) -> Result<Self> {
         let snap =
             StoreReadonly::open(&args.config.store.path, COLUMNS).context("open database")?;
 
         let db_last_valid_tip_block_number =
             snap.get_last_valid_tip_block()?.raw().number().unpack();
+        let db_tip_block_number = snap.get_tip_block()?.raw().number().unpack();
+
+        if args.include_bad_blocks && args.format == ExportFormat::V1 {
+            bail!("v1 format doesn't support bad blocks, use --format v2");
+        }
 
         let from_block = args.from_block.unwrap_or(0);
         let to_block = match args.to_block {
-            Some(to) => {
-                snap.get_block_hash_by_number(to)?
-                    .ok_or_else(|| anyhow!("{} block not found", to))?;
-
-                // TODO: support export bad block? (change `insert_bad_block` func to also include
-                // deposit requests, deposit asset scripts and withdrawals). then add new arg
-                // --skip-tip-bad-block-check. (also update file name).
-                if to > db_last_valid_tip_block_number {
-                    bail!(
-                        "bad block found, start from block {}",
-                        db_last_valid_tip_block_number + 1
-                    );
-                }
-
-                to
+            Some(to) if to > db_tip_block_number => bail!("{} block not found", to),
+            Some(to) if to > db_last_valid_tip_block_number && !args.include_bad_blocks => {
+                bail!(
+                    "bad block found, start from block {}, use --include-bad-blocks to export them",
+                    db_last_valid_tip_block_number + 1
+                );
             }
+            Some(to) => to,
+            None if args.include_bad_blocks => db_tip_block_number,
             None => db_last_valid_tip_block_number,
         };
         if from_block > to_block {
@@ -113,6 +144,7 @@ impl ExportBlock {
             output,
             from_block,
             to_block,
+            format: args.format,
             progress_bar,
         };
 
@@ -129,7 +161,10 @@ impl ExportBlock {
         if let Some(parent) = self.output.parent() {
             fs::create_dir_all(parent)?;
         }
-        self.write_to_mol()
+        match self.format {
+            ExportFormat::V1 => self.write_to_mol(),
+            ExportFormat::V2 => self.write_to_v2(),
+        }
     }
 
     pub fn write_to_mol(self) -> Result<()> {
@@ -142,6 +177,9 @@ impl ExportBlock {
         let mut writer = io::BufWriter::new(f);
         for block_number in self.from_block..=self.to_block {
             let exported_block = gw_utils::export_block::export_block(&self.snap, block_number)?;
+            if exported_block.is_bad_block() {
+                bail!("v1 format doesn't support bad block {}", block_number);
+            }
             let packed: packed::ExportedBlock = exported_block.into();
 
             writer.write_all(packed.as_slice())?;
@@ -158,4 +196,34 @@ impl ExportBlock {
 
         Ok(())
     }
+
+    pub fn write_to_v2(self) -> Result<()> {
+        let f = fs::OpenOptions::new()
+            .create_new(true)
+            .write(true)
+            .open(self.output)?;
+
+        let header = v2::Header {
+            rollup_type_hash: self.snap.get_chain_id()?,
+            from_block: self.from_block,
+            to_block: self.to_block,
+            blocks_per_segment: v2::DEFAULT_BLOCKS_PER_SEGMENT,
+        };
+        let mut writer = v2::ExportedBlockWriter::new(io::BufWriter::new(f), header)?;
+        for block_number in self.from_block..=self.to_block {
+            let exported_block = gw_utils::export_block::export_block(&self.snap, block_number)?;
+            writer.write_block(exported_block)?;
+
+            if let Some(ref progress_bar) = self.progress_bar {
+                progress_bar.inc(1)
+            }
+        }
+        writer.finish()?;
+
+        if let Some(ref progress_bar) = self.progress_bar {
+            progress_bar.finish_with_message("done");
+        }
+
+        Ok(())
+    }
 }
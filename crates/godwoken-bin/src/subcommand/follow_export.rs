@@ -0,0 +1,340 @@
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, BufWriter, Read};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use gw_common::blake2b::new_blake2b;
+use gw_config::{Config, ForkConfig};
+use gw_store::readonly::StoreReadonly;
+use gw_store::schema::COLUMNS;
+use gw_store::traits::chain_store::ChainStore;
+use gw_types::{h256::*, packed::RollupConfig, prelude::*};
+use gw_utils::{export_block::v2, find_finalized_upper_bound};
+
+pub const DEFAULT_ROTATE_BLOCKS: u64 = 100_000;
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+const MANIFEST_SUFFIX: &str = ".manifest.json";
+const PARTIAL_SUFFIX: &str = ".partial";
+
+pub struct FollowExportArgs {
+    pub config: Config,
+    pub output: PathBuf,
+    pub from_block: Option<u64>,
+    pub to_block: Option<u64>,
+    pub rotate_blocks: u64,
+    pub rotate_size: Option<u64>,
+    pub poll_interval: Duration,
+}
+
+/// FollowExport
+///
+/// Keep exporting finalized blocks to rotating v2 segment files. A segment is
+/// complete once its manifest `<segment file>.manifest.json` is written, an
+/// unfinished segment is kept as `<prefix>_<rollup type hash>_<from>.partial`
+/// and rewritten on restart.
+///
+/// Only blocks finalized for the last L1 confirmed block are exported, so
+/// segments are never invalidated by a revert.
+pub struct FollowExport {
+    store_path: PathBuf,
+    rollup_config: RollupConfig,
+    fork_config: ForkConfig,
+    rollup_type_hash: H256,
+    dir: PathBuf,
+    prefix: String,
+    next_block: u64,
+    to_block: Option<u64>,
+    rotate_blocks: u64,
+    rotate_size: Option<u64>,
+    poll_interval: Duration,
+    segment: Option<Segment>,
+}
+
+struct Segment {
+    writer: v2::ExportedBlockWriter<BufWriter<fs::File>>,
+    partial_path: PathBuf,
+    from_block: u64,
+    last_block_hash: H256,
+}
+
+impl FollowExport {
+    pub fn create(args: FollowExportArgs) -> Result<Self> {
+        if args.rotate_blocks == 0 {
+            bail!("rotate blocks must be greater than 0");
+        }
+
+        let fork_config = args.config.consensus.get_config().clone();
+        let rollup_type_hash: H256 = fork_config.genesis.rollup_type_hash.clone().into();
+        let rollup_config = fork_config.genesis.rollup_config.clone().into();
+
+        let dir = match args.output.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let prefix = {
+            let file_name = args
+                .output
+                .file_name()
+                .ok_or_else(|| anyhow!("no file name in path"))?;
+            let file_name = file_name.to_string_lossy();
+            format!("{}_{:x}", file_name, fork_config.genesis.rollup_type_hash)
+        };
+        fs::create_dir_all(&dir)?;
+
+        let mut follow_export = FollowExport {
+            store_path: args.config.store.path.clone(),
+            rollup_config,
+            fork_config,
+            rollup_type_hash,
+            dir,
+            prefix,
+            next_block: args.from_block.unwrap_or(0),
+            to_block: args.to_block,
+            rotate_blocks: args.rotate_blocks,
+            rotate_size: args.rotate_size,
+            poll_interval: args.poll_interval,
+            segment: None,
+        };
+
+        // Resume from existing segments
+        if let Some(last_block) = follow_export.last_exported_block()? {
+            if args.from_block.is_some() && args.from_block != Some(last_block + 1) {
+                log::warn!(
+                    "found exported segments to block {}, ignore --from-block",
+                    last_block
+                );
+            }
+            follow_export.next_block = last_block + 1;
+        }
+        follow_export.remove_partial_segments()?;
+
+        Ok(follow_export)
+    }
+
+    pub fn execute(mut self) -> Result<()> {
+        log::info!(
+            "follow export from block {} to {}",
+            self.next_block,
+            self.dir.display()
+        );
+
+        while !self.poll()? {
+            std::thread::sleep(self.poll_interval);
+        }
+        Ok(())
+    }
+
+    /// Export newly finalized blocks once, return true if `to_block` is
+    /// reached.
+    pub fn poll(&mut self) -> Result<bool> {
+        // Readonly db doesn't see new writes, reopen to refresh.
+        let snap = StoreReadonly::open(&self.store_path, COLUMNS).context("open database")?;
+        if snap.get_chain_id()? != self.rollup_type_hash {
+            bail!("database rollup type hash mismatch");
+        }
+
+        let mut last_finalized = self.last_finalized_block(&snap)?;
+        if let Some(to_block) = self.to_block {
+            last_finalized = last_finalized.min(to_block);
+        }
+
+        while self.next_block <= last_finalized {
+            let exported = gw_utils::export_block::export_block(&snap, self.next_block)?;
+            let block_hash = exported.block_hash();
+
+            if self.segment.is_none() {
+                self.segment = Some(self.open_segment()?);
+            }
+            let current = self.segment.as_mut().expect("segment");
+            current.writer.write_block(exported)?;
+            current.last_block_hash = block_hash;
+            self.next_block += 1;
+
+            let rotate = current.writer.block_count() >= self.rotate_blocks
+                || self
+                    .rotate_size
+                    .map_or(false, |size| current.writer.size() >= size);
+            if rotate {
+                let current = self.segment.take().expect("segment");
+                self.finish_segment(current)?;
+            }
+        }
+
+        if let Some(to_block) = self.to_block {
+            if self.next_block > to_block {
+                if let Some(current) = self.segment.take() {
+                    self.finish_segment(current)?;
+                }
+                log::info!("follow export reached block {}", to_block);
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Blocks finalized for the last L1 confirmed block, fallback to last
+    /// valid tip block if it isn't available yet.
+    fn last_finalized_block(&self, snap: &StoreReadonly) -> Result<u64> {
+        let block = match snap.get_last_confirmed_block_number_hash() {
+            Some(nh) => {
+                let block_number: u64 = nh.number().unpack();
+                let block_hash: H256 = nh.block_hash().unpack();
+                snap.get_block(&block_hash)?
+                    .ok_or_else(|| anyhow!("last confirmed block {} not found", block_number))?
+            }
+            None => snap.get_last_valid_tip_block()?,
+        };
+        find_finalized_upper_bound(&self.rollup_config, &self.fork_config, snap, &block)
+    }
+
+    fn open_segment(&self) -> Result<Segment> {
+        let from_block = self.next_block;
+        let to_block = match self.to_block {
+            Some(to) => to.min(from_block + self.rotate_blocks - 1),
+            None => from_block + self.rotate_blocks - 1,
+        };
+        let blocks_per_segment = self
+            .rotate_blocks
+            .min(v2::DEFAULT_BLOCKS_PER_SEGMENT as u64) as u32;
+        let header = v2::Header {
+            rollup_type_hash: self.rollup_type_hash,
+            from_block,
+            to_block,
+            blocks_per_segment,
+        };
+
+        let partial_path = self
+            .dir
+            .join(format!("{}_{}{}", self.prefix, from_block, PARTIAL_SUFFIX));
+        let f = fs::OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .write(true)
+            .open(&partial_path)?;
+        let writer = v2::ExportedBlockWriter::new(BufWriter::new(f), header)?;
+
+        Ok(Segment {
+            writer,
+            partial_path,
+            from_block,
+            last_block_hash: H256::zero(),
+        })
+    }
+
+    fn finish_segment(&self, segment: Segment) -> Result<()> {
+        let Segment {
+            writer,
+            partial_path,
+            from_block,
+            last_block_hash,
+        } = segment;
+        let block_count = writer.block_count();
+        let to_block = from_block + block_count - 1;
+
+        let f = writer.finish_at_last_written()?;
+        f.into_inner()
+            .map_err(|err| anyhow!("flush segment {}", err))?
+            .sync_all()?;
+
+        let file_name = format!("{}_{}_{}", self.prefix, from_block, to_block);
+        let path = self.dir.join(&file_name);
+        fs::rename(&partial_path, &path)?;
+
+        let (size, checksum) = file_checksum(&path)?;
+        let manifest = serde_json::json!({
+            "file": file_name,
+            "format": "v2",
+            "rollup_type_hash": format!("0x{}", hex(&self.rollup_type_hash)),
+            "from_block": from_block,
+            "to_block": to_block,
+            "block_count": block_count,
+            "last_block_hash": format!("0x{}", hex(&last_block_hash)),
+            "size": size,
+            "blake2b": format!("0x{}", hex(&checksum)),
+        });
+
+        // Write manifest last, archival jobs pick up segments by manifest.
+        let manifest_path = manifest_path_of(&path);
+        let tmp_path = with_suffix(&manifest_path, PARTIAL_SUFFIX);
+        fs::write(&tmp_path, serde_json::to_vec_pretty(&manifest)?)?;
+        fs::rename(&tmp_path, &manifest_path)?;
+
+        log::info!(
+            "exported segment {} block {} to {}",
+            path.display(),
+            from_block,
+            to_block
+        );
+        Ok(())
+    }
+
+    fn last_exported_block(&self) -> Result<Option<u64>> {
+        let mut last_block = None;
+        for entry in fs::read_dir(&self.dir)? {
+            let file_name = entry?.file_name();
+            let file_name = file_name.to_string_lossy();
+            let range = file_name
+                .strip_prefix(&self.prefix)
+                .and_then(|s| s.strip_prefix('_'))
+                .and_then(|s| s.strip_suffix(MANIFEST_SUFFIX));
+            let to_block = range
+                .and_then(|r| r.split_once('_'))
+                .and_then(|(_from, to)| to.parse::<u64>().ok());
+            if to_block > last_block {
+                last_block = to_block;
+            }
+        }
+        Ok(last_block)
+    }
+
+    fn remove_partial_segments(&self) -> Result<()> {
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let file_name = path.file_name().map(|f| f.to_string_lossy().into_owned());
+            let is_partial = file_name.map_or(false, |f| {
+                f.starts_with(&self.prefix) && f.ends_with(PARTIAL_SUFFIX)
+            });
+            if is_partial {
+                log::info!("remove unfinished segment {}", path.display());
+                fs::remove_file(&path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn manifest_path_of(path: &Path) -> PathBuf {
+    with_suffix(path, MANIFEST_SUFFIX)
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut s: OsString = path.as_os_str().to_owned();
+    s.push(suffix);
+    s.into()
+}
+
+fn file_checksum(path: &Path) -> Result<(u64, [u8; 32])> {
+    let mut f = io::BufReader::new(fs::File::open(path)?);
+    let mut hasher = new_blake2b();
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut size = 0;
+    loop {
+        let n = f.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        size += n as u64;
+    }
+    let mut hash = [0u8; 32];
+    hasher.finalize(&mut hash);
+    Ok((size, hash))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
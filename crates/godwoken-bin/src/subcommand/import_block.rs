@@ -10,7 +10,8 @@ use gw_config::Config;
 use gw_store::{traits::chain_store::ChainStore, Store};
 use gw_types::{offchain::ExportedBlock, packed::NumberHash, prelude::*};
 use gw_utils::export_block::{
-    check_block_post_state, insert_bad_block_hashes, ExportedBlockReader,
+    check_bad_block_post_state, check_block_post_state, insert_bad_block_hashes,
+    ExportedBlockFileReader,
 };
 use indicatif::{ProgressBar, ProgressStyle};
 
@@ -123,7 +124,17 @@ impl ImportBlock {
     pub fn read_from_mol(&mut self) -> Result<()> {
         let store = self.chain.store();
         let f = fs::File::open(&self.source)?;
-        let mut block_reader = ExportedBlockReader::new(BufReader::new(f));
+        let mut block_reader = ExportedBlockFileReader::open(BufReader::new(f))?;
+        if let Some(rollup_type_hash) = block_reader.rollup_type_hash() {
+            let chain_id = store.get_chain_id()?;
+            if rollup_type_hash != chain_id {
+                bail!(
+                    "rollup type hash mismatch, file {}, db {}",
+                    rollup_type_hash.pack(),
+                    chain_id.pack()
+                );
+            }
+        }
 
         // Seek new block
         let snap = store.get_snapshot();
@@ -169,7 +180,11 @@ impl ImportBlock {
         let read_in_background = std::thread::spawn(move || {
             for maybe_new_block in block_reader {
                 match maybe_new_block.as_ref() {
-                    Err(_) => return,
+                    // Forward read error, e.g. checksum mismatch, and stop.
+                    Err(_) => {
+                        let _ = tx.send(maybe_new_block);
+                        return;
+                    }
                     Ok((block, _size))
                         if to_block.is_some() && Some(block.block_number()) > to_block =>
                     {
@@ -232,6 +247,10 @@ fn insert_block(
     exported: ExportedBlock,
     last_submitted_block: &mut Option<u64>,
 ) -> Result<()> {
+    if exported.is_bad_block() {
+        return insert_bad_block(chain, exported);
+    }
+
     let mut tx_db = chain.store().begin_transaction_skip_concurrency_control();
     let block_number = exported.block_number();
 
@@ -259,6 +278,47 @@ fn insert_block(
     };
     chain.calculate_and_store_finalized_custodians(&mut tx_db, block_number)?;
 
+    if let Some(pending_reverted_block_hashes) = exported.pending_reverted_block_hashes {
+        insert_bad_block_hashes(&mut tx_db, pending_reverted_block_hashes)?;
+    }
+
+    tx_db.commit()?;
+
+    Ok(())
+}
+
+fn insert_bad_block(chain: &mut Chain, exported: ExportedBlock) -> Result<()> {
+    let mut tx_db = chain.store().begin_transaction_skip_concurrency_control();
+    let block_number = exported.block_number();
+    let block_hash = exported.block_hash();
+
+    let tip_block_hash = tx_db.get_tip_block_hash()?;
+    if exported.parent_block_hash() != tip_block_hash {
+        bail!("bad block parent isn't tip {}", tip_block_hash.pack());
+    }
+
+    if let Some(bad_block_hashes_vec) = exported.bad_block_hashes {
+        insert_bad_block_hashes(&mut tx_db, bad_block_hashes_vec)?;
+    }
+
+    tx_db.insert_bad_block(
+        &exported.block,
+        &exported.post_global_state,
+        &exported.deposit_info_vec,
+        HashSet::from_iter(exported.deposit_asset_scripts),
+        &exported.withdrawals,
+    )?;
+    let challenge_target = exported.bad_block.and_then(|b| b.challenge_target);
+    if let Some(target) = challenge_target {
+        tx_db.set_bad_block_challenge_target(&block_hash, &target)?;
+    }
+
+    check_bad_block_post_state(&tx_db, block_number, &exported.post_global_state)?;
+
+    if let Some(pending_reverted_block_hashes) = exported.pending_reverted_block_hashes {
+        insert_bad_block_hashes(&mut tx_db, pending_reverted_block_hashes)?;
+    }
+
     tx_db.commit()?;
 
     Ok(())
@@ -19,7 +19,7 @@ impl Migration for SMTTrieMigration {
         let mut store = Store::new(db);
 
         log::info!("deleting old SMT branches");
-        let db = store.as_inner_mut();
+        let db = store.as_inner_mut()?;
         db.clear_cf(COLUMN_ACCOUNT_SMT_BRANCH)
             .context("clear COLUMN_ACCOUNT_SMT_BRANCH")?;
 
@@ -36,13 +36,13 @@ impl Migration for SMTTrieMigration {
         log::info!("migrating state smt");
         {
             let len = store
-                .as_inner()
+                .as_inner()?
                 .get_int_property(COLUMN_ACCOUNT_SMT_LEAF, "rocksdb.estimate-num-keys")
                 .context("get estimate-num-keys of account smt leaves")?;
             let mut tx = store.begin_transaction_skip_concurrency_control();
             let mut state_smt = tx.state_smt().context("state_smt")?;
             for (i, (k, v)) in store
-                .as_inner()
+                .as_inner()?
                 .iter(COLUMN_ACCOUNT_SMT_LEAF, Direction::Forward)
                 .enumerate()
                 .progress_count(len)
@@ -75,13 +75,13 @@ impl Migration for SMTTrieMigration {
         log::info!("migrating block smt");
         {
             let len = store
-                .as_inner()
+                .as_inner()?
                 .get_int_property(COLUMN_BLOCK_SMT_LEAF, "rocksdb.estimate-num-keys")
                 .context("get estimate-num-keys of block smt leaves")?;
             let mut tx = store.begin_transaction_skip_concurrency_control();
             let mut block_smt = tx.block_smt().context("block_smt")?;
             for (i, (k, v)) in store
-                .as_inner()
+                .as_inner()?
                 .iter(COLUMN_BLOCK_SMT_LEAF, Direction::Forward)
                 .enumerate()
                 .progress_count(len)
@@ -109,7 +109,7 @@ impl Migration for SMTTrieMigration {
             let mut tx = store.begin_transaction_skip_concurrency_control();
             let mut reverted_block_smt = tx.reverted_block_smt().context("reverted_block_smt")?;
             for (k, v) in store
-                .as_inner()
+                .as_inner()?
                 .iter(COLUMN_REVERTED_BLOCK_SMT_LEAF, Direction::Forward)
             {
                 reverted_block_smt
@@ -125,11 +125,11 @@ impl Migration for SMTTrieMigration {
         }
 
         store
-            .as_inner()
+            .as_inner()?
             .delete(COLUMN_ACCOUNT_SMT_BRANCH, b"migrating")?;
 
         log::info!("SMTTrieMigration completed");
-        Ok(store.into_inner())
+        store.into_inner()
     }
     fn version(&self) -> &str {
         SMTTrieMigrationPlaceHolder.version()
@@ -1,5 +1,9 @@
+pub mod challenge_audit;
+pub mod custodian_audit;
+pub mod db;
 pub mod db_block_validator;
 pub mod export_block;
+pub mod follow_export;
 pub mod import_block;
 pub mod migrate;
 pub mod peer_id;
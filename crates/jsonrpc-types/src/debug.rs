@@ -39,6 +39,27 @@ pub struct DebugRunResult {
     pub debug_log: Vec<String>,
     pub execution_time_ms: u32,
     pub write_mem_smt_time_ms: u32,
+    // cycles profile, only returned when profiling is requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<DebugProfile>,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct DebugProfile {
+    // folded stacks of the backend execution cycles, `caller;callee cycles` per line
+    pub folded: Vec<String>,
+    // virtual cycles of syscalls, sorted by cycles in descending order
+    pub syscalls: Vec<DebugSyscallProfile>,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct DebugSyscallProfile {
+    pub name: String,
+    pub code: Uint64,
+    pub count: Uint64,
+    pub cycles: Uint64,
 }
 
 impl TryFrom<offchain::RunResult> for DebugRunResult {
@@ -73,6 +94,7 @@ impl TryFrom<offchain::RunResult> for DebugRunResult {
                 .collect(),
             execution_time_ms: 0,
             write_mem_smt_time_ms: 0,
+            profile: None,
         })
     }
 }
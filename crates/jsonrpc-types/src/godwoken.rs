@@ -1,6 +1,8 @@
 use anyhow::{anyhow, Context, Error as JsonError};
 use ckb_fixed_hash::{H160, H256};
-use ckb_jsonrpc_types::{JsonBytes, Script, Uint128, Uint32, Uint64};
+use ckb_jsonrpc_types::{
+    CellDep, JsonBytes, OutPoint, Script, Transaction, Uint128, Uint32, Uint64,
+};
 use gw_types::core::Timepoint;
 use gw_types::{bytes::Bytes, offchain, packed, prelude::*};
 use serde::{Deserialize, Serialize};
@@ -554,6 +556,61 @@ pub struct L2WithdrawalCommittedInfo {
     pub withdrawal_index: Uint32,
 }
 
+/// An L1 deposit cell out point, or the hash of the L1 transaction that
+/// creates the deposit cell.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(untagged)]
+pub enum DepositLookup {
+    OutPoint(OutPoint),
+    TxHash(H256),
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum DepositStatus {
+    /// The deposit cell is live on L1 and is not collected yet.
+    Pending,
+    /// Collected into the mem block.
+    MemBlock,
+    /// Included in a block that is not submitted to L1 yet.
+    Local,
+    Submitted,
+    Confirmed,
+    Finalized,
+    /// The deposit cell is refunded to the owner after cancel timeout.
+    Refunded,
+}
+
+impl Default for DepositStatus {
+    fn default() -> Self {
+        Self::Pending
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct DepositWithStatus {
+    pub out_point: OutPoint,
+    pub status: DepositStatus,
+    /// Not available for refunded deposits.
+    pub request: Option<DepositRequest>,
+    /// The recipient account, if it is created.
+    pub account_id: Option<Uint32>,
+    pub l2_committed_info: Option<L2DepositCommittedInfo>,
+    /// The custodian cell created for the deposit by the block submission
+    /// transaction, if it's still live. It may be merged into other
+    /// custodian cells after finalized.
+    pub custodian_out_point: Option<OutPoint>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct L2DepositCommittedInfo {
+    pub block_number: Uint64,
+    pub block_hash: H256,
+    pub deposit_index: Uint32,
+}
+
 #[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
 #[serde(rename_all = "snake_case")]
 pub struct SubmitTransactions {
@@ -953,6 +1010,8 @@ pub struct L2BlockCommittedInfo {
 pub enum AllowedEoaType {
     Unknown,
     Eth,
+    WebAuthn,
+    Multisig,
 }
 
 impl From<AllowedEoaType> for packed::Byte {
@@ -960,6 +1019,8 @@ impl From<AllowedEoaType> for packed::Byte {
         match json {
             AllowedEoaType::Unknown => packed::Byte::new(0),
             AllowedEoaType::Eth => packed::Byte::new(1),
+            AllowedEoaType::WebAuthn => packed::Byte::new(2),
+            AllowedEoaType::Multisig => packed::Byte::new(3),
         }
     }
 }
@@ -971,6 +1032,8 @@ impl TryFrom<packed::Byte> for AllowedEoaType {
         match u8::from(v) {
             0 => Ok(AllowedEoaType::Unknown),
             1 => Ok(AllowedEoaType::Eth),
+            2 => Ok(AllowedEoaType::WebAuthn),
+            3 => Ok(AllowedEoaType::Multisig),
             _ => Err(anyhow!("invalid allowed eoa type {}", v)),
         }
     }
@@ -984,6 +1047,7 @@ pub enum AllowedContractType {
     Sudt,
     Polyjuice,
     EthAddrReg,
+    ScriptHashAddrReg,
 }
 
 impl From<AllowedContractType> for packed::Byte {
@@ -994,6 +1058,7 @@ impl From<AllowedContractType> for packed::Byte {
             AllowedContractType::Sudt => packed::Byte::new(2),
             AllowedContractType::Polyjuice => packed::Byte::new(3),
             AllowedContractType::EthAddrReg => packed::Byte::new(4),
+            AllowedContractType::ScriptHashAddrReg => packed::Byte::new(5),
         }
     }
 }
@@ -1008,6 +1073,7 @@ impl TryFrom<packed::Byte> for AllowedContractType {
             2 => Ok(AllowedContractType::Sudt),
             3 => Ok(AllowedContractType::Polyjuice),
             4 => Ok(AllowedContractType::EthAddrReg),
+            5 => Ok(AllowedContractType::ScriptHashAddrReg),
             _ => Err(anyhow!("invalid allowed contract type {}", v)),
         }
     }
@@ -1207,6 +1273,9 @@ pub struct NodeInfo {
     // field instead of saying it's null.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub gasless_tx_support: Option<GaslessTxSupportConfig>,
+    /// Backend forks above the tip block, with the backends changed by each fork.
+    #[serde(default)]
+    pub staged_backend_forks: Vec<BackendForkInfo>,
 }
 
 #[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
@@ -1215,6 +1284,7 @@ pub enum NodeMode {
     FullNode,
     Test,
     ReadOnly,
+    Watchtower,
 }
 
 impl Default for NodeMode {
@@ -1231,6 +1301,13 @@ pub struct BackendInfo {
     pub backend_type: BackendType,
 }
 
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct BackendForkInfo {
+    pub fork_height: Uint64,
+    pub backends: Vec<BackendInfo>,
+}
+
 #[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum BackendType {
@@ -1344,6 +1421,95 @@ impl From<offchain::ErrorTxReceipt> for ErrorTxReceipt {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct SessionKey {
+    /// Eth address of the session key.
+    pub pubkey_hash: H160,
+    /// The session key is expired since this block.
+    pub expiry_block: Uint64,
+    /// Script hashes of contracts which the session key is allowed to call.
+    pub allowed_targets: Vec<H256>,
+    /// Max cost (transfer value + fee) of a transaction signed by the session key.
+    pub spending_cap: Uint128,
+}
+
+impl From<packed::SessionKey> for SessionKey {
+    fn from(session_key: packed::SessionKey) -> Self {
+        let pubkey_hash: [u8; 20] = session_key
+            .pubkey_hash()
+            .as_slice()
+            .try_into()
+            .expect("pubkey hash");
+        let expiry_block: u64 = session_key.expiry_block().unpack();
+        let spending_cap: u128 = session_key.spending_cap().unpack();
+        SessionKey {
+            pubkey_hash: H160(pubkey_hash),
+            expiry_block: expiry_block.into(),
+            allowed_targets: session_key
+                .allowed_targets()
+                .into_iter()
+                .map(|target| H256(target.unpack()))
+                .collect(),
+            spending_cap: spending_cap.into(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct P2PPeer {
+    pub session_id: Uint64,
+    pub address: String,
+    pub peer_id: Option<String>,
+    /// Whether the peer connected to us.
+    pub inbound: bool,
+    pub uptime_secs: Uint64,
+    pub protocols: Vec<P2PProtocolTraffic>,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct P2PProtocolTraffic {
+    pub protocol: String,
+    pub received_messages: Uint64,
+    pub received_bytes: Uint64,
+    pub sent_messages: Uint64,
+    pub sent_bytes: Uint64,
+}
+
+/// A liquidity provider offer to buy pending withdrawals for fast withdrawal.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct FastWithdrawalOffer {
+    /// The liquidity provider lock, it pays the withdrawal owner and becomes
+    /// the new owner of the withdrawal cell.
+    pub lock: Script,
+    pub lock_dep: CellDep,
+    /// Fee in parts per 10000 of the withdrawal capacity.
+    pub fee_rate: Uint32,
+    pub max_capacity: Uint64,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct FastWithdrawalQuote {
+    pub lp_lock_hash: H256,
+    pub capacity: Uint64,
+    pub fee: Uint64,
+    /// Paid to the withdrawal owner, i.e. `capacity - fee`.
+    pub payment: Uint64,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct FastWithdrawalMatch {
+    pub quote: FastWithdrawalQuote,
+    /// The transfer transaction with placeholder signatures, to be signed by
+    /// the withdrawal owner and the liquidity provider.
+    pub transaction: Transaction,
+}
+
 #[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
 #[serde(rename_all = "snake_case")]
 pub struct SUDTFeeConfig {
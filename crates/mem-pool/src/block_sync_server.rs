@@ -1,26 +1,32 @@
 //! P2P sync server for local/submitted/confirmed Blocks.
 
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, HashSet},
     sync::{Arc, Mutex},
 };
 
+use anyhow::{Context, Result};
 use bytes::Bytes;
 use futures::{StreamExt, TryStreamExt};
 use gw_config::SyncServerConfig;
-use gw_p2p_network::{FnSpawn, P2P_SYNC_PROTOCOL, P2P_SYNC_PROTOCOL_NAME};
+use gw_p2p_network::{
+    peers::Peers, FnSpawn, P2P_BLOCK_RANGE_PROTOCOL, P2P_BLOCK_RANGE_PROTOCOL_NAME,
+    P2P_SYNC_PROTOCOL, P2P_SYNC_PROTOCOL_NAME, P2P_SYNC_PROTOCOL_VERSION,
+};
+use gw_store::{traits::chain_store::ChainStore, Store};
 use gw_telemetry::traits::{OpenTelemetrySpanExt, TraceContextExt};
 use gw_types::{
     h256::*,
     packed::{
-        self, BlockSync, BlockSyncUnion, Confirmed, Found, L2Transaction, LocalBlock, NextMemBlock,
-        P2PSyncRequest, P2PSyncRequestReader, P2PSyncResponse, PushTransaction, Revert, Submitted,
-        TryAgain,
+        self, BlockRange, BlockRangeRequest, BlockRangeRequestReader, BlockSync, BlockSyncUnion,
+        Confirmed, FinalizedBlock, Found, L2Transaction, LocalBlock, NextMemBlock, P2PSyncRequest,
+        P2PSyncRequestReader, P2PSyncResponse, PushTransaction, Revert, ScheduledBackendFork,
+        ScriptVec, Submitted, TryAgain,
     },
     prelude::*,
 };
-use gw_utils::compression::StreamEncoder;
-use tentacle::{builder::MetaBuilder, service::ProtocolMeta};
+use gw_utils::{compression::StreamEncoder, find_finalized_upper_bound, RollupContext};
+use tentacle::{builder::MetaBuilder, service::ProtocolMeta, traits::ProtocolSpawn};
 use tokio::sync::broadcast::{channel, Receiver, Sender};
 
 #[derive(Default)]
@@ -34,20 +40,40 @@ pub struct BlockSyncServerState {
     buffer: BTreeMap<u64, BlockMessages>,
     tx: Sender<BlockSync>,
     buffer_capacity: u64,
+    // For serving finalized blocks that are no longer in the buffer.
+    store: Store,
+    rollup_context: RollupContext,
+    max_block_range: u32,
+    // Number of relays between the block producer and this node, 0 for the
+    // block producer.
+    hops: u32,
+    // Backend forks scheduled at runtime, sent to every new subscriber.
+    backend_forks: Vec<BlockSync>,
 }
 
 impl BlockSyncServerState {
-    pub fn new(config: &SyncServerConfig) -> Self {
+    pub fn new(config: &SyncServerConfig, store: Store, rollup_context: RollupContext) -> Self {
         let (tx, _) = channel(config.broadcast_channel_capacity);
         Self {
             buffer: Default::default(),
             tx,
             buffer_capacity: config.buffer_capacity,
+            store,
+            rollup_context,
+            max_block_range: config.max_block_range,
+            hops: 0,
+            backend_forks: Vec::new(),
         }
     }
 
+    /// Set by relays from the hops of received messages.
+    pub fn set_hops(&mut self, hops: u32) {
+        self.hops = hops;
+    }
+
     pub fn publish_local_block(&mut self, local_block: LocalBlock) {
         log::info!("publish local block");
+        let local_block = local_block.as_builder().hops(self.hops.pack()).build();
         let reader = local_block.as_reader();
         let raw = reader.block().raw();
         let number = raw.number().unpack();
@@ -103,6 +129,7 @@ impl BlockSyncServerState {
         let msg = PushTransaction::new_builder()
             .trace_id(packed::Byte16::from_slice(&span_context.trace_id().to_bytes()).unwrap())
             .span_id(packed::Byte8::from_slice(&span_context.span_id().to_bytes()).unwrap())
+            .hops(self.hops.pack())
             .transaction(tx)
             .build();
         let msg = BlockSync::new_builder().set(msg).build();
@@ -135,6 +162,19 @@ impl BlockSyncServerState {
         let _ = self.tx.send(msg);
     }
 
+    pub fn publish_backend_fork(&mut self, fork: ScheduledBackendFork) {
+        log::info!("publish backend fork");
+        let msg = BlockSync::new_builder().set(fork).build();
+        if !self
+            .backend_forks
+            .iter()
+            .any(|m| m.as_slice() == msg.as_slice())
+        {
+            self.backend_forks.push(msg.clone());
+        }
+        let _ = self.tx.send(msg);
+    }
+
     fn get_and_subscribe(
         &self,
         after: P2PSyncRequest,
@@ -143,9 +183,14 @@ impl BlockSyncServerState {
         if let Some(msgs) = self.buffer.get(&number) {
             if msgs.hash.as_slice() == after.block_hash().as_slice() {
                 let msgs = self
-                    .buffer
-                    .range(number + 1..)
-                    .flat_map(|(_, msgs)| msgs.messages.iter().cloned())
+                    .backend_forks
+                    .iter()
+                    .cloned()
+                    .chain(
+                        self.buffer
+                            .range(number + 1..)
+                            .flat_map(|(_, msgs)| msgs.messages.iter().cloned()),
+                    )
                     .collect();
                 return Ok((msgs, self.tx.subscribe()));
             }
@@ -154,11 +199,162 @@ impl BlockSyncServerState {
     }
 }
 
-pub fn block_sync_server_protocol(publisher: Arc<Mutex<BlockSyncServerState>>) -> ProtocolMeta {
-    let spawn = FnSpawn(move |context, control, mut read_part| {
+/// Read finalized blocks in the requested range from the store.
+///
+/// Blocks are finalized for the last confirmed block, which also covers
+/// blocks using timestamps as finality timepoints. The genesis block is built
+/// from the config by every node and isn't served.
+fn get_block_range(
+    store: &Store,
+    rollup_context: &RollupContext,
+    max_block_range: u32,
+    request: BlockRangeRequest,
+) -> Result<BlockRange> {
+    let start: u64 = request.start_block_number().unpack();
+    if start == 0 {
+        return Ok(BlockRange::default());
+    }
+    let snap = store.get_snapshot();
+    let last_confirmed_hash: H256 = snap
+        .get_last_confirmed_block_number_hash()
+        .context("last confirmed")?
+        .block_hash()
+        .unpack();
+    let last_confirmed = snap
+        .get_block(&last_confirmed_hash)?
+        .context("get last confirmed block")?;
+    let finalized = find_finalized_upper_bound(
+        &rollup_context.rollup_config,
+        &rollup_context.fork_config,
+        &snap,
+        &last_confirmed,
+    )?;
+    let count: u32 = request.count().unpack();
+    let end = start
+        .saturating_add(count.min(max_block_range).into())
+        .min(finalized + 1);
+    let blocks = (start..end).map(|b| {
+        let submit_tx_hash = snap
+            .get_block_submit_tx_hash(b)
+            .context("get submit tx hash")?;
+        let local_block = local_block_from_store(&snap, b)?;
+        let bad_block_hashes = get_bad_block_hashes(&snap, &local_block)?;
+        let local_block = local_block
+            .as_builder()
+            .bad_block_hashes(bad_block_hashes.pack())
+            .build();
+        Ok(FinalizedBlock::new_builder()
+            .local_block(local_block)
+            .submit_tx_hash(submit_tx_hash.pack())
+            .build())
+    });
+    let blocks = blocks.collect::<Result<Vec<_>>>()?;
+    Ok(BlockRange::new_builder().blocks(blocks.pack()).build())
+}
+
+/// Build the `LocalBlock` message of a valid block in the store.
+pub fn local_block_from_store(snap: &impl ChainStore, b: u64) -> Result<LocalBlock> {
+    let block_hash = snap
+        .get_block_hash_by_number(b)?
+        .context("get block hash")?;
+    let block = snap.get_block(&block_hash)?.context("get block")?;
+    let global_state = snap
+        .get_block_post_global_state(&block_hash)?
+        .context("get block post global state")?;
+    let deposit_info_vec = snap
+        .get_block_deposit_info_vec(b)
+        .context("get block deposit info vec")?;
+    let deposit_asset_scripts = {
+        let reader = deposit_info_vec.as_reader();
+        let asset_hashes: HashSet<H256> = reader
+            .iter()
+            .filter_map(|r| {
+                let h: H256 = r.request().sudt_script_hash().unpack();
+                if h.is_zero() {
+                    None
+                } else {
+                    Some(h)
+                }
+            })
+            .collect();
+        let asset_scripts = asset_hashes.into_iter().map(|h| {
+            snap.get_asset_script(&h)?
+                .with_context(|| format!("block {} asset script {} not found", b, h.pack()))
+        });
+        asset_scripts.collect::<Result<Vec<_>>>()?
+    };
+    let withdrawals = {
+        let reqs = block.as_reader().withdrawals();
+        let extra_reqs = reqs.iter().map(|w| {
+            let h = w.hash();
+            snap.get_withdrawal(&h)?
+                .with_context(|| format!("block {} withdrawal {} not found", b, h.pack()))
+        });
+        extra_reqs.collect::<Result<Vec<_>>>()?
+    };
+    Ok(LocalBlock::new_builder()
+        .block(block)
+        .post_global_state(global_state)
+        .deposit_info_vec(deposit_info_vec)
+        .deposit_asset_scripts(ScriptVec::new_builder().set(deposit_asset_scripts).build())
+        .withdrawals(withdrawals.pack())
+        .build())
+}
+
+/// Block hashes reverted between the parent block and this block, which must
+/// be inserted into the reverted block smt before the block is applied.
+fn get_bad_block_hashes(
+    snap: &impl ChainStore,
+    local_block: &LocalBlock,
+) -> Result<Option<Vec<Vec<H256>>>> {
+    let parent_block_hash: H256 = local_block.block().raw().parent_block_hash().unpack();
+    let parent_reverted_block_root: H256 =
+        match snap.get_block_post_global_state(&parent_block_hash)? {
+            Some(global_state) => global_state.reverted_block_root().unpack(),
+            // Genesis.
+            None => return Ok(None),
+        };
+    let mut reverted_block_root: H256 = local_block
+        .post_global_state()
+        .reverted_block_root()
+        .unpack();
+    if reverted_block_root == parent_reverted_block_root {
+        return Ok(None);
+    }
+    let mut bad_block_hashes = Vec::new();
+    while reverted_block_root != parent_reverted_block_root {
+        let reverted = snap
+            .get_reverted_block_hashes_by_root(&reverted_block_root)?
+            .context("get reverted block hashes")?;
+        bad_block_hashes.push(reverted.block_hashes);
+        reverted_block_root = reverted.prev_smt_root;
+    }
+    bad_block_hashes.reverse();
+    Ok(Some(bad_block_hashes))
+}
+
+pub fn block_sync_server_protocol(
+    publisher: Arc<Mutex<BlockSyncServerState>>,
+    peers: Arc<Peers>,
+) -> ProtocolMeta {
+    MetaBuilder::new()
+        .name(|_| P2P_SYNC_PROTOCOL_NAME.into())
+        .id(P2P_SYNC_PROTOCOL)
+        .support_versions(vec![P2P_SYNC_PROTOCOL_VERSION.into()])
+        .protocol_spawn(block_sync_server_spawn(publisher, peers))
+        .build()
+}
+
+pub fn block_sync_server_spawn(
+    publisher: Arc<Mutex<BlockSyncServerState>>,
+    peers: Arc<Peers>,
+) -> impl ProtocolSpawn + Send + Sync + 'static {
+    FnSpawn(move |context, control, mut read_part| {
         let publisher = publisher.clone();
+        let peers = peers.clone();
         let control = control.clone();
         let session_id = context.id;
+        peers.open_protocol(session_id, P2P_SYNC_PROTOCOL);
         tokio::spawn(async move {
             // Compress messages.
             //
@@ -168,13 +364,15 @@ pub fn block_sync_server_protocol(publisher: Arc<Mutex<BlockSyncServerState>>) -
             // will be compressed to just a few bytes.
             let mut encoder = StreamEncoder::new(3).expect("create StreamEncoder");
             'outer: while let Some(msg) = read_part.try_next().await? {
+                peers.record_received(session_id, P2P_SYNC_PROTOCOL, msg.len());
                 P2PSyncRequestReader::from_slice(msg.as_ref())?;
-                let request = P2PSyncRequest::new_unchecked(msg);
                 let mut send = |x: Bytes| {
                     let compressed: Bytes = encoder.encode(&x).expect("compress").into();
                     log::debug!("compression: {} -> {}", x.len(), compressed.len());
+                    peers.record_sent(session_id, P2P_SYNC_PROTOCOL, compressed.len());
                     control.send_message_to(session_id, P2P_SYNC_PROTOCOL, compressed)
                 };
+                let request = P2PSyncRequest::new_unchecked(msg);
                 let result = publisher.lock().unwrap().get_and_subscribe(request);
                 match result {
                     Ok((msgs, mut receiver)) => {
@@ -212,10 +410,64 @@ pub fn block_sync_server_protocol(publisher: Arc<Mutex<BlockSyncServerState>>) -
             }
             anyhow::Ok(())
         });
-    });
+    })
+}
+
+pub fn block_range_server_protocol(
+    publisher: Arc<Mutex<BlockSyncServerState>>,
+    peers: Arc<Peers>,
+) -> ProtocolMeta {
     MetaBuilder::new()
-        .name(|_| P2P_SYNC_PROTOCOL_NAME.into())
-        .id(P2P_SYNC_PROTOCOL)
-        .protocol_spawn(spawn)
+        .name(|_| P2P_BLOCK_RANGE_PROTOCOL_NAME.into())
+        .id(P2P_BLOCK_RANGE_PROTOCOL)
+        .protocol_spawn(block_range_server_spawn(publisher, peers))
         .build()
 }
+
+/// Answer each `BlockRangeRequest` with a `BlockRange` of finalized blocks.
+pub fn block_range_server_spawn(
+    publisher: Arc<Mutex<BlockSyncServerState>>,
+    peers: Arc<Peers>,
+) -> impl ProtocolSpawn + Send + Sync + 'static {
+    FnSpawn(move |context, control, mut read_part| {
+        let publisher = publisher.clone();
+        let peers = peers.clone();
+        let control = control.clone();
+        let session_id = context.id;
+        peers.open_protocol(session_id, P2P_BLOCK_RANGE_PROTOCOL);
+        tokio::spawn(async move {
+            // Blocks in adjacent ranges share a lot of content, so we keep
+            // using the same compression context in one session.
+            let mut encoder = StreamEncoder::new(3).expect("create StreamEncoder");
+            while let Some(msg) = read_part.try_next().await? {
+                peers.record_received(session_id, P2P_BLOCK_RANGE_PROTOCOL, msg.len());
+                BlockRangeRequestReader::from_slice(msg.as_ref())?;
+                let request = BlockRangeRequest::new_unchecked(msg);
+                let (store, rollup_context, max_block_range) = {
+                    let publisher = publisher.lock().unwrap();
+                    (
+                        publisher.store.clone(),
+                        publisher.rollup_context.clone(),
+                        publisher.max_block_range,
+                    )
+                };
+                let range = tokio::task::spawn_blocking(move || {
+                    get_block_range(&store, &rollup_context, max_block_range, request)
+                })
+                .await?;
+                // An empty range makes the peer sync the remaining blocks
+                // from L1, instead of closing the session.
+                let range = range.unwrap_or_else(|err| {
+                    log::warn!("get block range, session: {}: {:#}", session_id, err);
+                    BlockRange::default()
+                });
+                let compressed: Bytes = encoder.encode(range.as_slice()).expect("compress").into();
+                peers.record_sent(session_id, P2P_BLOCK_RANGE_PROTOCOL, compressed.len());
+                control
+                    .send_message_to(session_id, P2P_BLOCK_RANGE_PROTOCOL, compressed)
+                    .await?;
+            }
+            anyhow::Ok(())
+        });
+    })
+}
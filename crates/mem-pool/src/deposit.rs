@@ -1,5 +1,7 @@
 use anyhow::{anyhow, Result};
-use gw_common::{registry::context::RegistryContext, state::State};
+use gw_common::{
+    builtins::SCRIPT_HASH_REGISTRY_ACCOUNT_ID, registry::context::RegistryContext, state::State,
+};
 use gw_config::DepositTimeoutConfig;
 use gw_store::state::MemStateDB;
 use gw_types::core::Timepoint;
@@ -186,19 +188,18 @@ fn check_deposit_cell(
             ));
         }
 
+        // The builtin contracts can't handle script hash addresses yet.
+        let registry_id: u32 = cell.request.registry_id().unpack();
+        if registry_id == SCRIPT_HASH_REGISTRY_ACCOUNT_ID {
+            return Err(anyhow!(
+                "Invalid deposit registry id: script hash registry isn't supported yet"
+            ));
+        }
+
         // try extract address from deposit
-        let registry_ctx = RegistryContext::new(
-            ctx.rollup_config
-                .allowed_eoa_type_hashes()
-                .into_iter()
-                .collect(),
-        );
+        let registry_ctx = RegistryContext::from_rollup_config(&ctx.rollup_config);
 
-        match registry_ctx.extract_registry_address_from_deposit(
-            cell.request.registry_id().unpack(),
-            &script.code_hash(),
-            &script.args().raw_data(),
-        ) {
+        match registry_ctx.extract_registry_address_from_deposit(registry_id, &script) {
             Ok(reg_addr) => {
                 //Registry address could be duplicated with a contract account.
                 if let Some(script_hash) = state.get_script_hash_by_registry_address(&reg_addr)? {
@@ -136,6 +136,7 @@ impl FeeEntry {
     pub fn from_tx(
         tx: L2Transaction,
         gasless_tx_support_config: Option<&GaslessTxSupportConfig>,
+        sponsored_gas_price: Option<u128>,
         fee_config: &FeeConfig,
         backend_type: BackendType,
         order: usize,
@@ -143,6 +144,7 @@ impl FeeEntry {
         let raw_l2tx = tx.raw();
         let fee = parse_l2tx_fee_rate(
             gasless_tx_support_config,
+            sponsored_gas_price,
             fee_config,
             &raw_l2tx,
             backend_type,
@@ -203,9 +205,11 @@ fn parse_withdraw_fee_rate(
     })
 }
 
-/// parse tx fee rate
+/// parse tx fee rate, the fee of a sponsored tx is paid by the sponsor at
+/// `sponsored_gas_price`
 fn parse_l2tx_fee_rate(
     gasless_tx_support_config: Option<&GaslessTxSupportConfig>,
+    sponsored_gas_price: Option<u128>,
     fee_config: &FeeConfig,
     raw_l2tx: &gw_types::packed::RawL2Transaction,
     backend_type: BackendType,
@@ -217,6 +221,10 @@ fn parse_l2tx_fee_rate(
             let fee = match meta_args.to_enum() {
                 MetaContractArgsUnion::CreateAccount(args) => args.fee().amount().unpack(),
                 MetaContractArgsUnion::BatchCreateEthAccounts(args) => args.fee().amount().unpack(),
+                MetaContractArgsUnion::RegisterSessionKey(args) => args.fee().amount().unpack(),
+                MetaContractArgsUnion::RevokeSessionKey(args) => args.fee().amount().unpack(),
+                MetaContractArgsUnion::SetSponsorPolicy(args) => args.fee().amount().unpack(),
+                MetaContractArgsUnion::RemoveSponsorPolicy(args) => args.fee().amount().unpack(),
             };
             let cycles_limit: u64 = fee_config.meta_cycles_limit;
 
@@ -253,6 +261,8 @@ fn parse_l2tx_fee_rate(
             // Note: Polyjuice use CKB_SUDT to pay fee by default
             let (gas_limit, gas_price) = if poly_args.gas_price() > 0 {
                 (poly_args.gas(), poly_args.gas_price())
+            } else if let Some(gas_price) = sponsored_gas_price {
+                (poly_args.gas(), gas_price)
             } else {
                 // Check possible gasless tx.
                 if is_gasless_tx(gasless_tx_support_config, &poly_args) {
@@ -26,6 +26,7 @@ use gw_config::{MemBlockConfig, MemPoolConfig, NodeMode, SyscallCyclesConfig};
 use gw_generator::{
     error::TransactionError,
     generator::CyclesPool,
+    session_key::SessionKeySpend,
     traits::StateExt,
     verification::{transaction::TransactionVerifier, withdrawal::WithdrawalVerifier},
     Generator,
@@ -79,6 +80,20 @@ impl OutputParam {
     }
 }
 
+/// The mem pool state a tx signature was verified against.
+///
+/// The signature check depends on the sender's lock script and registry
+/// address, its session keys and their spent, and the block number. The lock
+/// script never changes, and the others only change with a tx of the sender,
+/// which bumps its nonce, or with a new mem block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerifiedSignature {
+    pub sender_nonce: u32,
+    pub block_number: u64,
+    /// The spend of the session key which signed the tx.
+    pub session_key_spend: Option<SessionKeySpend>,
+}
+
 /// MemPool
 pub struct MemPool {
     /// store
@@ -103,6 +118,7 @@ pub struct MemPool {
     polyjuice_contract_creator_allowlist: Option<PolyjuiceContractCreatorAllowList>,
     sudt_proxy_account_allowlist: SUDTProxyAccountAllowlist,
     sync_server: Option<Arc<std::sync::Mutex<BlockSyncServerState>>>,
+    node_mode: NodeMode,
     mem_block_config: MemBlockConfig,
     /// Cycles Pool
     cycles_pool: CyclesPool,
@@ -211,6 +227,7 @@ impl MemPool {
             pending_restored_tx_hashes,
             mem_pool_state,
             sync_server,
+            node_mode,
             mem_block_config: config.mem_block,
             cycles_pool,
             account_creator,
@@ -314,11 +331,33 @@ impl MemPool {
     /// Push a layer2 tx into pool
     #[instrument(skip_all)]
     pub fn push_transaction(&mut self, tx: L2Transaction) -> Result<()> {
+        self.push_transaction_inner(tx, None)
+    }
+
+    /// Push a layer2 tx whose signature has already been verified, e.g. by
+    /// the RPC signature verifier.
+    ///
+    /// The signature is verified again if the sender's nonce or the mem block
+    /// number changed since `verified`.
+    #[instrument(skip_all)]
+    pub fn push_verified_transaction(
+        &mut self,
+        tx: L2Transaction,
+        verified: VerifiedSignature,
+    ) -> Result<()> {
+        self.push_transaction_inner(tx, Some(verified))
+    }
+
+    fn push_transaction_inner(
+        &mut self,
+        tx: L2Transaction,
+        verified: Option<VerifiedSignature>,
+    ) -> Result<()> {
         tokio::task::block_in_place(|| {
             let mut db = self.store.begin_transaction();
 
             let mut state = self.mem_pool_state.load_state_db();
-            self.push_transaction_with_db(&mut db, &mut state, tx)?;
+            self.push_transaction_with_db(&mut db, &mut state, tx, verified)?;
             db.commit()?;
             self.mem_pool_state.store_state_db(state);
 
@@ -333,6 +372,7 @@ impl MemPool {
         db: &mut StoreTransaction,
         state: &mut StateDB,
         tx: L2Transaction,
+        verified: Option<VerifiedSignature>,
     ) -> Result<()> {
         // check duplication
         let tx_hash: H256 = tx.raw().hash();
@@ -350,6 +390,7 @@ impl MemPool {
         }
 
         // verify transaction
+        let block_number = self.mem_block.block_info().number().unpack();
         let polyjuice_creator_id = self.generator.get_polyjuice_creator_id(state)?;
         TransactionVerifier::new(
             state,
@@ -357,13 +398,24 @@ impl MemPool {
             polyjuice_creator_id,
             self.generator.fork_config(),
         )
-        .verify(&tx, self.mem_block.block_info().number().unpack())?;
+        .verify(&tx, block_number)?;
         // verify signature
-        self.generator.check_transaction_signature(state, &tx)?;
+        let sender_nonce = state.get_nonce(tx.raw().from_id().unpack())?;
+        let session_key_spend = match verified {
+            Some(verified)
+                if verified.sender_nonce == sender_nonce
+                    && verified.block_number == block_number =>
+            {
+                verified.session_key_spend
+            }
+            _ => self
+                .generator
+                .check_transaction_signature(state, &tx, block_number)?,
+        };
 
         // instantly run tx in background & update local state
         let t = Instant::now();
-        let tx_receipt = self.execute_tx(db, state, tx.clone())?;
+        let tx_receipt = self.execute_tx(db, state, tx.clone(), session_key_spend)?;
         log::debug!("[push tx] finalize tx time: {}ms", t.elapsed().as_millis());
 
         // save new addresses
@@ -816,7 +868,8 @@ impl MemPool {
                 {
                     Ok(Some((tx, next_batch))) => {
                         self.mem_block.append_new_addresses(next_batch);
-                        if let Err(err) = self.push_transaction_with_db(&mut db, &mut state_db, tx)
+                        if let Err(err) =
+                            self.push_transaction_with_db(&mut db, &mut state_db, tx, None)
                         {
                             tracing::error!("account creator err {}", err);
                         }
@@ -972,7 +1025,11 @@ impl MemPool {
         // deposits
         self.finalize_deposits(state, deposit_cells.clone())?;
 
-        if let Some(ref sync_server) = self.sync_server {
+        // Relays publish mem blocks received from the upstream peer instead.
+        if let (Some(ref sync_server), false) = (
+            &self.sync_server,
+            matches!(self.node_mode, NodeMode::ReadOnly),
+        ) {
             let mut sync_server = sync_server.lock().unwrap();
             sync_server.publish_next_mem_block(
                 NextMemBlock::new_builder()
@@ -985,7 +1042,7 @@ impl MemPool {
 
         // re-inject txs
         for tx in txs {
-            if let Err(err) = self.push_transaction_with_db(db, state, tx.clone()) {
+            if let Err(err) = self.push_transaction_with_db(db, state, tx.clone(), None) {
                 let tx_hash = tx.hash();
                 log::info!(
                     "[mem pool] fail to re-inject tx {}, error: {}",
@@ -1204,6 +1261,7 @@ impl MemPool {
         db: &StoreTransaction,
         state: &mut StateDB,
         tx: L2Transaction,
+        session_key_spend: Option<SessionKeySpend>,
     ) -> Result<TxReceipt> {
         let tip_block_hash = db.get_tip_block_hash()?;
         let chain_view = ChainView::new(&db, tip_block_hash);
@@ -1270,6 +1328,9 @@ impl MemPool {
             }
         }
 
+        if let Some(spend) = session_key_spend {
+            spend.record(state)?;
+        }
         state.finalise()?;
         // finalise dirty state
         let merkle_state = state.calculate_merkle_state()?;
@@ -0,0 +1,71 @@
+use gw_telemetry::metric::{
+    counter::Counter, encoding::text::Encode, family::Family, registry::Registry, Lazy,
+};
+
+static CHALLENGER_METRICS: Lazy<ChallengerMetrics> = Lazy::new(ChallengerMetrics::default);
+
+pub fn challenger() -> &'static ChallengerMetrics {
+    &CHALLENGER_METRICS
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Encode)]
+pub enum ChallengeAction {
+    EnterChallenge,
+    CancelChallenge,
+    Revert,
+}
+
+#[derive(Default)]
+pub struct ChallengerMetrics {
+    pub bad_blocks: Counter,
+    pub bad_challenges: Counter,
+    pub cancelable_bad_blocks: Counter,
+    sent_txs: Family<ActionLabel, Counter>,
+    failed_txs: Family<ActionLabel, Counter>,
+}
+
+impl ChallengerMetrics {
+    pub(crate) fn register(&self, config: &crate::Config, registry: &mut Registry) {
+        if config.node_mode == gw_config::NodeMode::ReadOnly {
+            return;
+        }
+        registry.register(
+            "bad_blocks",
+            "Number of bad blocks found on L1",
+            Box::new(self.bad_blocks.clone()),
+        );
+        registry.register(
+            "bad_challenges",
+            "Number of challenges found on L1 that can be cancelled",
+            Box::new(self.bad_challenges.clone()),
+        );
+        registry.register(
+            "cancelable_bad_blocks",
+            "Number of bad blocks not challenged because the offchain validator can cancel the challenge",
+            Box::new(self.cancelable_bad_blocks.clone()),
+        );
+        registry.register(
+            "sent_txs",
+            "Number of sent challenger transactions",
+            Box::new(self.sent_txs.clone()),
+        );
+        registry.register(
+            "failed_txs",
+            "Number of challenger transactions failed to build or send",
+            Box::new(self.failed_txs.clone()),
+        );
+    }
+
+    pub fn sent(&self, action: ChallengeAction) {
+        self.sent_txs.get_or_create(&ActionLabel { action }).inc();
+    }
+
+    pub fn failed(&self, action: ChallengeAction) {
+        self.failed_txs.get_or_create(&ActionLabel { action }).inc();
+    }
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, Encode)]
+struct ActionLabel {
+    action: ChallengeAction,
+}
@@ -4,7 +4,7 @@ use gw_telemetry::metric::{
     encoding::text::Encode, family::Family, gauge::Gauge, prometheus_client, registry::Registry,
     Lazy,
 };
-use gw_types::prelude::Unpack;
+use gw_types::{h256::H256, prelude::Unpack};
 use smol_str::SmolStr;
 
 static CUSTODIAN_METRICS: Lazy<CustodianMetrics> = Lazy::new(CustodianMetrics::default);
@@ -16,10 +16,29 @@ pub fn custodian() -> &'static CustodianMetrics {
 #[derive(Default)]
 pub struct CustodianMetrics {
     finalized_custodians: Family<CustodianLabel, Gauge>,
+    audit_holdings: Family<AuditLabel, Gauge>,
+    audit_obligations: Family<AuditLabel, Gauge>,
+    audit_deficit: Family<AuditLabel, Gauge>,
 }
 
 impl CustodianMetrics {
     pub(crate) fn register(&self, config: &crate::Config, registry: &mut Registry) {
+        registry.register(
+            "audit_holdings",
+            "Audited custodian holdings of the asset on L1",
+            Box::new(self.audit_holdings.clone()),
+        );
+        registry.register(
+            "audit_obligations",
+            "Audited obligations of the asset, L2 supply and pending withdrawals",
+            Box::new(self.audit_obligations.clone()),
+        );
+        registry.register(
+            "audit_deficit",
+            "Audited deficit of the asset, obligations minus holdings",
+            Box::new(self.audit_deficit.clone()),
+        );
+
         if config.node_mode != gw_config::NodeMode::FullNode {
             return;
         }
@@ -58,6 +77,23 @@ impl CustodianMetrics {
         }
     }
 
+    /// Set audit gauges of the asset. Amounts are in the smallest unit of the
+    /// asset on L1 and saturate at `u64::MAX`.
+    pub fn set_audit(&self, asset: &H256, holdings: u128, obligations: u128) {
+        let label = AuditLabel {
+            asset: format!("0x{}", hex::encode(asset)),
+        };
+        let gauge = |amount: u128| amount.min(u64::MAX as u128) as u64;
+        let deficit = obligations.saturating_sub(holdings);
+        self.audit_holdings
+            .get_or_create(&label)
+            .set(gauge(holdings));
+        self.audit_obligations
+            .get_or_create(&label)
+            .set(gauge(obligations));
+        self.audit_deficit.get_or_create(&label).set(gauge(deficit));
+    }
+
     fn finalized<F, O>(&self, custodian: &crate::Custodian, f: F) -> O
     where
         F: Fn(&Gauge, u32) -> O,
@@ -75,6 +111,12 @@ struct CustodianLabel {
     pub symbol: EncodableSmolStr,
 }
 
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Encode)]
+struct AuditLabel {
+    // Script hash of the L1 sUDT, zero for CKB.
+    asset: String,
+}
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 struct EncodableSmolStr(SmolStr);
 
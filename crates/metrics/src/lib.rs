@@ -29,13 +29,19 @@ const ENV_METRIC_MONITOR_CUSTODIAN_VEC_JSON: &str = "METRIC_MONITOR_CUSTODIAN_VE
 
 pub mod block_producer;
 pub mod chain;
+pub mod challenger;
 pub mod custodian;
+pub mod p2p;
 pub mod rpc;
+pub mod store;
 
 pub use block_producer::block_producer;
 pub use chain::chain;
+pub use challenger::challenger;
 pub use custodian::custodian;
+pub use p2p::p2p;
 pub use rpc::rpc;
+pub use store::store;
 
 /// Global metrics registry.
 type TextEncodeRegistry = Registry<Box<dyn encoding::text::SendSyncEncodeMetric>>;
@@ -60,8 +66,11 @@ pub fn init(config: &gw_config::Config) {
     let mut registry = Registry::with_prefix("gw");
     block_producer().register(&config, registry.sub_registry_with_prefix("block_producer"));
     chain().register(&config, registry.sub_registry_with_prefix("chain"));
+    challenger().register(&config, registry.sub_registry_with_prefix("challenger"));
     custodian().register(&config, registry.sub_registry_with_prefix("custodian"));
+    p2p().register(&config, registry.sub_registry_with_prefix("p2p"));
     rpc().register(&config, registry.sub_registry_with_prefix("rpc"));
+    store().register(&config, registry.sub_registry_with_prefix("store"));
 
     METRIC_REGISTRY.store(Arc::new(Some(registry)));
     CONFIG.store(Arc::new(config));
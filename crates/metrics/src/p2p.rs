@@ -0,0 +1,118 @@
+use gw_telemetry::metric::{
+    counter::Counter, encoding::text::Encode, family::Family, gauge::Gauge, registry::Registry,
+    Lazy,
+};
+
+static P2P_METRICS: Lazy<P2PMetrics> = Lazy::new(P2PMetrics::default);
+
+pub fn p2p() -> &'static P2PMetrics {
+    &P2P_METRICS
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, Encode)]
+enum Direction {
+    Inbound,
+    Outbound,
+}
+
+#[derive(Default)]
+pub struct P2PMetrics {
+    sessions: Family<SessionLabel, Gauge>,
+    received_messages: Family<TrafficLabel, Counter>,
+    received_bytes: Family<TrafficLabel, Counter>,
+    sent_messages: Family<TrafficLabel, Counter>,
+    sent_bytes: Family<TrafficLabel, Counter>,
+}
+
+impl P2PMetrics {
+    pub(crate) fn register(&self, _config: &crate::Config, registry: &mut Registry) {
+        registry.register(
+            "sessions",
+            "Number of connected p2p sessions",
+            Box::new(self.sessions.clone()),
+        );
+        registry.register(
+            "received_messages",
+            "Number of messages received from the peer",
+            Box::new(self.received_messages.clone()),
+        );
+        registry.register(
+            "received_bytes",
+            "Bytes received from the peer",
+            Box::new(self.received_bytes.clone()),
+        );
+        registry.register(
+            "sent_messages",
+            "Number of messages sent to the peer",
+            Box::new(self.sent_messages.clone()),
+        );
+        registry.register(
+            "sent_bytes",
+            "Bytes sent to the peer",
+            Box::new(self.sent_bytes.clone()),
+        );
+    }
+
+    fn sessions(&self, inbound: bool) -> Gauge {
+        let direction = if inbound {
+            Direction::Inbound
+        } else {
+            Direction::Outbound
+        };
+        self.sessions
+            .get_or_create(&SessionLabel { direction })
+            .clone()
+    }
+
+    pub fn session_opened(&self, inbound: bool) {
+        self.sessions(inbound).inc();
+    }
+
+    pub fn session_closed(&self, inbound: bool) {
+        self.sessions(inbound).dec();
+    }
+
+    pub fn received(&self, peer: &str, protocol: &'static str, bytes: usize) {
+        let label = TrafficLabel {
+            peer: peer.into(),
+            protocol,
+        };
+        self.received_messages.get_or_create(&label).inc();
+        self.received_bytes
+            .get_or_create(&label)
+            .inc_by(bytes as u64);
+    }
+
+    pub fn sent(&self, peer: &str, protocol: &'static str, bytes: usize) {
+        let label = TrafficLabel {
+            peer: peer.into(),
+            protocol,
+        };
+        self.sent_messages.get_or_create(&label).inc();
+        self.sent_bytes.get_or_create(&label).inc_by(bytes as u64);
+    }
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, Encode)]
+struct SessionLabel {
+    direction: Direction,
+}
+
+// Label for p2p traffic metrics.
+#[derive(Hash, Clone, Eq, PartialEq)]
+struct TrafficLabel {
+    // Peer id, or address if the peer id is unknown.
+    peer: String,
+    protocol: &'static str,
+}
+
+// Manual impl because String does not implement Encode.
+impl Encode for TrafficLabel {
+    fn encode(&self, writer: &mut dyn std::io::Write) -> Result<(), std::io::Error> {
+        write!(
+            writer,
+            "peer=\"{}\",protocol=\"{}\"",
+            self.peer, self.protocol
+        )
+    }
+}
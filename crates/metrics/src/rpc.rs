@@ -1,6 +1,12 @@
 use gw_telemetry::metric::{
-    counter::Counter, encoding::text::Encode, family::Family, gauge::Gauge, prometheus_client,
-    registry::Registry, Lazy,
+    counter::Counter,
+    encoding::text::Encode,
+    family::Family,
+    gauge::Gauge,
+    histogram::{exponential_buckets, Histogram},
+    prometheus_client,
+    registry::Registry,
+    Lazy,
 };
 
 static RPC_METRICS: Lazy<RPCMetrics> = Lazy::new(RPCMetrics::default);
@@ -15,10 +21,29 @@ pub enum RequestKind {
     Withdrawal,
 }
 
-#[derive(Default)]
 pub struct RPCMetrics {
     execute_transactions: Family<ExecutionLabel, Counter>,
     in_queue_requests: Family<RequestLabel, Gauge>,
+    signature_verify_batches: Counter,
+    signature_verify_batch_size: Histogram,
+    signature_verify_batch_duration: Histogram,
+    signature_verify_failures: Counter,
+    signature_verify_queue_len: Gauge,
+}
+
+impl Default for RPCMetrics {
+    fn default() -> Self {
+        Self {
+            execute_transactions: Default::default(),
+            in_queue_requests: Default::default(),
+            signature_verify_batches: Default::default(),
+            signature_verify_batch_size: Histogram::new(exponential_buckets(1.0, 2.0, 10)),
+            // 1ms to ~4s
+            signature_verify_batch_duration: Histogram::new(exponential_buckets(0.001, 2.0, 13)),
+            signature_verify_failures: Default::default(),
+            signature_verify_queue_len: Default::default(),
+        }
+    }
 }
 
 impl RPCMetrics {
@@ -35,6 +60,31 @@ impl RPCMetrics {
                 "Number of in queue requests",
                 Box::new(self.in_queue_requests.clone()),
             );
+            registry.register(
+                "signature_verify_batches",
+                "Number of verified transaction signature batches",
+                Box::new(self.signature_verify_batches.clone()),
+            );
+            registry.register(
+                "signature_verify_batch_size",
+                "Number of transactions in a signature verification batch",
+                Box::new(self.signature_verify_batch_size.clone()),
+            );
+            registry.register(
+                "signature_verify_batch_duration_seconds",
+                "Time to verify a batch of transaction signatures",
+                Box::new(self.signature_verify_batch_duration.clone()),
+            );
+            registry.register(
+                "signature_verify_failures",
+                "Number of transactions rejected by signature verification",
+                Box::new(self.signature_verify_failures.clone()),
+            );
+            registry.register(
+                "signature_verify_queue_len",
+                "Number of transactions waiting for signature verification",
+                Box::new(self.signature_verify_queue_len.clone()),
+            );
         }
     }
 
@@ -49,6 +99,23 @@ impl RPCMetrics {
             .get_or_create(&RequestLabel { kind })
             .clone()
     }
+
+    pub fn signature_verify_batch(
+        &self,
+        size: usize,
+        duration: std::time::Duration,
+        failures: u64,
+    ) {
+        self.signature_verify_batches.inc();
+        self.signature_verify_batch_size.observe(size as f64);
+        self.signature_verify_batch_duration
+            .observe(duration.as_secs_f64());
+        self.signature_verify_failures.inc_by(failures);
+    }
+
+    pub fn signature_verify_queue_len(&self) -> &Gauge {
+        &self.signature_verify_queue_len
+    }
 }
 
 // Label for the execute_transactions metric.
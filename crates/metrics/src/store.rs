@@ -0,0 +1,125 @@
+use gw_store::Store;
+use gw_telemetry::metric::{
+    encoding::text::Encode, family::Family, gauge::Gauge, registry::Registry, Lazy,
+};
+
+static STORE_METRICS: Lazy<StoreMetrics> = Lazy::new(StoreMetrics::default);
+
+pub fn store() -> &'static StoreMetrics {
+    &STORE_METRICS
+}
+
+/// RocksDB column family statistics. Gauges are updated by calling
+/// [`StoreMetrics::update`] periodically.
+#[derive(Default)]
+pub struct StoreMetrics {
+    total_sst_files_size: Family<ColumnLabel, Gauge>,
+    live_sst_files_size: Family<ColumnLabel, Gauge>,
+    estimate_num_keys: Family<ColumnLabel, Gauge>,
+    sst_files: Family<ColumnLabel, Gauge>,
+    estimate_pending_compaction_bytes: Family<ColumnLabel, Gauge>,
+    compaction_pending: Family<ColumnLabel, Gauge>,
+    mem_table_flush_pending: Family<ColumnLabel, Gauge>,
+    write_stopped: Gauge,
+    actual_delayed_write_rate: Gauge,
+}
+
+impl StoreMetrics {
+    pub(crate) fn register(&self, _config: &crate::Config, registry: &mut Registry) {
+        registry.register(
+            "total_sst_files_size",
+            "Total size (bytes) of all SST files of the column",
+            Box::new(self.total_sst_files_size.clone()),
+        );
+        registry.register(
+            "live_sst_files_size",
+            "Total size (bytes) of all SST files belonging to the latest version of the column",
+            Box::new(self.live_sst_files_size.clone()),
+        );
+        registry.register(
+            "estimate_num_keys",
+            "Estimated number of keys of the column",
+            Box::new(self.estimate_num_keys.clone()),
+        );
+        registry.register(
+            "sst_files",
+            "Number of SST files of the column",
+            Box::new(self.sst_files.clone()),
+        );
+        registry.register(
+            "estimate_pending_compaction_bytes",
+            "Estimated bytes compaction needs to rewrite to get all levels down to under target size",
+            Box::new(self.estimate_pending_compaction_bytes.clone()),
+        );
+        registry.register(
+            "compaction_pending",
+            "1 if at least one compaction is pending for the column, otherwise 0",
+            Box::new(self.compaction_pending.clone()),
+        );
+        registry.register(
+            "mem_table_flush_pending",
+            "1 if a memtable flush is pending for the column, otherwise 0",
+            Box::new(self.mem_table_flush_pending.clone()),
+        );
+        registry.register(
+            "write_stopped",
+            "1 if writes have been stopped",
+            Box::new(self.write_stopped.clone()),
+        );
+        registry.register(
+            "actual_delayed_write_rate",
+            "Current actual delayed write rate (bytes/s), 0 means no delay",
+            Box::new(self.actual_delayed_write_rate.clone()),
+        );
+    }
+
+    pub fn update(&self, store: &Store) {
+        let set = |family: &Family<ColumnLabel, Gauge>, column, value: Option<u64>| {
+            if let Some(value) = value {
+                family.get_or_create(&ColumnLabel { column }).set(value);
+            }
+        };
+
+        for stat in store.gather_cf_stats() {
+            let column = stat.name;
+            set(
+                &self.total_sst_files_size,
+                column,
+                stat.total_sst_files_size,
+            );
+            set(&self.live_sst_files_size, column, stat.live_sst_files_size);
+            set(&self.estimate_num_keys, column, stat.estimate_num_keys);
+            set(&self.sst_files, column, stat.num_sst_files);
+            set(
+                &self.estimate_pending_compaction_bytes,
+                column,
+                stat.estimate_pending_compaction_bytes,
+            );
+            set(&self.compaction_pending, column, stat.compaction_pending);
+            set(
+                &self.mem_table_flush_pending,
+                column,
+                stat.mem_table_flush_pending,
+            );
+            if let Some(stopped) = stat.is_write_stopped {
+                self.write_stopped.set(stopped);
+            }
+            if let Some(rate) = stat.actual_delayed_write_rate {
+                self.actual_delayed_write_rate.set(rate);
+            }
+        }
+    }
+}
+
+// Label for column family metrics.
+#[derive(Hash, Clone, Eq, PartialEq)]
+struct ColumnLabel {
+    column: usize,
+}
+
+// Manual impl because usize does not implement Encode.
+impl Encode for ColumnLabel {
+    fn encode(&self, writer: &mut dyn std::io::Write) -> Result<(), std::io::Error> {
+        write!(writer, "column=\"{}\"", self.column)
+    }
+}
@@ -53,7 +53,7 @@ async fn main() -> anyhow::Result<()> {
             allowed_peer_ids: None,
         }
     };
-    let mut network = P2PNetwork::init(&config, [protocol()]).await?;
+    let mut network = P2PNetwork::init(&config, [protocol()], Default::default()).await?;
     network.run().await;
 
     Ok(())
@@ -13,7 +13,7 @@ use tentacle::{
     builder::ServiceBuilder,
     context::{ServiceContext, SessionContext},
     multiaddr::{MultiAddr, Protocol},
-    secio::{PeerId, SecioKeyPair},
+    secio::SecioKeyPair,
     service::{
         ProtocolMeta, Service, ServiceAsyncControl, ServiceError, ServiceEvent, TargetProtocol,
     },
@@ -22,6 +22,10 @@ use tentacle::{
     ProtocolId, SubstreamReadPart,
 };
 
+use crate::peers::Peers;
+
+pub mod peers;
+
 const RECONNECT_BASE_DURATION: Duration = Duration::from_secs(2);
 
 /// Wrapper for tentacle Service. Automatically reconnect dial addresses.
@@ -30,7 +34,11 @@ pub struct P2PNetwork {
 }
 
 impl P2PNetwork {
-    pub async fn init<PS>(config: &P2PNetworkConfig, protocols: PS) -> Result<Self>
+    pub async fn init<PS>(
+        config: &P2PNetworkConfig,
+        protocols: PS,
+        peers: Arc<Peers>,
+    ) -> Result<Self>
     where
         PS: IntoIterator,
         PS::Item: Into<ProtocolMeta>,
@@ -83,9 +91,10 @@ impl P2PNetwork {
         };
         let mut service = builder.build(SHandle {
             dial_backoff,
-            allowed_peer_ids,
+            peers: peers.clone(),
         });
         let control = service.control().clone();
+        peers.init(control.clone(), allowed_peer_ids);
         // Send dial in another task to avoid deadlock.
         if !dial_vec.is_empty() {
             tokio::spawn(async move {
@@ -119,8 +128,8 @@ impl P2PNetwork {
 
 // Implement ServiceHandle to handle tentacle events.
 struct SHandle {
-    allowed_peer_ids: Option<HashSet<PeerId>>,
     dial_backoff: HashMap<MultiAddr, ExponentialBackoff>,
+    peers: Arc<Peers>,
 }
 
 impl SHandle {
@@ -178,21 +187,12 @@ impl ServiceHandle for SHandle {
         log::info!("service event: {:?}", event);
         match event {
             ServiceEvent::SessionClose { session_context } => {
+                self.peers.close_session(session_context.id);
                 self.re_dial(context, session_context.address.clone());
             }
             ServiceEvent::SessionOpen { session_context } => {
                 // Check allow list.
-                let mut allow = true;
-                if let Some(ref allowed) = self.allowed_peer_ids {
-                    if let Some(peer_id) = extract_peer_id(&session_context.address) {
-                        if !allowed.contains(&peer_id) {
-                            allow = false;
-                        }
-                    } else {
-                        allow = false;
-                    }
-                };
-                if !allow {
+                if !self.peers.open_session(&session_context) {
                     let _ = context.control().disconnect(session_context.id).await;
                 } else {
                     self.reset(session_context.address.clone());
@@ -219,9 +219,57 @@ impl<F: Fn(Arc<SessionContext>, &ServiceAsyncControl, SubstreamReadPart)> Protoc
     }
 }
 
+/// ProtocolSpawn helper that serves sessions dialed by us with `outbound` and
+/// sessions from peers with `inbound`, e.g. a relay syncs from its upstream
+/// and serves its downstream peers with the same protocol.
+pub struct DirectionalSpawn<O, I> {
+    pub outbound: O,
+    pub inbound: I,
+}
+
+impl<O: ProtocolSpawn, I: ProtocolSpawn> ProtocolSpawn for DirectionalSpawn<O, I> {
+    fn spawn(
+        &self,
+        context: Arc<SessionContext>,
+        control: &ServiceAsyncControl,
+        read_part: SubstreamReadPart,
+    ) {
+        if context.ty.is_outbound() {
+            self.outbound.spawn(context, control, read_part);
+        } else {
+            self.inbound.spawn(context, control, read_part);
+        }
+    }
+}
+
 // Protocol registry: all p2p protocols should be declared here.
 
 // Sync local blocks, block submission and confirmation events as well as mem
 // blocks and mem block transactions.
 pub const P2P_SYNC_PROTOCOL: ProtocolId = ProtocolId::new(3);
 pub const P2P_SYNC_PROTOCOL_NAME: &str = "/p2p/sync";
+// Version 0.0.2 relays messages (the `hops` fields) and schedules backend
+// forks. Peers of different versions don't open the protocol, instead of
+// failing to parse the messages.
+pub const P2P_SYNC_PROTOCOL_VERSION: &str = "0.0.2";
+
+// Forward transactions and withdrawal requests from read-only nodes to the
+// full node.
+pub const P2P_FORWARD_PROTOCOL: ProtocolId = ProtocolId::new(4);
+pub const P2P_FORWARD_PROTOCOL_NAME: &str = "/p2p/forward";
+
+// Serve finalized blocks in a range to read-only nodes catching up.
+pub const P2P_BLOCK_RANGE_PROTOCOL: ProtocolId = ProtocolId::new(5);
+pub const P2P_BLOCK_RANGE_PROTOCOL_NAME: &str = "/p2p/block_range";
+
+pub fn protocol_name(id: ProtocolId) -> &'static str {
+    if id == P2P_SYNC_PROTOCOL {
+        P2P_SYNC_PROTOCOL_NAME
+    } else if id == P2P_FORWARD_PROTOCOL {
+        P2P_FORWARD_PROTOCOL_NAME
+    } else if id == P2P_BLOCK_RANGE_PROTOCOL {
+        P2P_BLOCK_RANGE_PROTOCOL_NAME
+    } else {
+        "unknown"
+    }
+}
@@ -0,0 +1,354 @@
+//! Connected peers and their traffic, for peer management admin RPCs.
+//!
+//! Sessions are tracked by the service handle. Protocols record their traffic
+//! with [`Peers::record_received`] and [`Peers::record_sent`], which is also
+//! exported through gw_metrics.
+//!
+//! The runner creates one [`Peers`] for the p2p network, and shares it with
+//! the protocols and the RPC server.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::{Mutex, RwLock},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context, Result};
+use tentacle::{
+    context::SessionContext,
+    multiaddr::MultiAddr,
+    secio::PeerId,
+    service::{ServiceAsyncControl, TargetProtocol},
+    utils::extract_peer_id,
+    ProtocolId, SessionId,
+};
+
+use crate::protocol_name;
+
+/// Max number of peers labeled by their peer ids in metrics, other peers
+/// share the [`OTHER_PEERS_LABEL`], so that the number of metric series is
+/// bounded.
+const MAX_LABELED_PEERS: usize = 64;
+const OTHER_PEERS_LABEL: &str = "other";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Traffic {
+    pub received_messages: u64,
+    pub received_bytes: u64,
+    pub sent_messages: u64,
+    pub sent_bytes: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct PeerInfo {
+    pub session_id: SessionId,
+    pub address: MultiAddr,
+    pub peer_id: Option<PeerId>,
+    pub inbound: bool,
+    pub uptime: Duration,
+    /// Traffic of each opened protocol, by protocol name.
+    pub protocols: BTreeMap<&'static str, Traffic>,
+}
+
+struct Session {
+    address: MultiAddr,
+    peer_id: Option<PeerId>,
+    // Label of the peer in metrics.
+    label: String,
+    inbound: bool,
+    opened_at: Instant,
+    protocols: HashMap<ProtocolId, Traffic>,
+}
+
+#[derive(Default)]
+pub struct Peers {
+    // Also locked when the allowlist is checked or replaced, so that sessions
+    // are never opened with a replaced allowlist.
+    sessions: Mutex<HashMap<SessionId, Session>>,
+    allowed_peer_ids: RwLock<Option<HashSet<PeerId>>>,
+    // Peer ids labeled in metrics.
+    labeled_peers: Mutex<HashSet<PeerId>>,
+    control: Mutex<Option<ServiceAsyncControl>>,
+}
+
+impl Peers {
+    pub(crate) fn init(
+        &self,
+        control: ServiceAsyncControl,
+        allowed_peer_ids: Option<HashSet<PeerId>>,
+    ) {
+        *self.control.lock().unwrap() = Some(control);
+        *self.allowed_peer_ids.write().unwrap() = allowed_peer_ids;
+    }
+
+    fn is_allowed(&self, address: &MultiAddr) -> bool {
+        match *self.allowed_peer_ids.read().unwrap() {
+            Some(ref allowed) => extract_peer_id(address).map_or(false, |p| allowed.contains(&p)),
+            None => true,
+        }
+    }
+
+    /// Returns false if the peer is not allowed, and the session should be
+    /// disconnected.
+    pub(crate) fn open_session(&self, context: &SessionContext) -> bool {
+        self.insert_session(context.id, &context.address, context.ty.is_inbound())
+    }
+
+    fn insert_session(&self, id: SessionId, address: &MultiAddr, inbound: bool) -> bool {
+        let mut sessions = self.sessions.lock().unwrap();
+        if !self.is_allowed(address) {
+            return false;
+        }
+        let peer_id = extract_peer_id(address);
+        let label = self.label(peer_id.as_ref());
+        gw_metrics::p2p().session_opened(inbound);
+        let session = Session {
+            address: address.clone(),
+            peer_id,
+            label,
+            inbound,
+            opened_at: Instant::now(),
+            protocols: HashMap::new(),
+        };
+        sessions.insert(id, session);
+        true
+    }
+
+    fn label(&self, peer_id: Option<&PeerId>) -> String {
+        let peer_id = match peer_id {
+            Some(peer_id) => peer_id,
+            None => return OTHER_PEERS_LABEL.into(),
+        };
+        let mut labeled = self.labeled_peers.lock().unwrap();
+        if labeled.contains(peer_id) || labeled.len() < MAX_LABELED_PEERS {
+            labeled.insert(peer_id.clone());
+            peer_id.to_base58()
+        } else {
+            OTHER_PEERS_LABEL.into()
+        }
+    }
+
+    pub(crate) fn close_session(&self, id: SessionId) {
+        if let Some(session) = self.sessions.lock().unwrap().remove(&id) {
+            gw_metrics::p2p().session_closed(session.inbound);
+        }
+    }
+
+    /// Called when a protocol is opened on the session.
+    pub fn open_protocol(&self, id: SessionId, protocol: ProtocolId) {
+        if let Some(session) = self.sessions.lock().unwrap().get_mut(&id) {
+            session.protocols.entry(protocol).or_default();
+        }
+    }
+
+    pub fn record_received(&self, id: SessionId, protocol: ProtocolId, bytes: usize) {
+        if let Some(session) = self.sessions.lock().unwrap().get_mut(&id) {
+            let traffic = session.protocols.entry(protocol).or_default();
+            traffic.received_messages += 1;
+            traffic.received_bytes += bytes as u64;
+            gw_metrics::p2p().received(&session.label, protocol_name(protocol), bytes);
+        }
+    }
+
+    pub fn record_sent(&self, id: SessionId, protocol: ProtocolId, bytes: usize) {
+        if let Some(session) = self.sessions.lock().unwrap().get_mut(&id) {
+            let traffic = session.protocols.entry(protocol).or_default();
+            traffic.sent_messages += 1;
+            traffic.sent_bytes += bytes as u64;
+            gw_metrics::p2p().sent(&session.label, protocol_name(protocol), bytes);
+        }
+    }
+
+    /// Connected sessions, ordered by session id.
+    pub fn list(&self) -> Vec<PeerInfo> {
+        let sessions = self.sessions.lock().unwrap();
+        let mut peers: Vec<_> = sessions
+            .iter()
+            .map(|(id, s)| PeerInfo {
+                session_id: *id,
+                address: s.address.clone(),
+                peer_id: s.peer_id.clone(),
+                inbound: s.inbound,
+                uptime: s.opened_at.elapsed(),
+                protocols: s
+                    .protocols
+                    .iter()
+                    .map(|(p, traffic)| (protocol_name(*p), *traffic))
+                    .collect(),
+            })
+            .collect();
+        peers.sort_by_key(|p| p.session_id);
+        peers
+    }
+
+    fn control(&self) -> Result<ServiceAsyncControl> {
+        self.control
+            .lock()
+            .unwrap()
+            .clone()
+            .context("p2p network is not enabled")
+    }
+
+    /// Dial the address once, it's not reconnected like addresses in the
+    /// `dial` config.
+    pub async fn dial(&self, address: &str) -> Result<()> {
+        let address: MultiAddr = address.parse().context("parse dial address")?;
+        log::info!("dial {}", address);
+        self.control()?
+            .dial(address, TargetProtocol::All)
+            .await
+            .map_err(|err| anyhow!("dial: {:?}", err))
+    }
+
+    pub async fn disconnect(&self, id: SessionId) -> Result<()> {
+        if !self.sessions.lock().unwrap().contains_key(&id) {
+            return Err(anyhow!("session {} not found", id));
+        }
+        log::info!("disconnect session {}", id);
+        self.control()?
+            .disconnect(id)
+            .await
+            .map_err(|err| anyhow!("disconnect: {:?}", err))
+    }
+
+    pub fn allowed_peer_ids(&self) -> Option<Vec<PeerId>> {
+        let allowed = self.allowed_peer_ids.read().unwrap();
+        allowed.as_ref().map(|a| a.iter().cloned().collect())
+    }
+
+    /// Replace the allowlist, `None` allows all peers. Connected peers that
+    /// are not allowed are disconnected.
+    ///
+    /// The allowlist is replaced only if all peer ids are valid.
+    pub async fn set_allowed_peer_ids(&self, peer_ids: Option<Vec<String>>) -> Result<()> {
+        let allowed = match peer_ids {
+            Some(peer_ids) => {
+                let mut allowed = HashSet::with_capacity(peer_ids.len());
+                for p in peer_ids {
+                    let peer_id = p
+                        .parse()
+                        .with_context(|| format!("parse allowed peer id {}", p))?;
+                    allowed.insert(peer_id);
+                }
+                Some(allowed)
+            }
+            None => None,
+        };
+        for id in self.replace_allowed_peer_ids(allowed) {
+            // The session may be closed in the meantime.
+            if let Err(err) = self.disconnect(id).await {
+                log::warn!("disconnect session {} not allowed: {:#}", id, err);
+            }
+        }
+        Ok(())
+    }
+
+    /// Replace the allowlist, returns sessions that are not allowed.
+    fn replace_allowed_peer_ids(&self, allowed: Option<HashSet<PeerId>>) -> Vec<SessionId> {
+        let sessions = self.sessions.lock().unwrap();
+        *self.allowed_peer_ids.write().unwrap() = allowed;
+        sessions
+            .iter()
+            .filter(|(_, s)| !self.is_allowed(&s.address))
+            .map(|(id, _)| *id)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tentacle::secio::SecioKeyPair;
+
+    use super::*;
+
+    fn random_address() -> (PeerId, MultiAddr) {
+        let peer_id = SecioKeyPair::secp256k1_generated().public_key().peer_id();
+        let address = format!("/ip4/127.0.0.1/tcp/9999/p2p/{}", peer_id.to_base58());
+        (peer_id, address.parse().unwrap())
+    }
+
+    #[test]
+    fn test_allowed_peer_ids() {
+        let peers = Peers::default();
+        let (allowed, allowed_address) = random_address();
+        let (_, other_address) = random_address();
+        assert!(peers.insert_session(SessionId::new(1), &other_address, true));
+
+        let not_allowed = peers.replace_allowed_peer_ids(Some([allowed.clone()].into()));
+        assert_eq!(not_allowed, vec![SessionId::new(1)]);
+        assert!(!peers.insert_session(SessionId::new(2), &other_address, true));
+        assert!(peers.insert_session(SessionId::new(3), &allowed_address, true));
+        // Without peer id.
+        let address: MultiAddr = "/ip4/127.0.0.1/tcp/9999".parse().unwrap();
+        assert!(!peers.insert_session(SessionId::new(4), &address, false));
+        assert_eq!(peers.allowed_peer_ids(), Some(vec![allowed]));
+
+        assert!(peers.replace_allowed_peer_ids(None).is_empty());
+        assert!(peers.insert_session(SessionId::new(5), &other_address, true));
+    }
+
+    #[tokio::test]
+    async fn test_set_invalid_allowed_peer_ids() {
+        let peers = Peers::default();
+        let (allowed, _) = random_address();
+        peers
+            .set_allowed_peer_ids(Some(vec![allowed.to_base58()]))
+            .await
+            .unwrap();
+        let result = peers
+            .set_allowed_peer_ids(Some(vec!["invalid".into()]))
+            .await;
+        assert!(result.is_err());
+        assert_eq!(peers.allowed_peer_ids(), Some(vec![allowed]));
+    }
+
+    #[test]
+    fn test_metric_labels_are_bounded() {
+        let peers = Peers::default();
+        let addresses: Vec<_> = (0..=MAX_LABELED_PEERS).map(|_| random_address()).collect();
+        for (i, (_, address)) in addresses.iter().enumerate() {
+            assert!(peers.insert_session(SessionId::new(i), address, true));
+        }
+        let label = |id: usize| {
+            let sessions = peers.sessions.lock().unwrap();
+            sessions[&SessionId::new(id)].label.clone()
+        };
+        assert_eq!(label(0), addresses[0].0.to_base58());
+        assert_eq!(label(MAX_LABELED_PEERS), OTHER_PEERS_LABEL);
+
+        // Reconnected peers keep their labels.
+        peers.close_session(SessionId::new(0));
+        assert!(peers.insert_session(SessionId::new(100), &addresses[0].1, true));
+        assert_eq!(label(100), addresses[0].0.to_base58());
+    }
+
+    #[test]
+    fn test_record_traffic() {
+        let peers = Peers::default();
+        let (_, address) = random_address();
+        let id = SessionId::new(1);
+        assert!(peers.insert_session(id, &address, false));
+        let protocol = crate::P2P_SYNC_PROTOCOL;
+        peers.open_protocol(id, protocol);
+        peers.record_received(id, protocol, 10);
+        peers.record_sent(id, protocol, 20);
+        peers.record_sent(id, protocol, 30);
+
+        let list = peers.list();
+        assert_eq!(list.len(), 1);
+        assert!(!list[0].inbound);
+        let traffic = list[0].protocols[crate::P2P_SYNC_PROTOCOL_NAME];
+        assert_eq!(
+            traffic,
+            Traffic {
+                received_messages: 1,
+                received_bytes: 10,
+                sent_messages: 2,
+                sent_bytes: 50,
+            }
+        );
+
+        peers.close_session(id);
+        assert!(peers.list().is_empty());
+    }
+}
@@ -5,7 +5,10 @@ use ckb_types::bytes::Bytes;
 use gw_chain::chain::Chain;
 use gw_config::{Config, StoreConfig};
 use gw_generator::{
-    account_lock_manage::{secp256k1::Secp256k1Eth, AccountLockManage},
+    account_lock_manage::{
+        multisig::Secp256k1EthMultisig, secp256k1::Secp256k1Eth, webauthn::Secp256r1WebAuthn,
+        AccountLockManage,
+    },
     backend_manage::BackendManage,
     genesis::init_genesis,
     Generator,
@@ -44,6 +47,7 @@ pub async fn setup(args: SetupArgs) -> Result<Context> {
         path: to_db_store,
         options_file: config.store.options_file.clone(),
         cache_size: config.store.cache_size,
+        backend: config.store.backend,
     };
     let local_store = Store::open(&store_config, COLUMNS).unwrap();
     let rollup_type_script = {
@@ -110,6 +114,25 @@ pub async fn setup(args: SetupArgs) -> Result<Context> {
             eth_lock_script_type_hash.hash().unpack(),
             Arc::new(Secp256k1Eth::default()),
         );
+        // Passkey and multisig EOAs are optional
+        if let Some(webauthn_lock_script_type_hash) = allowed_eoa_type_hashes
+            .iter()
+            .find(|th| th.type_().to_entity() == AllowedEoaType::WebAuthn.into())
+        {
+            account_lock_manage.register_lock_algorithm(
+                webauthn_lock_script_type_hash.hash().unpack(),
+                Arc::new(Secp256r1WebAuthn::default()),
+            );
+        }
+        if let Some(multisig_lock_script_type_hash) = allowed_eoa_type_hashes
+            .iter()
+            .find(|th| th.type_().to_entity() == AllowedEoaType::Multisig.into())
+        {
+            account_lock_manage.register_lock_algorithm(
+                multisig_lock_script_type_hash.hash().unpack(),
+                Arc::new(Secp256k1EthMultisig::default()),
+            );
+        }
         Arc::new(Generator::new(
             backend_manage,
             account_lock_manage,
@@ -132,6 +155,7 @@ pub async fn setup(args: SetupArgs) -> Result<Context> {
             path: from_db_store,
             options_file: config.store.options_file.clone(),
             cache_size: config.store.cache_size,
+            backend: config.store.backend,
         };
         Store::open(&store_config, from_db_columns).unwrap()
     };
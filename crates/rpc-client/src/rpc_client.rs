@@ -23,7 +23,7 @@ use tracing::instrument;
 use crate::{
     ckb_client::CkbClient,
     indexer_client::CkbIndexerClient,
-    indexer_types::{Cell, Order, ScriptType, SearchKey, SearchKeyFilter},
+    indexer_types::{Cell, IOType, Order, ScriptType, SearchKey, SearchKeyFilter},
     utils::DEFAULT_QUERY_LIMIT,
 };
 
@@ -250,6 +250,55 @@ impl RPCClient {
         }))
     }
 
+    /// Find the committed transaction which consumes the cell.
+    #[instrument(skip_all, fields(tx_hash = %out_point.tx_hash(), index = Unpack::<u32>::unpack(&out_point.index())))]
+    pub async fn get_cell_consumer_tx(&self, out_point: &OutPoint) -> Result<Option<Transaction>> {
+        let tx_hash: H256 = out_point.tx_hash().unpack();
+        let lock = match self.get_cell_from_mempool(out_point.clone()).await? {
+            Some(cell) => cell.output.lock(),
+            None => return Ok(None),
+        };
+        let block_number = match self.ckb.get_transaction_block_number(tx_hash).await? {
+            Some(block_number) => block_number,
+            None => return Ok(None),
+        };
+
+        // The cell can only be consumed after it's created.
+        let search_key = SearchKey::with_lock(lock).with_filter(Some(SearchKeyFilter {
+            block_range: Some([
+                BlockNumber::from(block_number),
+                BlockNumber::from(u64::max_value()),
+            ]),
+            ..Default::default()
+        }));
+        let limit = Uint32::from(DEFAULT_QUERY_LIMIT as u32);
+        let mut cursor = None;
+        loop {
+            let txs = self
+                .indexer
+                .get_transactions(&search_key, &Order::Asc, limit, &cursor)
+                .await?;
+            if txs.objects.is_empty() {
+                return Ok(None);
+            }
+            cursor = Some(txs.last_cursor);
+
+            for tx in txs.objects {
+                if !matches!(tx.io_type, IOType::Input) {
+                    continue;
+                }
+                let consumer = match self.ckb.get_packed_transaction(tx.tx_hash.0).await? {
+                    Some(consumer) => consumer,
+                    None => continue,
+                };
+                let input = consumer.raw().inputs().get(tx.io_index.value() as usize);
+                if input.map(|i| i.previous_output()).as_ref() == Some(out_point) {
+                    return Ok(Some(consumer));
+                }
+            }
+        }
+    }
+
     pub async fn get_tip(&self) -> Result<NumberHash> {
         self.indexer.get_indexer_tip1().await.map(Into::into)
     }
@@ -375,6 +424,26 @@ impl RPCClient {
         Ok(deposit_infos)
     }
 
+    /// Parse a deposit cell of this rollup, returns `None` if the cell is not
+    /// a valid deposit cell.
+    pub fn parse_deposit_cell(&self, cell: CellInfo) -> Option<DepositInfo> {
+        let lock = cell.output.lock();
+        if lock.code_hash() != self.rollup_config.deposit_script_type_hash()
+            || lock.hash_type() != ScriptHashType::Type.into()
+        {
+            return None;
+        }
+        let args: Bytes = lock.args().unpack();
+        let rollup_type_hash = self.rollup_type_script.calc_script_hash();
+        if args.len() < 32 || args[..32] != rollup_type_hash.as_slice()[..] {
+            return None;
+        }
+        DepositLockArgsReader::verify(&args[32..], false).ok()?;
+        let deposit_lock_args = DepositLockArgs::new_unchecked(args.slice(32..));
+        let request = parse_deposit_request(&cell.output, &cell.data, &deposit_lock_args)?;
+        Some(DepositInfo { cell, request })
+    }
+
     #[instrument(skip_all)]
     pub async fn query_stake_cells_by_owner_lock_hashes(
         &self,
@@ -0,0 +1,202 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, bail, Context, Result};
+use ckb_fixed_hash::H160;
+use gw_config::BackendForkConfig;
+use gw_generator::{
+    account_lock_manage::{secp256k1::Secp256k1Eth, LockAlgorithm},
+    backend_manage::backend_fork_signing_message,
+    Generator,
+};
+use gw_jsonrpc_types::ckb_jsonrpc_types::JsonBytes;
+use gw_mem_pool::block_sync_server::BlockSyncServerState;
+use gw_store::{traits::chain_store::ChainStore, Store};
+use gw_types::{bytes::Bytes, packed::ScheduledBackendFork, prelude::*};
+
+use crate::registry::Registry;
+
+/// Forks must be scheduled at least this many blocks after the tip, so the
+/// mem block in producing is never affected.
+const MIN_FORK_DELAY_BLOCKS: u64 = 10;
+
+/// Applies backend forks signed by upgrade admins.
+///
+/// Scheduled forks are persisted in the store and reloaded on restart. They
+/// are also published to block sync peers, which verify the signature with
+/// their own `backend_fork_admins` and apply the fork in the same way.
+pub struct BackendForkScheduler {
+    store: Store,
+    generator: Arc<Generator>,
+    debug_generator: Arc<Generator>,
+    admins: Vec<H160>,
+    sync_server: Option<Arc<Mutex<BlockSyncServerState>>>,
+    // Serialize registrations so persisted forks are in order of fork height.
+    lock: Mutex<()>,
+}
+
+impl BackendForkScheduler {
+    pub(crate) fn new(
+        store: Store,
+        generator: Arc<Generator>,
+        debug_generator: Arc<Generator>,
+        admins: Vec<H160>,
+        sync_server: Option<Arc<Mutex<BlockSyncServerState>>>,
+    ) -> Self {
+        Self {
+            store,
+            generator,
+            debug_generator,
+            admins,
+            sync_server,
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub fn generator(&self) -> &Arc<Generator> {
+        &self.generator
+    }
+
+    /// Register forks persisted by previous runs, forks that have been added
+    /// to the consensus config are skipped.
+    ///
+    /// Blocking, backends are loaded and compiled.
+    pub(crate) fn reload(&self) -> Result<()> {
+        let _guard = self.lock.lock().expect("backend fork lock");
+        for fork in self.store.get_scheduled_backend_forks().into_iter() {
+            let (config, signer) = self.verify(&fork).context("reload backend fork")?;
+            let fork_height = config.fork_height;
+            if !self
+                .generator
+                .backend_manage()
+                .contains_backend_fork(&config)
+            {
+                self.register(config)
+                    .with_context(|| format!("reload backend fork at height {}", fork_height))?;
+                log::info!(
+                    "[backend fork] reloaded fork at height {} signed by {:#x}",
+                    fork_height,
+                    signer
+                );
+            }
+            self.publish(fork);
+        }
+        Ok(())
+    }
+
+    /// Verify, register, persist and publish the fork. Returns false if the
+    /// fork is already registered.
+    ///
+    /// Blocking, backends are loaded and compiled.
+    pub fn schedule(&self, fork: ScheduledBackendFork) -> Result<bool> {
+        let _guard = self.lock.lock().expect("backend fork lock");
+        let (config, signer) = self.verify(&fork)?;
+        if self
+            .generator
+            .backend_manage()
+            .contains_backend_fork(&config)
+        {
+            return Ok(false);
+        }
+        let fork_height = config.fork_height;
+        self.register(config)?;
+
+        let forks = self
+            .store
+            .get_scheduled_backend_forks()
+            .as_builder()
+            .push(fork.clone())
+            .build();
+        let mut store_tx = self.store.begin_transaction();
+        store_tx.set_scheduled_backend_forks(&forks)?;
+        store_tx.commit()?;
+
+        self.publish(fork);
+        log::warn!(
+            "[backend fork] scheduled at height {} by {:#x}",
+            fork_height,
+            signer
+        );
+        Ok(true)
+    }
+
+    fn verify(&self, fork: &ScheduledBackendFork) -> Result<(BackendForkConfig, H160)> {
+        let config: Bytes = fork.config().unpack();
+        let config: BackendForkConfig =
+            serde_json::from_slice(&config).context("parse backend fork config")?;
+        if config.sudt_proxy.is_some() {
+            bail!("sudt_proxy can't be changed by a runtime backend fork");
+        }
+        if config.backends.is_empty() {
+            bail!("no backends in the fork");
+        }
+
+        if self.admins.is_empty() {
+            bail!("backend fork admins are not configured");
+        }
+        let rollup_script_hash = &self.generator.rollup_context().rollup_script_hash;
+        let message = backend_fork_signing_message(rollup_script_hash, &config);
+        let signature: Bytes = fork.signature().unpack();
+        let signer = Secp256k1Eth::default()
+            .recover(message, &signature)
+            .map_err(|err| anyhow!("invalid signature: {}", err))?;
+        let signer = H160::from_slice(&signer).map_err(|_| anyhow!("invalid signer"))?;
+        if !self.admins.contains(&signer) {
+            bail!("{:#x} is not a backend fork admin", signer);
+        }
+        Ok((config, signer))
+    }
+
+    fn register(&self, config: BackendForkConfig) -> Result<()> {
+        self.generator.register_backend_fork(config.clone())?;
+        // The debug generator should not fallback to the replaced backends
+        if !Arc::ptr_eq(&self.generator, &self.debug_generator) {
+            self.debug_generator.register_backend_fork(config)?;
+        }
+        Ok(())
+    }
+
+    fn publish(&self, fork: ScheduledBackendFork) {
+        if let Some(ref sync_server) = self.sync_server {
+            sync_server
+                .lock()
+                .expect("block sync server")
+                .publish_backend_fork(fork);
+        }
+    }
+}
+
+/// Check the fork height and schedule the backend fork.
+pub(crate) async fn schedule_backend_fork(
+    ctx: Arc<Registry>,
+    config: BackendForkConfig,
+    signature: JsonBytes,
+) -> Result<()> {
+    let tip_number: u64 = ctx
+        .store
+        .get_last_valid_tip_block()?
+        .raw()
+        .number()
+        .unpack();
+    let min_fork_height = tip_number.saturating_add(MIN_FORK_DELAY_BLOCKS);
+    if config.fork_height < min_fork_height {
+        bail!(
+            "fork_height {} is too close to the tip {}, it must be at least {}",
+            config.fork_height,
+            tip_number,
+            min_fork_height
+        );
+    }
+
+    let fork = ScheduledBackendFork::new_builder()
+        .config(serde_json::to_vec(&config)?.pack())
+        .signature(signature.as_bytes().pack())
+        .build();
+    // Loading and compiling backends takes a while
+    let scheduled =
+        tokio::task::spawn_blocking(move || ctx.backend_fork_scheduler.schedule(fork)).await??;
+    if !scheduled {
+        bail!("the fork is already scheduled");
+    }
+
+    Ok(())
+}
@@ -2,7 +2,11 @@ use std::{convert::TryInto, sync::Arc, time::Instant};
 
 use anyhow::{anyhow, Result};
 use ckb_fixed_hash::H256 as JsonH256;
-use gw_jsonrpc_types::{ckb_jsonrpc_types::Uint64, debug::DebugRunResult};
+use gw_generator::{generator::CyclesPool, profiler::Profile};
+use gw_jsonrpc_types::{
+    ckb_jsonrpc_types::Uint64,
+    debug::{DebugProfile, DebugRunResult, DebugSyscallProfile},
+};
 use gw_store::{
     chain_view::ChainView,
     state::{
@@ -22,6 +26,7 @@ pub(crate) async fn replay_transaction(
     ctx: Arc<Registry>,
     tx_hash: JsonH256,
     max_cycles: Option<Uint64>,
+    profile: bool,
 ) -> Result<Option<DebugRunResult>> {
     let tx_hash = to_h256(tx_hash);
     let max_cycles: Option<u64> = max_cycles.map(Into::into);
@@ -84,14 +89,33 @@ pub(crate) async fn replay_transaction(
         let tx = block.transactions().get(tx_index as usize).unwrap();
         let raw_tx = tx.raw();
         let t = Instant::now();
-        let run_result = ctx.debug_generator.execute_transaction(
-            &chain_view,
-            &mut hist_state,
-            &block_info,
-            &raw_tx,
-            max_cycles,
-            None,
-        )?;
+        let mut debug_profile = None;
+        let run_result = if profile {
+            // Use an unlimited cycles pool to collect the cycles of syscalls
+            let syscall_config = ctx.mem_pool_config.mem_block.syscall_cycles.clone();
+            let mut cycles_pool = CyclesPool::new(u64::MAX, syscall_config);
+            let mut profile = Profile::default();
+            let run_result = ctx.debug_generator.execute_transaction_with_profile(
+                &chain_view,
+                &mut hist_state,
+                &block_info,
+                &raw_tx,
+                max_cycles,
+                Some(&mut cycles_pool),
+                &mut profile,
+            )?;
+            debug_profile = Some(to_debug_profile(&profile));
+            run_result
+        } else {
+            ctx.debug_generator.execute_transaction(
+                &chain_view,
+                &mut hist_state,
+                &block_info,
+                &raw_tx,
+                max_cycles,
+                None,
+            )?
+        };
         let execution_time = t.elapsed();
 
         // finalise
@@ -103,6 +127,7 @@ pub(crate) async fn replay_transaction(
         let mut debug_run_result: DebugRunResult = run_result.try_into()?;
         debug_run_result.execution_time_ms = execution_time.as_millis().try_into()?;
         debug_run_result.write_mem_smt_time_ms = write_mem_smt_time.as_millis().try_into()?;
+        debug_run_result.profile = debug_profile;
 
         Result::<_, anyhow::Error>::Ok(debug_run_result)
     })
@@ -111,3 +136,20 @@ pub(crate) async fn replay_transaction(
     // generate response
     Ok(Some(run_result))
 }
+
+fn to_debug_profile(profile: &Profile) -> DebugProfile {
+    let syscalls = profile
+        .syscall_report()
+        .into_iter()
+        .map(|(code, name, p)| DebugSyscallProfile {
+            name: name.to_string(),
+            code: code.into(),
+            count: p.count.into(),
+            cycles: p.cycles.into(),
+        })
+        .collect();
+    DebugProfile {
+        folded: profile.folded_stacks(),
+        syscalls,
+    }
+}
@@ -0,0 +1,236 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use anyhow::{bail, Context, Result};
+use gw_jsonrpc_types::{
+    godwoken::{FastWithdrawalMatch, FastWithdrawalOffer, FastWithdrawalQuote},
+    JsonCalcHash,
+};
+use gw_store::traits::chain_store::ChainStore;
+use gw_types::{
+    bytes::Bytes,
+    core::ScriptHashType,
+    h256::*,
+    offchain::{CellInfo, CellStatus},
+    packed::{CellDep, OutPoint, Script},
+    prelude::*,
+};
+use gw_utils::{
+    fee::fill_tx_fee,
+    transaction_skeleton::{Signature, TransactionSkeleton},
+    withdrawal::{parse_lock_args, transfer_to_new_owner},
+};
+
+use crate::registry::Registry;
+
+/// Fee rate of the transfer transaction, in shannons per KB. The fee is paid
+/// by the liquidity provider.
+const TRANSFER_TX_FEE_RATE: u64 = 1000;
+/// Offer fee rates are in parts per 10000 of the withdrawal capacity.
+const FEE_RATE_DENOMINATOR: u64 = 10000;
+
+#[derive(Clone)]
+struct Offer {
+    lock: Script,
+    lock_dep: CellDep,
+    fee_rate: u64,
+    max_capacity: u64,
+}
+
+impl Offer {
+    fn quote(&self, capacity: u64) -> Result<FastWithdrawalQuote> {
+        if capacity > self.max_capacity {
+            bail!(
+                "withdrawal capacity {} exceeds the offer max capacity {}",
+                capacity,
+                self.max_capacity
+            );
+        }
+        // Round up, so the fee is never zero for a non-zero fee rate.
+        let fee = (capacity as u128 * self.fee_rate as u128 + FEE_RATE_DENOMINATOR as u128 - 1)
+            / FEE_RATE_DENOMINATOR as u128;
+        let fee = fee as u64;
+        Ok(FastWithdrawalQuote {
+            lp_lock_hash: self.lock.hash().into(),
+            capacity: capacity.into(),
+            fee: fee.into(),
+            payment: (capacity - fee).into(),
+        })
+    }
+}
+
+/// Offers of liquidity providers, indexed by lock hash. Offers are kept in
+/// memory, liquidity providers should submit them again after the node
+/// restarts.
+#[derive(Default)]
+pub(crate) struct OfferBook {
+    offers: Mutex<HashMap<H256, Offer>>,
+}
+
+impl OfferBook {
+    fn get(&self, lock_hash: &H256) -> Option<Offer> {
+        self.offers.lock().unwrap().get(lock_hash).cloned()
+    }
+
+    /// The offer with the lowest fee rate that can buy the withdrawal.
+    fn best(&self, capacity: u64) -> Option<Offer> {
+        let offers = self.offers.lock().unwrap();
+        let offers = offers.values().filter(|o| o.max_capacity >= capacity);
+        offers.min_by_key(|o| o.fee_rate).cloned()
+    }
+}
+
+/// The old withdrawal lock rejects `UnlockWithdrawalViaTransfer`, so the
+/// RPCs are only served after the `enable_fast_withdrawal` fork.
+fn check_fork(ctx: &Registry) -> Result<()> {
+    let tip_number: u64 = ctx
+        .store
+        .get_last_valid_tip_block()?
+        .raw()
+        .number()
+        .unpack();
+    let fork_config = ctx.generator.fork_config();
+    if !fork_config.fast_withdrawal_enabled(tip_number + 1) {
+        bail!("fast withdrawal is not enabled yet");
+    }
+    Ok(())
+}
+
+/// Replace the offer of the same lock, an offer with zero max capacity
+/// cancels the previous one.
+pub(crate) fn submit_offer(ctx: &Registry, offer: FastWithdrawalOffer) -> Result<()> {
+    check_fork(ctx)?;
+    let fee_rate = u64::from(offer.fee_rate.value());
+    if fee_rate > FEE_RATE_DENOMINATOR {
+        bail!("fee rate {} exceeds {}", fee_rate, FEE_RATE_DENOMINATOR);
+    }
+    let offer = Offer {
+        lock: offer.lock.into(),
+        lock_dep: offer.lock_dep.into(),
+        fee_rate,
+        max_capacity: offer.max_capacity.value(),
+    };
+
+    let lock_hash = offer.lock.hash();
+    let mut offers = ctx.fast_withdrawal_offers.offers.lock().unwrap();
+    if offer.max_capacity == 0 {
+        offers.remove(&lock_hash);
+    } else {
+        offers.insert(lock_hash, offer);
+    }
+    Ok(())
+}
+
+pub(crate) fn get_offers(ctx: &Registry) -> Vec<FastWithdrawalOffer> {
+    let offers = ctx.fast_withdrawal_offers.offers.lock().unwrap();
+    let to_json = |o: &Offer| FastWithdrawalOffer {
+        lock: o.lock.clone().into(),
+        lock_dep: o.lock_dep.clone().into(),
+        fee_rate: (o.fee_rate as u32).into(),
+        max_capacity: o.max_capacity.into(),
+    };
+    offers.values().map(to_json).collect()
+}
+
+/// Returns `None` if no offer can buy the withdrawal.
+pub(crate) async fn get_quote(
+    ctx: &Registry,
+    withdrawal_out_point: OutPoint,
+) -> Result<Option<FastWithdrawalQuote>> {
+    check_fork(ctx)?;
+    let withdrawal_cell = get_withdrawal_cell(ctx, withdrawal_out_point).await?;
+    let capacity = withdrawal_cell.output.capacity().unpack();
+    match ctx.fast_withdrawal_offers.best(capacity) {
+        Some(offer) => Ok(Some(offer.quote(capacity)?)),
+        None => Ok(None),
+    }
+}
+
+/// Build the transaction that pays the withdrawal owner and transfers the
+/// withdrawal cell to the liquidity provider.
+pub(crate) async fn match_offer(
+    ctx: &Registry,
+    withdrawal_out_point: OutPoint,
+    lp_lock_hash: H256,
+    owner_lock_dep: CellDep,
+) -> Result<FastWithdrawalMatch> {
+    check_fork(ctx)?;
+    let offer = ctx
+        .fast_withdrawal_offers
+        .get(&lp_lock_hash)
+        .context("offer not found")?;
+    let withdrawal_cell = get_withdrawal_cell(ctx, withdrawal_out_point).await?;
+    let quote = offer.quote(withdrawal_cell.output.capacity().unpack())?;
+    let contracts_dep = ctx
+        .contracts_dep_manager
+        .as_ref()
+        .context("contracts dep is unavailable")?
+        .load();
+
+    let owner_lock = {
+        let args: Bytes = withdrawal_cell.output.lock().args().unpack();
+        parse_lock_args(&args)?.owner_lock
+    };
+    let owner_cell = ctx
+        .rpc_client
+        .query_owner_cell(owner_lock, None)
+        .await?
+        .context("owner cell not found")?;
+    let transferred = transfer_to_new_owner(
+        withdrawal_cell,
+        owner_cell,
+        &offer.lock,
+        quote.payment.value(),
+        contracts_dep.withdrawal_cell_lock.clone().into(),
+    )?;
+
+    let omni_lock_code_hash = ctx.system_type_script_config.omni_lock.hash();
+    let mut tx_skeleton = TransactionSkeleton::new(omni_lock_code_hash.0);
+    tx_skeleton.cell_deps_mut().extend(transferred.deps);
+    tx_skeleton.cell_deps_mut().push(owner_lock_dep.clone());
+    if offer.lock_dep.as_slice() != owner_lock_dep.as_slice() {
+        tx_skeleton.cell_deps_mut().push(offer.lock_dep.clone());
+    }
+    tx_skeleton.inputs_mut().extend(transferred.inputs);
+    tx_skeleton.witnesses_mut().extend(transferred.witness_args);
+    tx_skeleton.outputs_mut().extend(transferred.outputs);
+    fill_tx_fee(
+        &mut tx_skeleton,
+        &ctx.rpc_client.indexer,
+        offer.lock.clone(),
+        TRANSFER_TX_FEE_RATE,
+    )
+    .await?;
+
+    let entries = tx_skeleton.signature_entries();
+    let placeholders = entries.iter().map(Signature::zero_bytes_from_entry);
+    let sealed = tx_skeleton.seal(&entries, placeholders.collect())?;
+    Ok(FastWithdrawalMatch {
+        quote,
+        transaction: sealed.transaction.into(),
+    })
+}
+
+/// Only live CKB withdrawal cells of this rollup can be bought.
+async fn get_withdrawal_cell(ctx: &Registry, out_point: OutPoint) -> Result<CellInfo> {
+    let cell = match ctx.rpc_client.get_cell(out_point).await? {
+        Some(cell) if cell.status == CellStatus::Live => cell.cell,
+        _ => None,
+    };
+    let cell = cell.context("withdrawal cell not found")?;
+
+    let lock = cell.output.lock();
+    if lock.code_hash() != ctx.rollup_config.withdrawal_script_type_hash()
+        || lock.hash_type() != ScriptHashType::Type.into()
+    {
+        bail!("not a withdrawal cell");
+    }
+    let args: Bytes = lock.args().unpack();
+    let parsed = parse_lock_args(&args)?;
+    if parsed.rollup_type_hash != ctx.generator.rollup_context().rollup_script_hash {
+        bail!("withdrawal cell of another rollup");
+    }
+    if cell.output.type_().to_opt().is_some() {
+        bail!("only CKB withdrawals are supported");
+    }
+    Ok(cell)
+}
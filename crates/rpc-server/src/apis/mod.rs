@@ -1 +1,3 @@
+pub mod backend_fork;
 pub mod debug;
+pub mod fast_withdrawal;
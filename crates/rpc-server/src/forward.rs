@@ -0,0 +1,320 @@
+//! Forward transactions and withdrawal requests from read-only nodes to the
+//! full node over p2p.
+//!
+//! A read-only node runs the stateless checks and signature verification
+//! locally, then forwards the request and waits for the acknowledgement. The
+//! full node submits the request as it's submitted by RPC, and acknowledges
+//! with the RPC error if it's rejected.
+//!
+//! Only the session to the configured full node, identified by the peer id of
+//! its dial address, is used to forward requests. Forwarding a request which
+//! is already waiting for its acknowledgement doesn't resend it, all callers
+//! share the acknowledgement.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context, Result};
+use futures::TryStreamExt;
+use gw_jsonrpc_types::godwoken::MolJsonBytes;
+use gw_p2p_network::{
+    peers::Peers, DirectionalSpawn, FnSpawn, P2P_FORWARD_PROTOCOL, P2P_FORWARD_PROTOCOL_NAME,
+};
+use gw_types::{
+    bytes::Bytes,
+    h256::*,
+    packed::{
+        ForwardAck, ForwardAckReader, ForwardRequest, ForwardRequestReader, ForwardRequestUnion,
+        L2Transaction, WithdrawalRequestExtra,
+    },
+    prelude::*,
+};
+use tentacle::{
+    builder::MetaBuilder,
+    multiaddr::MultiAddr,
+    secio::PeerId,
+    service::{ProtocolMeta, ServiceAsyncControl},
+    traits::ProtocolSpawn,
+    utils::extract_peer_id,
+    SessionId,
+};
+use tokio::sync::oneshot;
+
+use crate::registry::{gw_submit_l2transaction, gw_submit_withdrawal_request, Registry, Request};
+
+const FORWARD_ACK_TIMEOUT: Duration = Duration::from_secs(10);
+/// Keep forwarded requests for RPC queries until they are synced back from
+/// the full node.
+const FORWARDED_REQUEST_TTL: Duration = Duration::from_secs(60);
+
+struct ForwardSession {
+    id: SessionId,
+    control: ServiceAsyncControl,
+}
+
+/// Forward requests to the full node, used by read-only nodes.
+pub struct ForwardClient {
+    full_node: PeerId,
+    session: Mutex<Option<ForwardSession>>,
+    /// Callers waiting for the acknowledgement of an in-flight request.
+    pending_acks: Mutex<HashMap<H256, Vec<oneshot::Sender<ForwardAck>>>>,
+    forwarded: RwLock<HashMap<H256, (Request, Instant)>>,
+    peers: Arc<Peers>,
+}
+
+impl ForwardClient {
+    pub fn new(full_node: PeerId, peers: Arc<Peers>) -> Self {
+        Self {
+            full_node,
+            session: Default::default(),
+            pending_acks: Default::default(),
+            forwarded: Default::default(),
+            peers,
+        }
+    }
+
+    /// Forward to the peer of the first dial address with a peer id, e.g.
+    /// /ip4/1.2.3.4/tcp/443/p2p/QmNv..., `None` if there is no such address.
+    pub fn from_dial_addresses(dial: &[String], peers: Arc<Peers>) -> Option<Self> {
+        let full_node = dial
+            .iter()
+            .filter_map(|d| d.parse::<MultiAddr>().ok())
+            .find_map(|d| extract_peer_id(&d))?;
+        Some(Self::new(full_node, peers))
+    }
+
+    /// Forward the request and wait for the acknowledgement. Returns the
+    /// error of the full node if it rejects the request.
+    pub(crate) async fn forward(
+        &self,
+        request: Request,
+    ) -> Result<Result<(), jsonrpc_core::Error>> {
+        let (id, control) = {
+            let session = self.session.lock().unwrap();
+            let session = session.as_ref().context("not connected to the full node")?;
+            (session.id, session.control.clone())
+        };
+        let hash = request.hash().0;
+        let msg = match request {
+            Request::Tx(ref tx) => ForwardRequest::new_builder().set(tx.clone()).build(),
+            Request::Withdrawal(ref w) => ForwardRequest::new_builder().set(w.clone()).build(),
+        };
+
+        let (ack_tx, ack_rx) = oneshot::channel();
+        let in_flight = {
+            let mut pending_acks = self.pending_acks.lock().unwrap();
+            let waiters = pending_acks.entry(hash).or_default();
+            waiters.push(ack_tx);
+            waiters.len() > 1
+        };
+        let result = async {
+            // The request is sent by the first caller.
+            if !in_flight {
+                let msg = msg.as_bytes();
+                self.peers.record_sent(id, P2P_FORWARD_PROTOCOL, msg.len());
+                control
+                    .send_message_to(id, P2P_FORWARD_PROTOCOL, msg)
+                    .await?;
+            }
+            let ack = tokio::time::timeout(FORWARD_ACK_TIMEOUT, ack_rx)
+                .await
+                .context("wait for forward ack")?
+                .map_err(|_| anyhow!("forward session closed or request failed"))?;
+            anyhow::Ok(ack)
+        }
+        .await;
+        // Waiters of a failed request fail together, so that the request
+        // can be forwarded again.
+        if result.is_err() && !in_flight {
+            self.pending_acks.lock().unwrap().remove(&hash);
+        }
+
+        let error: Bytes = result?.error().unpack();
+        if !error.is_empty() {
+            let error = serde_json::from_slice(&error).context("parse forward error")?;
+            return Ok(Err(error));
+        }
+        self.track(hash, request);
+        Ok(Ok(()))
+    }
+
+    fn track(&self, hash: H256, request: Request) {
+        let mut forwarded = self.forwarded.write().unwrap();
+        forwarded.retain(|_, (_, at)| at.elapsed() < FORWARDED_REQUEST_TTL);
+        forwarded.insert(hash, (request, Instant::now()));
+    }
+
+    fn get(&self, hash: &H256) -> Option<Request> {
+        let forwarded = self.forwarded.read().unwrap();
+        let (request, at) = forwarded.get(hash)?;
+        (at.elapsed() < FORWARDED_REQUEST_TTL).then(|| request.clone())
+    }
+
+    pub(crate) fn get_transaction(&self, hash: &H256) -> Option<L2Transaction> {
+        match self.get(hash)? {
+            Request::Tx(tx) => Some(tx),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn get_withdrawal(&self, hash: &H256) -> Option<WithdrawalRequestExtra> {
+        match self.get(hash)? {
+            Request::Withdrawal(w) => Some(w),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn contains(&self, hash: &H256) -> bool {
+        self.get(hash).is_some()
+    }
+
+    fn handle_ack(&self, ack: ForwardAck) {
+        let hash: H256 = ack.hash().unpack();
+        let waiters = self.pending_acks.lock().unwrap().remove(&hash);
+        for ack_tx in waiters.into_iter().flatten() {
+            let _ = ack_tx.send(ack.clone());
+        }
+    }
+
+    fn close_session(&self, id: SessionId) {
+        let mut session = self.session.lock().unwrap();
+        if session.as_ref().map(|s| s.id) == Some(id) {
+            *session = None;
+            // Waiting requests fail immediately.
+            self.pending_acks.lock().unwrap().clear();
+        }
+    }
+}
+
+/// The p2p protocol of read-only nodes, receives acknowledgements from the
+/// full node.
+pub fn forward_client_protocol(client: Arc<ForwardClient>) -> ProtocolMeta {
+    forward_protocol(forward_client_spawn(client))
+}
+
+/// The p2p protocol of the full node, submits forwarded requests.
+pub fn forward_server_protocol(registry: Arc<Registry>) -> ProtocolMeta {
+    forward_protocol(forward_server_spawn(registry))
+}
+
+/// The p2p protocol of relays, forwards requests from downstream peers to the
+/// upstream peer.
+pub fn forward_relay_protocol(client: Arc<ForwardClient>, registry: Arc<Registry>) -> ProtocolMeta {
+    forward_protocol(DirectionalSpawn {
+        outbound: forward_client_spawn(client),
+        inbound: forward_server_spawn(registry),
+    })
+}
+
+fn forward_protocol(spawn: impl ProtocolSpawn + Send + Sync + 'static) -> ProtocolMeta {
+    MetaBuilder::new()
+        .name(|_| P2P_FORWARD_PROTOCOL_NAME.into())
+        .id(P2P_FORWARD_PROTOCOL)
+        .protocol_spawn(spawn)
+        .build()
+}
+
+fn forward_client_spawn(client: Arc<ForwardClient>) -> impl ProtocolSpawn + Send + Sync + 'static {
+    FnSpawn(move |context, control, mut read_part| {
+        let client = client.clone();
+        let id = context.id;
+        if extract_peer_id(&context.address).as_ref() != Some(&client.full_node) {
+            log::warn!(
+                "ignore forward session {} of {}, it isn't the full node",
+                id,
+                context.address
+            );
+            return;
+        }
+        client.peers.open_protocol(id, P2P_FORWARD_PROTOCOL);
+        *client.session.lock().unwrap() = Some(ForwardSession {
+            id,
+            control: control.clone(),
+        });
+        tokio::spawn(async move {
+            let result = async {
+                while let Some(msg) = read_part.try_next().await? {
+                    client
+                        .peers
+                        .record_received(id, P2P_FORWARD_PROTOCOL, msg.len());
+                    ForwardAckReader::from_slice(msg.as_ref())?;
+                    client.handle_ack(ForwardAck::new_unchecked(msg));
+                }
+                anyhow::Ok(())
+            }
+            .await;
+            if let Err(err) = result {
+                log::warn!("forward session {} error: {:#}", id, err);
+            }
+            client.close_session(id);
+        });
+    })
+}
+
+fn forward_server_spawn(registry: Arc<Registry>) -> impl ProtocolSpawn + Send + Sync + 'static {
+    FnSpawn(move |context, control, mut read_part| {
+        let registry = registry.clone();
+        let control = control.clone();
+        let session_id = context.id;
+        registry
+            .peers
+            .open_protocol(session_id, P2P_FORWARD_PROTOCOL);
+        tokio::spawn(async move {
+            while let Some(msg) = read_part.try_next().await? {
+                registry
+                    .peers
+                    .record_received(session_id, P2P_FORWARD_PROTOCOL, msg.len());
+                ForwardRequestReader::from_slice(msg.as_ref())?;
+                let request = ForwardRequest::new_unchecked(msg);
+                let registry = registry.clone();
+                let control = control.clone();
+                tokio::spawn(async move {
+                    let ack = submit_forwarded(&registry, request).await.as_bytes();
+                    registry
+                        .peers
+                        .record_sent(session_id, P2P_FORWARD_PROTOCOL, ack.len());
+                    let result = control
+                        .send_message_to(session_id, P2P_FORWARD_PROTOCOL, ack)
+                        .await;
+                    if let Err(err) = result {
+                        log::warn!("send forward ack to session {}: {}", session_id, err);
+                    }
+                });
+            }
+            anyhow::Ok(())
+        });
+    })
+}
+
+async fn submit_forwarded(registry: &Registry, request: ForwardRequest) -> ForwardAck {
+    let (hash, result) = match request.to_enum() {
+        ForwardRequestUnion::L2Transaction(tx) => {
+            let hash = tx.hash();
+            let result = gw_submit_l2transaction(registry, MolJsonBytes(tx)).await;
+            (hash, result.map(|_| ()))
+        }
+        ForwardRequestUnion::WithdrawalRequestExtra(withdrawal) => {
+            let hash = withdrawal.hash();
+            let result = gw_submit_withdrawal_request(registry, MolJsonBytes(withdrawal)).await;
+            (hash, result.map(|_| ()))
+        }
+    };
+    let error = match result {
+        Ok(()) => Vec::new(),
+        Err(err) => {
+            log::info!(
+                "[forward] reject request {}, err: {}",
+                faster_hex::hex_string(&hash),
+                err.0.message
+            );
+            serde_json::to_vec(&err.0).expect("serialize rpc error")
+        }
+    };
+    ForwardAck::new_builder()
+        .hash(hash.pack())
+        .error(error.pack())
+        .build()
+}
@@ -1,6 +1,8 @@
+pub mod forward;
 pub(crate) mod in_queue_request_map;
 pub mod registry;
 pub mod server;
 
 mod apis;
+mod signature_verifier;
 mod utils;
@@ -8,7 +8,7 @@ use std::{
 use anyhow::{anyhow, Context};
 use async_trait::async_trait;
 use gw_common::blake2b::new_blake2b;
-use gw_common::builtins::{CKB_SUDT_ACCOUNT_ID, ETH_REGISTRY_ACCOUNT_ID};
+use gw_common::builtins::{CKB_SUDT_ACCOUNT_ID, ETH_REGISTRY_ACCOUNT_ID, RESERVED_ACCOUNT_ID};
 use gw_common::state::State;
 use gw_config::{
     BackendForkConfig, ChainConfig, FeeConfig, GaslessTxSupportConfig, MemPoolConfig, NodeMode,
@@ -16,29 +16,37 @@ use gw_config::{
 };
 use gw_generator::backend_manage::BackendManage;
 use gw_generator::generator::CyclesPool;
+use gw_generator::session_key::get_session_keys;
 use gw_generator::utils::get_tx_type;
 use gw_generator::{
     error::TransactionError, sudt::build_l2_sudt_script,
     verification::transaction::TransactionVerifier, Generator,
 };
 use gw_jsonrpc_types::{
-    ckb_jsonrpc_types::{JsonBytes, Script, Uint32, Uint64},
+    ckb_jsonrpc_types::{CellDep, JsonBytes, OutPoint, Script, Uint32, Uint64},
     debug::DebugRunResult,
     godwoken::*,
     test_mode::TestModePayload,
     JsonCalcHash,
 };
+use gw_mem_pool::block_sync_server::BlockSyncServerState;
+use gw_mem_pool::custodian::to_custodian_cell;
 use gw_mem_pool::fee::{
     queue::FeeQueue,
     types::{FeeEntry, FeeItem, FeeItemKind, FeeItemSender},
 };
+use gw_mem_pool::pool::VerifiedSignature;
+use gw_p2p_network::peers::{PeerInfo, Peers};
 use gw_polyjuice_sender_recover::recover::PolyjuiceSenderRecover;
+use gw_rpc_client::contract::ContractsCellDepManager;
 use gw_rpc_client::rpc_client::RPCClient;
 use gw_store::state::history::history_state::RWConfig;
 use gw_store::state::{BlockStateDB, MemStateDB};
 use gw_store::{
-    chain_view::ChainView, mem_pool_state::MemPoolState, traits::chain_store::ChainStore,
-    CfMemStat, Store,
+    chain_view::ChainView,
+    mem_pool_state::MemPoolState,
+    traits::chain_store::{BlockStatus, ChainStore},
+    CfMemStat, CfStat, Store,
 };
 use gw_telemetry::traits::{TelemetryContext, TelemetryContextNewSpan, TelemetrySpanExt};
 use gw_traits::CodeStore;
@@ -46,10 +54,12 @@ use gw_types::packed::RawL2Transaction;
 use gw_types::{
     bytes::Bytes,
     h256::*,
+    offchain::{CellInfo, CellStatus, CellWithStatus, CompatibleFinalizedTimepoint, DepositInfo},
     packed::{self, BlockInfo, Byte32, L2Transaction, RollupConfig, WithdrawalRequestExtra},
     prelude::*,
     U256,
 };
+use gw_utils::timepoint::finalized_timepoint;
 use gw_utils::RollupContext;
 use gw_version::Version;
 use jsonrpc_core::{ErrorCode, MetaIoHandler};
@@ -58,11 +68,17 @@ use lru::LruCache;
 use once_cell::sync::Lazy;
 use pprof::ProfilerGuard;
 use std::collections::HashMap;
+use tentacle::SessionId;
 use tokio::sync::{mpsc, Mutex};
 use tracing::instrument;
 
+use crate::apis::backend_fork::schedule_backend_fork;
+pub use crate::apis::backend_fork::BackendForkScheduler;
 use crate::apis::debug::replay_transaction;
+use crate::apis::fast_withdrawal::{self, OfferBook};
+use crate::forward::ForwardClient;
 use crate::in_queue_request_map::{InQueueRequestHandle, InQueueRequestMap};
+use crate::signature_verifier::{verify_signature, SignatureVerifier};
 use crate::utils::{to_h256, to_jsonh256};
 
 static PROFILER_GUARD: Lazy<tokio::sync::Mutex<Option<ProfilerGuard>>> =
@@ -151,6 +167,7 @@ pub struct RequestContext {
     _in_queue_handle: InQueueRequestHandle,
     trace: gw_telemetry::Context,
     in_queue_span: tracing::Span,
+    verified_signature: Option<VerifiedSignature>,
 }
 
 impl TelemetryContext for RequestContext {
@@ -187,6 +204,11 @@ pub struct RegistryArgs {
     pub gasless_tx_support_config: Option<GaslessTxSupportConfig>,
     pub polyjuice_sender_recover: PolyjuiceSenderRecover,
     pub debug_backend_forks: Option<Vec<BackendForkConfig>>,
+    pub forward_client: Option<Arc<ForwardClient>>,
+    pub contracts_dep_manager: Option<ContractsCellDepManager>,
+    pub block_sync_server_state: Option<Arc<std::sync::Mutex<BlockSyncServerState>>>,
+    /// Peers of the p2p network, for peer management RPCs.
+    pub peers: Arc<Peers>,
 }
 
 pub struct Registry {
@@ -199,6 +221,7 @@ pub struct Registry {
     pub(crate) backend_info: Vec<BackendInfo>,
     pub(crate) node_mode: NodeMode,
     pub(crate) submit_tx: mpsc::Sender<(Request, RequestContext)>,
+    pub(crate) signature_verifier: Option<SignatureVerifier>,
     pub(crate) rpc_client: RPCClient,
     pub(crate) send_tx_rate_limit: Option<SendTransactionRateLimiter>,
     pub(crate) send_tx_rate_limit_config: Option<RPCRateLimit>,
@@ -209,9 +232,14 @@ pub struct Registry {
     pub(crate) in_queue_request_map: Option<Arc<InQueueRequestMap>>,
     pub(crate) polyjuice_sender_recover: Arc<PolyjuiceSenderRecover>,
     pub(crate) debug_generator: Arc<Generator>,
+    pub(crate) backend_fork_scheduler: Arc<BackendForkScheduler>,
     pub(crate) system_type_script_config: SystemTypeScriptConfig,
     pub(crate) system_type_scripts: SystemTypeScripts,
     pub(crate) fee_config: FeeConfig,
+    pub(crate) forward_client: Option<Arc<ForwardClient>>,
+    pub(crate) contracts_dep_manager: Option<ContractsCellDepManager>,
+    pub(crate) fast_withdrawal_offers: OfferBook,
+    pub(crate) peers: Arc<Peers>,
 }
 
 impl Registry {
@@ -233,6 +261,10 @@ impl Registry {
             polyjuice_sender_recover,
             debug_backend_forks,
             gasless_tx_support_config,
+            forward_client,
+            contracts_dep_manager,
+            block_sync_server_state,
+            peers,
         } = args;
 
         let backend_info = get_backend_info(generator.clone());
@@ -254,7 +286,14 @@ impl Registry {
         };
         let (submit_tx, submit_rx) = mpsc::channel(RequestSubmitter::MAX_CHANNEL_SIZE);
         let polyjuice_sender_recover = Arc::new(polyjuice_sender_recover);
+        let mut signature_verifier = None;
         if let Some(mem_pool) = mem_pool.as_ref().to_owned() {
+            signature_verifier = Some(SignatureVerifier::spawn(
+                generator.clone(),
+                mem_pool_state.clone(),
+                &mem_pool_config.signature_verifier,
+            ));
+
             let submitter = RequestSubmitter {
                 mem_pool: Arc::clone(mem_pool),
                 submit_rx,
@@ -286,6 +325,20 @@ impl Registry {
             }
         };
 
+        let backend_fork_scheduler = Arc::new(BackendForkScheduler::new(
+            store.clone(),
+            generator.clone(),
+            debug_generator.clone(),
+            server_config.backend_fork_admins.clone(),
+            block_sync_server_state,
+        ));
+        // Register forks scheduled before the restart
+        tokio::task::spawn_blocking({
+            let backend_fork_scheduler = backend_fork_scheduler.clone();
+            move || backend_fork_scheduler.reload()
+        })
+        .await??;
+
         let system_type_scripts = SystemTypeScripts {
             eoa: system_type_script_config
                 .allowed_eoa_scripts
@@ -311,6 +364,7 @@ impl Registry {
             backend_info,
             node_mode,
             submit_tx,
+            signature_verifier,
             rpc_client,
             send_tx_rate_limit,
             send_tx_rate_limit_config,
@@ -323,11 +377,20 @@ impl Registry {
             in_queue_request_map,
             polyjuice_sender_recover,
             debug_generator,
+            backend_fork_scheduler,
             system_type_scripts,
+            forward_client,
+            contracts_dep_manager,
+            fast_withdrawal_offers: OfferBook::default(),
+            peers,
         }
         .into())
     }
 
+    pub fn backend_fork_scheduler(&self) -> Arc<BackendForkScheduler> {
+        self.backend_fork_scheduler.clone()
+    }
+
     pub fn to_handler(self: Arc<Self>) -> MetaIoHandler<Option<Session>> {
         let mut handler = MetaIoHandler::with_compatibility(jsonrpc_core::Compatibility::V2);
         if let Some(ref tests_rpc_impl) = self.tests_rpc_impl {
@@ -345,14 +408,14 @@ pub(crate) enum Request {
 }
 
 impl Request {
-    fn kind(&self) -> &'static str {
+    pub(crate) fn kind(&self) -> &'static str {
         match self {
             Request::Tx(_) => "tx",
             Request::Withdrawal(_) => "withdrawal",
         }
     }
 
-    fn hash(&self) -> ckb_types::H256 {
+    pub(crate) fn hash(&self) -> ckb_types::H256 {
         match self {
             Request::Tx(tx) => ckb_types::H256(tx.hash()),
             Request::Withdrawal(withdrawal) => ckb_types::H256(withdrawal.hash()),
@@ -389,6 +452,15 @@ struct RequestSubmitter {
     gasless_tx_support_config: Option<GaslessTxSupportConfig>,
 }
 
+fn mem_block_number(mem_pool_state: &MemPoolState) -> u64 {
+    mem_pool_state
+        .load_shared()
+        .mem_block
+        .as_ref()
+        .map(|block_info| block_info.number().unpack())
+        .unwrap_or_default()
+}
+
 #[instrument(skip_all, fields(req_kind = req.kind()))]
 fn req_to_entry(
     fee_config: &FeeConfig,
@@ -396,6 +468,7 @@ fn req_to_entry(
     generator: Arc<Generator>,
     req: Request,
     state: &(impl State + CodeStore),
+    block_number: u64,
     order: usize,
 ) -> anyhow::Result<FeeEntry> {
     match req {
@@ -403,13 +476,15 @@ fn req_to_entry(
             let receiver: u32 = tx.raw().to_id().unpack();
             let script_hash = state.get_script_hash(receiver)?;
             let backend_type = generator
-                .load_backend_and_block_consensus(0, state, &script_hash)
+                .load_backend(0, state, &script_hash)
                 .ok_or_else(|| anyhow!("can't find backend for receiver: {}", receiver))?
-                .0
                 .backend_type;
+            let sponsored_gas_price =
+                generator.get_sponsored_gas_price(state, &tx.raw(), block_number)?;
             FeeEntry::from_tx(
                 tx,
                 gasless_tx_support_config,
+                sponsored_gas_price,
                 fee_config,
                 backend_type,
                 order,
@@ -522,6 +597,7 @@ impl RequestSubmitter {
                     self.generator.clone(),
                     req,
                     &state,
+                    mem_block_number(&self.mem_pool_state),
                     queue_order.next(queue),
                 ) {
                     Ok(entry) => {
@@ -561,6 +637,7 @@ impl RequestSubmitter {
                     self.generator.clone(),
                     req,
                     &state,
+                    mem_block_number(&self.mem_pool_state),
                     queue_order.next(queue),
                 ) {
                     Ok(entry) => {
@@ -690,7 +767,10 @@ impl RequestSubmitter {
 
                             mem_pool.push_transaction(tx)
                         }
-                        FeeItem::Tx(tx) => mem_pool.push_transaction(tx),
+                        FeeItem::Tx(tx) => match ctx.verified_signature {
+                            Some(verified) => mem_pool.push_verified_transaction(tx, verified),
+                            None => mem_pool.push_transaction(tx),
+                        },
                         FeeItem::Withdrawal(withdrawal) => {
                             mem_pool.push_withdrawal_request(withdrawal).await
                         }
@@ -758,11 +838,14 @@ pub trait GwRpc {
         &self,
         withdrawal_request: WithdrawalRequestExtraJsonBytes,
     ) -> Result<JsonH256>;
+    async fn gw_revoke_session_key(&self, l2tx: L2TransactionJsonBytes)
+        -> Result<Option<JsonH256>>;
     async fn gw_get_withdrawal(
         &self,
         hash: JsonH256,
         verbose: Option<GetVerbose>,
     ) -> Result<Option<WithdrawalWithStatus>>;
+    async fn gw_get_deposit(&self, lookup: DepositLookup) -> Result<Option<DepositWithStatus>>;
     async fn gw_get_balance(
         &self,
         address: RegistryAddressJsonBytes,
@@ -784,6 +867,11 @@ pub trait GwRpc {
         account_id: AccountID,
         block_number: Option<Uint64>,
     ) -> Result<Uint32>;
+    async fn gw_get_session_keys(
+        &self,
+        account_id: AccountID,
+        block_number: Option<Uint64>,
+    ) -> Result<Vec<SessionKey>>;
     async fn gw_get_script(&self, script_hash: JsonH256) -> Result<Option<Script>>;
     async fn gw_get_script_hash(&self, account_id: AccountID) -> Result<JsonH256>;
     async fn gw_get_script_hash_by_registry_address(
@@ -814,12 +902,42 @@ pub trait GwRpc {
     async fn gw_report_pprof(&self) -> Result<()>;
 
     async fn gw_get_rocksdb_memory_stats(&self) -> Result<Vec<CfMemStat>>;
+    async fn gw_get_rocksdb_cf_stats(&self) -> Result<Vec<CfStat>>;
+    async fn gw_compact_rocksdb_column(
+        &self,
+        column: Uint32,
+        begin: Option<JsonBytes>,
+        end: Option<JsonBytes>,
+    ) -> Result<()>;
     async fn gw_dump_jemalloc_profiling(&self) -> Result<()>;
+    async fn gw_schedule_backend_fork(
+        &self,
+        fork: BackendForkConfig,
+        signature: JsonBytes,
+    ) -> Result<()>;
+    async fn gw_get_peers(&self) -> Result<Vec<P2PPeer>>;
+    async fn gw_dial_peer(&self, address: String) -> Result<()>;
+    async fn gw_disconnect_peer(&self, session_id: Uint64) -> Result<()>;
+    async fn gw_get_allowed_peer_ids(&self) -> Result<Option<Vec<String>>>;
+    async fn gw_set_allowed_peer_ids(&self, peer_ids: Option<Vec<String>>) -> Result<()>;
+    async fn gw_submit_fast_withdrawal_offer(&self, offer: FastWithdrawalOffer) -> Result<()>;
+    async fn gw_get_fast_withdrawal_offers(&self) -> Result<Vec<FastWithdrawalOffer>>;
+    async fn gw_get_fast_withdrawal_quote(
+        &self,
+        withdrawal_out_point: OutPoint,
+    ) -> Result<Option<FastWithdrawalQuote>>;
+    async fn gw_match_fast_withdrawal(
+        &self,
+        withdrawal_out_point: OutPoint,
+        lp_lock_hash: JsonH256,
+        owner_lock_dep: CellDep,
+    ) -> Result<FastWithdrawalMatch>;
 
     async fn debug_replay_transaction(
         &self,
         tx_hash: JsonH256,
         max_cycles: Option<Uint64>,
+        profile: Option<bool>,
     ) -> Result<Option<DebugRunResult>>;
 }
 
@@ -851,7 +969,11 @@ impl GwRpc for Arc<Registry> {
         Ok(self
             .in_queue_request_map
             .as_deref()
-            .map_or(false, |m| m.contains(&hash)))
+            .map_or(false, |m| m.contains(&hash))
+            || self
+                .forward_client
+                .as_deref()
+                .map_or(false, |c| c.contains(&hash)))
     }
     async fn gw_get_block_committed_info(
         &self,
@@ -889,8 +1011,10 @@ impl GwRpc for Arc<Registry> {
         &self,
         l2tx: L2TransactionJsonBytes,
     ) -> Result<Option<JsonH256>> {
-        if self.node_mode == NodeMode::ReadOnly {
-            return Err(method_not_found());
+        match self.node_mode {
+            NodeMode::ReadOnly => return gw_forward_l2transaction(self, l2tx).await,
+            NodeMode::Watchtower => return Err(method_not_found()),
+            NodeMode::FullNode | NodeMode::Test => {}
         }
         gw_submit_l2transaction(self, l2tx).await
     }
@@ -898,11 +1022,24 @@ impl GwRpc for Arc<Registry> {
         &self,
         withdrawal_request: WithdrawalRequestExtraJsonBytes,
     ) -> Result<JsonH256> {
-        if self.node_mode == NodeMode::ReadOnly {
-            return Err(method_not_found());
+        match self.node_mode {
+            NodeMode::ReadOnly => {
+                return gw_forward_withdrawal_request(self, withdrawal_request).await
+            }
+            NodeMode::Watchtower => return Err(method_not_found()),
+            NodeMode::FullNode | NodeMode::Test => {}
         }
         gw_submit_withdrawal_request(self, withdrawal_request).await
     }
+    async fn gw_revoke_session_key(
+        &self,
+        l2tx: L2TransactionJsonBytes,
+    ) -> Result<Option<JsonH256>> {
+        if matches!(self.node_mode, NodeMode::ReadOnly | NodeMode::Watchtower) {
+            return Err(method_not_found());
+        }
+        gw_revoke_session_key(self, l2tx).await
+    }
     async fn gw_get_withdrawal(
         &self,
         hash: JsonH256,
@@ -910,6 +1047,9 @@ impl GwRpc for Arc<Registry> {
     ) -> Result<Option<WithdrawalWithStatus>> {
         gw_get_withdrawal(self, hash, verbose).await
     }
+    async fn gw_get_deposit(&self, lookup: DepositLookup) -> Result<Option<DepositWithStatus>> {
+        gw_get_deposit(self, lookup).await
+    }
     async fn gw_get_balance(
         &self,
         address: RegistryAddressJsonBytes,
@@ -939,6 +1079,13 @@ impl GwRpc for Arc<Registry> {
     ) -> Result<Uint32> {
         gw_get_nonce(self, account_id, block_number).await
     }
+    async fn gw_get_session_keys(
+        &self,
+        account_id: AccountID,
+        block_number: Option<Uint64>,
+    ) -> Result<Vec<SessionKey>> {
+        gw_get_session_keys(self, account_id, block_number).await
+    }
     async fn gw_get_script(&self, script_hash: JsonH256) -> Result<Option<Script>> {
         gw_get_script(self, script_hash).await
     }
@@ -990,6 +1137,13 @@ impl GwRpc for Arc<Registry> {
             &self.system_type_scripts,
         );
         let eoa_scripts = to_eoa_scripts(&self.rollup_config, &self.system_type_scripts);
+        let tip_number = self
+            .store
+            .get_last_valid_tip_block()?
+            .raw()
+            .number()
+            .unpack();
+        let staged_backend_forks = get_staged_backend_forks(&self.generator, tip_number);
 
         Ok(NodeInfo {
             mode,
@@ -1000,6 +1154,7 @@ impl GwRpc for Arc<Registry> {
             gw_scripts,
             eoa_scripts,
             gasless_tx_support: self.gasless_tx_support_config.clone(),
+            staged_backend_forks,
         })
     }
     #[instrument(skip_all)]
@@ -1094,6 +1249,52 @@ impl GwRpc for Arc<Registry> {
         Ok(self.store.gather_mem_stats())
     }
     #[instrument(skip_all)]
+    async fn gw_get_rocksdb_cf_stats(&self) -> Result<Vec<CfStat>> {
+        if !self
+            .server_config
+            .enable_methods
+            .contains(&RPCMethods::Test)
+        {
+            return Err(method_not_found());
+        }
+
+        Ok(self.store.gather_cf_stats())
+    }
+    #[instrument(skip_all)]
+    async fn gw_compact_rocksdb_column(
+        &self,
+        column: Uint32,
+        begin: Option<JsonBytes>,
+        end: Option<JsonBytes>,
+    ) -> Result<()> {
+        if !self
+            .server_config
+            .enable_methods
+            .contains(&RPCMethods::Admin)
+        {
+            return Err(method_not_found());
+        }
+
+        let column: u32 = column.value();
+        let store = self.store.clone();
+        tokio::task::spawn_blocking(move || {
+            let t = Instant::now();
+            store.compact_range(
+                column as usize,
+                begin.as_ref().map(|b| b.as_bytes()),
+                end.as_ref().map(|b| b.as_bytes()),
+            )?;
+            log::info!(
+                "compact column {} done: {}ms",
+                column,
+                t.elapsed().as_millis()
+            );
+            anyhow::Ok(())
+        })
+        .await??;
+        Ok(())
+    }
+    #[instrument(skip_all)]
     async fn gw_dump_jemalloc_profiling(&self) -> Result<()> {
         if !self
             .server_config
@@ -1129,11 +1330,168 @@ impl GwRpc for Arc<Registry> {
         Ok(())
     }
 
+    #[instrument(skip_all)]
+    async fn gw_schedule_backend_fork(
+        &self,
+        fork: BackendForkConfig,
+        signature: JsonBytes,
+    ) -> Result<()> {
+        if !self
+            .server_config
+            .enable_methods
+            .contains(&RPCMethods::Admin)
+        {
+            return Err(method_not_found());
+        }
+
+        schedule_backend_fork(self.clone(), fork, signature)
+            .await
+            .map_err(|err| rpc_error(ErrorCode::InvalidRequest, err.to_string()))
+    }
+
+    #[instrument(skip_all)]
+    async fn gw_get_peers(&self) -> Result<Vec<P2PPeer>> {
+        if !self
+            .server_config
+            .enable_methods
+            .contains(&RPCMethods::Admin)
+        {
+            return Err(method_not_found());
+        }
+        Ok(gw_get_peers(&self.peers))
+    }
+
+    #[instrument(skip_all)]
+    async fn gw_dial_peer(&self, address: String) -> Result<()> {
+        if !self
+            .server_config
+            .enable_methods
+            .contains(&RPCMethods::Admin)
+        {
+            return Err(method_not_found());
+        }
+        self.peers
+            .dial(&address)
+            .await
+            .map_err(|err| rpc_error(ErrorCode::InvalidRequest, format!("{:#}", err)))
+    }
+
+    #[instrument(skip_all)]
+    async fn gw_disconnect_peer(&self, session_id: Uint64) -> Result<()> {
+        if !self
+            .server_config
+            .enable_methods
+            .contains(&RPCMethods::Admin)
+        {
+            return Err(method_not_found());
+        }
+        let session_id = SessionId::new(session_id.value() as usize);
+        self.peers
+            .disconnect(session_id)
+            .await
+            .map_err(|err| rpc_error(ErrorCode::InvalidRequest, format!("{:#}", err)))
+    }
+
+    #[instrument(skip_all)]
+    async fn gw_get_allowed_peer_ids(&self) -> Result<Option<Vec<String>>> {
+        if !self
+            .server_config
+            .enable_methods
+            .contains(&RPCMethods::Admin)
+        {
+            return Err(method_not_found());
+        }
+        let allowed = self.peers.allowed_peer_ids();
+        Ok(allowed.map(|a| a.iter().map(|p| p.to_base58()).collect()))
+    }
+
+    #[instrument(skip_all)]
+    async fn gw_set_allowed_peer_ids(&self, peer_ids: Option<Vec<String>>) -> Result<()> {
+        if !self
+            .server_config
+            .enable_methods
+            .contains(&RPCMethods::Admin)
+        {
+            return Err(method_not_found());
+        }
+        self.peers
+            .set_allowed_peer_ids(peer_ids)
+            .await
+            .map_err(|err| rpc_error(ErrorCode::InvalidRequest, format!("{:#}", err)))
+    }
+
+    #[instrument(skip_all)]
+    async fn gw_submit_fast_withdrawal_offer(&self, offer: FastWithdrawalOffer) -> Result<()> {
+        if !self
+            .server_config
+            .enable_methods
+            .contains(&RPCMethods::FastWithdrawal)
+        {
+            return Err(method_not_found());
+        }
+        fast_withdrawal::submit_offer(self, offer)
+            .map_err(|err| rpc_error(ErrorCode::InvalidParams, format!("{:#}", err)))
+    }
+
+    #[instrument(skip_all)]
+    async fn gw_get_fast_withdrawal_offers(&self) -> Result<Vec<FastWithdrawalOffer>> {
+        if !self
+            .server_config
+            .enable_methods
+            .contains(&RPCMethods::FastWithdrawal)
+        {
+            return Err(method_not_found());
+        }
+        Ok(fast_withdrawal::get_offers(self))
+    }
+
+    #[instrument(skip_all)]
+    async fn gw_get_fast_withdrawal_quote(
+        &self,
+        withdrawal_out_point: OutPoint,
+    ) -> Result<Option<FastWithdrawalQuote>> {
+        if !self
+            .server_config
+            .enable_methods
+            .contains(&RPCMethods::FastWithdrawal)
+        {
+            return Err(method_not_found());
+        }
+        fast_withdrawal::get_quote(self, withdrawal_out_point.into())
+            .await
+            .map_err(|err| rpc_error(ErrorCode::InvalidRequest, format!("{:#}", err)))
+    }
+
+    #[instrument(skip_all)]
+    async fn gw_match_fast_withdrawal(
+        &self,
+        withdrawal_out_point: OutPoint,
+        lp_lock_hash: JsonH256,
+        owner_lock_dep: CellDep,
+    ) -> Result<FastWithdrawalMatch> {
+        if !self
+            .server_config
+            .enable_methods
+            .contains(&RPCMethods::FastWithdrawal)
+        {
+            return Err(method_not_found());
+        }
+        fast_withdrawal::match_offer(
+            self,
+            withdrawal_out_point.into(),
+            to_h256(lp_lock_hash),
+            owner_lock_dep.into(),
+        )
+        .await
+        .map_err(|err| rpc_error(ErrorCode::InvalidRequest, format!("{:#}", err)))
+    }
+
     #[instrument(skip_all)]
     async fn debug_replay_transaction(
         &self,
         tx_hash: JsonH256,
         max_cycles: Option<Uint64>,
+        profile: Option<bool>,
     ) -> Result<Option<DebugRunResult>> {
         if !self
             .server_config
@@ -1143,10 +1501,33 @@ impl GwRpc for Arc<Registry> {
             return Err(method_not_found());
         }
 
-        Ok(replay_transaction(self.clone(), tx_hash, max_cycles).await?)
+        let profile = profile.unwrap_or(false);
+        Ok(replay_transaction(self.clone(), tx_hash, max_cycles, profile).await?)
     }
 }
 
+fn gw_get_peers(peers: &Peers) -> Vec<P2PPeer> {
+    let to_peer = |p: PeerInfo| P2PPeer {
+        session_id: (p.session_id.value() as u64).into(),
+        address: p.address.to_string(),
+        peer_id: p.peer_id.map(|p| p.to_base58()),
+        inbound: p.inbound,
+        uptime_secs: p.uptime.as_secs().into(),
+        protocols: p
+            .protocols
+            .into_iter()
+            .map(|(protocol, traffic)| P2PProtocolTraffic {
+                protocol: protocol.into(),
+                received_messages: traffic.received_messages.into(),
+                received_bytes: traffic.received_bytes.into(),
+                sent_messages: traffic.sent_messages.into(),
+                sent_bytes: traffic.sent_bytes.into(),
+            })
+            .collect(),
+    };
+    peers.list().into_iter().map(to_peer).collect()
+}
+
 #[instrument(skip_all)]
 async fn gw_get_transaction(
     ctx: &Registry,
@@ -1175,7 +1556,12 @@ async fn gw_get_transaction(
             status = L2TransactionStatus::Committed;
         }
         None => {
-            tx_opt = db.get_mem_pool_transaction(&tx_hash)?;
+            tx_opt = db.get_mem_pool_transaction(&tx_hash)?.or_else(|| {
+                // Forwarded to the full node but not synced back yet
+                ctx.forward_client
+                    .as_deref()
+                    .and_then(|c| c.get_transaction(&tx_hash))
+            });
             status = L2TransactionStatus::Pending;
         }
     };
@@ -1424,9 +1810,10 @@ async fn gw_execute_l2transaction(
             polyjuice_creator_id,
             ctx.generator.fork_config(),
         )
-        .verify(&tx, block_info.number().unpack())?;
+        .verify(&tx, number)?;
         // verify tx signature
-        ctx.generator.check_transaction_signature(&state, &tx)?;
+        ctx.generator
+            .check_transaction_signature(&state, &tx, number)?;
         // execute tx
         let raw_tx = tx.raw();
         let run_result = ctx.generator.execute_transaction(
@@ -1618,8 +2005,82 @@ async fn gw_execute_raw_l2transaction(
     Ok(run_result.into())
 }
 
+fn check_tx_size(ctx: &Registry, tx: &L2Transaction) -> Result<()> {
+    let block_number = mem_block_number(&ctx.mem_pool_state);
+    let max_tx_size = ctx.generator.fork_config().max_tx_size(block_number);
+    if tx.as_slice().len() > max_tx_size {
+        let err = TransactionError::ExceededMaxTxSize {
+            max_size: max_tx_size,
+            tx_size: tx.as_slice().len(),
+        };
+        return Err(rpc_error(ErrorCode::InvalidRequest, err.to_string()));
+    }
+    Ok(())
+}
+
+async fn verify_tx_signature(ctx: &Registry, tx: &L2Transaction) -> Result<VerifiedSignature> {
+    let result = match ctx.signature_verifier {
+        Some(ref verifier) => {
+            let result_rx = verifier.try_verify(tx.clone()).map_err(|err| match err {
+                mpsc::error::TrySendError::Full(_) => {
+                    rpc_error(BUSY_ERR_CODE, "signature verifier busy")
+                }
+                e => e.into(),
+            })?;
+            result_rx
+                .await
+                .map_err(|_| anyhow!("signature verifier dropped tx"))?
+        }
+        None => {
+            let generator = ctx.generator.clone();
+            let mem_pool_state = ctx.mem_pool_state.clone();
+            let tx = tx.clone();
+            tokio::task::spawn_blocking(move || {
+                let state = mem_pool_state.load_state_db();
+                let block_number = mem_block_number(&mem_pool_state);
+                verify_signature(&generator, &state, &tx, block_number)
+            })
+            .await?
+        }
+    };
+    result.map_err(|err| {
+        log::info!(
+            "[RPC] reject to submit tx {:?}, err: {}",
+            faster_hex::hex_string(&tx.hash()),
+            err
+        );
+        rpc_error(ErrorCode::InvalidRequest, err.to_string())
+    })
+}
+
+/// Forward the transaction to the full node, used by read-only nodes.
 #[instrument(skip_all)]
-async fn gw_submit_l2transaction(
+async fn gw_forward_l2transaction(
+    ctx: &Registry,
+    l2tx: L2TransactionJsonBytes,
+) -> Result<Option<JsonH256>> {
+    let forward_client = ctx.forward_client.as_ref().ok_or_else(method_not_found)?;
+    let tx = l2tx.0;
+    let sender_id: u32 = tx.raw().from_id().unpack();
+
+    check_tx_size(ctx, &tx)?;
+    // tx from zero is verified by the full node after its sender account is created
+    if 0 != sender_id {
+        verify_tx_signature(ctx, &tx).await?;
+    }
+
+    let tx_hash = tx.hash();
+    forward_client
+        .forward(Request::Tx(tx))
+        .await?
+        .map_err(MyRpcError)?;
+
+    // Return None for tx from zero because its from id will be updated after account creation.
+    Ok((0 != sender_id).then(|| to_jsonh256(tx_hash)))
+}
+
+#[instrument(skip_all)]
+pub(crate) async fn gw_submit_l2transaction(
     ctx: &Registry,
     l2tx: L2TransactionJsonBytes,
 ) -> Result<Option<JsonH256>> {
@@ -1658,27 +2119,7 @@ async fn gw_submit_l2transaction(
     }
 
     // TODO use TransactionVerifier after remove sender auto creator
-    // verify tx size
-    {
-        // block info
-        let block_info = ctx
-            .mem_pool_state
-            .load_shared()
-            .mem_block
-            .expect("mem block info");
-        // check tx size
-        let max_tx_size = ctx
-            .generator
-            .fork_config()
-            .max_tx_size(block_info.number().unpack());
-        if tx.as_slice().len() > max_tx_size {
-            let err = TransactionError::ExceededMaxTxSize {
-                max_size: max_tx_size,
-                tx_size: tx.as_slice().len(),
-            };
-            return Err(rpc_error(ErrorCode::InvalidRequest, err.to_string()));
-        }
-    }
+    check_tx_size(ctx, &tx)?;
 
     // check sender's nonce
     {
@@ -1706,6 +2147,13 @@ async fn gw_submit_l2transaction(
         }
     }
 
+    // verify signature, tx from zero is verified after its sender account is created
+    let verified_signature = if 0 != sender_id && ctx.signature_verifier.is_some() {
+        Some(verify_tx_signature(ctx, &tx).await?)
+    } else {
+        None
+    };
+
     let permit = ctx.submit_tx.try_reserve().map_err(|err| match err {
         mpsc::error::TrySendError::Full(_) => rpc_error(BUSY_ERR_CODE, "mem pool service busy"),
         e => e.into(),
@@ -1737,6 +2185,7 @@ async fn gw_submit_l2transaction(
             _in_queue_handle: handle,
             trace: gw_telemetry::current_context(),
             in_queue_span,
+            verified_signature,
         };
         permit.send((request, ctx));
     }
@@ -1744,14 +2193,30 @@ async fn gw_submit_l2transaction(
     Ok(tx_hash_json)
 }
 
+/// Submit a `RevokeSessionKey` transaction, other transactions are rejected.
 #[instrument(skip_all)]
-async fn gw_submit_withdrawal_request(
+async fn gw_revoke_session_key(
     ctx: &Registry,
-    withdrawal: WithdrawalRequestExtraJsonBytes,
-) -> Result<JsonH256> {
-    let withdrawal = withdrawal.0;
-    let withdrawal_hash = withdrawal.hash();
+    l2tx: L2TransactionJsonBytes,
+) -> Result<Option<JsonH256>> {
+    let raw_tx = l2tx.0.raw();
+    let to_id: u32 = raw_tx.to_id().unpack();
+    let args: Bytes = raw_tx.args().unpack();
+    let is_revoke = to_id == RESERVED_ACCOUNT_ID
+        && matches!(
+            packed::MetaContractArgsReader::from_slice(&args).map(|args| args.to_enum()),
+            Ok(packed::MetaContractArgsUnionReader::RevokeSessionKey(_))
+        );
+    if !is_revoke {
+        return Err(rpc_error(
+            ErrorCode::InvalidRequest,
+            "not a RevokeSessionKey transaction of the Meta contract",
+        ));
+    }
+    gw_submit_l2transaction(ctx, l2tx).await
+}
 
+fn check_withdrawal(ctx: &Registry, withdrawal: &WithdrawalRequestExtra) -> Result<()> {
     let last_valid = ctx.store.get_last_valid_tip_block_hash()?;
     let last_valid = ctx
         .store
@@ -1774,9 +2239,54 @@ async fn gw_submit_withdrawal_request(
             ),
         ));
     }
-    if let Err(err) = withdrawal_generator.verified_output(&withdrawal, &Default::default()) {
+    if let Err(err) = withdrawal_generator.verified_output(withdrawal, &Default::default()) {
         return Err(rpc_error(ErrorCode::InvalidRequest, err.to_string()));
     }
+    Ok(())
+}
+
+/// Forward the withdrawal request to the full node, used by read-only nodes.
+#[instrument(skip_all)]
+async fn gw_forward_withdrawal_request(
+    ctx: &Registry,
+    withdrawal: WithdrawalRequestExtraJsonBytes,
+) -> Result<JsonH256> {
+    let forward_client = ctx.forward_client.as_ref().ok_or_else(method_not_found)?;
+    let withdrawal = withdrawal.0;
+    let withdrawal_hash = withdrawal.hash();
+
+    check_withdrawal(ctx, &withdrawal)?;
+    {
+        let generator = ctx.generator.clone();
+        let mem_pool_state = ctx.mem_pool_state.clone();
+        let withdrawal = withdrawal.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let state = mem_pool_state.load_state_db();
+            generator.check_withdrawal_signature(&state, &withdrawal)
+        })
+        .await?;
+        if let Err(err) = result {
+            return Err(rpc_error(ErrorCode::InvalidRequest, err.to_string()));
+        }
+    }
+
+    forward_client
+        .forward(Request::Withdrawal(withdrawal))
+        .await?
+        .map_err(MyRpcError)?;
+
+    Ok(withdrawal_hash.into())
+}
+
+#[instrument(skip_all)]
+pub(crate) async fn gw_submit_withdrawal_request(
+    ctx: &Registry,
+    withdrawal: WithdrawalRequestExtraJsonBytes,
+) -> Result<JsonH256> {
+    let withdrawal = withdrawal.0;
+    let withdrawal_hash = withdrawal.hash();
+
+    check_withdrawal(ctx, &withdrawal)?;
 
     let permit = ctx.submit_tx.try_reserve().map_err(|err| match err {
         mpsc::error::TrySendError::Full(_) => rpc_error(BUSY_ERR_CODE, "mem pool service busy"),
@@ -1798,6 +2308,7 @@ async fn gw_submit_withdrawal_request(
             _in_queue_handle: handle,
             trace: gw_telemetry::current_context(),
             in_queue_span,
+            verified_signature: None,
         };
         permit.send((request, ctx));
     }
@@ -1857,9 +2368,299 @@ async fn gw_get_withdrawal(
             }));
         }
     }
+    // Forwarded to the full node but not synced back yet
+    if let Some(w) = ctx
+        .forward_client
+        .as_deref()
+        .and_then(|c| c.get_withdrawal(&withdrawal_hash))
+    {
+        return Ok(Some(WithdrawalWithStatus {
+            withdrawal: verbose.verbose().then(|| w.into()),
+            status: WithdrawalStatus::Pending,
+            ..Default::default()
+        }));
+    }
     Ok(None)
 }
 
+#[instrument(skip_all)]
+async fn gw_get_deposit(
+    ctx: &Registry,
+    lookup: DepositLookup,
+) -> Result<Option<DepositWithStatus>> {
+    let out_point: packed::OutPoint = match lookup {
+        DepositLookup::OutPoint(out_point) => out_point.into(),
+        DepositLookup::TxHash(tx_hash) => {
+            let tx = match ctx
+                .rpc_client
+                .ckb
+                .get_packed_transaction(to_h256(tx_hash))
+                .await?
+            {
+                Some(tx) => tx,
+                None => return Ok(None),
+            };
+            match find_deposit_out_point(&ctx.rpc_client, &tx) {
+                Some(out_point) => out_point,
+                None => return Ok(None),
+            }
+        }
+    };
+    let account_id = |request: &packed::DepositRequest| -> anyhow::Result<Option<Uint32>> {
+        let state = ctx.mem_pool_state.load_state_db();
+        let script_hash = request.script().hash();
+        Ok(state
+            .get_account_id_by_script_hash(&script_hash)?
+            .map(Into::into))
+    };
+
+    // Check the mem block first, the deposit may be included in a new block
+    // while we are checking the store.
+    if let Some(mem_pool) = ctx.mem_pool.as_ref() {
+        let request = {
+            let mem_pool = mem_pool.lock().await;
+            let deposits = mem_pool.mem_block().deposits();
+            let deposit = deposits.iter().find(|d| d.cell.out_point == out_point);
+            deposit.map(|d| d.request.clone())
+        };
+        if let Some(request) = request {
+            return Ok(Some(DepositWithStatus {
+                out_point: out_point.into(),
+                status: DepositStatus::MemBlock,
+                account_id: account_id(&request)?,
+                request: Some(request.into()),
+                ..Default::default()
+            }));
+        }
+    }
+
+    let db = ctx.store.get_snapshot();
+    if let Some(deposit_key) = db.get_deposit_key(&out_point) {
+        let block_number: u64 = deposit_key.block_number().unpack();
+        let deposit_index: u32 = deposit_key.index().unpack();
+        let deposit_infos: Vec<DepositInfo> = db
+            .get_block_deposit_info_vec(block_number)
+            .context("get deposit info vec")?
+            .unpack();
+        let request = deposit_infos
+            .get(deposit_index as usize)
+            .context("get deposit info")?
+            .request
+            .clone();
+        let block_hash = db
+            .get_block_hash_by_number(block_number)?
+            .context("get block hash")?;
+        let status = match db.get_block_status(block_number) {
+            BlockStatus::Local => DepositStatus::Local,
+            BlockStatus::Submitted => DepositStatus::Submitted,
+            BlockStatus::Confirmed if is_deposit_finalized(ctx, &db, &block_hash)? => {
+                DepositStatus::Finalized
+            }
+            BlockStatus::Confirmed => DepositStatus::Confirmed,
+        };
+        let custodian_out_point = match db.get_block_submit_tx_hash(block_number) {
+            Some(tx_hash) => {
+                let block = db.get_block(&block_hash)?.context("get block")?;
+                find_custodian_out_point(
+                    ctx,
+                    tx_hash,
+                    &block,
+                    &deposit_infos,
+                    deposit_index as usize,
+                )
+                .await?
+            }
+            None => None,
+        };
+        return Ok(Some(DepositWithStatus {
+            out_point: out_point.into(),
+            status,
+            account_id: account_id(&request)?,
+            request: Some(request.into()),
+            l2_committed_info: Some(L2DepositCommittedInfo {
+                block_number: block_number.into(),
+                block_hash: to_jsonh256(block_hash),
+                deposit_index: deposit_index.into(),
+            }),
+            custodian_out_point: custodian_out_point.map(Into::into),
+        }));
+    }
+
+    let cell_with_status = match ctx.rpc_client.get_cell(out_point.clone()).await? {
+        Some(cell_with_status) => cell_with_status,
+        None => return Ok(None),
+    };
+    match cell_with_status.status {
+        CellStatus::Live => {
+            let deposit = cell_with_status
+                .cell
+                .and_then(|cell| ctx.rpc_client.parse_deposit_cell(cell));
+            let deposit = match deposit {
+                Some(deposit) => deposit,
+                None => return Ok(None),
+            };
+            Ok(Some(DepositWithStatus {
+                out_point: out_point.into(),
+                status: DepositStatus::Pending,
+                account_id: account_id(&deposit.request)?,
+                request: Some(deposit.request.into()),
+                ..Default::default()
+            }))
+        }
+        CellStatus::Dead => {
+            let consumer = match ctx.rpc_client.get_cell_consumer_tx(&out_point).await? {
+                Some(consumer) => consumer,
+                None => return Ok(None),
+            };
+            // The deposit lock can only be unlocked by a block submission,
+            // which updates the rollup cell, or by a refund after cancel
+            // timeout. The block submission is not synced yet.
+            let rollup_type_script = Some(ctx.rpc_client.rollup_type_script.clone()).pack();
+            let is_submission = consumer
+                .raw()
+                .outputs()
+                .into_iter()
+                .any(|output| output.type_().as_slice() == rollup_type_script.as_slice());
+            if is_submission {
+                return Ok(None);
+            }
+            Ok(Some(DepositWithStatus {
+                out_point: out_point.into(),
+                status: DepositStatus::Refunded,
+                ..Default::default()
+            }))
+        }
+        CellStatus::Unknown => Ok(None),
+    }
+}
+
+/// The first deposit cell of this rollup created by the L1 transaction.
+fn find_deposit_out_point(
+    rpc_client: &RPCClient,
+    tx: &packed::Transaction,
+) -> Option<packed::OutPoint> {
+    let tx_hash = tx.hash();
+    let outputs = tx.raw().outputs().into_iter();
+    let outputs_data = tx.raw().outputs_data().into_iter();
+    for (index, (output, data)) in outputs.zip(outputs_data).enumerate() {
+        let out_point = packed::OutPoint::new_builder()
+            .tx_hash(tx_hash.pack())
+            .index((index as u32).pack())
+            .build();
+        let cell = CellInfo {
+            out_point: out_point.clone(),
+            output,
+            data: data.unpack(),
+        };
+        if rpc_client.parse_deposit_cell(cell).is_some() {
+            return Some(out_point);
+        }
+    }
+    None
+}
+
+/// The live custodian cell created for the deposit by the block submission
+/// transaction. Returns `None` if the custodian cell is merged.
+async fn find_custodian_out_point(
+    ctx: &Registry,
+    submit_tx_hash: H256,
+    block: &packed::L2Block,
+    deposit_infos: &[DepositInfo],
+    deposit_index: usize,
+) -> anyhow::Result<Option<packed::OutPoint>> {
+    let block_hash = block.hash();
+    let finalized_timepoint = finalized_timepoint(
+        &ctx.rollup_config,
+        ctx.generator.fork_config(),
+        block.raw().number().unpack(),
+        block.raw().timestamp().unpack(),
+    );
+    let rollup_context = ctx.generator.rollup_context();
+    let to_custodian = |deposit_info: &DepositInfo| {
+        to_custodian_cell(
+            rollup_context,
+            &block_hash,
+            &finalized_timepoint,
+            deposit_info,
+        )
+        .ok()
+    };
+    let (custodian_output, custodian_data) =
+        match deposit_infos.get(deposit_index).and_then(to_custodian) {
+            Some(custodian) => custodian,
+            None => return Ok(None),
+        };
+    let is_custodian = |output: &packed::CellOutput, data: &[u8]| {
+        output.as_slice() == custodian_output.as_slice() && data == custodian_data.as_ref()
+    };
+    // Identical deposits have identical custodian cells, match them in order.
+    let nth = deposit_infos[..deposit_index]
+        .iter()
+        .filter_map(to_custodian)
+        .filter(|(output, data)| is_custodian(output, data))
+        .count();
+
+    let tx = match ctx
+        .rpc_client
+        .ckb
+        .get_packed_transaction(submit_tx_hash)
+        .await?
+    {
+        Some(tx) => tx,
+        None => return Ok(None),
+    };
+    let outputs = tx.raw().outputs().into_iter();
+    let outputs_data = tx.raw().outputs_data().into_iter();
+    let index = outputs
+        .zip(outputs_data)
+        .enumerate()
+        .filter(|(_, (output, data))| is_custodian(output, &data.raw_data()))
+        .nth(nth)
+        .map(|(index, _)| index);
+    let out_point = match index {
+        Some(index) => packed::OutPoint::new_builder()
+            .tx_hash(submit_tx_hash.pack())
+            .index((index as u32).pack())
+            .build(),
+        None => return Ok(None),
+    };
+    match ctx.rpc_client.get_cell(out_point.clone()).await? {
+        Some(CellWithStatus {
+            status: CellStatus::Live,
+            ..
+        }) => Ok(Some(out_point)),
+        _ => Ok(None),
+    }
+}
+
+/// A deposit is finalized when its custodian cell is finalized in the last
+/// confirmed global state.
+fn is_deposit_finalized(
+    ctx: &Registry,
+    db: &impl ChainStore,
+    block_hash: &H256,
+) -> anyhow::Result<bool> {
+    let last_confirmed_block_hash: H256 = match db.get_last_confirmed_block_number_hash() {
+        Some(nh) => nh.block_hash().unpack(),
+        None => return Ok(false),
+    };
+    let global_state = db
+        .get_block_post_global_state(&last_confirmed_block_hash)?
+        .context("get last confirmed global state")?;
+    let compatible_finalized_timepoint = CompatibleFinalizedTimepoint::from_global_state(
+        &global_state,
+        ctx.rollup_config.finality_blocks().unpack(),
+    );
+    let block = db.get_block(block_hash)?.context("get block")?;
+    let deposit_finalized_timepoint = finalized_timepoint(
+        &ctx.rollup_config,
+        ctx.generator.fork_config(),
+        block.raw().number().unpack(),
+        block.raw().timestamp().unpack(),
+    );
+    Ok(compatible_finalized_timepoint.is_finalized(&deposit_finalized_timepoint))
+}
+
 #[instrument(skip_all)]
 async fn gw_get_balance(
     ctx: &Registry,
@@ -1947,6 +2748,28 @@ async fn gw_get_nonce(
     Ok(nonce.into())
 }
 
+#[instrument(skip_all)]
+async fn gw_get_session_keys(
+    ctx: &Registry,
+    account_id: AccountID,
+    block_number: Option<Uint64>,
+) -> Result<Vec<SessionKey>> {
+    let session_keys = match block_number {
+        Some(block_number) => {
+            let mut db = ctx.store.begin_transaction();
+            let tree =
+                BlockStateDB::from_store(&mut db, RWConfig::history_block(block_number.into()))?;
+            get_session_keys(&tree, account_id.into())?
+        }
+        None => {
+            let state = ctx.mem_pool_state.load_state_db();
+            get_session_keys(&state, account_id.into())?
+        }
+    };
+
+    Ok(session_keys.into_iter().map(Into::into).collect())
+}
+
 #[instrument(skip_all)]
 async fn gw_get_script(ctx: &Registry, script_hash: JsonH256) -> Result<Option<Script>> {
     let state = ctx.mem_pool_state.load_state_db();
@@ -2003,6 +2826,25 @@ fn get_backend_info(generator: Arc<Generator>) -> Vec<BackendInfo> {
         .collect()
 }
 
+fn get_staged_backend_forks(generator: &Generator, tip_number: u64) -> Vec<BackendForkInfo> {
+    generator
+        .backend_manage()
+        .get_backend_forks_after(tip_number)
+        .into_iter()
+        .map(|(fork_height, backends)| BackendForkInfo {
+            fork_height: fork_height.into(),
+            backends: backends
+                .into_iter()
+                .map(|b| BackendInfo {
+                    generator_checksum: ckb_fixed_hash::H256(b.generator_checksum),
+                    validator_script_type_hash: ckb_fixed_hash::H256(b.validator_script_type_hash),
+                    backend_type: to_rpc_backend_type(&b.backend_type),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
 fn to_rpc_backend_type(b_type: &gw_config::BackendType) -> BackendType {
     match b_type {
         gw_config::BackendType::EthAddrReg => BackendType::EthAddrReg,
@@ -2168,5 +3010,6 @@ pub fn to_rpc_node_mode(node_mode: &NodeMode) -> RpcNodeMode {
         NodeMode::FullNode => RpcNodeMode::FullNode,
         NodeMode::ReadOnly => RpcNodeMode::ReadOnly,
         NodeMode::Test => RpcNodeMode::Test,
+        NodeMode::Watchtower => RpcNodeMode::Watchtower,
     }
 }
@@ -0,0 +1,164 @@
+//! Batched signature verification of submitted transactions.
+//!
+//! Recovering secp256k1 public keys is the most expensive part of accepting a
+//! transaction. Instead of verifying on the request path, `gw_submit_l2transaction`
+//! pushes the transaction into a bounded queue and waits for the result. A
+//! background task drains the queue in batches and splits each batch across a
+//! pool of blocking workers.
+//!
+//! A verified tx carries the `VerifiedSignature` it was verified against, the
+//! mem pool verifies it again if the sender's state changed before it's pushed.
+
+use std::{sync::Arc, time::Instant};
+
+use gw_common::state::State;
+use gw_config::SignatureVerifierConfig;
+use gw_generator::{error::TransactionValidateError, Generator};
+use gw_mem_pool::pool::VerifiedSignature;
+use gw_store::{mem_pool_state::MemPoolState, state::MemStateDB};
+use gw_types::{packed::L2Transaction, prelude::*};
+use tokio::sync::{mpsc, oneshot};
+
+pub(crate) type VerifyResult = Result<VerifiedSignature, TransactionValidateError>;
+
+/// Verify the signature of `tx` against `state`.
+pub(crate) fn verify_signature(
+    generator: &Generator,
+    state: &MemStateDB,
+    tx: &L2Transaction,
+    block_number: u64,
+) -> VerifyResult {
+    let session_key_spend = generator.check_transaction_signature(state, tx, block_number)?;
+    Ok(VerifiedSignature {
+        sender_nonce: state.get_nonce(tx.raw().from_id().unpack())?,
+        block_number,
+        session_key_spend,
+    })
+}
+
+struct VerifyRequest {
+    tx: L2Transaction,
+    result_tx: oneshot::Sender<VerifyResult>,
+}
+
+#[derive(Clone)]
+pub(crate) struct SignatureVerifier {
+    queue_tx: mpsc::Sender<VerifyRequest>,
+}
+
+impl SignatureVerifier {
+    pub fn spawn(
+        generator: Arc<Generator>,
+        mem_pool_state: Arc<MemPoolState>,
+        config: &SignatureVerifierConfig,
+    ) -> Self {
+        let (queue_tx, queue_rx) = mpsc::channel(config.queue_size.max(1));
+        let worker = VerifyWorker {
+            generator,
+            mem_pool_state,
+            queue_rx,
+            workers: config.workers.max(1),
+            max_batch_size: config.max_batch_size.max(1),
+        };
+        tokio::spawn(worker.in_background());
+        Self { queue_tx }
+    }
+
+    /// Queue a transaction for signature verification.
+    ///
+    /// Returns `TrySendError::Full` if the queue is full, the caller should
+    /// reject the submission as busy.
+    pub fn try_verify(
+        &self,
+        tx: L2Transaction,
+    ) -> Result<oneshot::Receiver<VerifyResult>, mpsc::error::TrySendError<()>> {
+        let permit = self.queue_tx.try_reserve()?;
+        let (result_tx, result_rx) = oneshot::channel();
+        gw_metrics::rpc().signature_verify_queue_len().inc();
+        permit.send(VerifyRequest { tx, result_tx });
+        Ok(result_rx)
+    }
+}
+
+struct VerifyWorker {
+    generator: Arc<Generator>,
+    mem_pool_state: Arc<MemPoolState>,
+    queue_rx: mpsc::Receiver<VerifyRequest>,
+    workers: usize,
+    max_batch_size: usize,
+}
+
+impl VerifyWorker {
+    async fn in_background(mut self) {
+        loop {
+            // blocking current task until we receive a tx
+            let mut batch = match self.queue_rx.recv().await {
+                Some(req) => vec![req],
+                None => {
+                    log::error!("signature verify queue is closed");
+                    return;
+                }
+            };
+            while batch.len() < self.max_batch_size {
+                match self.queue_rx.try_recv() {
+                    Ok(req) => batch.push(req),
+                    Err(_) => break,
+                }
+            }
+            gw_metrics::rpc()
+                .signature_verify_queue_len()
+                .dec_by(batch.len() as i64);
+
+            self.verify_batch(batch).await;
+        }
+    }
+
+    async fn verify_batch(&self, mut batch: Vec<VerifyRequest>) {
+        let t = Instant::now();
+        let batch_size = batch.len();
+        let chunk_size = (batch_size + self.workers - 1) / self.workers;
+
+        let mut handles = Vec::with_capacity(self.workers);
+        while !batch.is_empty() {
+            let chunk: Vec<_> = batch.drain(..chunk_size.min(batch.len())).collect();
+            let generator = Arc::clone(&self.generator);
+            let mem_pool_state = Arc::clone(&self.mem_pool_state);
+            handles.push(tokio::task::spawn_blocking(move || {
+                let state = mem_pool_state.load_state_db();
+                let block_number = mem_pool_state
+                    .load_shared()
+                    .mem_block
+                    .as_ref()
+                    .map(|block_info| block_info.number().unpack())
+                    .unwrap_or_default();
+                let mut failures = 0u64;
+                for VerifyRequest { tx, result_tx } in chunk {
+                    let result = verify_signature(&generator, &state, &tx, block_number);
+                    if result.is_err() {
+                        failures += 1;
+                    }
+                    // The submitter may have gone, e.g. the connection is closed.
+                    let _ = result_tx.send(result);
+                }
+                failures
+            }));
+        }
+
+        let mut failures = 0u64;
+        for handle in handles {
+            match handle.await {
+                Ok(n) => failures += n,
+                // Dropped result senders of a panicked worker fail their requests.
+                Err(err) => log::error!("signature verify worker error: {}", err),
+            }
+        }
+
+        log::debug!(
+            "[signature verifier] verified {} txs, {} failures, {}ms",
+            batch_size,
+            failures,
+            t.elapsed().as_millis()
+        );
+        gw_metrics::rpc().signature_verify_batch(batch_size, t.elapsed(), failures);
+    }
+}
@@ -0,0 +1,57 @@
+use autorocks::DbIterator;
+
+use crate::mem_db::MemDbIterator;
+
+/// Column iterator of either storage backend.
+pub enum StoreIterator<'a, T> {
+    RocksDb(DbIterator<&'a T>),
+    Memory(MemDbIterator),
+}
+
+impl<'a, T> StoreIterator<'a, T> {
+    pub fn seek(&mut self, key: &[u8]) {
+        match self {
+            Self::RocksDb(iter) => iter.seek(key),
+            Self::Memory(iter) => iter.seek(key),
+        }
+    }
+
+    pub fn seek_for_prev(&mut self, key: &[u8]) {
+        match self {
+            Self::RocksDb(iter) => iter.seek_for_prev(key),
+            Self::Memory(iter) => iter.seek_for_prev(key),
+        }
+    }
+
+    pub fn valid(&self) -> bool {
+        match self {
+            Self::RocksDb(iter) => iter.valid(),
+            Self::Memory(iter) => iter.valid(),
+        }
+    }
+
+    pub fn key(&self) -> Option<&[u8]> {
+        match self {
+            Self::RocksDb(iter) => iter.key(),
+            Self::Memory(iter) => iter.key(),
+        }
+    }
+
+    pub fn value(&self) -> Option<&[u8]> {
+        match self {
+            Self::RocksDb(iter) => iter.value(),
+            Self::Memory(iter) => iter.value(),
+        }
+    }
+}
+
+impl<'a, T> Iterator for StoreIterator<'a, T> {
+    type Item = (Box<[u8]>, Box<[u8]>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::RocksDb(iter) => iter.next(),
+            Self::Memory(iter) => iter.next(),
+        }
+    }
+}
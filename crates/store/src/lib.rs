@@ -1,6 +1,8 @@
 pub extern crate autorocks;
 
 pub mod chain_view;
+pub mod iter;
+pub mod mem_db;
 pub mod mem_pool_state;
 pub mod migrate;
 pub mod readonly;
@@ -12,7 +14,7 @@ mod store_impl;
 pub mod traits;
 pub mod transaction;
 
-pub use store_impl::{CfMemStat, Store};
+pub use store_impl::{CfMemStat, CfStat, Store};
 
 #[cfg(test)]
 mod tests;
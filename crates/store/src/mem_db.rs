@@ -0,0 +1,318 @@
+//! In-memory storage backend.
+//!
+//! Each column is an `im::OrdMap`, so snapshots and iterators are cheap
+//! structural clones and never block writers. Intended for tests and ephemeral
+//! devnets; nothing is persisted.
+//!
+//! Like a RocksDB `TransactionDb`, a transaction locks every key it writes
+//! until it commits or rolls back, and a write to a key locked by another
+//! transaction fails after waiting [`LOCK_TIMEOUT`]. Transactions that skip
+//! concurrency control take no locks.
+
+use std::collections::HashMap;
+use std::ops::Bound;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use arc_swap::ArcSwap;
+use autorocks::Direction;
+use im::OrdMap;
+
+use crate::schema::Col;
+
+/// Same as the default `transaction_lock_timeout` of RocksDB.
+pub const LOCK_TIMEOUT: Duration = Duration::from_secs(1);
+
+type Column = OrdMap<Box<[u8]>, Box<[u8]>>;
+type Columns = Vec<Column>;
+
+#[derive(Default)]
+struct KeyLocks {
+    // Locked key -> id of the transaction holding the lock.
+    owners: Mutex<HashMap<(Col, Box<[u8]>), u64>>,
+    released: Condvar,
+    next_id: AtomicU64,
+}
+
+#[derive(Clone)]
+pub struct MemDb {
+    columns: Arc<ArcSwap<Columns>>,
+    locks: Arc<KeyLocks>,
+}
+
+impl MemDb {
+    pub fn new(columns: usize) -> Self {
+        Self {
+            columns: Arc::new(ArcSwap::from_pointee(vec![Column::new(); columns])),
+            locks: Default::default(),
+        }
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns.load().len()
+    }
+
+    pub fn get(&self, col: Col, key: &[u8]) -> Option<Box<[u8]>> {
+        self.columns.load()[col].get(key).cloned()
+    }
+
+    pub fn snapshot(&self) -> MemDbSnapshot {
+        MemDbSnapshot {
+            columns: self.columns.load_full(),
+        }
+    }
+
+    /// Begin a transaction.
+    ///
+    /// Reads see uncommitted writes of this transaction first, then the
+    /// latest committed data. If `skip_concurrency_control` is true, reads see
+    /// the data at the time the transaction began and writes take no locks.
+    pub fn begin_transaction(&self, skip_concurrency_control: bool) -> MemDbTransaction {
+        let columns = self.columns();
+        MemDbTransaction {
+            db: self.clone(),
+            id: self.locks.next_id.fetch_add(1, Ordering::Relaxed),
+            snapshot: skip_concurrency_control.then(|| self.snapshot()),
+            writes: vec![OrdMap::new(); columns],
+            locked_keys: Vec::new(),
+        }
+    }
+
+    pub fn iter(&self, col: Col, dir: Direction) -> MemDbIterator {
+        MemDbIterator::new(self.columns.load()[col].clone(), dir)
+    }
+
+    /// Apply a set of writes atomically. Conflicting writers are excluded by
+    /// the key locks.
+    fn write(&self, writes: &[OrdMap<Box<[u8]>, Option<Box<[u8]>>>]) {
+        self.columns.rcu(|columns| {
+            let mut columns = Columns::clone(columns);
+            for (column, col_writes) in columns.iter_mut().zip(writes) {
+                for (key, value) in col_writes {
+                    match value {
+                        Some(value) => column.insert(key.clone(), value.clone()),
+                        None => column.remove(key),
+                    };
+                }
+            }
+            columns
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct MemDbSnapshot {
+    columns: Arc<Columns>,
+}
+
+impl MemDbSnapshot {
+    pub fn get(&self, col: Col, key: &[u8]) -> Option<Box<[u8]>> {
+        self.columns[col].get(key).cloned()
+    }
+
+    pub fn iter(&self, col: Col, dir: Direction) -> MemDbIterator {
+        MemDbIterator::new(self.columns[col].clone(), dir)
+    }
+}
+
+pub struct MemDbTransaction {
+    db: MemDb,
+    id: u64,
+    snapshot: Option<MemDbSnapshot>,
+    // Pending writes, `None` means deleted.
+    writes: Vec<OrdMap<Box<[u8]>, Option<Box<[u8]>>>>,
+    locked_keys: Vec<(Col, Box<[u8]>)>,
+}
+
+impl MemDbTransaction {
+    pub fn get(&self, col: Col, key: &[u8]) -> Option<Box<[u8]>> {
+        match self.writes[col].get(key) {
+            Some(value) => value.clone(),
+            None => match self.snapshot {
+                Some(ref snapshot) => snapshot.get(col, key),
+                None => self.db.get(col, key),
+            },
+        }
+    }
+
+    pub fn put(&mut self, col: Col, key: &[u8], value: &[u8]) -> Result<()> {
+        self.lock_key(col, key)?;
+        self.writes[col].insert(key.into(), Some(value.into()));
+        Ok(())
+    }
+
+    pub fn delete(&mut self, col: Col, key: &[u8]) -> Result<()> {
+        self.lock_key(col, key)?;
+        self.writes[col].insert(key.into(), None);
+        Ok(())
+    }
+
+    pub fn commit(&mut self) {
+        self.db.write(&self.writes);
+        self.clear_writes();
+        self.unlock_keys();
+    }
+
+    pub fn rollback(&mut self) {
+        self.clear_writes();
+        self.unlock_keys();
+    }
+
+    /// Snapshot of committed data, pending writes are not included.
+    pub fn timestamped_snapshot(&self) -> MemDbSnapshot {
+        self.db.snapshot()
+    }
+
+    pub fn iter(&self, col: Col, dir: Direction) -> MemDbIterator {
+        let mut column = match self.snapshot {
+            Some(ref snapshot) => snapshot.columns[col].clone(),
+            None => self.db.columns.load()[col].clone(),
+        };
+        for (key, value) in &self.writes[col] {
+            match value {
+                Some(value) => column.insert(key.clone(), value.clone()),
+                None => column.remove(key),
+            };
+        }
+        MemDbIterator::new(column, dir)
+    }
+
+    fn clear_writes(&mut self) {
+        self.writes.iter_mut().for_each(OrdMap::clear);
+    }
+
+    fn lock_key(&mut self, col: Col, key: &[u8]) -> Result<()> {
+        if self.snapshot.is_some() {
+            return Ok(());
+        }
+        let locks = &self.db.locks;
+        let lock_key = (col, Box::<[u8]>::from(key));
+        let deadline = Instant::now() + LOCK_TIMEOUT;
+        let mut owners = locks.owners.lock().expect("lock key owners");
+        loop {
+            match owners.get(&lock_key) {
+                Some(&owner) if owner == self.id => return Ok(()),
+                Some(_) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        bail!("write conflict: key locked by another transaction");
+                    }
+                    owners = locks
+                        .released
+                        .wait_timeout(owners, deadline - now)
+                        .expect("lock key owners")
+                        .0;
+                }
+                None => {
+                    owners.insert(lock_key.clone(), self.id);
+                    self.locked_keys.push(lock_key);
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    fn unlock_keys(&mut self) {
+        if self.locked_keys.is_empty() {
+            return;
+        }
+        let locks = &self.db.locks;
+        let mut owners = locks.owners.lock().expect("lock key owners");
+        for key in self.locked_keys.drain(..) {
+            owners.remove(&key);
+        }
+        locks.released.notify_all();
+    }
+}
+
+impl Drop for MemDbTransaction {
+    fn drop(&mut self) {
+        self.unlock_keys();
+    }
+}
+
+/// Iterator over a point-in-time copy of a column.
+///
+/// Mirrors `autorocks::DbIterator`: it starts at the first (or last) key, and
+/// after `seek`/`seek_for_prev` the next call to `next` yields the entry the
+/// iterator was positioned at.
+pub struct MemDbIterator {
+    column: Column,
+    direction: Direction,
+    current: Option<(Box<[u8]>, Box<[u8]>)>,
+    just_seeked: bool,
+}
+
+impl MemDbIterator {
+    fn new(column: Column, direction: Direction) -> Self {
+        let current = match direction {
+            Direction::Forward => column.get_min(),
+            Direction::Backward => column.get_max(),
+        }
+        .cloned();
+        Self {
+            column,
+            direction,
+            current,
+            just_seeked: true,
+        }
+    }
+
+    /// Position at the first key that is greater than or equal to `key`.
+    pub fn seek(&mut self, key: &[u8]) {
+        self.current = self.first_in((Bound::Included(key), Bound::Unbounded));
+        self.just_seeked = true;
+    }
+
+    /// Position at the last key that is less than or equal to `key`.
+    pub fn seek_for_prev(&mut self, key: &[u8]) {
+        self.current = self.last_in((Bound::Unbounded, Bound::Included(key)));
+        self.just_seeked = true;
+    }
+
+    pub fn valid(&self) -> bool {
+        self.current.is_some()
+    }
+
+    pub fn key(&self) -> Option<&[u8]> {
+        self.current.as_ref().map(|(k, _)| &**k)
+    }
+
+    pub fn value(&self) -> Option<&[u8]> {
+        self.current.as_ref().map(|(_, v)| &**v)
+    }
+
+    fn first_in(&self, range: (Bound<&[u8]>, Bound<&[u8]>)) -> Option<(Box<[u8]>, Box<[u8]>)> {
+        self.column
+            .range::<_, [u8]>(range)
+            .next()
+            .map(|(k, v)| (k.clone(), v.clone()))
+    }
+
+    fn last_in(&self, range: (Bound<&[u8]>, Bound<&[u8]>)) -> Option<(Box<[u8]>, Box<[u8]>)> {
+        self.column
+            .range::<_, [u8]>(range)
+            .next_back()
+            .map(|(k, v)| (k.clone(), v.clone()))
+    }
+}
+
+impl Iterator for MemDbIterator {
+    type Item = (Box<[u8]>, Box<[u8]>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.just_seeked {
+            let current = self.current.take()?;
+            let key = &*current.0;
+            self.current = match self.direction {
+                Direction::Forward => self.first_in((Bound::Excluded(key), Bound::Unbounded)),
+                Direction::Backward => self.last_in((Bound::Unbounded, Bound::Excluded(key))),
+            };
+        } else {
+            self.just_seeked = false;
+        }
+        self.current.clone()
+    }
+}
@@ -9,18 +9,23 @@ use autorocks::{
     autorocks_sys::rocksdb::Status_SubCode, moveit::slot, DbOptions, Direction, ReadOnlyDb,
     TransactionDb,
 };
-use gw_config::StoreConfig;
+use gw_config::{StoreBackend, StoreConfig};
+use gw_types::{packed, prelude::*};
 
 use crate::{
     schema::{
-        COLUMNS, COLUMN_BAD_BLOCK, COLUMN_BLOCK, COLUMN_META, META_LAST_VALID_TIP_BLOCK_HASH_KEY,
-        META_TIP_BLOCK_HASH_KEY, MIGRATION_VERSION_KEY, REMOVED_COLUMN_BLOCK_DEPOSIT_REQUESTS,
-        REMOVED_COLUMN_L2BLOCK_COMMITTED_INFO,
+        COLUMNS, COLUMN_BAD_BLOCK, COLUMN_BAD_BLOCK_DEPOSIT_INFO_VEC, COLUMN_BLOCK,
+        COLUMN_BLOCK_DEPOSIT_INFO_VEC, COLUMN_DEPOSIT_INFO, COLUMN_META,
+        META_LAST_VALID_TIP_BLOCK_HASH_KEY, META_TIP_BLOCK_HASH_KEY, MIGRATION_VERSION_KEY,
+        REMOVED_COLUMN_BLOCK_DEPOSIT_REQUESTS, REMOVED_COLUMN_L2BLOCK_COMMITTED_INFO,
     },
     Store,
 };
 
 pub fn open_or_create_db(config: &StoreConfig, factory: MigrationFactory) -> Result<TransactionDb> {
+    if config.backend == StoreBackend::Memory {
+        bail!("in-memory store has no database to open or migrate");
+    }
     let read_only_db = match DbOptions::new(&config.path, 1).open_read_only() {
         Ok(db) => Some(db),
         Err(e) if e.sub_code == Status_SubCode::kPathNotFound => None,
@@ -37,19 +42,19 @@ pub fn open_or_create_db(config: &StoreConfig, factory: MigrationFactory) -> Res
                 );
                 bail!("The database is created by a higher version executable binary");
             }
-            Ordering::Equal => Ok(Store::open(config, COLUMNS)?.into_inner()),
+            Ordering::Equal => Ok(Store::open(config, COLUMNS)?.into_inner()?),
             Ordering::Less => {
                 log::info!("process migrations ...");
 
-                let db = Store::open(config, COLUMNS)?.into_inner();
+                let db = Store::open(config, COLUMNS)?.into_inner()?;
 
                 let _ = factory.migrate(db)?;
 
-                Ok(Store::open(config, COLUMNS)?.into_inner())
+                Ok(Store::open(config, COLUMNS)?.into_inner()?)
             }
         }
     } else {
-        let db = Store::open(config, COLUMNS)?.into_inner();
+        let db = Store::open(config, COLUMNS)?.into_inner()?;
         init_db_version(&db, factory.last_db_version())?;
         Ok(db)
     }
@@ -150,6 +155,71 @@ impl Migration for BadBlockColumnMigration {
     }
 }
 
+/// Check that column 13, now `COLUMN_BAD_BLOCK_DEPOSIT_INFO_VEC`, holds no data
+/// of earlier versions. Only deposit info vecs of existing bad blocks are
+/// allowed.
+struct BadBlockDepositInfoColumnMigration;
+
+impl Migration for BadBlockDepositInfoColumnMigration {
+    fn migrate(&self, db: TransactionDb) -> Result<TransactionDb> {
+        for (k, _) in db.iter(COLUMN_BAD_BLOCK_DEPOSIT_INFO_VEC, Direction::Forward) {
+            slot!(slice);
+            if db.get(COLUMN_BAD_BLOCK, &k, slice)?.is_none() {
+                bail!(
+                    "Cannot migrate to version {}, column {} has unknown data",
+                    self.version(),
+                    COLUMN_BAD_BLOCK_DEPOSIT_INFO_VEC
+                );
+            }
+        }
+        Ok(db)
+    }
+    fn version(&self) -> &str {
+        "20261017"
+    }
+}
+
+/// Number of deposits written by each batch of `DepositOutPointIndexMigration`.
+const DEPOSIT_INDEX_BATCH_SIZE: usize = 10_000;
+
+/// Index deposits of existing blocks by L1 out point.
+struct DepositOutPointIndexMigration;
+
+impl Migration for DepositOutPointIndexMigration {
+    fn migrate(&self, db: TransactionDb) -> Result<TransactionDb> {
+        let mut batch = db.new_write_batch();
+        let mut batch_len = 0;
+        let mut indexed = 0;
+        for (k, v) in db.iter(COLUMN_BLOCK_DEPOSIT_INFO_VEC, Direction::Forward) {
+            let block_number = u64::from_be_bytes(<[u8; 8]>::try_from(&k[..])?);
+            let deposit_info_vec = packed::DepositInfoVecReader::from_slice_should_be_ok(&v);
+            for (index, deposit_info) in deposit_info_vec.iter().enumerate() {
+                let key = packed::DepositKey::new_builder()
+                    .block_number(block_number.pack())
+                    .index((index as u32).pack())
+                    .build();
+                let out_point = deposit_info.cell().out_point();
+                batch.put(COLUMN_DEPOSIT_INFO, out_point.as_slice(), key.as_slice())?;
+                batch_len += 1;
+            }
+            if batch_len >= DEPOSIT_INDEX_BATCH_SIZE {
+                db.write(&mut batch)?;
+                indexed += batch_len;
+                log::info!("indexed {} deposits", indexed);
+                batch = db.new_write_batch();
+                batch_len = 0;
+            }
+        }
+        db.write(&mut batch)?;
+        indexed += batch_len;
+        log::info!("indexed {} deposits", indexed);
+        Ok(db)
+    }
+    fn version(&self) -> &str {
+        "20261018"
+    }
+}
+
 #[cfg(feature = "smt-trie")]
 pub struct SMTTrieMigrationPlaceHolder;
 
@@ -194,6 +264,8 @@ pub fn init_migration_factory() -> MigrationFactory {
     factory.insert(Box::new(
         DecoupleBlockProducingSubmissionAndConfirmationMigration,
     ));
+    factory.insert(Box::new(BadBlockDepositInfoColumnMigration));
+    factory.insert(Box::new(DepositOutPointIndexMigration));
     #[cfg(feature = "smt-trie")]
     factory.insert(Box::new(SMTTrieMigrationPlaceHolder));
     factory
@@ -254,8 +326,9 @@ mod tests {
             path: dir.path().to_owned(),
             options_file: None,
             cache_size: None,
+            ..Default::default()
         };
-        let old_db = Store::open(&config, COLUMNS)?.into_inner();
+        let old_db = Store::open(&config, COLUMNS)?.into_inner()?;
         let factory = init_migration_factory();
         assert!(factory.last_db_version().is_some());
 
@@ -280,6 +353,7 @@ mod tests {
             path: dir.path().to_owned(),
             options_file: None,
             cache_size: None,
+            ..Default::default()
         };
         let db = open_or_create_db(&config, init_migration_factory())?;
         {
@@ -297,4 +371,13 @@ mod tests {
         assert_eq!(v, Some(Ok(factory.last_db_version().unwrap().to_string())));
         Ok(())
     }
+
+    #[test]
+    fn test_open_in_memory_store() {
+        let config = StoreConfig {
+            backend: StoreBackend::Memory,
+            ..Default::default()
+        };
+        assert!(open_or_create_db(&config, init_migration_factory()).is_err());
+    }
 }
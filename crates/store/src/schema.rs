@@ -3,7 +3,7 @@
 /// Column families alias type
 pub type Col = usize;
 /// Total column number
-pub const COLUMNS: usize = 37;
+pub const COLUMNS: usize = 38;
 /// Column store meta data
 pub const COLUMN_META: Col = 0;
 /// Column store chain index
@@ -37,6 +37,9 @@ pub const COLUMN_ACCOUNT_SMT_LEAF: Col = 10;
 pub const COLUMN_BLOCK_SMT_BRANCH: Col = 11;
 /// Column block SMT leaf
 pub const COLUMN_BLOCK_SMT_LEAF: Col = 12;
+// Column 13 was never used by earlier versions, see BadBlockDepositInfoColumnMigration.
+/// Bad block hash -> deposit info vec.
+pub const COLUMN_BAD_BLOCK_DEPOSIT_INFO_VEC: Col = 13;
 /// Column script
 pub const COLUMN_SCRIPT: Col = 14;
 /// Column data
@@ -79,6 +82,10 @@ pub const COLUMN_BLOCK_SUBMIT_TX_HASH: Col = 7;
 pub const COLUMN_BLOCK_DEPOSIT_INFO_VEC: Col = 16;
 /// block number (in big endian) -> FinalizedCustodianCapacity.
 pub const COLUMN_BLOCK_POST_FINALIZED_CUSTODIAN_CAPACITY: Col = 36;
+/// L1 deposit cell out point -> DepositKey.
+///
+/// Only for deposits of valid blocks.
+pub const COLUMN_DEPOSIT_INFO: Col = 37;
 
 /// chain id
 pub const META_CHAIN_ID_KEY: &[u8] = b"CHAIN_ID";
@@ -95,6 +102,8 @@ pub const META_LAST_VALID_TIP_BLOCK_HASH_KEY: &[u8] = b"LAST_VALID_TIP_BLOCK_HAS
 pub const META_LAST_CONFIRMED_BLOCK_NUMBER_HASH_KEY: &[u8] = b"LAST_CONFIRMED_BLOCK_NUMBER";
 /// track the last submitted l2 block NumberAndHash
 pub const META_LAST_SUBMITTED_BLOCK_NUMBER_HASH_KEY: &[u8] = b"LAST_SUBMITTED_BLOCK_NUMBER";
+/// backend forks scheduled at runtime, see `ScheduledBackendForkVec`
+pub const META_SCHEDULED_BACKEND_FORKS_KEY: &[u8] = b"SCHEDULED_BACKEND_FORKS";
 
 /// CHAIN_SPEC_HASH_KEY tracks the hash of chain spec which created current database
 pub const CHAIN_SPEC_HASH_KEY: &[u8] = b"chain-spec-hash";
@@ -1,17 +1,39 @@
 use autorocks::{moveit::slot, Direction, Snapshot};
 
 use crate::{
+    iter::StoreIterator,
+    mem_db::MemDbSnapshot,
     schema::{Col, COLUMN_MEM_POOL_TRANSACTION},
     traits::{chain_store::ChainStore, kv_store::KVStoreRead},
 };
 
 pub struct StoreSnapshot {
-    inner: Snapshot,
+    inner: SnapshotInner,
+}
+
+enum SnapshotInner {
+    RocksDb(Snapshot),
+    Memory(MemDbSnapshot),
 }
 
 impl StoreSnapshot {
     pub(crate) fn new(inner: Snapshot) -> Self {
-        Self { inner }
+        Self {
+            inner: SnapshotInner::RocksDb(inner),
+        }
+    }
+
+    pub(crate) fn new_in_memory(inner: MemDbSnapshot) -> Self {
+        Self {
+            inner: SnapshotInner::Memory(inner),
+        }
+    }
+
+    pub(crate) fn get_iter(&self, col: Col, dir: Direction) -> StoreIterator<'_, Snapshot> {
+        match self.inner {
+            SnapshotInner::RocksDb(ref inner) => StoreIterator::RocksDb(inner.iter(col, dir)),
+            SnapshotInner::Memory(ref inner) => StoreIterator::Memory(inner.iter(col, dir)),
+        }
     }
 }
 
@@ -19,18 +41,22 @@ impl ChainStore for StoreSnapshot {}
 
 impl KVStoreRead for StoreSnapshot {
     fn get(&self, col: Col, key: &[u8]) -> Option<Box<[u8]>> {
-        slot!(slice);
-        self.inner
-            .get(col, key, slice)
-            .expect("db operation should be ok")
-            .map(|p| p.as_ref().into())
+        match self.inner {
+            SnapshotInner::RocksDb(ref inner) => {
+                slot!(slice);
+                inner
+                    .get(col, key, slice)
+                    .expect("db operation should be ok")
+                    .map(|p| p.as_ref().into())
+            }
+            SnapshotInner::Memory(ref inner) => inner.get(col, key),
+        }
     }
 }
 
 impl StoreSnapshot {
     pub fn iter_mem_pool_transactions(&self) -> impl Iterator<Item = Box<[u8]>> + '_ {
-        self.inner
-            .iter(COLUMN_MEM_POOL_TRANSACTION, Direction::Forward)
+        self.get_iter(COLUMN_MEM_POOL_TRANSACTION, Direction::Forward)
             .map(|(k, _)| k)
     }
 }
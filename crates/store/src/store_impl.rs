@@ -2,32 +2,43 @@
 
 use std::sync::Arc;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use autorocks::autorocks_sys::rocksdb::{
     TransactionDBWriteOptimizations, TransactionOptions, WriteOptions,
 };
 use autorocks::moveit::{moveit, slot};
 use autorocks::{DbOptions, TransactionDb, WriteBatch};
-use gw_config::StoreConfig;
+use gw_config::{StoreBackend, StoreConfig};
 use gw_smt::smt::Blake2bHasher;
 use gw_types::prelude::*;
 use serde::Serialize;
 use tempfile::TempDir;
 
+use crate::mem_db::MemDb;
 use crate::schema::{Col, COLUMNS};
 use crate::smt::smt_store::SMTBlockStore;
 use crate::state::{history::history_state::RWConfig, BlockStateDB};
 use crate::traits::{chain_store::ChainStore, kv_store::KVStoreRead};
+use crate::transaction::TransactionInner;
 use crate::{snapshot::StoreSnapshot, transaction::StoreTransaction};
 
 #[derive(Clone)]
 pub struct Store {
-    db: TransactionDb,
+    db: StoreDb,
     _temp_dir: Option<Arc<TempDir>>,
 }
 
+#[derive(Clone)]
+enum StoreDb {
+    RocksDb(TransactionDb),
+    Memory(MemDb),
+}
+
 impl Store {
     pub fn open(config: &StoreConfig, columns: usize) -> Result<Self> {
+        if config.backend == StoreBackend::Memory {
+            return Ok(Self::open_in_memory(columns));
+        }
         let mut opts = DbOptions::new(&config.path, columns);
         if let Some(ref opts_file) = config.options_file {
             opts.load_options_from_file(opts_file, config.cache_size.unwrap_or(0))?;
@@ -41,7 +52,7 @@ impl Store {
 
     pub fn new(db: TransactionDb) -> Self {
         Store {
-            db,
+            db: StoreDb::RocksDb(db),
             _temp_dir: None,
         }
     }
@@ -49,25 +60,50 @@ impl Store {
     pub fn open_tmp() -> Result<Self> {
         let dir = tempfile::tempdir()?;
         Ok(Self {
-            db: DbOptions::new(dir.path(), COLUMNS)
-                .create_if_missing(true)
-                .create_missing_column_families(true)
-                .open()?,
+            db: StoreDb::RocksDb(
+                DbOptions::new(dir.path(), COLUMNS)
+                    .create_if_missing(true)
+                    .create_missing_column_families(true)
+                    .open()?,
+            ),
             _temp_dir: Some(dir.into()),
         })
     }
 
-    pub fn begin_transaction(&self) -> StoreTransaction {
-        StoreTransaction {
-            inner: self.db.begin_transaction(),
+    /// Open a store that keeps everything in memory. Data is dropped with the
+    /// last clone of the store.
+    pub fn open_in_memory(columns: usize) -> Self {
+        Store {
+            db: StoreDb::Memory(MemDb::new(columns)),
+            _temp_dir: None,
         }
     }
 
+    pub fn is_in_memory(&self) -> bool {
+        matches!(self.db, StoreDb::Memory(_))
+    }
+
+    pub fn begin_transaction(&self) -> StoreTransaction {
+        let inner = match self.db {
+            StoreDb::RocksDb(ref db) => TransactionInner::RocksDb(db.begin_transaction()),
+            StoreDb::Memory(ref db) => TransactionInner::Memory(db.begin_transaction(false)),
+        };
+        StoreTransaction { inner }
+    }
+
     /// Begin transaction but disable concurrency control.
     ///
     /// This should be faster than a normal transaction when you know that there
     /// won't be any conflicts.
     pub fn begin_transaction_skip_concurrency_control(&self) -> StoreTransaction {
+        let db = match self.db {
+            StoreDb::RocksDb(ref db) => db,
+            StoreDb::Memory(ref db) => {
+                return StoreTransaction {
+                    inner: TransactionInner::Memory(db.begin_transaction(true)),
+                };
+            }
+        };
         moveit! {
             let write_options = WriteOptions::new();
             let mut transaction_options = TransactionOptions::new();
@@ -75,14 +111,19 @@ impl Store {
         transaction_options.as_mut().skip_concurrency_control = true;
         transaction_options.as_mut().set_snapshot = true;
         StoreTransaction {
-            inner: self
-                .db
-                .begin_transaction_with_options(&write_options, &transaction_options),
+            inner: TransactionInner::RocksDb(
+                db.begin_transaction_with_options(&write_options, &transaction_options),
+            ),
         }
     }
 
+    /// RocksDB memory statistics. Empty for an in-memory store.
     pub fn gather_mem_stats(&self) -> Vec<CfMemStat> {
-        let last_col = self.as_inner().default_col();
+        let db = match self.db {
+            StoreDb::RocksDb(ref db) => db,
+            StoreDb::Memory(_) => return Vec::new(),
+        };
+        let last_col = db.default_col();
         let mut result = Vec::with_capacity((last_col + 1) * 6);
 
         for c in 0..=last_col {
@@ -98,13 +139,62 @@ impl Store {
                     name: c,
                     // Skip rocksdb.
                     type_: &p[8..],
-                    value: self.as_inner().get_int_property(c, p),
+                    value: db.get_int_property(c, p),
                 })
             }
         }
         result
     }
 
+    /// RocksDB per column family storage statistics. Empty for an in-memory
+    /// store.
+    pub fn gather_cf_stats(&self) -> Vec<CfStat> {
+        let db = match self.db {
+            StoreDb::RocksDb(ref db) => db,
+            StoreDb::Memory(_) => return Vec::new(),
+        };
+        // DB wide properties, same for every column family.
+        let is_write_stopped = db.get_int_property(db.default_col(), "rocksdb.is-write-stopped");
+        let actual_delayed_write_rate =
+            db.get_int_property(db.default_col(), "rocksdb.actual-delayed-write-rate");
+
+        (0..=db.default_col())
+            .map(|c| {
+                let num_sst_files = (0..NUM_LEVELS)
+                    .map(|l| db.get_int_property(c, &format!("rocksdb.num-files-at-level{}", l)))
+                    .sum();
+                CfStat {
+                    name: c,
+                    total_sst_files_size: db.get_int_property(c, "rocksdb.total-sst-files-size"),
+                    live_sst_files_size: db.get_int_property(c, "rocksdb.live-sst-files-size"),
+                    estimate_num_keys: db.get_int_property(c, "rocksdb.estimate-num-keys"),
+                    num_sst_files,
+                    estimate_pending_compaction_bytes: db
+                        .get_int_property(c, "rocksdb.estimate-pending-compaction-bytes"),
+                    compaction_pending: db.get_int_property(c, "rocksdb.compaction-pending"),
+                    mem_table_flush_pending: db
+                        .get_int_property(c, "rocksdb.mem-table-flush-pending"),
+                    is_write_stopped,
+                    actual_delayed_write_rate,
+                }
+            })
+            .collect()
+    }
+
+    /// Manually compact keys in `[begin, end]` of column `col`. `None` means
+    /// the start/end of the column.
+    pub fn compact_range(&self, col: Col, begin: Option<&[u8]>, end: Option<&[u8]>) -> Result<()> {
+        let db = match self.db {
+            StoreDb::RocksDb(ref db) => db,
+            StoreDb::Memory(_) => bail!("in-memory store doesn't support compaction"),
+        };
+        if col > db.default_col() {
+            bail!("column {} not found", col);
+        }
+        db.compact_range(col, begin, end)?;
+        Ok(())
+    }
+
     /// Transactional range delete is not supported. If there are range deletes
     /// in the write_batch, must use this.
     pub fn write_skip_concurrency_control(&self, write_batch: &mut WriteBatch) -> Result<()> {
@@ -113,7 +203,7 @@ impl Store {
             let mut optimizations = TransactionDBWriteOptimizations::new();
         }
         optimizations.skip_concurrency_control = true;
-        self.db
+        self.as_inner()?
             .write_with_options(&options, &optimizations, write_batch)?;
         Ok(())
     }
@@ -145,19 +235,34 @@ impl Store {
     }
 
     pub fn get_snapshot(&self) -> StoreSnapshot {
-        StoreSnapshot::new(self.db.snapshot())
+        match self.db {
+            StoreDb::RocksDb(ref db) => StoreSnapshot::new(db.snapshot()),
+            StoreDb::Memory(ref db) => StoreSnapshot::new_in_memory(db.snapshot()),
+        }
     }
 
-    pub fn as_inner(&self) -> &TransactionDb {
-        &self.db
+    /// The RocksDB instance. Error if the store is in memory.
+    pub fn as_inner(&self) -> Result<&TransactionDb> {
+        match self.db {
+            StoreDb::RocksDb(ref db) => Ok(db),
+            StoreDb::Memory(_) => bail!("in-memory store has no rocksdb instance"),
+        }
     }
 
-    pub fn as_inner_mut(&mut self) -> &mut TransactionDb {
-        &mut self.db
+    /// The RocksDB instance. Error if the store is in memory.
+    pub fn as_inner_mut(&mut self) -> Result<&mut TransactionDb> {
+        match self.db {
+            StoreDb::RocksDb(ref mut db) => Ok(db),
+            StoreDb::Memory(_) => bail!("in-memory store has no rocksdb instance"),
+        }
     }
 
-    pub fn into_inner(self) -> TransactionDb {
-        self.db
+    /// The RocksDB instance. Error if the store is in memory.
+    pub fn into_inner(self) -> Result<TransactionDb> {
+        match self.db {
+            StoreDb::RocksDb(db) => Ok(db),
+            StoreDb::Memory(_) => bail!("in-memory store has no rocksdb instance"),
+        }
     }
 }
 
@@ -165,14 +270,36 @@ impl ChainStore for Store {}
 
 impl KVStoreRead for Store {
     fn get(&self, col: Col, key: &[u8]) -> Option<Box<[u8]>> {
-        slot!(slice);
-        self.db
-            .get(col, key, slice)
-            .expect("db operation should be ok")
-            .map(|p| p.as_ref().into())
+        match self.db {
+            StoreDb::RocksDb(ref db) => {
+                slot!(slice);
+                db.get(col, key, slice)
+                    .expect("db operation should be ok")
+                    .map(|p| p.as_ref().into())
+            }
+            StoreDb::Memory(ref db) => db.get(col, key),
+        }
     }
 }
 
+/// Default `num_levels` of column families.
+const NUM_LEVELS: usize = 7;
+
+#[derive(Serialize)]
+pub struct CfStat {
+    // Column name.
+    pub name: usize,
+    pub total_sst_files_size: Option<u64>,
+    pub live_sst_files_size: Option<u64>,
+    pub estimate_num_keys: Option<u64>,
+    pub num_sst_files: Option<u64>,
+    pub estimate_pending_compaction_bytes: Option<u64>,
+    pub compaction_pending: Option<u64>,
+    pub mem_table_flush_pending: Option<u64>,
+    pub is_write_stopped: Option<u64>,
+    pub actual_delayed_write_rate: Option<u64>,
+}
+
 #[derive(Serialize)]
 pub struct CfMemStat {
     // Column name.
@@ -0,0 +1,166 @@
+use autorocks::Direction;
+
+use crate::{
+    schema::COLUMNS,
+    traits::kv_store::{KVStoreRead, KVStoreWrite},
+    Store,
+};
+
+#[test]
+fn insert_get_and_rollback() {
+    let store = Store::open_in_memory(COLUMNS);
+    let mut store_txn = store.begin_transaction();
+    store_txn.insert_raw(0, &[0, 0], &[0, 0, 0]).unwrap();
+    store_txn.insert_raw(1, &[1, 1], &[1, 1, 1]).unwrap();
+
+    // uncommitted writes are only visible to the transaction itself
+    assert_eq!(&[1u8, 1, 1][..], &*store_txn.get(1, &[1, 1]).unwrap());
+    assert!(store.get(1, &[1, 1]).is_none());
+
+    store_txn.commit().unwrap();
+    assert_eq!(&[0u8, 0, 0][..], &*store.get(0, &[0, 0]).unwrap());
+    assert!(store.get(0, &[1, 1]).is_none());
+
+    let mut store_txn = store.begin_transaction();
+    store_txn.delete(1, &[1, 1]).unwrap();
+    store_txn.insert_raw(1, &[2], &[2]).unwrap();
+    assert!(store_txn.get(1, &[1, 1]).is_none());
+    store_txn.rollback().unwrap();
+    store_txn.commit().unwrap();
+    assert!(store.get(1, &[1, 1]).is_some());
+    assert!(store.get(1, &[2]).is_none());
+}
+
+#[test]
+fn snapshot_isolation() {
+    let store = Store::open_in_memory(COLUMNS);
+    let mut store_txn = store.begin_transaction();
+    store_txn.insert_raw(1, &[1], &[1]).unwrap();
+    store_txn.commit().unwrap();
+
+    let snap = store.get_snapshot();
+    let txn_snap = store_txn.snapshot();
+    let skip_cc_txn = store.begin_transaction_skip_concurrency_control();
+
+    let mut store_txn = store.begin_transaction();
+    store_txn.insert_raw(1, &[1], &[2]).unwrap();
+    store_txn.insert_raw(1, &[2], &[2]).unwrap();
+    store_txn.commit().unwrap();
+
+    assert_eq!(&[1u8][..], &*snap.get(1, &[1]).unwrap());
+    assert!(snap.get(1, &[2]).is_none());
+    assert_eq!(&[1u8][..], &*txn_snap.get(1, &[1]).unwrap());
+    assert_eq!(&[1u8][..], &*skip_cc_txn.get(1, &[1]).unwrap());
+    assert_eq!(&[2u8][..], &*store.get(1, &[1]).unwrap());
+}
+
+#[test]
+fn iterate_both_directions() {
+    let store = Store::open_in_memory(COLUMNS);
+    let mut store_txn = store.begin_transaction();
+    for k in [3u8, 0, 2, 1] {
+        store_txn.insert_raw(1, &[k], &[k, k]).unwrap();
+    }
+    store_txn.insert_raw(2, &[9], &[9]).unwrap();
+    store_txn.commit().unwrap();
+
+    // pending writes are merged into the iteration
+    let mut store_txn = store.begin_transaction();
+    store_txn.delete(1, &[2]).unwrap();
+    store_txn.insert_raw(1, &[4], &[4, 4]).unwrap();
+
+    let forward: Vec<_> = store_txn
+        .get_iter(1, Direction::Forward)
+        .map(|(k, v)| (k.to_vec(), v.to_vec()))
+        .collect();
+    assert_eq!(
+        vec![
+            (vec![0], vec![0, 0]),
+            (vec![1], vec![1, 1]),
+            (vec![3], vec![3, 3]),
+            (vec![4], vec![4, 4]),
+        ],
+        forward
+    );
+
+    let backward: Vec<_> = store_txn
+        .get_iter(1, Direction::Backward)
+        .map(|(k, _)| k.to_vec())
+        .collect();
+    assert_eq!(vec![vec![4], vec![3], vec![1], vec![0]], backward);
+
+    let mut iter = store_txn.get_iter(1, Direction::Forward);
+    iter.seek(&[2]);
+    assert_eq!(Some(&[3u8][..]), iter.key());
+    assert_eq!(vec![3], iter.next().unwrap().0.to_vec());
+    assert_eq!(vec![4], iter.next().unwrap().0.to_vec());
+    assert!(iter.next().is_none());
+    assert!(!iter.valid());
+
+    let mut iter = store_txn.get_iter(1, Direction::Backward);
+    iter.seek(&[5]);
+    assert!(!iter.valid());
+    assert!(iter.key().is_none());
+}
+
+#[test]
+fn seek_for_prev() {
+    let store = Store::open_in_memory(COLUMNS);
+    let mut store_txn = store.begin_transaction();
+    store_txn.insert_raw(1, &[0], &[0, 0, 0]).unwrap();
+    store_txn.insert_raw(1, &[1], &[1, 1, 1]).unwrap();
+    store_txn.insert_raw(2, &[2], &[2, 2, 2]).unwrap();
+    store_txn.insert_raw(1, &[3], &[3, 3, 3]).unwrap();
+    store_txn.commit().unwrap();
+
+    let store_txn = store.begin_transaction();
+    let mut raw_iter = store_txn.get_iter(1, Direction::Forward);
+    raw_iter.seek_for_prev(&[5]);
+    assert_eq!(&[3], raw_iter.key().unwrap());
+    assert_eq!(&[3, 3, 3], raw_iter.value().unwrap());
+
+    raw_iter.seek_for_prev(&[2]);
+    assert_eq!(&[1], raw_iter.key().unwrap());
+    assert_eq!(&[1, 1, 1], raw_iter.value().unwrap());
+
+    let mut raw_iter = store_txn.get_iter(1, Direction::Backward);
+    raw_iter.seek_for_prev(&[1]);
+    assert_eq!(vec![1], raw_iter.next().unwrap().0.to_vec());
+    assert_eq!(vec![0], raw_iter.next().unwrap().0.to_vec());
+    assert!(raw_iter.next().is_none());
+}
+
+#[test]
+fn write_conflict() {
+    let store = Store::open_in_memory(COLUMNS);
+    let mut txn_a = store.begin_transaction();
+    let mut txn_b = store.begin_transaction();
+    txn_a.insert_raw(1, &[1], &[1]).unwrap();
+    // key is locked by txn_a
+    assert!(txn_b.insert_raw(1, &[1], &[2]).is_err());
+    assert!(txn_b.delete(1, &[1]).is_err());
+    txn_b.insert_raw(1, &[2], &[2]).unwrap();
+
+    // skipping concurrency control takes no locks
+    let mut skip_cc_txn = store.begin_transaction_skip_concurrency_control();
+    skip_cc_txn.insert_raw(1, &[1], &[3]).unwrap();
+
+    txn_a.commit().unwrap();
+    txn_b.insert_raw(1, &[1], &[2]).unwrap();
+    txn_b.commit().unwrap();
+    assert_eq!(&[2u8][..], &*store.get(1, &[1]).unwrap());
+
+    // locks are released when a transaction is dropped
+    let mut txn_c = store.begin_transaction();
+    txn_c.insert_raw(1, &[3], &[3]).unwrap();
+    drop(txn_c);
+    let mut txn_d = store.begin_transaction();
+    txn_d.insert_raw(1, &[3], &[4]).unwrap();
+}
+
+#[test]
+fn no_rocksdb_instance() {
+    let store = Store::open_in_memory(COLUMNS);
+    assert!(store.as_inner().is_err());
+    assert!(store.into_inner().is_err());
+}
@@ -1,2 +1,3 @@
+mod mem_db;
 mod state_db;
 mod transaction;
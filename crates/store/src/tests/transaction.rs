@@ -254,7 +254,7 @@ fn delete_range() {
     store_txn.insert_raw(1, &[3], &[3, 3]).unwrap();
     store_txn.commit().unwrap();
 
-    let mut batch = store.as_inner().new_write_batch();
+    let mut batch = store.as_inner().unwrap().new_write_batch();
     batch.delete_range(1, &[0], &[3]).unwrap();
     store.write_skip_concurrency_control(&mut batch).unwrap();
 
@@ -283,7 +283,7 @@ fn range_search_and_delete() {
     store_txn.insert_raw(1, &[1, 3], &[6, 1, 2]).unwrap();
     store_txn.commit().unwrap();
 
-    let mut wb = store.as_inner().new_write_batch();
+    let mut wb = store.as_inner().unwrap().new_write_batch();
     wb.delete_range(1, &[1, 0], &[6]).unwrap();
     store.write_skip_concurrency_control(&mut wb).unwrap();
 
@@ -301,3 +301,29 @@ fn range_search_and_delete() {
 
     assert!(store_txn.get(1, &[6, 0]).is_some());
 }
+
+#[test]
+fn compact_range_and_cf_stats() {
+    let store = Store::open_tmp().unwrap();
+
+    let mut store_txn = store.begin_transaction();
+    for i in 0u8..10 {
+        store_txn.insert_raw(1, &[i], &[i, i]).unwrap();
+    }
+    store_txn.commit().unwrap();
+
+    store.compact_range(1, Some(&[0]), Some(&[9])).unwrap();
+    store.compact_range(1, None, None).unwrap();
+    assert!(store.compact_range(usize::MAX, None, None).is_err());
+
+    let stats = store.gather_cf_stats();
+    let stat = stats.iter().find(|s| s.name == 1).unwrap();
+    assert!(stat.total_sst_files_size.unwrap() > 0);
+    assert!(stat.num_sst_files.unwrap() > 0);
+    assert!(stat.estimate_num_keys.unwrap() > 0);
+    assert_eq!(stat.is_write_stopped, Some(0));
+
+    let store = Store::open_in_memory(crate::schema::COLUMNS);
+    assert!(store.gather_cf_stats().is_empty());
+    assert!(store.compact_range(1, None, None).is_err());
+}
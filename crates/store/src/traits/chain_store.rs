@@ -96,6 +96,13 @@ pub trait ChainStore: KVStoreRead {
         Some(from_box_should_be_ok!(NumberHashReader, data))
     }
 
+    fn get_scheduled_backend_forks(&self) -> packed::ScheduledBackendForkVec {
+        match self.get(COLUMN_META, META_SCHEDULED_BACKEND_FORKS_KEY) {
+            Some(data) => from_box_should_be_ok!(packed::ScheduledBackendForkVecReader, data),
+            None => Default::default(),
+        }
+    }
+
     fn get_block_status(&self, block_number: u64) -> BlockStatus {
         if Some(block_number)
             <= self
@@ -129,6 +136,17 @@ pub trait ChainStore: KVStoreRead {
         Some(from_box_should_be_ok!(packed::DepositInfoVecReader, data))
     }
 
+    /// Get the block number and index of a deposit by its L1 out point.
+    fn get_deposit_key(&self, out_point: &packed::OutPoint) -> Option<packed::DepositKey> {
+        let data = self.get(COLUMN_DEPOSIT_INFO, out_point.as_slice())?;
+        Some(from_box_should_be_ok!(packed::DepositKeyReader, data))
+    }
+
+    fn get_bad_block_deposit_info_vec(&self, block_hash: &H256) -> Option<DepositInfoVec> {
+        let data = self.get(COLUMN_BAD_BLOCK_DEPOSIT_INFO_VEC, block_hash.as_slice())?;
+        Some(from_box_should_be_ok!(packed::DepositInfoVecReader, data))
+    }
+
     fn get_block_post_finalized_custodian_capacity(
         &self,
         block_number: u64,
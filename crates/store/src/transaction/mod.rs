@@ -4,4 +4,5 @@ mod store_transaction;
 mod transaction_snapshot;
 
 pub use store_transaction::StoreTransaction;
+pub(crate) use store_transaction::TransactionInner;
 pub use transaction_snapshot::TransactionSnapshot;
@@ -4,7 +4,7 @@ use std::collections::HashSet;
 
 use anyhow::{bail, Context, Result};
 use autorocks::moveit::slot;
-use autorocks::{DbIterator, Direction};
+use autorocks::Direction;
 use gw_common::merkle_utils::calculate_state_checkpoint;
 use gw_smt::smt_h256_ext::SMTH256Ext;
 use gw_smt::{smt::SMT, smt_h256_ext::SMTH256};
@@ -18,35 +18,56 @@ use gw_types::{
     prelude::*,
 };
 
+use crate::iter::StoreIterator;
+use crate::mem_db::MemDbTransaction;
 use crate::schema::*;
 use crate::smt::smt_store::{SMTBlockStore, SMTRevertedBlockStore, SMTStateStore};
 use crate::traits::chain_store::ChainStore;
 use crate::traits::kv_store::KVStoreRead;
 use crate::traits::kv_store::{KVStore, KVStoreWrite};
 
+use super::transaction_snapshot::SnapshotInner;
 use super::TransactionSnapshot;
 
 pub struct StoreTransaction {
-    pub(crate) inner: autorocks::Transaction,
+    pub(crate) inner: TransactionInner,
+}
+
+pub(crate) enum TransactionInner {
+    RocksDb(autorocks::Transaction),
+    Memory(MemDbTransaction),
 }
 
 impl KVStoreRead for StoreTransaction {
     fn get(&self, col: Col, key: &[u8]) -> Option<Box<[u8]>> {
-        slot!(slice);
-        self.inner
-            .get(col, key, slice)
-            .expect("db operation should be ok")
-            .map(|p| p.as_ref().into())
+        match self.inner {
+            TransactionInner::RocksDb(ref inner) => {
+                slot!(slice);
+                inner
+                    .get(col, key, slice)
+                    .expect("db operation should be ok")
+                    .map(|p| p.as_ref().into())
+            }
+            TransactionInner::Memory(ref inner) => inner.get(col, key),
+        }
     }
 }
 
 impl KVStoreWrite for StoreTransaction {
     fn insert_raw(&mut self, col: Col, key: &[u8], value: &[u8]) -> Result<()> {
-        Ok(self.inner.put(col, key, value)?)
+        match self.inner {
+            TransactionInner::RocksDb(ref mut inner) => inner.put(col, key, value)?,
+            TransactionInner::Memory(ref mut inner) => inner.put(col, key, value)?,
+        }
+        Ok(())
     }
 
     fn delete(&mut self, col: Col, key: &[u8]) -> Result<()> {
-        Ok(self.inner.delete(col, key)?)
+        match self.inner {
+            TransactionInner::RocksDb(ref mut inner) => inner.delete(col, key)?,
+            TransactionInner::Memory(ref mut inner) => inner.delete(col, key)?,
+        }
+        Ok(())
     }
 }
 impl KVStore for StoreTransaction {}
@@ -54,27 +75,42 @@ impl ChainStore for StoreTransaction {}
 
 impl StoreTransaction {
     pub fn commit(&mut self) -> Result<()> {
-        self.inner.commit()?;
+        match self.inner {
+            TransactionInner::RocksDb(ref mut inner) => inner.commit()?,
+            TransactionInner::Memory(ref mut inner) => inner.commit(),
+        }
         Ok(())
     }
 
     pub fn rollback(&mut self) -> Result<()> {
-        self.inner.rollback()?;
+        match self.inner {
+            TransactionInner::RocksDb(ref mut inner) => inner.rollback()?,
+            TransactionInner::Memory(ref mut inner) => inner.rollback(),
+        }
         Ok(())
     }
 
     pub fn snapshot(&self) -> TransactionSnapshot {
-        TransactionSnapshot {
-            inner: self.inner.timestamped_snapshot(),
-        }
+        let inner = match self.inner {
+            TransactionInner::RocksDb(ref inner) => {
+                SnapshotInner::RocksDb(inner.timestamped_snapshot())
+            }
+            TransactionInner::Memory(ref inner) => {
+                SnapshotInner::Memory(inner.timestamped_snapshot())
+            }
+        };
+        TransactionSnapshot { inner }
     }
 
     pub(crate) fn get_iter(
         &self,
         col: Col,
         dir: Direction,
-    ) -> DbIterator<&'_ autorocks::Transaction> {
-        self.inner.iter(col, dir)
+    ) -> StoreIterator<'_, autorocks::Transaction> {
+        match self.inner {
+            TransactionInner::RocksDb(ref inner) => StoreIterator::RocksDb(inner.iter(col, dir)),
+            TransactionInner::Memory(ref inner) => StoreIterator::Memory(inner.iter(col, dir)),
+        }
     }
 
     pub fn setup_chain_id(&mut self, chain_id: H256) -> Result<()> {
@@ -87,6 +123,17 @@ impl StoreTransaction {
         Ok(())
     }
 
+    pub fn set_scheduled_backend_forks(
+        &mut self,
+        forks: &packed::ScheduledBackendForkVec,
+    ) -> Result<()> {
+        self.insert_raw(
+            COLUMN_META,
+            META_SCHEDULED_BACKEND_FORKS_KEY,
+            forks.as_slice(),
+        )
+    }
+
     pub fn set_tip_block_hash(&mut self, block_hash: H256) -> Result<()> {
         self.insert_raw(COLUMN_META, META_TIP_BLOCK_HASH_KEY, &block_hash)
     }
@@ -324,10 +371,25 @@ impl StoreTransaction {
             &block_number.to_be_bytes(),
             deposit_info_vec.as_slice(),
         )?;
+        // build deposit out point index
+        for (index, deposit_info) in deposit_info_vec.iter().enumerate() {
+            let key = packed::DepositKey::new_builder()
+                .block_number(block_number.pack())
+                .index((index as u32).pack())
+                .build();
+            let out_point = deposit_info.cell().out_point();
+            self.insert_raw(COLUMN_DEPOSIT_INFO, out_point.as_slice(), key.as_slice())?;
+        }
         Ok(())
     }
 
     pub fn delete_block_deposit_info_vec(&mut self, block_number: u64) -> Result<()> {
+        if let Some(deposit_info_vec) = self.get_block_deposit_info_vec(block_number) {
+            for deposit_info in deposit_info_vec {
+                let out_point = deposit_info.cell().out_point();
+                self.delete(COLUMN_DEPOSIT_INFO, out_point.as_slice())?;
+            }
+        }
         self.delete(COLUMN_BLOCK_DEPOSIT_INFO_VEC, &block_number.to_be_bytes())
     }
 
@@ -363,11 +425,17 @@ impl StoreTransaction {
         Ok(())
     }
 
+    /// Insert bad block along with its deposits, deposit asset scripts and
+    /// withdrawals, so that it can be exported later.
     pub fn insert_bad_block(
         &mut self,
         block: &packed::L2Block,
         global_state: &packed::GlobalState,
+        deposit_info_vec: &packed::DepositInfoVec,
+        deposit_asset_scripts: HashSet<Script>,
+        withdrawals: &[packed::WithdrawalRequestExtra],
     ) -> Result<()> {
+        debug_assert_eq!(block.withdrawals().len(), withdrawals.len());
         let block_hash = block.hash();
 
         let global_state = global_state.as_slice();
@@ -376,6 +444,20 @@ impl StoreTransaction {
 
         self.insert_raw(COLUMN_BAD_BLOCK, &block_hash, block.as_slice())?;
 
+        self.insert_raw(
+            COLUMN_BAD_BLOCK_DEPOSIT_INFO_VEC,
+            &block_hash,
+            deposit_info_vec.as_slice(),
+        )?;
+        self.insert_asset_scripts(deposit_asset_scripts)?;
+        for (index, withdrawal) in withdrawals.iter().enumerate() {
+            let key = WithdrawalKey::new_builder()
+                .block_hash(block_hash.pack())
+                .index(index.pack())
+                .build();
+            self.insert_raw(COLUMN_WITHDRAWAL, key.as_slice(), withdrawal.as_slice())?;
+        }
+
         // We add all block that submitted to layer-1 to block smt, even a bad block
         let mut block_smt = self.block_smt()?;
         block_smt
@@ -390,10 +472,20 @@ impl StoreTransaction {
         Ok(())
     }
 
-    /// Delete bad block and block global state.
+    /// Delete bad block, block global state, deposits and withdrawals.
     pub fn delete_bad_block(&mut self, block_hash: &H256) -> Result<()> {
+        if let Some(block) = self.get_bad_block(block_hash) {
+            for index in 0..block.withdrawals().len() {
+                let key = WithdrawalKey::new_builder()
+                    .block_hash(block_hash.pack())
+                    .index(index.pack())
+                    .build();
+                self.delete(COLUMN_WITHDRAWAL, key.as_slice())?;
+            }
+        }
         self.delete(COLUMN_BAD_BLOCK, block_hash.as_slice())?;
         self.delete(COLUMN_BLOCK_GLOBAL_STATE, block_hash.as_slice())?;
+        self.delete(COLUMN_BAD_BLOCK_DEPOSIT_INFO_VEC, block_hash.as_slice())?;
         Ok(())
     }
 
@@ -646,6 +738,11 @@ impl StoreTransaction {
             })
     }
 
+    pub fn get_asset_script_hash_iter(&self) -> impl Iterator<Item = H256> + '_ {
+        self.get_iter(COLUMN_ASSET_SCRIPT, Direction::Forward)
+            .map(|(key, _val)| key.as_ref().try_into().unwrap())
+    }
+
     pub fn get_mem_pool_transaction_iter(
         &self,
     ) -> impl Iterator<Item = (H256, packed::L2Transaction)> + '_ {
@@ -1,18 +1,29 @@
 use autorocks::{moveit::slot, SharedSnapshot};
 
+use crate::mem_db::MemDbSnapshot;
 use crate::traits::{chain_store::ChainStore, kv_store::KVStoreRead};
 
 pub struct TransactionSnapshot {
-    pub(super) inner: SharedSnapshot,
+    pub(super) inner: SnapshotInner,
+}
+
+pub(super) enum SnapshotInner {
+    RocksDb(SharedSnapshot),
+    Memory(MemDbSnapshot),
 }
 
 impl KVStoreRead for TransactionSnapshot {
     fn get(&self, col: crate::schema::Col, key: &[u8]) -> Option<Box<[u8]>> {
-        slot!(slice);
-        self.inner
-            .get(col, key, slice)
-            .unwrap()
-            .map(|p| p.as_ref().into())
+        match self.inner {
+            SnapshotInner::RocksDb(ref inner) => {
+                slot!(slice);
+                inner
+                    .get(col, key, slice)
+                    .unwrap()
+                    .map(|p| p.as_ref().into())
+            }
+            SnapshotInner::Memory(ref inner) => inner.get(col, key),
+        }
     }
 }
 
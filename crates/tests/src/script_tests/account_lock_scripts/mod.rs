@@ -1 +1,3 @@
 mod eth_account_lock;
+mod multisig_account_lock;
+mod webauthn_account_lock;
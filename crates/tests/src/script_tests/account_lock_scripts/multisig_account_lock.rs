@@ -0,0 +1,241 @@
+use crate::script_tests::programs::{
+    MULTISIG_ACCOUNT_LOCK_CODE_HASH, MULTISIG_ACCOUNT_LOCK_PROGRAM, SECP256K1_DATA,
+};
+use crate::script_tests::utils::layer1::*;
+use crate::testing_tool::chain::{ALWAYS_SUCCESS_CODE_HASH, ALWAYS_SUCCESS_PROGRAM};
+use crate::testing_tool::eth_wallet::EthWallet;
+use ckb_error::assert_error_eq;
+use ckb_script::{ScriptError, TransactionScriptsVerifier};
+use ckb_types::{
+    bytes::Bytes,
+    core::{Capacity, DepType, ScriptHashType, TransactionBuilder, TransactionView},
+    packed::{CellDep, CellInput, CellOutput, OutPoint, Script, WitnessArgs},
+    prelude::*,
+};
+use gw_generator::account_lock_manage::multisig::MultisigConfig;
+use gw_types::core::SigningType;
+use rand::{thread_rng, Rng};
+
+const ERROR_INVALID_ARGS: i8 = 5;
+const ERROR_WRONG_SIGNATURE: i8 = 41;
+
+fn random_out_point() -> OutPoint {
+    let mut rng = thread_rng();
+    let tx_hash = {
+        let mut buf = [0u8; 32];
+        rng.fill(&mut buf);
+        buf.pack()
+    };
+    OutPoint::new(tx_hash, 0)
+}
+
+fn insert_code_cell(dummy: &mut DummyDataLoader, data: &Bytes) -> OutPoint {
+    let out_point = random_out_point();
+    let cell = CellOutput::new_builder()
+        .capacity(Capacity::bytes(data.len()).expect("capacity").pack())
+        .build();
+    dummy.cells.insert(out_point.clone(), (cell, data.clone()));
+    out_point
+}
+
+fn gen_tx(
+    dummy: &mut DummyDataLoader,
+    lock_args: Bytes,
+    message: [u8; 32],
+    signatures: Bytes,
+) -> TransactionView {
+    let script_out_point = insert_code_cell(dummy, &MULTISIG_ACCOUNT_LOCK_PROGRAM);
+    let secp256k1_data_out_point = insert_code_cell(dummy, &SECP256K1_DATA);
+    let owner_lock_script_out_point = insert_code_cell(dummy, &ALWAYS_SUCCESS_PROGRAM);
+
+    // owner lock cell
+    let owner_lock_cell = CellOutput::new_builder()
+        .lock(
+            Script::new_builder()
+                .code_hash((*ALWAYS_SUCCESS_CODE_HASH).pack())
+                .hash_type(ScriptHashType::Data.into())
+                .build(),
+        )
+        .build();
+    let owner_lock_hash = owner_lock_cell.lock().calc_script_hash().unpack();
+    let owner_lock_cell_out_point = random_out_point();
+    dummy.cells.insert(
+        owner_lock_cell_out_point.clone(),
+        (owner_lock_cell, Bytes::default()),
+    );
+
+    // the cell to unlock
+    let dummy_capacity = Capacity::shannons(42);
+    let previous_out_point = random_out_point();
+    let previous_output_cell = {
+        let script = Script::new_builder()
+            .args(lock_args.pack())
+            .code_hash(CellOutput::calc_data_hash(&MULTISIG_ACCOUNT_LOCK_PROGRAM))
+            .hash_type(ScriptHashType::Data.into())
+            .build();
+        CellOutput::new_builder()
+            .capacity(dummy_capacity.pack())
+            .lock(script)
+            .build()
+    };
+    let mut input_data = owner_lock_hash.as_bytes().to_vec();
+    input_data.push(SigningType::Raw.into());
+    input_data.extend_from_slice(&message);
+    dummy.cells.insert(
+        previous_out_point.clone(),
+        (previous_output_cell, input_data.into()),
+    );
+
+    let code_dep = |out_point| {
+        CellDep::new_builder()
+            .out_point(out_point)
+            .dep_type(DepType::Code.into())
+            .build()
+    };
+    TransactionBuilder::default()
+        .cell_dep(code_dep(script_out_point))
+        .cell_dep(code_dep(secp256k1_data_out_point))
+        .cell_dep(code_dep(owner_lock_script_out_point))
+        .output(
+            CellOutput::new_builder()
+                .capacity(dummy_capacity.pack())
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .input(CellInput::new(previous_out_point, 0))
+        .input(CellInput::new(owner_lock_cell_out_point, 0))
+        .witness(
+            WitnessArgs::new_builder()
+                .lock(Some(signatures).pack())
+                .build()
+                .as_bytes()
+                .pack(),
+        )
+        .build()
+}
+
+fn verify(data_loader: &DummyDataLoader, tx: &TransactionView) -> Result<u64, ckb_error::Error> {
+    let resolved_tx = build_resolved_tx(data_loader, tx);
+    let mut verifier = TransactionScriptsVerifier::new(&resolved_tx, data_loader);
+    verifier.set_debug_printer(|_script, msg| println!("[script debug] {}", msg));
+    verifier.verify(MAX_CYCLES)
+}
+
+fn script_error(code: i8) -> ckb_error::Error {
+    let script_cell_index = 0;
+    ScriptError::ValidationFailure(
+        format!(
+            "by-data-hash/{}",
+            ckb_types::H256(*MULTISIG_ACCOUNT_LOCK_CODE_HASH)
+        ),
+        code,
+    )
+    .input_lock_script(script_cell_index)
+}
+
+/// 2-of-3 multisig
+fn setup() -> (Vec<EthWallet>, Bytes) {
+    let members: Vec<_> = (0..3).map(|_| EthWallet::random([42u8; 32])).collect();
+    let config = MultisigConfig::new(
+        2,
+        members
+            .iter()
+            .map(|m| m.registry_address.address.clone().try_into().unwrap())
+            .collect(),
+    )
+    .unwrap();
+    let lock_args = config.to_lock_args(&[42u8; 32]);
+    (members, lock_args)
+}
+
+fn sign(members: &[EthWallet], signers: &[usize], message: [u8; 32]) -> Bytes {
+    let mut signatures = Vec::new();
+    for i in signers {
+        signatures.extend_from_slice(&members[*i].sign_message(message).unwrap());
+    }
+    signatures.into()
+}
+
+#[test]
+fn test_multisig_signatures() {
+    let mut data_loader = DummyDataLoader::default();
+    let (members, lock_args) = setup();
+    let message: [u8; 32] = thread_rng().gen();
+    let tx = gen_tx(
+        &mut data_loader,
+        lock_args,
+        message,
+        sign(&members, &[2, 0], message),
+    );
+    let cycles = verify(&data_loader, &tx).expect("pass verification");
+    println!("multisig account lock cycles: {}", cycles);
+}
+
+#[test]
+fn test_multisig_threshold_not_met() {
+    let mut data_loader = DummyDataLoader::default();
+    let (members, lock_args) = setup();
+    let message: [u8; 32] = thread_rng().gen();
+    let tx = gen_tx(
+        &mut data_loader,
+        lock_args,
+        message,
+        sign(&members, &[1], message),
+    );
+    assert_error_eq!(
+        verify(&data_loader, &tx).unwrap_err(),
+        script_error(ERROR_WRONG_SIGNATURE)
+    );
+}
+
+#[test]
+fn test_multisig_duplicated_signer() {
+    let mut data_loader = DummyDataLoader::default();
+    let (members, lock_args) = setup();
+    let message: [u8; 32] = thread_rng().gen();
+    let tx = gen_tx(
+        &mut data_loader,
+        lock_args,
+        message,
+        sign(&members, &[1, 1], message),
+    );
+    assert_error_eq!(
+        verify(&data_loader, &tx).unwrap_err(),
+        script_error(ERROR_WRONG_SIGNATURE)
+    );
+}
+
+#[test]
+fn test_multisig_non_member() {
+    let mut data_loader = DummyDataLoader::default();
+    let (members, lock_args) = setup();
+    let outsider = EthWallet::random([42u8; 32]);
+    let message: [u8; 32] = thread_rng().gen();
+    let mut signatures = sign(&members, &[0], message).to_vec();
+    signatures.extend_from_slice(&outsider.sign_message(message).unwrap());
+    let tx = gen_tx(&mut data_loader, lock_args, message, signatures.into());
+    assert_error_eq!(
+        verify(&data_loader, &tx).unwrap_err(),
+        script_error(ERROR_WRONG_SIGNATURE)
+    );
+}
+
+#[test]
+fn test_multisig_tampered_args() {
+    let mut data_loader = DummyDataLoader::default();
+    let (members, lock_args) = setup();
+    // lower the threshold without updating the address
+    let mut tampered = lock_args.to_vec();
+    tampered[52] = 1;
+    let message: [u8; 32] = thread_rng().gen();
+    let tx = gen_tx(
+        &mut data_loader,
+        tampered.into(),
+        message,
+        sign(&members, &[0], message),
+    );
+    assert_error_eq!(
+        verify(&data_loader, &tx).unwrap_err(),
+        script_error(ERROR_INVALID_ARGS)
+    );
+}
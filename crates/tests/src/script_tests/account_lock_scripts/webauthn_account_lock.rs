@@ -0,0 +1,323 @@
+use crate::script_tests::programs::{
+    WEBAUTHN_ACCOUNT_LOCK_CODE_HASH, WEBAUTHN_ACCOUNT_LOCK_PROGRAM,
+};
+use crate::script_tests::utils::layer1::*;
+use crate::testing_tool::chain::{ALWAYS_SUCCESS_CODE_HASH, ALWAYS_SUCCESS_PROGRAM};
+use crate::testing_tool::webauthn_wallet::{
+    authenticator_data, client_data_json, platform_assertion, WebAuthnWallet, FLAG_USER_PRESENT,
+    FLAG_USER_VERIFIED,
+};
+use ckb_error::assert_error_eq;
+use ckb_script::{ScriptError, TransactionScriptsVerifier};
+use ckb_types::{
+    bytes::Bytes,
+    core::{Capacity, DepType, ScriptHashType, TransactionBuilder, TransactionView},
+    packed::{CellDep, CellInput, CellOutput, OutPoint, Script, WitnessArgs},
+    prelude::*,
+};
+use gw_types::core::SigningType;
+use rand::{thread_rng, Rng};
+
+const ERROR_WRONG_SIGNATURE: i8 = 41;
+
+fn random_out_point() -> OutPoint {
+    let mut rng = thread_rng();
+    let tx_hash = {
+        let mut buf = [0u8; 32];
+        rng.fill(&mut buf);
+        buf.pack()
+    };
+    OutPoint::new(tx_hash, 0)
+}
+
+fn gen_tx(
+    dummy: &mut DummyDataLoader,
+    lock_args: Bytes,
+    signing_type: SigningType,
+    message: [u8; 32],
+    signature: Bytes,
+) -> TransactionView {
+    // webauthn account lock
+    let script_out_point = random_out_point();
+    let script_cell = CellOutput::new_builder()
+        .capacity(
+            Capacity::bytes(WEBAUTHN_ACCOUNT_LOCK_PROGRAM.len())
+                .expect("script capacity")
+                .pack(),
+        )
+        .build();
+    let script_cell_data_hash = CellOutput::calc_data_hash(&WEBAUTHN_ACCOUNT_LOCK_PROGRAM);
+    dummy.cells.insert(
+        script_out_point.clone(),
+        (script_cell, WEBAUTHN_ACCOUNT_LOCK_PROGRAM.clone()),
+    );
+    // owner lock
+    let owner_lock_script_out_point = random_out_point();
+    let script_cell = CellOutput::new_builder()
+        .capacity(
+            Capacity::bytes(ALWAYS_SUCCESS_PROGRAM.len())
+                .expect("script capacity")
+                .pack(),
+        )
+        .build();
+    dummy.cells.insert(
+        owner_lock_script_out_point.clone(),
+        (script_cell, ALWAYS_SUCCESS_PROGRAM.clone()),
+    );
+    // owner lock cell
+    let owner_lock_cell = CellOutput::new_builder()
+        .lock(
+            Script::new_builder()
+                .code_hash((*ALWAYS_SUCCESS_CODE_HASH).pack())
+                .hash_type(ScriptHashType::Data.into())
+                .build(),
+        )
+        .build();
+    let owner_lock_hash = owner_lock_cell.lock().calc_script_hash().unpack();
+    let owner_lock_cell_out_point = random_out_point();
+    dummy.cells.insert(
+        owner_lock_cell_out_point.clone(),
+        (owner_lock_cell, Bytes::default()),
+    );
+
+    // the cell to unlock
+    let dummy_capacity = Capacity::shannons(42);
+    let previous_out_point = random_out_point();
+    let previous_output_cell = {
+        let script = Script::new_builder()
+            .args(lock_args.pack())
+            .code_hash(script_cell_data_hash)
+            .hash_type(ScriptHashType::Data.into())
+            .build();
+        CellOutput::new_builder()
+            .capacity(dummy_capacity.pack())
+            .lock(script)
+            .build()
+    };
+    let mut input_data = owner_lock_hash.as_bytes().to_vec();
+    input_data.push(signing_type.into());
+    input_data.extend_from_slice(&message);
+    dummy.cells.insert(
+        previous_out_point.clone(),
+        (previous_output_cell, input_data.into()),
+    );
+
+    TransactionBuilder::default()
+        .cell_dep(
+            CellDep::new_builder()
+                .out_point(script_out_point)
+                .dep_type(DepType::Code.into())
+                .build(),
+        )
+        .cell_dep(
+            CellDep::new_builder()
+                .out_point(owner_lock_script_out_point)
+                .dep_type(DepType::Code.into())
+                .build(),
+        )
+        .output(
+            CellOutput::new_builder()
+                .capacity(dummy_capacity.pack())
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .input(CellInput::new(previous_out_point, 0))
+        .input(CellInput::new(owner_lock_cell_out_point, 0))
+        .witness(
+            WitnessArgs::new_builder()
+                .lock(Some(signature).pack())
+                .build()
+                .as_bytes()
+                .pack(),
+        )
+        .build()
+}
+
+fn verify(data_loader: &DummyDataLoader, tx: &TransactionView) -> Result<u64, ckb_error::Error> {
+    let resolved_tx = build_resolved_tx(data_loader, tx);
+    let mut verifier = TransactionScriptsVerifier::new(&resolved_tx, data_loader);
+    verifier.set_debug_printer(|_script, msg| println!("[script debug] {}", msg));
+    verifier.verify(MAX_CYCLES)
+}
+
+fn wrong_signature_error() -> ckb_error::Error {
+    let script_cell_index = 0;
+    ScriptError::ValidationFailure(
+        format!(
+            "by-data-hash/{}",
+            ckb_types::H256(*WEBAUTHN_ACCOUNT_LOCK_CODE_HASH)
+        ),
+        ERROR_WRONG_SIGNATURE,
+    )
+    .input_lock_script(script_cell_index)
+}
+
+fn random_wallet_and_args() -> (WebAuthnWallet, Bytes) {
+    let rollup_script_hash = [42u8; 32];
+    let wallet = WebAuthnWallet::random(rollup_script_hash, [0u8; 32]);
+    let mut args = rollup_script_hash.to_vec();
+    args.extend_from_slice(&wallet.pubkey_hash());
+    (wallet, args.into())
+}
+
+#[test]
+fn test_webauthn_assertion() {
+    let mut data_loader = DummyDataLoader::default();
+    let (wallet, lock_args) = random_wallet_and_args();
+    let message: [u8; 32] = thread_rng().gen();
+    let signature = wallet.sign_message(message);
+    let tx = gen_tx(
+        &mut data_loader,
+        lock_args,
+        SigningType::Raw,
+        message,
+        signature,
+    );
+    let cycles = verify(&data_loader, &tx).expect("pass verification");
+    println!("webauthn account lock cycles: {}", cycles);
+}
+
+#[test]
+fn test_webauthn_platform_assertion() {
+    let mut data_loader = DummyDataLoader::default();
+    let lock_args = {
+        let mut args = [42u8; 32].to_vec();
+        args.extend(hex::decode(platform_assertion::PUBKEY_HASH).unwrap());
+        Bytes::from(args)
+    };
+    // The unlock cell of a tx signature challenge carries the EIP-712 message
+    // of the challenged tx.
+    let message: [u8; 32] = hex::decode(platform_assertion::MESSAGE)
+        .unwrap()
+        .try_into()
+        .unwrap();
+    let tx = gen_tx(
+        &mut data_loader,
+        lock_args,
+        SigningType::Raw,
+        message,
+        platform_assertion::signature(),
+    );
+    verify(&data_loader, &tx).expect("pass verification");
+}
+
+#[test]
+fn test_webauthn_wrong_challenge() {
+    let mut data_loader = DummyDataLoader::default();
+    let (wallet, lock_args) = random_wallet_and_args();
+    let message: [u8; 32] = thread_rng().gen();
+    let signature = wallet.sign_message(thread_rng().gen());
+    let tx = gen_tx(
+        &mut data_loader,
+        lock_args,
+        SigningType::Raw,
+        message,
+        signature,
+    );
+    assert_error_eq!(
+        verify(&data_loader, &tx).unwrap_err(),
+        wrong_signature_error()
+    );
+}
+
+#[test]
+fn test_webauthn_user_not_verified() {
+    let mut data_loader = DummyDataLoader::default();
+    let (wallet, lock_args) = random_wallet_and_args();
+    let message: [u8; 32] = thread_rng().gen();
+    let signature = wallet.sign_message_with_flags(message, FLAG_USER_PRESENT);
+    let tx = gen_tx(
+        &mut data_loader,
+        lock_args,
+        SigningType::Raw,
+        message,
+        signature,
+    );
+    assert_error_eq!(
+        verify(&data_loader, &tx).unwrap_err(),
+        wrong_signature_error()
+    );
+}
+
+#[test]
+fn test_webauthn_high_s() {
+    let mut data_loader = DummyDataLoader::default();
+    let (wallet, lock_args) = random_wallet_and_args();
+    let message: [u8; 32] = thread_rng().gen();
+    let signature = wallet.sign_assertion_high_s(
+        authenticator_data(FLAG_USER_PRESENT | FLAG_USER_VERIFIED),
+        client_data_json(message, "https://example.com"),
+    );
+    let tx = gen_tx(
+        &mut data_loader,
+        lock_args,
+        SigningType::Raw,
+        message,
+        signature,
+    );
+    assert_error_eq!(
+        verify(&data_loader, &tx).unwrap_err(),
+        wrong_signature_error()
+    );
+}
+
+#[test]
+fn test_webauthn_wrong_origin() {
+    let mut data_loader = DummyDataLoader::default();
+    let (wallet, lock_args) = random_wallet_and_args();
+    let message: [u8; 32] = thread_rng().gen();
+    let signature = wallet.sign_assertion(
+        authenticator_data(FLAG_USER_PRESENT | FLAG_USER_VERIFIED),
+        client_data_json(message, "https://example.com.evil.com"),
+    );
+    let tx = gen_tx(
+        &mut data_loader,
+        lock_args,
+        SigningType::Raw,
+        message,
+        signature,
+    );
+    assert_error_eq!(
+        verify(&data_loader, &tx).unwrap_err(),
+        wrong_signature_error()
+    );
+}
+
+#[test]
+fn test_webauthn_wrong_key() {
+    let mut data_loader = DummyDataLoader::default();
+    let (_wallet, lock_args) = random_wallet_and_args();
+    let (other, _) = random_wallet_and_args();
+    let message: [u8; 32] = thread_rng().gen();
+    let signature = other.sign_message(message);
+    let tx = gen_tx(
+        &mut data_loader,
+        lock_args,
+        SigningType::Raw,
+        message,
+        signature,
+    );
+    assert_error_eq!(
+        verify(&data_loader, &tx).unwrap_err(),
+        wrong_signature_error()
+    );
+}
+
+#[test]
+fn test_webauthn_reject_prefixed_message() {
+    let mut data_loader = DummyDataLoader::default();
+    let (wallet, lock_args) = random_wallet_and_args();
+    let message: [u8; 32] = thread_rng().gen();
+    let signature = wallet.sign_message(message);
+    let tx = gen_tx(
+        &mut data_loader,
+        lock_args,
+        SigningType::WithPrefix,
+        message,
+        signature,
+    );
+    assert_error_eq!(
+        verify(&data_loader, &tx).unwrap_err(),
+        wrong_signature_error()
+    );
+}
@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 
 use gw_builtin_binaries::{file_checksum, Resource};
 use gw_common::{
@@ -11,12 +11,14 @@ use gw_generator::{
     },
     backend_manage::BackendManage,
     error::TransactionError,
+    profiler::Profile,
     syscalls::error_codes::{GW_ERROR_ACCOUNT_NOT_FOUND, GW_ERROR_RECOVER, GW_FATAL_UNKNOWN_ARGS},
     traits::StateExt,
     Generator,
 };
 use gw_smt::smt::{SMT, SMTH256};
 use gw_store::{
+    schema::COLUMNS,
     smt::smt_store::SMTStateStore,
     snapshot::StoreSnapshot,
     state::{
@@ -54,7 +56,7 @@ fn new_state(store: StoreSnapshot) -> MemStateDB {
 
 #[test]
 fn test_example_sum() {
-    let store = Store::open_tmp().unwrap();
+    let store = Store::open_in_memory(COLUMNS);
     let mut tree = new_state(store.get_snapshot());
     let chain_view = DummyChainStore;
     let sender_script = Script::new_builder()
@@ -133,6 +135,92 @@ fn test_example_sum() {
     }
 }
 
+#[test]
+fn test_example_sum_profile() {
+    let store = Store::open_in_memory(COLUMNS);
+    let mut tree = new_state(store.get_snapshot());
+    let chain_view = DummyChainStore;
+    let from_id = tree
+        .create_account_from_script(
+            Script::new_builder()
+                .code_hash(ALWAYS_SUCCESS_CODE_HASH.pack())
+                .args([1u8; 20].to_vec().pack())
+                .hash_type(ScriptHashType::Type.into())
+                .build(),
+        )
+        .expect("create account");
+    let contract_id = tree
+        .create_account_from_script(
+            Script::new_builder()
+                .code_hash(SUM_PROGRAM_CODE_HASH.pack())
+                .args([0u8; 20].to_vec().pack())
+                .hash_type(ScriptHashType::Type.into())
+                .build(),
+        )
+        .expect("create account");
+
+    // The generator is stripped, symbols are loaded from the `.debug` companion
+    let mut symbols_path = SUM_PROGRAM_PATH.clone().into_os_string();
+    symbols_path.push(".debug");
+    assert!(PathBuf::from(symbols_path).exists());
+    let backend_manage = BackendManage::from_config(vec![BackendForkConfig {
+        fork_height: 0,
+        sudt_proxy: Default::default(),
+        backends: vec![BackendConfig {
+            generator: Resource::file_system(SUM_PROGRAM_PATH.to_path_buf()),
+            generator_checksum: file_checksum(&*SUM_PROGRAM_PATH).unwrap().into(),
+            validator_script_type_hash: (*SUM_PROGRAM_CODE_HASH).into(),
+            backend_type: BackendType::Unknown,
+        }],
+    }])
+    .unwrap();
+    let rollup_context = RollupContext {
+        rollup_config: Default::default(),
+        rollup_script_hash: [42u8; 32],
+        ..Default::default()
+    };
+    let generator = Generator::new(
+        backend_manage,
+        AccountLockManage::default(),
+        rollup_context,
+        Default::default(),
+    );
+
+    let block_info = new_block_info(&Default::default(), 1, 0);
+    let raw_tx = RawL2Transaction::new_builder()
+        .from_id(from_id.pack())
+        .to_id(contract_id.pack())
+        .args(Bytes::from(7u64.to_le_bytes().to_vec()).pack())
+        .build();
+    let mut profile = Profile::default();
+    let run_result = generator
+        .execute_transaction_with_profile(
+            &chain_view,
+            &mut tree,
+            &block_info,
+            &raw_tx,
+            None,
+            None,
+            &mut profile,
+        )
+        .expect("profile");
+    assert_eq!(run_result.return_data.as_ref(), 7u64.to_le_bytes());
+
+    assert!(profile.execution_cycles() > 0);
+    let folded = profile.folded_stacks();
+    assert!(
+        folded
+            .iter()
+            .any(|stack| stack.split(&[';', ' ']).any(|frame| frame == "main")),
+        "{:?}",
+        folded
+    );
+    assert!(profile
+        .syscall_report()
+        .iter()
+        .any(|(_code, name, p)| *name == "load" && p.count > 0));
+}
+
 pub enum AccountOp {
     Load {
         account_id: u32,
@@ -196,7 +284,7 @@ impl AccountOp {
 
 #[test]
 fn test_example_account_operation() {
-    let store = Store::open_tmp().unwrap();
+    let store = Store::open_in_memory(COLUMNS);
     let mut tree = new_state(store.get_snapshot());
     let chain_view = DummyChainStore;
 
@@ -434,7 +522,7 @@ fn test_example_account_operation() {
 
 #[test]
 fn test_example_recover_account() {
-    let store = Store::open_tmp().unwrap();
+    let store = Store::open_in_memory(COLUMNS);
     let mut tree = new_state(store.get_snapshot());
     let chain_view = DummyChainStore;
 
@@ -581,7 +669,7 @@ fn test_example_recover_account() {
 
 #[test]
 fn test_sudt_total_supply() {
-    let store = Store::open_tmp().unwrap();
+    let store = Store::open_in_memory(COLUMNS);
     let mut tree = new_state(store.get_snapshot());
     let chain_view = DummyChainStore;
     let rollup_config = RollupConfig::new_builder()
@@ -13,6 +13,7 @@ use gw_generator::traits::StateExt;
 use gw_smt::smt::SMT;
 use gw_smt::smt::SMTH256;
 use gw_store::{
+    schema::COLUMNS,
     smt::smt_store::SMTStateStore,
     snapshot::StoreSnapshot,
     state::{
@@ -81,7 +82,7 @@ impl TestingContext {
         };
 
         // deploy registry contract
-        let store = Store::open_tmp().unwrap();
+        let store = Store::open_in_memory(COLUMNS);
         let mut state = new_state(store.get_snapshot());
 
         // setup meta_contract
@@ -1,3 +1,4 @@
+mod fast_withdrawal;
 mod finality;
 
 use crate::script_tests::programs::{
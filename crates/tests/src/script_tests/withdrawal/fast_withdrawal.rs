@@ -0,0 +1,302 @@
+//! Fast withdrawal transfers run against the withdrawal lock built from
+//! `gwos/contracts`, the prebuilt binaries don't support
+//! `UnlockWithdrawalViaTransfer`.
+
+use crate::script_tests::programs::WITHDRAWAL_LOCK_PROGRAM;
+use crate::testing_tool::chain::{ALWAYS_SUCCESS_CODE_HASH, ALWAYS_SUCCESS_PROGRAM};
+use crate::testing_tool::verify_tx::{verify_tx, TxWithContext};
+
+use gw_block_producer::utils::global_state_last_finalized_timepoint_to_since;
+use gw_config::ContractsCellDep;
+use gw_types::bytes::Bytes;
+use gw_types::core::{DepType, ScriptHashType, Timepoint};
+use gw_types::offchain::{CellInfo, InputCellInfo};
+use gw_types::packed::{
+    CellDep, CellOutput, GlobalState, OutPoint, RollupConfig, Script, WithdrawalLockArgs,
+};
+use gw_types::prelude::*;
+use gw_utils::transaction_skeleton::TransactionSkeleton;
+use gw_utils::withdrawal::{build_lock_args, parse_lock_args, transfer_to_new_owner};
+
+const CKB: u64 = 100000000;
+const MAX_CYCLES: u64 = 700_000_000;
+
+struct Context {
+    rollup_type_hash: [u8; 32],
+    rollup_config: RollupConfig,
+    withdrawal_lock_type: Script,
+    cell_deps: Vec<InputCellInfo>,
+    contracts_dep: ContractsCellDep,
+}
+
+impl Context {
+    fn new() -> Self {
+        let always_cell = random_contract_cell(ALWAYS_SUCCESS_PROGRAM.clone());
+        let withdrawal_lock_cell = random_contract_cell(WITHDRAWAL_LOCK_PROGRAM.clone());
+        let withdrawal_lock_type = withdrawal_lock_cell.output.type_().to_opt().unwrap();
+
+        let rollup_config = RollupConfig::new_builder()
+            .withdrawal_script_type_hash(withdrawal_lock_type.hash().pack())
+            .finality_blocks(1u64.pack())
+            .build();
+        let contracts_dep = ContractsCellDep {
+            withdrawal_cell_lock: to_cell_dep(&withdrawal_lock_cell).into(),
+            l1_sudt_type: to_cell_dep(&always_cell).into(),
+            ..Default::default()
+        };
+
+        Context {
+            rollup_type_hash: rand::random(),
+            rollup_config,
+            withdrawal_lock_type,
+            cell_deps: vec![always_cell.into(), withdrawal_lock_cell.into()],
+            contracts_dep,
+        }
+    }
+
+    fn always_success_dep(&self) -> CellDep {
+        to_cell_dep(&self.cell_deps[0].cell)
+    }
+
+    fn withdrawal_cell(&self, owner_lock: &Script, finalized_timepoint: &Timepoint) -> CellInfo {
+        let lock_args = WithdrawalLockArgs::new_builder()
+            .withdrawal_block_hash(rand::random::<[u8; 32]>().pack())
+            .withdrawal_finalized_timepoint(finalized_timepoint.full_value().pack())
+            .account_script_hash(rand::random::<[u8; 32]>().pack())
+            .owner_lock_hash(owner_lock.hash().pack())
+            .build();
+        let args = build_lock_args(&self.rollup_type_hash, &lock_args, owner_lock);
+        let lock = Script::new_builder()
+            .code_hash(self.withdrawal_lock_type.hash().pack())
+            .hash_type(ScriptHashType::Type.into())
+            .args(args.pack())
+            .build();
+        random_cell(lock, 1000 * CKB)
+    }
+
+    fn verify(&self, tx_skeleton: TransactionSkeleton) -> anyhow::Result<u64> {
+        let tx_with_context = TxWithContext {
+            inputs: tx_skeleton.inputs().clone(),
+            cell_deps: self.cell_deps.clone(),
+            tx: tx_skeleton.seal(&[], vec![])?.transaction,
+        };
+        verify_tx(tx_with_context, MAX_CYCLES)
+    }
+}
+
+#[test]
+fn test_fast_withdrawal_transfer_and_unlock() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    const FEE: u64 = 10 * CKB;
+    const TX_FEE: u64 = 1000;
+
+    let ctx = Context::new();
+    let owner_lock = random_always_success_script();
+    let lp_lock = random_always_success_script();
+
+    // Pending withdrawal, finalized at 10s
+    let finalized_timepoint = Timepoint::from_timestamp(10_000);
+    let withdrawal_cell = ctx.withdrawal_cell(&owner_lock, &finalized_timepoint);
+    let capacity: u64 = withdrawal_cell.output.capacity().unpack();
+    let owner_cell = random_cell(owner_lock.clone(), 100 * CKB);
+    let lp_cell = random_cell(lp_lock.clone(), 2000 * CKB);
+
+    let transferred = transfer_to_new_owner(
+        withdrawal_cell,
+        owner_cell,
+        &lp_lock,
+        capacity - FEE,
+        ctx.contracts_dep.withdrawal_cell_lock.clone().into(),
+    )
+    .expect("transfer");
+
+    // The LP pays the owner and the tx fee
+    let lp_change = CellOutput::new_builder()
+        .capacity((2000 * CKB - (capacity - FEE) - TX_FEE).pack())
+        .lock(lp_lock.clone())
+        .build();
+    let mut tx_skeleton = TransactionSkeleton::default();
+    tx_skeleton.cell_deps_mut().extend(transferred.deps.clone());
+    tx_skeleton.cell_deps_mut().push(ctx.always_success_dep());
+    tx_skeleton.inputs_mut().extend(transferred.inputs.clone());
+    tx_skeleton.inputs_mut().push(lp_cell.into());
+    tx_skeleton
+        .witnesses_mut()
+        .extend(transferred.witness_args.clone());
+    tx_skeleton
+        .outputs_mut()
+        .extend(transferred.outputs.clone());
+    tx_skeleton.outputs_mut().push((lp_change, Bytes::new()));
+    assert_eq!(tx_skeleton.calculate_fee().unwrap(), TX_FEE);
+    ctx.verify(tx_skeleton).expect("transfer to lp");
+
+    // The withdrawal is owned by the LP now, the owner gets paid
+    let (transferred_output, transferred_data) = transferred.outputs[0].clone();
+    let args: Bytes = transferred_output.lock().args().unpack();
+    let parsed = parse_lock_args(&args).unwrap();
+    assert_eq!(parsed.owner_lock.hash(), lp_lock.hash());
+    let transferred_timepoint: u64 = parsed.lock_args.withdrawal_finalized_timepoint().unpack();
+    assert_eq!(transferred_timepoint, finalized_timepoint.full_value());
+    let (paid, _) = &transferred.outputs[1];
+    assert_eq!(paid.lock().hash(), owner_lock.hash());
+    let paid_capacity: u64 = paid.capacity().unpack();
+    assert_eq!(paid_capacity, 100 * CKB + capacity - FEE);
+
+    // After finality, the withdrawal is unlocked to the LP
+    let transferred_cell = CellInfo {
+        out_point: random_out_point(),
+        output: transferred_output,
+        data: transferred_data,
+    };
+    let global_state = GlobalState::new_builder()
+        .last_finalized_timepoint(Timepoint::from_timestamp(20_000).full_value().pack())
+        .build();
+    let rollup_cell = CellInfo {
+        out_point: random_out_point(),
+        output: CellOutput::new_builder().build(),
+        data: global_state.as_bytes(),
+    };
+    let unlocked = gw_block_producer::withdrawal::unlock_to_owner(
+        rollup_cell,
+        &ctx.rollup_config,
+        &ctx.contracts_dep,
+        vec![transferred_cell],
+        global_state_last_finalized_timepoint_to_since(&global_state),
+    )
+    .expect("unlock")
+    .expect("unlock to lp");
+    assert_eq!(unlocked.outputs[0].0.lock().hash(), lp_lock.hash());
+
+    let mut tx_skeleton = TransactionSkeleton::default();
+    tx_skeleton.cell_deps_mut().extend(unlocked.deps);
+    tx_skeleton.inputs_mut().extend(unlocked.inputs);
+    tx_skeleton.witnesses_mut().extend(unlocked.witness_args);
+    tx_skeleton.outputs_mut().extend(unlocked.outputs);
+    ctx.verify(tx_skeleton).expect("unlock to lp");
+}
+
+#[test]
+fn test_fast_withdrawal_transfer_requires_owner() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let ctx = Context::new();
+    let owner_lock = random_always_success_script();
+    let lp_lock = random_always_success_script();
+    let finalized_timepoint = Timepoint::from_timestamp(10_000);
+    let withdrawal_cell = ctx.withdrawal_cell(&owner_lock, &finalized_timepoint);
+    let owner_cell = random_cell(owner_lock.clone(), 100 * CKB);
+
+    // Only the owner cell can unlock the transfer
+    let err = transfer_to_new_owner(
+        withdrawal_cell.clone(),
+        random_cell(lp_lock.clone(), 100 * CKB),
+        &lp_lock,
+        0,
+        ctx.contracts_dep.withdrawal_cell_lock.clone().into(),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("owner cell lock not match"));
+
+    let transferred = transfer_to_new_owner(
+        withdrawal_cell,
+        owner_cell,
+        &lp_lock,
+        0,
+        ctx.contracts_dep.withdrawal_cell_lock.clone().into(),
+    )
+    .expect("transfer");
+    let build_tx = |inputs: Vec<InputCellInfo>, outputs: Vec<(CellOutput, Bytes)>| {
+        let mut tx_skeleton = TransactionSkeleton::default();
+        tx_skeleton.cell_deps_mut().extend(transferred.deps.clone());
+        tx_skeleton.cell_deps_mut().push(ctx.always_success_dep());
+        tx_skeleton.inputs_mut().extend(inputs);
+        tx_skeleton
+            .witnesses_mut()
+            .extend(transferred.witness_args.clone());
+        tx_skeleton.outputs_mut().extend(outputs);
+        tx_skeleton
+    };
+
+    // No owner cell
+    let tx_skeleton = build_tx(
+        transferred.inputs[..1].to_vec(),
+        transferred.outputs[..1].to_vec(),
+    );
+    ctx.verify(tx_skeleton).expect_err("no owner cell");
+
+    // Change the withdrawal
+    let (output, data) = transferred.outputs[0].clone();
+    let mut args = output.lock().args().raw_data().to_vec();
+    args[32] ^= 1; // withdrawal block hash
+    let err_output = output
+        .clone()
+        .as_builder()
+        .lock(output.lock().as_builder().args(args.pack()).build())
+        .build();
+    let mut outputs = transferred.outputs.clone();
+    outputs[0] = (err_output, data.clone());
+    let tx_skeleton = build_tx(transferred.inputs.clone(), outputs);
+    ctx.verify(tx_skeleton).expect_err("changed withdrawal");
+
+    // Change the capacity
+    let err_output = output
+        .as_builder()
+        .capacity((1000 * CKB - 1).pack())
+        .build();
+    let mut outputs = transferred.outputs.clone();
+    outputs[0] = (err_output, data);
+    let tx_skeleton = build_tx(transferred.inputs.clone(), outputs);
+    ctx.verify(tx_skeleton).expect_err("changed capacity");
+
+    let tx_skeleton = build_tx(transferred.inputs.clone(), transferred.outputs.clone());
+    ctx.verify(tx_skeleton).expect("transfer");
+}
+
+fn to_cell_dep(cell: &CellInfo) -> CellDep {
+    CellDep::new_builder()
+        .out_point(cell.out_point.clone())
+        .dep_type(DepType::Code.into())
+        .build()
+}
+
+fn random_out_point() -> OutPoint {
+    OutPoint::new_builder()
+        .tx_hash(rand::random::<[u8; 32]>().pack())
+        .build()
+}
+
+fn random_cell(lock: Script, capacity: u64) -> CellInfo {
+    CellInfo {
+        out_point: random_out_point(),
+        output: CellOutput::new_builder()
+            .capacity(capacity.pack())
+            .lock(lock)
+            .build(),
+        data: Bytes::new(),
+    }
+}
+
+fn random_contract_cell(program: Bytes) -> CellInfo {
+    let type_ = Script::new_builder()
+        .code_hash(ALWAYS_SUCCESS_CODE_HASH.clone().pack())
+        .hash_type(ScriptHashType::Data.into())
+        .args(rand::random::<[u8; 32]>().to_vec().pack())
+        .build();
+    CellInfo {
+        out_point: random_out_point(),
+        output: CellOutput::new_builder()
+            .capacity((1000 * CKB).pack())
+            .type_(Some(type_).pack())
+            .build(),
+        data: program,
+    }
+}
+
+fn random_always_success_script() -> Script {
+    Script::new_builder()
+        .code_hash(ALWAYS_SUCCESS_CODE_HASH.clone().pack())
+        .hash_type(ScriptHashType::Data.into())
+        .args(rand::random::<[u8; 20]>().to_vec().pack())
+        .build()
+}
@@ -20,7 +20,9 @@ use gw_generator::{
     Generator,
 };
 use gw_mem_pool::pool::{MemPool, MemPoolCreateArgs, OutputParam};
-use gw_store::{mem_pool_state::MemPoolState, traits::chain_store::ChainStore, Store};
+use gw_store::{
+    mem_pool_state::MemPoolState, schema::COLUMNS, traits::chain_store::ChainStore, Store,
+};
 use gw_types::{
     bytes::Bytes,
     core::{AllowedContractType, AllowedEoaType, ScriptHashType},
@@ -358,9 +360,9 @@ pub fn build_backend_manage(rollup_config: &RollupConfig) -> BackendManage {
     .expect("default backend")
 }
 
-pub async fn setup_chain(rollup_type_script: Script) -> Chain {
-    let mut account_lock_manage = AccountLockManage::default();
-    let rollup_config = RollupConfig::new_builder()
+/// Rollup config of [`setup_chain`].
+pub fn default_rollup_config() -> RollupConfig {
+    RollupConfig::new_builder()
         .allowed_eoa_type_hashes(
             vec![
                 AllowedTypeHash::new(AllowedEoaType::Eth, *ETH_ACCOUNT_LOCK_CODE_HASH),
@@ -386,7 +388,11 @@ pub async fn setup_chain(rollup_type_script: Script) -> Chain {
         .l2_sudt_validator_script_type_hash(SUDT_VALIDATOR_CODE_HASH.pack())
         .finality_blocks(DEFAULT_FINALITY_BLOCKS.pack())
         .chain_id(TEST_CHAIN_ID.pack())
-        .build();
+        .build()
+}
+
+pub async fn setup_chain(rollup_type_script: Script) -> Chain {
+    let mut account_lock_manage = AccountLockManage::default();
     account_lock_manage.register_lock_algorithm(*ALWAYS_SUCCESS_CODE_HASH, Arc::new(AlwaysSuccess));
     account_lock_manage.register_lock_algorithm(
         *ETH_ACCOUNT_LOCK_CODE_HASH,
@@ -394,7 +400,7 @@ pub async fn setup_chain(rollup_type_script: Script) -> Chain {
     );
     let chain = setup_chain_with_account_lock_manage(
         rollup_type_script,
-        rollup_config,
+        default_rollup_config(),
         account_lock_manage,
         None,
         None,
@@ -489,7 +495,7 @@ pub async fn setup_chain_with_account_lock_manage(
     opt_mem_pool_config: Option<MemPoolConfig>,
     opt_mem_pool_provider: Option<DummyMemPoolProvider>,
 ) -> Chain {
-    let store = opt_store.unwrap_or_else(|| Store::open_tmp().unwrap());
+    let store = opt_store.unwrap_or_else(|| Store::open_in_memory(COLUMNS));
     let mem_pool_config = opt_mem_pool_config.unwrap_or_else(|| MemPoolConfig {
         restore_path: tempfile::TempDir::new().unwrap().path().to_path_buf(),
         ..Default::default()
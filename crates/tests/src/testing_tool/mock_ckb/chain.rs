@@ -0,0 +1,340 @@
+#![allow(clippy::mutable_key_type)]
+
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+
+use ckb_script::TransactionScriptsVerifier;
+use ckb_traits::{CellDataProvider, HeaderProvider};
+use ckb_types::{
+    bytes::Bytes,
+    core::{
+        cell::{CellMeta, CellMetaBuilder, ResolvedTransaction},
+        BlockBuilder, BlockView, Capacity, Cycle, DepType, EpochNumberWithFraction, HeaderView,
+        TransactionInfo, TransactionView,
+    },
+    packed::{Byte32, CellOutput, OutPoint, OutPointVec},
+    prelude::*,
+};
+
+pub const MAX_CYCLES: Cycle = 70_000_000_000;
+pub const BLOCK_INTERVAL_MILLIS: u64 = 8_000;
+pub const EPOCH_LENGTH: u64 = 1_000;
+const MEDIAN_TIME_BLOCK_COUNT: usize = 37;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SendTxError {
+    #[error("transaction {0} is already in the pool or committed")]
+    Duplicated(Byte32),
+    #[error("failed to resolve transaction: {0}")]
+    Resolve(String),
+    #[error("failed to verify transaction: {0}")]
+    Verify(String),
+}
+
+#[derive(Clone)]
+pub struct CellEntry {
+    pub output: CellOutput,
+    pub data: Bytes,
+    pub block_number: u64,
+    pub tx_index: u32,
+    pub consumed: bool,
+}
+
+#[derive(Clone)]
+pub enum TxStatus {
+    Pending,
+    Committed { block_hash: Byte32 },
+}
+
+/// In-memory L1 chain: a UTXO set, a tx pool and blocks mined on demand.
+///
+/// Transactions are resolved against live cells and outputs of pending
+/// transactions, and their scripts are verified when they are sent. Since and
+/// other consensus rules are not checked.
+pub struct MockChain {
+    blocks: Vec<BlockView>,
+    block_numbers: HashMap<Byte32, u64>,
+    transactions: HashMap<Byte32, (TransactionView, TxStatus)>,
+    cells: HashMap<OutPoint, CellEntry>,
+    pool: Vec<TransactionView>,
+    pool_cells: HashMap<OutPoint, (CellOutput, Bytes)>,
+    pool_spent: HashSet<OutPoint>,
+}
+
+impl MockChain {
+    /// Create a chain with the genesis transactions, which are not verified.
+    pub fn new(genesis_transactions: Vec<TransactionView>, genesis_timestamp: u64) -> Self {
+        let mut chain = MockChain {
+            blocks: Vec::new(),
+            block_numbers: HashMap::new(),
+            transactions: HashMap::new(),
+            cells: HashMap::new(),
+            pool: Vec::new(),
+            pool_cells: HashMap::new(),
+            pool_spent: HashSet::new(),
+        };
+        let genesis = BlockBuilder::default()
+            .number(0.pack())
+            .timestamp(genesis_timestamp.pack())
+            .epoch(EpochNumberWithFraction::new(0, 0, EPOCH_LENGTH).pack())
+            .transactions(genesis_transactions)
+            .build();
+        chain.commit_block(genesis);
+        chain
+    }
+
+    pub fn tip(&self) -> &BlockView {
+        self.blocks.last().expect("genesis")
+    }
+
+    pub fn block_by_number(&self, number: u64) -> Option<&BlockView> {
+        self.blocks.get(number as usize)
+    }
+
+    pub fn block(&self, block_hash: &Byte32) -> Option<&BlockView> {
+        let number = self.block_numbers.get(block_hash)?;
+        self.block_by_number(*number)
+    }
+
+    pub fn blocks(&self) -> &[BlockView] {
+        &self.blocks
+    }
+
+    pub fn transaction(&self, tx_hash: &Byte32) -> Option<&(TransactionView, TxStatus)> {
+        self.transactions.get(tx_hash)
+    }
+
+    /// Committed cell, live or consumed.
+    pub fn cell(&self, out_point: &OutPoint) -> Option<&CellEntry> {
+        self.cells.get(out_point)
+    }
+
+    pub fn pending_transactions(&self) -> &[TransactionView] {
+        &self.pool
+    }
+
+    /// Median timestamp of the block and its previous blocks.
+    pub fn block_median_time(&self, block_hash: &Byte32) -> Option<u64> {
+        let number = *self.block_numbers.get(block_hash)? as usize;
+        let start = (number + 1).saturating_sub(MEDIAN_TIME_BLOCK_COUNT);
+        let mut timestamps: Vec<u64> = { self.blocks[start..=number].iter() }
+            .map(|b| b.timestamp())
+            .collect();
+        timestamps.sort_unstable();
+        Some(timestamps[timestamps.len() / 2])
+    }
+
+    /// Verify the transaction and add it to the pool.
+    pub fn send_transaction(&mut self, tx: TransactionView) -> Result<Byte32, SendTxError> {
+        let tx_hash = tx.hash();
+        if self.transactions.contains_key(&tx_hash) {
+            return Err(SendTxError::Duplicated(tx_hash));
+        }
+        self.verify_transaction(&tx)?;
+
+        self.pool_spent.extend(tx.input_pts_iter());
+        let outputs = tx.outputs_with_data_iter();
+        self.pool_cells
+            .extend(tx.output_pts().into_iter().zip(outputs));
+        self.transactions
+            .insert(tx_hash.clone(), (tx.clone(), TxStatus::Pending));
+        self.pool.push(tx);
+        Ok(tx_hash)
+    }
+
+    /// Resolve the transaction and run its scripts, returns the cycles.
+    pub fn verify_transaction(&self, tx: &TransactionView) -> Result<Cycle, SendTxError> {
+        let resolved_tx = self.resolve_transaction(tx)?;
+
+        let inputs_capacity = { resolved_tx.resolved_inputs.iter() }
+            .map(|c| c.cell_output.capacity().unpack())
+            .try_fold(Capacity::zero(), |sum, c: Capacity| sum.safe_add(c))
+            .map_err(|err| SendTxError::Verify(err.to_string()))?;
+        let outputs_capacity = match tx.outputs_capacity() {
+            Ok(capacity) => capacity,
+            Err(err) => return Err(SendTxError::Verify(err.to_string())),
+        };
+        if outputs_capacity > inputs_capacity {
+            return Err(SendTxError::Verify(format!(
+                "outputs capacity {} exceeds inputs capacity {}",
+                outputs_capacity, inputs_capacity
+            )));
+        }
+
+        TransactionScriptsVerifier::new(&resolved_tx, self)
+            .verify(MAX_CYCLES)
+            .map_err(|err| SendTxError::Verify(err.to_string()))
+    }
+
+    /// Commit pending transactions in a new block.
+    pub fn mine_block(&mut self) -> HeaderView {
+        let transactions = std::mem::take(&mut self.pool);
+        self.pool_cells.clear();
+        self.pool_spent.clear();
+        self.build_and_commit_block(transactions)
+    }
+
+    /// Commit a block with a transaction without inputs that creates the
+    /// cells, e.g. to fund wallets or deploy scripts.
+    pub fn mine_cells(&mut self, outputs: Vec<(CellOutput, Bytes)>) -> Vec<OutPoint> {
+        let (outputs, outputs_data): (Vec<_>, Vec<_>) = outputs.into_iter().unzip();
+        let tx = TransactionView::new_advanced_builder()
+            .outputs(outputs)
+            .outputs_data(outputs_data.into_iter().map(|d| d.pack()))
+            // Make the tx hash unique.
+            .witness(self.tip().hash().as_bytes().pack())
+            .build();
+        let out_points = tx.output_pts();
+
+        let mut transactions = std::mem::take(&mut self.pool);
+        transactions.insert(0, tx);
+        self.pool_cells.clear();
+        self.pool_spent.clear();
+        self.build_and_commit_block(transactions);
+        out_points
+    }
+
+    fn build_and_commit_block(&mut self, transactions: Vec<TransactionView>) -> HeaderView {
+        let parent = self.tip().header();
+        let number = parent.number() + 1;
+        let epoch = EpochNumberWithFraction::new(
+            number / EPOCH_LENGTH,
+            number % EPOCH_LENGTH,
+            EPOCH_LENGTH,
+        );
+        let block = BlockBuilder::default()
+            .parent_hash(parent.hash())
+            .number(number.pack())
+            .timestamp((parent.timestamp() + BLOCK_INTERVAL_MILLIS).pack())
+            .epoch(epoch.pack())
+            .transactions(transactions)
+            .build();
+        let header = block.header();
+        self.commit_block(block);
+        header
+    }
+
+    fn commit_block(&mut self, block: BlockView) {
+        let block_hash = block.hash();
+        let block_number = block.number();
+        for (tx_index, tx) in block.transactions().into_iter().enumerate() {
+            for out_point in tx.input_pts_iter() {
+                if let Some(cell) = self.cells.get_mut(&out_point) {
+                    cell.consumed = true;
+                }
+            }
+            let outputs = tx.outputs_with_data_iter();
+            for (out_point, (output, data)) in tx.output_pts().into_iter().zip(outputs) {
+                let cell = CellEntry {
+                    output,
+                    data,
+                    block_number,
+                    tx_index: tx_index as u32,
+                    consumed: false,
+                };
+                self.cells.insert(out_point, cell);
+            }
+            let status = TxStatus::Committed {
+                block_hash: block_hash.clone(),
+            };
+            self.transactions.insert(tx.hash(), (tx, status));
+        }
+        self.block_numbers.insert(block_hash, block_number);
+        self.blocks.push(block);
+    }
+
+    fn resolve_transaction(
+        &self,
+        tx: &TransactionView,
+    ) -> Result<ResolvedTransaction, SendTxError> {
+        let mut resolved_cell_deps = Vec::new();
+        let mut resolved_dep_groups = Vec::new();
+        for cell_dep in tx.cell_deps_iter() {
+            let cell_meta = self.live_cell_meta(&cell_dep.out_point())?;
+            let dep_type = DepType::try_from(cell_dep.dep_type())
+                .map_err(|_| SendTxError::Resolve("invalid dep type".to_string()))?;
+            match dep_type {
+                DepType::DepGroup => {
+                    let data = cell_meta.mem_cell_data.clone().unwrap_or_default();
+                    let out_points = OutPointVec::from_slice(&data)
+                        .map_err(|_| SendTxError::Resolve("invalid dep group".to_string()))?;
+                    for out_point in out_points.into_iter() {
+                        resolved_cell_deps.push(self.live_cell_meta(&out_point)?);
+                    }
+                    resolved_dep_groups.push(cell_meta);
+                }
+                DepType::Code => resolved_cell_deps.push(cell_meta),
+            }
+        }
+
+        let mut resolved_inputs = Vec::new();
+        let mut seen_inputs = HashSet::new();
+        for out_point in tx.input_pts_iter() {
+            if self.pool_spent.contains(&out_point) || !seen_inputs.insert(out_point.clone()) {
+                return Err(SendTxError::Resolve(format!("dead input {}", out_point)));
+            }
+            resolved_inputs.push(self.live_cell_meta(&out_point)?);
+        }
+        if resolved_inputs.is_empty() {
+            return Err(SendTxError::Resolve("no inputs".to_string()));
+        }
+
+        Ok(ResolvedTransaction {
+            transaction: tx.clone(),
+            resolved_cell_deps,
+            resolved_inputs,
+            resolved_dep_groups,
+        })
+    }
+
+    fn live_cell_meta(&self, out_point: &OutPoint) -> Result<CellMeta, SendTxError> {
+        if let Some(cell) = self.cells.get(out_point).filter(|c| !c.consumed) {
+            let block = &self.blocks[cell.block_number as usize];
+            let info = TransactionInfo::new(
+                cell.block_number,
+                block.epoch(),
+                block.hash(),
+                cell.tx_index as usize,
+            );
+            let meta = CellMetaBuilder::from_cell_output(cell.output.clone(), cell.data.clone())
+                .out_point(out_point.clone())
+                .transaction_info(info)
+                .build();
+            return Ok(meta);
+        }
+        if let Some((output, data)) = self.pool_cells.get(out_point) {
+            let meta = CellMetaBuilder::from_cell_output(output.clone(), data.clone())
+                .out_point(out_point.clone())
+                .build();
+            return Ok(meta);
+        }
+        Err(SendTxError::Resolve(format!(
+            "unknown or dead cell {}",
+            out_point
+        )))
+    }
+
+    fn any_cell_data(&self, out_point: &OutPoint) -> Option<&Bytes> {
+        match self.cells.get(out_point) {
+            Some(cell) => Some(&cell.data),
+            None => self.pool_cells.get(out_point).map(|(_, data)| data),
+        }
+    }
+}
+
+impl CellDataProvider for MockChain {
+    fn get_cell_data(&self, out_point: &OutPoint) -> Option<Bytes> {
+        self.any_cell_data(out_point).cloned()
+    }
+
+    fn get_cell_data_hash(&self, out_point: &OutPoint) -> Option<Byte32> {
+        self.any_cell_data(out_point)
+            .map(|data| CellOutput::calc_data_hash(data))
+    }
+}
+
+impl HeaderProvider for MockChain {
+    fn get_header(&self, block_hash: &Byte32) -> Option<HeaderView> {
+        self.block(block_hash).map(|b| b.header())
+    }
+}
@@ -0,0 +1,112 @@
+//! In-process stand-in of a CKB node and its built-in indexer.
+//!
+//! [`MockCkb`] serves the subset of CKB and indexer JSON-RPC used by
+//! [`RPCClient`] on a local port, backed by a [`MockChain`]. Sent
+//! transactions are verified by ckb-script and committed when a block is
+//! mined, which happens on demand or periodically with
+//! [`MockCkb::spawn_miner`].
+
+use std::{
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use axum::{routing::post, Extension, Router};
+use ckb_types::{
+    bytes::Bytes,
+    core::{HeaderView, TransactionView},
+    packed::{CellOutput, OutPoint},
+};
+use gw_rpc_client::{
+    ckb_client::CkbClient, indexer_client::CkbIndexerClient, rpc_client::RPCClient,
+};
+use gw_types::packed::{RollupConfig, Script};
+use jsonrpc_core::MetaIoHandler;
+use jsonrpc_utils::{axum_utils::handle_jsonrpc, pub_sub::Session};
+use tokio::task::JoinHandle;
+
+mod chain;
+mod rpc;
+
+pub use chain::{MockChain, SendTxError};
+
+use rpc::{add_mock_ckb_rpc_methods, MockCkbRpcImpl};
+
+pub struct MockCkb {
+    chain: Arc<Mutex<MockChain>>,
+    url: String,
+    server: JoinHandle<()>,
+}
+
+impl MockCkb {
+    /// Start serving a chain with the genesis transactions.
+    pub fn start(genesis_transactions: Vec<TransactionView>) -> Result<Self> {
+        let genesis_timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+        let chain = MockChain::new(genesis_transactions, genesis_timestamp as u64);
+        let chain = Arc::new(Mutex::new(chain));
+
+        let mut handler = MetaIoHandler::with_compatibility(jsonrpc_core::Compatibility::V2);
+        let rpc_impl = MockCkbRpcImpl {
+            chain: chain.clone(),
+        };
+        add_mock_ckb_rpc_methods(&mut handler, rpc_impl);
+        let app = Router::new()
+            .route("/", post(handle_jsonrpc::<Option<Session>>))
+            .layer(Extension(Arc::new(handler)));
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        listener.set_nonblocking(true)?;
+        let url = format!("http://{}", listener.local_addr()?);
+        let server = axum::Server::from_tcp(listener)?.serve(app.into_make_service());
+        let server = tokio::spawn(async move {
+            let _ = server.await;
+        });
+
+        Ok(MockCkb { chain, url, server })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Client that talks to the mock node, which also serves as the built-in
+    /// indexer.
+    pub fn rpc_client(&self, rollup_type_script: Script, rollup_config: RollupConfig) -> RPCClient {
+        let ckb = CkbClient::with_url(&self.url).expect("mock ckb client");
+        let indexer = CkbIndexerClient::from(ckb.clone());
+        RPCClient::new(rollup_type_script, rollup_config, ckb, indexer)
+    }
+
+    pub fn chain(&self) -> MutexGuard<'_, MockChain> {
+        self.chain.lock().expect("lock mock chain")
+    }
+
+    pub fn mine_block(&self) -> HeaderView {
+        self.chain().mine_block()
+    }
+
+    pub fn mine_cells(&self, outputs: Vec<(CellOutput, Bytes)>) -> Vec<OutPoint> {
+        self.chain().mine_cells(outputs)
+    }
+
+    /// Mine a block every interval until the mock node is dropped.
+    pub fn spawn_miner(&self, interval: Duration) -> JoinHandle<()> {
+        let chain = Arc::downgrade(&self.chain);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                match chain.upgrade() {
+                    Some(chain) => chain.lock().expect("lock mock chain").mine_block(),
+                    None => return,
+                };
+            }
+        })
+    }
+}
+
+impl Drop for MockCkb {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
@@ -0,0 +1,405 @@
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use ckb_fixed_hash::H256;
+use ckb_types::{core, packed, prelude::*};
+use gw_jsonrpc_types::{
+    ckb_jsonrpc_types::{
+        BlockNumber, BlockView, CellData, CellInfo, CellWithStatus, EstimateCycles, HeaderView,
+        JsonBytes, OutPoint, OutputsValidator, Timestamp, Transaction, TransactionView, Uint32,
+        Uint64,
+    },
+    number_hash::NumberHash,
+};
+use gw_rpc_client::{
+    error::CkbRpcError,
+    indexer_types::{Cell, IOType, Order, Pagination, ScriptType, SearchKey, Tx},
+};
+use jsonrpc_core::{Error, ErrorCode, Result};
+use jsonrpc_utils::rpc;
+use serde_json::{json, Value};
+
+use super::chain::{MockChain, SendTxError, TxStatus};
+
+/// The subset of CKB and built-in indexer RPCs used by godwoken.
+#[rpc]
+#[async_trait]
+pub trait MockCkbRpc {
+    async fn get_tip_block_number(&self) -> Result<BlockNumber>;
+    async fn get_tip_header(&self) -> Result<HeaderView>;
+    async fn get_block(&self, hash: H256) -> Result<Option<BlockView>>;
+    async fn get_block_by_number(&self, number: BlockNumber) -> Result<Option<BlockView>>;
+    async fn get_block_hash(&self, number: BlockNumber) -> Result<Option<H256>>;
+    async fn get_header(&self, hash: H256) -> Result<Option<HeaderView>>;
+    async fn get_header_by_number(&self, number: BlockNumber) -> Result<Option<HeaderView>>;
+    async fn get_block_median_time(&self, hash: H256) -> Result<Option<Timestamp>>;
+    async fn get_live_cell(&self, out_point: OutPoint, with_data: bool) -> Result<CellWithStatus>;
+    async fn get_transaction(&self, hash: H256, verbosity: Option<Uint32>)
+        -> Result<Option<Value>>;
+    async fn send_transaction(
+        &self,
+        tx: Transaction,
+        outputs_validator: Option<OutputsValidator>,
+    ) -> Result<H256>;
+    async fn estimate_cycles(&self, tx: Transaction) -> Result<EstimateCycles>;
+    async fn get_indexer_tip(&self) -> Result<NumberHash>;
+    async fn get_cells(
+        &self,
+        search_key: SearchKey,
+        order: Order,
+        limit: Uint32,
+        after: Option<JsonBytes>,
+    ) -> Result<Pagination<Cell>>;
+    async fn get_transactions(
+        &self,
+        search_key: SearchKey,
+        order: Order,
+        limit: Uint32,
+        after: Option<JsonBytes>,
+    ) -> Result<Pagination<Tx>>;
+}
+
+pub struct MockCkbRpcImpl {
+    pub chain: Arc<Mutex<MockChain>>,
+}
+
+impl MockCkbRpcImpl {
+    fn chain(&self) -> std::sync::MutexGuard<'_, MockChain> {
+        self.chain.lock().expect("lock mock chain")
+    }
+}
+
+#[async_trait]
+impl MockCkbRpc for MockCkbRpcImpl {
+    async fn get_tip_block_number(&self) -> Result<BlockNumber> {
+        Ok(self.chain().tip().number().into())
+    }
+
+    async fn get_tip_header(&self) -> Result<HeaderView> {
+        Ok(self.chain().tip().header().into())
+    }
+
+    async fn get_block(&self, hash: H256) -> Result<Option<BlockView>> {
+        let chain = self.chain();
+        Ok(chain.block(&hash.pack()).cloned().map(Into::into))
+    }
+
+    async fn get_block_by_number(&self, number: BlockNumber) -> Result<Option<BlockView>> {
+        let chain = self.chain();
+        Ok(chain
+            .block_by_number(number.value())
+            .cloned()
+            .map(Into::into))
+    }
+
+    async fn get_block_hash(&self, number: BlockNumber) -> Result<Option<H256>> {
+        let chain = self.chain();
+        let block = chain.block_by_number(number.value());
+        Ok(block.map(|b| b.hash().unpack()))
+    }
+
+    async fn get_header(&self, hash: H256) -> Result<Option<HeaderView>> {
+        let chain = self.chain();
+        Ok(chain.block(&hash.pack()).map(|b| b.header().into()))
+    }
+
+    async fn get_header_by_number(&self, number: BlockNumber) -> Result<Option<HeaderView>> {
+        let chain = self.chain();
+        let block = chain.block_by_number(number.value());
+        Ok(block.map(|b| b.header().into()))
+    }
+
+    async fn get_block_median_time(&self, hash: H256) -> Result<Option<Timestamp>> {
+        let chain = self.chain();
+        Ok(chain.block_median_time(&hash.pack()).map(Into::into))
+    }
+
+    async fn get_live_cell(&self, out_point: OutPoint, with_data: bool) -> Result<CellWithStatus> {
+        let out_point: packed::OutPoint = out_point.into();
+        let chain = self.chain();
+        let cell = match chain.cell(&out_point) {
+            Some(cell) => cell,
+            None => {
+                return Ok(CellWithStatus {
+                    cell: None,
+                    status: "unknown".to_string(),
+                })
+            }
+        };
+        if cell.consumed {
+            return Ok(CellWithStatus {
+                cell: None,
+                status: "dead".to_string(),
+            });
+        }
+
+        let data = with_data.then(|| CellData {
+            content: JsonBytes::from_bytes(cell.data.clone()),
+            hash: packed::CellOutput::calc_data_hash(&cell.data).unpack(),
+        });
+        Ok(CellWithStatus {
+            cell: Some(CellInfo {
+                output: cell.output.clone().into(),
+                data,
+            }),
+            status: "live".to_string(),
+        })
+    }
+
+    async fn get_transaction(
+        &self,
+        hash: H256,
+        verbosity: Option<Uint32>,
+    ) -> Result<Option<Value>> {
+        let chain = self.chain();
+        let (tx, status) = match chain.transaction(&hash.pack()) {
+            Some(tx) => tx,
+            None => return Ok(None),
+        };
+
+        let tx_status = match status {
+            TxStatus::Pending => json!({
+                "status": "pending",
+                "block_hash": null,
+                "reason": null,
+            }),
+            TxStatus::Committed { block_hash } => json!({
+                "status": "committed",
+                "block_hash": Unpack::<H256>::unpack(block_hash),
+                "reason": null,
+            }),
+        };
+        // Verbosity 1 returns the status only.
+        let transaction = match verbosity.map(|v| v.value()) {
+            Some(1) => None,
+            _ => Some(TransactionView::from(tx.clone())),
+        };
+        Ok(Some(json!({
+            "transaction": transaction,
+            "cycles": null,
+            "tx_status": tx_status,
+        })))
+    }
+
+    async fn send_transaction(
+        &self,
+        tx: Transaction,
+        _outputs_validator: Option<OutputsValidator>,
+    ) -> Result<H256> {
+        let tx = packed::Transaction::from(tx).into_view();
+        let tx_hash = self.chain().send_transaction(tx).map_err(send_tx_error)?;
+        Ok(tx_hash.unpack())
+    }
+
+    async fn estimate_cycles(&self, tx: Transaction) -> Result<EstimateCycles> {
+        let tx = packed::Transaction::from(tx).into_view();
+        let cycles = self
+            .chain()
+            .verify_transaction(&tx)
+            .map_err(send_tx_error)?;
+        Ok(EstimateCycles {
+            cycles: cycles.into(),
+        })
+    }
+
+    async fn get_indexer_tip(&self) -> Result<NumberHash> {
+        let chain = self.chain();
+        let tip = chain.tip();
+        Ok(NumberHash {
+            block_hash: tip.hash().unpack(),
+            block_number: tip.number().into(),
+        })
+    }
+
+    async fn get_cells(
+        &self,
+        search_key: SearchKey,
+        order: Order,
+        limit: Uint32,
+        after: Option<JsonBytes>,
+    ) -> Result<Pagination<Cell>> {
+        let chain = self.chain();
+        let mut cells = Vec::new();
+        for block in chain.blocks() {
+            for (tx_index, tx) in block.transactions().into_iter().enumerate() {
+                for (index, out_point) in tx.output_pts().into_iter().enumerate() {
+                    let cell = chain.cell(&out_point).expect("committed cell");
+                    if cell.consumed || !matches(&search_key, &cell.output, &cell.data, block) {
+                        continue;
+                    }
+                    let key = cursor(block.number(), tx_index as u32, 1, index as u32);
+                    let cell = Cell {
+                        output: cell.output.clone().into(),
+                        output_data: JsonBytes::from_bytes(cell.data.clone()),
+                        out_point: out_point.into(),
+                        block_number: block.number().into(),
+                        tx_index: (tx_index as u32).into(),
+                    };
+                    cells.push((key, cell));
+                }
+            }
+        }
+        Ok(paginate(cells, order, limit, after))
+    }
+
+    async fn get_transactions(
+        &self,
+        search_key: SearchKey,
+        order: Order,
+        limit: Uint32,
+        after: Option<JsonBytes>,
+    ) -> Result<Pagination<Tx>> {
+        let chain = self.chain();
+        let mut txs = Vec::new();
+        for block in chain.blocks() {
+            for (tx_index, tx) in block.transactions().into_iter().enumerate() {
+                let tx_hash: H256 = tx.hash().unpack();
+                let inputs = tx.input_pts_iter().map(|o| (IOType::Input, o));
+                let outputs = tx.output_pts().into_iter().map(|o| (IOType::Output, o));
+                let io = inputs.enumerate().chain(outputs.enumerate());
+                for (io_index, (io_type, out_point)) in io {
+                    let cell = match chain.cell(&out_point) {
+                        Some(cell) => cell,
+                        None => continue,
+                    };
+                    if !matches(&search_key, &cell.output, &cell.data, block) {
+                        continue;
+                    }
+                    let io_type_key = match io_type {
+                        IOType::Input => 0,
+                        IOType::Output => 1,
+                    };
+                    let key = cursor(
+                        block.number(),
+                        tx_index as u32,
+                        io_type_key,
+                        io_index as u32,
+                    );
+                    let tx = Tx {
+                        tx_hash: tx_hash.clone(),
+                        block_number: block.number().into(),
+                        tx_index: (tx_index as u32).into(),
+                        io_index: (io_index as u32).into(),
+                        io_type,
+                    };
+                    txs.push((key, tx));
+                }
+            }
+        }
+        Ok(paginate(txs, order, limit, after))
+    }
+}
+
+fn send_tx_error(err: SendTxError) -> Error {
+    let code = match err {
+        SendTxError::Duplicated(_) => CkbRpcError::PoolRejectedDuplicatedTransaction,
+        SendTxError::Resolve(_) => CkbRpcError::TransactionFailedToResolve,
+        SendTxError::Verify(_) => CkbRpcError::TransactionFailedToVerify,
+    };
+    Error {
+        code: ErrorCode::ServerError(code as i64),
+        message: err.to_string(),
+        data: None,
+    }
+}
+
+/// Whether the cell matches the script prefix and the filter of the search key.
+///
+/// For transactions, the block is where the cell is created or consumed.
+fn matches(
+    search_key: &SearchKey,
+    output: &packed::CellOutput,
+    data: &[u8],
+    block: &core::BlockView,
+) -> bool {
+    let (script, other_script) = match search_key.script_type {
+        ScriptType::Lock => (Some(output.lock()), output.type_().to_opt()),
+        ScriptType::Type => (output.type_().to_opt(), Some(output.lock())),
+    };
+    if !is_prefix_of(&search_key.script.clone().into(), script.as_ref()) {
+        return false;
+    }
+
+    let filter = match search_key.filter {
+        Some(ref filter) => filter,
+        None => return true,
+    };
+    let in_range =
+        |value: u64, range: &[Uint64; 2]| range[0].value() <= value && value < range[1].value();
+    if let Some(ref prefix) = filter.script {
+        if !is_prefix_of(&prefix.clone().into(), other_script.as_ref()) {
+            return false;
+        }
+    }
+    if let Some(ref range) = filter.output_data_len_range {
+        if !in_range(data.len() as u64, range) {
+            return false;
+        }
+    }
+    if let Some(ref range) = filter.output_capacity_range {
+        if !in_range(output.capacity().unpack(), range) {
+            return false;
+        }
+    }
+    if let Some(ref range) = filter.block_range {
+        if !in_range(block.number(), range) {
+            return false;
+        }
+    }
+    true
+}
+
+fn is_prefix_of(prefix: &packed::Script, script: Option<&packed::Script>) -> bool {
+    match script {
+        Some(script) => {
+            script.code_hash().as_slice() == prefix.code_hash().as_slice()
+                && script.hash_type().as_slice() == prefix.hash_type().as_slice()
+                && script
+                    .args()
+                    .raw_data()
+                    .starts_with(&prefix.args().raw_data())
+        }
+        None => false,
+    }
+}
+
+fn cursor(block_number: u64, tx_index: u32, io_type: u8, io_index: u32) -> Vec<u8> {
+    let mut key = Vec::with_capacity(17);
+    key.extend_from_slice(&block_number.to_be_bytes());
+    key.extend_from_slice(&tx_index.to_be_bytes());
+    key.push(io_type);
+    key.extend_from_slice(&io_index.to_be_bytes());
+    key
+}
+
+/// Items must be sorted by key in ascending order. The last cursor is empty
+/// when there are no more items.
+fn paginate<T>(
+    mut items: Vec<(Vec<u8>, T)>,
+    order: Order,
+    limit: Uint32,
+    after: Option<JsonBytes>,
+) -> Pagination<T> {
+    if let Order::Desc = order {
+        items.reverse();
+    }
+    let after = after.map(|a| a.into_bytes().to_vec());
+    let is_after = |key: &Vec<u8>| match (&after, &order) {
+        (None, _) => true,
+        (Some(after), Order::Asc) => key > after,
+        (Some(after), Order::Desc) => key < after,
+    };
+
+    let objects: Vec<_> = { items.into_iter() }
+        .filter(|(key, _)| is_after(key))
+        .take(limit.value() as usize)
+        .collect();
+    let last_cursor = match objects.last() {
+        Some((key, _)) => JsonBytes::from_vec(key.clone()),
+        None => JsonBytes::default(),
+    };
+    Pagination {
+        objects: objects.into_iter().map(|(_, item)| item).collect(),
+        last_cursor,
+    }
+}
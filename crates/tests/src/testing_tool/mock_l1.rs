@@ -0,0 +1,229 @@
+//! Rollup deployed on a [`MockCkb`] whose scripts always succeed, to run the
+//! challenger and the offchain mock against.
+
+use std::{collections::HashMap, sync::Arc};
+
+use ckb_crypto::secp::Privkey;
+use ckb_types::core::TransactionView;
+use gw_chain::chain::Chain;
+use gw_challenge::offchain::{
+    CKBGenesisInfo as OffChainGenesisInfo, OffChainMockContext, OffChainMockContextBuildArgs,
+};
+use gw_config::{BlockProducerConfig, SystemTypeScriptConfig};
+use gw_generator::account_lock_manage::{
+    always_success::AlwaysSuccess, secp256k1::Secp256k1Eth, AccountLockManage,
+};
+use gw_rpc_client::{contract::ContractsCellDepManager, rpc_client::RPCClient};
+use gw_types::{
+    bytes::Bytes,
+    core::{AllowedEoaType, DepType, ScriptHashType},
+    packed::{AllowedTypeHash, CellDep, CellOutput, GlobalState, OutPoint, OutPointVec, Script},
+    prelude::*,
+};
+use gw_utils::{genesis_info::CKBGenesisInfo, wallet::Wallet, RollupContext};
+
+use super::{
+    chain::{
+        default_rollup_config, setup_chain_with_account_lock_manage, ALWAYS_SUCCESS_CODE_HASH,
+        ALWAYS_SUCCESS_PROGRAM, ETH_ACCOUNT_LOCK_CODE_HASH,
+    },
+    mock_ckb::MockCkb,
+};
+
+pub const CKB: u64 = 100000000;
+
+pub fn always_success_script(args: &[u8]) -> Script {
+    Script::new_builder()
+        .code_hash(ALWAYS_SUCCESS_CODE_HASH.pack())
+        .hash_type(ScriptHashType::Data.into())
+        .args(args.pack())
+        .build()
+}
+
+/// Type script of the code cell of the allowed eoa lock.
+pub fn eoa_lock_type_script() -> Script {
+    always_success_script(&[11])
+}
+
+/// Lock of the wallet cells, also used by the offchain mock.
+pub fn wallet_lock() -> Script {
+    always_success_script(&[9; 20])
+}
+
+pub fn wallet() -> Wallet {
+    Wallet::new(Privkey::from_slice(&[42u8; 32]), wallet_lock())
+}
+
+pub fn system_type_scripts() -> SystemTypeScriptConfig {
+    SystemTypeScriptConfig {
+        state_validator: always_success_script(&[1]).into(),
+        deposit_lock: always_success_script(&[2]).into(),
+        stake_lock: always_success_script(&[3]).into(),
+        custodian_lock: always_success_script(&[4]).into(),
+        withdrawal_lock: always_success_script(&[5]).into(),
+        challenge_lock: always_success_script(&[6]).into(),
+        l1_sudt: always_success_script(&[7]).into(),
+        omni_lock: always_success_script(&[8]).into(),
+        allowed_eoa_scripts: vec![eoa_lock_type_script().into()],
+        ..Default::default()
+    }
+}
+
+fn code_cell(type_: Script) -> CellOutput {
+    CellOutput::new_builder()
+        .capacity((10000 * CKB).pack())
+        .type_(Some(type_).pack())
+        .build()
+}
+
+fn dep_group(out_points: Vec<OutPoint>) -> Bytes {
+    OutPointVec::new_builder()
+        .set(out_points)
+        .build()
+        .as_bytes()
+}
+
+// L1 genesis with the system cells of `CKBGenesisInfo`.
+fn genesis_transactions() -> Vec<TransactionView> {
+    let system_cells = TransactionView::new_advanced_builder()
+        .outputs((0..5u8).map(|i| code_cell(always_success_script(&[0xff, i]))))
+        .outputs_data((0..5).map(|_| ALWAYS_SUCCESS_PROGRAM.clone().pack()))
+        .build();
+    let sighash_group = dep_group(vec![OutPoint::new(system_cells.hash(), 1)]);
+    let multisig_group = dep_group(vec![OutPoint::new(system_cells.hash(), 4)]);
+    let dep_groups = TransactionView::new_advanced_builder()
+        .output(
+            CellOutput::new_builder()
+                .capacity((100 * CKB).pack())
+                .build(),
+        )
+        .output_data(sighash_group.pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity((100 * CKB).pack())
+                .build(),
+        )
+        .output_data(multisig_group.pack())
+        .build();
+    vec![system_cells, dep_groups]
+}
+
+/// Start a mock L1 with the code cells of [`system_type_scripts`] deployed.
+pub fn start_mock_ckb() -> MockCkb {
+    let mock_ckb = MockCkb::start(genesis_transactions()).unwrap();
+    let code_cells = (1..=8u8)
+        .map(|i| always_success_script(&[i]))
+        .chain([eoa_lock_type_script()])
+        .map(|type_| (code_cell(type_), ALWAYS_SUCCESS_PROGRAM.clone()))
+        .collect();
+    mock_ckb.mine_cells(code_cells);
+    mock_ckb
+}
+
+/// Chain whose rollup config refers to [`system_type_scripts`], so that
+/// cancel challenge transactions can be resolved on the mock L1.
+pub async fn setup_chain(rollup_type_script: Script) -> Chain {
+    let eoa_type_hash: [u8; 32] = eoa_lock_type_script().hash();
+    let rollup_config = default_rollup_config();
+    let allowed_eoa_type_hashes = rollup_config
+        .allowed_eoa_type_hashes()
+        .as_builder()
+        .push(AllowedTypeHash::new(AllowedEoaType::Eth, eoa_type_hash))
+        .build();
+    let rollup_config = rollup_config
+        .as_builder()
+        .allowed_eoa_type_hashes(allowed_eoa_type_hashes)
+        .challenge_script_type_hash(always_success_script(&[6]).hash().pack())
+        .build();
+
+    let mut account_lock_manage = AccountLockManage::default();
+    account_lock_manage.register_lock_algorithm(*ALWAYS_SUCCESS_CODE_HASH, Arc::new(AlwaysSuccess));
+    account_lock_manage.register_lock_algorithm(eoa_type_hash, Arc::new(AlwaysSuccess));
+    account_lock_manage.register_lock_algorithm(
+        *ETH_ACCOUNT_LOCK_CODE_HASH,
+        Arc::new(Secp256k1Eth::default()),
+    );
+    let chain = setup_chain_with_account_lock_manage(
+        rollup_type_script,
+        rollup_config,
+        account_lock_manage,
+        None,
+        None,
+        None,
+    )
+    .await;
+    chain.notify_new_tip().await.unwrap();
+    chain
+}
+
+/// Rollup submitted to the mock L1.
+pub struct MockL1Rollup {
+    pub rpc_client: RPCClient,
+    /// Rollup context whose config cell dep points to the mock L1.
+    pub rollup_context: RollupContext,
+    pub contracts_dep_manager: ContractsCellDepManager,
+    pub ckb_genesis_info: CKBGenesisInfo,
+}
+
+impl MockL1Rollup {
+    /// Mine the rollup config cell and the rollup cell with the global state.
+    pub async fn deploy(
+        mock_ckb: &MockCkb,
+        rollup_type_script: Script,
+        mut rollup_context: RollupContext,
+        global_state: &GlobalState,
+    ) -> Self {
+        let rollup_config = rollup_context.rollup_config.clone();
+        let rollup_config_out_point = mock_ckb.mine_cells(vec![(
+            CellOutput::new_builder()
+                .capacity((1000 * CKB).pack())
+                .build(),
+            rollup_config.as_bytes(),
+        )]);
+        let rollup_config_cell_dep = CellDep::new_builder()
+            .out_point(rollup_config_out_point[0].clone())
+            .dep_type(DepType::Code.into())
+            .build();
+        let rollup_cell = CellOutput::new_builder()
+            .capacity((1000 * CKB).pack())
+            .type_(Some(rollup_type_script.clone()).pack())
+            .lock(always_success_script(&[]))
+            .build();
+        mock_ckb.mine_cells(vec![(rollup_cell, global_state.as_bytes())]);
+
+        rollup_context.fork_config.chain.rollup_config_cell_dep =
+            rollup_config_cell_dep.clone().into();
+        let rpc_client = mock_ckb.rpc_client(rollup_type_script, rollup_config);
+        let contracts_dep_manager = ContractsCellDepManager::build(
+            rpc_client.clone(),
+            system_type_scripts(),
+            rollup_config_cell_dep.into(),
+        )
+        .await
+        .unwrap();
+        let ckb_genesis_info = CKBGenesisInfo::get(&rpc_client.ckb).await.unwrap();
+
+        MockL1Rollup {
+            rpc_client,
+            rollup_context,
+            contracts_dep_manager,
+            ckb_genesis_info,
+        }
+    }
+
+    pub async fn offchain_mock_context(&self, config: BlockProducerConfig) -> OffChainMockContext {
+        OffChainMockContext::build(OffChainMockContextBuildArgs {
+            rpc_client: &self.rpc_client,
+            rollup_context: self.rollup_context.clone(),
+            wallet: wallet(),
+            config,
+            ckb_genesis_info: OffChainGenesisInfo {
+                sighash_dep: self.ckb_genesis_info.sighash_dep(),
+            },
+            builtin_load_data: HashMap::new(),
+            contracts_dep_manager: self.contracts_dep_manager.clone(),
+        })
+        .await
+        .unwrap()
+    }
+}
@@ -3,6 +3,9 @@ pub mod chain;
 pub mod common;
 pub mod eth_wallet;
 pub mod mem_pool_provider;
+pub mod mock_ckb;
+pub mod mock_l1;
 pub mod polyjuice;
 pub mod rpc_server;
 pub mod verify_tx;
+pub mod webauthn_wallet;
@@ -72,6 +72,10 @@ impl RPCServer {
             gasless_tx_support_config: None,
             polyjuice_sender_recover,
             debug_backend_forks: None,
+            forward_client: None,
+            contracts_dep_manager: None,
+            block_sync_server_state: None,
+            peers: Default::default(),
         }
     }
 
@@ -0,0 +1,251 @@
+use gw_common::{builtins::ETH_REGISTRY_ACCOUNT_ID, registry_address::RegistryAddress};
+use gw_types::prelude::*;
+use gw_types::{
+    bytes::Bytes,
+    h256::*,
+    packed::{Script, WebAuthnSignature},
+};
+use p256::ecdsa::{signature::Signer, Signature, SigningKey};
+use rand::{rngs::OsRng, Rng};
+use sha2::Sha256;
+use sha3::{Digest, Keccak256};
+
+pub const FLAG_USER_PRESENT: u8 = 0x01;
+pub const FLAG_USER_VERIFIED: u8 = 0x04;
+pub const RP_ID: &str = "example.com";
+
+// Order of P-256.
+const P256_ORDER: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xbc, 0xe6, 0xfa, 0xad, 0xa7, 0x17, 0x9e, 0x84, 0xf3, 0xb9, 0xca, 0xc2, 0xfc, 0x63, 0x25, 0x51,
+];
+
+/// A passkey wallet which produces WebAuthn assertions like a platform
+/// authenticator does.
+pub struct WebAuthnWallet {
+    pub signing_key: SigningKey,
+    pub account_script: Script,
+    pub registry_address: RegistryAddress,
+}
+
+impl WebAuthnWallet {
+    pub fn random(rollup_script_hash: H256, code_hash: H256) -> Self {
+        let signing_key = loop {
+            if let Ok(key) = SigningKey::from_bytes(&OsRng.gen::<[u8; 32]>()) {
+                break key;
+            }
+        };
+        let pubkey_hash = pubkey_hash(RP_ID, &Self::pubkey_of(&signing_key));
+
+        let mut args = rollup_script_hash.as_slice().to_vec();
+        args.extend_from_slice(&pubkey_hash);
+        let account_script = Script::new_builder()
+            .code_hash(code_hash.pack())
+            .hash_type(gw_types::core::ScriptHashType::Type.into())
+            .args(Bytes::from(args).pack())
+            .build();
+        let registry_address = RegistryAddress::new(ETH_REGISTRY_ACCOUNT_ID, pubkey_hash.to_vec());
+
+        WebAuthnWallet {
+            signing_key,
+            account_script,
+            registry_address,
+        }
+    }
+
+    /// Uncompressed public key without the 0x04 tag: x | y
+    pub fn pubkey(&self) -> [u8; 64] {
+        Self::pubkey_of(&self.signing_key)
+    }
+
+    pub fn pubkey_hash(&self) -> [u8; 20] {
+        pubkey_hash(RP_ID, &self.pubkey())
+    }
+
+    /// Serialized `WebAuthnSignature` of `message` with user present and
+    /// user verified flags.
+    pub fn sign_message(&self, message: H256) -> Bytes {
+        self.sign_message_with_flags(message, FLAG_USER_PRESENT | FLAG_USER_VERIFIED)
+    }
+
+    pub fn sign_message_with_flags(&self, message: H256, flags: u8) -> Bytes {
+        self.sign_assertion(
+            authenticator_data(flags),
+            client_data_json(message, "https://example.com"),
+        )
+    }
+
+    /// Sign an assertion, the signature is normalized to low-s like the lock
+    /// requires.
+    pub fn sign_assertion(&self, authenticator_data: Vec<u8>, client_data_json: Vec<u8>) -> Bytes {
+        let signature = self.sign_raw(&authenticator_data, &client_data_json);
+        let signature = if is_high_s(&signature) {
+            flip_s(&signature)
+        } else {
+            signature
+        };
+        Self::assertion(
+            &self.pubkey(),
+            &signature,
+            authenticator_data,
+            client_data_json,
+        )
+    }
+
+    /// Sign an assertion with a high-s signature, which is valid ECDSA but
+    /// must be rejected by the lock.
+    pub fn sign_assertion_high_s(
+        &self,
+        authenticator_data: Vec<u8>,
+        client_data_json: Vec<u8>,
+    ) -> Bytes {
+        let signature = self.sign_raw(&authenticator_data, &client_data_json);
+        let signature = if is_high_s(&signature) {
+            signature
+        } else {
+            flip_s(&signature)
+        };
+        Self::assertion(
+            &self.pubkey(),
+            &signature,
+            authenticator_data,
+            client_data_json,
+        )
+    }
+
+    fn sign_raw(&self, authenticator_data: &[u8], client_data_json: &[u8]) -> [u8; 64] {
+        let mut signed_data = authenticator_data.to_vec();
+        signed_data.extend_from_slice(&Sha256::digest(client_data_json));
+        let signature: Signature = self.signing_key.sign(&signed_data);
+        let mut buf = [0u8; 64];
+        buf.copy_from_slice(signature.as_ref());
+        buf
+    }
+
+    fn assertion(
+        pubkey: &[u8],
+        signature: &[u8],
+        authenticator_data: Vec<u8>,
+        client_data_json: Vec<u8>,
+    ) -> Bytes {
+        WebAuthnSignature::new_builder()
+            .pubkey(Bytes::copy_from_slice(pubkey).pack())
+            .signature(Bytes::copy_from_slice(signature).pack())
+            .authenticator_data(Bytes::from(authenticator_data).pack())
+            .client_data_json(Bytes::from(client_data_json).pack())
+            .build()
+            .as_bytes()
+    }
+
+    fn pubkey_of(signing_key: &SigningKey) -> [u8; 64] {
+        let point = signing_key.verifying_key().to_encoded_point(false);
+        let mut pubkey = [0u8; 64];
+        pubkey.copy_from_slice(&point.as_bytes()[1..]);
+        pubkey
+    }
+}
+
+/// An assertion of a synced passkey of `example.com`, in the format a
+/// platform authenticator returns it: the backup eligible and backup state
+/// flags are set, the sign count is 0, the client data JSON carries the extra
+/// key Chrome inserts and the DER signature has a high s.
+///
+/// It signs the EIP-712 message of a tx with chain id 42, nonce 1 and args
+/// `hello`, sent by the ETH address `PUBKEY_HASH` to `RECEIVER_SCRIPT_HASH`.
+pub mod platform_assertion {
+    pub const PUBKEY: &str = "212e834a963e78e5aecb9fca96ba3c86d2b299c36ccd6c4813726a494c7daf2e814deb24b9b96012a7c7da2c6ac1e44ba73841085bd311f586689e4b8b5629a4";
+    pub const PUBKEY_HASH: &str = "ac8ff5b1fce1611b2330288b9837dbb51542be1c";
+    pub const AUTHENTICATOR_DATA: &str =
+        "a379a6f6eeafb9a55e378c118034e2751e682fab9f2d30ab13d2125586ce19471d00000000";
+    pub const CLIENT_DATA_JSON: &str = r#"{"type":"webauthn.get","challenge":"3dB0jbu-O0Mc2I4WfjiS-VqF42cYPv8iF70YNVcZ2ZM","origin":"https://example.com","crossOrigin":false,"other_keys_can_be_added_here":"do not compare clientDataJSON against a template. See https://goo.gl/yabPex"}"#;
+    pub const SIGNATURE_DER: &str = "304502206492593be6ff5f66ed3e943e7c710b14085df4743e319e0ccd8a63c84cfd85e1022100c7b2571b83274af2c928d3d02312beaa5f4a7683bae75f9b8bcbe3ac00a015d1";
+    pub const RECEIVER_SCRIPT_HASH: &str =
+        "310ec2e1ff63ae390220e88bb5b25fa284682081354fa6ecd062778f227094ab";
+    pub const MESSAGE: &str = "ddd0748dbbbe3b431cd88e167e3892f95a85e367183eff2217bd18355719d993";
+    pub const CHAIN_ID: u64 = 42;
+    pub const NONCE: u32 = 1;
+    pub const ARGS: &[u8] = b"hello";
+
+    /// Serialized `WebAuthnSignature`, the DER signature is converted to
+    /// r | s with a low s like clients must do before submitting.
+    pub fn signature() -> gw_types::bytes::Bytes {
+        let decode = |s: &str| hex::decode(s).expect("hex");
+        let signature = super::signature_from_der(&decode(SIGNATURE_DER));
+        super::WebAuthnWallet::assertion(
+            &decode(PUBKEY),
+            &signature,
+            decode(AUTHENTICATOR_DATA),
+            CLIENT_DATA_JSON.as_bytes().to_vec(),
+        )
+    }
+}
+
+/// Convert a DER encoded ECDSA signature to r | s, normalized to low-s.
+pub fn signature_from_der(der: &[u8]) -> [u8; 64] {
+    // SEQUENCE { INTEGER r, INTEGER s }
+    assert_eq!(der[0], 0x30, "DER sequence");
+    let mut signature = [0u8; 64];
+    let mut pos = 2;
+    for half in signature.chunks_mut(32) {
+        assert_eq!(der[pos], 0x02, "DER integer");
+        let len = der[pos + 1] as usize;
+        let int = &der[pos + 2..pos + 2 + len];
+        let int = &int[int.iter().take_while(|&&b| b == 0).count()..];
+        half[32 - int.len()..].copy_from_slice(int);
+        pos += 2 + len;
+    }
+    if is_high_s(&signature) {
+        flip_s(&signature)
+    } else {
+        signature
+    }
+}
+
+/// rp id hash | flags | sign count
+pub fn authenticator_data(flags: u8) -> Vec<u8> {
+    let mut data = Sha256::digest(RP_ID.as_bytes()).to_vec();
+    data.push(flags);
+    data.extend_from_slice(&1u32.to_be_bytes());
+    data
+}
+
+/// Client data JSON serialized the way browsers do.
+pub fn client_data_json(message: H256, origin: &str) -> Vec<u8> {
+    format!(
+        r#"{{"type":"webauthn.get","challenge":"{}","origin":"{}","crossOrigin":false}}"#,
+        base64::encode_config(message.as_slice(), base64::URL_SAFE_NO_PAD),
+        origin
+    )
+    .into_bytes()
+}
+
+/// keccak256(sha256(rp_id) | x | y)[12..]
+pub fn pubkey_hash(rp_id: &str, pubkey: &[u8]) -> [u8; 20] {
+    let mut hasher = Keccak256::new();
+    hasher.update(&Sha256::digest(rp_id.as_bytes()));
+    hasher.update(pubkey);
+    let buf = hasher.finalize();
+    let mut pubkey_hash = [0u8; 20];
+    pubkey_hash.copy_from_slice(&buf[12..]);
+    pubkey_hash
+}
+
+fn is_high_s(signature: &[u8; 64]) -> bool {
+    !gw_common::webauthn::is_low_s(signature)
+}
+
+// r | s => r | n - s
+fn flip_s(signature: &[u8; 64]) -> [u8; 64] {
+    let mut flipped = *signature;
+    let mut borrow = 0i16;
+    for i in (0..32).rev() {
+        let mut diff = P256_ORDER[i] as i16 - signature[32 + i] as i16 - borrow;
+        borrow = 0;
+        if diff < 0 {
+            diff += 256;
+            borrow = 1;
+        }
+        flipped[32 + i] = diff as u8;
+    }
+    flipped
+}
@@ -0,0 +1,332 @@
+use std::{sync::Arc, time::Duration};
+
+use ckb_types::core::TransactionView;
+use futures::TryStreamExt;
+use gw_block_producer::{
+    block_sync_client::{
+        block_range_client_protocol, block_sync_client_protocol, BlockSyncClient, P2PStream,
+    },
+    chain_updater::ChainUpdater,
+};
+use gw_config::{P2PNetworkConfig, SyncServerConfig};
+use gw_mem_pool::block_sync_server::{
+    block_range_server_protocol, block_sync_server_protocol, local_block_from_store,
+    BlockSyncServerState,
+};
+use gw_p2p_network::{
+    peers::Peers, FnSpawn, P2PNetwork, P2P_BLOCK_RANGE_PROTOCOL, P2P_BLOCK_RANGE_PROTOCOL_NAME,
+};
+use gw_rpc_server::registry::Registry;
+use gw_store::traits::chain_store::ChainStore;
+use gw_types::{
+    bytes::Bytes,
+    packed::{
+        BlockRange, BlockRangeReader, BlockRangeRequest, CellOutput, Confirmed, NumberHash, Script,
+    },
+    prelude::*,
+};
+use gw_utils::{compression::StreamDecoder, liveness::Liveness};
+use tentacle::{
+    builder::MetaBuilder,
+    service::{ProtocolMeta, ServiceAsyncControl},
+    SessionId, SubstreamReadPart,
+};
+use tokio::sync::{mpsc, Mutex};
+
+use crate::testing_tool::{
+    chain::{setup_chain, TestChain, DEFAULT_FINALITY_BLOCKS},
+    mock_ckb::MockCkb,
+    rpc_server::RPCServer,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+fn local_address() -> String {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    format!(
+        "/ip4/127.0.0.1/tcp/{}",
+        listener.local_addr().unwrap().port()
+    )
+}
+
+async fn start_p2p_network(
+    listen: Option<String>,
+    dial: Vec<String>,
+    protocols: Vec<ProtocolMeta>,
+    peers: Arc<Peers>,
+) {
+    let config = P2PNetworkConfig {
+        listen,
+        dial,
+        secret_key_path: None,
+        allowed_peer_ids: None,
+    };
+    let mut network = P2PNetwork::init(&config, protocols, peers).await.unwrap();
+    tokio::spawn(async move { network.run().await });
+}
+
+fn publish_block(state: &std::sync::Mutex<BlockSyncServerState>, chain: &TestChain, b: u64) {
+    let snap = chain.store().get_snapshot();
+    let local_block = local_block_from_store(&snap, b).unwrap();
+    state.lock().unwrap().publish_local_block(local_block);
+}
+
+fn publish_confirmed(state: &std::sync::Mutex<BlockSyncServerState>, chain: &TestChain, b: u64) {
+    let snap = chain.store().get_snapshot();
+    let block_hash = snap.get_block_hash_by_number(b).unwrap().unwrap();
+    let confirmed = Confirmed::new_builder()
+        .tx_hash(snap.get_block_submit_tx_hash(b).unwrap().pack())
+        .number_hash(
+            NumberHash::new_builder()
+                .number(b.pack())
+                .block_hash(block_hash.pack())
+                .build(),
+        )
+        .build();
+    state.lock().unwrap().publish_confirmed(confirmed);
+}
+
+// Full node with `blocks` confirmed blocks, which serves both the live stream
+// and block ranges.
+async fn start_full_node(
+    config: &SyncServerConfig,
+    blocks: u64,
+) -> (
+    TestChain,
+    Arc<std::sync::Mutex<BlockSyncServerState>>,
+    String,
+) {
+    let mut chain = TestChain::setup(Script::default()).await;
+    let rollup_context = chain.inner.generator().rollup_context().clone();
+    let state = Arc::new(std::sync::Mutex::new(BlockSyncServerState::new(
+        config,
+        chain.store().clone(),
+        rollup_context,
+    )));
+    publish_block(&state, &chain, 0);
+    for b in 1..=blocks {
+        chain
+            .produce_block(Default::default(), vec![])
+            .await
+            .unwrap();
+        publish_block(&state, &chain, b);
+        publish_confirmed(&state, &chain, b);
+    }
+    let address = local_address();
+    let peers = Arc::new(Peers::default());
+    start_p2p_network(
+        Some(address.clone()),
+        vec![],
+        vec![
+            block_sync_server_protocol(state.clone(), peers.clone()),
+            block_range_server_protocol(state.clone(), peers.clone()),
+        ],
+        peers,
+    )
+    .await;
+    (chain, state, address)
+}
+
+// The submit tx of block `b` on the full node.
+fn submit_tx(chain: &TestChain, b: u64) -> TransactionView {
+    let snap = chain.store().get_snapshot();
+    snap.get_block_submit_tx(b).unwrap().into_view()
+}
+
+// A read-only node far behind the full node catches up finalized blocks from
+// the store of the full node, then switches to the live stream.
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_block_sync_catch_up() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    // Blocks 17..=24 are in the buffer of the full node, blocks 1..=18 are
+    // finalized and caught up in 4 ranges.
+    let blocks = 24;
+    let finalized = blocks - DEFAULT_FINALITY_BLOCKS;
+    let sync_server_config = SyncServerConfig {
+        buffer_capacity: 8,
+        max_block_range: 5,
+        ..Default::default()
+    };
+    let (mut full_node, full_node_state, full_node_address) =
+        start_full_node(&sync_server_config, blocks).await;
+    let rollup_type_script = full_node.rollup_type_script.clone();
+    let rollup_context = full_node.inner.generator().rollup_context().clone();
+
+    // Only the finalized blocks are submitted on L1, the others can't be
+    // synced from L1 or caught up.
+    let genesis_tx = TransactionView::new_advanced_builder()
+        .output(CellOutput::new_builder().capacity(1000u64.pack()).build())
+        .output_data(Bytes::new().pack())
+        .build();
+    let genesis_tx_hash: [u8; 32] = genesis_tx.hash().unpack();
+    let l1_txs = std::iter::once(genesis_tx)
+        .chain((1..=finalized).map(|b| submit_tx(&full_node, b)))
+        .collect();
+    let mock_ckb = MockCkb::start(l1_txs).unwrap();
+    let rpc_client = mock_ckb.rpc_client(
+        rollup_type_script.clone(),
+        rollup_context.rollup_config.clone(),
+    );
+
+    // Read-only node
+    let read_only_chain = setup_chain(rollup_type_script.clone()).await;
+    let read_only_store = read_only_chain.store().clone();
+    let mut store_tx = read_only_store.begin_transaction();
+    store_tx
+        .set_block_submit_tx_hash(0, &genesis_tx_hash)
+        .unwrap();
+    store_tx.commit().unwrap();
+    let registry_args =
+        RPCServer::default_registry_args(&read_only_chain, rollup_type_script.clone(), None);
+    let registry = Registry::create(registry_args).await.unwrap();
+    let read_only_chain = Arc::new(Mutex::new(read_only_chain));
+    let p2p_stream_inbox: Arc<std::sync::Mutex<Option<P2PStream>>> = Default::default();
+    let block_range_stream_inbox: Arc<std::sync::Mutex<Option<P2PStream>>> = Default::default();
+    let read_only_peers = Arc::new(Peers::default());
+    start_p2p_network(
+        None,
+        vec![full_node_address],
+        vec![
+            block_sync_client_protocol(p2p_stream_inbox.clone(), read_only_peers.clone()),
+            block_range_client_protocol(block_range_stream_inbox.clone(), read_only_peers.clone()),
+        ],
+        read_only_peers,
+    )
+    .await;
+    let client = BlockSyncClient {
+        store: read_only_store.clone(),
+        rpc_client: rpc_client.clone(),
+        chain: read_only_chain.clone(),
+        mem_pool: None,
+        chain_updater: ChainUpdater::new(
+            read_only_chain,
+            rpc_client,
+            rollup_context,
+            rollup_type_script.clone(),
+        ),
+        rollup_type_script,
+        p2p_stream_inbox,
+        block_range_stream_inbox,
+        completed_initial_syncing: false,
+        liveness: Arc::new(Liveness::new(Duration::from_secs(60))),
+        relay: None,
+        max_hops: sync_server_config.max_hops,
+        backend_fork_scheduler: registry.backend_fork_scheduler(),
+    };
+    tokio::spawn(client.run());
+    let wait_synced = |full_node_tip: [u8; 32]| {
+        let read_only_store = read_only_store.clone();
+        async move {
+            tokio::time::timeout(TIMEOUT, async {
+                while read_only_store.get_last_valid_tip_block_hash().unwrap() != full_node_tip {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+            })
+            .await
+            .expect("read-only node syncs the tip")
+        }
+    };
+    wait_synced(full_node.last_valid_block().hash()).await;
+
+    // Finalized blocks are caught up with their submit txs, the others are
+    // received from the live stream.
+    let full_node_snap = full_node.store().get_snapshot();
+    for b in 1..=finalized {
+        assert_eq!(
+            read_only_store.get_block_submit_tx_hash(b),
+            full_node_snap.get_block_submit_tx_hash(b)
+        );
+    }
+    for b in finalized + 1..=blocks {
+        assert_eq!(read_only_store.get_block_submit_tx_hash(b), None);
+    }
+
+    // New blocks keep coming from the live stream.
+    full_node
+        .produce_block(Default::default(), vec![])
+        .await
+        .unwrap();
+    publish_block(&full_node_state, &full_node, blocks + 1);
+    wait_synced(full_node.last_valid_block().hash()).await;
+}
+
+type BlockRangePeer = (SessionId, ServiceAsyncControl, SubstreamReadPart);
+
+// Peer which sends raw block range requests.
+fn block_range_peer_protocol(sender: mpsc::UnboundedSender<BlockRangePeer>) -> ProtocolMeta {
+    MetaBuilder::new()
+        .name(|_| P2P_BLOCK_RANGE_PROTOCOL_NAME.into())
+        .id(P2P_BLOCK_RANGE_PROTOCOL)
+        .protocol_spawn(FnSpawn(move |context, control, read_part| {
+            let _ = sender.send((context.id, control.clone(), read_part));
+        }))
+        .build()
+}
+
+async fn request_block_range(
+    peer: &mut BlockRangePeer,
+    decoder: &mut StreamDecoder,
+    start: u64,
+    count: u32,
+) -> BlockRange {
+    let request = BlockRangeRequest::new_builder()
+        .start_block_number(start.pack())
+        .count(count.pack())
+        .build();
+    peer.1
+        .send_message_to(peer.0, P2P_BLOCK_RANGE_PROTOCOL, request.as_bytes())
+        .await
+        .unwrap();
+    let msg = tokio::time::timeout(TIMEOUT, peer.2.try_next())
+        .await
+        .expect("recv timeout")
+        .unwrap()
+        .expect("stream end");
+    let msg: Bytes = decoder.decode(&msg).unwrap().into();
+    BlockRangeReader::from_slice(&msg).unwrap();
+    BlockRange::new_unchecked(msg)
+}
+
+// The genesis block isn't served and the session is kept.
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_block_range_from_genesis() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let blocks = DEFAULT_FINALITY_BLOCKS + 2;
+    let (full_node, _full_node_state, full_node_address) =
+        start_full_node(&SyncServerConfig::default(), blocks).await;
+
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    start_p2p_network(
+        None,
+        vec![full_node_address],
+        vec![block_range_peer_protocol(sender)],
+        Default::default(),
+    )
+    .await;
+    let mut peer = tokio::time::timeout(TIMEOUT, receiver.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    let mut decoder = StreamDecoder::new();
+
+    let range = request_block_range(&mut peer, &mut decoder, 0, 2).await;
+    assert!(range.blocks().is_empty());
+
+    let range = request_block_range(&mut peer, &mut decoder, 1, 4).await;
+    let numbers: Vec<u64> = range
+        .blocks()
+        .into_iter()
+        .map(|f| f.local_block().block().raw().number().unpack())
+        .collect();
+    assert_eq!(numbers, vec![1, 2]);
+    let full_node_snap = full_node.store().get_snapshot();
+    for f in range.blocks() {
+        let b = f.local_block().block().raw().number().unpack();
+        assert_eq!(
+            Some(f.submit_tx_hash().unpack()),
+            full_node_snap.get_block_submit_tx_hash(b)
+        );
+    }
+}
@@ -0,0 +1,269 @@
+use std::{sync::Arc, time::Duration};
+
+use ckb_types::core::TransactionView;
+use futures::TryStreamExt;
+use gw_block_producer::{
+    block_sync_client::{block_sync_relay_protocol, BlockSyncClient, P2PStream},
+    chain_updater::ChainUpdater,
+};
+use gw_config::{P2PNetworkConfig, SyncServerConfig};
+use gw_mem_pool::block_sync_server::{
+    block_sync_server_protocol, local_block_from_store, BlockSyncServerState,
+};
+use gw_p2p_network::{
+    peers::Peers, FnSpawn, P2PNetwork, P2P_SYNC_PROTOCOL, P2P_SYNC_PROTOCOL_NAME,
+    P2P_SYNC_PROTOCOL_VERSION,
+};
+use gw_rpc_server::registry::Registry;
+use gw_store::traits::chain_store::ChainStore;
+use gw_types::{
+    bytes::Bytes,
+    packed::{
+        BlockSync, BlockSyncReader, BlockSyncUnion, CellOutput, NextMemBlock, P2PSyncRequest,
+        P2PSyncResponseReader, P2PSyncResponseUnionReader, Script,
+    },
+    prelude::*,
+};
+use gw_utils::{compression::StreamDecoder, liveness::Liveness};
+use tentacle::{
+    builder::MetaBuilder,
+    service::{ProtocolMeta, ServiceAsyncControl},
+    SessionId, SubstreamReadPart,
+};
+use tokio::sync::{mpsc, Mutex};
+
+use crate::testing_tool::{
+    chain::{setup_chain, TestChain},
+    mock_ckb::MockCkb,
+    rpc_server::RPCServer,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+fn local_address() -> String {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    format!(
+        "/ip4/127.0.0.1/tcp/{}",
+        listener.local_addr().unwrap().port()
+    )
+}
+
+async fn start_p2p_network(
+    listen: Option<String>,
+    dial: Vec<String>,
+    protocols: Vec<ProtocolMeta>,
+    peers: Arc<Peers>,
+) {
+    let config = P2PNetworkConfig {
+        listen,
+        dial,
+        secret_key_path: None,
+        allowed_peer_ids: None,
+    };
+    let mut network = P2PNetwork::init(&config, protocols, peers).await.unwrap();
+    tokio::spawn(async move { network.run().await });
+}
+
+type Downstream = (SessionId, ServiceAsyncControl, SubstreamReadPart);
+
+// Downstream peer of the relay, which checks the raw messages.
+fn downstream_protocol(sender: mpsc::UnboundedSender<Downstream>) -> ProtocolMeta {
+    MetaBuilder::new()
+        .name(|_| P2P_SYNC_PROTOCOL_NAME.into())
+        .id(P2P_SYNC_PROTOCOL)
+        .support_versions(vec![P2P_SYNC_PROTOCOL_VERSION.into()])
+        .protocol_spawn(FnSpawn(move |context, control, read_part| {
+            let _ = sender.send((context.id, control.clone(), read_part));
+        }))
+        .build()
+}
+
+async fn recv(read_part: &mut SubstreamReadPart, decoder: &mut StreamDecoder) -> Bytes {
+    let msg = tokio::time::timeout(TIMEOUT, read_part.try_next())
+        .await
+        .expect("recv timeout")
+        .unwrap()
+        .expect("stream end");
+    decoder.decode(&msg).unwrap().into()
+}
+
+// full node -> relay -> read-only node
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_block_sync_relay() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let sync_server_config = SyncServerConfig::default();
+
+    // Full node
+    let mut full_node = TestChain::setup(rollup_type_script.clone()).await;
+    let rollup_context = full_node.inner.generator().rollup_context().clone();
+    let full_node_state = Arc::new(std::sync::Mutex::new(BlockSyncServerState::new(
+        &sync_server_config,
+        full_node.store().clone(),
+        rollup_context.clone(),
+    )));
+    let genesis = local_block_from_store(&full_node.store().get_snapshot(), 0).unwrap();
+    full_node_state.lock().unwrap().publish_local_block(genesis);
+    full_node
+        .produce_block(Default::default(), vec![])
+        .await
+        .unwrap();
+    let block_1 = local_block_from_store(&full_node.store().get_snapshot(), 1).unwrap();
+    full_node_state.lock().unwrap().publish_local_block(block_1);
+    let full_node_address = local_address();
+    let full_node_peers = Arc::new(Peers::default());
+    start_p2p_network(
+        Some(full_node_address.clone()),
+        vec![],
+        vec![block_sync_server_protocol(
+            full_node_state.clone(),
+            full_node_peers.clone(),
+        )],
+        full_node_peers,
+    )
+    .await;
+
+    // Relay, the L2 genesis is submitted by a committed L1 tx.
+    let genesis_tx = TransactionView::new_advanced_builder()
+        .output(CellOutput::new_builder().capacity(1000u64.pack()).build())
+        .output_data(Bytes::new().pack())
+        .build();
+    let genesis_tx_hash: [u8; 32] = genesis_tx.hash().unpack();
+    let mock_ckb = MockCkb::start(vec![genesis_tx]).unwrap();
+    let rpc_client = mock_ckb.rpc_client(
+        rollup_type_script.clone(),
+        rollup_context.rollup_config.clone(),
+    );
+    let relay_chain = setup_chain(rollup_type_script.clone()).await;
+    let relay_store = relay_chain.store().clone();
+    let mut store_tx = relay_store.begin_transaction();
+    store_tx
+        .set_block_submit_tx_hash(0, &genesis_tx_hash)
+        .unwrap();
+    store_tx.commit().unwrap();
+    let relay_state = Arc::new(std::sync::Mutex::new(BlockSyncServerState::new(
+        &sync_server_config,
+        relay_store.clone(),
+        rollup_context.clone(),
+    )));
+    let mut registry_args =
+        RPCServer::default_registry_args(&relay_chain, rollup_type_script.clone(), None);
+    let relay_peers = Arc::new(Peers::default());
+    registry_args.block_sync_server_state = Some(relay_state.clone());
+    registry_args.peers = relay_peers.clone();
+    let registry = Registry::create(registry_args).await.unwrap();
+    let relay_chain = Arc::new(Mutex::new(relay_chain));
+    let p2p_stream_inbox: Arc<std::sync::Mutex<Option<P2PStream>>> = Default::default();
+    let relay_address = local_address();
+    start_p2p_network(
+        Some(relay_address.clone()),
+        vec![full_node_address],
+        vec![block_sync_relay_protocol(
+            p2p_stream_inbox.clone(),
+            relay_state.clone(),
+            relay_peers.clone(),
+        )],
+        relay_peers,
+    )
+    .await;
+    let client = BlockSyncClient {
+        store: relay_store.clone(),
+        rpc_client: rpc_client.clone(),
+        chain: relay_chain.clone(),
+        mem_pool: None,
+        chain_updater: ChainUpdater::new(
+            relay_chain,
+            rpc_client,
+            rollup_context,
+            rollup_type_script.clone(),
+        ),
+        rollup_type_script,
+        p2p_stream_inbox,
+        block_range_stream_inbox: Default::default(),
+        completed_initial_syncing: false,
+        liveness: Arc::new(Liveness::new(Duration::from_secs(60))),
+        relay: Some(relay_state),
+        max_hops: sync_server_config.max_hops,
+        backend_fork_scheduler: registry.backend_fork_scheduler(),
+    };
+    tokio::spawn(client.run());
+    tokio::time::timeout(TIMEOUT, async {
+        while relay_store.get_last_valid_tip_block_hash().unwrap()
+            != full_node.last_valid_block().hash()
+        {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .expect("relay syncs block 1");
+
+    // Read-only node
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    start_p2p_network(
+        None,
+        vec![relay_address],
+        vec![downstream_protocol(sender)],
+        Default::default(),
+    )
+    .await;
+    let (session_id, control, mut read_part) = tokio::time::timeout(TIMEOUT, receiver.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    let block_1 = full_node.last_valid_block();
+    let request = P2PSyncRequest::new_builder()
+        .block_number(1u64.pack())
+        .block_hash(block_1.hash().pack())
+        .build();
+    control
+        .send_message_to(session_id, P2P_SYNC_PROTOCOL, request.as_bytes())
+        .await
+        .unwrap();
+    let mut decoder = StreamDecoder::new();
+    let response = recv(&mut read_part, &mut decoder).await;
+    let response = P2PSyncResponseReader::from_slice(&response).unwrap();
+    assert!(matches!(
+        response.to_enum(),
+        P2PSyncResponseUnionReader::Found(_)
+    ));
+
+    // Blocks and mem blocks of the full node are relayed.
+    full_node
+        .produce_block(Default::default(), vec![])
+        .await
+        .unwrap();
+    let block_2 = local_block_from_store(&full_node.store().get_snapshot(), 2).unwrap();
+    full_node_state.lock().unwrap().publish_local_block(block_2);
+    let block_info = full_node
+        .mem_pool()
+        .await
+        .mem_block()
+        .block_info()
+        .to_owned();
+    let next_mem_block = NextMemBlock::new_builder()
+        .block_info(block_info.clone())
+        .build();
+    full_node_state
+        .lock()
+        .unwrap()
+        .publish_next_mem_block(next_mem_block);
+
+    let msg = recv(&mut read_part, &mut decoder).await;
+    BlockSyncReader::from_slice(&msg).unwrap();
+    match BlockSync::new_unchecked(msg).to_enum() {
+        BlockSyncUnion::LocalBlock(l) => {
+            assert_eq!(l.block().hash(), full_node.last_valid_block().hash());
+            assert_eq!(l.hops().unpack(), 1);
+        }
+        _ => panic!("expect local block"),
+    }
+    let msg = recv(&mut read_part, &mut decoder).await;
+    BlockSyncReader::from_slice(&msg).unwrap();
+    match BlockSync::new_unchecked(msg).to_enum() {
+        BlockSyncUnion::NextMemBlock(m) => {
+            assert_eq!(m.block_info().as_slice(), block_info.as_slice());
+        }
+        _ => panic!("expect next mem block"),
+    }
+}
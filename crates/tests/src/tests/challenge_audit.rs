@@ -0,0 +1,134 @@
+use std::collections::HashSet;
+
+use godwoken_bin::subcommand::challenge_audit::ChallengeAuditor;
+use gw_block_producer::challenger::{MAX_CANCEL_CYCLES, MAX_CANCEL_TX_BYTES};
+use gw_types::{
+    core::{ChallengeTargetType, ScriptHashType},
+    packed::{
+        DepositInfoVec, DepositRequest, RawWithdrawalRequest, Script, WithdrawalRequest,
+        WithdrawalRequestExtra,
+    },
+    prelude::*,
+};
+
+use crate::testing_tool::{
+    chain::{
+        apply_block_result, chain_generator, construct_block, into_deposit_info_cell,
+        produce_empty_block, DEFAULT_FINALITY_BLOCKS, TEST_CHAIN_ID,
+    },
+    mock_l1::{
+        always_success_script, eoa_lock_type_script, setup_chain, start_mock_ckb, MockL1Rollup, CKB,
+    },
+};
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_challenge_audit_withdrawal_budgets() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let mock_ckb = start_mock_ckb();
+    let rollup_type_script = Script::new_builder()
+        .code_hash(always_success_script(&[1]).hash().pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args(vec![42u8; 32].pack())
+        .build();
+    let mut chain = setup_chain(rollup_type_script.clone()).await;
+
+    // Deposit and withdraw from an account of the allowed eoa lock.
+    let alice_script = Script::new_builder()
+        .code_hash(eoa_lock_type_script().hash().pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args({
+            let mut args = rollup_type_script.hash().to_vec();
+            args.extend(&[42u8; 20]);
+            args.pack()
+        })
+        .build();
+    let deposit = DepositRequest::new_builder()
+        .capacity((4000u64 * CKB).pack())
+        .script(alice_script.clone())
+        .registry_id(gw_common::builtins::ETH_REGISTRY_ACCOUNT_ID.pack())
+        .build();
+    let deposit_info_vec = DepositInfoVec::new_builder()
+        .push(into_deposit_info_cell(chain.generator().rollup_context(), deposit).pack())
+        .build();
+    let block_result = {
+        let mem_pool = chain.mem_pool().as_ref().unwrap();
+        let mut mem_pool = mem_pool.lock().await;
+        construct_block(&chain, &mut mem_pool, deposit_info_vec.clone())
+            .await
+            .unwrap()
+    };
+    apply_block_result(&mut chain, block_result, deposit_info_vec, HashSet::new())
+        .await
+        .unwrap();
+    for _ in 0..DEFAULT_FINALITY_BLOCKS {
+        produce_empty_block(&mut chain).await.unwrap();
+    }
+
+    let withdrawal = {
+        let owner_lock = Script::default();
+        let raw = RawWithdrawalRequest::new_builder()
+            .capacity((1000 * CKB).pack())
+            .account_script_hash(alice_script.hash().pack())
+            .owner_lock_hash(owner_lock.hash().pack())
+            .registry_id(gw_common::builtins::ETH_REGISTRY_ACCOUNT_ID.pack())
+            .chain_id(TEST_CHAIN_ID.pack())
+            .build();
+        WithdrawalRequestExtra::new_builder()
+            .request(WithdrawalRequest::new_builder().raw(raw).build())
+            .owner_lock(owner_lock)
+            .build()
+    };
+    let block_result = {
+        let mem_pool = chain.mem_pool().as_ref().unwrap();
+        let mut mem_pool = mem_pool.lock().await;
+        mem_pool.push_withdrawal_request(withdrawal).await.unwrap();
+        construct_block(&chain, &mut mem_pool, Default::default())
+            .await
+            .unwrap()
+    };
+    let block_number: u64 = block_result.block.raw().number().unpack();
+    assert_eq!(block_result.block.withdrawals().len(), 1);
+    apply_block_result(&mut chain, block_result, Default::default(), HashSet::new())
+        .await
+        .unwrap();
+
+    let global_state = chain.local_state().last_global_state().clone();
+    let rollup_context = chain.generator().rollup_context().clone();
+    let l1_rollup = MockL1Rollup::deploy(
+        &mock_ckb,
+        rollup_type_script.clone(),
+        rollup_context,
+        &global_state,
+    )
+    .await;
+    let mock_ctx = l1_rollup.offchain_mock_context(Default::default()).await;
+    let auditor = |max_cycles| {
+        ChallengeAuditor::new(
+            chain_generator(&chain, rollup_type_script.clone()),
+            chain.store().clone(),
+            mock_ctx.clone(),
+            max_cycles,
+            MAX_CANCEL_TX_BYTES,
+        )
+    };
+
+    // The first strategy is within the default budgets.
+    let default_auditor = auditor(MAX_CANCEL_CYCLES);
+    let reports = default_auditor.audit_block(block_number).unwrap();
+    assert_eq!(reports.len(), 1);
+    let report = &reports[0];
+    assert_eq!(report.target_type, ChallengeTargetType::Withdrawal);
+    assert!(report.error.is_none(), "{:?}", report.error);
+    assert_eq!(report.attempts.len(), 1);
+    let cycles = report.attempts[0].cycles.expect("cancel tx cycles");
+    assert!(default_auditor.is_defensible(report));
+
+    // Both strategies exceed a cycle budget below the actual cycles.
+    let tight_auditor = auditor(cycles - 1);
+    let reports = tight_auditor.audit_block(block_number).unwrap();
+    let report = &reports[0];
+    assert_eq!(report.attempts.len(), 2);
+    assert!(report.attempts.iter().all(|a| a.error.is_none()));
+    assert!(!tight_auditor.is_defensible(report));
+}
@@ -0,0 +1,255 @@
+use gw_block_producer::{custodian_audit::CustodianAuditor, produce_block::ProduceBlockResult};
+use gw_chain::chain::{Chain, L1Action, L1ActionContext, SyncEvent, SyncParam};
+use gw_types::{
+    bytes::Bytes,
+    core::ScriptHashType,
+    packed::{
+        CellOutput, CustodianLockArgs, DepositInfoVec, DepositRequest, RawWithdrawalRequest,
+        Script, WithdrawalRequest, WithdrawalRequestExtra,
+    },
+    prelude::*,
+};
+use gw_utils::RollupContext;
+
+use crate::testing_tool::{
+    bad_block::generate_bad_block_using_first_withdrawal,
+    chain::{
+        build_sync_tx, construct_block, into_deposit_info_cell, DEFAULT_FINALITY_BLOCKS,
+        TEST_CHAIN_ID,
+    },
+    mock_l1::{
+        always_success_script, eoa_lock_type_script, setup_chain, start_mock_ckb, MockL1Rollup, CKB,
+    },
+};
+
+async fn sync_block(
+    chain: &mut Chain,
+    rollup_cell: &CellOutput,
+    result: ProduceBlockResult,
+    deposit_info_vec: DepositInfoVec,
+) {
+    let update = L1Action {
+        context: L1ActionContext::SubmitBlock {
+            l2block: result.block.clone(),
+            deposit_info_vec,
+            deposit_asset_scripts: Default::default(),
+            withdrawals: result.withdrawal_extras.clone(),
+        },
+        transaction: build_sync_tx(rollup_cell.clone(), result),
+    };
+    let param = SyncParam {
+        updates: vec![update],
+        reverts: Default::default(),
+    };
+    chain.sync(param).await.unwrap();
+    chain.notify_new_tip().await.unwrap();
+}
+
+async fn produce_block(chain: &Chain, deposit_info_vec: DepositInfoVec) -> ProduceBlockResult {
+    let mem_pool = chain.mem_pool().as_ref().unwrap();
+    let mut mem_pool = mem_pool.lock().await;
+    construct_block(chain, &mut mem_pool, deposit_info_vec)
+        .await
+        .unwrap()
+}
+
+async fn deposit(chain: &mut Chain, rollup_cell: &CellOutput, script: Script, capacity: u64) {
+    let deposit = DepositRequest::new_builder()
+        .capacity(capacity.pack())
+        .script(script)
+        .registry_id(gw_common::builtins::ETH_REGISTRY_ACCOUNT_ID.pack())
+        .build();
+    let deposit_info_vec = DepositInfoVec::new_builder()
+        .push(into_deposit_info_cell(chain.generator().rollup_context(), deposit).pack())
+        .build();
+    let result = produce_block(chain, deposit_info_vec.clone()).await;
+    sync_block(chain, rollup_cell, result, deposit_info_vec).await;
+}
+
+fn rollup_type_script() -> Script {
+    Script::new_builder()
+        .code_hash(always_success_script(&[1]).hash().pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args(vec![42u8; 32].pack())
+        .build()
+}
+
+fn eoa_script(rollup_type_script: &Script) -> Script {
+    Script::new_builder()
+        .code_hash(eoa_lock_type_script().hash().pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args({
+            let mut args = rollup_type_script.hash().to_vec();
+            args.extend(&[42u8; 20]);
+            args.pack()
+        })
+        .build()
+}
+
+fn custodian_cell(rollup_context: &RollupContext, capacity: u64) -> (CellOutput, Bytes) {
+    let lock = Script::new_builder()
+        .code_hash(rollup_context.rollup_config.custodian_script_type_hash())
+        .hash_type(ScriptHashType::Type.into())
+        .args({
+            let mut args = rollup_context.rollup_script_hash.to_vec();
+            args.extend_from_slice(CustodianLockArgs::default().as_slice());
+            args.pack()
+        })
+        .build();
+    let output = CellOutput::new_builder()
+        .capacity(capacity.pack())
+        .lock(lock)
+        .build();
+    (output, Bytes::new())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_custodian_audit_solvent() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let mock_ckb = start_mock_ckb();
+    let rollup_type_script = rollup_type_script();
+    let mut chain = setup_chain(rollup_type_script.clone()).await;
+    let rollup_cell = CellOutput::new_builder()
+        .type_(Some(rollup_type_script.clone()).pack())
+        .build();
+    let alice_script = eoa_script(&rollup_type_script);
+
+    deposit(&mut chain, &rollup_cell, alice_script.clone(), 4000 * CKB).await;
+    for _ in 0..DEFAULT_FINALITY_BLOCKS {
+        let result = produce_block(&chain, Default::default()).await;
+        sync_block(&mut chain, &rollup_cell, result, Default::default()).await;
+    }
+    let submitted_global_state = chain.local_state().last_global_state().clone();
+    let submitted_block_number: u64 = submitted_global_state.block().count().unpack() - 1;
+
+    // A local block with a deposit, which is not submitted yet.
+    deposit(&mut chain, &rollup_cell, alice_script.clone(), 1000 * CKB).await;
+
+    // A bad block with a withdrawal after the last valid tip.
+    let withdrawal = {
+        let owner_lock = Script::default();
+        let raw = RawWithdrawalRequest::new_builder()
+            .capacity((1000 * CKB).pack())
+            .account_script_hash(alice_script.hash().pack())
+            .owner_lock_hash(owner_lock.hash().pack())
+            .registry_id(gw_common::builtins::ETH_REGISTRY_ACCOUNT_ID.pack())
+            .chain_id(TEST_CHAIN_ID.pack())
+            .build();
+        WithdrawalRequestExtra::new_builder()
+            .request(WithdrawalRequest::new_builder().raw(raw).build())
+            .owner_lock(owner_lock)
+            .build()
+    };
+    let result = {
+        let mem_pool = chain.mem_pool().as_ref().unwrap();
+        let mut mem_pool = mem_pool.lock().await;
+        mem_pool.push_withdrawal_request(withdrawal).await.unwrap();
+        construct_block(&chain, &mut mem_pool, Default::default())
+            .await
+            .unwrap()
+    };
+    let (bad_block, bad_global_state) =
+        generate_bad_block_using_first_withdrawal(&chain, result.block, result.global_state);
+    let withdrawal_extras = result
+        .withdrawal_extras
+        .into_iter()
+        .enumerate()
+        .map(|(i, w)| {
+            let request = bad_block.withdrawals().get(i).unwrap();
+            w.as_builder().request(request).build()
+        })
+        .collect();
+    let bad_block_result = ProduceBlockResult {
+        block: bad_block,
+        global_state: bad_global_state,
+        withdrawal_extras,
+        ..result
+    };
+    sync_block(
+        &mut chain,
+        &rollup_cell,
+        bad_block_result,
+        Default::default(),
+    )
+    .await;
+    assert!(matches!(
+        chain.last_sync_event(),
+        SyncEvent::BadBlock { .. }
+    ));
+
+    let rollup_context = chain.generator().rollup_context().clone();
+    let l1_rollup = MockL1Rollup::deploy(
+        &mock_ckb,
+        rollup_type_script,
+        rollup_context.clone(),
+        &submitted_global_state,
+    )
+    .await;
+    mock_ckb.mine_cells(vec![custodian_cell(&rollup_context, 4000 * CKB)]);
+
+    let auditor =
+        CustodianAuditor::new(l1_rollup.rpc_client, chain.store().clone(), rollup_context);
+    let report = auditor.audit().await.unwrap();
+    assert_eq!(
+        report.submitted_block_number.value(),
+        submitted_block_number
+    );
+    assert_eq!(
+        report.last_valid_tip_block_number.value(),
+        submitted_block_number + 1
+    );
+    assert_eq!(report.deficit_assets().count(), 0);
+
+    let ckb = &report.assets[0];
+    assert_eq!(ckb.sudt_script_hash.0, [0u8; 32]);
+    assert_eq!(ckb.total_custodian.value(), (4000 * CKB) as u128);
+    assert_eq!(ckb.pending_deposits.value(), (1000 * CKB) as u128);
+    assert_eq!(ckb.l2_supply.value(), (5000 * CKB) as u128);
+    // The withdrawal of the bad block is not counted.
+    assert_eq!(ckb.pending_withdrawals.value(), 0);
+    assert_eq!(ckb.deficit.value(), 0);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_custodian_audit_insolvent() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let mock_ckb = start_mock_ckb();
+    let rollup_type_script = rollup_type_script();
+    let mut chain = setup_chain(rollup_type_script.clone()).await;
+    let rollup_cell = CellOutput::new_builder()
+        .type_(Some(rollup_type_script.clone()).pack())
+        .build();
+    let alice_script = eoa_script(&rollup_type_script);
+    deposit(&mut chain, &rollup_cell, alice_script, 4000 * CKB).await;
+
+    // Custodians hold less than the deposit.
+    let global_state = chain.local_state().last_global_state().clone();
+    let rollup_context = chain.generator().rollup_context().clone();
+    let l1_rollup = MockL1Rollup::deploy(
+        &mock_ckb,
+        rollup_type_script,
+        rollup_context.clone(),
+        &global_state,
+    )
+    .await;
+    mock_ckb.mine_cells(vec![custodian_cell(&rollup_context, 3000 * CKB)]);
+
+    let auditor =
+        CustodianAuditor::new(l1_rollup.rpc_client, chain.store().clone(), rollup_context);
+    let report = auditor.audit().await.unwrap();
+    assert_eq!(
+        report.submitted_block_number.value(),
+        report.last_valid_tip_block_number.value()
+    );
+
+    let deficits: Vec<_> = report.deficit_assets().collect();
+    assert_eq!(deficits.len(), 1);
+    let ckb = deficits[0];
+    assert_eq!(ckb.sudt_script_hash.0, [0u8; 32]);
+    assert_eq!(ckb.total_custodian.value(), (3000 * CKB) as u128);
+    assert_eq!(ckb.pending_deposits.value(), 0);
+    assert_eq!(ckb.l2_supply.value(), (4000 * CKB) as u128);
+    assert_eq!(ckb.deficit.value(), (1000 * CKB) as u128);
+}
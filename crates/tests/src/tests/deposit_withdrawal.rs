@@ -671,3 +671,49 @@ async fn test_produce_block_after_re_inject_withdrawal() {
         .unwrap()
         .is_none());
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_deposit_out_point_index() {
+    let rollup_type_script = Script::default();
+    let rollup_script_hash = rollup_type_script.hash();
+    let mut chain = setup_chain(rollup_type_script.clone()).await;
+    let user_script = Script::new_builder()
+        .code_hash(ALWAYS_SUCCESS_CODE_HASH.pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args({
+            let mut args = rollup_script_hash.to_vec();
+            args.extend(&[42u8; 20]);
+            args.pack()
+        })
+        .build();
+    deposite_to_chain(
+        &mut chain,
+        user_script,
+        600_00000000,
+        H256::zero(),
+        Script::default(),
+        0,
+    )
+    .await
+    .unwrap();
+
+    let mut db = chain.store().begin_transaction();
+    let tip_block = db.get_last_valid_tip_block().unwrap();
+    let tip_block_number: u64 = tip_block.raw().number().unpack();
+    let deposit = db
+        .get_block_deposit_info_vec(tip_block_number)
+        .unwrap()
+        .get(0)
+        .unwrap();
+    let out_point = deposit.cell().out_point();
+
+    let deposit_key = db.get_deposit_key(&out_point).expect("deposit key");
+    let block_number: u64 = deposit_key.block_number().unpack();
+    let index: u32 = deposit_key.index().unpack();
+    assert_eq!(block_number, tip_block_number);
+    assert_eq!(index, 0);
+
+    // The index is removed with the block
+    db.detach_block(&tip_block).unwrap();
+    assert!(db.get_deposit_key(&out_point).is_none());
+}
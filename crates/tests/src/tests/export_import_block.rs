@@ -12,14 +12,22 @@ use crate::testing_tool::chain::{
     ETH_ACCOUNT_LOCK_CODE_HASH,
 };
 
-use godwoken_bin::subcommand::{export_block::ExportBlock, import_block::ImportBlock};
+use godwoken_bin::subcommand::{
+    export_block::{ExportBlock, ExportFormat},
+    import_block::ImportBlock,
+};
 use gw_block_producer::produce_block::ProduceBlockResult;
 use gw_chain::chain::{Chain, ChallengeCell, L1Action, L1ActionContext, SyncEvent, SyncParam};
 use gw_config::StoreConfig;
 use gw_generator::account_lock_manage::always_success::AlwaysSuccess;
 use gw_generator::account_lock_manage::secp256k1::Secp256k1Eth;
 use gw_generator::account_lock_manage::AccountLockManage;
-use gw_store::{readonly::StoreReadonly, schema::COLUMNS, traits::chain_store::ChainStore, Store};
+use gw_store::{
+    readonly::StoreReadonly,
+    schema::{COLUMNS, COLUMN_BAD_BLOCK_DEPOSIT_INFO_VEC},
+    traits::{chain_store::ChainStore, kv_store::KVStoreWrite},
+    Store,
+};
 use gw_types::core::{Status, Timepoint};
 use gw_types::h256::*;
 use gw_types::packed::DepositInfoVec;
@@ -33,7 +41,7 @@ use gw_types::{
         RawWithdrawalRequest, RollupConfig, Script, WithdrawalRequest, WithdrawalRequestExtra,
     },
 };
-use gw_utils::export_block::check_block_post_state;
+use gw_utils::export_block::{check_bad_block_post_state, check_block_post_state, export_block};
 
 const CKB: u64 = 100000000;
 const MAX_MEM_BLOCK_WITHDRAWALS: u8 = 50;
@@ -184,8 +192,10 @@ async fn test_export_import_block() {
     let store_readonly = StoreReadonly::open(store_dir.path(), COLUMNS).unwrap();
     let tip_block = store_readonly.get_tip_block().unwrap();
     let tip_block_number = tip_block.raw().number().unpack();
+    // Export in v1 format, import-block should still read it
     let export_block =
-        ExportBlock::new_unchecked(store_readonly, export_path.clone(), 0, tip_block_number);
+        ExportBlock::new_unchecked(store_readonly, export_path.clone(), 0, tip_block_number)
+            .with_format(ExportFormat::V1);
     let export_store = export_block.store().clone();
     export_block.execute().unwrap();
 
@@ -265,7 +275,8 @@ async fn test_export_import_block() {
     let tip_block = store_readonly.get_tip_block().unwrap();
     let tip_block_number = tip_block.raw().number().unpack();
     let export_block =
-        ExportBlock::new_unchecked(store_readonly, export_path.clone(), 0, tip_block_number);
+        ExportBlock::new_unchecked(store_readonly, export_path.clone(), 0, tip_block_number)
+            .with_format(ExportFormat::V2);
     let export_store = export_block.store().clone();
     export_block.execute().unwrap();
 
@@ -317,13 +328,124 @@ async fn test_export_import_block() {
 
     let import_tx_db = import_store.begin_transaction();
     check_block_post_state(&import_tx_db, tip_block_number, &post_global_state).unwrap();
+
+    // Revert a bad block without producing new block, then submit another bad
+    // block and leave it waiting for challenge.
+    generate_and_revert_a_bad_block(&mut chain, &rollup_cell, accounts[3].clone()).await;
+    submit_a_bad_block(&mut chain, &rollup_cell, accounts[4].clone()).await;
+
+    // Export bad blocks
+    let export_path = {
+        let tmp_dir = tempfile::tempdir().expect("create temp dir");
+        let mut path_buf = tmp_dir.path().to_path_buf();
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap();
+        path_buf.set_file_name(format!("export_bad_block_{}", now.as_secs()));
+        path_buf
+    };
+    let store_readonly = StoreReadonly::open(store_dir.path(), COLUMNS).unwrap();
+    let tip_block = store_readonly.get_tip_block().unwrap();
+    let tip_block_number = tip_block.raw().number().unpack();
+    assert_ne!(
+        store_readonly.get_last_valid_tip_block_hash().unwrap(),
+        tip_block.hash()
+    );
+    let export_block =
+        ExportBlock::new_unchecked(store_readonly, export_path.clone(), 0, tip_block_number)
+            .with_format(ExportFormat::V2);
+    let export_store = export_block.store().clone();
+    export_block.execute().unwrap();
+
+    // Import bad blocks
+    let import_store_dir = tempfile::tempdir().expect("create temp dir");
+    let import_store = {
+        let config = StoreConfig {
+            path: import_store_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        Store::open(&config, COLUMNS).unwrap()
+    };
+    let import_chain = {
+        let mut account_lock_manage = AccountLockManage::default();
+        account_lock_manage
+            .register_lock_algorithm(*ALWAYS_SUCCESS_CODE_HASH, Arc::new(AlwaysSuccess));
+        account_lock_manage.register_lock_algorithm(
+            *ETH_ACCOUNT_LOCK_CODE_HASH,
+            Arc::new(Secp256k1Eth::default()),
+        );
+        setup_chain_with_account_lock_manage(
+            rollup_type_script.clone(),
+            rollup_config.clone(),
+            account_lock_manage,
+            Some(import_store),
+            None,
+            None,
+        )
+        .await
+    };
+    let import_block = ImportBlock::new_unchecked(import_chain, export_path);
+    let import_store = import_block.store().clone();
+    import_block.execute().await.unwrap();
+
+    // Check imported bad block, challenge target and reverted block smt
+    let tip_block_hash = export_store.get_tip_block_hash().unwrap();
+    let post_global_state = export_store
+        .get_block_post_global_state(&tip_block_hash)
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(import_store.get_tip_block_hash().unwrap(), tip_block_hash);
+    assert_eq!(
+        import_store.get_last_valid_tip_block_hash().unwrap(),
+        export_store.get_last_valid_tip_block_hash().unwrap()
+    );
+    assert_eq!(
+        import_store.get_reverted_block_smt_root().unwrap(),
+        export_store.get_reverted_block_smt_root().unwrap()
+    );
+    let challenge_target = export_store
+        .get_bad_block_challenge_target(&tip_block_hash)
+        .unwrap()
+        .expect("challenge target");
+    let import_challenge_target = import_store
+        .get_bad_block_challenge_target(&tip_block_hash)
+        .unwrap()
+        .expect("imported challenge target");
+    assert_eq!(
+        challenge_target.as_slice(),
+        import_challenge_target.as_slice()
+    );
+    assert_eq!(
+        import_store
+            .get_bad_block_deposit_info_vec(&tip_block_hash)
+            .map(|d| d.as_bytes()),
+        export_store
+            .get_bad_block_deposit_info_vec(&tip_block_hash)
+            .map(|d| d.as_bytes()),
+    );
+
+    let import_tx_db = import_store.begin_transaction();
+    check_bad_block_post_state(&import_tx_db, tip_block_number, &post_global_state).unwrap();
+
+    // Bad blocks inserted by earlier versions have no deposit info.
+    let mut tx_db = import_store.begin_transaction();
+    tx_db
+        .delete(COLUMN_BAD_BLOCK_DEPOSIT_INFO_VEC, tip_block_hash.as_slice())
+        .unwrap();
+    tx_db.commit().unwrap();
+    let store_readonly = StoreReadonly::open(import_store_dir.path(), COLUMNS).unwrap();
+    let exported = export_block(&store_readonly, tip_block_number).unwrap();
+    assert!(exported.deposit_info_vec.is_empty());
 }
 
-async fn generate_and_revert_a_bad_block(
+/// Submit a bad block, returns the block result with `Halting` status for
+/// challenge.
+async fn submit_a_bad_block(
     chain: &mut Chain,
     rollup_cell: &CellInfo,
     account_script: Script,
-) {
+) -> ProduceBlockResult {
     let prev_tip_block_number = chain.local_state().tip().raw().number().unpack();
 
     // update bad block
@@ -402,6 +524,23 @@ async fn generate_and_revert_a_bad_block(
     let tip_block_number: u64 = tip_block.raw().number().unpack();
     assert_eq!(tip_block_number, prev_tip_block_number + 1);
 
+    ProduceBlockResult {
+        global_state: bad_block_result
+            .global_state
+            .as_builder()
+            .status(Status::Halting.into())
+            .build(),
+        ..bad_block_result
+    }
+}
+
+async fn generate_and_revert_a_bad_block(
+    chain: &mut Chain,
+    rollup_cell: &CellInfo,
+    account_script: Script,
+) {
+    let bad_block_result = submit_a_bad_block(chain, rollup_cell, account_script).await;
+
     // challenge bad block
     let challenge_context = match chain.last_sync_event() {
         SyncEvent::BadBlock { context } => context.to_owned(),
@@ -413,15 +552,6 @@ async fn generate_and_revert_a_bad_block(
         output_data: Bytes::default(),
     };
 
-    let bad_block_result = ProduceBlockResult {
-        global_state: bad_block_result
-            .global_state
-            .as_builder()
-            .status(Status::Halting.into())
-            .build(),
-        ..bad_block_result
-    };
-
     let challenge_bad_block = L1Action {
         context: L1ActionContext::Challenge {
             cell: challenge_cell,
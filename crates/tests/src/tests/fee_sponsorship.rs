@@ -0,0 +1,204 @@
+use std::sync::Arc;
+
+use gw_common::{
+    blake2b::new_blake2b,
+    builtins::{CKB_SUDT_ACCOUNT_ID, RESERVED_ACCOUNT_ID},
+    state::State,
+};
+use gw_generator::{
+    account_lock_manage::{secp256k1::Secp256k1Eth, AccountLockManage},
+    sponsor::{get_sponsored_fee, sponsor_key, sponsor_policy_key, sponsor_used_key},
+    Generator,
+};
+use gw_store::{chain_view::ChainView, state::traits::JournalDB, traits::chain_store::ChainStore};
+use gw_traits::CodeStore;
+use gw_types::{
+    h256::*,
+    packed::{Byte4, Byte4Vec, RawL2Transaction, Script, SponsorPolicy},
+    prelude::*,
+    U256,
+};
+use gw_utils::RollupContext;
+
+use crate::testing_tool::{
+    chain::{build_backend_manage, TestChain, ETH_ACCOUNT_LOCK_CODE_HASH},
+    eth_wallet::EthWallet,
+    polyjuice::{
+        abi_encode_eth_address, erc20::SudtErc20ArgsBuilder, PolyjuiceAccount,
+        PolyjuiceArgsBuilder, PolyjuiceSystemLog,
+    },
+};
+
+const POLICY_GAS_PRICE: u128 = 1000;
+const PER_USER_QUOTA: u128 = 100_000_000;
+// selector of `transfer(address,uint256)`
+const TRANSFER_SELECTOR: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+
+fn fee_sponsorship_generator(chain: &TestChain) -> Generator {
+    let mut rollup_context: RollupContext = chain.inner.generator().rollup_context().clone();
+    rollup_context.fork_config.enable_fee_sponsorship = Some(0);
+    let mut account_lock_manage = AccountLockManage::default();
+    account_lock_manage.register_lock_algorithm(
+        *ETH_ACCOUNT_LOCK_CODE_HASH,
+        Arc::new(Secp256k1Eth::default()),
+    );
+    let backend_manage = build_backend_manage(&rollup_context.rollup_config);
+    Generator::new(
+        backend_manage,
+        account_lock_manage,
+        rollup_context,
+        Default::default(),
+    )
+}
+
+// Same as SetSponsorPolicy of the Meta contract
+fn set_sponsor_policy(
+    state: &mut (impl State + CodeStore),
+    sponsor_id: u32,
+    policy: &SponsorPolicy,
+) {
+    let target: H256 = policy.target().unpack();
+    let data = policy.as_bytes();
+    let mut data_hash = [0u8; 32];
+    let mut hasher = new_blake2b();
+    hasher.update(&data);
+    hasher.finalize(&mut data_hash);
+    state.insert_data(data_hash, data);
+    state
+        .update_value(RESERVED_ACCOUNT_ID, &sponsor_policy_key(&target), data_hash)
+        .unwrap();
+    let mut sponsor_id_value = [0u8; 32];
+    sponsor_id_value[..4].copy_from_slice(&sponsor_id.to_le_bytes());
+    state
+        .update_value(RESERVED_ACCOUNT_ID, &sponsor_key(&target), sponsor_id_value)
+        .unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_fee_sponsorship() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let chain = TestChain::setup(Script::default()).await;
+    let generator = fee_sponsorship_generator(&chain);
+    let mem_pool_state = chain.mem_pool_state().await;
+    let mut state = mem_pool_state.load_state_db();
+
+    let deployer = EthWallet::random(chain.rollup_type_hash());
+    let deployer_id = deployer
+        .create_account(&mut state, 1_000_000u128.into())
+        .unwrap();
+    let user = EthWallet::random(chain.rollup_type_hash());
+    let user_id = user.create_account(&mut state, 1_000u128.into()).unwrap();
+    let sponsor = EthWallet::random(chain.rollup_type_hash());
+    let sponsor_balance = U256::from(PER_USER_QUOTA * 10);
+    let sponsor_id = sponsor.create_account(&mut state, sponsor_balance).unwrap();
+    let polyjuice = PolyjuiceAccount::create(chain.rollup_type_hash(), &mut state).unwrap();
+
+    // Deploy erc20 contract
+    let deploy_args = SudtErc20ArgsBuilder::deploy(CKB_SUDT_ACCOUNT_ID, 18).finish();
+    let raw_tx = RawL2Transaction::new_builder()
+        .chain_id(chain.chain_id().pack())
+        .from_id(deployer_id.pack())
+        .to_id(polyjuice.id.pack())
+        .nonce(0u32.pack())
+        .args(deploy_args.pack())
+        .build();
+    let deploy_tx = deployer.sign_polyjuice_tx(&state, raw_tx).unwrap();
+    let deploy_tx_hash: H256 = deploy_tx.hash();
+    state.finalise().unwrap();
+    mem_pool_state.store_state_db(state);
+    {
+        let mut mem_pool = chain.mem_pool().await;
+        mem_pool.push_transaction(deploy_tx).unwrap();
+    }
+    let mut state = mem_pool_state.load_state_db();
+    let system_log = PolyjuiceSystemLog::parse_from_tx_hash(&chain, deploy_tx_hash).unwrap();
+    assert_eq!(system_log.status_code, 0);
+    let erc20_id = system_log.contract_account_id(&state).unwrap();
+    let erc20_script_hash = state.get_script_hash(erc20_id).unwrap();
+
+    let selectors = Byte4Vec::new_builder()
+        .push(Byte4::from_slice(&TRANSFER_SELECTOR).unwrap())
+        .build();
+    let policy = SponsorPolicy::new_builder()
+        .target(erc20_script_hash.pack())
+        .selectors(selectors)
+        .gas_price(POLICY_GAS_PRICE.pack())
+        .per_user_quota(PER_USER_QUOTA.pack())
+        .build();
+    set_sponsor_policy(&mut state, sponsor_id, &policy);
+
+    let raw_tx = |gas_price: u128, data: Vec<u8>| {
+        let args = PolyjuiceArgsBuilder::default()
+            .gas_limit(40000)
+            .gas_price(gas_price)
+            .data(data)
+            .finish();
+        RawL2Transaction::new_builder()
+            .chain_id(chain.chain_id().pack())
+            .from_id(user_id.pack())
+            .to_id(erc20_id.pack())
+            .nonce(0u32.pack())
+            .args(args.pack())
+            .build()
+    };
+    let transfer_data = {
+        let mut data = TRANSFER_SELECTOR.to_vec();
+        data.extend_from_slice(&abi_encode_eth_address(deployer.reg_address()));
+        // amount: 1
+        let mut amount = [0u8; 32];
+        amount[31] = 1;
+        data.extend_from_slice(&amount);
+        data
+    };
+    let sponsored_tx = raw_tx(0, transfer_data.clone());
+
+    // The mem-pool prioritizes the tx by the gas price of the sponsor
+    let gas_price = |state: &_, raw_tx: &RawL2Transaction| {
+        generator.get_sponsored_gas_price(state, raw_tx, 1).unwrap()
+    };
+    assert_eq!(gas_price(&state, &sponsored_tx), Some(POLICY_GAS_PRICE));
+    // Paid by the sender
+    assert_eq!(gas_price(&state, &raw_tx(1, transfer_data.clone())), None);
+    // Selector isn't sponsored
+    let mut other_data = transfer_data;
+    other_data[..4].copy_from_slice(&[9, 9, 9, 9]);
+    assert_eq!(gas_price(&state, &raw_tx(0, other_data)), None);
+
+    // The generator doesn't settle sponsorship by itself, the sponsor is
+    // charged by the Polyjuice backend. The pinned Polyjuice binary is built
+    // before `gw_sponsor_pay_fee`, the sponsorship states must be untouched.
+    // See gwos-evm/polyjuice-tests for the charging of the sponsor.
+    let block_info = chain.mem_pool().await.mem_block().block_info().to_owned();
+    let db = chain.store().begin_transaction();
+    let tip_block_hash = db.get_tip_block_hash().unwrap();
+    let chain_view = ChainView::new(&db, tip_block_hash);
+    let run_result = generator
+        .execute_transaction(
+            &chain_view,
+            &mut state,
+            &block_info,
+            &sponsored_tx,
+            None,
+            None,
+        )
+        .unwrap();
+    let system_log = PolyjuiceSystemLog::parse_logs(run_result.logs).unwrap();
+    assert_eq!(system_log.status_code, 0);
+    let balance = state
+        .get_sudt_balance(CKB_SUDT_ACCOUNT_ID, sponsor.reg_address())
+        .unwrap();
+    assert_eq!(balance, sponsor_balance);
+    let used = get_sponsored_fee(&state, &erc20_script_hash, user_id).unwrap();
+    assert!(used.is_zero());
+
+    // Quota is used up
+    state
+        .update_value(
+            RESERVED_ACCOUNT_ID,
+            &sponsor_used_key(&erc20_script_hash, user_id),
+            H256::from_u256(PER_USER_QUOTA.into()),
+        )
+        .unwrap();
+    assert_eq!(gas_price(&state, &sponsored_tx), None);
+}
@@ -0,0 +1,231 @@
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::testing_tool::chain::{
+    default_rollup_config, produce_empty_block, setup_chain_with_account_lock_manage,
+    ALWAYS_SUCCESS_CODE_HASH,
+};
+
+use godwoken_bin::subcommand::follow_export::{FollowExport, FollowExportArgs};
+use gw_chain::chain::Chain;
+use gw_config::{Config, Consensus, ForkConfig, GenesisConfig, StoreConfig};
+use gw_generator::account_lock_manage::always_success::AlwaysSuccess;
+use gw_generator::account_lock_manage::AccountLockManage;
+use gw_store::{schema::COLUMNS, traits::chain_store::ChainStore, Store};
+use gw_types::{
+    core::ScriptHashType,
+    packed::{RollupConfig, Script},
+    prelude::*,
+};
+use gw_utils::export_block::v2;
+
+const FINALITY_BLOCKS: u64 = 2;
+
+struct TestEnv {
+    chain: Chain,
+    config: Config,
+    export_dir: tempfile::TempDir,
+    _store_dir: tempfile::TempDir,
+}
+
+impl TestEnv {
+    async fn setup() -> Self {
+        let rollup_type_script = Script::new_builder()
+            .code_hash([3u8; 32].pack())
+            .hash_type(ScriptHashType::Type.into())
+            .args(vec![4u8; 32].pack())
+            .build();
+        let rollup_config: RollupConfig = default_rollup_config()
+            .as_builder()
+            .finality_blocks(FINALITY_BLOCKS.pack())
+            .build();
+
+        let store_dir = tempfile::tempdir().expect("create temp dir");
+        let store_config = StoreConfig {
+            path: store_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let store = Store::open(&store_config, COLUMNS).unwrap();
+        let chain = {
+            let mut account_lock_manage = AccountLockManage::default();
+            account_lock_manage
+                .register_lock_algorithm(*ALWAYS_SUCCESS_CODE_HASH, Arc::new(AlwaysSuccess));
+            setup_chain_with_account_lock_manage(
+                rollup_type_script.clone(),
+                rollup_config.clone(),
+                account_lock_manage,
+                Some(store),
+                None,
+                None,
+            )
+            .await
+        };
+
+        let fork_config = ForkConfig {
+            genesis: GenesisConfig {
+                rollup_type_hash: rollup_type_script.hash().into(),
+                rollup_config: rollup_config.into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let config = Config {
+            store: store_config,
+            consensus: Consensus::Config {
+                config: Box::new(fork_config),
+            },
+            ..Default::default()
+        };
+
+        TestEnv {
+            chain,
+            config,
+            export_dir: tempfile::tempdir().expect("create temp dir"),
+            _store_dir: store_dir,
+        }
+    }
+
+    async fn produce_blocks(&mut self, count: u64) {
+        for _ in 0..count {
+            produce_empty_block(&mut self.chain).await.unwrap();
+        }
+    }
+
+    fn follow_export(&self, to_block: Option<u64>, rotate_size: Option<u64>) -> FollowExport {
+        let args = FollowExportArgs {
+            config: self.config.clone(),
+            output: self.export_dir.path().join("blocks"),
+            from_block: None,
+            to_block,
+            rotate_blocks: 4,
+            rotate_size,
+            poll_interval: Duration::from_millis(10),
+        };
+        FollowExport::create(args).unwrap()
+    }
+
+    fn find_file(&self, suffix: &str) -> Option<String> {
+        list_files(self.export_dir.path())
+            .into_iter()
+            .find(|f| f.ends_with(suffix))
+    }
+
+    fn check_segment(&self, from_block: u64, to_block: u64) {
+        let manifest_file = self
+            .find_file(&format!("_{}_{}.manifest.json", from_block, to_block))
+            .expect("manifest");
+        let manifest: serde_json::Value = {
+            let data = fs::read(self.export_dir.path().join(&manifest_file)).unwrap();
+            serde_json::from_slice(&data).unwrap()
+        };
+        let segment_file = manifest_file.strip_suffix(".manifest.json").unwrap();
+        let segment_path = self.export_dir.path().join(segment_file);
+
+        let db = self.chain.store().get_snapshot();
+        let last_block_hash = db.get_block_hash_by_number(to_block).unwrap().unwrap();
+        assert_eq!(manifest["file"], segment_file);
+        assert_eq!(manifest["format"], "v2");
+        assert_eq!(manifest["from_block"], from_block);
+        assert_eq!(manifest["to_block"], to_block);
+        assert_eq!(manifest["block_count"], to_block - from_block + 1);
+        assert_eq!(
+            manifest["last_block_hash"],
+            format!("0x{}", hex::encode(last_block_hash))
+        );
+        assert_eq!(manifest["size"], fs::metadata(&segment_path).unwrap().len());
+
+        let reader = v2::ExportedBlockReader::open(fs::File::open(&segment_path).unwrap()).unwrap();
+        let block_numbers: Vec<u64> = reader.map(|r| r.unwrap().0.block_number()).collect();
+        assert_eq!(block_numbers, (from_block..=to_block).collect::<Vec<_>>());
+    }
+
+    fn partial_files(&self) -> Vec<String> {
+        list_files(self.export_dir.path())
+            .into_iter()
+            .filter(|f| f.ends_with(".partial"))
+            .collect()
+    }
+
+    fn manifest_count(&self) -> usize {
+        list_files(self.export_dir.path())
+            .into_iter()
+            .filter(|f| f.ends_with(".manifest.json"))
+            .count()
+    }
+}
+
+fn list_files(dir: &Path) -> Vec<String> {
+    let mut files: Vec<String> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    files.sort();
+    files
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_follow_export_rotate_by_blocks_and_resume() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let mut env = TestEnv::setup().await;
+    // Tip 7, blocks up to 5 are finalized.
+    env.produce_blocks(7).await;
+
+    let mut follow_export = env.follow_export(None, None);
+    assert!(!follow_export.poll().unwrap());
+    // Nothing new is finalized.
+    assert!(!follow_export.poll().unwrap());
+    env.check_segment(0, 3);
+    assert_eq!(env.manifest_count(), 1);
+    // Blocks 4 and 5 are in the unfinished segment, block 6 isn't exported.
+    assert_eq!(env.partial_files().len(), 1);
+    assert!(env.partial_files()[0].ends_with("_4.partial"));
+    assert!(env.find_file("_6.partial").is_none());
+
+    // Restart, the unfinished segment is rewritten from the last manifest.
+    drop(follow_export);
+    env.produce_blocks(3).await;
+    let mut follow_export = env.follow_export(Some(9), None);
+    assert!(env.partial_files().is_empty());
+
+    // Tip 10, blocks up to 8 are finalized.
+    assert!(!follow_export.poll().unwrap());
+    env.check_segment(4, 7);
+    assert_eq!(env.manifest_count(), 2);
+    assert!(env.find_file("_8.partial").is_some());
+
+    // Tip 11, reach to block 9.
+    env.produce_blocks(1).await;
+    assert!(follow_export.poll().unwrap());
+    env.check_segment(8, 9);
+    assert_eq!(env.manifest_count(), 3);
+    assert!(env.partial_files().is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_follow_export_rotate_by_size() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let mut env = TestEnv::setup().await;
+    // Tip 5, blocks up to 3 are finalized.
+    env.produce_blocks(5).await;
+
+    // Every block exceeds the rotate size.
+    let mut follow_export = env.follow_export(None, Some(1));
+    assert!(!follow_export.poll().unwrap());
+    for block_number in 0..=3 {
+        env.check_segment(block_number, block_number);
+    }
+    assert_eq!(env.manifest_count(), 4);
+    assert!(env.partial_files().is_empty());
+
+    // Restart resumes after the last manifest.
+    drop(follow_export);
+    env.produce_blocks(1).await;
+    let mut follow_export = env.follow_export(Some(4), Some(1));
+    assert!(follow_export.poll().unwrap());
+    env.check_segment(4, 4);
+    assert_eq!(env.manifest_count(), 5);
+}
@@ -0,0 +1,422 @@
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use futures::TryStreamExt;
+use gw_common::builtins::CKB_SUDT_ACCOUNT_ID;
+use gw_config::{NodeMode, P2PNetworkConfig};
+use gw_jsonrpc_types::godwoken::{L2TransactionStatus, MolJsonBytes};
+use gw_p2p_network::{
+    peers::Peers, FnSpawn, P2PNetwork, P2P_FORWARD_PROTOCOL, P2P_FORWARD_PROTOCOL_NAME,
+};
+use gw_rpc_server::{
+    forward::{forward_client_protocol, forward_server_protocol, ForwardClient},
+    registry::{GwRpc, Registry},
+};
+use gw_store::state::traits::JournalDB;
+use gw_types::{
+    h256::*,
+    packed::{
+        ForwardAck, ForwardRequest, ForwardRequestReader, ForwardRequestUnion, L2Transaction,
+        RawL2Transaction, Script,
+    },
+    prelude::*,
+};
+use jsonrpc_core::{ErrorCode, Result as RpcResult};
+use tentacle::{
+    builder::MetaBuilder,
+    secio::SecioKeyPair,
+    service::{ProtocolMeta, ServiceAsyncControl},
+    SessionId, SubstreamReadPart,
+};
+use tokio::sync::mpsc;
+
+use crate::testing_tool::{
+    chain::TestChain,
+    eth_wallet::EthWallet,
+    polyjuice::{erc20::SudtErc20ArgsBuilder, PolyjuiceAccount},
+    rpc_server::{wait_tx_committed, RPCServer},
+};
+
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+fn local_address() -> String {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    format!(
+        "/ip4/127.0.0.1/tcp/{}",
+        listener.local_addr().unwrap().port()
+    )
+}
+
+// Returns the secret key path and the dial address with the peer id.
+fn node_key(dir: &tempfile::TempDir, key: [u8; 32], address: &str) -> (PathBuf, String) {
+    let path = dir.path().join(hex::encode(&key[..4]));
+    std::fs::write(&path, key).unwrap();
+    let peer_id = SecioKeyPair::secp256k1_raw_key(key)
+        .unwrap()
+        .public_key()
+        .peer_id();
+    (path, format!("{}/p2p/{}", address, peer_id.to_base58()))
+}
+
+async fn start_p2p_network(
+    listen: Option<String>,
+    dial: Vec<String>,
+    secret_key_path: Option<PathBuf>,
+    protocols: Vec<ProtocolMeta>,
+    peers: Arc<Peers>,
+) {
+    let config = P2PNetworkConfig {
+        listen,
+        dial,
+        secret_key_path,
+        allowed_peer_ids: None,
+    };
+    let mut network = P2PNetwork::init(&config, protocols, peers).await.unwrap();
+    tokio::spawn(async move { network.run().await });
+}
+
+// Read-only node which forwards requests to the full node of `dial`.
+async fn start_read_only_node(
+    chain: &TestChain,
+    listen: Option<String>,
+    dial: String,
+) -> (Arc<Registry>, Arc<Peers>) {
+    let peers = Arc::new(Peers::default());
+    let client =
+        Arc::new(ForwardClient::from_dial_addresses(&[dial.clone()], peers.clone()).unwrap());
+    let mut registry_args =
+        RPCServer::default_registry_args(&chain.inner, chain.rollup_type_script.clone(), None);
+    registry_args.node_mode = NodeMode::ReadOnly;
+    registry_args.forward_client = Some(client.clone());
+    registry_args.peers = peers.clone();
+    let registry = Registry::create(registry_args).await.unwrap();
+    start_p2p_network(
+        listen,
+        vec![dial],
+        None,
+        vec![forward_client_protocol(client)],
+        peers.clone(),
+    )
+    .await;
+    (registry, peers)
+}
+
+// Returns the session of the full node once the forward protocol is opened.
+async fn wait_forward_session(peers: &Peers) -> SessionId {
+    tokio::time::timeout(TIMEOUT, async {
+        loop {
+            let session = peers
+                .list()
+                .into_iter()
+                .find(|p| p.protocols.contains_key(P2P_FORWARD_PROTOCOL_NAME));
+            if let Some(session) = session {
+                return session.session_id;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .expect("forward session")
+}
+
+type ForwardPeer = (SessionId, ServiceAsyncControl, SubstreamReadPart);
+
+// Full node which checks the raw forward messages and acknowledges them by
+// the test.
+fn mock_full_node_protocol(sender: mpsc::UnboundedSender<ForwardPeer>) -> ProtocolMeta {
+    MetaBuilder::new()
+        .name(|_| P2P_FORWARD_PROTOCOL_NAME.into())
+        .id(P2P_FORWARD_PROTOCOL)
+        .protocol_spawn(FnSpawn(move |context, control, read_part| {
+            let _ = sender.send((context.id, control.clone(), read_part));
+        }))
+        .build()
+}
+
+async fn recv_forwarded_tx(read_part: &mut SubstreamReadPart) -> L2Transaction {
+    let msg = tokio::time::timeout(TIMEOUT, read_part.try_next())
+        .await
+        .expect("recv timeout")
+        .unwrap()
+        .expect("stream end");
+    ForwardRequestReader::from_slice(&msg).unwrap();
+    match ForwardRequest::new_unchecked(msg).to_enum() {
+        ForwardRequestUnion::L2Transaction(tx) => tx,
+        _ => panic!("expect l2 transaction"),
+    }
+}
+
+async fn send_ack(peer: &ForwardPeer, tx: &L2Transaction, error: Option<jsonrpc_core::Error>) {
+    let error = error
+        .map(|err| serde_json::to_vec(&err).unwrap())
+        .unwrap_or_default();
+    let ack = ForwardAck::new_builder()
+        .hash(tx.hash().pack())
+        .error(error.pack())
+        .build();
+    peer.1
+        .send_message_to(peer.0, P2P_FORWARD_PROTOCOL, ack.as_bytes())
+        .await
+        .unwrap();
+}
+
+async fn submit_l2transaction(
+    registry: &Arc<Registry>,
+    tx: &L2Transaction,
+) -> RpcResult<Option<H256>> {
+    let r = registry
+        .gw_submit_l2transaction(MolJsonBytes(tx.clone()))
+        .await?;
+    Ok(r.map(Into::into))
+}
+
+async fn get_transaction_status(
+    registry: &Arc<Registry>,
+    tx_hash: H256,
+) -> Option<L2TransactionStatus> {
+    let tx = registry
+        .gw_get_transaction(tx_hash.into(), None)
+        .await
+        .map_err(jsonrpc_core::Error::from)
+        .unwrap();
+    tx.map(|tx| tx.status)
+}
+
+// Creates the accounts of the wallets and the polyjuice creator account.
+async fn create_accounts(chain: &TestChain, wallets: &[&EthWallet]) -> (Vec<u32>, u32) {
+    let mem_pool_state = chain.mem_pool_state().await;
+    let mut state = mem_pool_state.load_state_db();
+    let account_ids = wallets
+        .iter()
+        .map(|wallet| {
+            wallet
+                .create_account(&mut state, 1000000u128.into())
+                .unwrap()
+        })
+        .collect();
+    let polyjuice_account = PolyjuiceAccount::create(chain.rollup_type_hash(), &mut state).unwrap();
+    state.finalise().unwrap();
+    mem_pool_state.store_state_db(state);
+    (account_ids, polyjuice_account.id)
+}
+
+async fn sign_deploy_tx(
+    chain: &TestChain,
+    wallet: &EthWallet,
+    from_id: u32,
+    polyjuice_id: u32,
+    decimals: u8,
+) -> L2Transaction {
+    let deploy_args = SudtErc20ArgsBuilder::deploy(CKB_SUDT_ACCOUNT_ID, decimals).finish();
+    let raw_tx = RawL2Transaction::new_builder()
+        .chain_id(chain.chain_id().pack())
+        .from_id(from_id.pack())
+        .to_id(polyjuice_id.pack())
+        .nonce(0u32.pack())
+        .args(deploy_args.pack())
+        .build();
+    let state = chain.mem_pool_state().await.load_state_db();
+    wallet.sign_polyjuice_tx(&state, raw_tx).unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_forward_to_full_node() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let key_dir = tempfile::tempdir().unwrap();
+
+    // Full node
+    let full_node = TestChain::setup(Script::default()).await;
+    let full_node_registry = Registry::create(RPCServer::default_registry_args(
+        &full_node.inner,
+        full_node.rollup_type_script.clone(),
+        None,
+    ))
+    .await
+    .unwrap();
+    let full_node_address = local_address();
+    let (key_path, full_node_dial) = node_key(&key_dir, [1u8; 32], &full_node_address);
+    start_p2p_network(
+        Some(full_node_address),
+        vec![],
+        Some(key_path),
+        vec![forward_server_protocol(full_node_registry)],
+        Default::default(),
+    )
+    .await;
+
+    // Read-only node
+    let read_only_node = TestChain::setup(Script::default()).await;
+    let (read_only_registry, read_only_peers) =
+        start_read_only_node(&read_only_node, None, full_node_dial).await;
+    wait_forward_session(&read_only_peers).await;
+
+    // Same accounts on both nodes, except the sender of the bad signature tx
+    // which is another account on the full node.
+    let wallet = EthWallet::random(full_node.rollup_type_hash());
+    let full_node_wallet = EthWallet::random(full_node.rollup_type_hash());
+    let read_only_wallet = EthWallet::random(full_node.rollup_type_hash());
+    let (account_ids, polyjuice_id) =
+        create_accounts(&full_node, &[&wallet, &full_node_wallet]).await;
+    assert_eq!(
+        create_accounts(&read_only_node, &[&wallet, &read_only_wallet]).await,
+        (account_ids.clone(), polyjuice_id)
+    );
+    let (from_id, bad_sig_from_id) = (account_ids[0], account_ids[1]);
+
+    // Submitted on the read-only node, committed by the full node
+    let tx = sign_deploy_tx(&read_only_node, &wallet, from_id, polyjuice_id, 18).await;
+    let tx_hash = submit_l2transaction(&read_only_registry, &tx)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(tx_hash, tx.hash());
+    // Queried on the read-only node before it's synced back
+    assert_eq!(
+        get_transaction_status(&read_only_registry, tx_hash).await,
+        Some(L2TransactionStatus::Pending)
+    );
+    assert!(read_only_registry
+        .gw_is_request_in_queue(tx_hash.into())
+        .await
+        .map_err(jsonrpc_core::Error::from)
+        .unwrap());
+    wait_tx_committed(&full_node, &tx_hash, TIMEOUT)
+        .await
+        .unwrap();
+
+    // The signature passes the read-only node but is rejected by the full
+    // node, the error of the full node is returned.
+    let bad_sig_tx = sign_deploy_tx(
+        &read_only_node,
+        &read_only_wallet,
+        bad_sig_from_id,
+        polyjuice_id,
+        18,
+    )
+    .await;
+    let err = submit_l2transaction(&read_only_registry, &bad_sig_tx)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code, ErrorCode::InvalidRequest);
+    assert!(err.message.contains("Unlock error"), "{}", err.message);
+    // Rejected requests aren't tracked
+    assert_eq!(
+        get_transaction_status(&read_only_registry, bad_sig_tx.hash()).await,
+        None
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_forward_ack_and_session_close() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let key_dir = tempfile::tempdir().unwrap();
+
+    // Mock full node
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let full_node_address = local_address();
+    let (key_path, full_node_dial) = node_key(&key_dir, [2u8; 32], &full_node_address);
+    start_p2p_network(
+        Some(full_node_address),
+        vec![],
+        Some(key_path),
+        vec![mock_full_node_protocol(sender)],
+        Default::default(),
+    )
+    .await;
+
+    // Read-only node
+    let read_only_node = TestChain::setup(Script::default()).await;
+    let read_only_address = local_address();
+    let (read_only_registry, read_only_peers) = start_read_only_node(
+        &read_only_node,
+        Some(read_only_address.clone()),
+        full_node_dial,
+    )
+    .await;
+    let mut full_node = tokio::time::timeout(TIMEOUT, receiver.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    let full_node_session = wait_forward_session(&read_only_peers).await;
+
+    // Sessions of other peers aren't used to forward requests
+    let (other_sender, mut other_receiver) = mpsc::unbounded_channel();
+    start_p2p_network(
+        None,
+        vec![read_only_address],
+        None,
+        vec![mock_full_node_protocol(other_sender)],
+        Default::default(),
+    )
+    .await;
+    let mut other_peer = tokio::time::timeout(TIMEOUT, other_receiver.recv())
+        .await
+        .unwrap()
+        .unwrap();
+
+    let wallet = EthWallet::random(read_only_node.rollup_type_hash());
+    let (account_ids, polyjuice_id) = create_accounts(&read_only_node, &[&wallet]).await;
+    let from_id = account_ids[0];
+    let tx = sign_deploy_tx(&read_only_node, &wallet, from_id, polyjuice_id, 18).await;
+
+    // Concurrent submissions of the same tx are forwarded once and share the
+    // acknowledgement.
+    let submissions: Vec<_> = (0..2)
+        .map(|_| {
+            let registry = read_only_registry.clone();
+            let tx = tx.clone();
+            tokio::spawn(async move { submit_l2transaction(&registry, &tx).await })
+        })
+        .collect();
+    assert_eq!(recv_forwarded_tx(&mut full_node.2).await.hash(), tx.hash());
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    let rejected = jsonrpc_core::Error::invalid_params("rejected by the full node");
+    send_ack(&full_node, &tx, Some(rejected.clone())).await;
+    for submission in submissions {
+        let err = submission.await.unwrap().unwrap_err();
+        assert_eq!(err, rejected);
+    }
+    assert_eq!(
+        get_transaction_status(&read_only_registry, tx.hash()).await,
+        None
+    );
+
+    // The acknowledged request is forwarded again
+    let submission = {
+        let registry = read_only_registry.clone();
+        let tx = tx.clone();
+        tokio::spawn(async move { submit_l2transaction(&registry, &tx).await })
+    };
+    assert_eq!(recv_forwarded_tx(&mut full_node.2).await.hash(), tx.hash());
+    send_ack(&full_node, &tx, None).await;
+    assert_eq!(submission.await.unwrap().unwrap(), Some(tx.hash()));
+    assert_eq!(
+        get_transaction_status(&read_only_registry, tx.hash()).await,
+        Some(L2TransactionStatus::Pending)
+    );
+
+    // Waiting requests fail when the session is closed
+    let tx = sign_deploy_tx(&read_only_node, &wallet, from_id, polyjuice_id, 8).await;
+    let submission = {
+        let registry = read_only_registry.clone();
+        let tx = tx.clone();
+        tokio::spawn(async move { submit_l2transaction(&registry, &tx).await })
+    };
+    assert_eq!(recv_forwarded_tx(&mut full_node.2).await.hash(), tx.hash());
+    read_only_peers.disconnect(full_node_session).await.unwrap();
+    let err = tokio::time::timeout(Duration::from_secs(5), submission)
+        .await
+        .expect("fail before the ack timeout")
+        .unwrap()
+        .unwrap_err();
+    assert!(
+        err.message.contains("forward session closed"),
+        "{}",
+        err.message
+    );
+
+    // Nothing is forwarded to the other peer
+    let msg = tokio::time::timeout(Duration::from_millis(100), other_peer.2.try_next()).await;
+    assert!(msg.is_err(), "unexpected forward message");
+}
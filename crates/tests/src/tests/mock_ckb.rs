@@ -0,0 +1,125 @@
+use ckb_types::core::TransactionView;
+use gw_jsonrpc_types::ckb_jsonrpc_types::Status;
+use gw_rpc_client::{
+    error::{get_jsonrpc_error_code, CkbRpcError},
+    indexer_types::{Order, SearchKey},
+};
+use gw_types::{
+    bytes::Bytes,
+    core::{DepType, ScriptHashType},
+    offchain::CellStatus,
+    packed::{CellDep, CellInput, CellOutput, OutPoint, RollupConfig, Script},
+    prelude::*,
+};
+
+use crate::testing_tool::{
+    chain::{ALWAYS_SUCCESS_CODE_HASH, ALWAYS_SUCCESS_PROGRAM},
+    mock_ckb::MockCkb,
+};
+
+const CKB: u64 = 100000000;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mock_ckb_send_and_mine_transaction() {
+    let always_success_lock = Script::new_builder()
+        .code_hash(ALWAYS_SUCCESS_CODE_HASH.pack())
+        .hash_type(ScriptHashType::Data.into())
+        .build();
+    let genesis_tx = TransactionView::new_advanced_builder()
+        .output(
+            CellOutput::new_builder()
+                .capacity((1000 * CKB).pack())
+                .build(),
+        )
+        .output_data(ALWAYS_SUCCESS_PROGRAM.clone().pack())
+        .build();
+    let always_success_dep = CellDep::new_builder()
+        .out_point(OutPoint::new(genesis_tx.hash(), 0))
+        .dep_type(DepType::Code.into())
+        .build();
+
+    let mock_ckb = MockCkb::start(vec![genesis_tx]).unwrap();
+    let rpc_client = mock_ckb.rpc_client(Script::default(), RollupConfig::default());
+    let output = CellOutput::new_builder()
+        .capacity((100 * CKB).pack())
+        .lock(always_success_lock.clone())
+        .build();
+    let input_out_point = mock_ckb.mine_cells(vec![(output, Bytes::new())]).remove(0);
+    assert_eq!(rpc_client.get_tip().await.unwrap().number().unpack(), 1u64);
+
+    let tx = TransactionView::new_advanced_builder()
+        .cell_dep(always_success_dep)
+        .input(CellInput::new(input_out_point.clone(), 0))
+        .output(
+            CellOutput::new_builder()
+                .capacity((99 * CKB).pack())
+                .lock(always_success_lock.clone())
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .build();
+    let tx_hash = rpc_client.send_transaction(&tx.data()).await.unwrap();
+    let status = rpc_client
+        .ckb
+        .get_transaction_status(tx_hash)
+        .await
+        .unwrap();
+    assert_eq!(status, Some(Status::Pending));
+
+    // Double spend in the pool.
+    let double_spend = tx
+        .as_advanced_builder()
+        .set_outputs_data(vec![Bytes::from_static(b"double").pack()])
+        .build();
+    let err = rpc_client.send_transaction(&double_spend.data()).await;
+    let code = get_jsonrpc_error_code(&err.unwrap_err());
+    assert_eq!(code, Some(CkbRpcError::TransactionFailedToResolve as i64));
+
+    mock_ckb.mine_block();
+    let status = rpc_client
+        .ckb
+        .get_transaction_status(tx_hash)
+        .await
+        .unwrap();
+    assert_eq!(status, Some(Status::Committed));
+    let block_number = rpc_client.ckb.get_transaction_block_number(tx_hash);
+    assert_eq!(block_number.await.unwrap(), Some(2));
+    let input = rpc_client
+        .get_cell(input_out_point.clone())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(input.status, CellStatus::Dead);
+
+    let err = rpc_client.send_transaction(&tx.data()).await;
+    let code = get_jsonrpc_error_code(&err.unwrap_err());
+    assert_eq!(
+        code,
+        Some(CkbRpcError::PoolRejectedDuplicatedTransaction as i64)
+    );
+
+    let search_key = SearchKey::with_lock(always_success_lock);
+    let cells = rpc_client
+        .indexer
+        .get_cells(&search_key, &Order::Asc, 10.into(), &None)
+        .await
+        .unwrap();
+    assert_eq!(cells.objects.len(), 1);
+    let cell = cells.objects.into_iter().next().unwrap().info();
+    assert_eq!(cell.out_point, OutPoint::new(tx_hash.pack(), 0));
+
+    let txs = rpc_client
+        .indexer
+        .get_transactions(&search_key, &Order::Asc, 10.into(), &None)
+        .await
+        .unwrap();
+    let tx_hashes: Vec<[u8; 32]> = txs.objects.iter().map(|tx| tx.tx_hash.0).collect();
+    // Created in the mined cells tx, then consumed and created in the tx.
+    assert_eq!(tx_hashes.len(), 3);
+    assert_eq!(&tx_hashes[1..], &[tx_hash, tx_hash]);
+
+    let consumer = rpc_client.get_cell_consumer_tx(&input_out_point).await;
+    assert_eq!(consumer.unwrap().map(|tx| tx.hash()), Some(tx_hash));
+    let consumer = rpc_client.get_cell_consumer_tx(&cell.out_point).await;
+    assert!(consumer.unwrap().is_none());
+}
@@ -1,12 +1,25 @@
+mod block_sync_catch_up;
+mod block_sync_relay;
 mod calc_finalizing_range;
 mod chain;
+mod challenge_audit;
+mod custodian_audit;
 mod deposit_withdrawal;
 mod export_import_block;
+mod fee_sponsorship;
+mod follow_export;
+mod forward;
 mod mem_block_repackage;
 mod mem_pool_ckb_transfer_create_new_recipient_account;
 mod meta_contract_args;
+mod mock_ckb;
+mod multisig_lock;
 mod polyjuice_sender_recover;
 mod restore_mem_block;
 mod restore_mem_pool_pending_withdrawal;
 mod rpc_server;
+mod script_hash_registry;
+mod session_keys;
 mod unlock_withdrawal_to_owner;
+mod watchtower;
+mod webauthn_lock;
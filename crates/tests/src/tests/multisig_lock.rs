@@ -0,0 +1,254 @@
+use gw_common::registry::eth_registry::extract_eth_address_from_multisig_eoa;
+use gw_common::{builtins::ETH_REGISTRY_ACCOUNT_ID, registry_address::RegistryAddress};
+use gw_generator::account_lock_manage::{
+    eip712::{traits::EIP712Encode, types::Withdrawal},
+    multisig::{MultisigConfig, Secp256k1EthMultisig},
+    secp256k1::Secp256k1Eth,
+    LockAlgorithm,
+};
+use gw_generator::error::LockAlgorithmError;
+use gw_types::{
+    bytes::Bytes,
+    core::ScriptHashType,
+    h256::*,
+    packed::{
+        L2Transaction, RawL2Transaction, RawWithdrawalRequest, RollupConfig, Script,
+        WithdrawalRequest, WithdrawalRequestExtra,
+    },
+    prelude::*,
+};
+use gw_utils::RollupContext;
+
+use crate::testing_tool::eth_wallet::EthWallet;
+
+const CHAIN_ID: u64 = 42;
+const ROLLUP_SCRIPT_HASH: [u8; 32] = [1u8; 32];
+
+struct Multisig {
+    members: Vec<EthWallet>,
+    config: MultisigConfig,
+    account_script: Script,
+    registry_address: RegistryAddress,
+}
+
+impl Multisig {
+    fn random(threshold: u8, n: usize) -> Self {
+        let members: Vec<_> = (0..n)
+            .map(|_| EthWallet::random(ROLLUP_SCRIPT_HASH))
+            .collect();
+        let pubkey_hashes = members
+            .iter()
+            .map(|m| m.registry_address.address.clone().try_into().unwrap())
+            .collect();
+        let config = MultisigConfig::new(threshold, pubkey_hashes).unwrap();
+        let account_script = Script::new_builder()
+            .code_hash([2u8; 32].pack())
+            .hash_type(ScriptHashType::Type.into())
+            .args(config.to_lock_args(&ROLLUP_SCRIPT_HASH).pack())
+            .build();
+        let registry_address =
+            RegistryAddress::new(ETH_REGISTRY_ACCOUNT_ID, config.address().to_vec());
+        Multisig {
+            members,
+            config,
+            account_script,
+            registry_address,
+        }
+    }
+
+    fn sign(&self, signers: &[usize], message: H256) -> Bytes {
+        let mut signature = Vec::new();
+        for i in signers {
+            signature.extend_from_slice(&self.members[*i].sign_message(message).unwrap());
+        }
+        signature.into()
+    }
+}
+
+fn ctx() -> RollupContext {
+    RollupContext {
+        rollup_config: RollupConfig::new_builder()
+            .chain_id(CHAIN_ID.pack())
+            .build(),
+        ..Default::default()
+    }
+}
+
+fn receiver_script() -> Script {
+    Script::new_builder()
+        .code_hash([3u8; 32].pack())
+        .args(Bytes::from(ROLLUP_SCRIPT_HASH.to_vec()).pack())
+        .build()
+}
+
+fn tx_message(multisig: &Multisig, raw_tx: &RawL2Transaction) -> H256 {
+    Secp256k1Eth::eip712_signing_message(
+        CHAIN_ID,
+        raw_tx,
+        multisig.registry_address.clone(),
+        receiver_script().hash(),
+    )
+    .unwrap()
+}
+
+fn verify_tx(multisig: &Multisig, signers: &[usize]) -> Result<(), LockAlgorithmError> {
+    let raw_tx = RawL2Transaction::new_builder()
+        .chain_id(CHAIN_ID.pack())
+        .from_id(4u32.pack())
+        .to_id(5u32.pack())
+        .nonce(1u32.pack())
+        .args(Bytes::from_static(b"treasury").pack())
+        .build();
+    let signature = multisig.sign(signers, tx_message(multisig, &raw_tx));
+    let tx = L2Transaction::new_builder()
+        .raw(raw_tx)
+        .signature(signature.pack())
+        .build();
+    Secp256k1EthMultisig::default().verify_tx(
+        &ctx(),
+        multisig.registry_address.clone(),
+        multisig.account_script.clone(),
+        receiver_script(),
+        tx,
+    )
+}
+
+#[test]
+fn test_multisig_lock_args() {
+    let multisig = Multisig::random(2, 3);
+    let lock_args: Bytes = multisig.account_script.args().unpack();
+    assert_eq!(lock_args.len(), 32 + 20 + 2 + 20 * 3);
+    assert_eq!(
+        extract_eth_address_from_multisig_eoa(&lock_args).unwrap(),
+        multisig.config.address().to_vec()
+    );
+    assert_eq!(
+        MultisigConfig::from_lock_args(&lock_args).unwrap(),
+        multisig.config
+    );
+
+    // address must match the config
+    let mut tampered = lock_args.to_vec();
+    tampered[52] = 1;
+    assert!(extract_eth_address_from_multisig_eoa(&tampered).is_err());
+
+    // invalid threshold
+    assert!(MultisigConfig::new(0, vec![[0u8; 20]]).is_err());
+    assert!(MultisigConfig::new(2, vec![[0u8; 20]]).is_err());
+    assert!(MultisigConfig::new(1, vec![[0u8; 20]; 17]).is_err());
+}
+
+#[test]
+fn test_multisig_verify_tx() {
+    let multisig = Multisig::random(2, 3);
+    verify_tx(&multisig, &[0, 1]).expect("signed by 0 and 1");
+    verify_tx(&multisig, &[2, 0]).expect("signed by 2 and 0");
+
+    // threshold not met
+    let err = verify_tx(&multisig, &[1]).unwrap_err();
+    assert!(matches!(err, LockAlgorithmError::InvalidSignature(_)));
+    // duplicated signer
+    let err = verify_tx(&multisig, &[1, 1]).unwrap_err();
+    assert!(matches!(err, LockAlgorithmError::InvalidSignature(_)));
+    // too many signatures
+    let err = verify_tx(&multisig, &[0, 1, 2]).unwrap_err();
+    assert!(matches!(err, LockAlgorithmError::InvalidSignature(_)));
+}
+
+#[test]
+fn test_multisig_non_member() {
+    let multisig = Multisig::random(2, 3);
+    let other = Multisig::random(1, 1);
+    let raw_tx = RawL2Transaction::new_builder()
+        .chain_id(CHAIN_ID.pack())
+        .from_id(4u32.pack())
+        .to_id(5u32.pack())
+        .build();
+    let message = tx_message(&multisig, &raw_tx);
+    let mut signature = multisig.sign(&[0], message).to_vec();
+    signature.extend_from_slice(&other.sign(&[0], message));
+    let tx = L2Transaction::new_builder()
+        .raw(raw_tx)
+        .signature(signature.pack())
+        .build();
+    let err = Secp256k1EthMultisig::default()
+        .verify_tx(
+            &ctx(),
+            multisig.registry_address.clone(),
+            multisig.account_script.clone(),
+            receiver_script(),
+            tx,
+        )
+        .unwrap_err();
+    assert!(matches!(err, LockAlgorithmError::InvalidSignature(_)));
+}
+
+#[test]
+fn test_multisig_combine_signatures() {
+    let multisig = Multisig::random(2, 3);
+    let message = [7u8; 32];
+    let outsider = EthWallet::random(ROLLUP_SCRIPT_HASH);
+    let sig = |i: usize| multisig.members[i].sign_message(message).unwrap();
+
+    // non members and duplicated signers are skipped
+    let signatures = vec![
+        outsider.sign_message(message).unwrap(),
+        sig(2),
+        sig(2),
+        sig(0),
+    ];
+    let combined = multisig
+        .config
+        .combine_signatures(message, &signatures)
+        .unwrap();
+    let mut expected = sig(2).to_vec();
+    expected.extend_from_slice(&sig(0));
+    assert_eq!(combined.as_ref(), expected.as_slice());
+
+    let err = multisig
+        .config
+        .combine_signatures(message, &[sig(1), sig(1)])
+        .unwrap_err();
+    assert!(matches!(err, LockAlgorithmError::InvalidSignature(_)));
+}
+
+#[test]
+fn test_multisig_verify_withdrawal() {
+    let multisig = Multisig::random(2, 3);
+    let raw = RawWithdrawalRequest::new_builder()
+        .chain_id(CHAIN_ID.pack())
+        .nonce(1u32.pack())
+        .capacity((1000u64 * 10u64.pow(8)).pack())
+        .account_script_hash(multisig.account_script.hash().pack())
+        .owner_lock_hash(multisig.account_script.hash().pack())
+        .registry_id(ETH_REGISTRY_ACCOUNT_ID.pack())
+        .build();
+    let message = Withdrawal::from_raw(
+        raw.clone(),
+        multisig.account_script.clone(),
+        multisig.registry_address.clone(),
+    )
+    .expect("typed withdrawal")
+    .eip712_message(Secp256k1Eth::domain_with_chain_id(CHAIN_ID).hash_struct());
+    let verify = |signers: &[usize]| {
+        let withdrawal = WithdrawalRequestExtra::new_builder()
+            .request(
+                WithdrawalRequest::new_builder()
+                    .raw(raw.clone())
+                    .signature(multisig.sign(signers, message).pack())
+                    .build(),
+            )
+            .owner_lock(multisig.account_script.clone())
+            .build();
+        Secp256k1EthMultisig::default().verify_withdrawal(
+            &ctx(),
+            multisig.account_script.clone(),
+            &withdrawal,
+            multisig.registry_address.clone(),
+        )
+    };
+
+    verify(&[1, 2]).expect("verify withdrawal");
+    let err = verify(&[2]).unwrap_err();
+    assert!(matches!(err, LockAlgorithmError::InvalidSignature(_)));
+}
@@ -2,5 +2,6 @@ pub(crate) const BLOCK_MAX_CYCLES_LIMIT: u64 = 300_0000;
 
 pub mod execute_l2transaction;
 pub mod execute_raw_l2transaction;
+pub mod schedule_backend_fork;
 pub mod submit_l2transaction;
 pub mod submit_withdrawal_request;
@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use ckb_fixed_hash::H160;
+use gw_builtin_binaries::{file_checksum, Resource};
+use gw_config::{BackendConfig, BackendForkConfig, BackendType};
+use gw_generator::backend_manage::backend_fork_signing_message;
+use gw_rpc_server::registry::Registry;
+use gw_store::traits::chain_store::ChainStore;
+use gw_types::{packed::ScheduledBackendFork, prelude::*};
+
+use crate::testing_tool::{
+    chain::{TestChain, META_GENERATOR_PATH, META_VALIDATOR_SCRIPT_TYPE_HASH},
+    eth_wallet::EthWallet,
+    rpc_server::RPCServer,
+};
+
+async fn create_registry(chain: &TestChain, admin: &EthWallet) -> Arc<Registry> {
+    let mut args =
+        RPCServer::default_registry_args(&chain.inner, chain.rollup_type_script.clone(), None);
+    args.server_config.backend_fork_admins =
+        vec![H160::from_slice(&admin.reg_address().address).unwrap()];
+    Registry::create(args).await.unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_reload_scheduled_backend_fork() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let chain = TestChain::setup(Default::default()).await;
+    let admin = EthWallet::random(chain.rollup_type_hash());
+
+    let registry = create_registry(&chain, &admin).await;
+    let config = BackendForkConfig {
+        fork_height: 100,
+        backends: vec![BackendConfig {
+            generator: Resource::file_system(META_GENERATOR_PATH.into()),
+            generator_checksum: file_checksum(&META_GENERATOR_PATH).unwrap().into(),
+            validator_script_type_hash: META_VALIDATOR_SCRIPT_TYPE_HASH.into(),
+            backend_type: BackendType::Meta,
+        }],
+        sudt_proxy: None,
+    };
+    let sign = |wallet: &EthWallet, config: &BackendForkConfig| {
+        let message = backend_fork_signing_message(&chain.rollup_type_hash(), config);
+        let signature = wallet.inner.sign_message(message).unwrap();
+        ScheduledBackendFork::new_builder()
+            .config(serde_json::to_vec(config).unwrap().pack())
+            .signature(signature[..].pack())
+            .build()
+    };
+
+    // Not signed by an admin
+    let other = EthWallet::random(chain.rollup_type_hash());
+    let scheduler = registry.backend_fork_scheduler();
+    let fork = sign(&other, &config);
+    let err = tokio::task::spawn_blocking(move || scheduler.schedule(fork))
+        .await
+        .unwrap()
+        .unwrap_err();
+    assert!(err.to_string().contains("is not a backend fork admin"));
+    assert!(chain.store().get_scheduled_backend_forks().is_empty());
+
+    let scheduler = registry.backend_fork_scheduler();
+    let fork = sign(&admin, &config);
+    let scheduled = tokio::task::spawn_blocking(move || scheduler.schedule(fork))
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(scheduled);
+    assert_eq!(chain.store().get_scheduled_backend_forks().len(), 1);
+
+    // Scheduling the same fork again is a no-op
+    let scheduler = registry.backend_fork_scheduler();
+    let fork = sign(&admin, &config);
+    let scheduled = tokio::task::spawn_blocking(move || scheduler.schedule(fork))
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!scheduled);
+    assert_eq!(chain.store().get_scheduled_backend_forks().len(), 1);
+
+    // Restart with generators built from the config only
+    let registry = create_registry(&chain, &admin).await;
+    assert!(registry
+        .backend_fork_scheduler()
+        .generator()
+        .backend_manage()
+        .contains_backend_fork(&config));
+}
@@ -1,18 +1,22 @@
 use std::time::Duration;
 
+use futures::future::join_all;
 use gw_common::{
     builtins::{CKB_SUDT_ACCOUNT_ID, ETH_REGISTRY_ACCOUNT_ID},
     state::State,
 };
+use gw_config::SignatureVerifierConfig;
+use gw_mem_pool::pool::VerifiedSignature;
 use gw_smt::blake2b::new_blake2b;
 use gw_store::state::traits::JournalDB;
 use gw_types::prelude::*;
 use gw_types::{
     bytes::Bytes,
     h256::*,
-    packed::{Fee, RawL2Transaction, SUDTArgs, SUDTTransfer, Script},
+    packed::{Fee, L2Transaction, RawL2Transaction, SUDTArgs, SUDTTransfer, Script},
     U256,
 };
+use jsonrpc_core::ErrorCode;
 
 use crate::testing_tool::{
     chain::TestChain,
@@ -512,3 +516,236 @@ async fn test_invalid_polyjuice_tx_from_id_zero() {
         "unrecoverable txs should not be committed"
     );
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_reject_invalid_signature() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let chain = TestChain::setup(rollup_type_script).await;
+    let rpc_server = RPCServer::build(&chain, None).await.unwrap();
+
+    let mem_pool_state = chain.mem_pool_state().await;
+    let mut state = mem_pool_state.load_state_db();
+
+    let sender_wallet = EthWallet::random(chain.rollup_type_hash());
+    let sender_id = sender_wallet
+        .create_account(&mut state, 1000000u128.into())
+        .unwrap();
+    let polyjuice_account = PolyjuiceAccount::create(chain.rollup_type_hash(), &mut state).unwrap();
+
+    let deploy_args = SudtErc20ArgsBuilder::deploy(CKB_SUDT_ACCOUNT_ID, 18).finish();
+    let raw_tx = RawL2Transaction::new_builder()
+        .chain_id(chain.chain_id().pack())
+        .from_id(sender_id.pack())
+        .to_id(polyjuice_account.id.pack())
+        .nonce(0u32.pack())
+        .args(deploy_args.pack())
+        .build();
+
+    // Signed by another wallet
+    let other_wallet = EthWallet::random(chain.rollup_type_hash());
+    let bad_sig_tx = other_wallet
+        .sign_polyjuice_tx(&state, raw_tx.clone())
+        .unwrap();
+    let deploy_tx = sender_wallet.sign_polyjuice_tx(&state, raw_tx).unwrap();
+
+    state.finalise().unwrap();
+    mem_pool_state.store_state_db(state);
+
+    let err = rpc_server
+        .submit_l2transaction(&bad_sig_tx)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code, ErrorCode::InvalidRequest);
+    assert!(err.message.contains("Unlock error"), "{}", err.message);
+    assert!(!rpc_server
+        .is_request_in_queue(bad_sig_tx.hash())
+        .await
+        .unwrap());
+
+    let deploy_tx_hash = rpc_server
+        .submit_l2transaction(&deploy_tx)
+        .await
+        .unwrap()
+        .unwrap();
+    wait_tx_committed(&chain, &deploy_tx_hash, Duration::from_secs(30))
+        .await
+        .unwrap();
+}
+
+/// Create `count` senders and sign a deploy tx for each of them, the odd ones
+/// are signed by another wallet.
+async fn sign_deploy_txs(chain: &TestChain, count: usize) -> Vec<L2Transaction> {
+    let mem_pool_state = chain.mem_pool_state().await;
+    let mut state = mem_pool_state.load_state_db();
+    let polyjuice_account = PolyjuiceAccount::create(chain.rollup_type_hash(), &mut state).unwrap();
+    let other_wallet = EthWallet::random(chain.rollup_type_hash());
+
+    let deploy_args = SudtErc20ArgsBuilder::deploy(CKB_SUDT_ACCOUNT_ID, 18).finish();
+    let txs = (0..count)
+        .map(|i| {
+            let sender_wallet = EthWallet::random(chain.rollup_type_hash());
+            let sender_id = sender_wallet
+                .create_account(&mut state, 1000000u128.into())
+                .unwrap();
+            let raw_tx = RawL2Transaction::new_builder()
+                .chain_id(chain.chain_id().pack())
+                .from_id(sender_id.pack())
+                .to_id(polyjuice_account.id.pack())
+                .nonce(0u32.pack())
+                .args(deploy_args.pack())
+                .build();
+            let signer = if i % 2 == 0 {
+                &sender_wallet
+            } else {
+                &other_wallet
+            };
+            signer.sign_polyjuice_tx(&state, raw_tx).unwrap()
+        })
+        .collect();
+
+    state.finalise().unwrap();
+    mem_pool_state.store_state_db(state);
+    txs
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_batched_signature_verification() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let chain = TestChain::setup(rollup_type_script.clone()).await;
+    let mut registry_args =
+        RPCServer::default_registry_args(&chain.inner, rollup_type_script, None);
+    registry_args.mem_pool_config.signature_verifier = SignatureVerifierConfig {
+        workers: 2,
+        max_batch_size: 256,
+        queue_size: 256,
+    };
+    let rpc_server = RPCServer::build_from_registry_args(registry_args)
+        .await
+        .unwrap();
+
+    let txs = sign_deploy_txs(&chain, 32).await;
+    let results = join_all(txs.iter().map(|tx| rpc_server.submit_l2transaction(tx))).await;
+
+    // Every tx in a batch gets its own result.
+    for (i, (tx, result)) in txs.iter().zip(results).enumerate() {
+        if i % 2 == 0 {
+            assert_eq!(result.unwrap(), Some(tx.hash()));
+        } else {
+            let err = result.unwrap_err();
+            assert_eq!(err.code, ErrorCode::InvalidRequest);
+            assert!(err.message.contains("Unlock error"), "{}", err.message);
+            assert!(!rpc_server.is_request_in_queue(tx.hash()).await.unwrap());
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_signature_verifier_busy() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let chain = TestChain::setup(rollup_type_script.clone()).await;
+    let mut registry_args =
+        RPCServer::default_registry_args(&chain.inner, rollup_type_script, None);
+    registry_args.mem_pool_config.signature_verifier = SignatureVerifierConfig {
+        workers: 1,
+        max_batch_size: 1,
+        queue_size: 1,
+    };
+    let rpc_server = RPCServer::build_from_registry_args(registry_args)
+        .await
+        .unwrap();
+
+    let txs = sign_deploy_txs(&chain, 64).await;
+    let results = join_all(txs.iter().map(|tx| rpc_server.submit_l2transaction(tx))).await;
+
+    let mut busy = 0;
+    for (i, (tx, result)) in txs.iter().zip(results).enumerate() {
+        match result {
+            Ok(hash) => {
+                assert_eq!(i % 2, 0);
+                assert_eq!(hash, Some(tx.hash()));
+            }
+            Err(err) if err.code == ErrorCode::ServerError(-32006) => {
+                assert_eq!(err.message, "signature verifier busy");
+                busy += 1;
+            }
+            Err(err) => {
+                assert_eq!(i % 2, 1);
+                assert_eq!(err.code, ErrorCode::InvalidRequest);
+                assert!(err.message.contains("Unlock error"), "{}", err.message);
+            }
+        }
+    }
+    assert!(busy > 0, "flood should be rejected as busy");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_reverify_stale_signature_on_push() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let chain = TestChain::setup(rollup_type_script).await;
+
+    let mem_pool_state = chain.mem_pool_state().await;
+    let mut state = mem_pool_state.load_state_db();
+
+    let sender_wallet = EthWallet::random(chain.rollup_type_hash());
+    let sender_id = sender_wallet
+        .create_account(&mut state, 1000000u128.into())
+        .unwrap();
+    let polyjuice_account = PolyjuiceAccount::create(chain.rollup_type_hash(), &mut state).unwrap();
+
+    let deploy_args = SudtErc20ArgsBuilder::deploy(CKB_SUDT_ACCOUNT_ID, 18).finish();
+    let raw_tx = RawL2Transaction::new_builder()
+        .chain_id(chain.chain_id().pack())
+        .from_id(sender_id.pack())
+        .to_id(polyjuice_account.id.pack())
+        .nonce(0u32.pack())
+        .args(deploy_args.pack())
+        .build();
+    let other_wallet = EthWallet::random(chain.rollup_type_hash());
+    let bad_sig_tx = other_wallet
+        .sign_polyjuice_tx(&state, raw_tx.clone())
+        .unwrap();
+    let deploy_tx = sender_wallet.sign_polyjuice_tx(&state, raw_tx).unwrap();
+
+    state.finalise().unwrap();
+    mem_pool_state.store_state_db(state);
+
+    let mut mem_pool = chain.mem_pool().await;
+    let block_number = mem_pool.mem_block().block_info().number().unpack();
+
+    // Verified against another nonce or block, the signature is checked again.
+    for stale in [
+        VerifiedSignature {
+            sender_nonce: 1,
+            block_number,
+            session_key_spend: None,
+        },
+        VerifiedSignature {
+            sender_nonce: 0,
+            block_number: block_number + 1,
+            session_key_spend: None,
+        },
+    ] {
+        let err = mem_pool
+            .push_verified_transaction(bad_sig_tx.clone(), stale)
+            .unwrap_err();
+        assert!(err.to_string().contains("Unlock error"), "{}", err);
+    }
+
+    let verified = VerifiedSignature {
+        sender_nonce: 0,
+        block_number,
+        session_key_spend: None,
+    };
+    mem_pool
+        .push_verified_transaction(deploy_tx.clone(), verified)
+        .unwrap();
+    assert!(mem_pool.mem_block().txs_set().contains(&deploy_tx.hash()));
+}
@@ -365,6 +365,14 @@ async fn test_block_max_cycles_limit() -> anyhow::Result<()> {
                 sys_bn_mul_cycles: MAX_CYCLES_LIMIT,
                 sys_bn_fixed_pairing_cycles: MAX_CYCLES_LIMIT,
                 sys_bn_per_pairing_cycles: MAX_CYCLES_LIMIT,
+                sys_bls12_381_g1_add_cycles: MAX_CYCLES_LIMIT,
+                sys_bls12_381_g1_msm_per_pair_cycles: MAX_CYCLES_LIMIT,
+                sys_bls12_381_g2_add_cycles: MAX_CYCLES_LIMIT,
+                sys_bls12_381_g2_msm_per_pair_cycles: MAX_CYCLES_LIMIT,
+                sys_bls12_381_fixed_pairing_cycles: MAX_CYCLES_LIMIT,
+                sys_bls12_381_per_pairing_cycles: MAX_CYCLES_LIMIT,
+                sys_bls12_381_map_fp_to_g1_cycles: MAX_CYCLES_LIMIT,
+                sys_bls12_381_map_fp2_to_g2_cycles: MAX_CYCLES_LIMIT,
                 sys_revert_cycles: MAX_CYCLES_LIMIT,
                 sys_snapshot_cycles: MAX_CYCLES_LIMIT,
             },
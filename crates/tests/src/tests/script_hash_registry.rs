@@ -0,0 +1,126 @@
+#![allow(clippy::mutable_key_type)]
+
+use crate::testing_tool::chain::{
+    apply_block_result, construct_block, into_deposit_info_cell, setup_chain_with_config,
+    ALWAYS_SUCCESS_CODE_HASH, DEFAULT_FINALITY_BLOCKS, META_VALIDATOR_SCRIPT_TYPE_HASH,
+    TEST_CHAIN_ID,
+};
+
+use gw_common::{
+    builtins::{ETH_REGISTRY_ACCOUNT_ID, SCRIPT_HASH_REGISTRY_ACCOUNT_ID},
+    registry::context::RegistryContext,
+    registry_address::RegistryAddress,
+    state::State,
+};
+use gw_types::{
+    core::{AllowedContractType, AllowedEoaType, ScriptHashType},
+    packed::{AllowedTypeHash, DepositInfoVec, DepositRequest, RollupConfig, Script},
+    prelude::*,
+};
+
+use std::collections::HashSet;
+
+const SCRIPT_HASH_REGISTRY_TYPE_HASH: [u8; 32] = [7u8; 32];
+
+fn rollup_config(script_hash_registry: bool) -> RollupConfig {
+    let mut allowed_contract_type_hashes = vec![AllowedTypeHash::new(
+        AllowedContractType::Meta,
+        META_VALIDATOR_SCRIPT_TYPE_HASH,
+    )];
+    if script_hash_registry {
+        allowed_contract_type_hashes.push(AllowedTypeHash::new(
+            AllowedContractType::ScriptHashAddrReg,
+            SCRIPT_HASH_REGISTRY_TYPE_HASH,
+        ));
+    }
+    RollupConfig::new_builder()
+        .allowed_eoa_type_hashes(
+            vec![AllowedTypeHash::new(
+                AllowedEoaType::Eth,
+                *ALWAYS_SUCCESS_CODE_HASH,
+            )]
+            .pack(),
+        )
+        .allowed_contract_type_hashes(allowed_contract_type_hashes.pack())
+        .finality_blocks(DEFAULT_FINALITY_BLOCKS.pack())
+        .chain_id(TEST_CHAIN_ID.pack())
+        .build()
+}
+
+fn user_script(rollup_script_hash: &[u8]) -> Script {
+    Script::new_builder()
+        .code_hash(ALWAYS_SUCCESS_CODE_HASH.pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args({
+            let mut args = rollup_script_hash.to_vec();
+            args.extend(&[42u8; 20]);
+            args.pack()
+        })
+        .build()
+}
+
+#[test]
+fn test_extract_script_hash_address_from_deposit() {
+    let user_script = user_script(&[1u8; 32]);
+
+    let registry_ctx = RegistryContext::from_rollup_config(&rollup_config(true));
+    let addr = registry_ctx
+        .extract_registry_address_from_deposit(SCRIPT_HASH_REGISTRY_ACCOUNT_ID, &user_script)
+        .unwrap();
+    assert_eq!(
+        addr,
+        RegistryAddress::new(SCRIPT_HASH_REGISTRY_ACCOUNT_ID, user_script.hash().to_vec())
+    );
+    // ETH registry still works
+    let addr = registry_ctx
+        .extract_registry_address_from_deposit(ETH_REGISTRY_ACCOUNT_ID, &user_script)
+        .unwrap();
+    assert_eq!(addr.address, vec![42u8; 20]);
+
+    // the script hash registry isn't allowed
+    let registry_ctx = RegistryContext::from_rollup_config(&rollup_config(false));
+    assert!(registry_ctx
+        .extract_registry_address_from_deposit(SCRIPT_HASH_REGISTRY_ACCOUNT_ID, &user_script)
+        .is_err());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_refuse_deposit_to_script_hash_registry() {
+    let rollup_type_script = Script::default();
+    let rollup_script_hash = rollup_type_script.hash();
+    let mut chain = setup_chain_with_config(rollup_type_script, rollup_config(false)).await;
+    chain.notify_new_tip().await.unwrap();
+
+    // The builtin contracts can't handle script hash addresses yet, so the
+    // block producer doesn't package the deposit.
+    let capacity = 600_00000000u64;
+    let user_script = user_script(&rollup_script_hash);
+    let user_script_hash = user_script.hash();
+    let deposit = DepositRequest::new_builder()
+        .capacity(capacity.pack())
+        .script(user_script)
+        .registry_id(SCRIPT_HASH_REGISTRY_ACCOUNT_ID.pack())
+        .build();
+    let deposit_info_vec = DepositInfoVec::new_builder()
+        .push(into_deposit_info_cell(chain.generator().rollup_context(), deposit).pack())
+        .build();
+    let block_result = {
+        let mem_pool = chain.mem_pool().as_ref().unwrap();
+        let mut mem_pool = mem_pool.lock().await;
+        construct_block(&chain, &mut mem_pool, deposit_info_vec)
+            .await
+            .unwrap()
+    };
+    assert!(block_result.deposit_cells.is_empty());
+    apply_block_result(&mut chain, block_result, Default::default(), HashSet::new())
+        .await
+        .unwrap();
+
+    let mem_pool = chain.mem_pool().as_ref().unwrap().lock().await;
+    let tree = mem_pool.mem_pool_state().load_state_db();
+    assert_eq!(tree.get_account_count().unwrap(), 3, "3 builtin accounts");
+    assert!(tree
+        .get_account_id_by_script_hash(&user_script_hash)
+        .unwrap()
+        .is_none());
+}
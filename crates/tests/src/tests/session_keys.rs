@@ -0,0 +1,291 @@
+use std::sync::Arc;
+
+use gw_common::{
+    blake2b::new_blake2b, builtins::RESERVED_ACCOUNT_ID, registry_address::RegistryAddress,
+    state::State,
+};
+use gw_generator::{
+    account_lock_manage::{secp256k1::Secp256k1Eth, AccountLockManage},
+    error::{LockAlgorithmError, TransactionValidateError},
+    session_key::{
+        get_session_key_spent, session_key_slot_key, session_key_spent_key, SessionKeySpend,
+    },
+    Generator,
+};
+use gw_traits::CodeStore;
+use gw_types::{
+    h256::*,
+    packed::{Byte20, L2Transaction, RawL2Transaction, SessionKey},
+    prelude::*,
+    U256,
+};
+use gw_utils::RollupContext;
+
+use crate::testing_tool::{
+    chain::{build_backend_manage, TestChain, ETH_ACCOUNT_LOCK_CODE_HASH},
+    eth_wallet::EthWallet,
+    polyjuice::{PolyjuiceAccount, PolyjuiceArgsBuilder},
+};
+
+const SPENDING_CAP: u128 = 100_000;
+const EXPIRY_BLOCK: u64 = 100;
+
+fn session_keys_generator(chain: &TestChain) -> Generator {
+    let mut rollup_context: RollupContext = chain.inner.generator().rollup_context().clone();
+    rollup_context.fork_config.enable_session_keys = Some(0);
+    let mut account_lock_manage = AccountLockManage::default();
+    account_lock_manage.register_lock_algorithm(
+        *ETH_ACCOUNT_LOCK_CODE_HASH,
+        Arc::new(Secp256k1Eth::default()),
+    );
+    let backend_manage = build_backend_manage(&rollup_context.rollup_config);
+    Generator::new(
+        backend_manage,
+        account_lock_manage,
+        rollup_context,
+        Default::default(),
+    )
+}
+
+// Same as RegisterSessionKey of the Meta contract
+fn register_session_key(
+    state: &mut (impl State + CodeStore),
+    owner_id: u32,
+    slot: u8,
+    session_key: &SessionKey,
+) {
+    let data = session_key.as_bytes();
+    let mut data_hash = [0u8; 32];
+    let mut hasher = new_blake2b();
+    hasher.update(&data);
+    hasher.finalize(&mut data_hash);
+    state.insert_data(data_hash, data);
+    state
+        .update_value(
+            RESERVED_ACCOUNT_ID,
+            &session_key_slot_key(owner_id, slot),
+            data_hash,
+        )
+        .unwrap();
+    state
+        .update_value(
+            RESERVED_ACCOUNT_ID,
+            &session_key_spent_key(owner_id, session_key.pubkey_hash().as_slice()),
+            H256::zero(),
+        )
+        .unwrap();
+}
+
+fn sign_eip712(
+    wallet: &EthWallet,
+    sender_address: &RegistryAddress,
+    receiver_script_hash: H256,
+    raw_tx: RawL2Transaction,
+) -> L2Transaction {
+    let message = Secp256k1Eth::eip712_signing_message(
+        raw_tx.chain_id().unpack(),
+        &raw_tx,
+        sender_address.clone(),
+        receiver_script_hash,
+    )
+    .unwrap();
+    let signature = wallet.sign_message(message).unwrap();
+    L2Transaction::new_builder()
+        .raw(raw_tx)
+        .signature(signature.pack())
+        .build()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_session_key_signed_tx() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let chain = TestChain::setup(Default::default()).await;
+    let generator = session_keys_generator(&chain);
+    let mem_pool_state = chain.mem_pool_state().await;
+    let mut state = mem_pool_state.load_state_db();
+
+    let owner = EthWallet::random(chain.rollup_type_hash());
+    let owner_id = owner
+        .create_account(&mut state, 1_000_000u128.into())
+        .unwrap();
+    let polyjuice = PolyjuiceAccount::create(chain.rollup_type_hash(), &mut state).unwrap();
+    let polyjuice_script_hash = state.get_script_hash(polyjuice.id).unwrap();
+
+    let session_wallet = EthWallet::random(chain.rollup_type_hash());
+    let session_key = SessionKey::new_builder()
+        .pubkey_hash(Byte20::from_slice(&session_wallet.reg_address().address).unwrap())
+        .expiry_block(EXPIRY_BLOCK.pack())
+        .allowed_targets(vec![polyjuice_script_hash.pack()].pack())
+        .spending_cap(SPENDING_CAP.pack())
+        .build();
+    register_session_key(&mut state, owner_id, 3, &session_key);
+
+    let raw_tx = |args: Vec<u8>| {
+        RawL2Transaction::new_builder()
+            .chain_id(chain.chain_id().pack())
+            .from_id(owner_id.pack())
+            .to_id(polyjuice.id.pack())
+            .nonce(0u32.pack())
+            .args(args.pack())
+            .build()
+    };
+    let deploy_args = |gas_limit: u64| {
+        PolyjuiceArgsBuilder::default()
+            .create(true)
+            .gas_limit(gas_limit)
+            .gas_price(1)
+            .data(b"POLYJUICEcontract".to_vec())
+            .finish()
+    };
+    let check = |tx: &L2Transaction, block_number| {
+        generator.check_transaction_signature(&state, tx, block_number)
+    };
+    let session_key_err =
+        |result: Result<Option<SessionKeySpend>, TransactionValidateError>| match result {
+            Err(TransactionValidateError::Unlock(LockAlgorithmError::SessionKey(reason))) => reason,
+            other => panic!("unexpected result {:?}", other),
+        };
+
+    // Signed by the session key within its limits
+    let tx = session_wallet
+        .sign_polyjuice_tx(&state, raw_tx(deploy_args(21000)))
+        .unwrap();
+    let spend = check(&tx, EXPIRY_BLOCK - 1).expect("signed by the session key");
+    assert_eq!(
+        spend,
+        Some(SessionKeySpend {
+            owner_id,
+            pubkey_hash: session_wallet
+                .reg_address()
+                .address
+                .as_slice()
+                .try_into()
+                .unwrap(),
+            cost: U256::from(21000u64),
+        })
+    );
+
+    // Signed by the owner still works
+    let tx = owner
+        .sign_polyjuice_tx(&state, raw_tx(deploy_args(21000)))
+        .unwrap();
+    assert_eq!(check(&tx, EXPIRY_BLOCK).expect("signed by the owner"), None);
+
+    // Expired
+    let tx = session_wallet
+        .sign_polyjuice_tx(&state, raw_tx(deploy_args(21000)))
+        .unwrap();
+    let reason = session_key_err(check(&tx, EXPIRY_BLOCK));
+    assert!(reason.contains("expired"), "{}", reason);
+
+    // Exceeds the spending cap
+    let gas_limit = SPENDING_CAP as u64 + 1;
+    let tx = session_wallet
+        .sign_polyjuice_tx(&state, raw_tx(deploy_args(gas_limit)))
+        .unwrap();
+    let reason = session_key_err(check(&tx, 1));
+    assert!(reason.contains("spending cap"), "{}", reason);
+
+    // The cost of malformed polyjuice args can't be computed
+    let tx = sign_eip712(
+        &session_wallet,
+        owner.reg_address(),
+        polyjuice_script_hash,
+        raw_tx(b"not polyjuice args".to_vec()),
+    );
+    let reason = session_key_err(check(&tx, 1));
+    assert!(reason.contains("can't compute the cost"), "{}", reason);
+
+    // Revoked
+    state
+        .update_value(
+            RESERVED_ACCOUNT_ID,
+            &session_key_slot_key(owner_id, 3),
+            H256::zero(),
+        )
+        .unwrap();
+    let tx = session_wallet
+        .sign_polyjuice_tx(&state, raw_tx(deploy_args(21000)))
+        .unwrap();
+    let err = generator
+        .check_transaction_signature(&state, &tx, 1)
+        .unwrap_err();
+    assert!(matches!(err, TransactionValidateError::Unlock(_)));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_session_key_cumulative_spending_cap() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let chain = TestChain::setup(Default::default()).await;
+    let generator = session_keys_generator(&chain);
+    let mem_pool_state = chain.mem_pool_state().await;
+    let mut state = mem_pool_state.load_state_db();
+
+    let owner = EthWallet::random(chain.rollup_type_hash());
+    let owner_id = owner
+        .create_account(&mut state, 1_000_000u128.into())
+        .unwrap();
+    let polyjuice = PolyjuiceAccount::create(chain.rollup_type_hash(), &mut state).unwrap();
+    let polyjuice_script_hash = state.get_script_hash(polyjuice.id).unwrap();
+
+    let session_wallet = EthWallet::random(chain.rollup_type_hash());
+    let session_key = SessionKey::new_builder()
+        .pubkey_hash(Byte20::from_slice(&session_wallet.reg_address().address).unwrap())
+        .expiry_block(EXPIRY_BLOCK.pack())
+        .allowed_targets(vec![polyjuice_script_hash.pack()].pack())
+        .spending_cap(SPENDING_CAP.pack())
+        .build();
+    register_session_key(&mut state, owner_id, 0, &session_key);
+
+    // Each tx costs 60% of the spending cap
+    let gas_limit = (SPENDING_CAP * 6 / 10) as u64;
+    let sign_tx = |state: &_, nonce: u32| {
+        let args = PolyjuiceArgsBuilder::default()
+            .create(true)
+            .gas_limit(gas_limit)
+            .gas_price(1)
+            .data(b"POLYJUICEcontract".to_vec())
+            .finish();
+        let raw_tx = RawL2Transaction::new_builder()
+            .chain_id(chain.chain_id().pack())
+            .from_id(owner_id.pack())
+            .to_id(polyjuice.id.pack())
+            .nonce(nonce.pack())
+            .args(args.pack())
+            .build();
+        session_wallet.sign_polyjuice_tx(state, raw_tx).unwrap()
+    };
+
+    let tx = sign_tx(&state, 0);
+    let spend = generator
+        .check_transaction_signature(&state, &tx, 1)
+        .expect("within the spending cap")
+        .expect("signed by the session key");
+    spend.record(&mut state).unwrap();
+    state.set_nonce(owner_id, 1).unwrap();
+    assert_eq!(
+        get_session_key_spent(&state, owner_id, &session_key).unwrap(),
+        U256::from(gas_limit)
+    );
+
+    // Within the cap alone, but not together with the first tx
+    let tx = sign_tx(&state, 1);
+    let err = generator
+        .check_transaction_signature(&state, &tx, 1)
+        .unwrap_err();
+    match err {
+        TransactionValidateError::Unlock(LockAlgorithmError::SessionKey(reason)) => {
+            assert!(reason.contains("spending cap"), "{}", reason)
+        }
+        err => panic!("unexpected error {:?}", err),
+    }
+
+    // Registering the session key again resets its spent
+    register_session_key(&mut state, owner_id, 0, &session_key);
+    let spend = generator
+        .check_transaction_signature(&state, &tx, 1)
+        .expect("spent is reset");
+    assert!(spend.is_some());
+}
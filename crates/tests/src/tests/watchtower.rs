@@ -0,0 +1,237 @@
+use std::{collections::HashMap, sync::Arc};
+
+use gw_block_producer::{
+    challenger::{Challenger, ChallengerNewArgs},
+    cleaner::Cleaner,
+    produce_block::ProduceBlockResult,
+    types::ChainEvent,
+};
+use gw_chain::chain::{Chain, L1Action, L1ActionContext, SyncEvent, SyncParam};
+use gw_challenge::offchain::OffChainValidatorContext;
+use gw_config::{BlockProducerConfig, ChallengerConfig};
+use gw_types::{
+    bytes::Bytes,
+    core::{ScriptHashType, Status},
+    offchain::global_state_from_slice,
+    packed::{
+        Block, CellOutput, DepositInfoVec, DepositRequest, RawWithdrawalRequest, Script,
+        WithdrawalRequest, WithdrawalRequestExtra,
+    },
+    prelude::*,
+};
+use tokio::sync::Mutex;
+
+use crate::testing_tool::{
+    bad_block::generate_bad_block_using_first_withdrawal,
+    chain::{
+        build_sync_tx, construct_block, into_deposit_info_cell, DEFAULT_FINALITY_BLOCKS,
+        TEST_CHAIN_ID,
+    },
+    mock_l1::{
+        always_success_script, eoa_lock_type_script, setup_chain, start_mock_ckb, wallet,
+        wallet_lock, MockL1Rollup, CKB,
+    },
+};
+
+async fn sync_block(chain: &mut Chain, rollup_cell: &CellOutput, result: ProduceBlockResult) {
+    sync_block_with_deposits(chain, rollup_cell, result, Default::default()).await
+}
+
+async fn sync_block_with_deposits(
+    chain: &mut Chain,
+    rollup_cell: &CellOutput,
+    result: ProduceBlockResult,
+    deposit_info_vec: DepositInfoVec,
+) {
+    let update = L1Action {
+        context: L1ActionContext::SubmitBlock {
+            l2block: result.block.clone(),
+            deposit_info_vec,
+            deposit_asset_scripts: Default::default(),
+            withdrawals: result.withdrawal_extras.clone(),
+        },
+        transaction: build_sync_tx(rollup_cell.clone(), result),
+    };
+    let param = SyncParam {
+        updates: vec![update],
+        reverts: Default::default(),
+    };
+    chain.sync(param).await.unwrap();
+    chain.notify_new_tip().await.unwrap();
+}
+
+async fn produce_block(chain: &Chain, deposit_info_vec: DepositInfoVec) -> ProduceBlockResult {
+    let mem_pool = chain.mem_pool().as_ref().unwrap();
+    let mut mem_pool = mem_pool.lock().await;
+    construct_block(chain, &mut mem_pool, deposit_info_vec)
+        .await
+        .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_watchtower_enters_challenge_on_bad_block() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let mock_ckb = start_mock_ckb();
+
+    // L2 chain, the bad block withdraws from an unknown account.
+    let rollup_type_script = Script::new_builder()
+        .code_hash(always_success_script(&[1]).hash().pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args(vec![42u8; 32].pack())
+        .build();
+    let rollup_script_hash = rollup_type_script.hash();
+    let mut chain = setup_chain(rollup_type_script.clone()).await;
+    let rollup_cell = CellOutput::new_builder()
+        .type_(Some(rollup_type_script.clone()).pack())
+        .build();
+
+    let alice_script = Script::new_builder()
+        .code_hash(eoa_lock_type_script().hash().pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args({
+            let mut args = rollup_script_hash.to_vec();
+            args.extend(&[42u8; 20]);
+            args.pack()
+        })
+        .build();
+    let deposit = DepositRequest::new_builder()
+        .capacity((4000u64 * CKB).pack())
+        .script(alice_script.clone())
+        .registry_id(gw_common::builtins::ETH_REGISTRY_ACCOUNT_ID.pack())
+        .build();
+    let deposit_info_vec = DepositInfoVec::new_builder()
+        .push(into_deposit_info_cell(chain.generator().rollup_context(), deposit).pack())
+        .build();
+    let result = produce_block(&chain, deposit_info_vec.clone()).await;
+    sync_block_with_deposits(&mut chain, &rollup_cell, result, deposit_info_vec).await;
+    for _ in 0..DEFAULT_FINALITY_BLOCKS {
+        let result = produce_block(&chain, Default::default()).await;
+        sync_block(&mut chain, &rollup_cell, result).await;
+    }
+    assert!(chain.last_sync_event().is_success());
+
+    let withdrawal = {
+        let owner_lock = Script::default();
+        let raw = RawWithdrawalRequest::new_builder()
+            .capacity((1000 * CKB).pack())
+            .account_script_hash(alice_script.hash().pack())
+            .owner_lock_hash(owner_lock.hash().pack())
+            .registry_id(gw_common::builtins::ETH_REGISTRY_ACCOUNT_ID.pack())
+            .chain_id(TEST_CHAIN_ID.pack())
+            .build();
+        WithdrawalRequestExtra::new_builder()
+            .request(WithdrawalRequest::new_builder().raw(raw).build())
+            .owner_lock(owner_lock)
+            .build()
+    };
+    let result = {
+        let mem_pool = chain.mem_pool().as_ref().unwrap();
+        let mut mem_pool = mem_pool.lock().await;
+        mem_pool.push_withdrawal_request(withdrawal).await.unwrap();
+        construct_block(&chain, &mut mem_pool, Default::default())
+            .await
+            .unwrap()
+    };
+    let (bad_block, bad_global_state) =
+        generate_bad_block_using_first_withdrawal(&chain, result.block, result.global_state);
+    let withdrawal_extras = result
+        .withdrawal_extras
+        .into_iter()
+        .enumerate()
+        .map(|(i, w)| {
+            let request = bad_block.withdrawals().get(i).unwrap();
+            w.as_builder().request(request).build()
+        })
+        .collect();
+    let bad_block_result = ProduceBlockResult {
+        block: bad_block,
+        global_state: bad_global_state.clone(),
+        withdrawal_extras,
+        ..result
+    };
+    sync_block(&mut chain, &rollup_cell, bad_block_result).await;
+    assert!(matches!(
+        chain.last_sync_event(),
+        SyncEvent::BadBlock { .. }
+    ));
+
+    // The bad block is submitted on L1, and the watchtower wallet is funded.
+    let rollup_context = chain.generator().rollup_context().clone();
+    let challenge_script_type_hash = rollup_context.rollup_config.challenge_script_type_hash();
+    let l1_rollup = MockL1Rollup::deploy(
+        &mock_ckb,
+        rollup_type_script.clone(),
+        rollup_context,
+        &bad_global_state,
+    )
+    .await;
+    let wallet_cell = CellOutput::new_builder()
+        .capacity((100000 * CKB).pack())
+        .lock(wallet_lock())
+        .build();
+    mock_ckb.mine_cells(vec![(wallet_cell, Bytes::new())]);
+
+    // Watchtower
+    let config = BlockProducerConfig {
+        challenger_config: ChallengerConfig {
+            rewards_receiver_lock: always_success_script(&[10; 20]).into(),
+        },
+        ..Default::default()
+    };
+    let offchain_mock_context = l1_rollup.offchain_mock_context(config.clone()).await;
+    let offchain_validator_context = OffChainValidatorContext::build(
+        &offchain_mock_context,
+        Default::default(),
+        Default::default(),
+    )
+    .unwrap();
+    let MockL1Rollup {
+        rpc_client,
+        rollup_context,
+        contracts_dep_manager,
+        ckb_genesis_info,
+    } = l1_rollup;
+    let cleaner = Arc::new(Cleaner::new(
+        rpc_client.clone(),
+        ckb_genesis_info.clone(),
+        wallet(),
+        config.fee_rate,
+    ));
+    let mut challenger = Challenger::new(ChallengerNewArgs {
+        rollup_context,
+        rpc_client,
+        wallet: wallet(),
+        config,
+        debug_config: Default::default(),
+        builtin_load_data: HashMap::new(),
+        ckb_genesis_info,
+        chain: Arc::new(Mutex::new(chain)),
+        tests_control: None,
+        cleaner,
+        offchain_mock_context,
+        offchain_validator_context: Some(offchain_validator_context),
+        contracts_dep_manager,
+    });
+
+    let event = ChainEvent::NewBlock {
+        block: Block::default(),
+    };
+    challenger.handle_event(event).await.unwrap();
+
+    let pending_txs = mock_ckb.chain().pending_transactions().to_vec();
+    assert_eq!(pending_txs.len(), 1);
+    let (rollup_output, rollup_data) = pending_txs[0].output_with_data(0).unwrap();
+    assert_eq!(
+        rollup_output.type_().as_slice(),
+        Some(rollup_type_script).pack().as_slice()
+    );
+    let post_global_state = global_state_from_slice(&rollup_data).unwrap();
+    let status: u8 = post_global_state.status().into();
+    assert_eq!(status, Status::Halting as u8);
+    let (challenge_cell, _) = pending_txs[0].output_with_data(1).unwrap();
+    assert_eq!(
+        challenge_cell.lock().code_hash().as_slice(),
+        challenge_script_type_hash.as_slice()
+    );
+}
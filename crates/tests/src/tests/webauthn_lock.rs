@@ -0,0 +1,330 @@
+use gw_common::{builtins::ETH_REGISTRY_ACCOUNT_ID, registry_address::RegistryAddress};
+use gw_generator::account_lock_manage::{
+    eip712::{traits::EIP712Encode, types::Withdrawal},
+    secp256k1::Secp256k1Eth,
+    webauthn::Secp256r1WebAuthn,
+    LockAlgorithm,
+};
+use gw_generator::error::LockAlgorithmError;
+use gw_types::{
+    bytes::Bytes,
+    h256::*,
+    packed::{
+        L2Transaction, RawL2Transaction, RawWithdrawalRequest, RollupConfig, Script,
+        WithdrawalRequest, WithdrawalRequestExtra,
+    },
+    prelude::*,
+};
+use gw_utils::RollupContext;
+
+use crate::testing_tool::webauthn_wallet::{
+    authenticator_data, client_data_json, platform_assertion, WebAuthnWallet, FLAG_USER_PRESENT,
+};
+
+const CHAIN_ID: u64 = 42;
+
+fn setup() -> (RollupContext, WebAuthnWallet, Script) {
+    let rollup_script_hash = [1u8; 32];
+    let ctx = RollupContext {
+        rollup_config: RollupConfig::new_builder()
+            .chain_id(CHAIN_ID.pack())
+            .build(),
+        ..Default::default()
+    };
+    let wallet = WebAuthnWallet::random(rollup_script_hash, [2u8; 32]);
+    let receiver_script = Script::new_builder()
+        .code_hash([3u8; 32].pack())
+        .args(Bytes::from(rollup_script_hash.to_vec()).pack())
+        .build();
+    (ctx, wallet, receiver_script)
+}
+
+fn build_tx(
+    wallet: &WebAuthnWallet,
+    receiver_script: &Script,
+    sign: impl FnOnce(H256) -> Bytes,
+) -> L2Transaction {
+    let raw_tx = RawL2Transaction::new_builder()
+        .chain_id(CHAIN_ID.pack())
+        .from_id(4u32.pack())
+        .to_id(5u32.pack())
+        .nonce(1u32.pack())
+        .args(Bytes::from_static(b"hello").pack())
+        .build();
+    let message = Secp256k1Eth::eip712_signing_message(
+        CHAIN_ID,
+        &raw_tx,
+        wallet.registry_address.clone(),
+        receiver_script.hash(),
+    )
+    .expect("signing message");
+    L2Transaction::new_builder()
+        .raw(raw_tx)
+        .signature(sign(message).pack())
+        .build()
+}
+
+fn verify_tx(
+    ctx: &RollupContext,
+    wallet: &WebAuthnWallet,
+    receiver_script: Script,
+    tx: L2Transaction,
+) -> Result<(), LockAlgorithmError> {
+    Secp256r1WebAuthn::default().verify_tx(
+        ctx,
+        wallet.registry_address.clone(),
+        wallet.account_script.clone(),
+        receiver_script,
+        tx,
+    )
+}
+
+#[test]
+fn test_webauthn_verify_tx() {
+    let (ctx, wallet, receiver_script) = setup();
+    let tx = build_tx(&wallet, &receiver_script, |msg| wallet.sign_message(msg));
+    verify_tx(&ctx, &wallet, receiver_script, tx).expect("verify tx");
+}
+
+#[test]
+fn test_webauthn_platform_assertion() {
+    let (ctx, _wallet, receiver_script) = setup();
+    assert_eq!(
+        hex::encode(receiver_script.hash()),
+        platform_assertion::RECEIVER_SCRIPT_HASH
+    );
+    let pubkey_hash = hex::decode(platform_assertion::PUBKEY_HASH).unwrap();
+    let sender_address = RegistryAddress::new(ETH_REGISTRY_ACCOUNT_ID, pubkey_hash.clone());
+    let sender_script = Script::new_builder()
+        .code_hash([2u8; 32].pack())
+        .hash_type(gw_types::core::ScriptHashType::Type.into())
+        .args({
+            let mut args = [1u8; 32].to_vec();
+            args.extend_from_slice(&pubkey_hash);
+            Bytes::from(args).pack()
+        })
+        .build();
+
+    let raw_tx = RawL2Transaction::new_builder()
+        .chain_id(platform_assertion::CHAIN_ID.pack())
+        .from_id(4u32.pack())
+        .to_id(5u32.pack())
+        .nonce(platform_assertion::NONCE.pack())
+        .args(Bytes::from_static(platform_assertion::ARGS).pack())
+        .build();
+    let message = Secp256k1Eth::eip712_signing_message(
+        platform_assertion::CHAIN_ID,
+        &raw_tx,
+        sender_address.clone(),
+        receiver_script.hash(),
+    )
+    .expect("signing message");
+    assert_eq!(hex::encode(message), platform_assertion::MESSAGE);
+
+    let tx = L2Transaction::new_builder()
+        .raw(raw_tx)
+        .signature(platform_assertion::signature().pack())
+        .build();
+    Secp256r1WebAuthn::default()
+        .verify_tx(&ctx, sender_address, sender_script, receiver_script, tx)
+        .expect("verify tx");
+}
+
+#[test]
+fn test_webauthn_recover_pubkey_hash() {
+    let (_ctx, wallet, _receiver_script) = setup();
+    let message = [7u8; 32];
+    let signature = wallet.sign_message(message);
+    let pubkey_hash = Secp256r1WebAuthn::default()
+        .recover(message, &signature)
+        .expect("recover");
+    assert_eq!(pubkey_hash.as_ref(), &wallet.pubkey_hash()[..]);
+}
+
+#[test]
+fn test_webauthn_wrong_challenge() {
+    let (ctx, wallet, receiver_script) = setup();
+    let tx = build_tx(&wallet, &receiver_script, |_msg| {
+        wallet.sign_message([9u8; 32])
+    });
+    let err = verify_tx(&ctx, &wallet, receiver_script, tx).unwrap_err();
+    assert!(matches!(err, LockAlgorithmError::InvalidSignature(_)));
+}
+
+#[test]
+fn test_webauthn_user_not_verified() {
+    let (ctx, wallet, receiver_script) = setup();
+    let tx = build_tx(&wallet, &receiver_script, |msg| {
+        wallet.sign_message_with_flags(msg, FLAG_USER_PRESENT)
+    });
+    let err = verify_tx(&ctx, &wallet, receiver_script, tx).unwrap_err();
+    assert!(matches!(err, LockAlgorithmError::InvalidSignature(_)));
+}
+
+#[test]
+fn test_webauthn_wrong_client_data_type() {
+    let (ctx, wallet, receiver_script) = setup();
+    let tx = build_tx(&wallet, &receiver_script, |msg| {
+        let client_data_json = format!(
+            r#"{{"type":"webauthn.create","challenge":"{}","origin":"https://example.com"}}"#,
+            base64::encode_config(msg, base64::URL_SAFE_NO_PAD)
+        );
+        wallet.sign_assertion(authenticator_data(0x05), client_data_json.into_bytes())
+    });
+    let err = verify_tx(&ctx, &wallet, receiver_script, tx).unwrap_err();
+    assert!(matches!(err, LockAlgorithmError::InvalidSignature(_)));
+}
+
+#[test]
+fn test_webauthn_reject_high_s() {
+    let (ctx, wallet, receiver_script) = setup();
+    let tx = build_tx(&wallet, &receiver_script, |msg| {
+        wallet.sign_assertion_high_s(
+            authenticator_data(0x05),
+            client_data_json(msg, "https://example.com"),
+        )
+    });
+    let err = verify_tx(&ctx, &wallet, receiver_script, tx).unwrap_err();
+    assert!(matches!(err, LockAlgorithmError::InvalidSignature(_)));
+}
+
+#[test]
+fn test_webauthn_origin() {
+    let (ctx, wallet, receiver_script) = setup();
+    for origin in ["https://login.example.com", "https://example.com:8443"] {
+        let tx = build_tx(&wallet, &receiver_script, |msg| {
+            wallet.sign_assertion(authenticator_data(0x05), client_data_json(msg, origin))
+        });
+        verify_tx(&ctx, &wallet, receiver_script.clone(), tx).expect("verify tx");
+    }
+
+    for origin in [
+        "https://evil.com",
+        "https://example.com.evil.com",
+        "https://notexample.com",
+        "http://example.com",
+        "https://example.com/path",
+    ] {
+        let tx = build_tx(&wallet, &receiver_script, |msg| {
+            wallet.sign_assertion(authenticator_data(0x05), client_data_json(msg, origin))
+        });
+        let err = verify_tx(&ctx, &wallet, receiver_script.clone(), tx).unwrap_err();
+        assert!(
+            matches!(err, LockAlgorithmError::InvalidSignature(_)),
+            "origin {}",
+            origin
+        );
+    }
+}
+
+#[test]
+fn test_webauthn_malformed_client_data() {
+    let (ctx, wallet, receiver_script) = setup();
+    let sign_client_data = |template: &str| {
+        build_tx(&wallet, &receiver_script, |msg| {
+            let challenge = base64::encode_config(msg, base64::URL_SAFE_NO_PAD);
+            let json = template.replace("CHALLENGE", &challenge);
+            wallet.sign_assertion(authenticator_data(0x05), json.into_bytes())
+        })
+    };
+
+    // Extra fields and whitespace are fine.
+    let tx = sign_client_data(
+        r#"{ "type": "webauthn.get", "challenge": "CHALLENGE", "origin": "https://example.com", "other_keys_can_be_added_here": "do not compare clientDataJSON against a template. See https://goo.gl/yabPex" }"#,
+    );
+    verify_tx(&ctx, &wallet, receiver_script.clone(), tx).expect("verify tx");
+
+    for template in [
+        // duplicated challenge, a substring match would take the first one
+        r#"{"type":"webauthn.get","challenge":"CHALLENGE","challenge":"x","origin":"https://example.com"}"#,
+        // challenge hidden in another field
+        r#"{"type":"webauthn.get","x":{"challenge":"CHALLENGE"},"challenge":"x","origin":"https://example.com"}"#,
+        r#"{"type":"webauthn.get","challenge":"CHALLENGE","origin":"https://example.com","crossOrigin":true}"#,
+        r#"{"type":"webauthn.get","challenge":"CHALLENGE"}"#,
+        r#"{"type":"webauthn.get","challenge":"CHALLENGE","origin":"https://example.com"}trailing"#,
+    ] {
+        let tx = sign_client_data(template);
+        let err = verify_tx(&ctx, &wallet, receiver_script.clone(), tx).unwrap_err();
+        assert!(
+            matches!(err, LockAlgorithmError::InvalidSignature(_)),
+            "client data {}",
+            template
+        );
+    }
+}
+
+#[test]
+fn test_webauthn_wrong_key() {
+    let (ctx, wallet, receiver_script) = setup();
+    let other = WebAuthnWallet::random([1u8; 32], [2u8; 32]);
+    let tx = build_tx(&wallet, &receiver_script, |msg| other.sign_message(msg));
+    let err = verify_tx(&ctx, &wallet, receiver_script, tx).unwrap_err();
+    assert!(matches!(err, LockAlgorithmError::InvalidSignature(_)));
+}
+
+#[test]
+fn test_webauthn_reject_unprotected_tx() {
+    let (ctx, wallet, receiver_script) = setup();
+    let raw_tx = RawL2Transaction::new_builder()
+        .from_id(4u32.pack())
+        .to_id(5u32.pack())
+        .build();
+    let tx = L2Transaction::new_builder()
+        .raw(raw_tx)
+        .signature(wallet.sign_message([0u8; 32]).pack())
+        .build();
+    let err = verify_tx(&ctx, &wallet, receiver_script, tx).unwrap_err();
+    assert!(matches!(err, LockAlgorithmError::InvalidTransactionArgs));
+}
+
+#[test]
+fn test_webauthn_verify_withdrawal() {
+    let (ctx, wallet, _receiver_script) = setup();
+    let raw = RawWithdrawalRequest::new_builder()
+        .chain_id(CHAIN_ID.pack())
+        .nonce(1u32.pack())
+        .capacity((1000u64 * 10u64.pow(8)).pack())
+        .account_script_hash(wallet.account_script.hash().pack())
+        .owner_lock_hash(wallet.account_script.hash().pack())
+        .registry_id(wallet.registry_address.registry_id.pack())
+        .build();
+    let message = Withdrawal::from_raw(
+        raw.clone(),
+        wallet.account_script.clone(),
+        wallet.registry_address.clone(),
+    )
+    .expect("typed withdrawal")
+    .eip712_message(Secp256k1Eth::domain_with_chain_id(CHAIN_ID).hash_struct());
+    let build_withdrawal = |signature: Bytes| {
+        WithdrawalRequestExtra::new_builder()
+            .request(
+                WithdrawalRequest::new_builder()
+                    .raw(raw.clone())
+                    .signature(signature.pack())
+                    .build(),
+            )
+            .owner_lock(wallet.account_script.clone())
+            .build()
+    };
+    let lock = Secp256r1WebAuthn::default();
+
+    let withdrawal = build_withdrawal(wallet.sign_message(message));
+    lock.verify_withdrawal(
+        &ctx,
+        wallet.account_script.clone(),
+        &withdrawal,
+        wallet.registry_address.clone(),
+    )
+    .expect("verify withdrawal");
+
+    let withdrawal = build_withdrawal(wallet.sign_message([9u8; 32]));
+    let err = lock
+        .verify_withdrawal(
+            &ctx,
+            wallet.account_script.clone(),
+            &withdrawal,
+            wallet.registry_address.clone(),
+        )
+        .unwrap_err();
+    assert!(matches!(err, LockAlgorithmError::InvalidSignature(_)));
+}
@@ -0,0 +1,132 @@
+//! Schedule a backend fork on running nodes without restarting them.
+//!
+//! 1. `sign` checks the generators of a `BackendForkConfig` against their
+//!    checksums, and signs the fork with a backend fork admin key.
+//! 2. `submit` sends the signed fork to a node through the `Admin` RPC
+//!    `gw_schedule_backend_fork`. The node loads the generators from the paths
+//!    in the fork, so they must be available on the node.
+//!
+//! The node persists the fork and reloads it on restart. It also publishes
+//! the fork to read-only nodes over p2p block sync, they verify it with their
+//! own `backend_fork_admins` and load the generators from the same paths.
+//!
+//! Scheduled forks are reported in `staged_backend_forks` of `gw_get_node_info`.
+
+use std::{fs, path::PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use ckb_fixed_hash::H256;
+use ckb_jsonrpc_types::JsonBytes;
+use clap::Parser;
+use gw_config::BackendForkConfig;
+use gw_generator::backend_manage::{backend_fork_signing_message, Backend};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    account::{eth_sign, privkey_to_eth_address, read_privkey},
+    godwoken_rpc::GodwokenRpcClient,
+    utils::cli_args::H256Arg,
+};
+
+pub const BACKEND_FORK_COMMAND: &str = "backend-fork";
+
+/// Schedule a backend fork on running nodes
+#[derive(Parser)]
+#[clap(name = BACKEND_FORK_COMMAND)]
+pub enum BackendForkCommand {
+    /// Check the generators and sign a backend fork
+    Sign {
+        /// TOML file of the backend fork, in the format of `backend_forks` in
+        /// the consensus config, `sudt_proxy` is not allowed
+        #[clap(long)]
+        fork: PathBuf,
+        /// Rollup type hash
+        #[clap(long)]
+        rollup_type_hash: H256Arg,
+        /// The private key file path of a backend fork admin
+        #[clap(short = 'k', long)]
+        privkey_path: PathBuf,
+        /// Output signed fork file path
+        #[clap(short = 'o', long)]
+        output: PathBuf,
+    },
+    /// Submit a signed backend fork to a node
+    Submit {
+        /// Godwoken jsonrpc rpc sever URL
+        #[clap(short = 'g', long, default_value = "http://127.0.0.1:8119")]
+        godwoken_rpc_url: String,
+        /// Signed fork file path
+        #[clap(long)]
+        signed_fork: PathBuf,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+struct SignedBackendFork {
+    fork: BackendForkConfig,
+    signature: JsonBytes,
+}
+
+impl BackendForkCommand {
+    pub async fn run(self) -> Result<()> {
+        match self {
+            Self::Sign {
+                fork,
+                rollup_type_hash,
+                privkey_path,
+                output,
+            } => {
+                let content = fs::read_to_string(&fork)
+                    .with_context(|| format!("read {}", fork.display()))?;
+                let fork: BackendForkConfig = toml::from_str(&content)?;
+                if fork.sudt_proxy.is_some() {
+                    bail!("sudt_proxy can't be changed by a runtime backend fork");
+                }
+                for backend in &fork.backends {
+                    let generator = backend
+                        .generator
+                        .get()
+                        .with_context(|| format!("load generator from {}", backend.generator))?;
+                    Backend::build(
+                        backend.backend_type,
+                        backend.validator_script_type_hash.0,
+                        generator.into_owned().into(),
+                        backend.generator_checksum.0,
+                    )?;
+                }
+
+                let message = backend_fork_signing_message(&rollup_type_hash.0 .0, &fork);
+                let privkey = read_privkey(&privkey_path)?;
+                let signature = eth_sign(&H256(message), privkey.clone())?;
+                let signed = SignedBackendFork {
+                    fork,
+                    signature: JsonBytes::from_vec(signature.to_vec()),
+                };
+                fs::write(&output, serde_json::to_string_pretty(&signed)?)?;
+                println!(
+                    "signed by 0x{}, written to {}",
+                    hex::encode(privkey_to_eth_address(&privkey)?),
+                    output.display()
+                );
+            }
+            Self::Submit {
+                godwoken_rpc_url,
+                signed_fork,
+            } => {
+                let content = fs::read(&signed_fork)
+                    .with_context(|| format!("read {}", signed_fork.display()))?;
+                let signed: SignedBackendFork = serde_json::from_slice(&content)?;
+                let rpc_client = GodwokenRpcClient::new(&godwoken_rpc_url);
+                rpc_client
+                    .schedule_backend_fork(&signed.fork, signed.signature)
+                    .await
+                    .map_err(|err| anyhow!("schedule backend fork: {}", err))?;
+                println!(
+                    "backend fork is scheduled at height {}",
+                    signed.fork.fork_height
+                );
+            }
+        }
+        Ok(())
+    }
+}
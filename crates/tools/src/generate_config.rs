@@ -33,6 +33,7 @@ pub const GENERATE_CONFIG_COMMAND: &str = "generate-config";
 enum NodeModeV {
     Readonly,
     Fullnode,
+    Watchtower,
 }
 
 impl From<NodeModeV> for NodeMode {
@@ -40,6 +41,7 @@ impl From<NodeModeV> for NodeMode {
         match m {
             NodeModeV::Fullnode => Self::FullNode,
             NodeModeV::Readonly => Self::ReadOnly,
+            NodeModeV::Watchtower => Self::Watchtower,
         }
     }
 }
@@ -286,6 +288,7 @@ pub async fn generate_node_config(cmd: GenerateConfigCommand) -> Result<()> {
         path: cmd.store_path.unwrap_or_else(|| "./gw-db".into()),
         options_file: None,
         cache_size: None,
+        ..Default::default()
     };
     let rpc_client: RPCClientConfig = RPCClientConfig {
         indexer_url: cmd.ckb_indexer_rpc,
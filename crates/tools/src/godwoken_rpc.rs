@@ -10,8 +10,10 @@ use anyhow::{anyhow, Result};
 use ckb_jsonrpc_types::Script;
 use ckb_types::H256;
 use gw_common::{builtins::ETH_REGISTRY_ACCOUNT_ID, registry_address::RegistryAddress};
+use gw_config::BackendForkConfig;
 use gw_jsonrpc_types::{
-    ckb_jsonrpc_types::{JsonBytes, Uint32},
+    ckb_jsonrpc_types::{JsonBytes, Uint32, Uint64},
+    debug::DebugRunResult,
     debugger::{DumpChallengeTarget, ReprMockTransaction},
     godwoken::{RunResult, TxReceipt},
 };
@@ -164,6 +166,26 @@ impl GodwokenRpcClient {
             .map(Into::into)
     }
 
+    pub async fn schedule_backend_fork(
+        &self,
+        fork: &BackendForkConfig,
+        signature: JsonBytes,
+    ) -> Result<()> {
+        let params = serde_json::to_value((fork, signature))?;
+        self.rpc::<()>("schedule_backend_fork", params).await
+    }
+
+    pub async fn debug_replay_transaction(
+        &self,
+        tx_hash: &H256,
+        max_cycles: Option<u64>,
+        profile: bool,
+    ) -> Result<Option<DebugRunResult>> {
+        let params = serde_json::to_value((tx_hash, max_cycles.map(Uint64::from), profile))?;
+        self.raw_rpc::<Option<DebugRunResult>>("debug_replay_transaction", params)
+            .await
+    }
+
     async fn rpc<SuccessResponse: serde::de::DeserializeOwned>(
         &self,
         method: &str,
@@ -2,6 +2,7 @@
 
 mod account;
 mod address;
+mod backend_fork;
 mod create_creator_account;
 mod deploy_genesis;
 mod deploy_scripts;
@@ -11,8 +12,10 @@ mod generate_config;
 mod get_balance;
 pub mod godwoken_rpc;
 mod hasher;
+mod multisig;
 mod polyjuice;
 mod prepare_scripts;
+mod profile_tx;
 mod report_accounts;
 mod scan_eth_address;
 mod setup;
@@ -31,6 +34,7 @@ use std::{
 
 use account::read_privkey;
 use anyhow::{anyhow, Result};
+use backend_fork::{BackendForkCommand, BACKEND_FORK_COMMAND};
 use clap::{value_t, App, Arg, CommandFactory, FromArgMatches, SubCommand};
 use deploy_genesis::DeployRollupCellArgs;
 use deploy_scripts::{DeployScriptsCommand, DEPLOY_SCRIPTS_COMMAND};
@@ -41,6 +45,8 @@ use gw_common::builtins::ETH_REGISTRY_ACCOUNT_ID;
 use gw_jsonrpc_types::godwoken::ChallengeTargetType;
 use gw_rpc_client::indexer_client::CkbIndexerClient;
 use gw_types::{offchain::CompatibleFinalizedTimepoint, prelude::*};
+use multisig::{MultisigCommand, MULTISIG_COMMAND};
+use profile_tx::{ProfileTxCommand, PROFILE_TX_COMMAND};
 use tracing_subscriber::prelude::*;
 
 use crate::{
@@ -761,6 +767,9 @@ async fn main() -> Result<()> {
                         .help("input file"),
                 ))
         .subcommand(scan_eth_address::command())
+        .subcommand(MultisigCommand::command())
+        .subcommand(ProfileTxCommand::command())
+        .subcommand(BackendForkCommand::command())
         ;
 
     let matches = app.clone().get_matches();
@@ -1366,6 +1375,15 @@ async fn main() -> Result<()> {
         Some((scan_eth_address::COMMAND, m)) => {
             scan_eth_address::run(m).await.unwrap();
         }
+        Some((MULTISIG_COMMAND, m)) => {
+            MultisigCommand::from_arg_matches(m)?.run()?;
+        }
+        Some((PROFILE_TX_COMMAND, m)) => {
+            ProfileTxCommand::from_arg_matches(m)?.run().await?;
+        }
+        Some((BACKEND_FORK_COMMAND, m)) => {
+            BackendForkCommand::from_arg_matches(m)?.run().await?;
+        }
         _ => {
             app.print_help().expect("print help");
         }
@@ -0,0 +1,270 @@
+//! Offline signature collection for M-of-N multisig accounts.
+//!
+//! 1. `new-request` creates a signing request file of an L2 transaction or a
+//!    withdrawal.
+//! 2. Each member checks the request and adds a signature with `sign`. The
+//!    request file is passed around offline.
+//! 3. `combine` picks `threshold` signatures and outputs the signed
+//!    transaction or withdrawal, which can be submitted through Godwoken RPC.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use ckb_fixed_hash::H256;
+use ckb_jsonrpc_types::JsonBytes;
+use clap::Parser;
+use gw_common::{builtins::ETH_REGISTRY_ACCOUNT_ID, registry_address::RegistryAddress};
+use gw_generator::account_lock_manage::{
+    eip712::{traits::EIP712Encode, types::Withdrawal},
+    multisig::{MultisigConfig, SIGNATURE_SIZE},
+    secp256k1::Secp256k1Eth,
+};
+use gw_jsonrpc_types::godwoken::{RawL2Transaction, WithdrawalRequestExtra};
+use gw_types::{packed, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    account::{eth_sign, privkey_to_eth_address, read_privkey},
+    utils::cli_args::{H160Arg, H256Arg},
+};
+
+pub const MULTISIG_COMMAND: &str = "multisig";
+
+/// M-of-N multisig account tools
+#[derive(Parser)]
+#[clap(name = MULTISIG_COMMAND)]
+pub enum MultisigCommand {
+    /// Print the lock args and the address of a multisig account
+    Address {
+        /// Number of signatures required
+        #[clap(long)]
+        threshold: u8,
+        /// ETH address of a member, at most 16 members
+        #[clap(long = "member", required = true)]
+        members: Vec<H160Arg>,
+        /// Rollup type hash
+        #[clap(long)]
+        rollup_type_hash: H256Arg,
+    },
+    /// Create a signing request of an L2 transaction or a withdrawal
+    NewRequest {
+        /// Lock args of the multisig account
+        #[clap(long)]
+        lock_args: String,
+        /// JSON file of the raw L2 transaction
+        #[clap(long)]
+        raw_l2tx: Option<PathBuf>,
+        /// Script hash of the transaction receiver, required by --raw-l2tx
+        #[clap(long)]
+        to_script_hash: Option<H256Arg>,
+        /// JSON file of the withdrawal request extra, the signature is ignored
+        #[clap(long)]
+        withdrawal: Option<PathBuf>,
+        /// Output request file path
+        #[clap(short = 'o', long)]
+        output: PathBuf,
+    },
+    /// Sign a request and add the signature to the request file
+    Sign {
+        /// Request file path
+        #[clap(long)]
+        request: PathBuf,
+        /// The private key file path of a member
+        #[clap(short = 'k', long)]
+        privkey_path: PathBuf,
+    },
+    /// Combine signatures of a request and print the signed transaction or withdrawal
+    Combine {
+        /// Request file path
+        #[clap(long)]
+        request: PathBuf,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Payload {
+    L2Transaction {
+        raw: RawL2Transaction,
+        to_script_hash: H256,
+    },
+    Withdrawal {
+        withdrawal: WithdrawalRequestExtra,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+struct SigningRequest {
+    lock_args: JsonBytes,
+    payload: Payload,
+    message: H256,
+    signatures: Vec<JsonBytes>,
+}
+
+impl SigningRequest {
+    fn read(path: &Path) -> Result<Self> {
+        let content = fs::read(path).with_context(|| format!("read {}", path.display()))?;
+        Ok(serde_json::from_slice(&content)?)
+    }
+
+    fn write(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    fn config(&self) -> Result<MultisigConfig> {
+        MultisigConfig::from_lock_args(self.lock_args.as_bytes())
+            .map_err(|err| anyhow!("invalid multisig lock args: {}", err))
+    }
+
+    /// Calculate the EIP-712 message from the payload, so signers don't need
+    /// to trust the message in the request file.
+    fn calc_message(&self) -> Result<[u8; 32]> {
+        let config = self.config()?;
+        let address = RegistryAddress::new(ETH_REGISTRY_ACCOUNT_ID, config.address().to_vec());
+        match &self.payload {
+            Payload::L2Transaction {
+                raw,
+                to_script_hash,
+            } => {
+                let raw: packed::RawL2Transaction = raw.clone().into();
+                let chain_id = raw.chain_id().unpack();
+                if !raw.is_chain_id_protected() {
+                    bail!("multisig transaction must be chain id protected");
+                }
+                Secp256k1Eth::eip712_signing_message(chain_id, &raw, address, to_script_hash.0)
+            }
+            Payload::Withdrawal { withdrawal } => {
+                let withdrawal: packed::WithdrawalRequestExtra = withdrawal.clone().into();
+                let chain_id = withdrawal.raw().chain_id().unpack();
+                let typed_message =
+                    Withdrawal::from_raw(withdrawal.raw(), withdrawal.owner_lock(), address)?;
+                Ok(typed_message
+                    .eip712_message(Secp256k1Eth::domain_with_chain_id(chain_id).hash_struct()))
+            }
+        }
+    }
+}
+
+impl MultisigCommand {
+    pub fn run(self) -> Result<()> {
+        match self {
+            MultisigCommand::Address {
+                threshold,
+                members,
+                rollup_type_hash,
+            } => {
+                let config = MultisigConfig::new(
+                    threshold,
+                    members.into_iter().map(|m| m.0 .0).collect(),
+                )
+                .map_err(|_| anyhow!("threshold must be in 1..=members, at most 16 members"))?;
+                let lock_args = config.to_lock_args(&rollup_type_hash.0 .0);
+                println!("lock args: 0x{}", hex::encode(&lock_args));
+                println!("address: 0x{}", hex::encode(config.address()));
+            }
+            MultisigCommand::NewRequest {
+                lock_args,
+                raw_l2tx,
+                to_script_hash,
+                withdrawal,
+                output,
+            } => {
+                let lock_args = hex::decode(lock_args.trim_start_matches("0x"))?;
+                let payload = match (raw_l2tx, withdrawal) {
+                    (Some(_), Some(_)) => bail!("only one of raw l2tx and withdrawal is allowed"),
+                    (Some(path), None) => Payload::L2Transaction {
+                        raw: serde_json::from_slice(&fs::read(path)?)?,
+                        to_script_hash: to_script_hash
+                            .ok_or_else(|| anyhow!("to script hash is required"))?
+                            .0,
+                    },
+                    (None, Some(path)) => Payload::Withdrawal {
+                        withdrawal: serde_json::from_slice(&fs::read(path)?)?,
+                    },
+                    (None, None) => bail!("raw l2tx or withdrawal is required"),
+                };
+                let mut request = SigningRequest {
+                    lock_args: JsonBytes::from_vec(lock_args),
+                    payload,
+                    message: H256::default(),
+                    signatures: Vec::new(),
+                };
+                request.message = request.calc_message()?.into();
+                request.write(&output)?;
+                println!("message: {:#x}", request.message);
+            }
+            MultisigCommand::Sign {
+                request: path,
+                privkey_path,
+            } => {
+                let mut request = SigningRequest::read(&path)?;
+                let config = request.config()?;
+                let message = request.calc_message()?;
+                if message != request.message.0 {
+                    bail!("message mismatch, the request may have been tampered");
+                }
+
+                let privkey = read_privkey(&privkey_path)?;
+                let eth_address = privkey_to_eth_address(&privkey)?;
+                if !config
+                    .pubkey_hashes
+                    .iter()
+                    .any(|h| h.as_slice() == eth_address.as_ref())
+                {
+                    bail!("0x{} is not a member", hex::encode(&eth_address));
+                }
+                let signature = eth_sign(&request.message, privkey)?;
+                request
+                    .signatures
+                    .push(JsonBytes::from_vec(signature.to_vec()));
+                request.write(&path)?;
+                println!(
+                    "signed by 0x{}, {} of {} signatures collected",
+                    hex::encode(&eth_address),
+                    request.signatures.len(),
+                    config.threshold
+                );
+            }
+            MultisigCommand::Combine { request } => {
+                let request = SigningRequest::read(&request)?;
+                let config = request.config()?;
+                let message = request.calc_message()?;
+                let signatures = request
+                    .signatures
+                    .iter()
+                    .map(|sig| {
+                        sig.as_bytes()
+                            .try_into()
+                            .map_err(|_| anyhow!("invalid signature length {}", sig.len()))
+                    })
+                    .collect::<Result<Vec<[u8; SIGNATURE_SIZE]>>>()?;
+                let signature = config
+                    .combine_signatures(message, &signatures)
+                    .map_err(|err| anyhow!("{}", err))?;
+                let signed = match request.payload {
+                    Payload::L2Transaction { raw, .. } => packed::L2Transaction::new_builder()
+                        .raw(raw.into())
+                        .signature(signature.pack())
+                        .build()
+                        .as_bytes(),
+                    Payload::Withdrawal { withdrawal } => {
+                        let withdrawal: packed::WithdrawalRequestExtra = withdrawal.into();
+                        let req = withdrawal
+                            .request()
+                            .as_builder()
+                            .signature(signature.pack())
+                            .build();
+                        withdrawal.as_builder().request(req).build().as_bytes()
+                    }
+                };
+                println!("signature: 0x{}", hex::encode(&signature));
+                println!("signed: 0x{}", hex::encode(&signed));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use clap::Parser;
+
+use crate::{godwoken_rpc::GodwokenRpcClient, utils::cli_args::H256Arg};
+
+pub const PROFILE_TX_COMMAND: &str = "profile-tx";
+
+/// Replay a committed transaction and profile the cycles of the backend.
+///
+/// Requires the `Debug` RPC module. The output file is in the folded stack
+/// format, render it with `inferno-flamegraph` or `flamegraph.pl`.
+#[derive(Parser)]
+#[clap(name = PROFILE_TX_COMMAND)]
+pub struct ProfileTxCommand {
+    /// Godwoken jsonrpc rpc sever URL
+    #[clap(short = 'g', long, default_value = "http://127.0.0.1:8119")]
+    godwoken_rpc_url: String,
+    /// Transaction hash
+    #[clap(long)]
+    tx_hash: H256Arg,
+    /// Override the max cycles of the transaction
+    #[clap(long)]
+    max_cycles: Option<u64>,
+    /// Output folded stacks file path
+    #[clap(short = 'o', long)]
+    output: PathBuf,
+}
+
+impl ProfileTxCommand {
+    pub async fn run(self) -> Result<()> {
+        let rpc_client = GodwokenRpcClient::new(&self.godwoken_rpc_url);
+        let run_result = rpc_client
+            .debug_replay_transaction(&self.tx_hash.0, self.max_cycles, true)
+            .await?
+            .ok_or_else(|| anyhow!("transaction not found"))?;
+        let profile = run_result
+            .profile
+            .ok_or_else(|| anyhow!("no profile returned, is the node outdated?"))?;
+
+        let mut folded = profile.folded.join("\n");
+        folded.push('\n');
+        fs::write(&self.output, folded)
+            .with_context(|| format!("write output to {}", self.output.to_string_lossy()))?;
+
+        let cycles = run_result.cycles;
+        println!("exit code: {}", run_result.exit_code);
+        println!(
+            "cycles: execution {}, virtual {}, total {}",
+            cycles.execution.value(),
+            cycles.r#virtual.value(),
+            cycles.total.value()
+        );
+        println!();
+        println!(
+            "{:<28} {:>6} {:>10} {:>14}",
+            "syscall", "code", "count", "cycles"
+        );
+        for syscall in profile.syscalls {
+            println!(
+                "{:<28} {:>6} {:>10} {:>14}",
+                syscall.name,
+                syscall.code.value(),
+                syscall.count.value(),
+                syscall.cycles.value()
+            );
+        }
+        println!();
+        println!("folded stacks written to {}", self.output.to_string_lossy());
+        Ok(())
+    }
+}
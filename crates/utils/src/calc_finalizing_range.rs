@@ -32,8 +32,8 @@ fn is_older_block_finalized(
     Ok(compatible_finalized_timepoint.is_finalized(&older_block_timepoint))
 }
 
-// Returns the highest block that is finalized for `block`.
-fn find_finalized_upper_bound(
+/// Returns the highest block that is finalized for `block`.
+pub fn find_finalized_upper_bound(
     rollup_config: &RollupConfig,
     fork_config: &ForkConfig,
     db: &impl ChainStore,
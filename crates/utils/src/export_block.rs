@@ -8,27 +8,52 @@ use gw_store::{
 use gw_types::{
     bytes::Bytes,
     h256::*,
-    offchain::ExportedBlock,
+    offchain::{ExportedBadBlock, ExportedBlock},
     packed::{self, GlobalState},
     prelude::*,
 };
 
-pub fn export_block(snap: &StoreReadonly, block_number: u64) -> Result<ExportedBlock> {
-    let block_hash = snap
-        .get_block_hash_by_number(block_number)?
-        .ok_or_else(|| anyhow!("block {} not found", block_number))?;
+pub mod v2;
 
-    let block = snap
-        .get_block(&block_hash)?
-        .ok_or_else(|| anyhow!("block {} not found", block_number))?;
+/// Export valid block, or bad block if `block_number` is after the last valid
+/// tip block.
+pub fn export_block(snap: &StoreReadonly, block_number: u64) -> Result<ExportedBlock> {
+    let (block_hash, block) = match snap.get_block_hash_by_number(block_number)? {
+        Some(block_hash) => {
+            let block = snap
+                .get_block(&block_hash)?
+                .ok_or_else(|| anyhow!("block {} not found", block_number))?;
+            (block_hash, block)
+        }
+        None => {
+            let block_hash = get_bad_block_hash_by_number(snap, block_number)?
+                .ok_or_else(|| anyhow!("block {} not found", block_number))?;
+            let block = snap
+                .get_bad_block(&block_hash)
+                .ok_or_else(|| anyhow!("bad block {} not found", block_number))?;
+            (block_hash, block)
+        }
+    };
+    let bad_block = snap.get_block(&block_hash)?.is_none();
 
     let post_global_state = snap
         .get_block_post_global_state(&block_hash)?
         .ok_or_else(|| anyhow!("block {} post global state not found", block_number))?;
 
-    let deposit_requests = snap
-        .get_block_deposit_info_vec(block_number)
-        .context("get block deposit info vec")?;
+    let deposit_requests = if bad_block {
+        // Bad blocks inserted by earlier versions have no deposit info.
+        snap.get_bad_block_deposit_info_vec(&block_hash)
+            .unwrap_or_else(|| {
+                log::warn!(
+                    "bad block {} has no deposit info, export empty deposits",
+                    block_number
+                );
+                Default::default()
+            })
+    } else {
+        snap.get_block_deposit_info_vec(block_number)
+            .context("get block deposit info vec")?
+    };
 
     let deposit_asset_scripts = {
         let reader = deposit_requests.as_reader();
@@ -50,16 +75,48 @@ pub fn export_block(snap: &StoreReadonly, block_number: u64) -> Result<ExportedB
 
     let withdrawals = {
         let reqs = block.as_reader().withdrawals();
-        let extra_reqs = reqs.iter().map(|w| {
+        let extra_reqs = reqs.iter().enumerate().map(|(index, w)| {
             let h = w.hash();
-            snap.get_withdrawal(&h)?
+            // Bad block withdrawals are not indexed by hash.
+            let withdrawal = if bad_block {
+                let key = packed::WithdrawalKey::new_builder()
+                    .block_hash(block_hash.pack())
+                    .index(index.pack())
+                    .build();
+                snap.get_withdrawal_by_key(&key)?
+            } else {
+                snap.get_withdrawal(&h)?
+            };
+            withdrawal
                 .ok_or_else(|| anyhow!("block {} withdrawal {} not found", block_number, h.pack()))
         });
         extra_reqs.collect::<Result<Vec<_>>>()?
     };
 
-    let bad_block_hashes = get_bad_block_hashes(snap, block_number)?;
-    let submit_tx_hash = snap.get_block_submit_tx_hash(block_number);
+    let parent_reverted_block_root = match block_number.checked_sub(1) {
+        Some(_) => {
+            let parent_block_hash: H256 = block.raw().parent_block_hash().unpack();
+            get_block_reverted_block_root(snap, &parent_block_hash)?
+        }
+        None => post_global_state.reverted_block_root().unpack(),
+    };
+    let reverted_block_root = post_global_state.reverted_block_root().unpack();
+    let bad_block_hashes =
+        get_bad_block_hashes(snap, parent_reverted_block_root, reverted_block_root)?;
+
+    let pending_reverted_block_hashes = if snap.get_tip_block_hash()? == block_hash {
+        let db_reverted_block_root = snap.get_reverted_block_smt_root()?;
+        get_bad_block_hashes(snap, reverted_block_root, db_reverted_block_root)?
+    } else {
+        None
+    };
+
+    let (submit_tx_hash, bad_block) = if bad_block {
+        let challenge_target = snap.get_bad_block_challenge_target(&block_hash)?;
+        (None, Some(ExportedBadBlock { challenge_target }))
+    } else {
+        (snap.get_block_submit_tx_hash(block_number), None)
+    };
 
     let exported_block = ExportedBlock {
         block,
@@ -69,11 +126,35 @@ pub fn export_block(snap: &StoreReadonly, block_number: u64) -> Result<ExportedB
         withdrawals,
         bad_block_hashes,
         submit_tx_hash,
+        bad_block,
+        pending_reverted_block_hashes,
     };
 
     Ok(exported_block)
 }
 
+/// Bad blocks are not indexed by number, walk back from tip block.
+fn get_bad_block_hash_by_number(snap: &StoreReadonly, block_number: u64) -> Result<Option<H256>> {
+    let last_valid_tip_block_hash = snap.get_last_valid_tip_block_hash()?;
+    let mut block_hash = snap.get_tip_block_hash()?;
+    while block_hash != last_valid_tip_block_hash {
+        let block = match snap.get_bad_block(&block_hash) {
+            Some(block) => block,
+            None => bail!("bad block {} not found", block_hash.pack()),
+        };
+        let number: u64 = block.raw().number().unpack();
+        if number == block_number {
+            return Ok(Some(block_hash));
+        }
+        if number < block_number {
+            break;
+        }
+        block_hash = block.raw().parent_block_hash().unpack();
+    }
+
+    Ok(None)
+}
+
 pub fn read_block_size(reader: &mut impl Read) -> Result<Option<u32>> {
     let mut full_size_buf = [0u8; 4];
 
@@ -173,12 +254,83 @@ impl<Reader: Read + Seek> Iterator for ExportedBlockReader<Reader> {
     }
 }
 
+/// Reader for both the length prefixed v1 format and the v2 format.
+pub enum ExportedBlockFileReader<Reader: Read + Seek> {
+    V1(ExportedBlockReader<Reader>),
+    V2(v2::ExportedBlockReader<Reader>),
+}
+
+impl<Reader: Read + Seek> ExportedBlockFileReader<Reader> {
+    /// Detect format by the v2 magic. A v1 file starts with the molecule full
+    /// size of the first block, which can't be that large.
+    pub fn open(mut reader: Reader) -> Result<Self> {
+        let mut magic = [0u8; 4];
+        let is_v2 = match reader.read_exact(&mut magic) {
+            Ok(()) => magic == v2::MAGIC,
+            Err(ref e) if e.kind() == ErrorKind::UnexpectedEof => false,
+            Err(e) => bail!(e),
+        };
+        reader.seek(SeekFrom::Start(0))?;
+
+        if is_v2 {
+            Ok(Self::V2(v2::ExportedBlockReader::open(reader)?))
+        } else {
+            Ok(Self::V1(ExportedBlockReader::new(reader)))
+        }
+    }
+
+    /// Rollup type hash recorded in v2 header.
+    pub fn rollup_type_hash(&self) -> Option<H256> {
+        match self {
+            Self::V1(_) => None,
+            Self::V2(reader) => Some(reader.header().rollup_type_hash),
+        }
+    }
+
+    pub fn peek_block(&mut self) -> Result<Option<(ExportedBlock, usize)>> {
+        match self {
+            Self::V1(reader) => reader.peek_block(),
+            Self::V2(reader) => reader.peek_block(),
+        }
+    }
+
+    pub fn skip_blocks(&mut self, blocks: u64) -> Result<(u64, u64)> {
+        match self {
+            Self::V1(reader) => reader.skip_blocks(blocks),
+            Self::V2(reader) => reader.skip_blocks(blocks),
+        }
+    }
+}
+
+impl<Reader: Read + Seek> Iterator for ExportedBlockFileReader<Reader> {
+    type Item = Result<(ExportedBlock, usize)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::V1(reader) => reader.next(),
+            Self::V2(reader) => reader.next(),
+        }
+    }
+}
+
 pub fn insert_bad_block_hashes(
     tx_db: &mut StoreTransaction,
     bad_block_hashes_vec: Vec<Vec<H256>>,
 ) -> Result<()> {
     let mut reverted_block_smt = tx_db.reverted_block_smt()?;
     for bad_block_hashes in bad_block_hashes_vec {
+        // Already inserted as pending reverted block hashes of previous import.
+        let mut inserted = true;
+        for block_hash in bad_block_hashes.iter() {
+            if reverted_block_smt.get(&(*block_hash).into())?.is_zero() {
+                inserted = false;
+                break;
+            }
+        }
+        if inserted {
+            continue;
+        }
+
         let prev_smt_root = *reverted_block_smt.root();
         for block_hash in bad_block_hashes.iter() {
             reverted_block_smt.update((*block_hash).into(), SMTH256Ext::one())?;
@@ -240,12 +392,41 @@ pub fn check_block_post_state(
     Ok(())
 }
 
-fn get_bad_block_hashes(snap: &StoreReadonly, block_number: u64) -> Result<Option<Vec<Vec<H256>>>> {
-    let parent_reverted_block_root = {
-        let parent_block_number = block_number.saturating_sub(1);
-        get_block_reverted_block_root(snap, parent_block_number)?
-    };
-    let reverted_block_root = get_block_reverted_block_root(snap, block_number)?;
+/// Bad block doesn't change account state, only check block smt, reverted
+/// block root and tip.
+pub fn check_bad_block_post_state(
+    tx_db: &StoreTransaction,
+    block_number: u64,
+    post_global_state: &GlobalState,
+) -> Result<()> {
+    let expected_block_root: H256 = post_global_state.block().merkle_root().unpack();
+    if tx_db.get_block_smt_root()? != expected_block_root {
+        bail!("replicate bad block {} block smt diff", block_number);
+    }
+
+    let expected_reverted_block_root: H256 = post_global_state.reverted_block_root().unpack();
+    if tx_db.get_reverted_block_smt_root()? != expected_reverted_block_root {
+        bail!(
+            "replicate bad block {} reverted block root diff",
+            block_number
+        );
+    }
+
+    let expected_tip_block_hash: H256 = post_global_state.tip_block_hash().unpack();
+    if tx_db.get_tip_block_hash()? != expected_tip_block_hash {
+        bail!("replicate bad block {} tip block hash diff", block_number);
+    }
+
+    Ok(())
+}
+
+/// Reverted block hashes between two reverted block smt roots, in the order
+/// they were reverted.
+fn get_bad_block_hashes(
+    snap: &StoreReadonly,
+    parent_reverted_block_root: H256,
+    reverted_block_root: H256,
+) -> Result<Option<Vec<Vec<H256>>>> {
     if reverted_block_root == parent_reverted_block_root {
         return Ok(None);
     }
@@ -264,14 +445,11 @@ fn get_bad_block_hashes(snap: &StoreReadonly, block_number: u64) -> Result<Optio
     Ok(Some(bad_block_hashes))
 }
 
-fn get_block_reverted_block_root(snap: &impl ChainStore, block_number: u64) -> Result<H256> {
-    let block_hash = snap
-        .get_block_hash_by_number(block_number)?
-        .ok_or_else(|| anyhow!("block {} not found", block_number))?;
-
+/// Works for both valid and bad blocks.
+fn get_block_reverted_block_root(snap: &impl ChainStore, block_hash: &H256) -> Result<H256> {
     let post_global_state = snap
-        .get_block_post_global_state(&block_hash)?
-        .ok_or_else(|| anyhow!("block {} post global state not found", block_number))?;
+        .get_block_post_global_state(block_hash)?
+        .ok_or_else(|| anyhow!("block {} post global state not found", block_hash.pack()))?;
 
     Ok(post_global_state.reverted_block_root().unpack())
 }
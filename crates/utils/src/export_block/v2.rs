@@ -0,0 +1,679 @@
+//! Version 2 block export format.
+//!
+//! ```text
+//! file    = header | segment* | index | footer
+//! header  = magic(4) | version u32 | rollup type hash(32) | from block u64
+//!           | to block u64 | blocks per segment u32 | blake2b(previous fields)(32)
+//! segment = (chunk len u32 | chunk)*
+//! index   = (first block u64 | block count u32 | offset u64 | size u64 | blake2b(segment)(32))*
+//! footer  = index offset u64 | segment count u32 | blake2b(index)(32) | magic(4)
+//! ```
+//!
+//! Integers are little endian. Each segment is an independent zstd stream
+//! (see [`crate::compression`]), one chunk per molecule `ExportedBlockRecord`,
+//! so a reader only has to decompress the segment that contains the block it
+//! seeks to. The footer is written last, a truncated file is rejected on open.
+//!
+//! Unlike v1, bad blocks after the last valid block can be exported, along
+//! with their challenge target and pending reverted block hashes.
+
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use gw_common::blake2b::new_blake2b;
+use gw_types::{bytes::Bytes, h256::*, offchain::ExportedBlock, packed, prelude::*};
+
+use crate::compression::{StreamDecoder, StreamEncoder};
+
+pub const MAGIC: [u8; 4] = *b"GWEB";
+pub const VERSION: u32 = 2;
+pub const DEFAULT_BLOCKS_PER_SEGMENT: u32 = 1024;
+
+const COMPRESSION_LEVEL: i32 = 3;
+const HEADER_SIZE: usize = 4 + 4 + 32 + 8 + 8 + 4 + 32;
+const INDEX_ENTRY_SIZE: usize = 8 + 4 + 8 + 8 + 32;
+const FOOTER_SIZE: usize = 8 + 4 + 32 + 4;
+
+fn checksum(data: &[u8]) -> [u8; 32] {
+    let mut hasher = new_blake2b();
+    hasher.update(data);
+    let mut hash = [0u8; 32];
+    hasher.finalize(&mut hash);
+    hash
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(buf: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(buf[offset..offset + 8].try_into().unwrap())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+    pub rollup_type_hash: H256,
+    pub from_block: u64,
+    pub to_block: u64,
+    pub blocks_per_segment: u32,
+}
+
+impl Header {
+    pub fn block_count(&self) -> u64 {
+        self.to_block - self.from_block + 1
+    }
+
+    fn segment_count(&self) -> usize {
+        let bps = self.blocks_per_segment as u64;
+        ((self.block_count() + bps - 1) / bps) as usize
+    }
+
+    fn to_bytes(self) -> [u8; HEADER_SIZE] {
+        let mut buf = [0u8; HEADER_SIZE];
+        buf[..4].copy_from_slice(&MAGIC);
+        buf[4..8].copy_from_slice(&VERSION.to_le_bytes());
+        buf[8..40].copy_from_slice(self.rollup_type_hash.as_slice());
+        buf[40..48].copy_from_slice(&self.from_block.to_le_bytes());
+        buf[48..56].copy_from_slice(&self.to_block.to_le_bytes());
+        buf[56..60].copy_from_slice(&self.blocks_per_segment.to_le_bytes());
+        let hash = checksum(&buf[..60]);
+        buf[60..].copy_from_slice(&hash);
+        buf
+    }
+
+    fn from_bytes(buf: &[u8; HEADER_SIZE]) -> Result<Self> {
+        ensure!(buf[..4] == MAGIC, "not a v2 block export file");
+        let version = read_u32(buf, 4);
+        ensure!(version == VERSION, "unsupported export version {}", version);
+        ensure!(
+            checksum(&buf[..60]) == buf[60..],
+            "header checksum mismatch"
+        );
+
+        let header = Header {
+            rollup_type_hash: buf[8..40].try_into().unwrap(),
+            from_block: read_u64(buf, 40),
+            to_block: read_u64(buf, 48),
+            blocks_per_segment: read_u32(buf, 56),
+        };
+        ensure!(
+            header.from_block <= header.to_block && header.blocks_per_segment > 0,
+            "invalid header {:?}",
+            header
+        );
+        Ok(header)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct IndexEntry {
+    first_block: u64,
+    block_count: u32,
+    offset: u64,
+    size: u64,
+    checksum: [u8; 32],
+}
+
+impl IndexEntry {
+    fn write_to(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.first_block.to_le_bytes());
+        buf.extend_from_slice(&self.block_count.to_le_bytes());
+        buf.extend_from_slice(&self.offset.to_le_bytes());
+        buf.extend_from_slice(&self.size.to_le_bytes());
+        buf.extend_from_slice(&self.checksum);
+    }
+
+    fn from_slice(buf: &[u8]) -> Self {
+        IndexEntry {
+            first_block: read_u64(buf, 0),
+            block_count: read_u32(buf, 8),
+            offset: read_u64(buf, 12),
+            size: read_u64(buf, 20),
+            checksum: buf[28..60].try_into().unwrap(),
+        }
+    }
+}
+
+struct SegmentBuilder {
+    encoder: StreamEncoder<'static>,
+    first_block: u64,
+    block_count: u32,
+    data: Vec<u8>,
+}
+
+pub struct ExportedBlockWriter<W: Write> {
+    inner: W,
+    header: Header,
+    pos: u64,
+    next_block: u64,
+    segment: Option<SegmentBuilder>,
+    index: Vec<IndexEntry>,
+}
+
+impl<W: Write> ExportedBlockWriter<W> {
+    pub fn new(mut inner: W, header: Header) -> Result<Self> {
+        ensure!(
+            header.from_block <= header.to_block && header.blocks_per_segment > 0,
+            "invalid header {:?}",
+            header
+        );
+        let header_bytes = header.to_bytes();
+        inner.write_all(&header_bytes)?;
+
+        Ok(ExportedBlockWriter {
+            inner,
+            header,
+            pos: HEADER_SIZE as u64,
+            next_block: header.from_block,
+            segment: None,
+            index: Vec::with_capacity(header.segment_count()),
+        })
+    }
+
+    /// Blocks must be written in order, from `from_block` to `to_block`.
+    pub fn write_block(&mut self, exported: ExportedBlock) -> Result<()> {
+        let block_number = exported.block_number();
+        if block_number != self.next_block || block_number > self.header.to_block {
+            bail!("expect block {}, got {}", self.next_block, block_number);
+        }
+
+        if self.segment.is_none() {
+            self.segment = Some(SegmentBuilder {
+                encoder: StreamEncoder::new(COMPRESSION_LEVEL)?,
+                first_block: block_number,
+                block_count: 0,
+                data: Vec::new(),
+            });
+        }
+        let segment = self.segment.as_mut().expect("segment");
+
+        let packed: packed::ExportedBlockRecord = exported.into();
+        let chunk = segment.encoder.encode(packed.as_slice())?;
+        segment
+            .data
+            .extend_from_slice(&(chunk.len() as u32).to_le_bytes());
+        segment.data.extend_from_slice(&chunk);
+        segment.block_count += 1;
+        self.next_block += 1;
+
+        if segment.block_count == self.header.blocks_per_segment {
+            self.flush_segment()?;
+        }
+
+        Ok(())
+    }
+
+    /// Write index and footer. Fails if not all blocks in the header range
+    /// have been written.
+    pub fn finish(mut self) -> Result<W> {
+        self.flush_segment()?;
+        if self.next_block != self.header.to_block + 1 {
+            bail!(
+                "missing blocks from {} to {}",
+                self.next_block,
+                self.header.to_block
+            );
+        }
+
+        let mut index = Vec::with_capacity(self.index.len() * INDEX_ENTRY_SIZE);
+        for entry in self.index.iter() {
+            entry.write_to(&mut index);
+        }
+        self.inner.write_all(&index)?;
+
+        let mut footer = Vec::with_capacity(FOOTER_SIZE);
+        footer.extend_from_slice(&self.pos.to_le_bytes());
+        footer.extend_from_slice(&(self.index.len() as u32).to_le_bytes());
+        footer.extend_from_slice(&checksum(&index));
+        footer.extend_from_slice(&MAGIC);
+        self.inner.write_all(&footer)?;
+        self.inner.flush()?;
+
+        Ok(self.inner)
+    }
+
+    /// Blocks written so far.
+    pub fn block_count(&self) -> u64 {
+        self.next_block - self.header.from_block
+    }
+
+    /// Bytes written so far, including buffered segment.
+    pub fn size(&self) -> u64 {
+        let buffered = self.segment.as_ref().map(|s| s.data.len() as u64);
+        self.pos + buffered.unwrap_or(0)
+    }
+
+    fn flush_segment(&mut self) -> Result<()> {
+        let segment = match self.segment.take() {
+            Some(segment) => segment,
+            None => return Ok(()),
+        };
+        self.inner.write_all(&segment.data)?;
+        self.index.push(IndexEntry {
+            first_block: segment.first_block,
+            block_count: segment.block_count,
+            offset: self.pos,
+            size: segment.data.len() as u64,
+            checksum: checksum(&segment.data),
+        });
+        self.pos += segment.data.len() as u64;
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> ExportedBlockWriter<W> {
+    /// Like [`Self::finish`], but rewrite header `to_block` to the last
+    /// written block. For writers that don't know where to stop ahead.
+    pub fn finish_at_last_written(mut self) -> Result<W> {
+        ensure!(self.block_count() > 0, "no block written");
+        self.header.to_block = self.next_block - 1;
+        self.inner.seek(SeekFrom::Start(0))?;
+        self.inner.write_all(&self.header.to_bytes())?;
+        self.inner.seek(SeekFrom::Start(self.pos))?;
+        self.finish()
+    }
+}
+
+struct SegmentCursor {
+    segment: usize,
+    data: Bytes,
+    pos: usize,
+    next_block: u64,
+    decoder: StreamDecoder,
+}
+
+impl SegmentCursor {
+    /// Decompress next block in segment, returns it along with its on-disk size.
+    fn next_block(&mut self) -> Result<(packed::ExportedBlockRecord, usize)> {
+        let block_number = self.next_block;
+        let corrupted = || anyhow!("block {} corrupted", block_number);
+
+        let len_end = self.pos + 4;
+        let len = read_u32(self.data.get(self.pos..len_end).ok_or_else(corrupted)?, 0) as usize;
+        let chunk = self
+            .data
+            .get(len_end..len_end + len)
+            .ok_or_else(corrupted)?;
+        let buf = self.decoder.decode(chunk).with_context(corrupted)?;
+        packed::ExportedBlockRecordReader::verify(&buf, false).with_context(corrupted)?;
+        let packed = packed::ExportedBlockRecord::new_unchecked(Bytes::from(buf));
+
+        let number: u64 = packed.block().block().raw().number().unpack();
+        ensure!(
+            number == block_number,
+            "expect block {}, got {}",
+            block_number,
+            number
+        );
+
+        self.pos = len_end + len;
+        self.next_block += 1;
+        Ok((packed, 4 + len))
+    }
+}
+
+pub struct ExportedBlockReader<Reader: Read + Seek> {
+    inner: Reader,
+    header: Header,
+    index: Vec<IndexEntry>,
+    next_block: u64,
+    cursor: Option<SegmentCursor>,
+    peeked: Option<(packed::ExportedBlockRecord, usize)>,
+}
+
+impl<Reader: Read + Seek> ExportedBlockReader<Reader> {
+    /// Read and verify header, footer and index. Blocks are read lazily.
+    pub fn open(mut inner: Reader) -> Result<Self> {
+        inner.seek(SeekFrom::Start(0))?;
+        let mut header_buf = [0u8; HEADER_SIZE];
+        inner
+            .read_exact(&mut header_buf)
+            .context("read export header")?;
+        let header = Header::from_bytes(&header_buf)?;
+
+        let file_len = inner.seek(SeekFrom::End(0))?;
+        if file_len < (HEADER_SIZE + FOOTER_SIZE) as u64 {
+            bail!("export file truncated");
+        }
+        let mut footer = [0u8; FOOTER_SIZE];
+        inner.seek(SeekFrom::End(-(FOOTER_SIZE as i64)))?;
+        inner.read_exact(&mut footer)?;
+        if footer[FOOTER_SIZE - 4..] != MAGIC {
+            bail!("export file truncated, footer not found");
+        }
+        let index_offset = read_u64(&footer, 0);
+        let segment_count = read_u32(&footer, 8) as usize;
+        ensure!(
+            segment_count == header.segment_count(),
+            "expect {} segments, got {}",
+            header.segment_count(),
+            segment_count
+        );
+        let index_len = segment_count * INDEX_ENTRY_SIZE;
+        ensure!(
+            index_offset + (index_len + FOOTER_SIZE) as u64 == file_len,
+            "index offset {} out of range",
+            index_offset
+        );
+
+        let mut index_buf = vec![0u8; index_len];
+        inner.seek(SeekFrom::Start(index_offset))?;
+        inner.read_exact(&mut index_buf)?;
+        ensure!(
+            checksum(&index_buf) == footer[12..44],
+            "index checksum mismatch"
+        );
+
+        let index: Vec<_> = index_buf
+            .chunks_exact(INDEX_ENTRY_SIZE)
+            .map(IndexEntry::from_slice)
+            .collect();
+        let mut expected_block = header.from_block;
+        let mut expected_offset = HEADER_SIZE as u64;
+        for (i, entry) in index.iter().enumerate() {
+            let is_last = i + 1 == index.len();
+            let full = entry.block_count == header.blocks_per_segment;
+            ensure!(
+                entry.first_block == expected_block
+                    && entry.offset == expected_offset
+                    && entry.block_count > 0
+                    && (full || is_last),
+                "segment {} index entry invalid",
+                i
+            );
+            expected_block += entry.block_count as u64;
+            expected_offset += entry.size;
+        }
+        ensure!(
+            expected_block == header.to_block + 1 && expected_offset == index_offset,
+            "index doesn't cover block {} to {}",
+            header.from_block,
+            header.to_block
+        );
+
+        Ok(ExportedBlockReader {
+            inner,
+            header,
+            index,
+            next_block: header.from_block,
+            cursor: None,
+            peeked: None,
+        })
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    pub fn peek_block(&mut self) -> Result<Option<(ExportedBlock, usize)>> {
+        if self.peeked.is_none() {
+            self.peeked = self.read_packed_block()?;
+        }
+        let peeked = self.peeked.clone();
+        Ok(peeked.map(|(packed, size)| (packed.into(), size)))
+    }
+
+    /// Skip `blocks` blocks, returns skipped block count and size in bytes.
+    ///
+    /// Whole segments are skipped without reading them.
+    pub fn skip_blocks(&mut self, blocks: u64) -> Result<(u64, u64)> {
+        let mut count = 0;
+        let mut size = 0;
+        if blocks == 0 {
+            return Ok((count, size));
+        }
+        if let Some((_, block_size)) = self.peeked.take() {
+            count += 1;
+            size += block_size as u64;
+        }
+
+        let target = self
+            .next_block
+            .saturating_add(blocks - count)
+            .min(self.header.to_block + 1);
+        while self.next_block < target {
+            let segment = self.segment_of(self.next_block);
+            let entry = self.index[segment];
+            let segment_end = entry.first_block + entry.block_count as u64;
+            if self.next_block == entry.first_block && segment_end <= target {
+                count += entry.block_count as u64;
+                size += entry.size;
+                self.next_block = segment_end;
+                self.cursor = None;
+            } else {
+                let (_block, block_size) = self.cursor_at(self.next_block)?.next_block()?;
+                count += 1;
+                size += block_size as u64;
+                self.next_block += 1;
+            }
+        }
+
+        Ok((count, size))
+    }
+
+    /// Position the reader so that the next block read is `block_number`.
+    pub fn seek_block(&mut self, block_number: u64) -> Result<()> {
+        if block_number < self.header.from_block || block_number > self.header.to_block + 1 {
+            bail!(
+                "block {} out of range {} to {}",
+                block_number,
+                self.header.from_block,
+                self.header.to_block
+            );
+        }
+        self.peeked = None;
+        self.next_block = block_number;
+        Ok(())
+    }
+
+    fn segment_of(&self, block_number: u64) -> usize {
+        ((block_number - self.header.from_block) / self.header.blocks_per_segment as u64) as usize
+    }
+
+    fn cursor_at(&mut self, block_number: u64) -> Result<&mut SegmentCursor> {
+        let segment = self.segment_of(block_number);
+        let reusable = matches!(
+            self.cursor,
+            Some(ref c) if c.segment == segment && c.next_block <= block_number
+        );
+        if !reusable {
+            let entry = self.index[segment];
+            let mut data = vec![0u8; entry.size as usize];
+            self.inner.seek(SeekFrom::Start(entry.offset))?;
+            self.inner
+                .read_exact(&mut data)
+                .with_context(|| format!("read segment {}", segment))?;
+            if checksum(&data) != entry.checksum {
+                bail!(
+                    "segment {} (block {} to {}) checksum mismatch",
+                    segment,
+                    entry.first_block,
+                    entry.first_block + entry.block_count as u64 - 1
+                );
+            }
+            self.cursor = Some(SegmentCursor {
+                segment,
+                data: data.into(),
+                pos: 0,
+                next_block: entry.first_block,
+                decoder: StreamDecoder::new(),
+            });
+        }
+
+        let cursor = self.cursor.as_mut().expect("cursor");
+        // Chunks are compressed as one stream, decode the ones before target.
+        while cursor.next_block < block_number {
+            cursor.next_block()?;
+        }
+        Ok(cursor)
+    }
+
+    fn read_packed_block(&mut self) -> Result<Option<(packed::ExportedBlockRecord, usize)>> {
+        if let Some(peeked) = self.peeked.take() {
+            return Ok(Some(peeked));
+        }
+        if self.next_block > self.header.to_block {
+            return Ok(None);
+        }
+        let (packed, size) = self.cursor_at(self.next_block)?.next_block()?;
+        self.next_block += 1;
+        Ok(Some((packed, size)))
+    }
+}
+
+impl<Reader: Read + Seek> Iterator for ExportedBlockReader<Reader> {
+    type Item = Result<(ExportedBlock, usize)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let block = self.read_packed_block();
+        block
+            .map(|b| b.map(|(packed, size)| (packed.into(), size)))
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use gw_types::{
+        offchain::ExportedBadBlock,
+        packed::{ChallengeTarget, L2Block, RawL2Block},
+    };
+
+    use super::*;
+
+    fn exported_block(number: u64) -> ExportedBlock {
+        let raw = RawL2Block::new_builder().number(number.pack()).build();
+        ExportedBlock {
+            block: L2Block::new_builder().raw(raw).build(),
+            post_global_state: Default::default(),
+            deposit_info_vec: Default::default(),
+            deposit_asset_scripts: Default::default(),
+            withdrawals: Default::default(),
+            bad_block_hashes: None,
+            submit_tx_hash: Some([number as u8; 32]),
+            bad_block: None,
+            pending_reverted_block_hashes: None,
+        }
+    }
+
+    fn export(from_block: u64, to_block: u64, blocks_per_segment: u32) -> Vec<u8> {
+        let header = Header {
+            rollup_type_hash: [1u8; 32],
+            from_block,
+            to_block,
+            blocks_per_segment,
+        };
+        let mut writer = ExportedBlockWriter::new(Vec::new(), header).unwrap();
+        for number in from_block..=to_block {
+            writer.write_block(exported_block(number)).unwrap();
+        }
+        writer.finish().unwrap()
+    }
+
+    #[test]
+    fn test_write_and_read_bad_block() {
+        let header = Header {
+            rollup_type_hash: [1u8; 32],
+            from_block: 0,
+            to_block: 1,
+            blocks_per_segment: 4,
+        };
+        let target = ChallengeTarget::new_builder()
+            .block_hash([2u8; 32].pack())
+            .target_index(1u32.pack())
+            .build();
+        let bad_block = || {
+            let mut block = exported_block(1);
+            block.bad_block = Some(ExportedBadBlock {
+                challenge_target: Some(target.clone()),
+            });
+            block.pending_reverted_block_hashes = Some(vec![vec![[3u8; 32]]]);
+            block
+        };
+
+        let mut writer = ExportedBlockWriter::new(Vec::new(), header).unwrap();
+        writer.write_block(exported_block(0)).unwrap();
+        writer.write_block(bad_block()).unwrap();
+        let buf = writer.finish().unwrap();
+
+        let mut reader = ExportedBlockReader::open(Cursor::new(buf)).unwrap();
+        let (valid, _size) = reader.next().unwrap().unwrap();
+        assert!(!valid.is_bad_block());
+        let (bad, _size) = reader.next().unwrap().unwrap();
+        assert_eq!(bad, bad_block());
+    }
+
+    #[test]
+    fn test_write_and_read() {
+        let buf = export(5, 30, 4);
+        let reader = ExportedBlockReader::open(Cursor::new(buf)).unwrap();
+        assert_eq!(reader.header().rollup_type_hash, [1u8; 32]);
+
+        let blocks: Vec<_> = reader.map(|r| r.unwrap().0).collect();
+        assert_eq!(blocks.len(), 26);
+        for (block, number) in blocks.into_iter().zip(5..) {
+            assert_eq!(block, exported_block(number));
+        }
+    }
+
+    #[test]
+    fn test_finish_at_last_written() {
+        let header = Header {
+            rollup_type_hash: [1u8; 32],
+            from_block: 10,
+            to_block: 109,
+            blocks_per_segment: 4,
+        };
+        let mut writer = ExportedBlockWriter::new(Cursor::new(Vec::new()), header).unwrap();
+        for number in 10..=20 {
+            writer.write_block(exported_block(number)).unwrap();
+        }
+        assert_eq!(writer.block_count(), 11);
+        let buf = writer.finish_at_last_written().unwrap().into_inner();
+
+        let reader = ExportedBlockReader::open(Cursor::new(buf)).unwrap();
+        assert_eq!(reader.header().to_block, 20);
+        let numbers: Vec<_> = reader.map(|r| r.unwrap().0.block_number()).collect();
+        assert_eq!(numbers, (10..=20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_skip_and_seek() {
+        let buf = export(0, 20, 4);
+        let mut reader = ExportedBlockReader::open(Cursor::new(buf)).unwrap();
+
+        assert_eq!(reader.peek_block().unwrap().unwrap().0.block_number(), 0);
+        let (count, _size) = reader.skip_blocks(9).unwrap();
+        assert_eq!(count, 9);
+        assert_eq!(reader.next().unwrap().unwrap().0.block_number(), 9);
+
+        reader.seek_block(2).unwrap();
+        assert_eq!(reader.next().unwrap().unwrap().0.block_number(), 2);
+        assert_eq!(reader.next().unwrap().unwrap().0.block_number(), 3);
+        assert_eq!(reader.next().unwrap().unwrap().0.block_number(), 4);
+
+        let (count, _size) = reader.skip_blocks(100).unwrap();
+        assert_eq!(count, 16);
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_detect_truncated_and_corrupted() {
+        let buf = export(0, 9, 4);
+
+        let truncated = buf[..buf.len() - 1].to_vec();
+        assert!(ExportedBlockReader::open(Cursor::new(truncated)).is_err());
+
+        let mut corrupted = buf;
+        corrupted[HEADER_SIZE + 8] ^= 1;
+        let mut reader = ExportedBlockReader::open(Cursor::new(corrupted)).unwrap();
+        let err = reader.next().unwrap().unwrap_err();
+        assert!(err.to_string().contains("checksum mismatch"));
+
+        // Other segments are still readable.
+        reader.seek_block(4).unwrap();
+        assert_eq!(reader.next().unwrap().unwrap().0.block_number(), 4);
+    }
+}
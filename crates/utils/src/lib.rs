@@ -19,7 +19,7 @@ pub mod type_id;
 pub mod wallet;
 pub mod withdrawal;
 
-pub use calc_finalizing_range::calc_finalizing_range;
+pub use calc_finalizing_range::{calc_finalizing_range, find_finalized_upper_bound};
 pub use query_rollup_cell::query_rollup_cell;
 pub use rollup_context::RollupContext;
 pub use timepoint::{finalized_timepoint, global_state_finalized_timepoint};
@@ -1,6 +1,11 @@
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use gw_types::bytes::Bytes;
-use gw_types::packed::{Script, ScriptReader, WithdrawalLockArgs, WithdrawalLockArgsReader};
+use gw_types::offchain::{CellInfo, InputCellInfo};
+use gw_types::packed::{
+    CellDep, CellOutput, Script, ScriptReader, UnlockWithdrawalViaTransfer,
+    UnlockWithdrawalWitness, UnlockWithdrawalWitnessUnion, WithdrawalLockArgs,
+    WithdrawalLockArgsReader, WitnessArgs,
+};
 use gw_types::prelude::*;
 
 pub struct ParsedWithdrawalLockArgs {
@@ -58,3 +63,92 @@ pub fn parse_lock_args(args: &Bytes) -> Result<ParsedWithdrawalLockArgs> {
         owner_lock,
     })
 }
+
+/// args: rollup_type_hash | withdrawal lock args | owner lock len | owner lock
+pub fn build_lock_args(
+    rollup_type_hash: &[u8; 32],
+    lock_args: &WithdrawalLockArgs,
+    owner_lock: &Script,
+) -> Bytes {
+    let mut args = rollup_type_hash.to_vec();
+    args.extend_from_slice(&lock_args.as_bytes());
+    args.extend_from_slice(&(owner_lock.as_bytes().len() as u32).to_be_bytes());
+    args.extend_from_slice(&owner_lock.as_bytes());
+    Bytes::from(args)
+}
+
+pub struct TransferredWithdrawal {
+    pub deps: Vec<CellDep>,
+    pub inputs: Vec<InputCellInfo>,
+    pub witness_args: Vec<WitnessArgs>,
+    pub outputs: Vec<(CellOutput, Bytes)>,
+}
+
+/// Transfer the withdrawal cell to `new_owner_lock`, e.g. a liquidity provider
+/// of fast withdrawal.
+///
+/// The owner cell is required by the withdrawal lock to prove the owner agrees
+/// with the transfer. It is returned to the owner with `payment` added, so the
+/// transaction must be balanced by the cells of the new owner.
+pub fn transfer_to_new_owner(
+    withdrawal_cell: CellInfo,
+    owner_cell: CellInfo,
+    new_owner_lock: &Script,
+    payment: u64,
+    withdrawal_lock_dep: CellDep,
+) -> Result<TransferredWithdrawal> {
+    let withdrawal_lock = withdrawal_cell.output.lock();
+    let args: Bytes = withdrawal_lock.args().unpack();
+    let parsed = parse_lock_args(&args)?;
+    if owner_cell.output.lock().hash() != parsed.owner_lock.hash() {
+        bail!("owner cell lock not match");
+    }
+    if new_owner_lock.hash() == parsed.owner_lock.hash() {
+        bail!("transfer to the same owner");
+    }
+
+    let new_lock_args = parsed
+        .lock_args
+        .as_builder()
+        .owner_lock_hash(new_owner_lock.calc_script_hash())
+        .build();
+    let new_args = build_lock_args(&parsed.rollup_type_hash, &new_lock_args, new_owner_lock);
+    let transferred = withdrawal_cell
+        .output
+        .clone()
+        .as_builder()
+        .lock(withdrawal_lock.as_builder().args(new_args.pack()).build())
+        .build();
+
+    let owner_capacity: u64 = owner_cell.output.capacity().unpack();
+    let paid = CellOutput::new_builder()
+        .capacity(
+            owner_capacity
+                .checked_add(payment)
+                .context("payment overflow")?
+                .pack(),
+        )
+        .lock(parsed.owner_lock)
+        .build();
+
+    let unlock_via_transfer_witness = {
+        let unlock_args = UnlockWithdrawalViaTransfer::new_builder().build();
+        let unlock_witness = UnlockWithdrawalWitness::new_builder()
+            .set(UnlockWithdrawalWitnessUnion::UnlockWithdrawalViaTransfer(
+                unlock_args,
+            ))
+            .build();
+        WitnessArgs::new_builder()
+            .lock(Some(unlock_witness.as_bytes()).pack())
+            .build()
+    };
+
+    // The withdrawal lock checks the output at the same index as the input,
+    // so the withdrawal cell must be the first input.
+    Ok(TransferredWithdrawal {
+        deps: vec![withdrawal_lock_dep],
+        inputs: vec![withdrawal_cell.clone().into(), owner_cell.into()],
+        witness_args: vec![unlock_via_transfer_witness],
+        outputs: vec![(transferred, withdrawal_cell.data), (paid, Bytes::new())],
+    })
+}
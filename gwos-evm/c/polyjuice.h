@@ -23,6 +23,10 @@
 #pragma pop_macro("errno")
 
 #include "common.h"
+#pragma push_macro("errno")
+#undef errno
+#include "sponsor_utils.h"
+#pragma pop_macro("errno")
 #include "polyjuice_errors.h"
 #include "polyjuice_utils.h"
 
@@ -1703,10 +1707,17 @@ int run_polyjuice() {
     ckb_debug("gas not enough");
     return clean_evmc_result_and_return(&res, -1);
   }
-  uint256_t fee_u256 = calculate_fee(g_gas_price, gas_used);
   gw_reg_addr_t sender_addr = new_reg_addr(msg.sender.bytes);
-  ret = sudt_pay_fee(&context, g_sudt_id, /* g_sudt_id must already exists */
-                     sender_addr, fee_u256);
+  if (is_create(msg.kind)) {
+    uint256_t fee_u256 = calculate_fee(g_gas_price, gas_used);
+    ret = sudt_pay_fee(&context, g_sudt_id, /* g_sudt_id must already exists */
+                       sender_addr, fee_u256);
+  } else {
+    /* a call with a zero gas price may be paid by the sponsor of the contract,
+     * see sponsor_utils.h */
+    ret = gw_sponsor_pay_fee(&context, g_sudt_id, sender_addr, gas_used,
+                             g_gas_price, msg.input_data, msg.input_size);
+  }
   if (ret != 0) {
     debug_print_int("[run_polyjuice] pay fee to block_producer failed", ret);
     return clean_evmc_result_and_return(&res, ret);
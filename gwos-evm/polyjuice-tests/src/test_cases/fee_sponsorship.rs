@@ -0,0 +1,179 @@
+//! Test fee sponsorship, the sponsor of ERC20 pays the fee of `transfer`
+//!   See ./evm-contracts/ERC20.bin and gwos/c/sponsor_utils.h
+
+use crate::helper::{
+    self, deploy, eth_addr_to_ethabi_addr, new_block_info, parse_log, setup, Log, MockContractInfo,
+    PolyjuiceArgsBuilder, CKB_SUDT_ACCOUNT_ID, CREATOR_ACCOUNT_ID, ETH_REGISTRY_ACCOUNT_ID,
+    L2TX_MAX_CYCLES, RESERVED_ACCOUNT_ID,
+};
+use gw_common::{blake2b::new_blake2b, registry_address::RegistryAddress, state::State};
+use gw_generator::sponsor::{get_sponsored_fee, sponsor_key, sponsor_policy_key, sponsor_used_key};
+use gw_store::traits::chain_store::ChainStore;
+use gw_store::{chain_view::ChainView, state::traits::JournalDB};
+use gw_traits::CodeStore;
+use gw_types::{
+    bytes::Bytes,
+    h256::*,
+    packed::{Byte4, Byte4Vec, RawL2Transaction, SponsorPolicy},
+    prelude::*,
+    U256,
+};
+
+const INIT_CODE: &str = include_str!("./evm-contracts/ERC20.bin");
+const POLICY_GAS_PRICE: u128 = 1000;
+const PER_USER_QUOTA: u128 = 100_000_000;
+// selector of `transfer(address,uint256)`
+const TRANSFER_SELECTOR: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+
+// Same as SetSponsorPolicy of the Meta contract
+fn set_sponsor_policy(state: &mut crate::DummyState, sponsor_id: u32, policy: &SponsorPolicy) {
+    let target: H256 = policy.target().unpack();
+    let data = policy.as_bytes();
+    let mut data_hash = [0u8; 32];
+    let mut hasher = new_blake2b();
+    hasher.update(&data);
+    hasher.finalize(&mut data_hash);
+    state.insert_data(data_hash, data);
+    state
+        .update_value(RESERVED_ACCOUNT_ID, &sponsor_policy_key(&target), data_hash)
+        .unwrap();
+    let mut sponsor_id_value = [0u8; 32];
+    sponsor_id_value[..4].copy_from_slice(&sponsor_id.to_le_bytes());
+    state
+        .update_value(RESERVED_ACCOUNT_ID, &sponsor_key(&target), sponsor_id_value)
+        .unwrap();
+}
+
+fn gas_used(logs: &[gw_types::packed::LogItem]) -> u64 {
+    logs.iter()
+        .find_map(|log| match parse_log(log) {
+            Log::PolyjuiceSystem { gas_used, .. } => Some(gas_used),
+            _ => None,
+        })
+        .expect("polyjuice system log")
+}
+
+#[test]
+fn test_fee_sponsorship() {
+    let (store, mut state, generator) = setup();
+    let block_producer = crate::helper::create_block_producer(&mut state);
+
+    let user_eth_address = [1u8; 20];
+    let (user_id, _user_script_hash) =
+        helper::create_eth_eoa_account(&mut state, &user_eth_address, 2000000u64.into());
+    let user_address = RegistryAddress::new(ETH_REGISTRY_ACCOUNT_ID, user_eth_address.to_vec());
+    let to_eth_address = [2u8; 20];
+    helper::create_eth_eoa_account(&mut state, &to_eth_address, 0u64.into());
+    let sponsor_eth_address = [3u8; 20];
+    let sponsor_balance = U256::from(PER_USER_QUOTA * 10);
+    let (sponsor_id, _sponsor_script_hash) =
+        helper::create_eth_eoa_account(&mut state, &sponsor_eth_address, sponsor_balance);
+    let sponsor_address =
+        RegistryAddress::new(ETH_REGISTRY_ACCOUNT_ID, sponsor_eth_address.to_vec());
+
+    // Deploy ERC20, the user holds the initial supply
+    deploy(
+        &generator,
+        &store,
+        &mut state,
+        CREATOR_ACCOUNT_ID,
+        user_id,
+        INIT_CODE,
+        199694,
+        0,
+        block_producer.clone(),
+        1,
+    );
+    let erc20_contract = MockContractInfo::create(&user_eth_address, 0);
+    let erc20_id = state
+        .get_account_id_by_script_hash(&erc20_contract.script_hash)
+        .unwrap()
+        .unwrap();
+
+    let selectors = Byte4Vec::new_builder()
+        .push(Byte4::from_slice(&TRANSFER_SELECTOR).unwrap())
+        .build();
+    let policy = SponsorPolicy::new_builder()
+        .target(erc20_contract.script_hash.pack())
+        .selectors(selectors)
+        .gas_price(POLICY_GAS_PRICE.pack())
+        .per_user_quota(PER_USER_QUOTA.pack())
+        .build();
+    set_sponsor_policy(&mut state, sponsor_id, &policy);
+
+    let transfer_input = hex::decode(format!(
+        "a9059cbb{}0000000000000000000000000000000000000000000000000000000000000001",
+        hex::encode(eth_addr_to_ethabi_addr(&to_eth_address))
+    ))
+    .unwrap();
+    let call = |state: &mut crate::DummyState, block_number: u64, input: &[u8]| {
+        let block_info = new_block_info(block_producer.clone(), block_number, block_number);
+        let args = PolyjuiceArgsBuilder::default()
+            .gas_limit(100000)
+            .gas_price(0)
+            .value(0)
+            .input(input)
+            .build();
+        let raw_tx = RawL2Transaction::new_builder()
+            .from_id(user_id.pack())
+            .to_id(erc20_id.pack())
+            .args(Bytes::from(args).pack())
+            .build();
+        let db = &store.begin_transaction();
+        let tip_block_hash = db.get_tip_block_hash().unwrap();
+        let run_result = generator
+            .execute_transaction(
+                &ChainView::new(&db, tip_block_hash),
+                state,
+                &block_info,
+                &raw_tx,
+                L2TX_MAX_CYCLES,
+                None,
+            )
+            .expect("call ERC20");
+        state.finalise().expect("update state");
+        run_result
+    };
+    let balance_of = |state: &crate::DummyState, address: &RegistryAddress| {
+        state
+            .get_sudt_balance(CKB_SUDT_ACCOUNT_ID, address)
+            .unwrap()
+    };
+    let user_balance = balance_of(&state, &user_address);
+
+    // The sponsor pays `gas_used * policy.gas_price`
+    let run_result = call(&mut state, 2, &transfer_input);
+    let fee = U256::from(gas_used(&run_result.logs)) * U256::from(POLICY_GAS_PRICE);
+    assert!(!fee.is_zero());
+    assert_eq!(balance_of(&state, &sponsor_address), sponsor_balance - fee);
+    assert_eq!(balance_of(&state, &user_address), user_balance);
+    let used = get_sponsored_fee(&state, &erc20_contract.script_hash, user_id).unwrap();
+    assert_eq!(used, fee);
+
+    // The selector isn't sponsored, the user pays a zero fee
+    let balance_of_input = hex::decode(format!(
+        "70a08231{}",
+        hex::encode(eth_addr_to_ethabi_addr(&to_eth_address))
+    ))
+    .unwrap();
+    call(&mut state, 3, &balance_of_input);
+    assert_eq!(balance_of(&state, &sponsor_address), sponsor_balance - fee);
+    assert_eq!(balance_of(&state, &user_address), user_balance);
+
+    // The fee is limited by the remaining quota of the user
+    let remaining_quota = U256::from(1);
+    state
+        .update_value(
+            RESERVED_ACCOUNT_ID,
+            &sponsor_used_key(&erc20_contract.script_hash, user_id),
+            H256::from_u256(U256::from(PER_USER_QUOTA) - remaining_quota),
+        )
+        .unwrap();
+    call(&mut state, 4, &transfer_input);
+    assert_eq!(
+        balance_of(&state, &sponsor_address),
+        sponsor_balance - fee - remaining_quota
+    );
+    let used = get_sponsored_fee(&state, &erc20_contract.script_hash, user_id).unwrap();
+    assert_eq!(used, U256::from(PER_USER_QUOTA));
+}
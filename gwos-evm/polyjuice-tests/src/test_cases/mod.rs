@@ -38,5 +38,6 @@ mod address_collision;
 mod beacon_proxy;
 mod error;
 mod eth_addr_reg;
+mod fee_sponsorship;
 mod gas_price;
 mod utils;
@@ -9,11 +9,38 @@
 #include "ckb_syscalls.h"
 #include "gw_eth_addr_reg.h"
 #include "gw_syscalls.h"
-#include "sudt_utils.h"
+#include "sponsor_utils.h"
 
 /* MSG_TYPE */
 #define MSG_CREATE_ACCOUNT 0
 #define MSG_BATCH_CREATE_ACCOUNTS 1
+#define MSG_REGISTER_SESSION_KEY 2
+#define MSG_REVOKE_SESSION_KEY 3
+#define MSG_SET_SPONSOR_POLICY 4
+#define MSG_REMOVE_SPONSOR_POLICY 5
+
+/* Session keys of an account are stored in slots of the Meta contract,
+ * a slot holds the data hash of the SessionKey, zero if it's free.
+ *
+ * slot key: blake2b("session_key" | owner_id | slot index)
+ *
+ * The total cost spent by a session key is recorded by the generator under
+ * blake2b("session_key_spent" | owner_id | pubkey hash), it's reset to zero
+ * when the session key is registered or revoked.
+ */
+#define SESSION_KEY_SLOT_PREFIX "session_key"
+#define SESSION_KEY_SPENT_PREFIX "session_key_spent"
+#define MAX_SESSION_KEYS 8
+#define MAX_SESSION_KEY_ALLOWED_TARGETS 16
+#define MAX_SESSION_KEY_SIZE 1024
+#define SESSION_KEY_PUBKEY_HASH_SIZE 20
+#define NO_SESSION_KEY_SLOT 0xFF
+
+/* A sponsored contract has one sponsor, the Meta contract stores the sponsor
+ * id and the data hash of the SponsorPolicy. The fee paid by the sponsor for
+ * each user is recorded by `gw_sponsor_pay_fee` of the backends, see
+ * sponsor_utils.h for the storage format.
+ */
 
 int handle_fee(gw_context_t *ctx, uint32_t registry_id, uint256_t amount) {
   if (ctx == NULL) {
@@ -45,6 +72,261 @@ int handle_fee(gw_context_t *ctx, uint32_t registry_id, uint256_t amount) {
   return 0;
 }
 
+void session_key_slot_key(uint32_t owner_id, uint8_t index,
+                          uint8_t slot_key[GW_KEY_BYTES]) {
+  blake2b_state blake2b_ctx;
+  blake2b_init(&blake2b_ctx, GW_KEY_BYTES);
+  blake2b_update(&blake2b_ctx, (uint8_t *)SESSION_KEY_SLOT_PREFIX,
+                 sizeof(SESSION_KEY_SLOT_PREFIX) - 1);
+  blake2b_update(&blake2b_ctx, (uint8_t *)&owner_id, sizeof(uint32_t));
+  blake2b_update(&blake2b_ctx, &index, 1);
+  blake2b_final(&blake2b_ctx, slot_key, GW_KEY_BYTES);
+}
+
+int reset_session_key_spent(gw_context_t *ctx, uint32_t owner_id,
+                            const uint8_t *pubkey_hash) {
+  uint8_t spent_key[GW_KEY_BYTES] = {0};
+  blake2b_state blake2b_ctx;
+  blake2b_init(&blake2b_ctx, GW_KEY_BYTES);
+  blake2b_update(&blake2b_ctx, (uint8_t *)SESSION_KEY_SPENT_PREFIX,
+                 sizeof(SESSION_KEY_SPENT_PREFIX) - 1);
+  blake2b_update(&blake2b_ctx, (uint8_t *)&owner_id, sizeof(uint32_t));
+  blake2b_update(&blake2b_ctx, pubkey_hash, SESSION_KEY_PUBKEY_HASH_SIZE);
+  blake2b_final(&blake2b_ctx, spent_key, GW_KEY_BYTES);
+  uint8_t zero_value[GW_VALUE_BYTES] = {0};
+  return ctx->sys_store(ctx, ctx->transaction_context.to_id, spent_key,
+                        GW_KEY_BYTES, zero_value);
+}
+
+/* Find the slot of the session key by pubkey hash.
+ *
+ * found_slot: the slot of the session key, NO_SESSION_KEY_SLOT if not found
+ * free_slot: a free or expired slot, NO_SESSION_KEY_SLOT if all are in use
+ */
+int find_session_key_slot(gw_context_t *ctx, uint32_t owner_id,
+                          const uint8_t *pubkey_hash, uint8_t *found_slot,
+                          uint8_t *free_slot) {
+  *found_slot = NO_SESSION_KEY_SLOT;
+  *free_slot = NO_SESSION_KEY_SLOT;
+
+  uint8_t zero_hash[GW_VALUE_BYTES] = {0};
+  uint8_t slot_key[GW_KEY_BYTES] = {0};
+  uint8_t data_hash[GW_VALUE_BYTES] = {0};
+  uint8_t data[MAX_SESSION_KEY_SIZE] = {0};
+  for (uint8_t i = 0; i < MAX_SESSION_KEYS; i++) {
+    session_key_slot_key(owner_id, i, slot_key);
+    int ret = ctx->sys_load(ctx, ctx->transaction_context.to_id, slot_key,
+                            GW_KEY_BYTES, data_hash);
+    if (ret != 0) {
+      return ret;
+    }
+    if (memcmp(data_hash, zero_hash, GW_VALUE_BYTES) == 0) {
+      if (*free_slot == NO_SESSION_KEY_SLOT) {
+        *free_slot = i;
+      }
+      continue;
+    }
+
+    uint64_t len = MAX_SESSION_KEY_SIZE;
+    ret = ctx->sys_load_data(ctx, data_hash, &len, 0, data);
+    if (ret != 0) {
+      return ret;
+    }
+    mol_seg_t session_key_seg;
+    session_key_seg.ptr = data;
+    session_key_seg.size = len;
+    if (MolReader_SessionKey_verify(&session_key_seg, false) != MOL_OK) {
+      return GW_FATAL_INVALID_DATA;
+    }
+    mol_seg_t pubkey_hash_seg =
+        MolReader_SessionKey_get_pubkey_hash(&session_key_seg);
+    if (memcmp(pubkey_hash_seg.ptr, pubkey_hash,
+               SESSION_KEY_PUBKEY_HASH_SIZE) == 0) {
+      *found_slot = i;
+      continue;
+    }
+    mol_seg_t expiry_seg =
+        MolReader_SessionKey_get_expiry_block(&session_key_seg);
+    uint64_t expiry_block = 0;
+    _gw_fast_memcpy((uint8_t *)(&expiry_block), expiry_seg.ptr,
+                    sizeof(uint64_t));
+    if (expiry_block <= ctx->block_info.number &&
+        *free_slot == NO_SESSION_KEY_SLOT) {
+      *free_slot = i;
+    }
+  }
+  return 0;
+}
+
+int register_session_key(gw_context_t *ctx, mol_seg_t *session_key_seg) {
+  mol_seg_t expiry_seg = MolReader_SessionKey_get_expiry_block(session_key_seg);
+  uint64_t expiry_block = 0;
+  _gw_fast_memcpy((uint8_t *)(&expiry_block), expiry_seg.ptr,
+                  sizeof(uint64_t));
+  if (expiry_block <= ctx->block_info.number) {
+    ckb_debug("session key is already expired");
+    return GW_FATAL_INVALID_DATA;
+  }
+  mol_seg_t targets_seg =
+      MolReader_SessionKey_get_allowed_targets(session_key_seg);
+  uint32_t targets_len = MolReader_Byte32Vec_length(&targets_seg);
+  if (targets_len == 0 || targets_len > MAX_SESSION_KEY_ALLOWED_TARGETS) {
+    ckb_debug("invalid session key allowed targets");
+    return GW_FATAL_INVALID_DATA;
+  }
+  if (session_key_seg->size > MAX_SESSION_KEY_SIZE) {
+    return GW_FATAL_INVALID_DATA;
+  }
+
+  uint32_t owner_id = ctx->transaction_context.from_id;
+  mol_seg_t pubkey_hash_seg =
+      MolReader_SessionKey_get_pubkey_hash(session_key_seg);
+  uint8_t found_slot = NO_SESSION_KEY_SLOT;
+  uint8_t free_slot = NO_SESSION_KEY_SLOT;
+  int ret = find_session_key_slot(ctx, owner_id, pubkey_hash_seg.ptr,
+                                  &found_slot, &free_slot);
+  if (ret != 0) {
+    return ret;
+  }
+  /* replace the existing one */
+  uint8_t slot = found_slot != NO_SESSION_KEY_SLOT ? found_slot : free_slot;
+  if (slot == NO_SESSION_KEY_SLOT) {
+    ckb_debug("too many session keys");
+    return GW_FATAL_BUFFER_OVERFLOW;
+  }
+
+  ret = ctx->sys_store_data(ctx, session_key_seg->size, session_key_seg->ptr);
+  if (ret != 0) {
+    return ret;
+  }
+  uint8_t data_hash[GW_VALUE_BYTES] = {0};
+  blake2b_hash(data_hash, session_key_seg->ptr, session_key_seg->size);
+  uint8_t slot_key[GW_KEY_BYTES] = {0};
+  session_key_slot_key(owner_id, slot, slot_key);
+  ret = ctx->sys_store(ctx, ctx->transaction_context.to_id, slot_key,
+                       GW_KEY_BYTES, data_hash);
+  if (ret != 0) {
+    return ret;
+  }
+  return reset_session_key_spent(ctx, owner_id, pubkey_hash_seg.ptr);
+}
+
+int revoke_session_key(gw_context_t *ctx, mol_seg_t *pubkey_hash_seg) {
+  uint32_t owner_id = ctx->transaction_context.from_id;
+  uint8_t found_slot = NO_SESSION_KEY_SLOT;
+  uint8_t free_slot = NO_SESSION_KEY_SLOT;
+  int ret = find_session_key_slot(ctx, owner_id, pubkey_hash_seg->ptr,
+                                  &found_slot, &free_slot);
+  if (ret != 0) {
+    return ret;
+  }
+  if (found_slot == NO_SESSION_KEY_SLOT) {
+    ckb_debug("session key not found");
+    return GW_ERROR_NOT_FOUND;
+  }
+  uint8_t zero_hash[GW_VALUE_BYTES] = {0};
+  uint8_t slot_key[GW_KEY_BYTES] = {0};
+  session_key_slot_key(owner_id, found_slot, slot_key);
+  ret = ctx->sys_store(ctx, ctx->transaction_context.to_id, slot_key,
+                       GW_KEY_BYTES, zero_hash);
+  if (ret != 0) {
+    return ret;
+  }
+  return reset_session_key_spent(ctx, owner_id, pubkey_hash_seg->ptr);
+}
+
+int set_sponsor_policy(gw_context_t *ctx, mol_seg_t *policy_seg) {
+  if (policy_seg->size > MAX_SPONSOR_POLICY_SIZE) {
+    return GW_FATAL_INVALID_DATA;
+  }
+  mol_seg_t selectors_seg = MolReader_SponsorPolicy_get_selectors(policy_seg);
+  if (MolReader_Byte4Vec_length(&selectors_seg) >
+      MAX_SPONSOR_POLICY_SELECTORS) {
+    ckb_debug("too many sponsored selectors");
+    return GW_FATAL_INVALID_DATA;
+  }
+  mol_seg_t target_seg = MolReader_SponsorPolicy_get_target(policy_seg);
+  uint32_t target_id = 0;
+  int ret = ctx->sys_get_account_id_by_script_hash(ctx, target_seg.ptr,
+                                                   &target_id);
+  if (ret != 0) {
+    ckb_debug("sponsored contract not found");
+    return ret;
+  }
+
+  uint32_t sponsor_id = ctx->transaction_context.from_id;
+  uint32_t current_sponsor_id = 0;
+  ret = load_sponsor_id(ctx, target_seg.ptr, &current_sponsor_id);
+  if (ret != 0) {
+    return ret;
+  }
+  if (current_sponsor_id != 0 && current_sponsor_id != sponsor_id) {
+    ckb_debug("the contract is sponsored by another account");
+    return GW_FATAL_INVALID_CONTEXT;
+  }
+
+  ret = ctx->sys_store_data(ctx, policy_seg->size, policy_seg->ptr);
+  if (ret != 0) {
+    return ret;
+  }
+  uint8_t key[GW_KEY_BYTES] = {0};
+  uint8_t value[GW_VALUE_BYTES] = {0};
+  blake2b_hash(value, policy_seg->ptr, policy_seg->size);
+  sponsor_key(SPONSOR_POLICY_PREFIX, sizeof(SPONSOR_POLICY_PREFIX) - 1,
+              target_seg.ptr, key);
+  ret = ctx->sys_store(ctx, ctx->transaction_context.to_id, key, GW_KEY_BYTES,
+                       value);
+  if (ret != 0) {
+    return ret;
+  }
+  memset(value, 0, GW_VALUE_BYTES);
+  _gw_fast_memcpy(value, (uint8_t *)(&sponsor_id), sizeof(uint32_t));
+  sponsor_key(SPONSOR_PREFIX, sizeof(SPONSOR_PREFIX) - 1, target_seg.ptr, key);
+  return ctx->sys_store(ctx, ctx->transaction_context.to_id, key, GW_KEY_BYTES,
+                        value);
+}
+
+int remove_sponsor_policy(gw_context_t *ctx, mol_seg_t *target_seg) {
+  uint32_t current_sponsor_id = 0;
+  int ret = load_sponsor_id(ctx, target_seg->ptr, &current_sponsor_id);
+  if (ret != 0) {
+    return ret;
+  }
+  if (current_sponsor_id == 0 ||
+      current_sponsor_id != ctx->transaction_context.from_id) {
+    ckb_debug("sponsor policy not found");
+    return GW_ERROR_NOT_FOUND;
+  }
+  uint8_t zero_hash[GW_VALUE_BYTES] = {0};
+  uint8_t key[GW_KEY_BYTES] = {0};
+  sponsor_key(SPONSOR_POLICY_PREFIX, sizeof(SPONSOR_POLICY_PREFIX) - 1,
+              target_seg->ptr, key);
+  ret = ctx->sys_store(ctx, ctx->transaction_context.to_id, key, GW_KEY_BYTES,
+                       zero_hash);
+  if (ret != 0) {
+    return ret;
+  }
+  sponsor_key(SPONSOR_PREFIX, sizeof(SPONSOR_PREFIX) - 1, target_seg->ptr,
+              key);
+  return ctx->sys_store(ctx, ctx->transaction_context.to_id, key, GW_KEY_BYTES,
+                        zero_hash);
+}
+
+int handle_message_fee(gw_context_t *ctx, mol_seg_t *fee_seg) {
+  mol_seg_t amount_seg = MolReader_Fee_get_amount(fee_seg);
+  mol_seg_t reg_id_seg = MolReader_Fee_get_registry_id(fee_seg);
+
+  uint256_t fee_amount = {0};
+  _gw_fast_memcpy((uint8_t *)(&fee_amount), (uint8_t *)amount_seg.ptr,
+                  sizeof(uint128_t));
+
+  uint32_t reg_id = 0;
+  _gw_fast_memcpy((uint8_t *)(&reg_id), (uint8_t *)reg_id_seg.ptr,
+                  sizeof(uint32_t));
+
+  return handle_fee(ctx, reg_id, fee_amount);
+}
+
 int main() {
   /* initialize context */
   gw_context_t ctx = {0};
@@ -162,6 +444,60 @@ int main() {
       ckb_debug("failed to set return data to last created eth account id");
       return ret;
     }
+  } else if (msg.item_id == MSG_REGISTER_SESSION_KEY) {
+    mol_seg_t fee_seg = MolReader_RegisterSessionKey_get_fee(&msg.seg);
+    ret = handle_message_fee(&ctx, &fee_seg);
+    if (ret != 0) {
+      ckb_debug("failed to handle fee");
+      return ret;
+    }
+    mol_seg_t session_key_seg =
+        MolReader_RegisterSessionKey_get_session_key(&msg.seg);
+    ret = register_session_key(&ctx, &session_key_seg);
+    if (ret != 0) {
+      ckb_debug("failed to register session key");
+      return ret;
+    }
+  } else if (msg.item_id == MSG_REVOKE_SESSION_KEY) {
+    mol_seg_t fee_seg = MolReader_RevokeSessionKey_get_fee(&msg.seg);
+    ret = handle_message_fee(&ctx, &fee_seg);
+    if (ret != 0) {
+      ckb_debug("failed to handle fee");
+      return ret;
+    }
+    mol_seg_t pubkey_hash_seg =
+        MolReader_RevokeSessionKey_get_pubkey_hash(&msg.seg);
+    ret = revoke_session_key(&ctx, &pubkey_hash_seg);
+    if (ret != 0) {
+      ckb_debug("failed to revoke session key");
+      return ret;
+    }
+  } else if (msg.item_id == MSG_SET_SPONSOR_POLICY) {
+    mol_seg_t fee_seg = MolReader_SetSponsorPolicy_get_fee(&msg.seg);
+    ret = handle_message_fee(&ctx, &fee_seg);
+    if (ret != 0) {
+      ckb_debug("failed to handle fee");
+      return ret;
+    }
+    mol_seg_t policy_seg = MolReader_SetSponsorPolicy_get_policy(&msg.seg);
+    ret = set_sponsor_policy(&ctx, &policy_seg);
+    if (ret != 0) {
+      ckb_debug("failed to set sponsor policy");
+      return ret;
+    }
+  } else if (msg.item_id == MSG_REMOVE_SPONSOR_POLICY) {
+    mol_seg_t fee_seg = MolReader_RemoveSponsorPolicy_get_fee(&msg.seg);
+    ret = handle_message_fee(&ctx, &fee_seg);
+    if (ret != 0) {
+      ckb_debug("failed to handle fee");
+      return ret;
+    }
+    mol_seg_t target_seg = MolReader_RemoveSponsorPolicy_get_target(&msg.seg);
+    ret = remove_sponsor_policy(&ctx, &target_seg);
+    if (ret != 0) {
+      ckb_debug("failed to remove sponsor policy");
+      return ret;
+    }
   } else {
     return GW_FATAL_UNKNOWN_ARGS;
   }
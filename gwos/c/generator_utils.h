@@ -39,6 +39,14 @@
 #define GW_SYS_BN_ADD 3601
 #define GW_SYS_BN_MUL 3602
 #define GW_SYS_BN_PAIRING 3603
+/* Syscall for BLS12-381 curve operations (EIP-2537) */
+#define GW_SYS_BLS12_381_G1_ADD 3611
+#define GW_SYS_BLS12_381_G1_MSM 3612
+#define GW_SYS_BLS12_381_G2_ADD 3613
+#define GW_SYS_BLS12_381_G2_MSM 3614
+#define GW_SYS_BLS12_381_PAIRING 3615
+#define GW_SYS_BLS12_381_MAP_FP_TO_G1 3616
+#define GW_SYS_BLS12_381_MAP_FP2_TO_G2 3617
 /* Syscall state */
 #define GW_SYS_SNAPSHOT 3701
 #define GW_SYS_REVERT 3702
@@ -379,6 +387,56 @@ int sys_bn_pairing(const uint8_t *input, const size_t input_size,
                  input, input_size, 0);
 }
 
+/* BLS12-381 syscalls, G1 output is 128 bytes and G2 output is 256 bytes */
+int sys_bls12_381_g1_add(const uint8_t *input, const size_t input_size,
+                         uint8_t *output) {
+  volatile uint64_t output_len = 128;
+  return syscall(GW_SYS_BLS12_381_G1_ADD, output, &output_len, 0, input,
+                 input_size, 0);
+}
+
+int sys_bls12_381_g1_msm(const uint8_t *input, const size_t input_size,
+                         uint8_t *output) {
+  volatile uint64_t output_len = 128;
+  return syscall(GW_SYS_BLS12_381_G1_MSM, output, &output_len, 0, input,
+                 input_size, 0);
+}
+
+int sys_bls12_381_g2_add(const uint8_t *input, const size_t input_size,
+                         uint8_t *output) {
+  volatile uint64_t output_len = 256;
+  return syscall(GW_SYS_BLS12_381_G2_ADD, output, &output_len, 0, input,
+                 input_size, 0);
+}
+
+int sys_bls12_381_g2_msm(const uint8_t *input, const size_t input_size,
+                         uint8_t *output) {
+  volatile uint64_t output_len = 256;
+  return syscall(GW_SYS_BLS12_381_G2_MSM, output, &output_len, 0, input,
+                 input_size, 0);
+}
+
+int sys_bls12_381_pairing(const uint8_t *input, const size_t input_size,
+                          uint8_t *output) {
+  volatile uint64_t output_len = 32;
+  return syscall(GW_SYS_BLS12_381_PAIRING, output, &output_len, 0, input,
+                 input_size, 0);
+}
+
+int sys_bls12_381_map_fp_to_g1(const uint8_t *input, const size_t input_size,
+                               uint8_t *output) {
+  volatile uint64_t output_len = 128;
+  return syscall(GW_SYS_BLS12_381_MAP_FP_TO_G1, output, &output_len, 0, input,
+                 input_size, 0);
+}
+
+int sys_bls12_381_map_fp2_to_g2(const uint8_t *input, const size_t input_size,
+                                uint8_t *output) {
+  volatile uint64_t output_len = 256;
+  return syscall(GW_SYS_BLS12_381_MAP_FP2_TO_G2, output, &output_len, 0, input,
+                 input_size, 0);
+}
+
 int sys_log(gw_context_t *ctx, uint32_t account_id, uint8_t service_flag,
             uint64_t data_length, const uint8_t *data) {
   if (ctx == NULL) {
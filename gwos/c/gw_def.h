@@ -52,6 +52,10 @@ typedef unsigned __int128 uint128_t;
 
 #define GW_ALLOWED_EOA_UNKNOWN 0
 #define GW_ALLOWED_EOA_ETH 1
+#define GW_ALLOWED_EOA_WEBAUTHN 2
+#define GW_ALLOWED_EOA_MULTISIG 3
+/* max pubkey hashes of a multisig EOA */
+#define GW_MULTISIG_MAX_PUBKEY_HASHES 16
 
 #define GW_ALLOWED_CONTRACT_UNKNOWN 0
 #define GW_ALLOWED_CONTRACT_META 1
@@ -52,4 +52,10 @@
 /* Registry error */
 #define GW_REGISTRY_ERROR_DUPLICATE_MAPPING 101
 
+/* BLS12-381 syscall errors */
+#define GW_ERROR_BLS12_381_INVALID_INPUT_LENGTH 110
+#define GW_ERROR_BLS12_381_INVALID_FIELD_ELEMENT 111
+#define GW_ERROR_BLS12_381_POINT_NOT_ON_CURVE 112
+#define GW_ERROR_BLS12_381_POINT_NOT_IN_SUBGROUP 113
+
 #endif
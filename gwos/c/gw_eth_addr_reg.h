@@ -88,7 +88,8 @@ int gw_update_eth_address_register(
 /**
  * @brief register an account into `ETH Address Registry` by its script_hash
  *
- * Option 1: ETH EOA (externally owned account)
+ * Option 1: ETH EOA (externally owned account), including WebAuthn and
+ * multisig EOAs
  * Option 2: Polyjuice Contract Account
  *
  * @param ctx gw_context
@@ -159,15 +160,18 @@ int gw_register_eth_address(gw_context_t *ctx,
 
     mol_seg_t type_seg =
         MolReader_AllowedTypeHash_get_type_(&allowed_type_hash_res.seg);
-    if (*(uint8_t *)type_seg.ptr == GW_ALLOWED_EOA_ETH) {
+    /* WebAuthn EOA shares the ETH EOA args layout, the address is derived
+     * from its P-256 public key */
+    uint8_t eoa_type = *(uint8_t *)type_seg.ptr;
+    if (eoa_type == GW_ALLOWED_EOA_ETH ||
+        eoa_type == GW_ALLOWED_EOA_WEBAUTHN) {
       mol_seg_t eth_lock_code_hash_seg =
           MolReader_AllowedTypeHash_get_hash(&allowed_type_hash_res.seg);
 
       if (memcmp(script_code_hash_seg.ptr, eth_lock_code_hash_seg.ptr,
                  script_code_hash_seg.size) == 0) {
         ckb_debug(
-            "[gw_register_eth_address] This is an ETH externally owned "
-            "account");
+            "[gw_register_eth_address] This is an externally owned account");
         if (raw_bytes_seg.size != 52) {
           ckb_debug("[gw_register_eth_address] not eth_account_lock");
           return GW_FATAL_UNKNOWN_ARGS;
@@ -177,6 +181,43 @@ int gw_register_eth_address(gw_context_t *ctx,
         return gw_update_eth_address_register(ctx, eth_address, script_hash,
                                               false);
       }
+    } else if (eoa_type == GW_ALLOWED_EOA_MULTISIG) {
+      mol_seg_t multisig_lock_code_hash_seg =
+          MolReader_AllowedTypeHash_get_hash(&allowed_type_hash_res.seg);
+
+      if (memcmp(script_code_hash_seg.ptr, multisig_lock_code_hash_seg.ptr,
+                 script_code_hash_seg.size) == 0) {
+        /* args: rollup_script_hash | eth_address | threshold |
+         * pubkey_hashes_count | pubkey_hashes
+         *
+         * eth_address = blake2b(threshold | count | pubkey_hashes)[..20]
+         */
+        ckb_debug("[gw_register_eth_address] This is a multisig account");
+        if (raw_bytes_seg.size < 54) {
+          ckb_debug("[gw_register_eth_address] not multisig_account_lock");
+          return GW_FATAL_UNKNOWN_ARGS;
+        }
+        uint8_t threshold = raw_bytes_seg.ptr[52];
+        uint8_t count = raw_bytes_seg.ptr[53];
+        if (threshold == 0 || threshold > count ||
+            count > GW_MULTISIG_MAX_PUBKEY_HASHES ||
+            raw_bytes_seg.size != 54 + (uint32_t)count * GW_ETH_ADDRESS_LEN) {
+          ckb_debug("[gw_register_eth_address] invalid multisig config");
+          return GW_FATAL_UNKNOWN_ARGS;
+        }
+        uint8_t config_hash[GW_KEY_BYTES];
+        blake2b_hash(config_hash, raw_bytes_seg.ptr + 52,
+                     raw_bytes_seg.size - 52);
+        if (memcmp(config_hash, raw_bytes_seg.ptr + 32, GW_ETH_ADDRESS_LEN) !=
+            0) {
+          ckb_debug("[gw_register_eth_address] mismatch multisig address");
+          return GW_FATAL_UNKNOWN_ARGS;
+        }
+        _gw_fast_memcpy(eth_address, raw_bytes_seg.ptr + 32,
+                        GW_ETH_ADDRESS_LEN);
+        return gw_update_eth_address_register(ctx, eth_address, script_hash,
+                                              false);
+      }
     }
   }
 
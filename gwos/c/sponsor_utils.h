@@ -0,0 +1,264 @@
+/*
+ * Sponsor Utils
+ * This file provides the fee sponsorship of Godwoken layer2 contracts.
+ *
+ * A sponsor sets a SponsorPolicy of a contract through the Meta contract, the
+ * policy limits the sponsored function selectors, the gas price paid by the
+ * sponsor and the max fee paid for each user.
+ *
+ * Backends settle the fee of a transaction by `gw_sponsor_pay_fee` instead of
+ * `sudt_pay_fee`, so the sponsorship is executed by both the generator and the
+ * validator of the backend.
+ *
+ * ## Storage format
+ *
+ * All sponsorship states are stored in the Meta contract:
+ *
+ * sponsor key: blake2b("sponsor" | target), value: sponsor_id(4 bytes)
+ * policy key: blake2b("sponsor_policy" | target), value: data hash of policy
+ * used key: blake2b("sponsor_used" | target | user_id), value: uint256
+ */
+
+#ifndef GW_SPONSOR_UTILS_H_
+#define GW_SPONSOR_UTILS_H_
+
+#include "gw_def.h"
+#include "sudt_utils.h"
+#include "uint256.h"
+
+#define GW_META_CONTRACT_ACCOUNT_ID 0
+
+#define SPONSOR_PREFIX "sponsor"
+#define SPONSOR_POLICY_PREFIX "sponsor_policy"
+#define SPONSOR_USED_PREFIX "sponsor_used"
+#define MAX_SPONSOR_POLICY_SELECTORS 16
+#define MAX_SPONSOR_POLICY_SIZE 1024
+#define SPONSOR_SELECTOR_SIZE 4
+
+void sponsor_key(const char *prefix, uint32_t prefix_len,
+                 const uint8_t target[32], uint8_t key[GW_KEY_BYTES]) {
+  blake2b_state blake2b_ctx;
+  blake2b_init(&blake2b_ctx, GW_KEY_BYTES);
+  blake2b_update(&blake2b_ctx, (uint8_t *)prefix, prefix_len);
+  blake2b_update(&blake2b_ctx, target, 32);
+  blake2b_final(&blake2b_ctx, key, GW_KEY_BYTES);
+}
+
+void sponsor_used_key(const uint8_t target[32], uint32_t user_id,
+                      uint8_t key[GW_KEY_BYTES]) {
+  blake2b_state blake2b_ctx;
+  blake2b_init(&blake2b_ctx, GW_KEY_BYTES);
+  blake2b_update(&blake2b_ctx, (uint8_t *)SPONSOR_USED_PREFIX,
+                 sizeof(SPONSOR_USED_PREFIX) - 1);
+  blake2b_update(&blake2b_ctx, target, 32);
+  blake2b_update(&blake2b_ctx, (uint8_t *)(&user_id), sizeof(uint32_t));
+  blake2b_final(&blake2b_ctx, key, GW_KEY_BYTES);
+}
+
+/* Load the sponsor id of the target, 0 if it isn't sponsored */
+int load_sponsor_id(gw_context_t *ctx, const uint8_t target[32],
+                    uint32_t *sponsor_id) {
+  uint8_t key[GW_KEY_BYTES] = {0};
+  uint8_t value[GW_VALUE_BYTES] = {0};
+  sponsor_key(SPONSOR_PREFIX, sizeof(SPONSOR_PREFIX) - 1, target, key);
+  int ret = ctx->sys_load(ctx, GW_META_CONTRACT_ACCOUNT_ID, key, GW_KEY_BYTES,
+                          value);
+  if (ret != 0) {
+    return ret;
+  }
+  _gw_fast_memcpy((uint8_t *)sponsor_id, value, sizeof(uint32_t));
+  return 0;
+}
+
+/* a * b, the result never overflows */
+void _sponsor_mul_fee(uint64_t a, uint128_t b, uint256_t *result) {
+  uint32_t a_limbs[2] = {(uint32_t)a, (uint32_t)(a >> 32)};
+  uint32_t b_limbs[4] = {(uint32_t)b, (uint32_t)(b >> 32),
+                         (uint32_t)(b >> 64), (uint32_t)(b >> 96)};
+  gw_uint256_zero(result);
+  for (int i = 0; i < 2; i++) {
+    uint64_t carry = 0;
+    for (int j = 0; j < 4; j++) {
+      uint64_t product = (uint64_t)a_limbs[i] * b_limbs[j] +
+                         result->array[i + j] + carry;
+      result->array[i + j] = (uint32_t)product;
+      carry = product >> 32;
+    }
+    result->array[i + 4] = (uint32_t)carry;
+  }
+}
+
+void _sponsor_min(uint256_t *a, const uint256_t b) {
+  if (gw_uint256_cmp(*a, b) == GW_UINT256_LARGER) {
+    *a = b;
+  }
+}
+
+/* Returns if the call data matches the sponsored selectors of the policy */
+int _sponsor_policy_matches(mol_seg_t *policy_seg, const uint8_t *call_data,
+                            uint32_t call_data_len) {
+  mol_seg_t selectors_seg = MolReader_SponsorPolicy_get_selectors(policy_seg);
+  uint32_t selectors_len = MolReader_Byte4Vec_length(&selectors_seg);
+  if (selectors_len == 0) {
+    return 1;
+  }
+  if (call_data_len < SPONSOR_SELECTOR_SIZE) {
+    return 0;
+  }
+  for (uint32_t i = 0; i < selectors_len; i++) {
+    mol_seg_res_t selector_res = MolReader_Byte4Vec_get(&selectors_seg, i);
+    if (selector_res.errno != MOL_OK) {
+      return 0;
+    }
+    if (memcmp(selector_res.seg.ptr, call_data, SPONSOR_SELECTOR_SIZE) == 0) {
+      return 1;
+    }
+  }
+  return 0;
+}
+
+/* Charge the sponsor of the called contract `gas_used * policy.gas_price`,
+ * the fee is limited by the remaining quota of the user and the balance of
+ * the sponsor. The charged fee is recorded in the Meta contract.
+ *
+ * `sponsored` is 0 if the contract isn't sponsored, the call data doesn't
+ * match the policy or the sponsor isn't an ETH account.
+ */
+int _sponsor_pay_fee(gw_context_t *ctx, const uint32_t sudt_id,
+                     uint64_t gas_used, const uint8_t *call_data,
+                     uint32_t call_data_len, int *sponsored) {
+  *sponsored = 0;
+  uint8_t target[32] = {0};
+  int ret = ctx->sys_get_script_hash_by_account_id(
+      ctx, ctx->transaction_context.to_id, target);
+  if (ret != 0) {
+    return ret;
+  }
+  uint32_t sponsor_id = 0;
+  ret = load_sponsor_id(ctx, target, &sponsor_id);
+  if (ret != 0) {
+    return ret;
+  }
+  if (sponsor_id == 0) {
+    return 0;
+  }
+
+  /* load the policy */
+  uint8_t key[GW_KEY_BYTES] = {0};
+  uint8_t data_hash[GW_VALUE_BYTES] = {0};
+  sponsor_key(SPONSOR_POLICY_PREFIX, sizeof(SPONSOR_POLICY_PREFIX) - 1, target,
+              key);
+  ret = ctx->sys_load(ctx, GW_META_CONTRACT_ACCOUNT_ID, key, GW_KEY_BYTES,
+                      data_hash);
+  if (ret != 0) {
+    return ret;
+  }
+  uint8_t policy[MAX_SPONSOR_POLICY_SIZE] = {0};
+  uint64_t policy_len = MAX_SPONSOR_POLICY_SIZE;
+  ret = ctx->sys_load_data(ctx, data_hash, &policy_len, 0, policy);
+  if (ret != 0) {
+    return ret;
+  }
+  mol_seg_t policy_seg;
+  policy_seg.ptr = policy;
+  policy_seg.size = policy_len;
+  if (MolReader_SponsorPolicy_verify(&policy_seg, false) != MOL_OK) {
+    return GW_FATAL_INVALID_DATA;
+  }
+  if (!_sponsor_policy_matches(&policy_seg, call_data, call_data_len)) {
+    return 0;
+  }
+
+  /* the sponsor's registry address */
+  uint8_t sponsor_script_hash[32] = {0};
+  ret = ctx->sys_get_script_hash_by_account_id(ctx, sponsor_id,
+                                               sponsor_script_hash);
+  if (ret != 0) {
+    return ret;
+  }
+  gw_reg_addr_t sponsor_addr;
+  ret = ctx->sys_get_registry_address_by_script_hash(
+      ctx, sponsor_script_hash, GW_DEFAULT_ETH_REGISTRY_ACCOUNT_ID,
+      &sponsor_addr);
+  if (ret != 0) {
+    ckb_debug("sponsor has no registry address");
+    return 0;
+  }
+  *sponsored = 1;
+
+  /* fee = min(gas_used * gas_price, quota - used, balance) */
+  mol_seg_t gas_price_seg = MolReader_SponsorPolicy_get_gas_price(&policy_seg);
+  uint128_t gas_price = 0;
+  _gw_fast_memcpy((uint8_t *)(&gas_price), gas_price_seg.ptr,
+                  sizeof(uint128_t));
+  uint256_t fee;
+  _sponsor_mul_fee(gas_used, gas_price, &fee);
+
+  mol_seg_t quota_seg = MolReader_SponsorPolicy_get_per_user_quota(&policy_seg);
+  uint256_t quota;
+  gw_uint256_zero(&quota);
+  _gw_fast_memcpy((uint8_t *)(&quota), quota_seg.ptr, sizeof(uint128_t));
+  uint8_t used_key[GW_KEY_BYTES] = {0};
+  sponsor_used_key(target, ctx->transaction_context.from_id, used_key);
+  uint256_t used;
+  ret = ctx->sys_load(ctx, GW_META_CONTRACT_ACCOUNT_ID, used_key,
+                      GW_KEY_BYTES, (uint8_t *)(&used));
+  if (ret != 0) {
+    return ret;
+  }
+  uint256_t remaining_quota;
+  if (gw_uint256_underflow_sub(quota, used, &remaining_quota) != 0) {
+    gw_uint256_zero(&remaining_quota);
+  }
+  _sponsor_min(&fee, remaining_quota);
+
+  uint256_t balance;
+  ret = sudt_get_balance(ctx, sudt_id, sponsor_addr, &balance);
+  if (ret != 0) {
+    return ret;
+  }
+  _sponsor_min(&fee, balance);
+
+  uint256_t zero;
+  gw_uint256_zero(&zero);
+  if (gw_uint256_cmp(fee, zero) == GW_UINT256_EQUAL) {
+    return 0;
+  }
+  ret = sudt_pay_fee(ctx, sudt_id, sponsor_addr, fee);
+  if (ret != 0) {
+    return ret;
+  }
+  /* used never overflows, it's less than the quota */
+  uint256_t new_used;
+  gw_uint256_overflow_add(used, fee, &new_used);
+  return ctx->sys_store(ctx, GW_META_CONTRACT_ACCOUNT_ID, used_key,
+                        GW_KEY_BYTES, (uint8_t *)(&new_used));
+}
+
+/* Pay the fee of a contract call.
+ *
+ * A call with a zero gas price is sponsored, the sponsor of the called
+ * contract pays `gas_used * policy.gas_price`. Otherwise the payer pays
+ * `gas_used * gas_price`, which is zero for a call that isn't sponsored.
+ *
+ * The caller must only pass calls to a contract, not contract creations or
+ * native transfers.
+ */
+int gw_sponsor_pay_fee(gw_context_t *ctx, const uint32_t sudt_id,
+                       gw_reg_addr_t payer_addr, uint64_t gas_used,
+                       uint128_t gas_price, const uint8_t *call_data,
+                       uint32_t call_data_len) {
+  if (gas_price == 0) {
+    int sponsored = 0;
+    int ret = _sponsor_pay_fee(ctx, sudt_id, gas_used, call_data,
+                               call_data_len, &sponsored);
+    if (ret != 0 || sponsored) {
+      return ret;
+    }
+  }
+  uint256_t fee;
+  _sponsor_mul_fee(gas_used, gas_price, &fee);
+  return sudt_pay_fee(ctx, sudt_id, payer_addr, fee);
+}
+
+#endif /* GW_SPONSOR_UTILS_H_ */
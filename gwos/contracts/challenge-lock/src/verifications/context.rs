@@ -17,9 +17,9 @@ use gw_utils::{
     gw_types::packed::CKBMerkleProof,
 };
 
-pub struct TxContextInput<'a> {
+pub struct TxContextInput<'a, 'b> {
     pub tx: L2Transaction,
-    pub kv_state: KVState<'a>,
+    pub kv_state: &'a KVState<'b>,
     pub scripts: ScriptVec,
     pub raw_block: RawL2Block,
     pub rollup_config: &'a RollupConfig,
@@ -74,16 +74,28 @@ impl EIP712Encode for WithdrawalAsset {
 #[derive(Debug)]
 pub enum AddressRegistry {
     ETH,
+    ScriptHash,
 }
 
 impl AddressRegistry {
     fn to_string(&self) -> &str {
-        "ETH"
+        match self {
+            Self::ETH => "ETH",
+            Self::ScriptHash => "SCRIPT_HASH",
+        }
+    }
+
+    fn address_len(&self) -> usize {
+        match self {
+            Self::ETH => 20,
+            Self::ScriptHash => 32,
+        }
     }
 
     pub fn from_registry_id(registry_id: u32) -> Result<Self, Error> {
         match registry_id {
             gw_utils::gw_common::builtins::ETH_REGISTRY_ACCOUNT_ID => Ok(Self::ETH),
+            gw_utils::gw_common::builtins::SCRIPT_HASH_REGISTRY_ACCOUNT_ID => Ok(Self::ScriptHash),
             _ => {
                 debug!("Unsupported registry id : {}", registry_id);
                 Err(Error::InvalidArgs)
@@ -95,7 +107,7 @@ impl AddressRegistry {
 #[derive(Debug)]
 pub struct RegistryAddress {
     registry: AddressRegistry,
-    address: [u8; 20],
+    address: Vec<u8>,
 }
 
 impl RegistryAddress {
@@ -103,16 +115,18 @@ impl RegistryAddress {
         address: gw_utils::gw_common::registry_address::RegistryAddress,
     ) -> Result<Self, Error> {
         let registry = AddressRegistry::from_registry_id(address.registry_id)?;
-        if address.address.len() != 20 {
+        if address.address.len() != registry.address_len() {
             debug!(
-                "Invalid ETH address len, expected 20, got {}",
+                "Invalid {} address len, expected {}, got {}",
+                registry.to_string(),
+                registry.address_len(),
                 address.address.len()
             );
             return Err(Error::InvalidArgs);
         }
         Ok(RegistryAddress {
             registry,
-            address: address.address.try_into().expect("eth address"),
+            address: address.address,
         })
     }
 }
@@ -123,7 +137,12 @@ impl EIP712Encode for RegistryAddress {
     }
 
     fn encode_type(&self, buf: &mut Vec<u8>) {
-        buf.extend(b"RegistryAddress(string registry,address address)");
+        match self.registry {
+            AddressRegistry::ETH => buf.extend(b"RegistryAddress(string registry,address address)"),
+            AddressRegistry::ScriptHash => {
+                buf.extend(b"RegistryAddress(string registry,bytes32 address)")
+            }
+        }
     }
 
     fn encode_data(&self, buf: &mut Vec<u8>) {
@@ -134,7 +153,16 @@ impl EIP712Encode for RegistryAddress {
             hasher.finalize().into()
         };
         buf.extend(ethabi::encode(&[Token::Uint(registry.into())]));
-        buf.extend(ethabi::encode(&[Token::Address(self.address.into())]));
+        match self.registry {
+            AddressRegistry::ETH => {
+                let address = ethabi::Address::from_slice(&self.address);
+                buf.extend(ethabi::encode(&[Token::Address(address)]));
+            }
+            AddressRegistry::ScriptHash => {
+                let address: [u8; 32] = self.address.as_slice().try_into().expect("script hash");
+                buf.extend(ethabi::encode(&[Token::Uint(address.into())]));
+            }
+        }
     }
 }
 
@@ -1,5 +1,6 @@
 pub mod context;
 mod eip712;
+mod session_key;
 pub mod tx_execution;
 pub mod tx_signature;
 pub mod withdrawal;
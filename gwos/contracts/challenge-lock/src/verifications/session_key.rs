@@ -0,0 +1,220 @@
+use core::convert::TryFrom;
+use core::result::Result;
+use gw_state::kv_state::KVState;
+use gw_types::{
+    core::{AllowedContractType, AllowedEoaType, ScriptHashType, SigningType},
+    h256::H256,
+    packed::{
+        ETHAddrRegArgsReader, ETHAddrRegArgsUnionReader, MetaContractArgsReader,
+        MetaContractArgsUnionReader, RawL2Transaction, RollupConfig, SUDTArgsReader,
+        SUDTArgsUnionReader, Script, SessionKey,
+    },
+    prelude::*,
+    U256,
+};
+use gw_utils::gw_common::{
+    blake2b::new_blake2b,
+    builtins::{CKB_SUDT_ACCOUNT_ID, RESERVED_ACCOUNT_ID},
+    session_key::{
+        check_session_key_limits, get_session_key_spent, session_key_slot_key, MAX_SESSION_KEYS,
+    },
+    state::State,
+};
+use gw_utils::{
+    ckb_std::{ckb_types::bytes::Bytes, debug},
+    error::Error,
+    gw_types,
+    signature::check_l2_account_signature_cell,
+};
+
+pub struct SessionKeyInput<'a, 'b> {
+    pub rollup_config: &'a RollupConfig,
+    pub kv_state: &'a KVState<'b>,
+    pub block_number: u64,
+    pub raw_tx: &'a RawL2Transaction,
+    pub sender: &'a Script,
+    pub receiver: &'a Script,
+    pub receiver_script_hash: &'a H256,
+    pub session_key: SessionKey,
+}
+
+/// Verify the tx is signed by a session key of the ETH sender.
+///
+/// The session key must be registered in a slot of the Meta contract in the
+/// kv state, and the tx must be within its limits. The kv state must include
+/// the spent of the session key too. The signature is verified
+/// by the ETH account lock of the session key, which must be in the inputs.
+pub fn verify_session_key_signature(
+    input: SessionKeyInput,
+    signing_type: SigningType,
+    message: H256,
+) -> Result<(), Error> {
+    let SessionKeyInput {
+        rollup_config,
+        kv_state,
+        block_number,
+        raw_tx,
+        sender,
+        receiver,
+        receiver_script_hash,
+        session_key,
+    } = input;
+
+    if !is_eoa_type(rollup_config, sender, AllowedEoaType::Eth) {
+        debug!("session keys are only supported by ETH accounts");
+        return Err(Error::UnknownEOAScript);
+    }
+
+    // the session key must be registered by the sender
+    let data_hash: H256 = {
+        let mut hash = [0u8; 32];
+        let mut hasher = new_blake2b();
+        hasher.update(session_key.as_slice());
+        hasher.finalize(&mut hash);
+        hash
+    };
+    let sender_id: u32 = raw_tx.from_id().unpack();
+    let is_registered = (0..MAX_SESSION_KEYS).any(|index| {
+        let slot_key = session_key_slot_key(sender_id, index);
+        // slots not in the kv state are skipped
+        kv_state.get_value(RESERVED_ACCOUNT_ID, &slot_key).ok() == Some(data_hash)
+    });
+    if !is_registered {
+        debug!("session key isn't registered by the sender");
+        return Err(Error::SMTKeyMissing);
+    }
+
+    // the cost spent by the session key before the tx
+    let spent = get_session_key_spent(kv_state, sender_id, &session_key).map_err(|_err| {
+        debug!("session key spent isn't in the kv state: {:?}", _err);
+        Error::SMTKeyMissing
+    })?;
+
+    let to_id: u32 = raw_tx.to_id().unpack();
+    let cost = tx_cost(rollup_config, receiver, raw_tx);
+    check_session_key_limits(
+        &session_key,
+        block_number,
+        to_id,
+        receiver_script_hash,
+        cost,
+        spent,
+    )
+    .map_err(|_err| {
+        debug!("tx exceeds the limits of the session key: {:?}", _err);
+        Error::WrongSignature
+    })?;
+
+    // the session key's lock, args: rollup_script_hash | pubkey_hash
+    let sender_args: Bytes = sender.args().unpack();
+    if sender_args.len() < 32 {
+        return Err(Error::InvalidArgs);
+    }
+    let mut args = [0u8; 52];
+    args[..32].copy_from_slice(&sender_args[..32]);
+    args[32..].copy_from_slice(session_key.pubkey_hash().as_slice());
+    let signer = Script::new_builder()
+        .code_hash(sender.code_hash())
+        .hash_type(ScriptHashType::Type.into())
+        .args(args[..].pack())
+        .build();
+    check_l2_account_signature_cell(&signer.hash(), signing_type, message)
+}
+
+fn is_eoa_type(rollup_config: &RollupConfig, script: &Script, expected: AllowedEoaType) -> bool {
+    rollup_config
+        .allowed_eoa_type_hashes()
+        .into_iter()
+        .any(|type_hash| {
+            let type_: u8 = type_hash.type_().into();
+            type_ == u8::from(expected) && type_hash.hash() == script.code_hash()
+        })
+}
+
+/// Transfer value plus the maximum fee of the tx, must be the same as the
+/// `cost` of typed transactions in the generator.
+fn tx_cost(
+    rollup_config: &RollupConfig,
+    receiver: &Script,
+    raw_tx: &RawL2Transaction,
+) -> Option<U256> {
+    let type_: u8 = rollup_config
+        .allowed_contract_type_hashes()
+        .into_iter()
+        .find(|type_hash| type_hash.hash() == receiver.code_hash())?
+        .type_()
+        .into();
+    let args: Bytes = raw_tx.args().unpack();
+    match AllowedContractType::try_from(type_).ok()? {
+        AllowedContractType::Meta => {
+            use MetaContractArgsUnionReader::*;
+            let args = MetaContractArgsReader::from_slice(&args).ok()?;
+            let fee = match args.to_enum() {
+                CreateAccount(args) => args.fee().amount().unpack(),
+                BatchCreateEthAccounts(args) => args.fee().amount().unpack(),
+                RegisterSessionKey(args) => args.fee().amount().unpack(),
+                RevokeSessionKey(args) => args.fee().amount().unpack(),
+                SetSponsorPolicy(args) => args.fee().amount().unpack(),
+                RemoveSponsorPolicy(args) => args.fee().amount().unpack(),
+            };
+            Some(U256::from(fee))
+        }
+        AllowedContractType::Sudt => {
+            let args = SUDTArgsReader::from_slice(&args).ok()?;
+            match args.to_enum() {
+                SUDTArgsUnionReader::SUDTQuery(_) => None,
+                SUDTArgsUnionReader::SUDTTransfer(args) => {
+                    let fee: u128 = args.fee().amount().unpack();
+                    let to_id: u32 = raw_tx.to_id().unpack();
+                    if to_id == CKB_SUDT_ACCOUNT_ID {
+                        let value: U256 = args.amount().unpack();
+                        value.checked_add(U256::from(fee))
+                    } else {
+                        Some(U256::from(fee))
+                    }
+                }
+            }
+        }
+        AllowedContractType::Polyjuice => polyjuice_tx_cost(&args),
+        AllowedContractType::EthAddrReg => {
+            use ETHAddrRegArgsUnionReader::*;
+            let args = ETHAddrRegArgsReader::from_slice(&args).ok()?;
+            match args.to_enum() {
+                EthToGw(_) | GwToEth(_) => None,
+                SetMapping(args) => Some(U256::from(args.fee().amount().unpack())),
+                BatchSetMapping(args) => Some(U256::from(args.fee().amount().unpack())),
+            }
+        }
+        AllowedContractType::ScriptHashAddrReg | AllowedContractType::Unknown => None,
+    }
+}
+
+/// value + gas_price * gas_limit
+fn polyjuice_tx_cost(args: &[u8]) -> Option<U256> {
+    if args.len() < 52 || args[0..7] != b"\xFF\xFF\xFFPOLY"[..] {
+        return None;
+    }
+    let read_u128 = |data: &[u8]| {
+        let mut buf = [0u8; 16];
+        buf.copy_from_slice(data);
+        u128::from_le_bytes(buf)
+    };
+    let gas_limit = {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(&args[8..16]);
+        u64::from_le_bytes(buf)
+    };
+    let gas_price = read_u128(&args[16..32]);
+    let value = read_u128(&args[32..48]);
+    let data_size = {
+        let mut buf = [0u8; 4];
+        buf.copy_from_slice(&args[48..52]);
+        u32::from_le_bytes(buf)
+    } as usize;
+    // a transfer tx carries the 20 bytes to_address after the data
+    if args.len() != 52 + data_size && args.len() != 52 + data_size + 20 {
+        return None;
+    }
+    let cost = value.checked_add(gas_price.checked_mul(gas_limit.into())?)?;
+    Some(U256::from(cost))
+}
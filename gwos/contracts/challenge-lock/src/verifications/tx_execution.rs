@@ -53,7 +53,7 @@ pub fn verify_tx_execution(
 
     let input = TxContextInput {
         tx,
-        kv_state,
+        kv_state: &kv_state,
         scripts,
         raw_block,
         rollup_config,
@@ -1,5 +1,6 @@
 use crate::verifications::context::{verify_tx_context, TxContext, TxContextInput};
 use crate::verifications::eip712::{traits::EIP712Encode, types::EIP712Domain};
+use crate::verifications::session_key::{verify_session_key_signature, SessionKeyInput};
 use alloc::vec;
 use core::result::Result;
 use gw_state::{ckb_smt::smt::Pair, constants::GW_MAX_KV_PAIRS, kv_state::KVState};
@@ -16,9 +17,10 @@ use gw_utils::{
     },
     error::Error,
     gw_types::{
-        core::SigningType,
+        core::{AllowedEoaType, SigningType},
         packed::{
             CCTransactionSignatureWitness, CCTransactionSignatureWitnessReader, Script, ScriptVec,
+            SessionKey, SessionKeyReader,
         },
     },
     signature::check_l2_account_signature_cell,
@@ -31,7 +33,19 @@ pub fn verify_tx_signature(
     rollup_config: &RollupConfig,
     lock_args: &ChallengeLockArgs,
 ) -> Result<(), Error> {
-    let witness_args: Bytes = load_witness_args(0, Source::GroupInput)?
+    let witness_args = load_witness_args(0, Source::GroupInput)?;
+    // txs signed by a session key carry the session key in the input type
+    let session_key = match witness_args.input_type().to_opt() {
+        Some(data) => {
+            let data: Bytes = data.unpack();
+            match SessionKeyReader::verify(&data, false) {
+                Ok(_) => Some(SessionKey::new_unchecked(data)),
+                Err(_) => return Err(Error::InvalidArgs),
+            }
+        }
+        None => None,
+    };
+    let witness_args: Bytes = witness_args
         .lock()
         .to_opt()
         .ok_or(Error::InvalidArgs)?
@@ -68,12 +82,13 @@ pub fn verify_tx_signature(
         .build();
     let target = lock_args.target();
     let raw_block = unlock_args.raw_l2block();
+    let block_number: u64 = raw_block.number().unpack();
     let tx_proof = unlock_args.tx_proof();
     let raw_tx = tx.raw();
 
     let input = TxContextInput {
         tx,
-        kv_state,
+        kv_state: &kv_state,
         scripts,
         raw_block,
         rollup_config,
@@ -85,11 +100,18 @@ pub fn verify_tx_signature(
         sender_script_hash,
         receiver_script_hash,
         receiver,
-        sender: _,
+        sender,
         sender_address,
     } = verify_tx_context(input)?;
 
-    let (message, signing_type) = match try_assemble_polyjuice_args(&raw_tx, receiver) {
+    // WebAuthn and multisig EOAs always sign the EIP-712 message, even for polyjuice txs.
+    let polyjuice_args = if is_eip712_only_sender(rollup_config, &sender) {
+        None
+    } else {
+        try_assemble_polyjuice_args(&raw_tx, receiver.clone())
+    };
+
+    let (message, signing_type) = match polyjuice_args {
         Some(rlp_data) => {
             let mut hasher = Keccak256::new();
             hasher.update(&*rlp_data);
@@ -101,7 +123,7 @@ pub fn verify_tx_signature(
         None => {
             let chain_id = raw_tx.chain_id().unpack();
             let typed_tx = crate::verifications::eip712::types::L2Transaction::from_raw(
-                raw_tx,
+                raw_tx.clone(),
                 sender_address,
                 receiver_script_hash,
             )?;
@@ -112,8 +134,21 @@ pub fn verify_tx_signature(
     };
 
     // verify sender's script is in the input
-    check_l2_account_signature_cell(&sender_script_hash, signing_type, message)?;
-    Ok(())
+    let session_key = match session_key {
+        Some(session_key) => session_key,
+        None => return check_l2_account_signature_cell(&sender_script_hash, signing_type, message),
+    };
+    let input = SessionKeyInput {
+        rollup_config,
+        kv_state: &kv_state,
+        block_number,
+        raw_tx: &raw_tx,
+        sender: &sender,
+        receiver: &receiver,
+        receiver_script_hash: &receiver_script_hash,
+        session_key,
+    };
+    verify_session_key_signature(input, signing_type, message)
 }
 
 fn try_assemble_polyjuice_args(
@@ -183,3 +218,15 @@ fn try_assemble_polyjuice_args(
     stream.finalize_unbounded_list();
     Some(Bytes::from(stream.out().to_vec()))
 }
+
+fn is_eip712_only_sender(rollup_config: &RollupConfig, sender: &Script) -> bool {
+    rollup_config
+        .allowed_eoa_type_hashes()
+        .into_iter()
+        .any(|type_hash| {
+            let type_: u8 = type_hash.type_().into();
+            (type_ == u8::from(AllowedEoaType::WebAuthn)
+                || type_ == u8::from(AllowedEoaType::Multisig))
+                && type_hash.hash() == sender.code_hash()
+        })
+}
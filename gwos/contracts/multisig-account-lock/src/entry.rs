@@ -0,0 +1,86 @@
+// Import from `core` instead of from `std` since we are in no-std mode
+use core::{convert::TryFrom, result::Result};
+
+// Import CKB syscalls and structures
+// https://nervosnetwork.github.io/ckb-std/riscv64imac-unknown-none-elf/doc/ckb_std/index.html
+use crate::{
+    ckb_std::{
+        ckb_constants::Source,
+        ckb_types::{bytes::Bytes, prelude::Unpack as CKBUnpack},
+        debug,
+        high_level::load_script,
+        syscalls::load_cell_data,
+    },
+    multisig::{eth_prefixed_message, extract_multisig_lock_args, verify_signatures},
+};
+use gw_utils::{
+    cells::utils::search_lock_hash,
+    ckb_std::high_level::load_witness_args,
+    error::Error,
+    gw_types::{core::SigningType, h256::H256},
+};
+
+/// Multisig account lock
+/// script args: rollup_script_hash(32 bytes) | eth_address(20 bytes) | threshold(1 byte) |
+///     pubkey_hashes_count(1 byte) | pubkey_hashes(20 bytes * count)
+/// data: onetime_owner_lock_hash(32 bytes) | signing type (1 byte) | message(32 bytes)
+/// witness lock: signatures(65 bytes * threshold)
+pub fn main() -> Result<(), Error> {
+    // parse args
+    let script = load_script()?;
+    let args: Bytes = CKBUnpack::unpack(&script.args());
+    let config = extract_multisig_lock_args(args)?;
+    debug!("multisig threshold {}", config.threshold);
+
+    // parse data
+    let (onetime_owner_lock_hash, signing_type, message) = parse_data()?;
+
+    // check owner lock hash cell
+    // to prevent others unlock this cell
+    if search_lock_hash(&onetime_owner_lock_hash, Source::Input).is_none() {
+        return Err(Error::OwnerCellNotFound);
+    }
+
+    // verify signatures
+    debug!("Verify message signatures {:?}", &message);
+    let witness_args = load_witness_args(0, Source::GroupInput)?;
+    let signatures: Bytes = witness_args
+        .lock()
+        .to_opt()
+        .ok_or(Error::WrongSignature)?
+        .unpack();
+    let message = match signing_type {
+        SigningType::WithPrefix => eth_prefixed_message(message),
+        SigningType::Raw => message,
+    };
+    verify_signatures(&config, &signatures, message)?;
+
+    Ok(())
+}
+
+/// parse cell's data
+/// return (onetime_owner_lock_hash, sign type, message)
+fn parse_data() -> Result<([u8; 32], SigningType, H256), Error> {
+    let mut data = [0u8; 65];
+    let loaded_size = load_cell_data(&mut data, 0, 0, Source::GroupInput)?;
+
+    if loaded_size != 65 {
+        debug!("Invalid data size: {}", loaded_size);
+        return Err(Error::Encoding);
+    }
+
+    // copy owner lock hash
+    let mut owner_lock_hash = [0u8; 32];
+    owner_lock_hash.copy_from_slice(&data[..32]);
+
+    // copy message
+    let signing_type = SigningType::try_from(data[32]).map_err(|err| {
+        debug!("Invalid signature message type {}", err);
+        Error::Encoding
+    })?;
+
+    let mut msg = [0u8; 32];
+    msg.copy_from_slice(&data[33..65]);
+
+    Ok((owner_lock_hash, signing_type, msg.into()))
+}
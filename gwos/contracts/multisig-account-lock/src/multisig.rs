@@ -0,0 +1,99 @@
+//! M-of-N secp256k1 Eth multisig implementation
+
+use gw_utils::{
+    ckb_std::debug,
+    error::Error,
+    gw_common::registry::eth_registry::extract_eth_address_from_multisig_eoa,
+    gw_types::{bytes::Bytes, h256::H256},
+};
+use secp256k1_utils::recover_uncompressed_key;
+use sha3::{Digest, Keccak256};
+
+pub type EthAddress = [u8; 20];
+
+const SIGNATURE_SIZE: usize = 65;
+// rollup_script_hash | eth_address | threshold | pubkey_hashes_count
+const PUBKEY_HASHES_OFFSET: usize = 54;
+
+pub struct MultisigConfig {
+    pub threshold: usize,
+    pub pubkey_hashes: Bytes,
+}
+
+impl MultisigConfig {
+    fn contains(&self, pubkey_hash: &EthAddress) -> Option<usize> {
+        self.pubkey_hashes
+            .chunks_exact(20)
+            .position(|h| h == pubkey_hash)
+    }
+}
+
+/// Parse and validate the multisig lock args
+pub fn extract_multisig_lock_args(lock_args: Bytes) -> Result<MultisigConfig, Error> {
+    extract_eth_address_from_multisig_eoa(&lock_args).map_err(|_| {
+        debug!("Invalid multisig lock args len: {}", lock_args.len());
+        Error::InvalidArgs
+    })?;
+    Ok(MultisigConfig {
+        threshold: lock_args[52] as usize,
+        pubkey_hashes: lock_args.slice(PUBKEY_HASHES_OFFSET..),
+    })
+}
+
+/// Verify `threshold` signatures of `message`, each one must be signed by a
+/// different pubkey hash of the config.
+pub fn verify_signatures(
+    config: &MultisigConfig,
+    signatures: &[u8],
+    message: H256,
+) -> Result<(), Error> {
+    if signatures.len() != config.threshold * SIGNATURE_SIZE {
+        debug!(
+            "signatures len: {}, expected len: {}",
+            signatures.len(),
+            config.threshold * SIGNATURE_SIZE
+        );
+        return Err(Error::WrongSignature);
+    }
+
+    // at most 16 pubkey hashes
+    let mut signed: u16 = 0;
+    for signature in signatures.chunks_exact(SIGNATURE_SIZE) {
+        let mut buf = [0u8; SIGNATURE_SIZE];
+        buf.copy_from_slice(signature);
+        let pubkey_hash = recover_pubkey_hash(buf, message)?;
+        let index = config.contains(&pubkey_hash).ok_or_else(|| {
+            debug!("Signer {:?} is not a member", pubkey_hash);
+            Error::WrongSignature
+        })?;
+        if signed & (1 << index) != 0 {
+            debug!("Duplicated signer {:?}", pubkey_hash);
+            return Err(Error::WrongSignature);
+        }
+        signed |= 1 << index;
+    }
+    Ok(())
+}
+
+pub fn eth_prefixed_message(message: H256) -> H256 {
+    let mut hasher = Keccak256::new();
+    hasher.update("\x19Ethereum Signed Message:\n32");
+    hasher.update(message.as_slice());
+    let buf = hasher.finalize();
+    let mut signing_message = [0u8; 32];
+    signing_message.copy_from_slice(&buf[..]);
+    signing_message.into()
+}
+
+fn recover_pubkey_hash(signature: [u8; 65], message: H256) -> Result<EthAddress, Error> {
+    let pubkey = recover_uncompressed_key(message.into(), signature).map_err(|err| {
+        debug!("failed to recover secp256k1 pubkey, error number: {}", err);
+        Error::WrongSignature
+    })?;
+    let mut hasher = Keccak256::new();
+    hasher.update(&pubkey[1..]);
+    let buf = hasher.finalize();
+    let mut pubkey_hash = [0u8; 20];
+    pubkey_hash.copy_from_slice(&buf[12..]);
+    Ok(pubkey_hash)
+}
@@ -248,9 +248,7 @@ fn check_layer2_deposit(
     kv_state: &mut KVState,
     deposit_cells: &[DepositRequestCell],
 ) -> Result<(), Error> {
-    let registry_ctx = gw_common::registry::context::RegistryContext::new(
-        config.allowed_eoa_type_hashes().into_iter().collect(),
-    );
+    let registry_ctx = gw_common::registry::context::RegistryContext::from_rollup_config(config);
     for request in deposit_cells {
         // check that account's script is a valid EOA script
         if request.account_script.hash_type() != ScriptHashType::Type.into() {
@@ -270,11 +268,8 @@ fn check_layer2_deposit(
                 // account isn't exist
                 let _new_id = kv_state.create_account(request.account_script_hash)?;
                 let script = &request.account_script;
-                let addr = registry_ctx.extract_registry_address_from_deposit(
-                    registry_id,
-                    &script.code_hash(),
-                    &script.args().raw_data(),
-                )?;
+                let addr =
+                    registry_ctx.extract_registry_address_from_deposit(registry_id, script)?;
                 // mapping addr to script hash
                 kv_state.mapping_registry_address_to_script_hash(
                     addr.clone(),
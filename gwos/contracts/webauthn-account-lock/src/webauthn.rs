@@ -0,0 +1,173 @@
+//! WebAuthn (secp256r1) assertion verification
+
+use core::convert::TryFrom;
+
+use gw_utils::{
+    ckb_std::debug,
+    error::Error,
+    gw_common::webauthn,
+    gw_types::{bytes::Bytes, h256::H256, packed::WebAuthnSignatureReader, prelude::*},
+};
+use p256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+use p256::EncodedPoint;
+use sha2::Sha256;
+use sha3::{Digest, Keccak256};
+
+pub type PubkeyHash = [u8; 20];
+
+const PUBKEY_SIZE: usize = 64;
+const SIGNATURE_SIZE: usize = 64;
+// rp id hash (32 bytes) | flags (1 byte) | sign count (4 bytes)
+const MIN_AUTHENTICATOR_DATA_SIZE: usize = 37;
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+// base64url of 32 bytes without padding
+const CHALLENGE_SIZE: usize = 43;
+
+pub fn extract_webauthn_lock_args(lock_args: Bytes) -> Result<(H256, PubkeyHash), Error> {
+    if lock_args.len() != 52 {
+        debug!("Invalid lock args len: {}", lock_args.len());
+        return Err(Error::InvalidArgs);
+    }
+    let rollup_script_hash = {
+        let mut buf = [0u8; 32];
+        buf.copy_from_slice(&lock_args[..32]);
+        buf.into()
+    };
+    let pubkey_hash = {
+        let mut buf = [0u8; 20];
+        buf.copy_from_slice(&lock_args[32..]);
+        buf
+    };
+    Ok((rollup_script_hash, pubkey_hash))
+}
+
+/// keccak256(rp_id_hash | x | y)[12..], binding the account to its relying
+/// party.
+pub fn calc_pubkey_hash(rp_id_hash: &[u8], pubkey: &[u8]) -> PubkeyHash {
+    let mut hasher = Keccak256::new();
+    hasher.update(rp_id_hash);
+    hasher.update(pubkey);
+    let buf = hasher.finalize();
+    let mut pubkey_hash = [0u8; 20];
+    pubkey_hash.copy_from_slice(&buf[12..]);
+    pubkey_hash
+}
+
+/// Verify a WebAuthn assertion of `message`.
+///
+/// The challenge in client data JSON must be the base64url encoded `message`,
+/// the origin must belong to the relying party of the authenticator data, the
+/// signature must be low-s, and the authenticator must have verified the user
+/// (e.g. biometric).
+pub fn verify_assertion(
+    pubkey_hash: &PubkeyHash,
+    assertion: WebAuthnSignatureReader,
+    message: &H256,
+) -> Result<(), Error> {
+    let pubkey = assertion.pubkey().raw_data();
+    let signature = assertion.signature().raw_data();
+    let authenticator_data = assertion.authenticator_data().raw_data();
+    let client_data_json = assertion.client_data_json().raw_data();
+
+    if pubkey.len() != PUBKEY_SIZE || signature.len() != SIGNATURE_SIZE {
+        debug!(
+            "Invalid pubkey len: {}, signature len: {}",
+            pubkey.len(),
+            signature.len()
+        );
+        return Err(Error::WrongSignature);
+    }
+    // check authenticator flags
+    if authenticator_data.len() < MIN_AUTHENTICATOR_DATA_SIZE {
+        debug!(
+            "Invalid authenticator data len: {}",
+            authenticator_data.len()
+        );
+        return Err(Error::WrongSignature);
+    }
+    let rp_id_hash = &authenticator_data[..32];
+    if &calc_pubkey_hash(rp_id_hash, pubkey) != pubkey_hash {
+        debug!("Mismatch pubkey hash");
+        return Err(Error::WrongSignature);
+    }
+    let flags = authenticator_data[32];
+    if flags & FLAG_USER_PRESENT == 0 || flags & FLAG_USER_VERIFIED == 0 {
+        debug!("User not present or not verified, flags: {:#x}", flags);
+        return Err(Error::WrongSignature);
+    }
+
+    // check client data
+    let client_data = webauthn::parse_client_data(client_data_json).ok_or_else(|| {
+        debug!("Invalid client data json");
+        Error::WrongSignature
+    })?;
+    if client_data.type_ != webauthn::CLIENT_DATA_TYPE_GET {
+        debug!("Invalid client data type");
+        return Err(Error::WrongSignature);
+    }
+    if client_data.challenge.as_bytes() != base64url_encode_32(message.as_slice()) {
+        debug!("Mismatch challenge");
+        return Err(Error::WrongSignature);
+    }
+    if !webauthn::verify_origin(client_data.origin, rp_id_hash, |data| {
+        Sha256::digest(data).into()
+    }) {
+        debug!("Origin mismatches rp id hash");
+        return Err(Error::WrongSignature);
+    }
+
+    // verify signature of authenticator_data | sha256(client_data_json)
+    if !webauthn::is_low_s(signature) {
+        debug!("High s signature");
+        return Err(Error::WrongSignature);
+    }
+    let verifying_key =
+        VerifyingKey::from_encoded_point(&EncodedPoint::from_untagged_bytes(pubkey.into()))
+            .map_err(|_| {
+                debug!("Invalid secp256r1 pubkey");
+                Error::WrongSignature
+            })?;
+    let signature = Signature::try_from(signature).map_err(|_| {
+        debug!("Invalid secp256r1 signature");
+        Error::WrongSignature
+    })?;
+    let client_data_hash = Sha256::digest(client_data_json);
+    let mut signed_data = [0u8; 1024];
+    let signed_data_len = authenticator_data.len() + client_data_hash.len();
+    if signed_data_len > signed_data.len() {
+        debug!("Authenticator data too long: {}", authenticator_data.len());
+        return Err(Error::WrongSignature);
+    }
+    signed_data[..authenticator_data.len()].copy_from_slice(authenticator_data);
+    signed_data[authenticator_data.len()..signed_data_len].copy_from_slice(&client_data_hash);
+    verifying_key
+        .verify(&signed_data[..signed_data_len], &signature)
+        .map_err(|_| {
+            debug!("Wrong secp256r1 signature");
+            Error::WrongSignature
+        })
+}
+
+fn base64url_encode_32(input: &[u8]) -> [u8; CHALLENGE_SIZE] {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+    debug_assert_eq!(input.len(), 32);
+
+    let mut out = [0u8; CHALLENGE_SIZE];
+    let mut bits: u32 = 0;
+    let mut bits_len = 0;
+    let mut i = 0;
+    for byte in input {
+        bits = (bits << 8) | *byte as u32;
+        bits_len += 8;
+        while bits_len >= 6 {
+            bits_len -= 6;
+            out[i] = ALPHABET[((bits >> bits_len) & 0x3f) as usize];
+            i += 1;
+        }
+    }
+    if bits_len > 0 {
+        out[i] = ALPHABET[((bits << (6 - bits_len)) & 0x3f) as usize];
+    }
+    out
+}
@@ -213,11 +213,49 @@ pub fn main() -> Result<(), Error> {
                 return Err(Error::OwnerCellNotFound);
             }
 
+            Ok(())
+        }
+        UnlockWithdrawalWitnessUnion::UnlockWithdrawalViaTransfer(_unlock_args) => {
+            // the owner must agree with the transfer
+            if search_lock_hash(&owner_lock_hash, Source::Input).is_none() {
+                return Err(Error::OwnerCellNotFound);
+            }
+
+            // check whether output cell at same index only change the owner lock
+            let withdrawal_lock_hash = load_cell_lock_hash(0, Source::GroupInput)?;
+            for (index, _) in QueryIter::new(load_cell_lock_hash, Source::Input)
+                .enumerate()
+                .filter(|(_idx, lock_hash)| lock_hash == &withdrawal_lock_hash)
+            {
+                check_output_cell_has_same_content(index, Source::Input, index)?;
+
+                let output_lock = load_cell_lock(index, Source::Output)?;
+                if output_lock.code_hash().as_slice() != script.code_hash().as_slice()
+                    || output_lock.hash_type() != script.hash_type()
+                {
+                    return Err(Error::InvalidOutput);
+                }
+                let output_args = parse_lock_args(&output_lock)?;
+                if output_args.rollup_type_hash != rollup_type_hash
+                    || !is_same_withdrawal(&output_args.lock_args, &lock_args)
+                {
+                    return Err(Error::InvalidOutput);
+                }
+            }
+
             Ok(())
         }
     }
 }
 
+/// Same withdrawal regardless of the owner lock.
+fn is_same_withdrawal(a: &WithdrawalLockArgs, b: &WithdrawalLockArgs) -> bool {
+    a.withdrawal_block_hash().as_slice() == b.withdrawal_block_hash().as_slice()
+        && a.withdrawal_finalized_timepoint().as_slice()
+            == b.withdrawal_finalized_timepoint().as_slice()
+        && a.account_script_hash().as_slice() == b.account_script_hash().as_slice()
+}
+
 fn check_output_cell_has_same_content(
     input_index: usize,
     input_source: Source,
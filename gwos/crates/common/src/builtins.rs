@@ -3,3 +3,6 @@
 pub const RESERVED_ACCOUNT_ID: u32 = 0;
 pub const CKB_SUDT_ACCOUNT_ID: u32 = 1;
 pub const ETH_REGISTRY_ACCOUNT_ID: u32 = 2;
+/// Script hash registry, only created at genesis if the rollup config allows
+/// `AllowedContractType::ScriptHashAddrReg`
+pub const SCRIPT_HASH_REGISTRY_ACCOUNT_ID: u32 = 3;
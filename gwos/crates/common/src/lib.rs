@@ -5,9 +5,11 @@ pub mod ckb_decimal;
 pub mod error;
 pub mod merkle_utils;
 pub mod registry;
+pub mod session_key;
 pub mod state;
 #[cfg(test)]
 pub mod test_traits;
+pub mod webauthn;
 pub use gw_types::registry_address;
 
 // re-exports
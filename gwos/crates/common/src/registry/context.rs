@@ -1,58 +1,111 @@
 use gw_types::{
-    core::AllowedEoaType,
-    packed::{AllowedTypeHash, Byte32},
+    core::{AllowedContractType, AllowedEoaType},
+    packed::{AllowedTypeHash, Byte32, RollupConfig, Script},
     prelude::*,
 };
 
 use crate::{
-    builtins::ETH_REGISTRY_ACCOUNT_ID, error::Error, registry_address::RegistryAddress, vec::Vec,
+    builtins::{ETH_REGISTRY_ACCOUNT_ID, SCRIPT_HASH_REGISTRY_ACCOUNT_ID},
+    error::Error,
+    registry::{
+        eth_registry::{extract_eth_address_from_eoa, extract_eth_address_from_multisig_eoa},
+        script_hash_registry::extract_script_hash_address_from_eoa,
+    },
+    registry_address::RegistryAddress,
+    vec::Vec,
 };
 
 pub struct RegistryContext {
     allowed_eoa_type_hashes: Vec<AllowedTypeHash>,
+    script_hash_registry: bool,
 }
 
 impl RegistryContext {
     pub fn new(allowed_eoa_type_hashes: Vec<AllowedTypeHash>) -> Self {
         Self {
             allowed_eoa_type_hashes,
+            script_hash_registry: false,
         }
     }
+
+    /// The script hash registry is enabled if the rollup config allows
+    /// `AllowedContractType::ScriptHashAddrReg`
+    pub fn from_rollup_config(rollup_config: &RollupConfig) -> Self {
+        let script_hash_registry = rollup_config
+            .allowed_contract_type_hashes()
+            .into_iter()
+            .any(|type_hash| {
+                let type_: u8 = type_hash.type_().into();
+                type_ == AllowedContractType::ScriptHashAddrReg as u8
+            });
+        Self {
+            allowed_eoa_type_hashes: rollup_config
+                .allowed_eoa_type_hashes()
+                .into_iter()
+                .collect(),
+            script_hash_registry,
+        }
+    }
+
     fn find_eoa_type_by_hash(&self, code_hash: &Byte32) -> Option<&AllowedTypeHash> {
         self.allowed_eoa_type_hashes
             .iter()
             .find(|type_hash| type_hash.hash().as_slice() == code_hash.as_slice())
     }
 
+    fn find_eoa_type(&self, code_hash: &Byte32) -> Result<Option<AllowedEoaType>, Error> {
+        self.find_eoa_type_by_hash(code_hash)
+            .map(|type_hash| {
+                let type_: u8 = type_hash.type_().into();
+                type_.try_into()
+            })
+            .transpose()
+            .map_err(|_err| Error::UnknownEoaCodeHash)
+    }
+
     /// Extract EOA registry address from deposit request
     // TODO support extract ETH address from tron EOA
     pub fn extract_registry_address_from_deposit(
         &self,
         registry_id: u32,
-        code_hash: &Byte32,
-        args: &[u8],
+        script: &Script,
     ) -> Result<RegistryAddress, Error> {
-        // We only support ETH registry in current stage
-        if registry_id != ETH_REGISTRY_ACCOUNT_ID {
-            return Err(Error::InvalidArgs);
+        match registry_id {
+            ETH_REGISTRY_ACCOUNT_ID => self.extract_eth_address(registry_id, script),
+            SCRIPT_HASH_REGISTRY_ACCOUNT_ID if self.script_hash_registry => {
+                // Any allowed EOA can be registered by its script hash
+                match self.find_eoa_type(&script.code_hash())? {
+                    Some(AllowedEoaType::Unknown) | None => Err(Error::UnknownEoaCodeHash),
+                    Some(_) => {
+                        let address = extract_script_hash_address_from_eoa(script);
+                        Ok(RegistryAddress::new(registry_id, address))
+                    }
+                }
+            }
+            _ => Err(Error::InvalidArgs),
         }
+    }
+
+    fn extract_eth_address(
+        &self,
+        registry_id: u32,
+        script: &Script,
+    ) -> Result<RegistryAddress, Error> {
+        let args = script.args().raw_data();
         // Check EOA code hash
-        match self
-            .find_eoa_type_by_hash(code_hash)
-            .map(|type_hash| {
-                let type_: u8 = type_hash.type_().into();
-                type_.try_into()
-            })
-            .transpose()
-            .map_err(|_err| Error::UnknownEoaCodeHash)?
-        {
-            Some(AllowedEoaType::Eth) => {
+        match self.find_eoa_type(&script.code_hash())? {
+            // WebAuthn EOA args has the same layout as ETH EOA, the address
+            // is derived from the P-256 public key.
+            Some(AllowedEoaType::Eth) | Some(AllowedEoaType::WebAuthn) => {
                 // extract ETH EOA
-                let address =
-                    { crate::registry::eth_registry::extract_eth_address_from_eoa(args)? };
+                let address = extract_eth_address_from_eoa(&args)?;
                 let addr = RegistryAddress::new(registry_id, address);
                 Ok(addr)
             }
+            Some(AllowedEoaType::Multisig) => {
+                let address = extract_eth_address_from_multisig_eoa(&args)?;
+                Ok(RegistryAddress::new(registry_id, address))
+            }
             _ => Err(Error::UnknownEoaCodeHash),
         }
     }
@@ -1,11 +1,15 @@
 #![allow(dead_code)]
 
+use crate::blake2b::new_blake2b;
 use crate::error::Error;
 use crate::vec::Vec;
 
 const EOA_SCRIPT_ARGS_LEN: usize = 52;
 /// 32 + 4 + 20
 const CONTRACT_ACCOUNT_SCRIPT_ARGS_LEN: usize = 56;
+/// 32 + 20 + 1 (threshold) + 1 (pubkey hashes count)
+const MULTISIG_EOA_SCRIPT_ARGS_MIN_LEN: usize = 54;
+pub const MULTISIG_MAX_PUBKEY_HASHES: usize = 16;
 
 /// Extract ETH address from an ETH EOA script args
 pub fn extract_eth_address_from_eoa(script_args: &[u8]) -> Result<Vec<u8>, Error> {
@@ -22,3 +26,40 @@ pub fn extract_eth_address_from_contract(script_args: &[u8]) -> Result<Vec<u8>,
     }
     Ok(script_args[36..].to_vec())
 }
+
+/// Extract ETH address from a multisig EOA script args
+///
+/// script args: rollup_script_hash(32 bytes) | eth_address(20 bytes) | threshold(1 byte) |
+/// pubkey_hashes_count(1 byte) | pubkey_hashes(20 bytes * count)
+///
+/// The address must be `calc_multisig_address` of the multisig config, so it can't be
+/// claimed by an account with a different config.
+pub fn extract_eth_address_from_multisig_eoa(script_args: &[u8]) -> Result<Vec<u8>, Error> {
+    if script_args.len() < MULTISIG_EOA_SCRIPT_ARGS_MIN_LEN {
+        return Err(Error::InvalidArgs);
+    }
+    let threshold = script_args[52] as usize;
+    let count = script_args[53] as usize;
+    if threshold == 0
+        || threshold > count
+        || count > MULTISIG_MAX_PUBKEY_HASHES
+        || script_args.len() != MULTISIG_EOA_SCRIPT_ARGS_MIN_LEN + 20 * count
+    {
+        return Err(Error::InvalidArgs);
+    }
+    if calc_multisig_address(&script_args[52..]) != script_args[32..52] {
+        return Err(Error::InvalidArgs);
+    }
+    Ok(script_args[32..52].to_vec())
+}
+
+/// blake2b(threshold | pubkey_hashes_count | pubkey_hashes)[..20]
+pub fn calc_multisig_address(multisig_config: &[u8]) -> [u8; 20] {
+    let mut hasher = new_blake2b();
+    hasher.update(multisig_config);
+    let mut buf = [0u8; 32];
+    hasher.finalize(&mut buf);
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&buf[..20]);
+    address
+}
@@ -1,2 +1,3 @@
 pub mod context;
 pub mod eth_registry;
+pub mod script_hash_registry;
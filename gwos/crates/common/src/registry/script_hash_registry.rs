@@ -0,0 +1,11 @@
+use gw_types::{packed::Script, prelude::*};
+
+use crate::vec::Vec;
+
+/// Script hash registry address is the 32 bytes script hash of the account
+pub const SCRIPT_HASH_ADDRESS_LEN: usize = 32;
+
+/// Extract script hash address from an EOA script
+pub fn extract_script_hash_address_from_eoa(script: &Script) -> Vec<u8> {
+    script.hash().to_vec()
+}
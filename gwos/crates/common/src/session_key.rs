@@ -0,0 +1,183 @@
+//! Session key checks shared by the challenge lock and the off-chain
+//! generator, so that both accept exactly the same transactions.
+//!
+//! The Meta contract keeps up to `MAX_SESSION_KEYS` session keys of an
+//! account in slots of its own storage, a slot holds the data hash of a
+//! `SessionKey`. The cost spent by a session key is kept in the storage too,
+//! it's reset when the session key is registered or revoked.
+
+use gw_types::{
+    h256::{H256Ext, H256},
+    packed::SessionKey,
+    prelude::*,
+    U256,
+};
+
+use crate::{blake2b::new_blake2b, builtins::RESERVED_ACCOUNT_ID, error::Error, state::State};
+
+/// Keep in sync with the Meta contract.
+pub const MAX_SESSION_KEYS: u8 = 8;
+const SESSION_KEY_SLOT_PREFIX: &[u8] = b"session_key";
+const SESSION_KEY_SPENT_PREFIX: &[u8] = b"session_key_spent";
+
+/// Key of a session key slot in the Meta contract storage.
+pub fn session_key_slot_key(owner_id: u32, index: u8) -> H256 {
+    let mut key = [0u8; 32];
+    let mut hasher = new_blake2b();
+    hasher.update(SESSION_KEY_SLOT_PREFIX);
+    hasher.update(&owner_id.to_le_bytes());
+    hasher.update(&[index]);
+    hasher.finalize(&mut key);
+    key
+}
+
+/// Key of the cost spent by a session key in the Meta contract storage.
+pub fn session_key_spent_key(owner_id: u32, pubkey_hash: &[u8]) -> H256 {
+    let mut key = [0u8; 32];
+    let mut hasher = new_blake2b();
+    hasher.update(SESSION_KEY_SPENT_PREFIX);
+    hasher.update(&owner_id.to_le_bytes());
+    hasher.update(pubkey_hash);
+    hasher.finalize(&mut key);
+    key
+}
+
+/// The cost spent by the session key since it's registered.
+pub fn get_session_key_spent<S: State>(
+    state: &S,
+    owner_id: u32,
+    session_key: &SessionKey,
+) -> Result<U256, Error> {
+    let key = session_key_spent_key(owner_id, session_key.pubkey_hash().as_slice());
+    let value = state.get_value(RESERVED_ACCOUNT_ID, &key)?;
+    Ok(value.to_u256())
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SessionKeyError {
+    Expired { expiry_block: u64 },
+    MetaContractCall,
+    TargetNotAllowed,
+    UnknownCost,
+    ExceedsSpendingCap { spending_cap: u128, spent: U256 },
+}
+
+/// Check the transaction is within the limits of the session key.
+///
+/// `cost` is the transfer value plus the maximum fee of the transaction,
+/// transactions whose cost can't be computed are rejected. `spent` is the
+/// cost spent by the session key before the transaction, the sum of them
+/// must be within the spending cap.
+pub fn check_session_key_limits(
+    session_key: &SessionKey,
+    block_number: u64,
+    to_id: u32,
+    receiver_script_hash: &H256,
+    cost: Option<U256>,
+    spent: U256,
+) -> Result<(), SessionKeyError> {
+    let expiry_block: u64 = session_key.expiry_block().unpack();
+    if block_number >= expiry_block {
+        return Err(SessionKeyError::Expired { expiry_block });
+    }
+    // Session keys must not manage session keys
+    if to_id == RESERVED_ACCOUNT_ID {
+        return Err(SessionKeyError::MetaContractCall);
+    }
+    let is_allowed = session_key
+        .allowed_targets()
+        .into_iter()
+        .any(|target| target.as_slice() == receiver_script_hash.as_slice());
+    if !is_allowed {
+        return Err(SessionKeyError::TargetNotAllowed);
+    }
+    let cost = cost.ok_or(SessionKeyError::UnknownCost)?;
+    let spending_cap: u128 = session_key.spending_cap().unpack();
+    match spent.checked_add(cost) {
+        Some(total) if total <= U256::from(spending_cap) => {}
+        _ => {
+            return Err(SessionKeyError::ExceedsSpendingCap {
+                spending_cap,
+                spent,
+            })
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use gw_types::packed::Byte20;
+
+    use super::*;
+
+    fn session_key() -> SessionKey {
+        SessionKey::new_builder()
+            .pubkey_hash(Byte20::from_slice(&[1u8; 20]).unwrap())
+            .expiry_block(100u64.pack())
+            .allowed_targets(vec![[2u8; 32].pack()].pack())
+            .spending_cap(1000u128.pack())
+            .build()
+    }
+
+    #[test]
+    fn test_check_session_key_limits() {
+        let key = session_key();
+        let check = |block_number, to_id, target: [u8; 32], cost: Option<u64>| {
+            let cost = cost.map(U256::from);
+            check_session_key_limits(&key, block_number, to_id, &target, cost, U256::zero())
+        };
+
+        assert_eq!(check(99, 5, [2u8; 32], Some(1000)), Ok(()));
+        assert_eq!(
+            check(100, 5, [2u8; 32], Some(1)),
+            Err(SessionKeyError::Expired { expiry_block: 100 })
+        );
+        assert_eq!(
+            check(1, RESERVED_ACCOUNT_ID, [2u8; 32], Some(1)),
+            Err(SessionKeyError::MetaContractCall)
+        );
+        assert_eq!(
+            check(1, 5, [3u8; 32], Some(1)),
+            Err(SessionKeyError::TargetNotAllowed)
+        );
+        assert_eq!(
+            check(1, 5, [2u8; 32], None),
+            Err(SessionKeyError::UnknownCost)
+        );
+        assert_eq!(
+            check(1, 5, [2u8; 32], Some(1001)),
+            Err(SessionKeyError::ExceedsSpendingCap {
+                spending_cap: 1000,
+                spent: U256::zero()
+            })
+        );
+
+        // the cost spent before counts towards the spending cap
+        let check_spent = |cost: u64, spent: u64| {
+            check_session_key_limits(
+                &key,
+                1,
+                5,
+                &[2u8; 32],
+                Some(U256::from(cost)),
+                U256::from(spent),
+            )
+        };
+        assert_eq!(check_spent(400, 600), Ok(()));
+        assert_eq!(
+            check_spent(401, 600),
+            Err(SessionKeyError::ExceedsSpendingCap {
+                spending_cap: 1000,
+                spent: U256::from(600u64)
+            })
+        );
+        assert_eq!(
+            check_spent(1, u64::MAX),
+            Err(SessionKeyError::ExceedsSpendingCap {
+                spending_cap: 1000,
+                spent: U256::from(u64::MAX)
+            })
+        );
+    }
+}
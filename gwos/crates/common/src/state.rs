@@ -21,8 +21,9 @@ use gw_types::{
     U256,
 };
 
-use crate::builtins::ETH_REGISTRY_ACCOUNT_ID;
+use crate::builtins::{ETH_REGISTRY_ACCOUNT_ID, SCRIPT_HASH_REGISTRY_ACCOUNT_ID};
 use crate::error::Error;
+use crate::registry::script_hash_registry::SCRIPT_HASH_ADDRESS_LEN;
 use crate::registry_address::RegistryAddress;
 use crate::vec::Vec;
 use crate::{blake2b::new_blake2b, merkle_utils::calculate_state_checkpoint};
@@ -307,7 +308,20 @@ pub trait State {
         if value.is_zero() {
             return Ok(None);
         }
-        Ok(Some(RegistryAddress::from_slice(value.as_slice()).unwrap()))
+        if let Some(addr) = RegistryAddress::from_slice(value.as_slice()) {
+            return Ok(Some(addr));
+        }
+        // A script hash address can't be inlined into the value,
+        // only the registry id and the address len are stored.
+        let addr_len = u32::from_le_bytes(value[4..8].try_into().unwrap()) as usize;
+        if addr_len != SCRIPT_HASH_ADDRESS_LEN {
+            return Err(Error::InvalidArgs);
+        }
+        let registry_id = u32::from_le_bytes(value[..4].try_into().unwrap());
+        Ok(Some(RegistryAddress::new(
+            registry_id,
+            script_hash.as_slice().to_vec(),
+        )))
     }
 
     /// This function create a bi-direction mapping between registry address & script_hash
@@ -316,15 +330,13 @@ pub trait State {
         addr: RegistryAddress,
         script_hash: H256,
     ) -> Result<(), Error> {
-        // Only support addr len == 20 for now, we can revisit the condition in later version
-        if addr.address.len() != 20 {
-            return Err(Error::InvalidArgs);
-        }
         if script_hash.is_zero() {
             return Err(Error::InvalidArgs);
         }
-        if addr.registry_id != ETH_REGISTRY_ACCOUNT_ID {
-            return Err(Error::InvalidArgs);
+        match addr.registry_id {
+            ETH_REGISTRY_ACCOUNT_ID if addr.address.len() == 20 => {}
+            SCRIPT_HASH_REGISTRY_ACCOUNT_ID if addr.address == script_hash.as_slice() => {}
+            _ => return Err(Error::InvalidArgs),
         }
         // Check duplication
         if self
@@ -340,8 +352,14 @@ pub trait State {
         {
             let key = build_script_hash_to_registry_address_key(&script_hash);
             let mut addr_buf = [0u8; 32];
-            addr.write_to_slice(&mut addr_buf)
-                .expect("write addr to buf");
+            if addr.address.len() == SCRIPT_HASH_ADDRESS_LEN {
+                // the address is the script hash itself
+                addr_buf[..4].copy_from_slice(&addr.registry_id.to_le_bytes());
+                addr_buf[4..8].copy_from_slice(&(SCRIPT_HASH_ADDRESS_LEN as u32).to_le_bytes());
+            } else {
+                addr.write_to_slice(&mut addr_buf)
+                    .expect("write addr to buf");
+            }
             self.update_value(addr.registry_id, &key, addr_buf)?;
         }
         // registry address -> script hash
@@ -0,0 +1,368 @@
+//! WebAuthn assertion checks shared by the webauthn account lock and the
+//! off-chain generator, so that both accept exactly the same assertions.
+
+/// Half of the order of P-256, `s` of a signature must not be greater than it.
+const P256_HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0x80, 0x00, 0x00, 0x00, 0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xde, 0x73, 0x7d, 0x56, 0xd3, 0x8b, 0xcf, 0x42, 0x79, 0xdc, 0xe5, 0x61, 0x7e, 0x31, 0x92, 0xa8,
+];
+const MAX_JSON_DEPTH: usize = 8;
+
+pub const CLIENT_DATA_TYPE_GET: &str = "webauthn.get";
+
+/// Checked fields of the client data JSON.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ClientData<'a> {
+    pub type_: &'a str,
+    pub challenge: &'a str,
+    pub origin: &'a str,
+}
+
+/// Whether `s` of a P-256 signature `r | s` is in the lower half of the
+/// order, so that a signature can't be malleated into another valid one.
+pub fn is_low_s(signature: &[u8]) -> bool {
+    signature.len() == 64 && signature[32..] <= P256_HALF_ORDER[..]
+}
+
+/// Parse the client data JSON.
+///
+/// The JSON must be a valid object with unique keys, and `type`, `challenge`
+/// and `origin` must be strings without escapes, which is always the case for
+/// the serialization of browsers. `crossOrigin` must be absent or false.
+pub fn parse_client_data(json: &[u8]) -> Option<ClientData> {
+    let mut parser = JsonParser { json, pos: 0 };
+    let mut type_ = None;
+    let mut challenge = None;
+    let mut origin = None;
+    let mut cross_origin = None;
+
+    parser.expect(b'{')?;
+    if !parser.try_consume(b'}') {
+        loop {
+            let key = parser.string()?;
+            parser.expect(b':')?;
+            let field = match key.raw {
+                b"type" => Some(&mut type_),
+                b"challenge" => Some(&mut challenge),
+                b"origin" => Some(&mut origin),
+                _ => None,
+            };
+            match field {
+                Some(field) => {
+                    parser.skip_ws();
+                    let value = parser.string()?;
+                    if field.is_some() || value.escaped {
+                        return None;
+                    }
+                    *field = Some(core::str::from_utf8(value.raw).ok()?);
+                }
+                None if key.raw == b"crossOrigin" => {
+                    if cross_origin.is_some() {
+                        return None;
+                    }
+                    parser.skip_ws();
+                    cross_origin = Some(parser.literal()?);
+                }
+                None => parser.value(0)?,
+            }
+            parser.skip_ws();
+            if parser.try_consume(b',') {
+                continue;
+            }
+            parser.expect(b'}')?;
+            break;
+        }
+    }
+    parser.skip_ws();
+    if parser.pos != json.len() || cross_origin.unwrap_or(Literal::False) != Literal::False {
+        return None;
+    }
+
+    Some(ClientData {
+        type_: type_?,
+        challenge: challenge?,
+        origin: origin?,
+    })
+}
+
+/// Check that `origin` belongs to the relying party of `rp_id_hash`.
+///
+/// The origin must be `https://<host>[:port]`, or `http://localhost[:port]`,
+/// and the relying party id must be the host or one of its parent domains.
+pub fn verify_origin(origin: &str, rp_id_hash: &[u8], sha256: impl Fn(&[u8]) -> [u8; 32]) -> bool {
+    let (host, https) = match origin.strip_prefix("https://") {
+        Some(rest) => (rest, true),
+        None => match origin.strip_prefix("http://") {
+            Some(rest) => (rest, false),
+            None => return false,
+        },
+    };
+    let host = match host.split_once(':') {
+        Some((host, port)) => {
+            if port.is_empty() || port.len() > 5 || !port.bytes().all(|b| b.is_ascii_digit()) {
+                return false;
+            }
+            host
+        }
+        None => host,
+    };
+    let valid_host = !host.is_empty()
+        && host
+            .split('.')
+            .all(|label| !label.is_empty() && label.bytes().all(is_host_char));
+    if !valid_host || (!https && host != "localhost") {
+        return false;
+    }
+
+    let mut rp_id = host;
+    loop {
+        if sha256(rp_id.as_bytes())[..] == *rp_id_hash {
+            return true;
+        }
+        match rp_id.split_once('.') {
+            Some((_, parent)) => rp_id = parent,
+            None => return false,
+        }
+    }
+}
+
+fn is_host_char(b: u8) -> bool {
+    b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-'
+}
+
+struct JsonString<'a> {
+    // Bytes between the quotes.
+    raw: &'a [u8],
+    escaped: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Literal {
+    True,
+    False,
+    Null,
+}
+
+struct JsonParser<'a> {
+    json: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.json.get(self.pos).copied()
+    }
+
+    fn skip_ws(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn try_consume(&mut self, b: u8) -> bool {
+        self.skip_ws();
+        if self.peek() == Some(b) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, b: u8) -> Option<()> {
+        self.try_consume(b).then_some(())
+    }
+
+    fn value(&mut self, depth: usize) -> Option<()> {
+        if depth > MAX_JSON_DEPTH {
+            return None;
+        }
+        self.skip_ws();
+        match self.peek()? {
+            b'"' => self.string().map(|_| ()),
+            b'{' => {
+                self.pos += 1;
+                if self.try_consume(b'}') {
+                    return Some(());
+                }
+                loop {
+                    self.skip_ws();
+                    self.string()?;
+                    self.expect(b':')?;
+                    self.value(depth + 1)?;
+                    if !self.try_consume(b',') {
+                        return self.expect(b'}');
+                    }
+                }
+            }
+            b'[' => {
+                self.pos += 1;
+                if self.try_consume(b']') {
+                    return Some(());
+                }
+                loop {
+                    self.value(depth + 1)?;
+                    if !self.try_consume(b',') {
+                        return self.expect(b']');
+                    }
+                }
+            }
+            b'-' | b'0'..=b'9' => self.number(),
+            _ => self.literal().map(|_| ()),
+        }
+    }
+
+    fn string(&mut self) -> Option<JsonString<'a>> {
+        self.skip_ws();
+        if self.peek()? != b'"' {
+            return None;
+        }
+        self.pos += 1;
+        let start = self.pos;
+        let mut escaped = false;
+        loop {
+            match self.peek()? {
+                b'"' => break,
+                b'\\' => {
+                    escaped = true;
+                    self.pos += 1;
+                    match self.peek()? {
+                        b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't' => {}
+                        b'u' => {
+                            let hex = self.json.get(self.pos + 1..self.pos + 5)?;
+                            if !hex.iter().all(u8::is_ascii_hexdigit) {
+                                return None;
+                            }
+                            self.pos += 4;
+                        }
+                        _ => return None,
+                    }
+                }
+                0..=0x1f => return None,
+                _ => {}
+            }
+            self.pos += 1;
+        }
+        let raw = &self.json[start..self.pos];
+        self.pos += 1;
+        Some(JsonString { raw, escaped })
+    }
+
+    fn number(&mut self) -> Option<()> {
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        match self.peek()? {
+            b'0' => self.pos += 1,
+            b'1'..=b'9' => self.digits()?,
+            _ => return None,
+        }
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            self.digits()?;
+        }
+        if let Some(b'e' | b'E') = self.peek() {
+            self.pos += 1;
+            if let Some(b'+' | b'-') = self.peek() {
+                self.pos += 1;
+            }
+            self.digits()?;
+        }
+        Some(())
+    }
+
+    fn digits(&mut self) -> Option<()> {
+        let start = self.pos;
+        while self.peek().map_or(false, |b| b.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        (self.pos > start).then_some(())
+    }
+
+    fn literal(&mut self) -> Option<Literal> {
+        let rest = &self.json[self.pos..];
+        let (literal, len) = if rest.starts_with(b"true") {
+            (Literal::True, 4)
+        } else if rest.starts_with(b"false") {
+            (Literal::False, 5)
+        } else if rest.starts_with(b"null") {
+            (Literal::Null, 4)
+        } else {
+            return None;
+        };
+        self.pos += len;
+        Some(literal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Not a hash, but enough to tell rp ids apart.
+    fn fake_hash(data: &[u8]) -> [u8; 32] {
+        let mut buf = [0u8; 32];
+        buf[..data.len()].copy_from_slice(data);
+        buf
+    }
+
+    #[test]
+    fn test_parse_client_data() {
+        let client_data = parse_client_data(
+            br#" {"type":"webauthn.get","challenge":"abc","origin":"https://a.com","crossOrigin":false,"x":[1,-2.5e3,{"y":null}]} "#,
+        );
+        assert_eq!(
+            client_data,
+            Some(ClientData {
+                type_: "webauthn.get",
+                challenge: "abc",
+                origin: "https://a.com",
+            })
+        );
+
+        for json in [
+            &br#"{"type":"webauthn.get","challenge":"abc","challenge":"def","origin":"https://a.com"}"#[..],
+            br#"{"type":"webauthn.get","challenge":"a\u0041","origin":"https://a.com"}"#,
+            br#"{"type":"webauthn.get","challenge":"abc","origin":"https://a.com","crossOrigin":true}"#,
+            br#"{"type":"webauthn.get","origin":"https://a.com"}"#,
+            br#"{"type":"webauthn.get","challenge":"abc","origin":"https://a.com",}"#,
+            br#"{"type":"webauthn.get","challenge":"abc","origin":"https://a.com"}{}"#,
+            br#"{"type":"webauthn.get","challenge":"abc","origin":"https://a.com","x":[[[[[[[[[[1]]]]]]]]]]}"#,
+        ] {
+            assert_eq!(parse_client_data(json), None);
+        }
+    }
+
+    #[test]
+    fn test_verify_origin() {
+        let rp_id_hash = fake_hash(b"example.com");
+        for origin in ["https://example.com", "https://login.example.com:8443"] {
+            assert!(verify_origin(origin, &rp_id_hash, fake_hash), "{}", origin);
+        }
+        for origin in [
+            "http://example.com",
+            "https://example.com.evil.com",
+            "https://badexample.com",
+            "https://example.com:",
+            "https://Example.com",
+            "example.com",
+        ] {
+            assert!(!verify_origin(origin, &rp_id_hash, fake_hash), "{}", origin);
+        }
+        assert!(verify_origin(
+            "http://localhost:3000",
+            &fake_hash(b"localhost"),
+            fake_hash
+        ));
+    }
+
+    #[test]
+    fn test_is_low_s() {
+        let mut signature = [0u8; 64];
+        signature[32..].copy_from_slice(&P256_HALF_ORDER);
+        assert!(is_low_s(&signature));
+        signature[63] += 1;
+        assert!(!is_low_s(&signature));
+        assert!(!is_low_s(&signature[..63]));
+    }
+}
@@ -1,5 +1,5 @@
 use crate::h256::H256;
-use crate::offchain::ExportedBlock;
+use crate::offchain::{ExportedBadBlock, ExportedBlock};
 use crate::{packed, prelude::*};
 
 impl From<ExportedBlock> for packed::ExportedBlock {
@@ -33,9 +33,44 @@ impl From<packed::ExportedBlock> for ExportedBlock {
             withdrawals,
             bad_block_hashes: exported.bad_block_hashes().unpack(),
             submit_tx_hash: exported.submit_tx_hash().unpack(),
+            bad_block: None,
+            pending_reverted_block_hashes: None,
+        }
+    }
+}
+
+impl From<ExportedBlock> for packed::ExportedBlockRecord {
+    fn from(mut exported: ExportedBlock) -> Self {
+        let bad_block = exported.bad_block.take().map(|bad_block| {
+            packed::ExportedBadBlock::new_builder()
+                .challenge_target(bad_block.challenge_target.pack())
+                .build()
+        });
+        let pending_reverted_block_hashes = exported.pending_reverted_block_hashes.take();
+
+        packed::ExportedBlockRecord::new_builder()
+            .block(exported.into())
+            .bad_block(bad_block.pack())
+            .pending_reverted_block_hashes(pending_reverted_block_hashes.pack())
+            .build()
+    }
+}
+
+impl From<packed::ExportedBlockRecord> for ExportedBlock {
+    fn from(record: packed::ExportedBlockRecord) -> Self {
+        let bad_block = record.bad_block().to_opt().map(|b| ExportedBadBlock {
+            challenge_target: b.challenge_target().to_opt(),
+        });
+
+        ExportedBlock {
+            bad_block,
+            pending_reverted_block_hashes: record.pending_reverted_block_hashes().unpack(),
+            ..record.block().into()
         }
     }
 }
 
 impl_conversion_for_vector!(Vec<H256>, Byte32VecVec, Byte32VecVecReader);
 impl_conversion_for_option!(Vec<Vec<H256>>, Byte32VecVecOpt, Byte32VecVecOptReader);
+impl_conversion_for_packed_optional_pack!(ChallengeTarget, ChallengeTargetOpt);
+impl_conversion_for_packed_optional_pack!(ExportedBadBlock, ExportedBadBlockOpt);
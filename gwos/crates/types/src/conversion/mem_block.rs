@@ -126,6 +126,7 @@ impl_conversion_for_vector!(DepositInfo, DepositInfoVec, DepositInfoVecReader);
 impl_conversion_for_vector!(SudtCustodian, SudtCustodianVec, SudtCustodianVecReader);
 impl_conversion_for_packed_iterator_pack!(WithdrawalRequestExtra, WithdrawalRequestExtraVec);
 impl_conversion_for_packed_iterator_pack!(DepositInfo, DepositInfoVec);
+impl_conversion_for_packed_iterator_pack!(FinalizedBlock, FinalizedBlockVec);
 impl_conversion_for_option!(H256, Byte32Opt, Byte32OptReader);
 impl_conversion_for_vector!(
     RegistryAddress,
@@ -207,6 +207,8 @@ impl From<GlobalStateV0> for GlobalState {
 pub enum AllowedEoaType {
     Unknown,
     Eth,
+    WebAuthn,
+    Multisig,
 }
 
 impl From<AllowedEoaType> for u8 {
@@ -223,6 +225,8 @@ impl TryFrom<u8> for AllowedEoaType {
         match value {
             0 => Ok(AllowedEoaType::Unknown),
             1 => Ok(AllowedEoaType::Eth),
+            2 => Ok(AllowedEoaType::WebAuthn),
+            3 => Ok(AllowedEoaType::Multisig),
             n => Err(n),
         }
     }
@@ -243,6 +247,7 @@ pub enum AllowedContractType {
     Sudt,
     Polyjuice,
     EthAddrReg,
+    ScriptHashAddrReg,
 }
 
 impl From<AllowedContractType> for u8 {
@@ -262,6 +267,7 @@ impl TryFrom<u8> for AllowedContractType {
             2 => Ok(AllowedContractType::Sudt),
             3 => Ok(AllowedContractType::Polyjuice),
             4 => Ok(AllowedContractType::EthAddrReg),
+            5 => Ok(AllowedContractType::ScriptHashAddrReg),
             n => Err(n),
         }
     }
@@ -1,6 +1,8 @@
 use crate::{
     h256::H256,
-    packed::{DepositInfoVec, GlobalState, L2Block, Script, WithdrawalRequestExtra},
+    packed::{
+        ChallengeTarget, DepositInfoVec, GlobalState, L2Block, Script, WithdrawalRequestExtra,
+    },
     prelude::*,
 };
 
@@ -13,6 +15,17 @@ pub struct ExportedBlock {
     pub withdrawals: Vec<WithdrawalRequestExtra>,
     pub bad_block_hashes: Option<Vec<Vec<H256>>>,
     pub submit_tx_hash: Option<H256>,
+    /// Set if this is a bad block, i.e. submitted after the last valid block
+    /// and not reverted yet.
+    pub bad_block: Option<ExportedBadBlock>,
+    /// Reverted block hashes not included in any block post global state yet.
+    /// Only set for the tip block.
+    pub pending_reverted_block_hashes: Option<Vec<Vec<H256>>>,
+}
+
+#[derive(Debug)]
+pub struct ExportedBadBlock {
+    pub challenge_target: Option<ChallengeTarget>,
 }
 
 impl ExportedBlock {
@@ -27,6 +40,10 @@ impl ExportedBlock {
     pub fn parent_block_hash(&self) -> H256 {
         self.block.raw().parent_block_hash().unpack()
     }
+
+    pub fn is_bad_block(&self) -> bool {
+        self.bad_block.is_some()
+    }
 }
 
 impl PartialEq for ExportedBlock {
@@ -36,7 +53,18 @@ impl PartialEq for ExportedBlock {
             && self.bad_block_hashes == other.bad_block_hashes
             && self.deposit_info_vec.as_slice() == other.deposit_info_vec.as_slice()
             && self.submit_tx_hash == other.submit_tx_hash
+            && self.bad_block == other.bad_block
+            && self.pending_reverted_block_hashes == other.pending_reverted_block_hashes
     }
 }
 
 impl Eq for ExportedBlock {}
+
+impl PartialEq for ExportedBadBlock {
+    fn eq(&self, other: &Self) -> bool {
+        let target = |b: &Self| b.challenge_target.as_ref().map(|t| t.as_slice().to_vec());
+        target(self) == target(other)
+    }
+}
+
+impl Eq for ExportedBadBlock {}